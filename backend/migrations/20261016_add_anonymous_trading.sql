@@ -0,0 +1,18 @@
+-- Per-market anonymous trading: flagged markets keep usernames off the
+-- trade tape, trade broadcasts and payout journal. Admin views of trader
+-- identities, and changes to the flag, are logged in trade_identity_audit.
+-- The prediction engine also creates these at startup; this keeps fresh
+-- databases in step.
+ALTER TABLE events ADD COLUMN IF NOT EXISTS anonymous_trading BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE IF NOT EXISTS trade_identity_audit (
+    id BIGSERIAL PRIMARY KEY,
+    event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    action VARCHAR(32) NOT NULL,
+    actor VARCHAR(255) NOT NULL,
+    reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_trade_identity_audit_event
+    ON trade_identity_audit(event_id, created_at);
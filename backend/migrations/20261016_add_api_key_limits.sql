@@ -0,0 +1,13 @@
+-- Per-key request limits and hourly usage for API keys, which bots also
+-- use against the prediction engine. A NULL limit means the engine's
+-- configured default. The engine also creates these at startup; this
+-- keeps fresh databases in step.
+ALTER TABLE api_keys ADD COLUMN IF NOT EXISTS rate_limit_per_minute INTEGER;
+
+CREATE TABLE IF NOT EXISTS api_key_usage (
+    key_id INTEGER NOT NULL REFERENCES api_keys(id) ON DELETE CASCADE,
+    hour TIMESTAMPTZ NOT NULL,
+    requests BIGINT NOT NULL DEFAULT 0,
+    rejected BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (key_id, hour)
+);
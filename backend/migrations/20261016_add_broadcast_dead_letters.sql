@@ -0,0 +1,15 @@
+-- Real-time broadcasts no subscriber received (none connected, or one fell
+-- too far behind), kept so reconnecting clients can backfill them. The
+-- prediction engine prunes rows past its retention window and also creates
+-- this table at startup; this keeps fresh databases in step.
+CREATE TABLE IF NOT EXISTS broadcast_dead_letters (
+    id BIGSERIAL PRIMARY KEY,
+    message_type VARCHAR(64) NOT NULL,
+    message JSONB NOT NULL,
+    reason VARCHAR(32) NOT NULL,
+    sent_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_broadcast_dead_letters_sent_at
+    ON broadcast_dead_letters(sent_at, id);
@@ -0,0 +1,12 @@
+-- Closing reminders sent by the prediction engine. One row per market,
+-- reminder threshold and closing_date, so a market is announced once as it
+-- comes within the configured hours of closing, and again only if its
+-- closing_date moves. The engine also creates this table at startup; this
+-- keeps fresh databases in step.
+CREATE TABLE IF NOT EXISTS closing_reminders (
+    event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    hours_before DOUBLE PRECISION NOT NULL,
+    closing_date TIMESTAMPTZ NOT NULL,
+    sent_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (event_id, hours_before, closing_date)
+);
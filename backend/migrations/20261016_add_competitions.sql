@@ -0,0 +1,22 @@
+-- Trading competitions: entrants trade competition-tagged markets from a
+-- wallet seeded with the competition's starting bankroll, separate from
+-- their reputation. The prediction engine also creates these at startup;
+-- this keeps fresh databases and the backend's view of the schema in step.
+CREATE TABLE IF NOT EXISTS competitions (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    starting_bankroll_ledger BIGINT NOT NULL CHECK (starting_bankroll_ledger > 0),
+    ends_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE events ADD COLUMN IF NOT EXISTS competition_id INTEGER REFERENCES competitions(id);
+
+CREATE TABLE IF NOT EXISTS competition_wallets (
+    competition_id INTEGER NOT NULL REFERENCES competitions(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    balance_ledger BIGINT NOT NULL CHECK (balance_ledger >= 0),
+    staked_ledger BIGINT NOT NULL DEFAULT 0 CHECK (staked_ledger >= 0),
+    joined_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (competition_id, user_id)
+);
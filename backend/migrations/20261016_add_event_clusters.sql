@@ -0,0 +1,16 @@
+-- Admin-defined clusters of events expected to resolve together, used by
+-- the prediction engine's exposure report. `inverse` members resolve
+-- opposite to the rest of their cluster. The engine also creates these on
+-- first use; this keeps fresh databases in step.
+CREATE TABLE IF NOT EXISTS event_clusters (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS event_cluster_members (
+    cluster_id INTEGER NOT NULL REFERENCES event_clusters(id) ON DELETE CASCADE,
+    event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    inverse BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (cluster_id, event_id)
+);
@@ -0,0 +1,13 @@
+-- Structured metadata for events: resolution criteria, fine print, the
+-- unit and bounds of the quantity a question resolves to, and the source
+-- URL. The prediction engine's importer fills these alongside the details
+-- text, and admins edit them through the engine's metadata endpoint. The
+-- engine also creates these columns at startup; this keeps fresh databases
+-- in step.
+ALTER TABLE events
+    ADD COLUMN IF NOT EXISTS resolution_criteria TEXT,
+    ADD COLUMN IF NOT EXISTS fine_print TEXT,
+    ADD COLUMN IF NOT EXISTS value_unit TEXT,
+    ADD COLUMN IF NOT EXISTS value_lower_bound DOUBLE PRECISION,
+    ADD COLUMN IF NOT EXISTS value_upper_bound DOUBLE PRECISION,
+    ADD COLUMN IF NOT EXISTS source_url TEXT;
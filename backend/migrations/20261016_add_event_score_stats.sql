@@ -0,0 +1,17 @@
+-- Crowd scores of resolved events, behind the forecast peer bonus. When an
+-- event resolves, the prediction engine averages the time-weighted scores
+-- of its journaled forecasts, counting only accounts old enough and with
+-- enough resolved forecasts, weighted by RP held plus staked. A forecast's
+-- peer score is its own log score minus crowd_log_score. A reverted
+-- resolution drops the row. The engine also creates this table at startup;
+-- this keeps fresh databases in step.
+CREATE TABLE IF NOT EXISTS event_score_stats (
+    event_id INTEGER PRIMARY KEY REFERENCES events(id) ON DELETE CASCADE,
+    outcome BOOLEAN NOT NULL,
+    crowd_log_score DOUBLE PRECISION,
+    crowd_brier_score DOUBLE PRECISION,
+    total_weight DOUBLE PRECISION NOT NULL,
+    eligible_forecasters INTEGER NOT NULL,
+    excluded_forecasters INTEGER NOT NULL,
+    computed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
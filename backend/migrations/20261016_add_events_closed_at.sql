@@ -0,0 +1,10 @@
+-- The prediction engine stamps closed_at when a market passes its
+-- closing_date (see its close sweep) and refuses trades on stamped markets.
+-- The engine also adds the column at startup; this keeps fresh databases
+-- and the backend's view of the schema in step.
+ALTER TABLE events ADD COLUMN IF NOT EXISTS closed_at TIMESTAMPTZ;
+
+-- Markets already past due and unresolved count as closed from now.
+UPDATE events
+SET closed_at = NOW()
+WHERE closed_at IS NULL AND outcome IS NULL AND closing_date <= NOW();
@@ -0,0 +1,15 @@
+-- Forecast journal: every submitted or revised unstaked forecast, with its
+-- time, behind the user's current row in predictions. The prediction engine
+-- also creates this table on first use; this keeps fresh databases and the
+-- backend's view of the schema in step.
+CREATE TABLE IF NOT EXISTS forecast_revisions (
+    id BIGSERIAL PRIMARY KEY,
+    prediction_id INTEGER NOT NULL REFERENCES predictions(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    probability DOUBLE PRECISION NOT NULL CHECK (probability >= 0 AND probability <= 1),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_forecast_revisions_user_event
+    ON forecast_revisions (user_id, event_id, created_at);
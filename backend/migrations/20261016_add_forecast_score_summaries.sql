@@ -0,0 +1,38 @@
+-- Compacted forecast histories. Once an event is resolved and past its
+-- dispute window, the prediction engine collapses each journaled forecast
+-- into one summary row with its time-weighted scores and moves the
+-- revisions from forecast_revisions into forecast_revisions_archive (or
+-- deletes them, if archiving is turned off). The engine also creates these
+-- tables on first use; this keeps fresh databases in step.
+CREATE TABLE IF NOT EXISTS forecast_score_summaries (
+    prediction_id INTEGER PRIMARY KEY REFERENCES predictions(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    outcome BOOLEAN NOT NULL,
+    revision_count INTEGER NOT NULL,
+    first_forecast_at TIMESTAMPTZ NOT NULL,
+    last_forecast_at TIMESTAMPTZ NOT NULL,
+    final_probability DOUBLE PRECISION NOT NULL,
+    scored_until TIMESTAMPTZ NOT NULL,
+    coverage DOUBLE PRECISION NOT NULL,
+    time_weighted_log_score DOUBLE PRECISION NOT NULL,
+    time_weighted_brier_score DOUBLE PRECISION NOT NULL,
+    revisions_archived BOOLEAN NOT NULL,
+    compacted_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_forecast_score_summaries_user
+    ON forecast_score_summaries (user_id, event_id);
+
+CREATE TABLE IF NOT EXISTS forecast_revisions_archive (
+    id BIGINT PRIMARY KEY,
+    prediction_id INTEGER NOT NULL REFERENCES predictions(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    probability DOUBLE PRECISION NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    archived_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_forecast_revisions_archive_prediction
+    ON forecast_revisions_archive (prediction_id, created_at);
@@ -0,0 +1,15 @@
+-- Snapshots that pin a paged competition leaderboard to one ranking: the
+-- first page stores the ranking under a version hashed from its contents,
+-- and later pages read it by id until it expires. The prediction engine
+-- also creates this at startup; this keeps fresh databases in step.
+CREATE TABLE IF NOT EXISTS leaderboard_snapshots (
+    id BIGSERIAL PRIMARY KEY,
+    competition_id INTEGER NOT NULL REFERENCES competitions(id) ON DELETE CASCADE,
+    version VARCHAR(64) NOT NULL,
+    leaderboard JSONB NOT NULL,
+    taken_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_leaderboard_snapshots_version
+    ON leaderboard_snapshots (competition_id, version);
@@ -0,0 +1,17 @@
+-- How well the market maker's closing prices forecast outcomes. When a
+-- binary event resolves, the prediction engine records its closing
+-- probability, category, liquidity and trade count with the Brier and log
+-- scores of that price, and averages them by category and liquidity bucket
+-- for tuning liquidity_b. The engine also creates this at startup (and
+-- backfills earlier resolutions); this keeps fresh databases in step.
+CREATE TABLE IF NOT EXISTS market_accuracy (
+    event_id INTEGER PRIMARY KEY REFERENCES events(id) ON DELETE CASCADE,
+    category TEXT,
+    outcome BOOLEAN NOT NULL,
+    closing_prob DOUBLE PRECISION NOT NULL,
+    liquidity_b DOUBLE PRECISION,
+    trades INTEGER NOT NULL DEFAULT 0,
+    brier_score DOUBLE PRECISION NOT NULL,
+    log_score DOUBLE PRECISION NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
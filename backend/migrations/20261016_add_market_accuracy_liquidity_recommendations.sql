@@ -0,0 +1,15 @@
+-- Per-category liquidity_b recommendations the prediction engine learns
+-- from market_accuracy: an accuracy-weighted, volatility-adjusted depth per
+-- category, refreshed periodically. A category's recommendation is only
+-- applied to new admin-created and imported markets once auto_apply is
+-- turned on for it. Named to sort after add_market_accuracy. The engine
+-- also creates this at startup; this keeps fresh databases in step.
+CREATE TABLE IF NOT EXISTS liquidity_recommendations (
+    category TEXT PRIMARY KEY,
+    recommended_b DOUBLE PRECISION NOT NULL CHECK (recommended_b > 0),
+    markets INTEGER NOT NULL,
+    mean_log_score DOUBLE PRECISION NOT NULL,
+    volatility DOUBLE PRECISION,
+    auto_apply BOOLEAN NOT NULL DEFAULT FALSE,
+    computed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
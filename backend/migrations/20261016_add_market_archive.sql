@@ -0,0 +1,52 @@
+-- Cold storage for long-resolved markets. The prediction engine moves an
+-- event's market_updates, market_outcome_updates and resolution_payouts
+-- rows to these archive twins once it has been resolved for a while
+-- (MARKET_ARCHIVE_AFTER_DAYS), logs the move in archived_events, and reads
+-- full histories through the *_all views. The engine also creates these at
+-- startup, keeping the twins' columns in step with the hot tables; this
+-- keeps fresh databases in step.
+CREATE TABLE IF NOT EXISTS archived_events (
+    event_id INTEGER PRIMARY KEY REFERENCES events(id) ON DELETE CASCADE,
+    rows_archived BIGINT NOT NULL,
+    archived_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    restored_at TIMESTAMPTZ,
+    restored_by TEXT,
+    restore_reason TEXT
+);
+
+CREATE TABLE IF NOT EXISTS market_updates_archive (LIKE market_updates);
+CREATE INDEX IF NOT EXISTS idx_market_updates_archive_event
+    ON market_updates_archive (event_id);
+CREATE OR REPLACE VIEW market_updates_all AS
+    SELECT * FROM market_updates
+    UNION ALL
+    SELECT * FROM market_updates_archive;
+
+CREATE TABLE IF NOT EXISTS market_outcome_updates_archive (LIKE market_outcome_updates);
+CREATE INDEX IF NOT EXISTS idx_market_outcome_updates_archive_event
+    ON market_outcome_updates_archive (event_id);
+CREATE OR REPLACE VIEW market_outcome_updates_all AS
+    SELECT * FROM market_outcome_updates
+    UNION ALL
+    SELECT * FROM market_outcome_updates_archive;
+
+CREATE TABLE IF NOT EXISTS resolution_payouts (
+    id BIGSERIAL PRIMARY KEY,
+    event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    outcome VARCHAR(50) NOT NULL,
+    yes_shares DOUBLE PRECISION NOT NULL,
+    no_shares DOUBLE PRECISION NOT NULL,
+    staked_yes_ledger BIGINT NOT NULL,
+    staked_no_ledger BIGINT NOT NULL,
+    payout_ledger BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    reverted_at TIMESTAMPTZ
+);
+CREATE TABLE IF NOT EXISTS resolution_payouts_archive (LIKE resolution_payouts);
+CREATE INDEX IF NOT EXISTS idx_resolution_payouts_archive_event
+    ON resolution_payouts_archive (event_id);
+CREATE OR REPLACE VIEW resolution_payouts_all AS
+    SELECT * FROM resolution_payouts
+    UNION ALL
+    SELECT * FROM resolution_payouts_archive;
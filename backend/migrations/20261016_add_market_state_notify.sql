@@ -0,0 +1,33 @@
+-- Announces changes to an event's market state on the market_state_changed
+-- channel, with the event id as payload, so the prediction engine can evict
+-- its cached copy. The engine also installs these triggers at startup; this
+-- keeps fresh databases in step.
+CREATE OR REPLACE FUNCTION notify_market_state_changed() RETURNS trigger AS $$
+DECLARE
+    changed_id INTEGER;
+BEGIN
+    IF TG_TABLE_NAME = 'events' AND TG_OP = 'DELETE' THEN
+        changed_id := OLD.id;
+    ELSIF TG_TABLE_NAME = 'events' THEN
+        changed_id := NEW.id;
+    ELSIF TG_OP = 'DELETE' THEN
+        changed_id := OLD.event_id;
+    ELSE
+        changed_id := NEW.event_id;
+    END IF;
+    PERFORM pg_notify('market_state_changed', changed_id::text);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER market_state_changed
+    AFTER INSERT OR UPDATE OR DELETE ON events
+    FOR EACH ROW EXECUTE FUNCTION notify_market_state_changed();
+
+CREATE OR REPLACE TRIGGER market_state_changed
+    AFTER INSERT OR UPDATE OR DELETE ON event_outcome_states
+    FOR EACH ROW EXECUTE FUNCTION notify_market_state_changed();
+
+CREATE OR REPLACE TRIGGER market_state_changed
+    AFTER INSERT OR UPDATE OR DELETE ON event_outcomes
+    FOR EACH ROW EXECUTE FUNCTION notify_market_state_changed();
@@ -0,0 +1,5 @@
+-- Serves the prediction engine's sparkline lookups, which find each
+-- market's last trade before a series of times. The engine also creates
+-- this index at startup; this keeps fresh databases in step.
+CREATE INDEX IF NOT EXISTS idx_market_updates_event_created
+    ON market_updates(event_id, created_at, id);
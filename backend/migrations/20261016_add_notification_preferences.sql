@@ -0,0 +1,12 @@
+-- Which engine notifications each user receives on their WebSocket topic
+-- (user:<id>) and is listed for in webhook payloads: market resolutions,
+-- rank changes from persuasion scoring, and closing reminders. Users
+-- without a row get all three. The prediction engine also creates this at
+-- startup; this keeps fresh databases in step.
+CREATE TABLE IF NOT EXISTS notification_preferences (
+    user_id INTEGER PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    resolutions BOOLEAN NOT NULL DEFAULT TRUE,
+    rank_changes BOOLEAN NOT NULL DEFAULT TRUE,
+    closing_reminders BOOLEAN NOT NULL DEFAULT TRUE,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,11 @@
+-- Previewed binary resolutions awaiting an admin's commit or cancel. The
+-- prediction engine dry-runs the resolution, stores the per-user payouts
+-- and market maker P&L here, and on commit resolves for real only if the
+-- settlements still match. The engine also creates this table at startup;
+-- this keeps fresh databases in step.
+CREATE TABLE IF NOT EXISTS pending_resolutions (
+    event_id INTEGER PRIMARY KEY REFERENCES events(id) ON DELETE CASCADE,
+    outcome BOOLEAN NOT NULL,
+    report JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,38 @@
+-- Journal of RP the prediction engine hands out: one onboarding grant per
+-- user and any faucet top-ups. Users created so far got their starting RP
+-- from the rp_balance_ledger column default; that is recorded here as
+-- their onboarding grant and the default is dropped to 0, since the
+-- engine's faucet sweep now credits new users. The engine also does all of
+-- this at startup; this keeps fresh databases in step.
+CREATE TABLE IF NOT EXISTS rp_faucet_grants (
+    id BIGSERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind VARCHAR(16) NOT NULL CHECK (kind IN ('onboarding', 'topup')),
+    amount_ledger BIGINT NOT NULL CHECK (amount_ledger >= 0),
+    balance_after_ledger BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_rp_faucet_grants_onboarding
+    ON rp_faucet_grants(user_id) WHERE kind = 'onboarding';
+
+CREATE INDEX IF NOT EXISTS idx_rp_faucet_grants_user
+    ON rp_faucet_grants(user_id, kind, created_at);
+
+BEGIN;
+LOCK TABLE users IN SHARE ROW EXCLUSIVE MODE;
+INSERT INTO rp_faucet_grants (user_id, kind, amount_ledger, created_at)
+SELECT u.id, 'onboarding', 1000000000, COALESCE(u.created_at, NOW())
+FROM users u
+WHERE NOT EXISTS (
+    SELECT 1 FROM rp_faucet_grants g
+    WHERE g.user_id = u.id AND g.kind = 'onboarding'
+)
+AND EXISTS (
+    SELECT 1 FROM information_schema.columns
+    WHERE table_schema = current_schema()
+      AND table_name = 'users' AND column_name = 'rp_balance_ledger'
+      AND column_default IS NOT NULL AND column_default <> '0'
+);
+ALTER TABLE users ALTER COLUMN rp_balance_ledger SET DEFAULT 0;
+COMMIT;
@@ -0,0 +1,12 @@
+-- Per-user checksum over the scoring inputs of resolved markets (resolved
+-- predictions, standing settlement payouts, realized P&L), sealed by the
+-- engine's resolution and dispute transactions and recomputed by score
+-- audits to catch out-of-band changes. The prediction engine also creates
+-- this at startup; this keeps fresh databases in step.
+CREATE TABLE IF NOT EXISTS user_score_checksums (
+    user_id INTEGER PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    checksum VARCHAR(64) NOT NULL,
+    resolved_predictions INTEGER NOT NULL,
+    settlements INTEGER NOT NULL,
+    sealed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
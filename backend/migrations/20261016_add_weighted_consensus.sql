@@ -0,0 +1,19 @@
+-- Reputation-weighted consensus probability. The prediction engine pools
+-- each user's latest belief on a binary event (their forecast, or the price
+-- their last buy moved the market to), weighted by RP held plus staked, and
+-- stores it beside market_prob. At resolution it records both in
+-- consensus_accuracy so their calibration can be compared. The engine also
+-- creates these at startup; this keeps fresh databases in step.
+ALTER TABLE events
+    ADD COLUMN IF NOT EXISTS weighted_prob DOUBLE PRECISION,
+    ADD COLUMN IF NOT EXISTS weighted_prob_contributors INTEGER,
+    ADD COLUMN IF NOT EXISTS weighted_prob_updated_at TIMESTAMPTZ;
+
+CREATE TABLE IF NOT EXISTS consensus_accuracy (
+    event_id INTEGER PRIMARY KEY REFERENCES events(id) ON DELETE CASCADE,
+    outcome BOOLEAN NOT NULL,
+    market_prob DOUBLE PRECISION NOT NULL,
+    weighted_prob DOUBLE PRECISION,
+    contributors INTEGER NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,17 @@
+-- Hourly comment summaries pushed to the prediction engine, shown as "buzz"
+-- beside market probabilities. Pushing the same event and hour again
+-- replaces the row. consensus_accuracy also records the comment velocity
+-- (comments per hour over the last day) when an event resolves. Named to
+-- sort after add_weighted_consensus, which creates that table. The engine
+-- also creates these at startup; this keeps fresh databases in step.
+CREATE TABLE IF NOT EXISTS event_comment_stats (
+    event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    hour TIMESTAMPTZ NOT NULL,
+    comment_count INTEGER NOT NULL CHECK (comment_count >= 0),
+    sentiment DOUBLE PRECISION CHECK (sentiment BETWEEN -1 AND 1),
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (event_id, hour)
+);
+
+ALTER TABLE consensus_accuracy
+    ADD COLUMN IF NOT EXISTS comment_velocity DOUBLE PRECISION;
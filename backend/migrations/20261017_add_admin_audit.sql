@@ -0,0 +1,31 @@
+-- Append-only log of every state-changing admin request: the action, who
+-- made it, the request and its status, and before/after snapshots of the
+-- market it touched. Mirrors the table the prediction engine creates at
+-- startup.
+CREATE TABLE IF NOT EXISTS admin_audit (
+    id BIGSERIAL PRIMARY KEY,
+    action VARCHAR(40) NOT NULL,
+    actor TEXT,
+    method VARCHAR(10) NOT NULL,
+    path TEXT NOT NULL,
+    query TEXT,
+    params JSONB,
+    event_id INTEGER,
+    status SMALLINT NOT NULL,
+    before JSONB,
+    after JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_admin_audit_event
+    ON admin_audit (event_id, id DESC) WHERE event_id IS NOT NULL;
+
+CREATE OR REPLACE FUNCTION admin_audit_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'admin_audit is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER admin_audit_append_only
+    BEFORE UPDATE OR DELETE ON admin_audit
+    FOR EACH ROW EXECUTE FUNCTION admin_audit_append_only();
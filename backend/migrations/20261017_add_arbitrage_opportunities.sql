@@ -0,0 +1,12 @@
+-- Clusters whose linked prices leave a gap of at least the scan's minimum,
+-- one row per cluster with when the gap was first seen. The prediction
+-- engine's arbitrage scan rewrites the rows, dropping clusters whose gap
+-- has closed, and creates the table on first use.
+CREATE TABLE IF NOT EXISTS arbitrage_opportunities (
+    cluster_id INTEGER PRIMARY KEY REFERENCES event_clusters(id) ON DELETE CASCADE,
+    gap DOUBLE PRECISION NOT NULL,
+    probability_sum DOUBLE PRECISION,
+    legs JSONB NOT NULL,
+    first_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    detected_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,7 @@
+-- How a cluster's events are linked: 'correlated' (they resolve together,
+-- inverse members the other way), 'mutually_exclusive' (at most one
+-- resolves YES) or 'related' (no constraint). Only correlated clusters
+-- enter the exposure report; the consistency and arbitrage checks price
+-- the others.
+ALTER TABLE event_clusters
+    ADD COLUMN IF NOT EXISTS relation VARCHAR(20) NOT NULL DEFAULT 'correlated';
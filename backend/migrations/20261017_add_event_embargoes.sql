@@ -0,0 +1,17 @@
+-- Trading embargo windows: while one of an event's [starts_at, ends_at)
+-- windows is open, buys and sells on its market fail. Windows are kept
+-- after they end so admins can see what was paused and why. The
+-- prediction engine also creates this at startup.
+CREATE TABLE IF NOT EXISTS event_embargoes (
+    id BIGSERIAL PRIMARY KEY,
+    event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    starts_at TIMESTAMPTZ NOT NULL,
+    ends_at TIMESTAMPTZ NOT NULL,
+    reason TEXT,
+    created_by VARCHAR(255) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (ends_at > starts_at)
+);
+
+CREATE INDEX IF NOT EXISTS idx_event_embargoes_event
+    ON event_embargoes(event_id, ends_at);
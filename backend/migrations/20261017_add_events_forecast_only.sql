@@ -0,0 +1,4 @@
+-- Imported numeric and date questions are forecast-only: they take
+-- forecasts but have no market, and every trade path refuses them.
+ALTER TABLE events
+  ADD COLUMN IF NOT EXISTS forecast_only BOOLEAN NOT NULL DEFAULT FALSE;
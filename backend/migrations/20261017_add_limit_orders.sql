@@ -0,0 +1,36 @@
+-- Resting limit orders: a stake that trades when the market price reaches
+-- the trigger. The stake is reserved out of the wallet balance when the
+-- order is placed and returned on cancel or expiry. Mirrors the table the
+-- prediction engine creates at startup.
+CREATE TABLE IF NOT EXISTS limit_orders (
+    id BIGSERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    side VARCHAR(3) NOT NULL CHECK (side IN ('yes', 'no')),
+    trigger_prob DOUBLE PRECISION NOT NULL
+        CHECK (trigger_prob > 0 AND trigger_prob < 1),
+    stake_ledger BIGINT NOT NULL CHECK (stake_ledger > 0),
+    filled_ledger BIGINT NOT NULL DEFAULT 0,
+    reserved_ledger BIGINT NOT NULL DEFAULT 0 CHECK (reserved_ledger >= 0),
+    status VARCHAR(16) NOT NULL DEFAULT 'open',
+    market_update_id INTEGER,
+    fill_prob DOUBLE PRECISION,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    filled_at TIMESTAMPTZ,
+    cancelled_at TIMESTAMPTZ
+);
+
+ALTER TABLE limit_orders
+    ADD COLUMN IF NOT EXISTS filled_ledger BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS reserved_ledger BIGINT NOT NULL DEFAULT 0
+        CHECK (reserved_ledger >= 0);
+
+ALTER TABLE limit_orders DROP CONSTRAINT IF EXISTS limit_orders_status_check;
+ALTER TABLE limit_orders ADD CONSTRAINT limit_orders_status_check
+    CHECK (status IN ('open', 'filled', 'failed', 'cancelled', 'expired'));
+
+CREATE INDEX IF NOT EXISTS idx_limit_orders_open
+    ON limit_orders (event_id, created_at, id) WHERE status = 'open';
+CREATE INDEX IF NOT EXISTS idx_limit_orders_user
+    ON limit_orders (user_id, created_at DESC);
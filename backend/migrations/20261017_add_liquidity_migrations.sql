@@ -0,0 +1,22 @@
+-- Audit trail of liquidity_b changes on live markets: the market state
+-- before and after each rescale, who ran it and why. The prediction engine
+-- writes a row per migration and creates the table at startup.
+CREATE TABLE IF NOT EXISTS liquidity_migrations (
+    id BIGSERIAL PRIMARY KEY,
+    event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    old_b DOUBLE PRECISION NOT NULL,
+    new_b DOUBLE PRECISION NOT NULL,
+    old_q_yes DOUBLE PRECISION NOT NULL,
+    old_q_no DOUBLE PRECISION NOT NULL,
+    new_q_yes DOUBLE PRECISION NOT NULL,
+    new_q_no DOUBLE PRECISION NOT NULL,
+    old_cost DOUBLE PRECISION NOT NULL,
+    new_cost DOUBLE PRECISION NOT NULL,
+    market_prob DOUBLE PRECISION NOT NULL,
+    actor VARCHAR(255) NOT NULL,
+    reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_liquidity_migrations_event
+    ON liquidity_migrations(event_id, created_at);
@@ -0,0 +1,14 @@
+-- Trending score per open market from the last 24 hours of trades, unique
+-- traders, price movement and stake rate. Rewritten by the prediction
+-- engine's periodic heat refresh, which also creates the table on first use.
+CREATE TABLE IF NOT EXISTS market_heat (
+    event_id INTEGER PRIMARY KEY REFERENCES events(id) ON DELETE CASCADE,
+    heat DOUBLE PRECISION NOT NULL,
+    trades_24h BIGINT NOT NULL,
+    unique_traders_24h BIGINT NOT NULL,
+    prob_move_24h DOUBLE PRECISION NOT NULL,
+    stake_per_hour DOUBLE PRECISION NOT NULL,
+    computed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_market_heat_heat ON market_heat (heat DESC);
@@ -0,0 +1,13 @@
+-- Every price move a trade makes in a market, the source of the candle
+-- charts. The prediction engine creates the table at startup and, while it
+-- is still empty, backfills it from the buy journal.
+CREATE TABLE IF NOT EXISTS market_price_history (
+    id BIGSERIAL PRIMARY KEY,
+    event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    prev_prob DOUBLE PRECISION NOT NULL,
+    prob DOUBLE PRECISION NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_market_price_history_event_created
+    ON market_price_history (event_id, created_at, id);
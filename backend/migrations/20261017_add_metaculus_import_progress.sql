@@ -0,0 +1,18 @@
+-- Resumable state for the bulk Metaculus import (next page URL, counts,
+-- pause flag, last error) and the per-day API request counter it spends
+-- against. The prediction engine also creates both tables before an import.
+CREATE TABLE IF NOT EXISTS metaculus_import_progress (
+    job VARCHAR(64) PRIMARY KEY,
+    next_url TEXT,
+    page INTEGER NOT NULL DEFAULT 0,
+    total_stored INTEGER NOT NULL DEFAULT 0,
+    status VARCHAR(16) NOT NULL DEFAULT 'idle',
+    pause_requested BOOLEAN NOT NULL DEFAULT FALSE,
+    last_error TEXT,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS metaculus_request_budget (
+    day DATE PRIMARY KEY,
+    request_count INTEGER NOT NULL DEFAULT 0
+);
@@ -0,0 +1,35 @@
+-- MLS delivery-service state: each group's pinned epoch and tracked
+-- PublicGroup, the commits it accepted, and Welcomes waiting for their key
+-- package. The prediction engine runs the same DDL at startup.
+CREATE TABLE IF NOT EXISTS mls_ds_groups (
+    group_id BYTEA PRIMARY KEY,
+    epoch BIGINT NOT NULL,
+    verified BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS mls_ds_group_state (
+    group_id BYTEA NOT NULL REFERENCES mls_ds_groups(group_id) ON DELETE CASCADE,
+    key BYTEA NOT NULL,
+    value BYTEA NOT NULL,
+    PRIMARY KEY (group_id, key)
+);
+
+CREATE TABLE IF NOT EXISTS mls_ds_commits (
+    group_id BYTEA NOT NULL REFERENCES mls_ds_groups(group_id) ON DELETE CASCADE,
+    epoch BIGINT NOT NULL,
+    message BYTEA NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (group_id, epoch)
+);
+
+CREATE TABLE IF NOT EXISTS mls_ds_welcomes (
+    id BIGSERIAL PRIMARY KEY,
+    key_package_ref BYTEA NOT NULL,
+    welcome BYTEA NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_mls_ds_welcomes_ref
+    ON mls_ds_welcomes(key_package_ref, created_at);
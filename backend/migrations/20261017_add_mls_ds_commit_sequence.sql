@@ -0,0 +1,30 @@
+-- Number each group's accepted commits by a per-group sequence so clients
+-- can page the backlog after the last one they saw. The prediction engine
+-- runs the same DDL, including the backfill, at startup.
+CREATE TABLE IF NOT EXISTS mls_ds_commit_counters (
+    group_id BYTEA PRIMARY KEY REFERENCES mls_ds_groups(group_id) ON DELETE CASCADE,
+    last_sequence BIGINT NOT NULL
+);
+
+ALTER TABLE mls_ds_commits ADD COLUMN IF NOT EXISTS sequence BIGINT;
+
+-- Commits stored before sequencing are numbered in epoch order
+UPDATE mls_ds_commits c
+SET sequence = numbered.sequence
+FROM (
+    SELECT group_id, epoch,
+           ROW_NUMBER() OVER (PARTITION BY group_id ORDER BY epoch) AS sequence
+    FROM mls_ds_commits
+) numbered
+WHERE c.sequence IS NULL
+  AND c.group_id = numbered.group_id
+  AND c.epoch = numbered.epoch;
+
+INSERT INTO mls_ds_commit_counters (group_id, last_sequence)
+SELECT group_id, MAX(sequence) FROM mls_ds_commits GROUP BY group_id
+ON CONFLICT (group_id) DO NOTHING;
+
+ALTER TABLE mls_ds_commits ALTER COLUMN sequence SET NOT NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_mls_ds_commits_sequence
+    ON mls_ds_commits(group_id, sequence);
@@ -0,0 +1,38 @@
+-- Directory of MLS groups by application group id: the latest GroupInfo
+-- for external joins, membership hints, and invite tokens (stored only as
+-- their SHA-256 hash). The prediction engine creates these tables at
+-- startup as well.
+CREATE TABLE IF NOT EXISTS mls_directory_groups (
+    app_group_id TEXT PRIMARY KEY,
+    mls_group_id BYTEA NOT NULL,
+    epoch BIGINT NOT NULL,
+    group_info BYTEA NOT NULL,
+    ratchet_tree BYTEA,
+    owner_user_id INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS mls_directory_members (
+    app_group_id TEXT NOT NULL
+        REFERENCES mls_directory_groups(app_group_id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    added_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (app_group_id, user_id)
+);
+
+CREATE TABLE IF NOT EXISTS mls_directory_invites (
+    id BIGSERIAL PRIMARY KEY,
+    app_group_id TEXT NOT NULL
+        REFERENCES mls_directory_groups(app_group_id) ON DELETE CASCADE,
+    token_hash TEXT NOT NULL UNIQUE,
+    inviter_user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    invitee_user_id INTEGER REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    redeemed_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
+
+-- Welcomes can name the directory group they admit the recipient to
+ALTER TABLE mls_ds_welcomes ADD COLUMN IF NOT EXISTS app_group_id TEXT;
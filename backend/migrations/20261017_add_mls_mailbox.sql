@@ -0,0 +1,18 @@
+-- Per-recipient mailbox for MLS ciphertexts, so undelivered messages
+-- survive a relay restart. Rows stay pending until the recipient acks them.
+-- Also created by the prediction engine at startup.
+CREATE TABLE IF NOT EXISTS mls_mailbox_messages (
+    id BIGSERIAL PRIMARY KEY,
+    recipient_user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    sender_user_id INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    group_id BYTEA,
+    message BYTEA NOT NULL,
+    enqueued_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ,
+    delivery_count INTEGER NOT NULL DEFAULT 0,
+    last_delivered_at TIMESTAMPTZ,
+    acked_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_mls_mailbox_pending
+    ON mls_mailbox_messages(recipient_user_id, id) WHERE acked_at IS NULL;
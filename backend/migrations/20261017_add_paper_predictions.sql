@@ -0,0 +1,15 @@
+-- Unstaked practice forecasts on already-resolved binary events, scored on
+-- submission next to the market's final probability. One per user and
+-- event. The prediction engine creates the table on first use too.
+CREATE TABLE IF NOT EXISTS paper_predictions (
+    id BIGSERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    probability DOUBLE PRECISION NOT NULL,
+    outcome BOOLEAN NOT NULL,
+    brier_score DOUBLE PRECISION NOT NULL,
+    log_score DOUBLE PRECISION NOT NULL,
+    market_prob DOUBLE PRECISION,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, event_id)
+);
@@ -0,0 +1,26 @@
+-- Registered push devices and a log of every notice sent to them. Notices
+-- carry routing metadata only, never ciphertext. The prediction engine
+-- creates both tables at startup as well.
+CREATE TABLE IF NOT EXISTS push_devices (
+    id BIGSERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    platform VARCHAR(16) NOT NULL CHECK (platform IN ('apns', 'fcm', 'web')),
+    token TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_pushed_at TIMESTAMPTZ,
+    pushed_through BIGINT NOT NULL DEFAULT 0,
+    UNIQUE (platform, token)
+);
+
+CREATE TABLE IF NOT EXISTS push_dispatches (
+    id BIGSERIAL PRIMARY KEY,
+    device_id BIGINT NOT NULL REFERENCES push_devices(id) ON DELETE CASCADE,
+    message_count INTEGER NOT NULL,
+    through_message_id BIGINT NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_push_dispatches_device
+    ON push_dispatches(device_id, created_at);
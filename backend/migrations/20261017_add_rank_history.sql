@@ -0,0 +1,20 @@
+-- Each user's last published global rank and the history of rank changes,
+-- with the event whose resolution or trade triggered the recompute.
+-- Mirrors the tables the prediction engine creates at startup.
+CREATE TABLE IF NOT EXISTS global_ranks (
+    user_id INTEGER PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    rank BIGINT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS rank_transitions (
+    id BIGSERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    old_rank BIGINT,
+    new_rank BIGINT NOT NULL,
+    cause_event_id INTEGER REFERENCES events(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_rank_transitions_user
+    ON rank_transitions (user_id, created_at DESC, id DESC);
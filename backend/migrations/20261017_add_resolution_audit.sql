@@ -0,0 +1,19 @@
+-- Audit trail for resolutions, disputed reverts and re-resolutions, with
+-- the positions and RP each one moved. Resolution reverts look up open
+-- payouts through idx_resolution_payouts_open. The prediction engine
+-- creates both at startup.
+CREATE TABLE IF NOT EXISTS resolution_audit (
+    id BIGSERIAL PRIMARY KEY,
+    event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    action VARCHAR(20) NOT NULL,
+    outcome VARCHAR(50),
+    previous_outcome VARCHAR(50),
+    actor TEXT,
+    reason TEXT,
+    positions INTEGER NOT NULL DEFAULT 0,
+    amount_ledger BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_resolution_payouts_open
+    ON resolution_payouts (event_id) WHERE reverted_at IS NULL;
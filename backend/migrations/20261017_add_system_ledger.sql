@@ -0,0 +1,27 @@
+-- Append-only ledger of the house, fee pool and faucet accounts: every RP
+-- movement between a user and the system books its other side here, from
+-- the prediction engine's trades and faucet and from the backend's question
+-- bonds and rewards, weekly decay and signal rewards. The prediction engine
+-- creates the same objects at startup and books the house's opening row.
+CREATE TABLE IF NOT EXISTS system_ledger (
+    id BIGSERIAL PRIMARY KEY,
+    account VARCHAR(16) NOT NULL CHECK (account IN ('house', 'fee_pool', 'faucet')),
+    kind VARCHAR(20) NOT NULL,
+    user_id INTEGER,
+    event_id INTEGER,
+    delta_ledger BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_system_ledger_opening
+    ON system_ledger (kind) WHERE kind = 'opening';
+
+CREATE OR REPLACE FUNCTION system_ledger_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'system_ledger is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER system_ledger_append_only
+    BEFORE UPDATE OR DELETE ON system_ledger
+    FOR EACH ROW EXECUTE FUNCTION system_ledger_append_only();
@@ -0,0 +1,11 @@
+-- Durable realized P&L per user and market. user_shares carries the
+-- running figure only while a position is open, so settlements and sells
+-- also add to this total. Mirrors the table the prediction engine creates
+-- at startup.
+CREATE TABLE IF NOT EXISTS user_realized_pnl (
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    realized_pnl_ledger BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, event_id)
+);
@@ -0,0 +1,7 @@
+-- Forecasts on events resolved N/A settle as 'not_applicable': they are
+-- closed but unscored, so accuracy denominators only count correct/incorrect.
+ALTER TABLE predictions
+  DROP CONSTRAINT IF EXISTS predictions_outcome_check;
+ALTER TABLE predictions
+  ADD CONSTRAINT predictions_outcome_check
+  CHECK (outcome IN ('correct', 'incorrect', 'pending', 'not_applicable'));
@@ -0,0 +1,22 @@
+-- Admin actions are logged as pending before they run and completed with
+-- their status and after snapshot once they have, so an entry may be
+-- updated exactly once, from no status to one. Mirrors the prediction
+-- engine's startup schema.
+ALTER TABLE admin_audit ALTER COLUMN status DROP NOT NULL;
+
+CREATE OR REPLACE FUNCTION admin_audit_append_only() RETURNS trigger AS $$
+BEGIN
+    -- A pending entry is completed once, with its status and after
+    -- snapshot; nothing else about it changes
+    IF TG_OP = 'UPDATE' AND OLD.status IS NULL AND NEW.status IS NOT NULL
+       AND (NEW.id, NEW.action, NEW.actor, NEW.method, NEW.path, NEW.query,
+            NEW.params, NEW.event_id, NEW.before, NEW.created_at)
+           IS NOT DISTINCT FROM
+           (OLD.id, OLD.action, OLD.actor, OLD.method, OLD.path, OLD.query,
+            OLD.params, OLD.event_id, OLD.before, OLD.created_at)
+    THEN
+        RETURN NEW;
+    END IF;
+    RAISE EXCEPTION 'admin_audit is append-only';
+END;
+$$ LANGUAGE plpgsql;
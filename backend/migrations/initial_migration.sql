@@ -76,7 +76,7 @@ CREATE TABLE IF NOT EXISTS predictions (
     confidence INTEGER CHECK (confidence BETWEEN 0 AND 100),
     created_at TIMESTAMP DEFAULT NOW(),
     resolved_at TIMESTAMP,
-    outcome TEXT CHECK (outcome IN ('correct', 'incorrect', 'pending')),
+    outcome TEXT CHECK (outcome IN ('correct', 'incorrect', 'pending', 'not_applicable')),
     -- Numerical prediction support
     prediction_type VARCHAR(20) DEFAULT 'binary' CHECK (prediction_type IN ('binary', 'numeric', 'discrete', 'multiple_choice', 'date')),
     numerical_value DECIMAL(15,6), -- Point estimate for numerical predictions
@@ -19,23 +19,23 @@ const topPredictorsFor = async (userId) => {
      ),
      in_topic AS (
        SELECT p.user_id,
-              COUNT(*) FILTER (WHERE p.outcome IS NOT NULL) AS resolved,
+              COUNT(*) FILTER (WHERE LOWER(COALESCE(p.outcome, '')) IN ('correct', 'incorrect')) AS resolved,
               100.0 * COUNT(*) FILTER (WHERE LOWER(COALESCE(p.outcome, '')) = 'correct')
-                / NULLIF(COUNT(*) FILTER (WHERE p.outcome IS NOT NULL), 0) AS accuracy_percent
+                / NULLIF(COUNT(*) FILTER (WHERE LOWER(COALESCE(p.outcome, '')) IN ('correct', 'incorrect')), 0) AS accuracy_percent
        FROM predictions p
        JOIN event_topics et ON et.event_id = p.event_id
        JOIN my_topics mt ON mt.topic_id = et.topic_id
        GROUP BY p.user_id
-       HAVING COUNT(*) FILTER (WHERE p.outcome IS NOT NULL) >= $2
+       HAVING COUNT(*) FILTER (WHERE LOWER(COALESCE(p.outcome, '')) IN ('correct', 'incorrect')) >= $2
      ),
      global_acc AS (
        SELECT p.user_id,
-              COUNT(*) FILTER (WHERE p.outcome IS NOT NULL) AS resolved,
+              COUNT(*) FILTER (WHERE LOWER(COALESCE(p.outcome, '')) IN ('correct', 'incorrect')) AS resolved,
               100.0 * COUNT(*) FILTER (WHERE LOWER(COALESCE(p.outcome, '')) = 'correct')
-                / NULLIF(COUNT(*) FILTER (WHERE p.outcome IS NOT NULL), 0) AS accuracy_percent
+                / NULLIF(COUNT(*) FILTER (WHERE LOWER(COALESCE(p.outcome, '')) IN ('correct', 'incorrect')), 0) AS accuracy_percent
        FROM predictions p
        GROUP BY p.user_id
-       HAVING COUNT(*) FILTER (WHERE p.outcome IS NOT NULL) >= $2
+       HAVING COUNT(*) FILTER (WHERE LOWER(COALESCE(p.outcome, '')) IN ('correct', 'incorrect')) >= $2
      ),
      ranked AS (
        SELECT user_id, accuracy_percent, resolved, 0 AS tier FROM in_topic
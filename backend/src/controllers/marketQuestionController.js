@@ -5,6 +5,7 @@ const {
   validateNumericBuckets,
   seedEventOutcomes
 } = require('../utils/eventOutcomes');
+const { bookFaucet } = require('../services/systemLedgerService');
 
 const LEDGER_SCALE = 1_000_000n;
 
@@ -77,6 +78,7 @@ const settleCreatorReward = async ({
   client,
   submissionId,
   creatorUserId,
+  eventId,
   rewardLedger,
   rewardColumn
 }) => {
@@ -96,6 +98,12 @@ const settleCreatorReward = async ({
     'UPDATE users SET rp_balance_ledger = rp_balance_ledger + $1::bigint WHERE id = $2',
     [rewardLedger, creatorUserId]
   );
+  await bookFaucet(client, {
+    kind: 'question_reward',
+    userIds: [creatorUserId],
+    userDeltaLedger: rewardLedger,
+    eventId
+  });
   return true;
 };
 
@@ -167,6 +175,7 @@ const runAutomaticMarketQuestionRewards = async (client) => {
             client,
             submissionId,
             creatorUserId,
+            eventId: Number(submission.approved_event_id),
             rewardLedger: toLedgerString(CREATOR_TRACTION_REWARD_RP),
             rewardColumn: 'creator_traction_reward_paid'
           });
@@ -196,6 +205,7 @@ const runAutomaticMarketQuestionRewards = async (client) => {
             client,
             submissionId,
             creatorUserId,
+            eventId: Number(submission.approved_event_id),
             rewardLedger: toLedgerString(CREATOR_RESOLUTION_REWARD_RP),
             rewardColumn: 'creator_resolution_reward_paid'
           });
@@ -328,6 +338,11 @@ exports.createSubmission = async (req, res) => {
     if (balanceUpdate.rows.length === 0) {
       throw httpError(400, 'Insufficient RP balance for submission bond');
     }
+    await bookFaucet(client, {
+      kind: 'question_bond',
+      userIds: [creatorUserId],
+      userDeltaLedger: -BigInt(bondLedger)
+    });
 
     const insertRes = await client.query(
       `INSERT INTO market_question_submissions
@@ -502,6 +517,11 @@ exports.submitReview = async (req, res) => {
     if (reviewerBalanceRes.rows.length === 0) {
       throw httpError(400, 'Insufficient RP balance for validator stake');
     }
+    await bookFaucet(client, {
+      kind: 'validator_stake',
+      userIds: [reviewerUserId],
+      userDeltaLedger: -BigInt(validatorStakeLedger)
+    });
 
     await client.query(
       `INSERT INTO market_question_reviews
@@ -575,6 +595,12 @@ exports.submitReview = async (req, res) => {
          WHERE id = $2`,
         [creatorApprovalPayoutLedger, submission.creator_user_id]
       );
+      await bookFaucet(client, {
+        kind: 'question_reward',
+        userIds: [Number(submission.creator_user_id)],
+        userDeltaLedger: creatorApprovalPayoutLedger,
+        eventId: approvedEventId
+      });
     }
 
     await client.query(
@@ -585,16 +611,23 @@ exports.submitReview = async (req, res) => {
       [submissionId, winningVote, validatorPayoutLedger]
     );
 
-    await client.query(
+    const paidValidatorsRes = await client.query(
       `UPDATE users
        SET rp_balance_ledger = rp_balance_ledger + $2::bigint
        WHERE id IN (
          SELECT reviewer_user_id
          FROM market_question_reviews
          WHERE submission_id = $1 AND vote = $3
-       )`,
+       )
+       RETURNING id`,
       [submissionId, validatorPayoutLedger, winningVote]
     );
+    await bookFaucet(client, {
+      kind: 'validator_payout',
+      userIds: paidValidatorsRes.rows.map((row) => Number(row.id)),
+      userDeltaLedger: validatorPayoutLedger,
+      eventId: approvedEventId
+    });
 
     const finalizedRes = await client.query(
       `UPDATE market_question_submissions
@@ -578,7 +578,7 @@ exports.getPosts = async (req, res) => {
               (
                 SELECT ROUND(
                   (100.0 * COUNT(ap.id) FILTER (WHERE LOWER(COALESCE(ap.outcome, '')) = 'correct')
-                   / NULLIF(COUNT(ap.id) FILTER (WHERE ap.outcome IS NOT NULL), 0))::NUMERIC, 1
+                   / NULLIF(COUNT(ap.id) FILTER (WHERE LOWER(COALESCE(ap.outcome, '')) IN ('correct', 'incorrect')), 0))::NUMERIC, 1
                 )::DOUBLE PRECISION
                 FROM predictions ap WHERE ap.user_id = p.user_id
               ) AS author_accuracy,
@@ -1010,7 +1010,7 @@ exports.getFeed = async (req, res) => {
               (
                 SELECT ROUND(
                   (100.0 * COUNT(ap.id) FILTER (WHERE LOWER(COALESCE(ap.outcome, '')) = 'correct')
-                   / NULLIF(COUNT(ap.id) FILTER (WHERE ap.outcome IS NOT NULL), 0))::NUMERIC, 1
+                   / NULLIF(COUNT(ap.id) FILTER (WHERE LOWER(COALESCE(ap.outcome, '')) IN ('correct', 'incorrect')), 0))::NUMERIC, 1
                 )::DOUBLE PRECISION
                 FROM predictions ap WHERE ap.user_id = p.user_id
               ) AS author_accuracy,
@@ -24,16 +24,16 @@ exports.getMyPredictionDashboard = async (req, res) => {
           SELECT
             COUNT(*)::INT AS total_predictions,
             COUNT(*) FILTER (WHERE outcome IS NULL)::INT AS pending_predictions,
-            COUNT(*) FILTER (WHERE outcome IS NOT NULL)::INT AS resolved_predictions,
+            COUNT(*) FILTER (WHERE LOWER(COALESCE(outcome, '')) IN ('correct', 'incorrect'))::INT AS resolved_predictions,
             COUNT(*) FILTER (WHERE LOWER(COALESCE(outcome, '')) = 'correct')::INT AS correct_predictions,
             COUNT(*) FILTER (WHERE LOWER(COALESCE(outcome, '')) = 'incorrect')::INT AS incorrect_predictions,
             ROUND(COALESCE(AVG(confidence), 0)::NUMERIC, 2)::DOUBLE PRECISION AS average_confidence,
             CASE
-              WHEN COUNT(*) FILTER (WHERE outcome IS NOT NULL) = 0 THEN NULL
+              WHEN COUNT(*) FILTER (WHERE LOWER(COALESCE(outcome, '')) IN ('correct', 'incorrect')) = 0 THEN NULL
               ELSE ROUND(
                 (
                   100.0 * COUNT(*) FILTER (WHERE LOWER(COALESCE(outcome, '')) = 'correct')
-                  / NULLIF(COUNT(*) FILTER (WHERE outcome IS NOT NULL), 0)
+                  / NULLIF(COUNT(*) FILTER (WHERE LOWER(COALESCE(outcome, '')) IN ('correct', 'incorrect')), 0)
                 )::NUMERIC,
                 2
               )::DOUBLE PRECISION
@@ -556,6 +556,8 @@ exports.resolveEvent = asyncHandler(async (req, res) => {
     method: 'POST',
     headers: {
       'Content-Type': 'application/json',
+      // Names the admin in the engine's admin_audit log
+      ...(req.user?.userId ? { 'x-admin-actor': `user:${req.user.userId}` } : {}),
       ...(process.env.PREDICTION_ENGINE_AUTH_TOKEN ? { 'x-engine-token': process.env.PREDICTION_ENGINE_AUTH_TOKEN } : {})
     },
     body: JSON.stringify(engineBody)
@@ -1051,7 +1051,7 @@ exports.getNetworkGraph = async (req, res) => {
                ROUND(
                  (
                    100.0 * COUNT(p.id) FILTER (WHERE LOWER(COALESCE(p.outcome, '')) = 'correct')
-                   / NULLIF(COUNT(p.id) FILTER (WHERE p.outcome IS NOT NULL), 0)
+                   / NULLIF(COUNT(p.id) FILTER (WHERE LOWER(COALESCE(p.outcome, '')) IN ('correct', 'incorrect')), 0)
                  )::NUMERIC, 1
                )::DOUBLE PRECISION AS accuracy_percent
         FROM users u
@@ -1098,7 +1098,7 @@ exports.getFollowers = async (req, res) => {
               ROUND(
                 (
                   100.0 * COUNT(p.id) FILTER (WHERE LOWER(COALESCE(p.outcome, '')) = 'correct')
-                  / NULLIF(COUNT(p.id) FILTER (WHERE p.outcome IS NOT NULL), 0)
+                  / NULLIF(COUNT(p.id) FILTER (WHERE LOWER(COALESCE(p.outcome, '')) IN ('correct', 'incorrect')), 0)
                 )::NUMERIC, 1
               )::DOUBLE PRECISION AS accuracy_percent,
               EXISTS (
@@ -1268,7 +1268,7 @@ exports.getFollowing = async (req, res) => {
               ROUND(
                 (
                   100.0 * COUNT(p.id) FILTER (WHERE LOWER(COALESCE(p.outcome, '')) = 'correct')
-                  / NULLIF(COUNT(p.id) FILTER (WHERE p.outcome IS NOT NULL), 0)
+                  / NULLIF(COUNT(p.id) FILTER (WHERE LOWER(COALESCE(p.outcome, '')) IN ('correct', 'incorrect')), 0)
                 )::NUMERIC, 1
               )::DOUBLE PRECISION AS accuracy_percent,
               EXISTS (
@@ -488,13 +488,13 @@ router.post("/events/:eventId/sell", authenticateJWT, requirePhoneVerified, requ
     }
 
     try {
-        const { share_type, amount } = req.body;
+        const { share_type, amount, sell_all, payout } = req.body;
         const userId = req.user.id;
 
         const response = await fetch(`http://prediction-engine:3001/events/${eventId}/sell`, {
             method: 'POST',
             headers: predictionEngineHeaders,
-            body: JSON.stringify({ user_id: userId, share_type, amount })
+            body: JSON.stringify({ user_id: userId, share_type, amount, sell_all, payout })
         });
 
         const data = await response.json();
@@ -511,7 +511,7 @@ router.post("/events/:eventId/sell", authenticateJWT, requirePhoneVerified, requ
                         action: 'sell',
                         user_id: userId,
                         share_type,
-                        amount,
+                        amount: data.shares_sold ?? amount,
                         timestamp: new Date().toISOString()
                     });
                     console.log('📡 Market update broadcast (sell):', eventId, 'new_prob:', data.new_prob);
@@ -530,7 +530,7 @@ router.post("/events/:eventId/sell", authenticateJWT, requirePhoneVerified, requ
 
 router.post("/events/:eventId/update", authenticateJWT, requirePhoneVerified, requireScope('market:trade'), idempotent, async (req, res) => {
     const { eventId } = req.params;
-    const { stake, target_prob } = req.body;
+    const { stake, target_prob, max_cost, min_shares } = req.body;
     const userId = req.user.id;
     const eventIdNumber = Number(eventId);
     let client = null;
@@ -581,6 +581,9 @@ router.post("/events/:eventId/update", authenticateJWT, requirePhoneVerified, re
         }
 
         const updatePayload = { user_id: userId, stake, target_prob };
+        // Slippage bounds from the quote the UI showed; the engine answers 409 past them
+        if (max_cost != null) updatePayload.max_cost = max_cost;
+        if (min_shares != null) updatePayload.min_shares = min_shares;
         if (referralPayload) {
             updatePayload.referral_post_id = referralPayload.postId;
             updatePayload.referral_click_id = referralPayload.clickId;
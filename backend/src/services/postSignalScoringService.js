@@ -8,6 +8,7 @@
 
 const { getPool, executeWithTransaction } = require('../db');
 const config = require('./persuasiveAlphaConfig');
+const { bookFaucet } = require('./systemLedgerService');
 
 const LOG_CTX = '[PersuasionScorer]';
 const PREDICTION_ENGINE_BASE_URL = process.env.PREDICTION_ENGINE_BASE_URL || 'http://prediction-engine:3001';
@@ -211,6 +212,12 @@ const mintPayouts = async (client) => {
 
             // Update author RP balance
             await client.query(`UPDATE users SET rp_balance_ledger = rp_balance_ledger + $1 WHERE id = $2`, [rewardLedger, row.author_user_id]);
+            await bookFaucet(client, {
+                kind: 'signal_reward',
+                userIds: [row.author_user_id],
+                userDeltaLedger: rewardLedger,
+                eventId: row.event_id
+            });
 
             stats.payout_rows_created++;
             stats.minted_ledger_total += rewardLedger;
@@ -0,0 +1,28 @@
+// backend/src/services/systemLedgerService.js
+//
+// RP the backend credits or debits outside markets (question bonds and
+// rewards, validator stakes, weekly decay, signal rewards) is booked against
+// the faucet account in system_ledger, in the caller's transaction, so the
+// prediction engine's solvency audit still sees users' RP plus the system
+// accounts total 0.
+
+const FAUCET_ACCOUNT = 'faucet';
+
+/**
+ * Books the other side of adding `userDeltaLedger` (negative for a debit)
+ * to the RP of each of `userIds`.
+ */
+async function bookFaucet(client, { kind, userIds, userDeltaLedger, eventId = null }) {
+  const delta = BigInt(userDeltaLedger);
+  if (delta === 0n || userIds.length === 0) {
+    return;
+  }
+  await client.query(
+    `INSERT INTO system_ledger (account, kind, user_id, event_id, delta_ledger)
+     SELECT $1, $2, u.id, $3, -$4::bigint
+     FROM UNNEST($5::int[]) AS u(id)`,
+    [FAUCET_ACCOUNT, kind, eventId, delta.toString(), userIds]
+  );
+}
+
+module.exports = { bookFaucet };
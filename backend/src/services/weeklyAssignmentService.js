@@ -1,4 +1,5 @@
 const db = require('../db');
+const { bookFaucet } = require('./systemLedgerService');
 
 const LEDGER_SCALE = 1_000_000n;
 const WEEKLY_REQUIREMENT_DIVISOR = 100n; // 1%
@@ -406,6 +407,11 @@ class WeeklyAssignmentService {
           SET rp_balance_ledger = $1
           WHERE id = $2
         `, [newBalanceLedger.toString(), user.id]);
+        await bookFaucet(client, {
+          kind: 'weekly_decay',
+          userIds: [user.id],
+          userDeltaLedger: -decayLedger
+        });
 
         // Log the decay (idempotent)
         await client.query(`
@@ -185,6 +185,15 @@ describe('postSignalScoringService', () => {
         const authorBalanceUpdates = queryLog.filter(q => q.sql.includes('UPDATE users SET rp_balance_ledger = rp_balance_ledger'));
         expect(authorBalanceUpdates.length).toBe(3);
 
+        // Each credit is booked against the faucet so the engine's solvency audit balances
+        const faucetBookings = queryLog.filter(q => q.sql.includes('INSERT INTO system_ledger'));
+        expect(faucetBookings.length).toBe(3);
+        faucetBookings.forEach((booking, i) => {
+            expect(booking.params[0]).toBe('faucet');
+            expect(booking.params[1]).toBe('signal_reward');
+            expect(booking.params[3]).toBe(authorBalanceUpdates[i].params[0].toString());
+        });
+
         expect(stats.skipped_by_cap).toBe(0);
     });
 });
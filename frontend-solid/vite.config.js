@@ -46,6 +46,7 @@ export default defineConfig({
     alias: {
       '@': path.resolve(__dirname, './src'),
       '@openmls': path.resolve(__dirname, '../shared/openmls-pkg'),
+      '@intellacc-math': path.resolve(__dirname, '../shared/intellacc-math-pkg'),
       '@shared': path.resolve(__dirname, '../shared'),
       '@app-services': path.resolve(__dirname, './src/services'),
       '@app-vault-service': path.resolve(__dirname, './src/services/mls/vaultService.js'),
@@ -0,0 +1,20 @@
+// Shared setup; each target only uses some of these helpers.
+#![allow(dead_code)]
+
+use openmls_wasm::MlsClient;
+
+/// Client with an identity, so its key package is in storage and a Welcome
+/// addressed to it gets past the key package lookup.
+pub fn client_with_identity() -> MlsClient {
+    let mut client = MlsClient::new();
+    client.create_identity("fuzz").expect("create identity");
+    client
+}
+
+/// Client that owns a single-member group; returns the group id that
+/// decrypt/commit inputs are processed against.
+pub fn client_with_group() -> (MlsClient, Vec<u8>) {
+    let mut client = client_with_identity();
+    let group_id = client.create_group(b"fuzz-group").expect("create group");
+    (client, group_id)
+}
@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+mod common;
+
+// Any ciphertext must come back as Ok or Err, never a panic or OOM.
+fuzz_target!(|data: &[u8]| {
+    let (mut client, group_id) = common::client_with_group();
+    let _ = client.decrypt_message_native(&group_id, data);
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+mod common;
+
+fuzz_target!(|data: &[u8]| {
+    let (mut client, group_id) = common::client_with_group();
+    let _ = client.process_commit_native(&group_id, data);
+});
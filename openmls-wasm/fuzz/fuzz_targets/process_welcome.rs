@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+mod common;
+
+// The ratchet tree travels out of band from the Welcome, so fuzz both.
+fuzz_target!(|input: (&[u8], Option<&[u8]>)| {
+    let (welcome, ratchet_tree) = input;
+    let mut client = common::client_with_identity();
+    let _ = client.process_welcome_native(welcome, ratchet_tree);
+});
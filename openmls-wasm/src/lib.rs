@@ -62,6 +62,7 @@ const KEY_PACKAGE_LIFETIME_SECONDS: u64 = 60 * 60 * 24 * 28 * 3;
 const MESSAGE_PADDING_SIZE: usize = 32;
 const RESUMPTION_PSK_WINDOW: usize = 32;
 const DEFAULT_EXTERNAL_PSK_ID_LEN: usize = 16;
+const KEY_PACKAGE_CIPHERSUITE: Ciphersuite = Ciphersuite::MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519;
 
 #[wasm_bindgen]
 pub fn init_logging() {
@@ -70,19 +71,19 @@ pub fn init_logging() {
     wasm_log!("OpenMLS WASM initialized");
 }
 
-#[derive(serde::Serialize, Clone)]
-struct LifetimeInfo {
-    not_before: u64,
-    not_after: u64,
-    range_seconds: u64,
-    has_acceptable_range: bool,
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct LifetimeInfo {
+    pub not_before: u64,
+    pub not_after: u64,
+    pub range_seconds: u64,
+    pub has_acceptable_range: bool,
 }
 
 #[derive(serde::Serialize, Clone)]
-struct CommitIdentitySummary {
-    identity: String,
-    is_basic: bool,
-    lifetime: Option<LifetimeInfo>,
+pub struct CommitIdentitySummary {
+    pub identity: String,
+    pub is_basic: bool,
+    pub lifetime: Option<LifetimeInfo>,
 }
 
 /// Member info extracted from a staged welcome for inspection
@@ -121,19 +122,62 @@ struct PendingStagedWelcome {
 }
 
 #[derive(serde::Serialize)]
-struct CommitRemoveSummary {
-    leaf_index: u32,
-    identity: Option<CommitIdentitySummary>,
+pub struct CommitRemoveSummary {
+    pub leaf_index: u32,
+    pub identity: Option<CommitIdentitySummary>,
 }
 
 #[derive(serde::Serialize)]
-struct StagedCommitSummary {
-    adds: Vec<CommitIdentitySummary>,
-    removes: Vec<CommitRemoveSummary>,
-    updates: Vec<CommitIdentitySummary>,
-    self_removed: bool,
-    epoch: u64,
-    aad_hex: String,
+pub struct StagedCommitSummary {
+    pub adds: Vec<CommitIdentitySummary>,
+    pub removes: Vec<CommitRemoveSummary>,
+    pub updates: Vec<CommitIdentitySummary>,
+    pub self_removed: bool,
+    pub epoch: u64,
+    pub aad_hex: String,
+}
+
+/// What a commit we created produced, TLS-serialized the way each was
+/// handed to JS. The commit stays pending until `merge_pending_commit`.
+pub struct CommitOutput {
+    pub commit: Vec<u8>,
+    pub welcome: Option<Vec<u8>>,
+    pub group_info: Option<Vec<u8>>,
+}
+
+impl CommitOutput {
+    fn serialize(
+        commit: impl Serialize,
+        welcome: Option<impl Serialize>,
+        group_info: Option<impl Serialize>,
+    ) -> Result<Self, String> {
+        let commit = commit.tls_serialize_detached()
+            .map_err(|e| format!("Error serializing commit: {:?}", e))?;
+        let welcome = welcome
+            .map(|welcome| welcome.tls_serialize_detached())
+            .transpose()
+            .map_err(|e| format!("Error serializing welcome: {:?}", e))?;
+        let group_info = group_info
+            .map(|group_info| group_info.tls_serialize_detached())
+            .transpose()
+            .map_err(|e| format!("Error serializing group info: {:?}", e))?;
+        Ok(CommitOutput { commit, welcome, group_info })
+    }
+
+    /// `[commit, welcome or null, group_info?]`, as self_update and
+    /// remove_member return it to JS
+    fn into_commit_first_array(self) -> js_sys::Array {
+        let array = js_sys::Array::new();
+        array.push(&js_sys::Uint8Array::from(&self.commit[..]));
+        match self.welcome {
+            Some(welcome) => array.push(&js_sys::Uint8Array::from(&welcome[..])),
+            None => array.push(&JsValue::NULL),
+        };
+        if let Some(group_info) = self.group_info {
+            array.push(&js_sys::Uint8Array::from(&group_info[..]));
+        }
+        array
+    }
 }
 
 #[derive(serde::Serialize)]
@@ -181,13 +225,14 @@ struct ProposalSummary {
     sender_type: String,
 }
 
-/// Single key package with its metadata - used for batch generation
-#[derive(serde::Serialize)]
-struct KeyPackageInfo {
-    key_package_bytes: Vec<u8>,
-    hash: String,
-    lifetime: LifetimeInfo,
-    is_last_resort: bool,
+/// Single key package with its metadata - used for batch generation, and
+/// the upload format the delivery server validates before storing
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct KeyPackageInfo {
+    pub key_package_bytes: Vec<u8>,
+    pub hash: String,
+    pub lifetime: LifetimeInfo,
+    pub is_last_resort: bool,
 }
 
 /// Result from generating multiple key packages
@@ -196,6 +241,16 @@ struct GeneratedKeyPackages {
     key_packages: Vec<KeyPackageInfo>,
 }
 
+/// What an uploaded key package turned out to be once validated
+#[derive(serde::Serialize, Debug)]
+pub struct ValidatedKeyPackage {
+    pub hash: String,
+    pub identity: String,
+    pub ciphersuite: String,
+    pub lifetime: LifetimeInfo,
+    pub is_last_resort: bool,
+}
+
 fn lifetime_info_from(lifetime: &Lifetime) -> LifetimeInfo {
     let not_before = lifetime.not_before();
     let not_after = lifetime.not_after();
@@ -839,6 +894,17 @@ impl MlsClient {
         lifetime_info_value(key_package.life_time())
     }
 
+    /// Server-side check of an upload ({ key_package_bytes, hash, lifetime, is_last_resort })
+    /// before it is stored; needs no identity, so any client instance can run it
+    pub fn validate_key_package_upload(upload: JsValue, now_seconds: f64) -> Result<JsValue, JsValue> {
+        let upload: KeyPackageInfo = serde_wasm_bindgen::from_value(upload)
+            .map_err(|e| JsValue::from_str(&format!("Error reading key package upload: {:?}", e)))?;
+        let validated = Self::validate_key_package_upload_native(&upload, now_seconds as u64)
+            .map_err(|e| JsValue::from_str(&e))?;
+        serde_wasm_bindgen::to_value(&validated)
+            .map_err(|e| JsValue::from_str(&format!("Error serializing result: {:?}", e)))
+    }
+
     pub fn restore_identity(&mut self, credential_bytes: Vec<u8>, bundle_bytes: Vec<u8>, signature_key_bytes: Vec<u8>) -> Result<(), JsValue> {
         let mut slice = credential_bytes.as_slice();
         let credential = <Credential as Deserialize>::tls_deserialize(&mut slice)
@@ -1074,48 +1140,18 @@ impl MlsClient {
     // ... Group Management ...
 
     pub fn add_member(&mut self, group_id_bytes: &[u8], key_package_bytes: &[u8]) -> Result<js_sys::Array, JsValue> {
-        let signer = self.signature_keypair.as_ref()
-            .ok_or_else(|| JsValue::from_str("No signature keypair available"))?;
-            
-        let group = self.groups.get_mut(group_id_bytes)
-            .ok_or_else(|| JsValue::from_str("Group not found"))?;
-        
-        let provider = &self.provider;
-        
-        // Use KeyPackageIn to deserialize and convert to KeyPackage
-        let key_package_in = KeyPackageIn::tls_deserialize(&mut &key_package_bytes[..])
-             .map_err(|e| JsValue::from_str(&format!("Error deserializing key package: {:?}", e)))?;
+        let output = self.add_member_native(group_id_bytes, key_package_bytes)
+            .map_err(|e| JsValue::from_str(&e))?;
 
-        let key_package = key_package_in.validate(provider.crypto(), ProtocolVersion::Mls10)
-             .map_err(|e| JsValue::from_str(&format!("Error validating key package: {:?}", e)))?;
-
-        if !key_package.life_time().has_acceptable_range() {
-            return Err(JsValue::from_str("KeyPackage lifetime exceeds acceptable range"));
-        }
-
-        let (commit, welcome_msg, group_info) = group.add_members(
-            provider,
-            signer,
-            &[key_package],
-        ).map_err(|e| JsValue::from_str(&format!("Error adding member: {:?}", e)))?;
-
-        // Serialize as full MlsMessage (includes type tag)
-        let commit_bytes = commit.tls_serialize_detached()
-            .map_err(|e| JsValue::from_str(&format!("Error serializing commit: {:?}", e)))?;
-
-        // Serialize welcome as full MlsMessage (includes type tag for Welcome)
-        let welcome_bytes = welcome_msg.tls_serialize_detached()
-            .map_err(|e| JsValue::from_str(&format!("Error serializing welcome: {:?}", e)))?;
-
-        wasm_log!(&format!("[WASM] add_member: Welcome MlsMessage serialized to {} bytes", welcome_bytes.len()));
+        wasm_log!(&format!("[WASM] add_member: Welcome MlsMessage serialized to {} bytes", output.welcome.as_ref().map_or(0, Vec::len)));
 
         let array = js_sys::Array::new();
-        array.push(&js_sys::Uint8Array::from(&welcome_bytes[..]));
-        array.push(&js_sys::Uint8Array::from(&commit_bytes[..]));
-        if let Some(group_info) = group_info {
-            let group_info_bytes = group_info.tls_serialize_detached()
-                .map_err(|e| JsValue::from_str(&format!("Error serializing group info: {:?}", e)))?;
-            array.push(&js_sys::Uint8Array::from(&group_info_bytes[..]));
+        if let Some(welcome) = &output.welcome {
+            array.push(&js_sys::Uint8Array::from(&welcome[..]));
+        }
+        array.push(&js_sys::Uint8Array::from(&output.commit[..]));
+        if let Some(group_info) = &output.group_info {
+            array.push(&js_sys::Uint8Array::from(&group_info[..]));
         }
         Ok(array)
     }
@@ -1306,97 +1342,21 @@ impl MlsClient {
     /// Update own leaf node (key rotation for Post-Compromise Security)
     /// Returns [commit_bytes, optional_welcome_bytes, optional_group_info_bytes]
     pub fn self_update(&mut self, group_id_bytes: &[u8]) -> Result<js_sys::Array, JsValue> {
-        let signer = self.signature_keypair.as_ref()
-            .ok_or_else(|| JsValue::from_str("No signature keypair available"))?;
-
-        let group = self.groups.get_mut(group_id_bytes)
-            .ok_or_else(|| JsValue::from_str("Group not found"))?;
-
-        let provider = &self.provider;
-
-        // Use default LeafNodeParameters (only updates HPKE encryption key)
-        let leaf_node_params = LeafNodeParameters::default();
-
-        let commit_bundle = group.self_update(
-            provider,
-            signer,
-            leaf_node_params,
-        ).map_err(|e| JsValue::from_str(&format!("Error performing self update: {:?}", e)))?;
-
-        let (commit, welcome_option, group_info_option) = commit_bundle.into_contents();
-
-        // Serialize commit
-        let commit_bytes = commit.tls_serialize_detached()
-            .map_err(|e| JsValue::from_str(&format!("Error serializing commit: {:?}", e)))?;
-
-        let array = js_sys::Array::new();
-        array.push(&js_sys::Uint8Array::from(&commit_bytes[..]));
-
-        // Serialize welcome if present (may exist if there were pending Add proposals)
-        if let Some(welcome) = welcome_option {
-            let welcome_bytes = welcome.tls_serialize_detached()
-                .map_err(|e| JsValue::from_str(&format!("Error serializing welcome: {:?}", e)))?;
-            array.push(&js_sys::Uint8Array::from(&welcome_bytes[..]));
-        } else {
-            array.push(&JsValue::NULL);
-        }
-
-        // Serialize group info if present
-        if let Some(group_info) = group_info_option {
-            let group_info_bytes = group_info.tls_serialize_detached()
-                .map_err(|e| JsValue::from_str(&format!("Error serializing group info: {:?}", e)))?;
-            array.push(&js_sys::Uint8Array::from(&group_info_bytes[..]));
-        }
+        let output = self.self_update_native(group_id_bytes)
+            .map_err(|e| JsValue::from_str(&e))?;
 
         wasm_log!(&format!("[WASM] self_update: key rotation commit created for group, new epoch pending"));
-        Ok(array)
+        Ok(output.into_commit_first_array())
     }
 
     /// Remove a member from the group by their leaf index
     /// Returns [commit_bytes, optional_welcome_bytes, optional_group_info_bytes]
     pub fn remove_member(&mut self, group_id_bytes: &[u8], leaf_index: u32) -> Result<js_sys::Array, JsValue> {
-        let signer = self.signature_keypair.as_ref()
-            .ok_or_else(|| JsValue::from_str("No signature keypair available"))?;
-
-        let group = self.groups.get_mut(group_id_bytes)
-            .ok_or_else(|| JsValue::from_str("Group not found"))?;
-
-        let provider = &self.provider;
-
-        // Convert u32 to LeafNodeIndex
-        let leaf_node_index = LeafNodeIndex::new(leaf_index);
-
-        let (commit, welcome_option, group_info_option) = group.remove_members(
-            provider,
-            signer,
-            &[leaf_node_index],
-        ).map_err(|e| JsValue::from_str(&format!("Error removing member: {:?}", e)))?;
-
-        // Serialize commit
-        let commit_bytes = commit.tls_serialize_detached()
-            .map_err(|e| JsValue::from_str(&format!("Error serializing commit: {:?}", e)))?;
-
-        let array = js_sys::Array::new();
-        array.push(&js_sys::Uint8Array::from(&commit_bytes[..]));
-
-        // Serialize welcome if present (may exist if there were pending Add proposals)
-        if let Some(welcome) = welcome_option {
-            let welcome_bytes = welcome.tls_serialize_detached()
-                .map_err(|e| JsValue::from_str(&format!("Error serializing welcome: {:?}", e)))?;
-            array.push(&js_sys::Uint8Array::from(&welcome_bytes[..]));
-        } else {
-            array.push(&JsValue::NULL);
-        }
-
-        // Serialize group info if present
-        if let Some(group_info) = group_info_option {
-            let group_info_bytes = group_info.tls_serialize_detached()
-                .map_err(|e| JsValue::from_str(&format!("Error serializing group info: {:?}", e)))?;
-            array.push(&js_sys::Uint8Array::from(&group_info_bytes[..]));
-        }
+        let output = self.remove_member_native(group_id_bytes, leaf_index)
+            .map_err(|e| JsValue::from_str(&e))?;
 
         wasm_log!(&format!("[WASM] remove_member: removed leaf index {} from group", leaf_index));
-        Ok(array)
+        Ok(output.into_commit_first_array())
     }
 
     /// Leave the group voluntarily (creates a self-remove proposal)
@@ -1586,144 +1546,15 @@ impl MlsClient {
     }
 
     pub fn process_welcome(&mut self, welcome_bytes: &[u8], ratchet_tree_bytes: Option<Vec<u8>>) -> Result<Vec<u8>, JsValue> {
-        let provider = &self.provider;
-
-        // First deserialize as MlsMessageIn (the full MLS message wrapper)
-        let mls_message_in = MlsMessageIn::tls_deserialize(&mut &welcome_bytes[..])
-            .map_err(|e| JsValue::from_str(&format!("Error deserializing MLS message: {:?}", e)))?;
-
-        // Extract the Welcome from the MLS message body
-        let welcome = match mls_message_in.extract() {
-            MlsMessageBodyIn::Welcome(welcome) => welcome,
-            other => return Err(JsValue::from_str(&format!("Message is not a Welcome, got: {:?}", std::mem::discriminant(&other)))),
-        };
-
-        let ratchet_tree = if let Some(bytes) = ratchet_tree_bytes {
-            Some(RatchetTreeIn::tls_deserialize(&mut &bytes[..])
-                .map_err(|e| JsValue::from_str(&format!("Error deserializing ratchet tree: {:?}", e)))?)
-        } else {
-            None
-        };
-
-        let group_config = MlsGroupJoinConfig::builder()
-            .wire_format_policy(WireFormatPolicy::default())
-            .padding_size(MESSAGE_PADDING_SIZE)
-            .max_past_epochs(5)  // Allow decrypting messages from up to 5 previous epochs
-            .sender_ratchet_configuration(SenderRatchetConfiguration::new(
-                10,  // out_of_order_tolerance
-                2000 // maximum_forward_distance
-            ))
-            .use_ratchet_tree_extension(true)
-            .number_of_resumption_psks(RESUMPTION_PSK_WINDOW)
-            .build();
-
-        #[cfg(feature = "logging")]
-        {
-            wasm_log!(&format!("[WASM] Processing Welcome with secrets count: {}", welcome.secrets().len()));
-            for (i, secret) in welcome.secrets().iter().enumerate() {
-                wasm_log!(&format!("[WASM] Welcome secret #{} expects KeyPackage Hash: {:?}", i, secret.new_member()));
-            }
-        }
-
-        let staged_welcome = StagedWelcome::new_from_welcome(
-            provider,
-            &group_config,
-            welcome,
-            ratchet_tree,
-        ).map_err(|e| JsValue::from_str(&format!("Error creating staged welcome: {:?}", e)))?;
-
-        let group = staged_welcome.into_group(provider)
-            .map_err(|e| JsValue::from_str(&format!("Error creating group from welcome: {:?}", e)))?;
-
-        let group_id = group.group_id().as_slice().to_vec();
-        self.groups.insert(group_id.clone(), group);
-
-        Ok(group_id)
+        self.process_welcome_native(welcome_bytes, ratchet_tree_bytes.as_deref())
+            .map_err(|e| JsValue::from_str(&e))
     }
 
     pub fn process_commit(&mut self, group_id_bytes: &[u8], commit_bytes: &[u8]) -> Result<JsValue, JsValue> {
-        let group = self.groups.get_mut(group_id_bytes)
-            .ok_or_else(|| JsValue::from_str("Group not found"))?;
-
-        if self.staged_commits.contains_key(group_id_bytes) {
-            return Err(JsValue::from_str("Staged commit already pending for group"));
-        }
-
-        let provider = &self.provider;
-
-        let message_in = MlsMessageIn::tls_deserialize(&mut &commit_bytes[..])
-            .map_err(|e| JsValue::from_str(&format!("Error deserializing commit message: {:?}", e)))?;
-
-        let protocol_message = ProtocolMessage::try_from(message_in)
-            .map_err(|e| JsValue::from_str(&format!("Error converting message: {:?}", e)))?;
-
-        let processed_message = group.process_message(
-            provider,
-            protocol_message,
-        ).map_err(|e| JsValue::from_str(&format!("Error processing message: {:?}", e)))?;
-
-        let aad_hex = hex::encode(processed_message.aad());
-        let message_epoch = processed_message.epoch().as_u64();
-
-        match processed_message.into_content() {
-            ProcessedMessageContent::StagedCommitMessage(staged_commit) => {
-                let staged_commit = *staged_commit;
-                let mut member_identities = HashMap::new();
-                for member in group.members() {
-                    member_identities.insert(
-                        member.index.u32(),
-                        credential_identity_summary(&member.credential),
-                    );
-                }
-
-                let adds = staged_commit
-                    .add_proposals()
-                    .map(|proposal| {
-                        let key_package = proposal.add_proposal().key_package();
-                        let lifetime = Some(key_package.life_time());
-                        commit_identity_summary(key_package.leaf_node().credential(), lifetime)
-                    })
-                    .collect::<Vec<_>>();
-
-                let updates = staged_commit
-                    .update_proposals()
-                    .map(|proposal| {
-                        let leaf_node = proposal.update_proposal().leaf_node();
-                        let lifetime = match leaf_node.leaf_node_source() {
-                            LeafNodeSource::KeyPackage(lifetime) => Some(lifetime),
-                            _ => None,
-                        };
-                        commit_identity_summary(leaf_node.credential(), lifetime)
-                    })
-                    .collect::<Vec<_>>();
-
-                let removes = staged_commit
-                    .remove_proposals()
-                    .map(|proposal| {
-                        let leaf_index = proposal.remove_proposal().removed().u32();
-                        CommitRemoveSummary {
-                            leaf_index,
-                            identity: member_identities.get(&leaf_index).cloned(),
-                        }
-                    })
-                    .collect::<Vec<_>>();
-
-                let summary = StagedCommitSummary {
-                    adds,
-                    removes,
-                    updates,
-                    self_removed: staged_commit.self_removed(),
-                    epoch: message_epoch,
-                    aad_hex,
-                };
-
-                self.staged_commits.insert(group_id_bytes.to_vec(), staged_commit);
-
-                serde_wasm_bindgen::to_value(&summary)
-                    .map_err(|e| JsValue::from_str(&format!("Error serializing commit summary: {:?}", e)))
-            },
-            _ => Err(JsValue::from_str("Message was not a commit")),
-        }
+        let summary = self.process_commit_native(group_id_bytes, commit_bytes)
+            .map_err(|e| JsValue::from_str(&e))?;
+        serde_wasm_bindgen::to_value(&summary)
+            .map_err(|e| JsValue::from_str(&format!("Error serializing commit summary: {:?}", e)))
     }
 
     pub fn process_proposal(&mut self, group_id_bytes: &[u8], proposal_bytes: &[u8]) -> Result<JsValue, JsValue> {
@@ -1966,29 +1797,8 @@ impl MlsClient {
     }
 
     pub fn decrypt_message(&mut self, group_id_bytes: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, JsValue> {
-        let group = self.groups.get_mut(group_id_bytes)
-            .ok_or_else(|| JsValue::from_str("Group not found"))?;
-
-        let provider = &self.provider;
-
-        // Deserialize directly to MlsMessageIn
-        let message_in = MlsMessageIn::tls_deserialize(&mut &ciphertext[..])
-            .map_err(|e| JsValue::from_str(&format!("Error deserializing message: {:?}", e)))?;
-
-        let protocol_message = ProtocolMessage::try_from(message_in)
-            .map_err(|e| JsValue::from_str(&format!("Error converting message: {:?}", e)))?;
-
-        let processed_message = group.process_message(
-            provider,
-            protocol_message,
-        ).map_err(|e| JsValue::from_str(&format!("Error processing message: {:?}", e)))?;
-
-        match processed_message.into_content() {
-            ProcessedMessageContent::ApplicationMessage(app_message) => {
-                Ok(app_message.into_bytes())
-            },
-            _ => Err(JsValue::from_str("Message was not an application message")),
-        }
+        self.decrypt_message_native(group_id_bytes, ciphertext)
+            .map_err(|e| JsValue::from_str(&e))
     }
 
     pub fn decrypt_message_with_aad(&mut self, group_id_bytes: &[u8], ciphertext: &[u8]) -> Result<JsValue, JsValue> {
@@ -2368,6 +2178,293 @@ impl MlsClient {
     }
 }
 
+// Native cores of the entry points that parse attacker-controlled bytes.
+// They return plain String errors (JsValue can't be built off wasm32), so the
+// cargo-fuzz targets in fuzz/ and native servers can drive them; the
+// #[wasm_bindgen] methods above only wrap them.
+impl MlsClient {
+    /// Validates an uploaded key package at unix time `now`: exactly one
+    /// well-formed KeyPackage with a valid signature, our ciphersuite, an
+    /// acceptable lifetime covering `now`, and metadata that matches what
+    /// the package actually contains.
+    pub fn validate_key_package_upload_native(upload: &KeyPackageInfo, now: u64) -> Result<ValidatedKeyPackage, String> {
+        let crypto = RustCrypto::default();
+        let key_package = KeyPackageIn::tls_deserialize_exact(&upload.key_package_bytes)
+            .map_err(|e| format!("Error deserializing key package: {:?}", e))?
+            .validate(&crypto, ProtocolVersion::Mls10)
+            .map_err(|e| format!("Error validating key package: {:?}", e))?;
+
+        if key_package.ciphersuite() != KEY_PACKAGE_CIPHERSUITE {
+            return Err(format!("Unsupported ciphersuite: {:?}", key_package.ciphersuite()));
+        }
+
+        let lifetime = key_package.life_time();
+        if !lifetime.has_acceptable_range() {
+            return Err("KeyPackage lifetime exceeds acceptable range".to_string());
+        }
+        if now < lifetime.not_before() || now > lifetime.not_after() {
+            return Err(format!(
+                "KeyPackage is not valid at {} (valid {} to {})",
+                now,
+                lifetime.not_before(),
+                lifetime.not_after()
+            ));
+        }
+
+        let hash = key_package.hash_ref(&crypto)
+            .map_err(|e| format!("Error hashing key package: {:?}", e))?;
+        let hash_hex = hex::encode(hash.as_slice());
+        if !upload.hash.eq_ignore_ascii_case(&hash_hex) {
+            return Err("Claimed hash does not match the key package".to_string());
+        }
+        let lifetime = lifetime_info_from(lifetime);
+        if upload.lifetime.not_before != lifetime.not_before || upload.lifetime.not_after != lifetime.not_after {
+            return Err("Claimed lifetime does not match the key package".to_string());
+        }
+        if upload.is_last_resort != key_package.last_resort() {
+            return Err("Claimed last-resort flag does not match the key package".to_string());
+        }
+
+        Ok(ValidatedKeyPackage {
+            hash: hash_hex,
+            identity: credential_identity_summary(key_package.leaf_node().credential()).identity,
+            ciphersuite: format!("{:?}", key_package.ciphersuite()),
+            lifetime,
+            is_last_resort: upload.is_last_resort,
+        })
+    }
+
+    pub fn add_member_native(&mut self, group_id_bytes: &[u8], key_package_bytes: &[u8]) -> Result<CommitOutput, String> {
+        let signer = self.signature_keypair.as_ref()
+            .ok_or_else(|| "No signature keypair available".to_string())?;
+
+        let group = self.groups.get_mut(group_id_bytes)
+            .ok_or_else(|| "Group not found".to_string())?;
+
+        let provider = &self.provider;
+
+        // Use KeyPackageIn to deserialize and convert to KeyPackage
+        let key_package_in = KeyPackageIn::tls_deserialize(&mut &key_package_bytes[..])
+            .map_err(|e| format!("Error deserializing key package: {:?}", e))?;
+
+        let key_package = key_package_in.validate(provider.crypto(), ProtocolVersion::Mls10)
+            .map_err(|e| format!("Error validating key package: {:?}", e))?;
+
+        if !key_package.life_time().has_acceptable_range() {
+            return Err("KeyPackage lifetime exceeds acceptable range".to_string());
+        }
+
+        let (commit, welcome, group_info) = group.add_members(
+            provider,
+            signer,
+            &[key_package],
+        ).map_err(|e| format!("Error adding member: {:?}", e))?;
+
+        CommitOutput::serialize(commit, Some(welcome), group_info)
+    }
+
+    /// Rotates our own leaf's HPKE encryption key (post-compromise security)
+    pub fn self_update_native(&mut self, group_id_bytes: &[u8]) -> Result<CommitOutput, String> {
+        let signer = self.signature_keypair.as_ref()
+            .ok_or_else(|| "No signature keypair available".to_string())?;
+
+        let group = self.groups.get_mut(group_id_bytes)
+            .ok_or_else(|| "Group not found".to_string())?;
+
+        let commit_bundle = group.self_update(
+            &self.provider,
+            signer,
+            LeafNodeParameters::default(),
+        ).map_err(|e| format!("Error performing self update: {:?}", e))?;
+
+        // A welcome may exist if there were pending Add proposals
+        let (commit, welcome, group_info) = commit_bundle.into_contents();
+        CommitOutput::serialize(commit, welcome, group_info)
+    }
+
+    pub fn remove_member_native(&mut self, group_id_bytes: &[u8], leaf_index: u32) -> Result<CommitOutput, String> {
+        let signer = self.signature_keypair.as_ref()
+            .ok_or_else(|| "No signature keypair available".to_string())?;
+
+        let group = self.groups.get_mut(group_id_bytes)
+            .ok_or_else(|| "Group not found".to_string())?;
+
+        let (commit, welcome, group_info) = group.remove_members(
+            &self.provider,
+            signer,
+            &[LeafNodeIndex::new(leaf_index)],
+        ).map_err(|e| format!("Error removing member: {:?}", e))?;
+
+        CommitOutput::serialize(commit, welcome, group_info)
+    }
+
+    pub fn process_welcome_native(&mut self, welcome_bytes: &[u8], ratchet_tree_bytes: Option<&[u8]>) -> Result<Vec<u8>, String> {
+        let provider = &self.provider;
+
+        // First deserialize as MlsMessageIn (the full MLS message wrapper)
+        let mls_message_in = MlsMessageIn::tls_deserialize(&mut &welcome_bytes[..])
+            .map_err(|e| format!("Error deserializing MLS message: {:?}", e))?;
+
+        // Extract the Welcome from the MLS message body
+        let welcome = match mls_message_in.extract() {
+            MlsMessageBodyIn::Welcome(welcome) => welcome,
+            other => return Err(format!("Message is not a Welcome, got: {:?}", std::mem::discriminant(&other))),
+        };
+
+        let ratchet_tree = if let Some(bytes) = ratchet_tree_bytes {
+            Some(RatchetTreeIn::tls_deserialize(&mut &bytes[..])
+                .map_err(|e| format!("Error deserializing ratchet tree: {:?}", e))?)
+        } else {
+            None
+        };
+
+        let group_config = MlsGroupJoinConfig::builder()
+            .wire_format_policy(WireFormatPolicy::default())
+            .padding_size(MESSAGE_PADDING_SIZE)
+            .max_past_epochs(5)  // Allow decrypting messages from up to 5 previous epochs
+            .sender_ratchet_configuration(SenderRatchetConfiguration::new(
+                10,  // out_of_order_tolerance
+                2000 // maximum_forward_distance
+            ))
+            .use_ratchet_tree_extension(true)
+            .number_of_resumption_psks(RESUMPTION_PSK_WINDOW)
+            .build();
+
+        #[cfg(feature = "logging")]
+        {
+            wasm_log!(&format!("[WASM] Processing Welcome with secrets count: {}", welcome.secrets().len()));
+            for (i, secret) in welcome.secrets().iter().enumerate() {
+                wasm_log!(&format!("[WASM] Welcome secret #{} expects KeyPackage Hash: {:?}", i, secret.new_member()));
+            }
+        }
+
+        let staged_welcome = StagedWelcome::new_from_welcome(
+            provider,
+            &group_config,
+            welcome,
+            ratchet_tree,
+        ).map_err(|e| format!("Error creating staged welcome: {:?}", e))?;
+
+        let group = staged_welcome.into_group(provider)
+            .map_err(|e| format!("Error creating group from welcome: {:?}", e))?;
+
+        let group_id = group.group_id().as_slice().to_vec();
+        self.groups.insert(group_id.clone(), group);
+
+        Ok(group_id)
+    }
+
+    pub fn process_commit_native(&mut self, group_id_bytes: &[u8], commit_bytes: &[u8]) -> Result<StagedCommitSummary, String> {
+        let group = self.groups.get_mut(group_id_bytes)
+            .ok_or_else(|| "Group not found".to_string())?;
+
+        if self.staged_commits.contains_key(group_id_bytes) {
+            return Err("Staged commit already pending for group".to_string());
+        }
+
+        let provider = &self.provider;
+
+        let message_in = MlsMessageIn::tls_deserialize(&mut &commit_bytes[..])
+            .map_err(|e| format!("Error deserializing commit message: {:?}", e))?;
+
+        let protocol_message = ProtocolMessage::try_from(message_in)
+            .map_err(|e| format!("Error converting message: {:?}", e))?;
+
+        let processed_message = group.process_message(
+            provider,
+            protocol_message,
+        ).map_err(|e| format!("Error processing message: {:?}", e))?;
+
+        let aad_hex = hex::encode(processed_message.aad());
+        let message_epoch = processed_message.epoch().as_u64();
+
+        match processed_message.into_content() {
+            ProcessedMessageContent::StagedCommitMessage(staged_commit) => {
+                let staged_commit = *staged_commit;
+                let mut member_identities = HashMap::new();
+                for member in group.members() {
+                    member_identities.insert(
+                        member.index.u32(),
+                        credential_identity_summary(&member.credential),
+                    );
+                }
+
+                let adds = staged_commit
+                    .add_proposals()
+                    .map(|proposal| {
+                        let key_package = proposal.add_proposal().key_package();
+                        let lifetime = Some(key_package.life_time());
+                        commit_identity_summary(key_package.leaf_node().credential(), lifetime)
+                    })
+                    .collect::<Vec<_>>();
+
+                let updates = staged_commit
+                    .update_proposals()
+                    .map(|proposal| {
+                        let leaf_node = proposal.update_proposal().leaf_node();
+                        let lifetime = match leaf_node.leaf_node_source() {
+                            LeafNodeSource::KeyPackage(lifetime) => Some(lifetime),
+                            _ => None,
+                        };
+                        commit_identity_summary(leaf_node.credential(), lifetime)
+                    })
+                    .collect::<Vec<_>>();
+
+                let removes = staged_commit
+                    .remove_proposals()
+                    .map(|proposal| {
+                        let leaf_index = proposal.remove_proposal().removed().u32();
+                        CommitRemoveSummary {
+                            leaf_index,
+                            identity: member_identities.get(&leaf_index).cloned(),
+                        }
+                    })
+                    .collect::<Vec<_>>();
+
+                let summary = StagedCommitSummary {
+                    adds,
+                    removes,
+                    updates,
+                    self_removed: staged_commit.self_removed(),
+                    epoch: message_epoch,
+                    aad_hex,
+                };
+
+                self.staged_commits.insert(group_id_bytes.to_vec(), staged_commit);
+
+                Ok(summary)
+            },
+            _ => Err("Message was not a commit".to_string()),
+        }
+    }
+
+    pub fn decrypt_message_native(&mut self, group_id_bytes: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+        let group = self.groups.get_mut(group_id_bytes)
+            .ok_or_else(|| "Group not found".to_string())?;
+
+        let provider = &self.provider;
+
+        // Deserialize directly to MlsMessageIn
+        let message_in = MlsMessageIn::tls_deserialize(&mut &ciphertext[..])
+            .map_err(|e| format!("Error deserializing message: {:?}", e))?;
+
+        let protocol_message = ProtocolMessage::try_from(message_in)
+            .map_err(|e| format!("Error converting message: {:?}", e))?;
+
+        let processed_message = group.process_message(
+            provider,
+            protocol_message,
+        ).map_err(|e| format!("Error processing message: {:?}", e))?;
+
+        match processed_message.into_content() {
+            ProcessedMessageContent::ApplicationMessage(app_message) => {
+                Ok(app_message.into_bytes())
+            },
+            _ => Err("Message was not an application message".to_string()),
+        }
+    }
+}
+
 // --- High Performance Storage ---
 
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
@@ -3031,6 +3128,72 @@ mod tests {
         assert!(events.last().unwrap().value.is_none());
     }
 
+    fn key_package_upload(last_resort: bool) -> KeyPackageInfo {
+        let provider = GranularProvider::default();
+        let signature_keypair = SignatureKeyPair::new(KEY_PACKAGE_CIPHERSUITE.signature_algorithm())
+            .expect("signature keypair");
+        let credential_with_key = CredentialWithKey {
+            credential: Credential::new(CredentialType::Basic, b"test-user".to_vec()),
+            signature_key: signature_keypair.to_public_vec().into(),
+        };
+        let mut builder = KeyPackage::builder()
+            .key_package_lifetime(Lifetime::new(KEY_PACKAGE_LIFETIME_SECONDS));
+        if last_resort {
+            let capabilities = Capabilities::new(None, None, Some(&[ExtensionType::LastResort]), None, None);
+            builder = builder.leaf_node_capabilities(capabilities).mark_as_last_resort();
+        }
+        let key_package_bundle = builder
+            .build(KEY_PACKAGE_CIPHERSUITE, &provider, &signature_keypair, credential_with_key)
+            .expect("key package bundle");
+        let key_package = key_package_bundle.key_package();
+        KeyPackageInfo {
+            key_package_bytes: key_package.tls_serialize_detached().expect("serialize"),
+            hash: hex::encode(key_package.hash_ref(provider.crypto()).expect("hash").as_slice()),
+            lifetime: lifetime_info_from(key_package.life_time()),
+            is_last_resort: last_resort,
+        }
+    }
+
+    #[test]
+    fn key_package_upload_validates_signature_lifetime_and_claims() {
+        let upload = key_package_upload(false);
+        let now = upload.lifetime.not_before + 60;
+        let validated = MlsClient::validate_key_package_upload_native(&upload, now)
+            .expect("valid upload");
+        assert_eq!(validated.identity, "test-user");
+        assert_eq!(validated.hash, upload.hash);
+        assert!(!validated.is_last_resort);
+        let last_resort = key_package_upload(true);
+        assert!(MlsClient::validate_key_package_upload_native(&last_resort, now)
+            .expect("valid last-resort upload")
+            .is_last_resort);
+
+        let rejects = |upload: &KeyPackageInfo, now: u64, expected: &str| {
+            let err = MlsClient::validate_key_package_upload_native(upload, now).unwrap_err();
+            assert!(err.contains(expected), "{}", err);
+        };
+        rejects(&upload, upload.lifetime.not_after + 1, "not valid at");
+        rejects(&upload, upload.lifetime.not_before - 1, "not valid at");
+
+        let mut tampered = upload.clone();
+        *tampered.key_package_bytes.last_mut().unwrap() ^= 1;
+        rejects(&tampered, now, "Error validating key package");
+        let mut trailing = upload.clone();
+        trailing.key_package_bytes.push(0);
+        rejects(&trailing, now, "Error deserializing key package");
+        rejects(&KeyPackageInfo { key_package_bytes: vec![0; 16], ..upload.clone() }, now, "Error deserializing");
+
+        let mut wrong_hash = upload.clone();
+        wrong_hash.hash = last_resort.hash.clone();
+        rejects(&wrong_hash, now, "Claimed hash");
+        let mut wrong_lifetime = upload.clone();
+        wrong_lifetime.lifetime.not_after += 1;
+        rejects(&wrong_lifetime, now, "Claimed lifetime");
+        let mut wrong_flag = upload.clone();
+        wrong_flag.is_last_resort = true;
+        rejects(&wrong_flag, now, "last-resort");
+    }
+
     #[test]
     fn join_config_roundtrip_records_events() {
         let storage = GranularStorage::default();
@@ -0,0 +1,44 @@
+//! Stamps the binary with the commit and time it was built from, for
+//! GET /version. Docker builds have no .git, so they pass GIT_SHA instead;
+//! SOURCE_DATE_EPOCH pins the build time for reproducible builds.
+
+use std::env;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
+    let sha = env::var("GIT_SHA")
+        .ok()
+        .filter(|sha| !sha.trim().is_empty())
+        .or_else(|| {
+            Command::new("git")
+                .args(["rev-parse", "HEAD"])
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+        })
+        .map(|sha| sha.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    let built_at = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default()
+        });
+
+    println!("cargo:rustc-env=ENGINE_GIT_SHA={}", sha);
+    println!(
+        "cargo:rustc-env=ENGINE_GIT_SHORT_SHA={}",
+        sha.get(..7).unwrap_or(&sha)
+    );
+    println!("cargo:rustc-env=ENGINE_BUILT_AT={}", built_at);
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs/heads");
+}
@@ -0,0 +1,262 @@
+//! wasm-bindgen wrapper over `intellacc-math` for trade and score previews
+//! in the browser.
+//!
+//! The frontend renders stake-slider previews (shares, cost, price after)
+//! without a round trip to the engine. Each quote runs the same code path
+//! the engine executes the trade with — `Market::apply_trade` on the stake
+//! in ledger units for binary buys, `Market::apply_sell` for sells and
+//! `MultiMarket::buy_outcome` for N-outcome buys — so a preview matches the
+//! executed trade as long as the market state it was fed is current.
+//!
+//! The prediction form likewise shows what a forecast would score under
+//! either outcome as the user drags it, through the scoring rules the
+//! engine scores resolved predictions with (`intellacc_math::scoring`).
+//!
+//! Build with `scripts/build_intellacc_math_wasm.sh`, which runs `wasm-pack`
+//! and writes the package to `shared/intellacc-math-pkg` (imported by the
+//! frontend as `@intellacc-math`). Errors reach JavaScript as thrown
+//! strings, worded as the engine would word them.
+
+use intellacc_math::lmsr::{self, from_ledger_units, to_ledger_units, Market, Side};
+use intellacc_math::{kelly, multi, scoring};
+use wasm_bindgen::prelude::*;
+
+/// A binary buy: what a stake gets at the current market state.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BuyQuote {
+    pub shares: f64,
+    /// RP debited, after rounding to ledger units as the engine does.
+    pub cost: f64,
+    pub prob_before: f64,
+    pub prob_after: f64,
+}
+
+/// A binary sell: what selling shares pays at the current market state.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SellQuote {
+    /// RP credited, after rounding to ledger units.
+    pub payout: f64,
+    pub prob_before: f64,
+    pub prob_after: f64,
+}
+
+/// An N-outcome buy.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiBuyQuote {
+    pub shares: f64,
+    pub cost: f64,
+    pub probs_after: Vec<f64>,
+}
+
+/// What a binary forecast scores if the event resolves each way.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScorePreview {
+    pub log_if_yes: f64,
+    pub log_if_no: f64,
+    pub brier_if_yes: f64,
+    pub brier_if_no: f64,
+}
+
+fn market(q_yes: f64, q_no: f64, b: f64) -> Result<Market, String> {
+    if !b.is_finite() || b <= 0.0 {
+        return Err("liquidity parameter b must be positive and finite".to_string());
+    }
+    if !q_yes.is_finite() || !q_no.is_finite() {
+        return Err("market quantities must be finite".to_string());
+    }
+    Ok(Market { q_yes, q_no, b })
+}
+
+/// Probability of YES at the given market state.
+#[wasm_bindgen]
+pub fn prob_yes(q_yes: f64, q_no: f64, b: f64) -> Result<f64, String> {
+    Ok(market(q_yes, q_no, b)?.prob_yes())
+}
+
+/// LMSR cost function at the given market state.
+#[wasm_bindgen]
+pub fn cost(q_yes: f64, q_no: f64, b: f64) -> Result<f64, String> {
+    Ok(market(q_yes, q_no, b)?.cost())
+}
+
+/// Quotes buying `side` ("yes" or "no") with `stake` RP.
+#[wasm_bindgen]
+pub fn quote_buy(
+    side: &str,
+    q_yes: f64,
+    q_no: f64,
+    b: f64,
+    stake: f64,
+) -> Result<BuyQuote, String> {
+    let side = Side::from_str(side)?;
+    let mut market = market(q_yes, q_no, b)?;
+    let prob_before = market.prob_yes();
+    let (shares, cost_ledger) = market.apply_trade(side, to_ledger_units(stake)?)?;
+    Ok(BuyQuote {
+        shares,
+        cost: from_ledger_units(cost_ledger),
+        prob_before,
+        prob_after: market.prob_yes(),
+    })
+}
+
+/// Quotes selling `shares` of `side`.
+#[wasm_bindgen]
+pub fn quote_sell(
+    side: &str,
+    q_yes: f64,
+    q_no: f64,
+    b: f64,
+    shares: f64,
+) -> Result<SellQuote, String> {
+    let side = Side::from_str(side)?;
+    let mut market = market(q_yes, q_no, b)?;
+    let prob_before = market.prob_yes();
+    let payout_ledger = market.apply_sell(side, shares)?;
+    Ok(SellQuote {
+        payout: from_ledger_units(payout_ledger),
+        prob_before,
+        prob_after: market.prob_yes(),
+    })
+}
+
+/// Worst-case market-maker loss of a binary market with liquidity `b`.
+#[wasm_bindgen]
+pub fn max_subsidy_for_liquidity(b: f64) -> f64 {
+    lmsr::max_subsidy_for_liquidity(b)
+}
+
+/// Kelly stake for a trader believing `belief` in a market at `market_prob`;
+/// `fraction` is the engine's `kelly_fraction` (1.0 is full Kelly).
+#[wasm_bindgen]
+pub fn kelly_stake(belief: f64, market_prob: f64, balance: f64, fraction: f64) -> f64 {
+    kelly::kelly_stake(belief, market_prob, balance, fraction)
+}
+
+fn checked_probability(probability: f64) -> Result<f64, String> {
+    if !(0.0..=1.0).contains(&probability) {
+        return Err("probability must be between 0 and 1".to_string());
+    }
+    Ok(probability)
+}
+
+/// Log score of a binary forecast: ln of the probability given to what
+/// happened, floored as the engine floors it; 0 is perfect.
+#[wasm_bindgen]
+pub fn log_score(probability: f64, outcome: bool) -> Result<f64, String> {
+    Ok(scoring::log_score(
+        checked_probability(probability)?,
+        outcome,
+    ))
+}
+
+/// Brier score of a binary forecast; 0 is perfect.
+#[wasm_bindgen]
+pub fn brier_score(probability: f64, outcome: bool) -> Result<f64, String> {
+    Ok(scoring::brier_score(
+        checked_probability(probability)?,
+        outcome,
+    ))
+}
+
+/// Cross-entropy of `probability` against a possibly fractional `target`.
+#[wasm_bindgen]
+pub fn log_loss(target: f64, probability: f64) -> Result<f64, String> {
+    Ok(scoring::log_loss(
+        checked_probability(target)?,
+        checked_probability(probability)?,
+    ))
+}
+
+/// Scores of a forecast of `probability` under both outcomes.
+#[wasm_bindgen]
+pub fn preview_scores(probability: f64) -> Result<ScorePreview, String> {
+    let p = checked_probability(probability)?;
+    Ok(ScorePreview {
+        log_if_yes: scoring::log_score(p, true),
+        log_if_no: scoring::log_score(p, false),
+        brier_if_yes: scoring::brier_score(p, true),
+        brier_if_no: scoring::brier_score(p, false),
+    })
+}
+
+/// Outcome probabilities of an N-outcome market.
+#[wasm_bindgen]
+pub fn multi_probs(q: Vec<f64>, b: f64) -> Result<Vec<f64>, String> {
+    Ok(multi::MultiMarket::new(q, b)
+        .map_err(|e| e.to_string())?
+        .probs())
+}
+
+/// Quotes buying outcome `outcome` of an N-outcome market with `stake` RP.
+#[wasm_bindgen]
+pub fn quote_buy_outcome(
+    q: Vec<f64>,
+    b: f64,
+    outcome: usize,
+    stake: f64,
+) -> Result<MultiBuyQuote, String> {
+    let mut market = multi::MultiMarket::new(q, b).map_err(|e| e.to_string())?;
+    let (shares, cost) = market
+        .buy_outcome(outcome, stake)
+        .map_err(|e| e.to_string())?;
+    Ok(MultiBuyQuote {
+        shares,
+        cost,
+        probs_after: market.probs(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_match_the_engine_trade_path() {
+        let (q_yes, q_no, b) = (120.0, 40.0, 500.0);
+        let quote = quote_buy("YES", q_yes, q_no, b, 25.0).unwrap();
+        let mut engine = Market { q_yes, q_no, b };
+        let (shares, cost_ledger) = engine.buy_yes(to_ledger_units(25.0).unwrap()).unwrap();
+        assert_eq!(quote.shares, shares);
+        assert_eq!(quote.cost, from_ledger_units(cost_ledger));
+        assert_eq!(quote.prob_after, engine.prob_yes());
+        assert!(quote.prob_after > quote.prob_before);
+
+        let sell = quote_sell("yes", engine.q_yes, engine.q_no, b, shares).unwrap();
+        assert!((sell.payout - quote.cost).abs() < 1e-5);
+        assert!((sell.prob_after - quote.prob_before).abs() < 1e-12);
+
+        assert!(quote_buy("maybe", q_yes, q_no, b, 25.0).is_err());
+        assert!(quote_buy("no", q_yes, q_no, 0.0, 25.0).is_err());
+        assert!(prob_yes(0.0, 0.0, f64::NAN).is_err());
+    }
+
+    #[test]
+    fn multi_quotes_match_the_engine_trade_path() {
+        let q = vec![10.0, 0.0, -5.0];
+        let quote = quote_buy_outcome(q.clone(), 100.0, 2, 8.0).unwrap();
+        let mut engine = multi::MultiMarket::new(q.clone(), 100.0).unwrap();
+        let (shares, cost) = engine.buy_outcome(2, 8.0).unwrap();
+        assert_eq!((quote.shares, quote.cost), (shares, cost));
+        assert_eq!(quote.probs_after, engine.probs());
+        assert!(quote.probs_after[2] > multi_probs(q.clone(), 100.0).unwrap()[2]);
+        assert!(quote_buy_outcome(q, 100.0, 3, 8.0).is_err());
+    }
+
+    #[test]
+    fn score_previews_match_the_engine_scoring_rules() {
+        let preview = preview_scores(0.8).unwrap();
+        assert_eq!(preview.log_if_yes, scoring::log_score(0.8, true));
+        assert_eq!(preview.log_if_no, scoring::log_score(0.8, false));
+        assert_eq!(preview.brier_if_yes, scoring::brier_score(0.8, true));
+        assert_eq!(preview.brier_if_no, scoring::brier_score(0.8, false));
+        assert_eq!(log_loss(1.0, 0.8).unwrap(), -preview.log_if_yes);
+        assert_eq!(log_score(0.0, true).unwrap(), scoring::LOG_SCORE_FLOOR.ln());
+        assert!(brier_score(1.2, true).is_err());
+        assert!(preview_scores(f64::NAN).is_err());
+    }
+}
@@ -0,0 +1,36 @@
+//! The f64 functions that only std provides, from libm for `no_std` builds.
+
+pub(crate) trait Float {
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn floor(self) -> Self;
+    fn ceil(self) -> Self;
+    fn fract(self) -> Self;
+    fn powi(self, n: i32) -> Self;
+}
+
+impl Float for f64 {
+    fn exp(self) -> f64 {
+        libm::exp(self)
+    }
+
+    fn ln(self) -> f64 {
+        libm::log(self)
+    }
+
+    fn floor(self) -> f64 {
+        libm::floor(self)
+    }
+
+    fn ceil(self) -> f64 {
+        libm::ceil(self)
+    }
+
+    fn fract(self) -> f64 {
+        self - libm::trunc(self)
+    }
+
+    fn powi(self, n: i32) -> f64 {
+        libm::pow(self, n as f64)
+    }
+}
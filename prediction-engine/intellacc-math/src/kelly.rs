@@ -0,0 +1,28 @@
+//! Kelly-criterion stake sizing for binary markets.
+
+/// Stake suggested to a trader who believes `belief` in a market priced at
+/// `market_prob`: their edge over the price times `balance`, scaled by
+/// `fraction` (1.0 is full Kelly) and capped at `fraction` of the balance.
+pub fn kelly_stake(belief: f64, market_prob: f64, balance: f64, fraction: f64) -> f64 {
+    let edge = if belief > market_prob {
+        (belief - market_prob) / (1.0 - market_prob)
+    } else {
+        (market_prob - belief) / market_prob
+    };
+    (edge * balance * fraction).max(0.0).min(balance * fraction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stake_scales_with_edge_and_is_capped() {
+        // 0.7 against 0.5 is an edge of 0.4 on the YES side
+        assert!((kelly_stake(0.7, 0.5, 100.0, 0.25) - 10.0).abs() < 1e-12);
+        // ...and 0.3 the same edge on the NO side
+        assert!((kelly_stake(0.3, 0.5, 100.0, 0.25) - 10.0).abs() < 1e-12);
+        assert_eq!(kelly_stake(0.5, 0.5, 100.0, 0.25), 0.0);
+        assert_eq!(kelly_stake(1.0, 0.01, 100.0, 0.25), 25.0);
+    }
+}
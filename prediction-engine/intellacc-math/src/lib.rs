@@ -0,0 +1,22 @@
+//! Pure market and scoring math shared by the prediction engine and its
+//! clients: the binary and N-outcome LMSR cores, proper scoring rules and
+//! Kelly sizing.
+//!
+//! Nothing here does I/O, so the crate builds for `wasm32-unknown-unknown`
+//! (for trade previews in the browser) and, with `default-features = false,
+//! features = ["libm"]`, for `no_std` targets. The engine re-exports it under
+//! its old module paths (`lmsr_core`, `lmsr_multi_core`).
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(all(not(feature = "std"), not(feature = "libm")))]
+compile_error!("intellacc-math needs either the `std` or the `libm` feature");
+
+#[cfg(not(feature = "std"))]
+mod float;
+pub mod kelly;
+pub mod lmsr;
+pub mod multi;
+pub mod scoring;
@@ -0,0 +1,62 @@
+//! Proper scoring rules for binary forecasts.
+
+#[cfg(not(feature = "std"))]
+use crate::float::Float;
+
+/// Probabilities are floored here before taking logs, so an extreme
+/// forecast on the wrong side costs a bounded amount.
+pub const LOG_SCORE_FLOOR: f64 = 0.0001;
+
+/// (p - o)^2, lower is better.
+pub fn brier_score(probability: f64, outcome: bool) -> f64 {
+    let target = if outcome { 1.0 } else { 0.0 };
+    (probability.clamp(0.0, 1.0) - target).powi(2)
+}
+
+/// ln of the probability assigned to what happened; 0 is perfect.
+pub fn log_score(probability: f64, outcome: bool) -> f64 {
+    let p = probability.clamp(0.0, 1.0);
+    let assigned = if outcome { p } else { 1.0 - p };
+    assigned.max(LOG_SCORE_FLOOR).ln()
+}
+
+/// Cross-entropy of `probability` against a `target` that may be
+/// fractional, both clamped to [0, 1]; 0 is perfect. For a 0/1 target it
+/// is the negated log score.
+pub fn log_loss(target: f64, probability: f64) -> f64 {
+    let t = target.clamp(0.0, 1.0);
+    let p = probability.clamp(0.0, 1.0);
+    -(t * p.max(LOG_SCORE_FLOOR).ln() + (1.0 - t) * (1.0 - p).max(LOG_SCORE_FLOOR).ln())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn brier_score_matches_definition() {
+        assert_eq!(brier_score(1.0, true), 0.0);
+        assert_eq!(brier_score(0.0, true), 1.0);
+        assert!((brier_score(0.7, true) - 0.09).abs() < 1e-12);
+        assert!((brier_score(0.7, false) - 0.49).abs() < 1e-12);
+    }
+
+    #[test]
+    fn log_score_is_floored_and_symmetric() {
+        assert_eq!(log_score(1.0, true), 0.0);
+        assert!((log_score(0.25, false) - 0.75_f64.ln()).abs() < 1e-12);
+        assert!((log_score(0.25, false) - log_score(0.75, true)).abs() < 1e-12);
+        // A certain forecast on the wrong side is bounded by the floor.
+        assert!((log_score(0.0, true) - LOG_SCORE_FLOOR.ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn log_loss_negates_the_log_score_for_a_known_outcome() {
+        for p in [0.0, 0.2, 0.5, 0.9, 1.0] {
+            assert!((log_loss(1.0, p) + log_score(p, true)).abs() < 1e-12);
+            assert!((log_loss(0.0, p) + log_score(p, false)).abs() < 1e-12);
+        }
+        let half = log_loss(0.5, 0.8);
+        assert!((half - -(0.5 * 0.8_f64.ln() + 0.5 * 0.2_f64.ln())).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,476 @@
+//! Append-only log of admin actions.
+//!
+//! Every request that is not a GET leaves a row in `admin_audit`, except
+//! the routes [`admin_action`] opts out: users' own trades, forecasts, keys
+//! and devices, the messaging pipeline and read-only checks. A row records
+//! who asked, the request and a snapshot of its target before it ran; once
+//! it has, the status and an after snapshot complete it. `GET /admin/audit`
+//! lists the log.
+
+use anyhow::{anyhow, Result};
+use axum::http::Method;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{FromRow, PgPool};
+
+use crate::api_error::{coded, ErrorCode};
+
+pub const MAX_LIMIT: i64 = 500;
+
+/// What an admin action changes, and so what its snapshots hold.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    /// The event row with its embargo windows
+    Event(i32),
+    /// A category's liquidity recommendation
+    Category(String),
+    /// Sealed score checksums, of the `user_id` in the query if any
+    Scores,
+    /// Nothing to snapshot; the request alone is logged
+    Request,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub action: String,
+    pub actor: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+    pub params: Option<Value>,
+    pub event_id: Option<i32>,
+    /// None while pending: the action started but its outcome wasn't recorded
+    pub status: Option<i16>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A request about to run, with the target snapshot taken before it.
+pub struct Pending<'a> {
+    pub action: &'static str,
+    pub actor: Option<&'a str>,
+    pub method: &'a str,
+    pub path: &'a str,
+    pub query: Option<&'a str>,
+    pub params: Option<&'a Value>,
+    pub target: &'a Target,
+    pub before: Option<&'a Value>,
+}
+
+pub async fn ensure_admin_audit_table(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS admin_audit (
+            id BIGSERIAL PRIMARY KEY,
+            action VARCHAR(40) NOT NULL,
+            actor TEXT,
+            method VARCHAR(10) NOT NULL,
+            path TEXT NOT NULL,
+            query TEXT,
+            params JSONB,
+            event_id INTEGER,
+            status SMALLINT,
+            before JSONB,
+            after JSONB,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    // Entries are written pending, before the action runs
+    sqlx::query("ALTER TABLE admin_audit ALTER COLUMN status DROP NOT NULL")
+        .execute(pool)
+        .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_admin_audit_event
+         ON admin_audit (event_id, id DESC) WHERE event_id IS NOT NULL",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        r#"
+        CREATE OR REPLACE FUNCTION admin_audit_append_only() RETURNS trigger AS $$
+        BEGIN
+            -- A pending entry is completed once, with its status and after
+            -- snapshot; nothing else about it changes
+            IF TG_OP = 'UPDATE' AND OLD.status IS NULL AND NEW.status IS NOT NULL
+               AND (NEW.id, NEW.action, NEW.actor, NEW.method, NEW.path, NEW.query,
+                    NEW.params, NEW.event_id, NEW.before, NEW.created_at)
+                   IS NOT DISTINCT FROM
+                   (OLD.id, OLD.action, OLD.actor, OLD.method, OLD.path, OLD.query,
+                    OLD.params, OLD.event_id, OLD.before, OLD.created_at)
+            THEN
+                RETURN NEW;
+            END IF;
+            RAISE EXCEPTION 'admin_audit is append-only';
+        END;
+        $$ LANGUAGE plpgsql;
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE OR REPLACE TRIGGER admin_audit_append_only
+         BEFORE UPDATE OR DELETE ON admin_audit
+         FOR EACH ROW EXECUTE FUNCTION admin_audit_append_only()",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Logged for admin routes without a name of their own, so a new route is
+/// audited before anyone names it.
+pub const UNNAMED_ACTION: &str = "admin_request";
+
+/// The admin action a request performs, if it is one that gets logged.
+pub fn admin_action(method: &Method, path: &str) -> Option<(&'static str, Target)> {
+    if [Method::GET, Method::HEAD, Method::OPTIONS].contains(method) {
+        return None;
+    }
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    if !audited(&segments) {
+        return None;
+    }
+    if let ["events", id, route @ ..] = segments.as_slice() {
+        if let Ok(event_id) = id.parse::<i32>() {
+            return Some((event_action(method, route), Target::Event(event_id)));
+        }
+    }
+    let action = match (method.clone(), segments.as_slice()) {
+        (Method::PUT, ["liquidity", "recommendations", category]) => {
+            return Some((
+                "liquidity_auto_apply",
+                Target::Category(category.to_string()),
+            ))
+        }
+        (Method::POST, ["scores", "reseal"]) => return Some(("score_reseal", Target::Scores)),
+        (Method::POST, ["scores", "audit"]) => return Some(("score_audit", Target::Scores)),
+        (Method::POST, ["markets"]) => "market_create",
+        (Method::POST, ["markets", "close-sweep"]) => "close_sweep",
+        (Method::POST, ["event-clusters"]) => "cluster_create",
+        (Method::POST, ["competitions"]) => "competition_create",
+        (Method::POST, ["competitions", _, "markets"]) => "competition_market_add",
+        (Method::POST, ["archive", "run"]) => "archive_run",
+        (Method::POST, ["resolutions", "sync"]) => "resolution_sync",
+        (Method::POST, ["resolutions", "backfill-metaculus"]) => "resolution_backfill",
+        (Method::POST, ["imports", "sync-all"]) => "import_sync_all",
+        (Method::POST, ["imports", "sync", _]) => "import_sync",
+        (Method::POST, ["metaculus", "bulk-import", "pause"]) => "import_pause",
+        (Method::POST, ["admin", "cache", "warm"]) => "cache_warm",
+        (Method::POST, ["admin", "backtest"]) => "backtest",
+        (Method::POST, ["partitions", "market-updates", "maintain"]) => "partition_maintenance",
+        (Method::POST, ["consensus", "refresh"]) => "consensus_refresh",
+        (Method::POST, ["forecasts", "compact"]) => "forecast_compaction",
+        (Method::POST, ["liquidity", "recommendations", "refresh"]) => {
+            "liquidity_recommendations_refresh"
+        }
+        (Method::POST, ["faucet", "sweep"]) => "faucet_sweep",
+        (Method::POST, ["messages", "retention", "sweep"]) => "mailbox_retention_sweep",
+        (Method::POST, ["persuasion", "score-mature-episodes"]) => "persuasion_scoring",
+        _ => UNNAMED_ACTION,
+    };
+    Some((action, Target::Request))
+}
+
+fn event_action(method: &Method, route: &[&str]) -> &'static str {
+    match (method.clone(), route) {
+        (Method::POST, ["market-resolve"]) => "resolve",
+        (Method::POST, ["market-resolve", "preview"]) => "resolution_preview",
+        (Method::POST, ["market-resolve", "commit"]) => "resolution_commit",
+        (Method::POST, ["market-resolve", "cancel"]) => "resolution_cancel",
+        (Method::POST, ["dispute"]) => "dispute",
+        (Method::POST, ["embargoes"]) => "embargo_add",
+        (Method::DELETE, ["embargoes", _]) => "embargo_remove",
+        (Method::POST, ["liquidity"]) => "liquidity_migrate",
+        (Method::PUT, ["privacy"]) => "privacy_set",
+        (Method::PUT, ["metadata"]) => "metadata_update",
+        (Method::POST, ["archive"]) => "archive",
+        (Method::POST, ["archive", "restore"]) => "archive_restore",
+        (Method::POST, ["trades", "identified"]) => "identified_trades",
+        _ => UNNAMED_ACTION,
+    }
+}
+
+/// False for the non-GET routes that are not admin actions.
+fn audited(segments: &[&str]) -> bool {
+    match segments {
+        // Users' own keys, devices, limit orders, mailboxes and provisioning
+        ["users" | "user", ..] => false,
+        // Messaging and ingestion pipelines
+        ["mls" | "directory", ..] => false,
+        ["messages", "enqueue"] | ["push", "dispatch"] | ["comments", "ingest"] => false,
+        // Read-only invariant checks
+        ["lmsr", check] => !check.starts_with("verify-"),
+        ["competitions", _, "join"] => false,
+        ["events", _, route] => !matches!(
+            *route,
+            "update"
+                | "trade"
+                | "sell"
+                | "close"
+                | "update-outcome"
+                | "sell-outcome"
+                | "numeric-trade"
+                | "numeric-sell"
+                | "numeric-bucket-buy"
+                | "numeric-bucket-sell"
+                | "paper-prediction"
+                | "forecast"
+        ),
+        _ => true,
+    }
+}
+
+/// The current state of `target`; None when it does not exist.
+pub async fn snapshot(
+    pool: &PgPool,
+    target: &Target,
+    query: Option<&str>,
+) -> Result<Option<Value>> {
+    match target {
+        Target::Event(event_id) => Ok(sqlx::query_scalar(
+            "SELECT to_jsonb(e) || jsonb_build_object('embargoes', (
+                     SELECT COALESCE(jsonb_agg(to_jsonb(w) ORDER BY w.id), '[]'::jsonb)
+                     FROM event_embargoes w WHERE w.event_id = e.id))
+                 FROM events e WHERE e.id = $1",
+        )
+        .bind(event_id)
+        .fetch_optional(pool)
+        .await?),
+        Target::Category(category) => Ok(sqlx::query_scalar(
+            "SELECT to_jsonb(r) FROM liquidity_recommendations r WHERE r.category = $1",
+        )
+        .bind(category)
+        .fetch_optional(pool)
+        .await?),
+        Target::Scores => {
+            let Some(user_id) = query_param(query, "user_id").and_then(|id| id.parse::<i32>().ok())
+            else {
+                return Ok(None);
+            };
+            Ok(sqlx::query_scalar(
+                "SELECT to_jsonb(c) FROM user_score_checksums c WHERE c.user_id = $1",
+            )
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?)
+        }
+        Target::Request => Ok(None),
+    }
+}
+
+fn query_param<'a>(query: Option<&'a str>, name: &str) -> Option<&'a str> {
+    query?.split('&').find_map(|pair| {
+        pair.split_once('=')
+            .filter(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+    })
+}
+
+/// Logs an action before it runs, as pending; returns its entry id.
+pub async fn open(pool: &PgPool, pending: &Pending<'_>) -> Result<i64> {
+    let event_id = match pending.target {
+        Target::Event(event_id) => Some(*event_id),
+        _ => None,
+    };
+    Ok(sqlx::query_scalar(
+        "INSERT INTO admin_audit
+             (action, actor, method, path, query, params, event_id, before)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         RETURNING id",
+    )
+    .bind(pending.action)
+    .bind(pending.actor)
+    .bind(pending.method)
+    .bind(pending.path)
+    .bind(pending.query)
+    .bind(pending.params)
+    .bind(event_id)
+    .bind(pending.before)
+    .fetch_one(pool)
+    .await?)
+}
+
+/// Completes a pending entry with the action's status, taking the after
+/// snapshot now.
+pub async fn finish(
+    pool: &PgPool,
+    id: i64,
+    target: &Target,
+    query: Option<&str>,
+    status: u16,
+) -> Result<()> {
+    let after = snapshot(pool, target, query).await?;
+    let finished = sqlx::query(
+        "UPDATE admin_audit SET status = $2, after = $3 WHERE id = $1 AND status IS NULL",
+    )
+    .bind(id)
+    .bind(status as i16)
+    .bind(after)
+    .execute(pool)
+    .await?;
+    if finished.rows_affected() == 0 {
+        return Err(anyhow!("audit entry {} is not pending", id));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter<'a> {
+    pub action: Option<&'a str>,
+    pub actor: Option<&'a str>,
+    pub event_id: Option<i32>,
+    /// Only entries older than this id, to page back through the log
+    pub before_id: Option<i64>,
+}
+
+/// Logged actions matching `filter`, newest first.
+pub async fn list(pool: &PgPool, filter: &AuditFilter<'_>, limit: i64) -> Result<Vec<AuditEntry>> {
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("limit must be between 1 and {}", MAX_LIMIT),
+        ));
+    }
+    Ok(sqlx::query_as(
+        "SELECT id, action, actor, method, path, query, params, event_id, status,
+                before, after, created_at
+         FROM admin_audit
+         WHERE ($1::text IS NULL OR action = $1)
+           AND ($2::text IS NULL OR actor = $2)
+           AND ($3::integer IS NULL OR event_id = $3)
+           AND ($4::bigint IS NULL OR id < $4)
+         ORDER BY id DESC
+         LIMIT $5",
+    )
+    .bind(filter.action)
+    .bind(filter.actor)
+    .bind(filter.event_id)
+    .bind(filter.before_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// The actor named by a request: the header if set, else the body's or
+/// query's `actor`.
+pub fn request_actor<'a>(
+    header: Option<&'a str>,
+    params: Option<&'a Value>,
+    query: Option<&'a str>,
+) -> Option<&'a str> {
+    [
+        header,
+        params.and_then(|p| p.get("actor")).and_then(|v| v.as_str()),
+        query_param(query, "actor"),
+    ]
+    .into_iter()
+    .flatten()
+    .map(str::trim)
+    .find(|actor| !actor.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn admin_routes_are_logged_and_trading_is_not() {
+        assert_eq!(
+            admin_action(&Method::POST, "/events/3/market-resolve"),
+            Some(("resolve", Target::Event(3)))
+        );
+        assert_eq!(
+            admin_action(&Method::DELETE, "/events/3/embargoes/9"),
+            Some(("embargo_remove", Target::Event(3)))
+        );
+        assert_eq!(
+            admin_action(&Method::PUT, "/liquidity/recommendations/sports"),
+            Some((
+                "liquidity_auto_apply",
+                Target::Category("sports".to_string())
+            ))
+        );
+        assert_eq!(
+            admin_action(&Method::POST, "/scores/reseal"),
+            Some(("score_reseal", Target::Scores))
+        );
+        assert_eq!(admin_action(&Method::GET, "/events/3/embargoes"), None);
+        assert_eq!(admin_action(&Method::POST, "/events/3/trade"), None);
+        assert_eq!(admin_action(&Method::PUT, "/events/3/forecast"), None);
+        assert_eq!(
+            admin_action(&Method::POST, "/events/3/update-outcome"),
+            None
+        );
+        assert_eq!(admin_action(&Method::POST, "/users/4/limit-orders"), None);
+        assert_eq!(admin_action(&Method::POST, "/competitions/2/join"), None);
+        assert_eq!(
+            admin_action(&Method::POST, "/lmsr/verify-consistency"),
+            None
+        );
+    }
+
+    #[test]
+    fn admin_routes_without_a_name_are_still_logged() {
+        for (path, action) in [
+            ("/markets", "market_create"),
+            ("/event-clusters", "cluster_create"),
+            ("/competitions/2/markets", "competition_market_add"),
+            ("/archive/run", "archive_run"),
+            ("/resolutions/sync", "resolution_sync"),
+            ("/imports/sync-all", "import_sync_all"),
+            ("/admin/cache/warm", "cache_warm"),
+            (
+                "/partitions/market-updates/maintain",
+                "partition_maintenance",
+            ),
+            ("/consensus/refresh", "consensus_refresh"),
+            ("/forecasts/compact", "forecast_compaction"),
+            (
+                "/liquidity/recommendations/refresh",
+                "liquidity_recommendations_refresh",
+            ),
+            ("/some/new-admin-route", UNNAMED_ACTION),
+            ("/events/x/dispute", UNNAMED_ACTION),
+        ] {
+            assert_eq!(
+                admin_action(&Method::POST, path),
+                Some((action, Target::Request)),
+                "{}",
+                path
+            );
+        }
+        assert_eq!(
+            admin_action(&Method::POST, "/scores/audit"),
+            Some(("score_audit", Target::Scores))
+        );
+        assert_eq!(
+            admin_action(&Method::DELETE, "/events/3/unnamed"),
+            Some((UNNAMED_ACTION, Target::Event(3)))
+        );
+    }
+
+    #[test]
+    fn the_header_actor_wins_over_the_body() {
+        let body = json!({ "actor": "body-admin" });
+        assert_eq!(
+            request_actor(Some("mod-7"), Some(&body), None),
+            Some("mod-7")
+        );
+        assert_eq!(
+            request_actor(Some("  "), Some(&body), None),
+            Some("body-admin")
+        );
+        assert_eq!(
+            request_actor(None, None, Some("user_id=4&actor=ops")),
+            Some("ops")
+        );
+        assert_eq!(request_actor(Some("  "), None, None), None);
+    }
+}
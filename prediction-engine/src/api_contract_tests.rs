@@ -0,0 +1,722 @@
+//! API contract tests for the HTTP surface the Node backend consumes
+//!
+//! Drives the real router (auth guard included) in-process against a seeded
+//! database and reduces every JSON response to its shape: object keys with
+//! the type of each value, arrays by their first element. Shapes are compared
+//! with golden files under `tests/golden/api/`, so renaming a field, dropping
+//! one or changing its type fails here instead of silently breaking the
+//! backend. Values themselves are not compared.
+//!
+//! Run with `UPDATE_GOLDEN=1` to (re)write the golden files after an
+//! intentional contract change, and commit them with the change. Without it
+//! a missing golden file fails the test like a changed shape does, so a
+//! checkout without its goldens can't pass by recording them.
+//!
+//! Not covered: /ws, the Metaculus and provider import/sync endpoints and
+//! resolution sync (they call external APIs), persuasion scoring (needs the
+//! backend's social tables) and the multi-outcome/numeric trade endpoints.
+
+use crate::integration_tests::{
+    cleanup_test_database, create_test_event, create_test_users, setup_test_database, test_config,
+};
+use crate::market_cache::{self, MarketStateCache};
+use crate::{
+    admin_audit, api_keys, arbitrage, build_router, comment_buzz, consensus, dead_letters,
+    event_metadata, event_search, group_directory, limit_orders, liquidity_migration,
+    liquidity_recommendations, mailbox, market_accuracy, market_heat, mls_delivery, notifications,
+    price_history, push, rank_history, score_integrity, sparklines, system_accounts, AppState,
+};
+use anyhow::{anyhow, Result};
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use moka::future::Cache;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::path::PathBuf;
+use tokio::sync::broadcast;
+use tower::ServiceExt;
+
+pub(crate) const TEST_TOKEN: &str = "contract-test-token";
+
+fn golden_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden/api")
+}
+
+fn update_golden() -> bool {
+    std::env::var("UPDATE_GOLDEN").is_ok_and(|v| v == "1" || v == "true")
+}
+
+/// Type skeleton of a JSON value. Arrays are described by their first
+/// element, so an empty list and a populated one differ; seed data makes
+/// sure the lists we snapshot are populated.
+fn shape(value: &Value) -> Value {
+    match value {
+        Value::Null => json!("null"),
+        Value::Bool(_) => json!("boolean"),
+        Value::Number(_) => json!("number"),
+        Value::String(_) => json!("string"),
+        Value::Array(items) => match items.first() {
+            Some(first) => json!([shape(first)]),
+            None => json!([]),
+        },
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), shape(value)))
+                .collect(),
+        ),
+    }
+}
+
+struct ContractRecorder {
+    mismatches: Vec<String>,
+}
+
+impl ContractRecorder {
+    fn check(&mut self, name: &str, status: StatusCode, body: &Value) -> Result<()> {
+        let actual = json!({ "status": status.as_u16(), "shape": shape(body) });
+        let path = golden_dir().join(format!("{}.json", name));
+
+        if update_golden() {
+            std::fs::create_dir_all(golden_dir())?;
+            std::fs::write(&path, serde_json::to_string_pretty(&actual)? + "\n")?;
+            println!("📝 Wrote golden shape for {}", name);
+            return Ok(());
+        }
+        if !path.exists() {
+            self.mismatches.push(format!(
+                "{}:\n  no golden file at {}\n  actual   {}",
+                name,
+                path.display(),
+                actual
+            ));
+            return Ok(());
+        }
+
+        let expected: Value = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+        if expected != actual {
+            self.mismatches.push(format!(
+                "{}:\n  expected {}\n  actual   {}",
+                name, expected, actual
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) async fn call(
+    app: &Router,
+    method: &str,
+    uri: &str,
+    body: Option<Value>,
+    authorized: bool,
+) -> Result<(StatusCode, Value)> {
+    let mut builder = Request::builder().method(method).uri(uri);
+    if authorized {
+        builder = builder.header("x-engine-token", TEST_TOKEN);
+    }
+    send(app, builder, body).await
+}
+
+/// Like `call`, but authenticated with a bot's API key.
+async fn call_with_key(
+    app: &Router,
+    method: &str,
+    uri: &str,
+    body: Option<Value>,
+    api_key: &str,
+) -> Result<(StatusCode, Value)> {
+    let builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("x-api-key", api_key);
+    send(app, builder, body).await
+}
+
+pub(crate) async fn send(
+    app: &Router,
+    builder: axum::http::request::Builder,
+    body: Option<Value>,
+) -> Result<(StatusCode, Value)> {
+    let (method, uri) = (
+        builder.method_ref().cloned().unwrap_or_default(),
+        builder.uri_ref().cloned().unwrap_or_default(),
+    );
+    let request = match body {
+        Some(body) => builder
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))?,
+        None => builder.body(Body::empty())?,
+    };
+
+    let response = app.clone().oneshot(request).await?;
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let body = if bytes.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_slice(&bytes)
+            .map_err(|e| anyhow!("{} {} returned non-JSON body: {}", method, uri, e))?
+    };
+    Ok((status, body))
+}
+
+/// The integration schema is a trimmed copy of production; /events reads a
+/// few columns it leaves out and decodes closing_date as a naive timestamp.
+async fn align_events_schema(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        "ALTER TABLE events
+             ALTER COLUMN closing_date TYPE TIMESTAMP,
+             ADD COLUMN IF NOT EXISTS topic_id INTEGER,
+             ADD COLUMN IF NOT EXISTS details TEXT",
+    )
+    .execute(pool)
+    .await?;
+    event_search::ensure_search_schema(pool).await?;
+    consensus::ensure_consensus_schema(pool).await?;
+    market_accuracy::ensure_market_accuracy_table(pool).await?;
+    liquidity_recommendations::ensure_recommendations_table(pool).await?;
+    liquidity_migration::ensure_migrations_table(pool).await?;
+    notifications::ensure_preferences_table(pool).await?;
+    score_integrity::ensure_checksums_table(pool).await?;
+    event_metadata::ensure_metadata_schema(pool).await?;
+    api_keys::ensure_api_key_tables(pool).await?;
+    market_cache::ensure_notify_triggers(pool).await?;
+    comment_buzz::ensure_comment_stats_table(pool).await?;
+    mls_delivery::ensure_delivery_tables(pool).await?;
+    mailbox::ensure_mailbox_table(pool).await?;
+    push::ensure_push_tables(pool).await?;
+    group_directory::ensure_directory_tables(pool).await?;
+    arbitrage::ensure_arbitrage_table(pool).await?;
+    price_history::ensure_price_history_table(pool).await?;
+    admin_audit::ensure_admin_audit_table(pool).await?;
+    rank_history::ensure_rank_history_tables(pool).await?;
+    limit_orders::ensure_limit_orders_table(pool).await?;
+    dead_letters::ensure_dead_letter_table(pool).await?;
+    Ok(())
+}
+
+pub(crate) fn test_app(pool: PgPool) -> Router {
+    let (tx, _rx) = broadcast::channel::<String>(dead_letters::BROADCAST_CAPACITY);
+    build_router(AppState {
+        db: pool.clone(),
+        analytics_db: pool,
+        tx,
+        in_flight: dead_letters::InFlight::default(),
+        cache: Cache::builder().max_capacity(1000).build(),
+        market_cache: MarketStateCache::new(30),
+        sparkline_cache: sparklines::SparklineCache::default(),
+        config: test_config(),
+        auth_token: Some(TEST_TOKEN.to_string()),
+        api_key_limiter: api_keys::RateLimiter::default(),
+    })
+}
+
+#[tokio::test]
+async fn api_responses_match_golden_shapes() -> Result<()> {
+    let test_db = setup_test_database().await?;
+    let pool = test_db.pool.clone();
+    align_events_schema(&pool).await?;
+
+    let users = create_test_users(&pool, 2).await?;
+    let (alice, bob) = (users[0].id, users[1].id);
+    system_accounts::ensure_system_accounts(&pool).await?;
+    let open_event = create_test_event(&pool, "Contract Open Event").await?;
+    let resolved_event = create_test_event(&pool, "Contract Resolved Event").await?;
+
+    let app = test_app(pool.clone());
+    let mut recorder = ContractRecorder {
+        mismatches: Vec::new(),
+    };
+
+    let (status, body) = call(&app, "GET", "/health", None, false).await?;
+    recorder.check("health", status, &body)?;
+
+    let uri = format!("/events/{}/market", open_event);
+    let (status, body) = call(&app, "GET", &uri, None, false).await?;
+    recorder.check("unauthorized", status, &body)?;
+
+    // Trading on the open event
+    let uri = format!("/events/{}/quote?target_prob=0.7&stake=25", open_event);
+    let (status, body) = call(&app, "GET", &uri, None, true).await?;
+    recorder.check("trade_quote", status, &body)?;
+    let uri = format!("/events/{}/update", open_event);
+    let trade = json!({ "user_id": alice, "target_prob": 0.7, "stake": 25.0 });
+    let (status, buy) = call(&app, "POST", &uri, Some(trade), true).await?;
+    recorder.check("market_update", status, &buy)?;
+    let uri = format!("/events/{}/trade", open_event);
+    let trade = json!({ "user_id": bob, "target_prob": 0.6, "stake": 10.0 });
+    let (status, body) = call(&app, "POST", &uri, Some(trade), true).await?;
+    recorder.check("market_trade", status, &body)?;
+    let trade = json!({ "user_id": bob, "target_prob": 0.6, "stake": 10.0, "min_shares": 1e9 });
+    let (status, body) = call(&app, "POST", &uri, Some(trade), true).await?;
+    recorder.check("market_trade_slippage", status, &body)?;
+
+    let share_type = buy["share_type"].as_str().unwrap_or("yes").to_string();
+    let amount = buy["shares_acquired"].as_f64().unwrap_or(1.0) / 2.0;
+    let uri = format!(
+        "/events/{}/sell-quote?user_id={}&side={}&amount={}",
+        open_event, alice, share_type, amount
+    );
+    let (status, body) = call(&app, "GET", &uri, None, true).await?;
+    recorder.check("sell_quote", status, &body)?;
+
+    let uri = format!("/events/{}/sell", open_event);
+    let sell = json!({ "user_id": alice, "share_type": share_type, "amount": amount });
+    let (status, body) = call(&app, "POST", &uri, Some(sell), true).await?;
+    recorder.check("market_sell", status, &body)?;
+    let sell = json!({ "user_id": alice, "share_type": share_type, "payout": 1.0 });
+    let (status, body) = call(&app, "POST", &uri, Some(sell), true).await?;
+    recorder.check("market_sell_payout", status, &body)?;
+
+    let (status, body) = call(&app, "GET", "/events", None, false).await?;
+    recorder.check("events", status, &body)?;
+
+    let uri = format!("/events/{}/market", open_event);
+    let (status, body) = call(&app, "GET", &uri, None, true).await?;
+    recorder.check("market_state", status, &body)?;
+
+    let uri = format!("/events/{}/trades", open_event);
+    let (status, body) = call(&app, "GET", &uri, None, true).await?;
+    recorder.check("event_trades", status, &body)?;
+
+    let uri = format!("/events/{}/trades/identified", open_event);
+    let view = json!({ "actor": "admin", "reason": "contract check" });
+    let (status, body) = call(&app, "POST", &uri, Some(view), true).await?;
+    recorder.check("event_trades_identified", status, &body)?;
+
+    let uri = format!("/events/{}/privacy/audit", open_event);
+    let (status, body) = call(&app, "GET", &uri, None, true).await?;
+    recorder.check("trade_identity_audit", status, &body)?;
+
+    // A future window leaves the event tradable for the rest of the run
+    let uri = format!("/events/{}/embargoes", open_event);
+    let window = json!({
+        "starts_at": chrono::Utc::now() + chrono::Duration::days(1),
+        "ends_at": chrono::Utc::now() + chrono::Duration::days(2),
+        "reason": "contract check",
+        "actor": "admin",
+    });
+    let (status, body) = call(&app, "POST", &uri, Some(window), true).await?;
+    recorder.check("event_embargo_add", status, &body)?;
+    let (status, body) = call(&app, "GET", &uri, None, true).await?;
+    recorder.check("event_embargoes", status, &body)?;
+
+    let uri = format!("/events/{}/liquidity", open_event);
+    let migration = json!({ "liquidity_b": 2500.0, "actor": "admin", "reason": "contract check" });
+    let (status, body) = call(&app, "POST", &uri, Some(migration), true).await?;
+    recorder.check("liquidity_migration", status, &body)?;
+
+    let uri = format!("/events/{}/shares?user_id={}", open_event, alice);
+    let (status, body) = call(&app, "GET", &uri, None, true).await?;
+    recorder.check("user_shares", status, &body)?;
+
+    let uri = format!("/events/{}/kelly?belief=0.6&user_id={}", open_event, bob);
+    let (status, body) = call(&app, "GET", &uri, None, true).await?;
+    recorder.check("kelly", status, &body)?;
+
+    let uri = format!("/events/{}/kelly?user_id={}", open_event, bob);
+    let (status, body) = call(&app, "GET", &uri, None, true).await?;
+    recorder.check("kelly_missing_belief", status, &body)?;
+
+    // Market creation
+    let market = json!({
+        "title": "Contract test market",
+        "closing_date": (chrono::Utc::now() + chrono::Duration::days(30)).to_rfc3339(),
+        "category": "science",
+        "max_subsidy": 250.0
+    });
+    let (status, body) = call(&app, "POST", "/markets", Some(market), true).await?;
+    recorder.check("create_market", status, &body)?;
+
+    let market = json!({ "title": "No liquidity", "closing_date": "2030-01-01T00:00:00Z" });
+    let (status, body) = call(&app, "POST", "/markets", Some(market), true).await?;
+    recorder.check("create_market_missing_liquidity", status, &body)?;
+
+    // Resolution and the accuracy side of the API
+    let uri = format!("/events/{}/update", resolved_event);
+    let trade = json!({ "user_id": bob, "target_prob": 0.35, "stake": 10.0 });
+    call(&app, "POST", &uri, Some(trade), true).await?;
+
+    let uri = format!("/events/{}/market-resolve", resolved_event);
+    let (status, body) = call(&app, "POST", &uri, Some(json!({ "outcome": true })), true).await?;
+    recorder.check("market_resolve", status, &body)?;
+
+    let uri = format!("/events/{}/market-resolve", open_event);
+    let body = json!({ "outcome": "maybe" });
+    let (status, body) = call(&app, "POST", &uri, Some(body), true).await?;
+    recorder.check("market_resolve_invalid_outcome", status, &body)?;
+
+    let uri = format!("/events/{}/paper-prediction", resolved_event);
+    let paper = json!({ "user_id": alice, "probability": 0.8 });
+    let (status, body) = call(&app, "POST", &uri, Some(paper), true).await?;
+    recorder.check("paper_prediction", status, &body)?;
+
+    let uri = format!("/events/{}/paper-prediction", open_event);
+    let paper = json!({ "user_id": alice, "probability": 0.8 });
+    let (status, body) = call(&app, "POST", &uri, Some(paper), true).await?;
+    recorder.check("paper_prediction_unresolved", status, &body)?;
+
+    let uri = format!("/events/{}/paper-prediction", resolved_event);
+    let paper = json!({ "user_id": 999_999, "probability": 0.8 });
+    let (status, body) = call(&app, "POST", &uri, Some(paper), true).await?;
+    recorder.check("paper_prediction_unknown_user", status, &body)?;
+
+    let uri = format!("/events/{}/forecast", resolved_event);
+    let forecast = json!({ "user_id": alice, "probability": 0.6 });
+    let (status, body) = call(&app, "POST", &uri, Some(forecast), true).await?;
+    recorder.check("forecast_resolved_event", status, &body)?;
+
+    let uri = format!("/events/{}/forecast", open_event);
+    let forecast = json!({ "user_id": bob, "probability": 0.6 });
+    let (status, body) = call(&app, "PUT", &uri, Some(forecast), true).await?;
+    recorder.check("forecast_update_missing", status, &body)?;
+
+    let uri = format!("/user/{}/events/{}/forecast-history", bob, open_event);
+    let (status, body) = call(&app, "GET", &uri, None, true).await?;
+    recorder.check("forecast_history_missing", status, &body)?;
+
+    let uri = format!("/users/{}/paper-predictions", alice);
+    let (status, body) = call(&app, "GET", &uri, None, true).await?;
+    recorder.check("user_paper_predictions", status, &body)?;
+
+    let uri = format!("/events/{}/dispute", resolved_event);
+    let dispute = json!({ "actor": "admin", "outcome": false });
+    let (status, body) = call(&app, "POST", &uri, Some(dispute), true).await?;
+    recorder.check("dispute_missing_reason", status, &body)?;
+
+    // Invariant verification
+    let checks = [
+        (
+            "verify_balance_invariant",
+            "/lmsr/verify-balance-invariant",
+            json!({ "user_id": alice }),
+        ),
+        (
+            "verify_staked_invariant",
+            "/lmsr/verify-staked-invariant",
+            json!({ "user_id": alice }),
+        ),
+        (
+            "verify_post_resolution",
+            "/lmsr/verify-post-resolution",
+            json!({ "event_id": resolved_event }),
+        ),
+        (
+            "verify_consistency",
+            "/lmsr/verify-consistency",
+            json!({ "event_id": open_event }),
+        ),
+    ];
+    for (name, uri, payload) in checks {
+        let (status, body) = call(&app, "POST", uri, Some(payload), true).await?;
+        recorder.check(name, status, &body)?;
+    }
+
+    let (status, body) = call(&app, "GET", "/lmsr/solvency", None, true).await?;
+    recorder.check("solvency", status, &body)?;
+
+    let (status, body) = call(&app, "POST", "/markets/close-sweep", None, true).await?;
+    recorder.check("close_sweep", status, &body)?;
+
+    let uri = format!(
+        "/markets/sparklines?event_ids={},999999&points=6",
+        open_event
+    );
+    let (status, body) = call(&app, "GET", &uri, None, true).await?;
+    recorder.check("market_sparklines", status, &body)?;
+
+    // Heat is refreshed on a schedule; score the trades above now
+    market_heat::refresh(&pool).await?;
+    let (status, body) = call(&app, "GET", "/markets/trending?limit=5", None, true).await?;
+    recorder.check("trending_markets", status, &body)?;
+
+    // A window in the future, so the shape doesn't depend on which
+    // broadcasts above have been stored yet
+    let since = (chrono::Utc::now() + chrono::Duration::days(1))
+        .to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    let uri = format!("/events/stream/backfill?since={}", since);
+    let (status, body) = call(&app, "GET", &uri, None, true).await?;
+    recorder.check("stream_backfill", status, &body)?;
+
+    let (status, body) = call(&app, "GET", "/competitions/999999/leaderboard", None, true).await?;
+    recorder.check("competition_leaderboard_not_found", status, &body)?;
+    let uri = "/competitions/999999/leaderboard?limit=10&offset=0";
+    let (status, body) = call(&app, "GET", uri, None, true).await?;
+    recorder.check("competition_leaderboard_page_not_found", status, &body)?;
+
+    let (status, body) = call(&app, "POST", "/faucet/sweep", None, true).await?;
+    recorder.check("faucet_sweep", status, &body)?;
+    let signup = json!({
+        "username": "contract_signup",
+        "email": "contract_signup@test.com",
+        "password_hash": "hash"
+    });
+    let (status, body) = call(&app, "POST", "/users/provision", Some(signup), true).await?;
+    recorder.check("user_provision", status, &body)?;
+    let taken = json!({
+        "username": "contract_signup",
+        "email": "someone_else@test.com",
+        "password_hash": "hash"
+    });
+    let (status, body) = call(&app, "POST", "/users/provision", Some(taken), true).await?;
+    recorder.check("user_provision_taken", status, &body)?;
+
+    let (status, body) = call(&app, "POST", "/forecasts/compact", None, true).await?;
+    recorder.check("forecast_compaction", status, &body)?;
+
+    let (status, body) = call(&app, "POST", "/scores/audit", None, true).await?;
+    recorder.check("score_audit", status, &body)?;
+    let (status, body) = call(&app, "POST", "/scores/audit?user_id=0", None, true).await?;
+    recorder.check("score_audit_invalid_user", status, &body)?;
+    let uri = format!("/scores/reseal?user_id={}&actor=admin", alice);
+    let (status, body) = call(&app, "POST", &uri, None, true).await?;
+    recorder.check("score_reseal", status, &body)?;
+    // Event-targeted entries snapshot the whole events row, so the golden
+    // pins the reseal's instead
+    let uri = "/admin/audit?action=score_reseal&limit=10";
+    let (status, body) = call(&app, "GET", uri, None, true).await?;
+    recorder.check("admin_audit", status, &body)?;
+
+    let uri = format!("/events/{}/metadata", open_event);
+    let metadata = json!({
+        "resolution_criteria": "Resolves YES if the contract test passes.",
+        "value_unit": "tests",
+        "source_url": "https://example.com/contract",
+    });
+    let (status, body) = call(&app, "PUT", &uri, Some(metadata), true).await?;
+    recorder.check("event_metadata_update", status, &body)?;
+
+    let summaries = json!({ "summaries": [{
+        "event_id": open_event,
+        "hour": chrono::Utc::now() - chrono::Duration::hours(1),
+        "comment_count": 12,
+        "sentiment": 0.25,
+    }] });
+    let (status, body) = call(&app, "POST", "/comments/ingest", Some(summaries), true).await?;
+    recorder.check("comment_ingest", status, &body)?;
+
+    let message = json!({ "message": "not hex" });
+    let (status, body) = call(&app, "POST", "/mls/messages", Some(message), true).await?;
+    recorder.check("mls_message_invalid", status, &body)?;
+    let publish = json!({ "user_id": alice, "group_info": "not hex" });
+    let (status, body) = call(&app, "POST", "/directory/groups/chat", Some(publish), true).await?;
+    recorder.check("directory_publish_invalid", status, &body)?;
+    let uri = format!("/directory/groups/chat?user_id={}", alice);
+    let (status, body) = call(&app, "GET", &uri, None, true).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{}", body);
+
+    let (status, body) = call(&app, "GET", "/clusters/999999", None, true).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{}", body);
+
+    let (status, body) = call(&app, "POST", "/archive/run", None, true).await?;
+    recorder.check("archive_run", status, &body)?;
+    let uri = "/admin/cache/warm?targets=markets,leaderboards";
+    let (status, body) = call(&app, "POST", uri, None, true).await?;
+    recorder.check("cache_warm", status, &body)?;
+    let uri = "/admin/cache/warm?targets=markets,everything";
+    let (status, body) = call(&app, "POST", uri, None, true).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    let backtest = json!({
+        "clip_epsilon": 0.01,
+        "pll_penalty": 0.5,
+        "time_weight": { "curve": "exponential", "half_life_hours": 24.0 },
+        "min_resolved": 1,
+    });
+    let (status, body) = call(&app, "POST", "/admin/backtest", Some(backtest), true).await?;
+    recorder.check("backtest", status, &body)?;
+    let backtest = json!({ "clip_epsilon": 0.0 });
+    let (status, body) = call(&app, "POST", "/admin/backtest", Some(backtest), true).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+    let uri = format!("/users/{}/limit-orders", alice);
+    let order =
+        json!({ "event_id": open_event, "side": "yes", "trigger_prob": 0.01, "stake": 5.0 });
+    let (status, body) = call(&app, "POST", &uri, Some(order), true).await?;
+    recorder.check("limit_order_placed", status, &body)?;
+    let order_id = body["id"].as_i64().expect("limit order id");
+    let (status, body) = call(&app, "GET", &uri, None, true).await?;
+    recorder.check("user_limit_orders", status, &body)?;
+    let uri = format!("/users/{}/limit-orders/{}", alice, order_id);
+    let (status, body) = call(&app, "DELETE", &uri, None, true).await?;
+    recorder.check("limit_order_cancelled", status, &body)?;
+    let (status, body) = call(&app, "DELETE", &uri, None, true).await?;
+    assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+
+    // Trades and resolutions re-rank in the background; settle the snapshot
+    // and give alice a transition so the read has one to show
+    rank_history::update_global_rankings(&pool, None).await?;
+    sqlx::query(
+        "INSERT INTO rank_transitions (user_id, old_rank, new_rank, cause_event_id)
+         VALUES ($1, 2, 1, $2)",
+    )
+    .bind(alice)
+    .bind(resolved_event)
+    .execute(&pool)
+    .await?;
+    let uri = format!("/user/{}/rank-history", alice);
+    let (status, body) = call(&app, "GET", &uri, None, true).await?;
+    recorder.check("user_rank_history", status, &body)?;
+    let uri = format!("/events/{}/archive/restore", resolved_event);
+    let (status, body) = call(&app, "POST", &uri, Some(json!({})), true).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+    // Import and webhook reporting (read-only, no provider calls)
+    let reads = [
+        ("imports_status", "/imports/status".to_string()),
+        ("source_status_report", "/imports/source-status".to_string()),
+        (
+            "event_source_status",
+            format!("/events/{}/source-status", open_event),
+        ),
+        ("user_portfolio", format!("/users/{}/portfolio", alice)),
+        ("user_pnl", format!("/users/{}/pnl", alice)),
+        (
+            "user_event_shares",
+            format!("/users/{}/shares/{}", alice, open_event),
+        ),
+        ("user_dashboard", format!("/user/{}/dashboard", alice)),
+        ("user_exposure", format!("/user/{}/exposure", alice)),
+        ("user_faucet", format!("/user/{}/faucet", alice)),
+        ("user_preferences", format!("/user/{}/preferences", alice)),
+        ("user_predictions", format!("/user/{}/predictions", alice)),
+        ("user_risk", format!("/user/{}/risk", alice)),
+        ("mls_welcomes", "/mls/welcomes/00".to_string()),
+        ("user_mailbox", format!("/users/{}/mailbox", alice)),
+        (
+            "user_push_devices",
+            format!("/users/{}/push-devices", alice),
+        ),
+        ("event_clusters", "/event-clusters".to_string()),
+        (
+            "resolution_history",
+            format!("/events/{}/resolution-history", resolved_event),
+        ),
+        ("webhook_deliveries", "/webhooks/deliveries".to_string()),
+        (
+            "metaculus_import_progress",
+            "/metaculus/import-progress".to_string(),
+        ),
+        ("engine_version", "/version".to_string()),
+        (
+            "event_search",
+            "/events/search?q=contract&status=all".to_string(),
+        ),
+        ("consensus_accuracy", "/consensus/accuracy".to_string()),
+        ("market_accuracy", "/analytics/market-accuracy".to_string()),
+        ("arbitrage_report", "/analytics/arbitrage".to_string()),
+        (
+            "liquidity_recommendations",
+            "/liquidity/recommendations".to_string(),
+        ),
+        ("archive_status", "/archive/status".to_string()),
+        ("partition_status", "/partitions/market-updates".to_string()),
+        ("event_metadata", format!("/events/{}/metadata", open_event)),
+        (
+            "liquidity_migrations",
+            format!("/events/{}/liquidity/migrations", open_event),
+        ),
+        (
+            "market_state_at",
+            format!("/events/{}/state-at?ts=2020-01-01T00:00:00Z", open_event),
+        ),
+        (
+            "price_history",
+            format!("/events/{}/history?resolution=1d&buckets=7", open_event),
+        ),
+        (
+            "price_history_invalid",
+            format!("/events/{}/history?resolution=2h", open_event),
+        ),
+        (
+            "market_depth",
+            format!("/events/{}/depth?step=0.25", open_event),
+        ),
+        (
+            "market_depth_invalid",
+            format!("/events/{}/depth?step=0", open_event),
+        ),
+        (
+            "closing_soon",
+            "/events/closing-soon?within=30d".to_string(),
+        ),
+    ];
+    for (name, uri) in reads {
+        let (status, body) = call(&app, "GET", &uri, None, true).await?;
+        recorder.check(name, status, &body)?;
+    }
+
+    // Bot API keys: bound to their owner, scoped, and rate limited
+    let uri = format!("/users/{}/api-keys", alice);
+    let key = json!({ "name": "alice-bot", "scope": "trade" });
+    let (status, body) = call(&app, "POST", &uri, Some(key), true).await?;
+    recorder.check("api_key_create", status, &body)?;
+    let alice_key = body["key"]["api_key"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    let uri = format!("/users/{}/api-keys", bob);
+    let key = json!({ "name": "bob-dashboard", "rate_limit_per_minute": 2 });
+    let (_, body) = call(&app, "POST", &uri, Some(key), true).await?;
+    let bob_key = body["key"]["api_key"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+
+    let uri = format!("/users/{}/portfolio", alice);
+    let (status, _) = call_with_key(&app, "GET", &uri, None, &alice_key).await?;
+    assert_eq!(status, StatusCode::OK);
+    let uri = format!("/users/{}/portfolio", bob);
+    let (status, _) = call_with_key(&app, "GET", &uri, None, &alice_key).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let uri = format!("/events/{}/update", open_event);
+    let trade = json!({ "user_id": alice, "target_prob": 0.6, "stake": 5.0 });
+    let (status, _) = call_with_key(&app, "POST", &uri, Some(trade), &alice_key).await?;
+    assert_eq!(status, StatusCode::OK);
+    let trade = json!({ "user_id": bob, "target_prob": 0.6, "stake": 5.0 });
+    let (status, _) = call_with_key(&app, "POST", &uri, Some(trade.clone()), &alice_key).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = call_with_key(&app, "POST", &uri, Some(trade), &bob_key).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN, "read-only key traded");
+    let (status, _) = call_with_key(&app, "POST", "/markets/close-sweep", None, &alice_key).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let uri = format!("/users/{}/api-keys", bob);
+    for expected in [
+        StatusCode::OK,
+        StatusCode::OK,
+        StatusCode::TOO_MANY_REQUESTS,
+    ] {
+        let (status, _) = call_with_key(&app, "GET", &uri, None, &bob_key).await?;
+        assert_eq!(status, expected);
+    }
+
+    let uri = format!("/users/{}/api-keys", alice);
+    let (status, body) = call(&app, "GET", &uri, None, true).await?;
+    recorder.check("api_keys_list", status, &body)?;
+    let alice_key_id = body["keys"][0]["id"].as_i64().unwrap_or_default();
+    let uri = format!("/users/{}/api-keys/{}", alice, alice_key_id);
+    let (status, _) = call(&app, "DELETE", &uri, None, true).await?;
+    assert_eq!(status, StatusCode::OK);
+    let uri = format!("/users/{}/portfolio", alice);
+    let (status, _) = call_with_key(&app, "GET", &uri, None, &alice_key).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "revoked key still works");
+
+    // Closing bob's position sells both sides; a second close has nothing left
+    let uri = format!("/events/{}/close", open_event);
+    let close = json!({ "user_id": bob });
+    let (status, body) = call(&app, "POST", &uri, Some(close.clone()), true).await?;
+    recorder.check("close_position", status, &body)?;
+    let (status, body) = call(&app, "POST", &uri, Some(close), true).await?;
+    recorder.check("close_position_empty", status, &body)?;
+
+    cleanup_test_database(test_db).await?;
+
+    assert!(
+        recorder.mismatches.is_empty(),
+        "API response shapes changed or unrecorded (rerun with UPDATE_GOLDEN=1 if intentional):\n{}",
+        recorder.mismatches.join("\n")
+    );
+    Ok(())
+}
@@ -0,0 +1,197 @@
+//! Machine-readable codes on API error responses.
+//!
+//! Every error body is `{"error": <message>, "code": <CODE>}`. Codes are
+//! stable, so the Node layer can translate or retry a failure without
+//! matching on text; the union is generated into
+//! `shared/types/ApiErrorCode.ts`. Domain code returns a `CodedError` (see
+//! `coded`) and `ErrorCode::of` reads the code back off the error chain.
+
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fmt;
+
+use crate::db_adapter::sqlstate;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ts_rs::TS)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[ts(export, export_to = "../../shared/types/ApiErrorCode.ts")]
+pub enum ErrorCode {
+    /// Malformed or out-of-range input
+    InvalidRequest,
+    Unauthorized,
+    /// Authenticated, but not allowed this (API key scope, group membership)
+    Forbidden,
+    NotFound,
+    /// The request lost to a concurrent change (epoch, identity taken, order state)
+    Conflict,
+    RateLimited,
+    InsufficientBalance,
+    InsufficientShares,
+    MarketClosed,
+    MarketResolved,
+    MarketEmbargoed,
+    /// Imported as forecast-only; it takes forecasts but not trades
+    MarketForecastOnly,
+    /// Shares bought within the hold period can't be sold yet
+    HoldActive,
+    /// The fill would break the caller's max_cost/min_shares bounds
+    SlippageExceeded,
+    /// The caller's market_version is out of date; retry with a fresh quote
+    StaleVersion,
+    /// The trade kept conflicting with concurrent trades; retry it
+    SerializationRetriesExhausted,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn status(self) -> StatusCode {
+        match self {
+            ErrorCode::InvalidRequest
+            | ErrorCode::InsufficientBalance
+            | ErrorCode::InsufficientShares
+            | ErrorCode::MarketClosed
+            | ErrorCode::MarketResolved
+            | ErrorCode::MarketEmbargoed
+            | ErrorCode::MarketForecastOnly
+            | ErrorCode::HoldActive => StatusCode::BAD_REQUEST,
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::Forbidden => StatusCode::FORBIDDEN,
+            ErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ErrorCode::Conflict | ErrorCode::SlippageExceeded | ErrorCode::StaleVersion => {
+                StatusCode::CONFLICT
+            }
+            ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::SerializationRetriesExhausted => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// The code `error` or any error it wraps carries: a `CodedError`'s
+    /// own, or SerializationRetriesExhausted for lock contention the retry
+    /// loops gave up on.
+    pub fn of(error: &anyhow::Error) -> Option<ErrorCode> {
+        error.chain().find_map(|cause| {
+            if let Some(coded) = cause.downcast_ref::<CodedError>() {
+                return Some(coded.code);
+            }
+            match cause.downcast_ref::<sqlx::Error>() {
+                Some(sqlx::Error::Database(db_error)) => match db_error.code().as_deref() {
+                    Some(
+                        sqlstate::SERIALIZATION_FAILURE
+                        | sqlstate::DEADLOCK_DETECTED
+                        | sqlstate::LOCK_NOT_AVAILABLE,
+                    ) => Some(ErrorCode::SerializationRetriesExhausted),
+                    _ => None,
+                },
+                _ => None,
+            }
+        })
+    }
+}
+
+/// A failure with a fixed code; its message is for people.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodedError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl fmt::Display for CodedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CodedError {}
+
+/// An `anyhow` error carrying `code`.
+pub fn coded(code: ErrorCode, message: impl Into<String>) -> anyhow::Error {
+    anyhow::Error::new(CodedError {
+        code,
+        message: message.into(),
+    })
+}
+
+/// The response for an error that carries a code, with the coded message
+/// rather than any context wrapped around it; None for other errors.
+pub fn coded_response(error: &anyhow::Error) -> Option<(StatusCode, Json<Value>)> {
+    match ErrorCode::of(error)? {
+        ErrorCode::SerializationRetriesExhausted => Some(api_error(
+            ErrorCode::SerializationRetriesExhausted,
+            "Too many concurrent trades on this market; retry the request",
+        )),
+        code => {
+            let message = error
+                .chain()
+                .find_map(|cause| cause.downcast_ref::<CodedError>())
+                .map_or_else(|| error.to_string(), |coded| coded.message.clone());
+            Some(api_error(code, &message))
+        }
+    }
+}
+
+/// An error body with `code`.
+pub fn error_body(code: ErrorCode, message: &str) -> Value {
+    json!({ "error": message, "code": code })
+}
+
+/// An error response with `code` and its status.
+pub fn api_error(code: ErrorCode, message: &str) -> (StatusCode, Json<Value>) {
+    (code.status(), Json(error_body(code, message)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coded_errors_keep_their_code_under_context() {
+        let error = coded(
+            ErrorCode::HoldActive,
+            "Hold period not expired for recent purchases",
+        )
+        .context("Share sale error");
+        assert_eq!(ErrorCode::of(&error), Some(ErrorCode::HoldActive));
+        let (status, Json(body)) = coded_response(&error).unwrap();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body,
+            json!({
+                "error": "Hold period not expired for recent purchases",
+                "code": "HOLD_ACTIVE"
+            })
+        );
+        for (code, status) in [
+            (ErrorCode::InsufficientBalance, 400),
+            (ErrorCode::MarketEmbargoed, 400),
+            (ErrorCode::SlippageExceeded, 409),
+            (ErrorCode::SerializationRetriesExhausted, 503),
+            (ErrorCode::NotFound, 404),
+        ] {
+            assert_eq!(code.status().as_u16(), status, "{:?}", code);
+        }
+    }
+
+    #[test]
+    fn uncoded_errors_have_no_code_whatever_they_say() {
+        let error = anyhow::anyhow!("Insufficient RP balance");
+        assert_eq!(ErrorCode::of(&error), None);
+        assert!(coded_response(&error).is_none());
+    }
+
+    #[test]
+    fn bodies_carry_the_screaming_snake_code() {
+        let (status, Json(body)) = api_error(ErrorCode::MarketClosed, "Market closed");
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body,
+            json!({ "error": "Market closed", "code": "MARKET_CLOSED" })
+        );
+        assert_eq!(
+            error_body(ErrorCode::SerializationRetriesExhausted, "busy")["code"],
+            "SERIALIZATION_RETRIES_EXHAUSTED"
+        );
+    }
+}
@@ -0,0 +1,494 @@
+//! API keys for bot traders.
+//!
+//! Keys live in the backend's `api_keys` table, so a key works against both
+//! the backend and the engine: the engine only ever sees its SHA-256 hash,
+//! and a key sent as `x-api-key` acts as the user it belongs to. Its scopes
+//! decide what it can reach: `market:read` covers the market, portfolio and
+//! forecast reads, `market:trade` adds trading, limit orders and forecasting. Everything
+//! else (admin, imports, resolution, key management) stays behind the
+//! engine token. Whatever user a request names, in the path, the
+//! `user_id` query parameter or the JSON body, has to be the key's owner.
+//!
+//! Each key has a per-minute request limit, counted in memory, so several
+//! engines each allow the full limit. Requests and rejections are tallied
+//! per hour in `api_key_usage` for the owner's key listing.
+
+use anyhow::Result;
+use axum::http::Method;
+use chrono::{DateTime, Utc};
+use moka::future::Cache;
+use rand::RngCore;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use sqlx::{PgPool, Row};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::api_error::{coded, ErrorCode};
+
+pub const KEY_PREFIX: &str = "sk_live_";
+pub const MAX_RATE_LIMIT_PER_MINUTE: i32 = 6000;
+pub const MAX_NAME_LENGTH: usize = 255;
+
+const READ_SCOPE: &str = "market:read";
+const TRADE_SCOPE: &str = "market:trade";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Scope {
+    ReadOnly,
+    Trade,
+}
+
+impl Scope {
+    pub fn parse(scope: &str) -> Option<Self> {
+        match scope.trim().to_lowercase().as_str() {
+            "read-only" | "read_only" | "read" => Some(Self::ReadOnly),
+            "trade" => Some(Self::Trade),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::ReadOnly => "read-only",
+            Self::Trade => "trade",
+        }
+    }
+
+    fn scopes(self) -> Vec<&'static str> {
+        match self {
+            Self::ReadOnly => vec![READ_SCOPE],
+            Self::Trade => vec![READ_SCOPE, TRADE_SCOPE],
+        }
+    }
+
+    /// The widest engine scope in a key's scope list, if it has any.
+    fn from_scopes(scopes: &[String]) -> Option<Self> {
+        if scopes.iter().any(|s| s == TRADE_SCOPE) {
+            Some(Self::Trade)
+        } else if scopes.iter().any(|s| s == READ_SCOPE) {
+            Some(Self::ReadOnly)
+        } else {
+            None
+        }
+    }
+}
+
+/// A verified key.
+#[derive(Debug, Clone)]
+pub struct ApiKey {
+    pub id: i32,
+    pub user_id: i32,
+    pub scope: Scope,
+    pub rate_limit_per_minute: u32,
+}
+
+pub fn hash_key(raw: &str) -> String {
+    hex::encode(Sha256::digest(raw.as_bytes()))
+}
+
+fn generate_key() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("{}{}", KEY_PREFIX, hex::encode(bytes))
+}
+
+pub async fn ensure_api_key_tables(pool: &PgPool) -> Result<()> {
+    // Created by the backend's migrations; repeated so the engine can run
+    // against a database the backend hasn't set up
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS api_keys (
+            id SERIAL PRIMARY KEY,
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            name VARCHAR(255) NOT NULL,
+            key_hash VARCHAR(255) NOT NULL UNIQUE,
+            scopes VARCHAR(255)[] DEFAULT '{"market:read", "market:trade", "social:post"}',
+            is_bot BOOLEAN DEFAULT false,
+            last_used_at TIMESTAMP WITH TIME ZONE,
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("ALTER TABLE api_keys ADD COLUMN IF NOT EXISTS rate_limit_per_minute INTEGER")
+        .execute(pool)
+        .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS api_key_usage (
+            key_id INTEGER NOT NULL REFERENCES api_keys(id) ON DELETE CASCADE,
+            hour TIMESTAMPTZ NOT NULL,
+            requests BIGINT NOT NULL DEFAULT 0,
+            rejected BIGINT NOT NULL DEFAULT 0,
+            PRIMARY KEY (key_id, hour)
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Creates a key for `user_id` and returns it with the plaintext key, which
+/// isn't stored and can't be shown again. `rate_limit_per_minute` of `None`
+/// leaves the key on the configured default. A user has at most one key,
+/// as the backend enforces, so a leaked key can't quietly coexist with its
+/// replacement.
+pub async fn create_key(
+    pool: &PgPool,
+    user_id: i32,
+    name: &str,
+    scope: Scope,
+    rate_limit_per_minute: Option<i32>,
+) -> Result<Value> {
+    let name = name.trim();
+    if name.is_empty() || name.len() > MAX_NAME_LENGTH {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("name must be between 1 and {} characters", MAX_NAME_LENGTH),
+        ));
+    }
+    if let Some(limit) = rate_limit_per_minute {
+        if !(1..=MAX_RATE_LIMIT_PER_MINUTE).contains(&limit) {
+            return Err(coded(
+                ErrorCode::InvalidRequest,
+                format!(
+                    "rate_limit_per_minute must be between 1 and {}",
+                    MAX_RATE_LIMIT_PER_MINUTE
+                ),
+            ));
+        }
+    }
+
+    let mut tx = pool.begin().await?;
+    // Serializes concurrent creates for the same user
+    sqlx::query("SELECT id FROM users WHERE id = $1 FOR UPDATE")
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| coded(ErrorCode::NotFound, "User not found"))?;
+    let existing: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM api_keys WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    if existing > 0 {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "An API key already exists; it must be revoked before creating a new one",
+        ));
+    }
+
+    let key = generate_key();
+    let row = sqlx::query(
+        r#"
+        INSERT INTO api_keys (user_id, name, key_hash, scopes, is_bot, rate_limit_per_minute)
+        VALUES ($1, $2, $3, $4, true, $5)
+        RETURNING id, created_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(name)
+    .bind(hash_key(&key))
+    .bind(scope.scopes())
+    .bind(rate_limit_per_minute)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(json!({
+        "api_key": key,
+        "id": row.get::<i32, _>("id"),
+        "user_id": user_id,
+        "name": name,
+        "scope": scope.as_str(),
+        "rate_limit_per_minute": rate_limit_per_minute,
+        "created_at": row.get::<DateTime<Utc>, _>("created_at"),
+    }))
+}
+
+/// Deletes one of `user_id`'s keys, as the backend does. Returns whether
+/// there was such a key.
+pub async fn revoke_key(pool: &PgPool, user_id: i32, key_id: i32) -> Result<bool> {
+    let deleted = sqlx::query("DELETE FROM api_keys WHERE id = $1 AND user_id = $2")
+        .bind(key_id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(deleted.rows_affected() > 0)
+}
+
+/// The user's keys with their usage. Keys without a limit of their own
+/// show `default_rate_limit`.
+pub async fn list_keys(pool: &PgPool, user_id: i32, default_rate_limit: u32) -> Result<Value> {
+    let rows = sqlx::query(
+        r#"
+        SELECT k.id, k.name, k.scopes, k.is_bot, k.rate_limit_per_minute,
+               k.created_at, k.last_used_at,
+               COALESCE(SUM(u.requests), 0)::BIGINT AS requests,
+               COALESCE(SUM(u.rejected), 0)::BIGINT AS rejected,
+               COALESCE(SUM(u.requests) FILTER (WHERE u.hour > NOW() - INTERVAL '24 hours'), 0)
+                   ::BIGINT AS requests_24h,
+               COALESCE(SUM(u.rejected) FILTER (WHERE u.hour > NOW() - INTERVAL '24 hours'), 0)
+                   ::BIGINT AS rejected_24h
+        FROM api_keys k
+        LEFT JOIN api_key_usage u ON u.key_id = k.id
+        WHERE k.user_id = $1
+        GROUP BY k.id
+        ORDER BY k.created_at DESC, k.id DESC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    let keys: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            let scopes: Vec<String> = row
+                .get::<Option<Vec<String>>, _>("scopes")
+                .unwrap_or_default();
+            let rate_limit = row
+                .get::<Option<i32>, _>("rate_limit_per_minute")
+                .map_or(default_rate_limit, |l| l as u32);
+            json!({
+                "id": row.get::<i32, _>("id"),
+                "name": row.get::<String, _>("name"),
+                "scope": Scope::from_scopes(&scopes).map(Scope::as_str),
+                "scopes": scopes,
+                "is_bot": row.get::<Option<bool>, _>("is_bot").unwrap_or(false),
+                "rate_limit_per_minute": rate_limit,
+                "created_at": row.get::<Option<DateTime<Utc>>, _>("created_at"),
+                "last_used_at": row.get::<Option<DateTime<Utc>>, _>("last_used_at"),
+                "usage": {
+                    "requests": row.get::<i64, _>("requests"),
+                    "rejected": row.get::<i64, _>("rejected"),
+                    "requests_24h": row.get::<i64, _>("requests_24h"),
+                    "rejected_24h": row.get::<i64, _>("rejected_24h"),
+                },
+            })
+        })
+        .collect();
+    Ok(json!({ "user_id": user_id, "keys": keys }))
+}
+
+/// Looks up a presented key. Keys without an engine scope don't verify.
+pub async fn authenticate(
+    pool: &PgPool,
+    raw: &str,
+    default_rate_limit: u32,
+) -> Result<Option<ApiKey>> {
+    if !raw.starts_with(KEY_PREFIX) {
+        return Ok(None);
+    }
+    let row = sqlx::query(
+        "SELECT id, user_id, scopes, rate_limit_per_minute FROM api_keys WHERE key_hash = $1",
+    )
+    .bind(hash_key(raw))
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.and_then(|row| {
+        let scopes: Vec<String> = row
+            .get::<Option<Vec<String>>, _>("scopes")
+            .unwrap_or_default();
+        Some(ApiKey {
+            id: row.get("id"),
+            user_id: row.get("user_id"),
+            scope: Scope::from_scopes(&scopes)?,
+            rate_limit_per_minute: row
+                .get::<Option<i32>, _>("rate_limit_per_minute")
+                .map_or(default_rate_limit, |l| l as u32),
+        })
+    }))
+}
+
+/// Tallies one request against the key's current hour.
+pub async fn record_usage(pool: &PgPool, key_id: i32, rejected: bool) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO api_key_usage (key_id, hour, requests, rejected)
+        VALUES ($1, date_trunc('hour', NOW()), 1, $2)
+        ON CONFLICT (key_id, hour) DO UPDATE
+        SET requests = api_key_usage.requests + 1,
+            rejected = api_key_usage.rejected + EXCLUDED.rejected
+        "#,
+    )
+    .bind(key_id)
+    .bind(rejected as i64)
+    .execute(pool)
+    .await?;
+    if !rejected {
+        sqlx::query("UPDATE api_keys SET last_used_at = NOW() WHERE id = $1")
+            .bind(key_id)
+            .execute(pool)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Requests per key in the current minute. Keys roll over each minute, so
+/// the TTL only has to clear out old ones.
+#[derive(Clone)]
+pub struct RateLimiter {
+    windows: Cache<(i32, i64), Arc<AtomicU32>>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self {
+            windows: Cache::builder()
+                .max_capacity(100_000)
+                .time_to_live(Duration::from_secs(120))
+                .build(),
+        }
+    }
+}
+
+impl RateLimiter {
+    /// Counts a request for `key` and returns how many it has left this
+    /// minute, or `None` if it is over its limit.
+    pub async fn check(&self, key: &ApiKey) -> Option<u32> {
+        let minute = Utc::now().timestamp().div_euclid(60);
+        let count = self
+            .windows
+            .get_with((key.id, minute), async { Arc::new(AtomicU32::new(0)) })
+            .await
+            .fetch_add(1, Ordering::Relaxed)
+            + 1;
+        key.rate_limit_per_minute.checked_sub(count)
+    }
+}
+
+/// The scope a key needs for a route, or `None` if keys can't use it.
+pub fn required_scope(method: &Method, path: &str) -> Option<Scope> {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let read = *method == Method::GET;
+    let write = *method == Method::POST || *method == Method::PUT;
+    match segments.as_slice() {
+        ["version"]
+        | ["events", "search" | "closing-soon"]
+        | ["markets", "sparklines" | "trending"]
+        | ["consensus", "accuracy"]
+        | ["analytics", "market-accuracy"]
+        | ["events", _, "market" | "metadata" | "trades" | "kelly" | "sell-quote" | "quote" | "depth"]
+        | ["events", _, "numeric-quote" | "distribution" | "resolution-history" | "state-at" | "history"]
+        | ["users", _, "portfolio" | "pnl" | "paper-predictions" | "api-keys" | "limit-orders"]
+        | ["users", _, "shares", _]
+        | ["user", _, "dashboard" | "exposure" | "faucet" | "risk" | "preferences" | "predictions"
+        | "rank-history"]
+        | ["user", _, "events", _, "forecast-history"]
+        | ["competitions", _, "leaderboard"]
+            if read =>
+        {
+            Some(Scope::ReadOnly)
+        }
+        ["events", _, "forecast"] if write => Some(Scope::Trade),
+        ["events", _, "update" | "trade" | "update-outcome" | "sell" | "sell-outcome" | "numeric-trade"
+        | "numeric-sell" | "numeric-bucket-buy" | "numeric-bucket-sell" | "paper-prediction"
+        | "close"]
+        | ["competitions", _, "join"]
+        | ["users", _, "limit-orders"]
+            if *method == Method::POST =>
+        {
+            Some(Scope::Trade)
+        }
+        ["users", _, "limit-orders", _] if *method == Method::DELETE => Some(Scope::Trade),
+        _ => None,
+    }
+}
+
+/// The user a `/users/:id/...` or `/user/:id/...` path acts for.
+pub fn path_user_id(path: &str) -> Option<&str> {
+    let mut segments = path.trim_matches('/').split('/');
+    match (segments.next(), segments.next()) {
+        (Some("users" | "user"), Some(id)) => Some(id),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_only_keys_reach_reads_but_not_trades() {
+        for (method, path) in [
+            (Method::GET, "/events/3/market"),
+            (Method::GET, "/users/7/portfolio"),
+            (Method::GET, "/users/7/pnl"),
+            (Method::GET, "/users/7/api-keys"),
+            (Method::GET, "/user/7/events/3/forecast-history"),
+            (Method::GET, "/user/7/dashboard"),
+            (Method::GET, "/user/7/rank-history"),
+            (Method::GET, "/users/7/shares/3"),
+            (Method::GET, "/events/3/quote"),
+            (Method::GET, "/events/3/depth"),
+            (Method::GET, "/events/3/history"),
+            (Method::GET, "/markets/trending"),
+            (Method::GET, "/events/search"),
+        ] {
+            assert_eq!(
+                required_scope(&method, path),
+                Some(Scope::ReadOnly),
+                "{}",
+                path
+            );
+        }
+        for (method, path) in [
+            (Method::POST, "/events/3/update"),
+            (Method::POST, "/events/3/trade"),
+            (Method::POST, "/events/3/sell-outcome"),
+            (Method::POST, "/events/3/close"),
+            (Method::PUT, "/events/3/forecast"),
+            (Method::POST, "/users/7/limit-orders"),
+            (Method::DELETE, "/users/7/limit-orders/12"),
+        ] {
+            assert_eq!(
+                required_scope(&method, path),
+                Some(Scope::Trade),
+                "{}",
+                path
+            );
+        }
+        assert!(Scope::ReadOnly < Scope::Trade);
+    }
+
+    #[test]
+    fn admin_routes_are_closed_to_keys() {
+        for (method, path) in [
+            (Method::POST, "/events/3/market-resolve"),
+            (Method::POST, "/events/3/market-resolve/commit"),
+            (Method::POST, "/users/7/api-keys"),
+            (Method::DELETE, "/users/7/api-keys/2"),
+            (Method::GET, "/events/3/shares"),
+            (Method::POST, "/events/3/trades/identified"),
+            (Method::GET, "/metaculus/sync"),
+            (Method::POST, "/markets"),
+            (Method::POST, "/users/provision"),
+            (Method::GET, "/events/3/update"),
+        ] {
+            assert_eq!(required_scope(&method, path), None, "{} {}", method, path);
+        }
+    }
+
+    #[test]
+    fn scopes_round_trip_through_the_backend_list() {
+        let stored = |scope: Scope| -> Vec<String> {
+            scope.scopes().into_iter().map(String::from).collect()
+        };
+        assert_eq!(
+            Scope::from_scopes(&stored(Scope::Trade)),
+            Some(Scope::Trade)
+        );
+        assert_eq!(
+            Scope::from_scopes(&stored(Scope::ReadOnly)),
+            Some(Scope::ReadOnly)
+        );
+        assert_eq!(Scope::from_scopes(&["social:post".to_string()]), None);
+        assert_eq!(Scope::parse("Read-Only"), Some(Scope::ReadOnly));
+        assert_eq!(path_user_id("/users/7/portfolio"), Some("7"));
+        assert_eq!(path_user_id("/events/7/market"), None);
+    }
+}
@@ -0,0 +1,137 @@
+//! Internal arbitrage between linked markets.
+//!
+//! A scan prices every correlated and mutually exclusive cluster (see
+//! `cluster_consistency`) and records those whose gap is at least
+//! `min_gap`: complementary markets whose YES prices sum to 0.95 leave a
+//! 0.05 gap, as does a set of candidates summing to 1.05. Opportunities are
+//! kept in `arbitrage_opportunities`, one row per cluster, with when the gap
+//! was first seen; a cluster whose gap closes drops out on the next scan.
+//! The report serves the market-maker bot as well as anyone curious, so
+//! it only repeats prices that are already public.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::{PgPool, Row};
+use std::collections::HashSet;
+
+use crate::cluster_consistency::{self, ArbitrageLeg};
+use crate::exposure::ClusterRelation;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Opportunity {
+    pub cluster_id: i32,
+    pub name: String,
+    pub relation: ClusterRelation,
+    pub gap: f64,
+    pub probability_sum: Option<f64>,
+    pub legs: Vec<ArbitrageLeg>,
+    /// Not recorded by the previous scan.
+    pub new: bool,
+}
+
+pub async fn ensure_arbitrage_table(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS arbitrage_opportunities (
+            cluster_id INTEGER PRIMARY KEY REFERENCES event_clusters(id) ON DELETE CASCADE,
+            gap DOUBLE PRECISION NOT NULL,
+            probability_sum DOUBLE PRECISION,
+            legs JSONB NOT NULL,
+            first_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            detected_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Prices every constrained cluster and replaces the recorded
+/// opportunities with those whose gap is at least `min_gap`, widest first.
+pub async fn scan(pool: &PgPool, min_gap: f64) -> Result<Vec<Opportunity>> {
+    let clusters = cluster_consistency::load_constrained_clusters(pool).await?;
+    let mut opportunities: Vec<Opportunity> = clusters
+        .into_iter()
+        .filter_map(|cluster| {
+            let assessment = cluster_consistency::assess(cluster.relation, &cluster.members);
+            if assessment.legs.is_empty() || assessment.arbitrage_gap < min_gap {
+                return None;
+            }
+            Some(Opportunity {
+                cluster_id: cluster.cluster_id,
+                name: cluster.name,
+                relation: cluster.relation,
+                gap: assessment.arbitrage_gap,
+                probability_sum: assessment.probability_sum,
+                legs: assessment.legs,
+                new: true,
+            })
+        })
+        .collect();
+    opportunities.sort_by(|a, b| b.gap.total_cmp(&a.gap));
+
+    let mut tx = pool.begin().await?;
+    let recorded: HashSet<i32> =
+        sqlx::query_scalar("SELECT cluster_id FROM arbitrage_opportunities FOR UPDATE")
+            .fetch_all(&mut *tx)
+            .await?
+            .into_iter()
+            .collect();
+    let cluster_ids: Vec<i32> = opportunities.iter().map(|o| o.cluster_id).collect();
+    sqlx::query("DELETE FROM arbitrage_opportunities WHERE NOT (cluster_id = ANY($1))")
+        .bind(&cluster_ids)
+        .execute(&mut *tx)
+        .await?;
+    for opportunity in &mut opportunities {
+        opportunity.new = !recorded.contains(&opportunity.cluster_id);
+        sqlx::query(
+            "INSERT INTO arbitrage_opportunities (cluster_id, gap, probability_sum, legs)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (cluster_id) DO UPDATE
+             SET gap = EXCLUDED.gap,
+                 probability_sum = EXCLUDED.probability_sum,
+                 legs = EXCLUDED.legs,
+                 detected_at = NOW()",
+        )
+        .bind(opportunity.cluster_id)
+        .bind(opportunity.gap)
+        .bind(opportunity.probability_sum)
+        .bind(sqlx::types::Json(&opportunity.legs))
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(opportunities)
+}
+
+/// The opportunities the last scan recorded, widest gap first.
+pub async fn report(pool: &PgPool) -> Result<Value> {
+    let rows = sqlx::query(
+        "SELECT a.cluster_id, c.name, c.relation, a.gap, a.probability_sum, a.legs,
+                a.first_seen_at, a.detected_at
+         FROM arbitrage_opportunities a
+         JOIN event_clusters c ON c.id = a.cluster_id
+         ORDER BY a.gap DESC, a.cluster_id",
+    )
+    .fetch_all(pool)
+    .await?;
+    let opportunities: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            json!({
+                "cluster_id": row.get::<i32, _>("cluster_id"),
+                "name": row.get::<String, _>("name"),
+                "relation": row.get::<String, _>("relation"),
+                "gap": row.get::<f64, _>("gap"),
+                "probability_sum": row.get::<Option<f64>, _>("probability_sum"),
+                "legs": row.get::<Value, _>("legs"),
+                "first_seen_at": row.get::<DateTime<Utc>, _>("first_seen_at"),
+                "detected_at": row.get::<DateTime<Utc>, _>("detected_at"),
+            })
+        })
+        .collect();
+    Ok(json!({ "opportunities": opportunities }))
+}
@@ -0,0 +1,348 @@
+//! Cold storage for long-resolved markets.
+//!
+//! Once an event is past `archive_after_days` (always longer than the
+//! dispute window), its trade journal and settlement rows move into
+//! `<table>_archive` twins, recorded in `archived_events`. Whole-history
+//! reads go through the `<table>_all` views, so an archived market looks
+//! the same from outside. Buys a post-signal episode references stay hot,
+//! and an audit can restore an event until an admin archives it again.
+
+use crate::api_error::{coded, ErrorCode};
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::{PgConnection, PgPool, Postgres, Row, Transaction};
+
+/// Tables archived per event. Each has an `event_id` column.
+pub const ARCHIVED_TABLES: [&str; 3] = [
+    "market_updates",
+    "market_outcome_updates",
+    "resolution_payouts",
+];
+
+/// Events archived per run.
+const ARCHIVE_BATCH: i64 = 50;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Archival {
+    pub events: usize,
+    pub rows: u64,
+}
+
+async fn table_exists(conn: &mut PgConnection, table: &str) -> Result<bool> {
+    Ok(sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL")
+        .bind(table)
+        .fetch_one(conn)
+        .await?)
+}
+
+/// Column names and types of `table`, in order.
+async fn columns(conn: &mut PgConnection, table: &str) -> Result<Vec<(String, String)>> {
+    Ok(sqlx::query_as(
+        "SELECT a.attname::text, format_type(a.atttypid, a.atttypmod)
+         FROM pg_attribute a
+         WHERE a.attrelid = $1::regclass AND a.attnum > 0 AND NOT a.attisdropped
+         ORDER BY a.attnum",
+    )
+    .bind(table)
+    .fetch_all(conn)
+    .await?)
+}
+
+fn column_list(columns: &[(String, String)]) -> String {
+    columns
+        .iter()
+        .map(|(name, _)| format!("\"{}\"", name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Creates the archive twin and `_all` view of each archived table that
+/// exists, adding to the twin any column the hot table has gained since.
+pub async fn ensure_archive_schema(pool: &PgPool) -> Result<()> {
+    let mut conn = pool.acquire().await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS archived_events (
+            event_id INTEGER PRIMARY KEY REFERENCES events(id) ON DELETE CASCADE,
+            rows_archived BIGINT NOT NULL,
+            archived_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            restored_at TIMESTAMPTZ,
+            restored_by TEXT,
+            restore_reason TEXT
+        );
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+    for table in ARCHIVED_TABLES {
+        if !table_exists(&mut conn, table).await? {
+            continue;
+        }
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS {table}_archive (LIKE {table})"
+        ))
+        .execute(&mut *conn)
+        .await?;
+        let hot = columns(&mut conn, table).await?;
+        let archived = columns(&mut conn, &format!("{}_archive", table)).await?;
+        for (name, data_type) in &hot {
+            if !archived.iter().any(|(archived, _)| archived == name) {
+                sqlx::query(&format!(
+                    "ALTER TABLE {}_archive ADD COLUMN IF NOT EXISTS \"{}\" {}",
+                    table, name, data_type
+                ))
+                .execute(&mut *conn)
+                .await?;
+            }
+        }
+        sqlx::query(&format!(
+            "CREATE INDEX IF NOT EXISTS idx_{table}_archive_event ON {table}_archive (event_id)"
+        ))
+        .execute(&mut *conn)
+        .await?;
+        let cols = column_list(&hot);
+        sqlx::query(&format!(
+            "CREATE OR REPLACE VIEW {table}_all AS
+             SELECT {cols} FROM {table}
+             UNION ALL
+             SELECT {cols} FROM {table}_archive"
+        ))
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Moves the rows of `event_ids` from each hot table to its archive (or
+/// back, when `restore` is set). Returns rows moved per event.
+async fn move_rows(
+    tx: &mut Transaction<'_, Postgres>,
+    event_ids: &[i32],
+    restore: bool,
+) -> Result<BTreeMap<i32, i64>> {
+    let mut moved = BTreeMap::new();
+    for table in ARCHIVED_TABLES {
+        let archive = format!("{}_archive", table);
+        if !table_exists(tx, &archive).await? {
+            continue;
+        }
+        let cols = column_list(&columns(tx, table).await?);
+        let (from, to) = if restore {
+            (archive.as_str(), table)
+        } else {
+            (table, archive.as_str())
+        };
+        // Buys a post-signal episode references stay hot; moving one would
+        // cascade the episode away with it
+        let pinned = if !restore
+            && table == "market_updates"
+            && table_exists(tx, "post_signal_episodes").await?
+        {
+            " AND NOT EXISTS (SELECT 1 FROM post_signal_episodes pse
+                              WHERE pse.market_update_id = market_updates.id)"
+        } else {
+            ""
+        };
+        let event_rows: Vec<i32> = sqlx::query_scalar(&format!(
+            r#"
+            WITH moved AS (
+                DELETE FROM {from} WHERE event_id = ANY($1){pinned}
+                RETURNING {cols}
+            )
+            INSERT INTO {to} ({cols}) SELECT {cols} FROM moved
+            RETURNING event_id
+            "#
+        ))
+        .bind(event_ids)
+        .fetch_all(&mut **tx)
+        .await?;
+        for event_id in event_rows {
+            *moved.entry(event_id).or_insert(0) += 1;
+        }
+    }
+    Ok(moved)
+}
+
+async fn record_archived(
+    tx: &mut Transaction<'_, Postgres>,
+    event_ids: &[i32],
+    moved: &BTreeMap<i32, i64>,
+) -> Result<()> {
+    let rows: Vec<i64> = event_ids
+        .iter()
+        .map(|id| moved.get(id).copied().unwrap_or(0))
+        .collect();
+    sqlx::query(
+        r#"
+        INSERT INTO archived_events (event_id, rows_archived)
+        SELECT * FROM UNNEST($1::integer[], $2::bigint[])
+        ON CONFLICT (event_id) DO UPDATE
+        SET rows_archived = EXCLUDED.rows_archived,
+            archived_at = NOW(),
+            restored_at = NULL,
+            restored_by = NULL,
+            restore_reason = NULL
+        "#,
+    )
+    .bind(event_ids)
+    .bind(&rows)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Archives a batch of events resolved more than `after_days` ago that
+/// have never been archived. Call until it archives nothing to catch up.
+pub async fn archive_resolved(pool: &PgPool, after_days: f64) -> Result<Archival> {
+    let mut tx = pool.begin().await?;
+    let event_ids: Vec<i32> = sqlx::query_scalar(
+        r#"
+        SELECT e.id FROM events e
+        WHERE e.outcome IS NOT NULL
+          AND e.resolved_at::timestamptz <= NOW() - $1 * INTERVAL '1 day'
+          AND NOT EXISTS (SELECT 1 FROM archived_events a WHERE a.event_id = e.id)
+        ORDER BY e.id
+        LIMIT $2
+        FOR UPDATE OF e SKIP LOCKED
+        "#,
+    )
+    .bind(after_days)
+    .bind(ARCHIVE_BATCH)
+    .fetch_all(&mut *tx)
+    .await?;
+    if event_ids.is_empty() {
+        return Ok(Archival::default());
+    }
+    let moved = move_rows(&mut tx, &event_ids, false).await?;
+    record_archived(&mut tx, &event_ids, &moved).await?;
+    tx.commit().await?;
+    Ok(Archival {
+        events: event_ids.len(),
+        rows: moved.values().sum::<i64>() as u64,
+    })
+}
+
+/// Archives one event now, including one an audit restored. It must be
+/// resolved and past its dispute window.
+pub async fn archive_event(
+    pool: &PgPool,
+    event_id: i32,
+    dispute_window_hours: f64,
+) -> Result<Value> {
+    let mut tx = pool.begin().await?;
+    let event = sqlx::query(
+        "SELECT outcome IS NOT NULL AS resolved,
+                COALESCE(resolved_at::timestamptz <= NOW() - $2 * INTERVAL '1 hour', false)
+                    AS settled
+         FROM events WHERE id = $1 FOR UPDATE",
+    )
+    .bind(event_id)
+    .bind(dispute_window_hours)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| coded(ErrorCode::NotFound, "Event not found"))?;
+    if !event.get::<bool, _>("resolved") {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "event must be resolved before it is archived",
+        ));
+    }
+    if !event.get::<bool, _>("settled") {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!(
+                "event must be past its {}h dispute window before it is archived",
+                dispute_window_hours
+            ),
+        ));
+    }
+    let archived: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM archived_events
+                        WHERE event_id = $1 AND restored_at IS NULL)",
+    )
+    .bind(event_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if archived {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("Event {} is already archived", event_id),
+        ));
+    }
+    let moved = move_rows(&mut tx, &[event_id], false).await?;
+    let rows: i64 = moved.values().sum();
+    record_archived(&mut tx, &[event_id], &moved).await?;
+    tx.commit().await?;
+    Ok(json!({ "event_id": event_id, "rows_archived": rows }))
+}
+
+/// Moves an archived event's rows back to the hot tables for an audit.
+/// Scheduled runs won't archive it again.
+pub async fn restore_event(
+    pool: &PgPool,
+    event_id: i32,
+    actor: Option<&str>,
+    reason: Option<&str>,
+) -> Result<Value> {
+    let mut tx = pool.begin().await?;
+    let archived: Option<bool> = sqlx::query_scalar(
+        "SELECT restored_at IS NULL FROM archived_events WHERE event_id = $1 FOR UPDATE",
+    )
+    .bind(event_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    if archived != Some(true) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("Event {} is not archived", event_id),
+        ));
+    }
+    let moved = move_rows(&mut tx, &[event_id], true).await?;
+    sqlx::query(
+        "UPDATE archived_events
+         SET restored_at = NOW(), restored_by = $2, restore_reason = $3
+         WHERE event_id = $1",
+    )
+    .bind(event_id)
+    .bind(actor)
+    .bind(reason)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(json!({
+        "event_id": event_id,
+        "rows_restored": moved.values().sum::<i64>(),
+    }))
+}
+
+/// Archived and restored event counts and the rows held in each archive.
+pub async fn get_status(pool: &PgPool) -> Result<Value> {
+    let mut conn = pool.acquire().await?;
+    let events = sqlx::query(
+        "SELECT COUNT(*) FILTER (WHERE restored_at IS NULL) AS archived,
+                COUNT(*) FILTER (WHERE restored_at IS NOT NULL) AS restored,
+                MAX(archived_at) AS last_archived_at
+         FROM archived_events",
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    let mut tables = serde_json::Map::new();
+    for table in ARCHIVED_TABLES {
+        let archive = format!("{}_archive", table);
+        if table_exists(&mut conn, &archive).await? {
+            let rows: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", archive))
+                .fetch_one(&mut *conn)
+                .await?;
+            tables.insert(table.to_string(), json!(rows));
+        }
+    }
+    Ok(json!({
+        "archived_events": events.get::<i64, _>("archived"),
+        "restored_events": events.get::<i64, _>("restored"),
+        "last_archived_at": events.get::<Option<chrono::DateTime<chrono::Utc>>, _>("last_archived_at"),
+        "archived_rows": tables,
+    }))
+}
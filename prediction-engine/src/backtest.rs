@@ -0,0 +1,582 @@
+//! Scoring backtests: the leaderboard replayed under candidate scoring
+//! parameters, read from one snapshot without writing anything.
+//!
+//! Each resolved binary prediction's probability history is sliced as
+//! forecast scoring slices it and scored under `clip_epsilon`, `time_weight`
+//! and `pll_penalty` (see `ScoringParams`). Users are ranked by their mean
+//! score among those with at least `min_resolved` predictions, and the
+//! response pairs that leaderboard with today's, with each user's rank shift.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Row};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::api_error::{coded, ErrorCode};
+use crate::db_adapter::{tx_backend, Isolation};
+use crate::forecasts::{score_slices, ScoreSlice};
+use crate::user_predictions::{MIN_RANKED_PREDICTIONS, SCORING_JOINS};
+use intellacc_math::scoring::LOG_SCORE_FLOOR;
+
+/// Users listed on each leaderboard when the request sets no limit.
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 500;
+
+/// How a slice's weight grows with when it was held.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "curve", rename_all = "snake_case")]
+pub enum TimeWeightCurve {
+    /// Time weighed equally, as production does.
+    Uniform,
+    /// From 0 at the first forecast up to the resolution.
+    Linear,
+    /// Halving every `half_life_hours` before the resolution.
+    Exponential { half_life_hours: f64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScoringParams {
+    /// Floor on the probability given to what happened, before the log.
+    pub clip_epsilon: f64,
+    /// Share of the peer log loss (how far a log score fell below the
+    /// event's crowd mean) charged on top of the log score.
+    pub pll_penalty: f64,
+    pub time_weight: TimeWeightCurve,
+}
+
+impl ScoringParams {
+    /// What production scores with.
+    pub fn current() -> Self {
+        Self {
+            clip_epsilon: LOG_SCORE_FLOOR,
+            pll_penalty: 0.0,
+            time_weight: TimeWeightCurve::Uniform,
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if !(self.clip_epsilon > 0.0 && self.clip_epsilon < 0.5) {
+            return Err(coded(
+                ErrorCode::InvalidRequest,
+                "clip_epsilon must be between 0 and 0.5 (exclusive)",
+            ));
+        }
+        if !(self.pll_penalty.is_finite() && (0.0..=10.0).contains(&self.pll_penalty)) {
+            return Err(coded(
+                ErrorCode::InvalidRequest,
+                "pll_penalty must be between 0 and 10",
+            ));
+        }
+        if let TimeWeightCurve::Exponential { half_life_hours } = self.time_weight {
+            if !(half_life_hours.is_finite() && half_life_hours > 0.0) {
+                return Err(coded(
+                    ErrorCode::InvalidRequest,
+                    "half_life_hours must be a positive number of hours",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `POST /admin/backtest`; unset parameters keep their current value.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BacktestRequest {
+    pub clip_epsilon: Option<f64>,
+    pub pll_penalty: Option<f64>,
+    pub time_weight: Option<TimeWeightCurve>,
+    pub min_resolved: Option<i64>,
+    pub limit: Option<usize>,
+}
+
+impl BacktestRequest {
+    pub fn candidate(&self) -> Result<ScoringParams> {
+        let current = ScoringParams::current();
+        let params = ScoringParams {
+            clip_epsilon: self.clip_epsilon.unwrap_or(current.clip_epsilon),
+            pll_penalty: self.pll_penalty.unwrap_or(current.pll_penalty),
+            time_weight: self.time_weight.unwrap_or(current.time_weight),
+        };
+        params.validate()?;
+        Ok(params)
+    }
+}
+
+/// One resolved binary prediction as the replay sees it.
+#[derive(Debug, Clone)]
+pub struct ReplayPrediction {
+    pub user_id: i32,
+    pub event_id: i32,
+    pub outcome: bool,
+    /// Oldest first.
+    pub revisions: Vec<(DateTime<Utc>, f64)>,
+    pub resolved_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LeaderboardEntry {
+    pub rank: usize,
+    pub user_id: i32,
+    pub username: Option<String>,
+    /// Mean penalized score; higher is better, 0 is perfect.
+    pub score: f64,
+    pub mean_log_score: f64,
+    pub predictions: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Leaderboard {
+    pub params: ScoringParams,
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RankShift {
+    pub user_id: i32,
+    pub username: Option<String>,
+    pub current_rank: usize,
+    pub candidate_rank: usize,
+    /// Places gained under the candidate; negative when the user drops.
+    pub shift: i64,
+    pub current_score: f64,
+    pub candidate_score: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BacktestSummary {
+    pub predictions_replayed: usize,
+    pub events: usize,
+    pub users_ranked: usize,
+    pub users_moved: usize,
+    pub mean_abs_shift: f64,
+    pub max_abs_shift: i64,
+    /// Correlation of the two rankings; 1 when nobody moves.
+    pub rank_correlation: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BacktestReport {
+    pub min_resolved: i64,
+    pub current: Leaderboard,
+    pub candidate: Leaderboard,
+    /// Largest moves first, up to the leaderboard limit.
+    pub rank_shifts: Vec<RankShift>,
+    pub summary: BacktestSummary,
+}
+
+/// Replays every resolved prediction under the current and the requested
+/// parameters and compares the rankings.
+pub async fn run(pool: &PgPool, request: &BacktestRequest) -> Result<BacktestReport> {
+    let candidate = request.candidate()?;
+    let min_resolved = request.min_resolved.unwrap_or(MIN_RANKED_PREDICTIONS);
+    if min_resolved < 1 {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "min_resolved must be at least 1",
+        ));
+    }
+    let limit = request.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let (predictions, usernames) = load_history(pool).await?;
+    let current = ScoringParams::current();
+    let current_scores = rank(&score_users(&predictions, &current), min_resolved);
+    let candidate_scores = rank(&score_users(&predictions, &candidate), min_resolved);
+    let name = |user_id: i32| usernames.get(&user_id).cloned();
+
+    let mut shifts = rank_shifts(&current_scores, &candidate_scores);
+    let summary = BacktestSummary {
+        predictions_replayed: predictions.len(),
+        events: predictions
+            .iter()
+            .map(|p| p.event_id)
+            .collect::<BTreeSet<_>>()
+            .len(),
+        users_ranked: current_scores.len(),
+        users_moved: shifts.iter().filter(|s| s.shift != 0).count(),
+        mean_abs_shift: if shifts.is_empty() {
+            0.0
+        } else {
+            shifts.iter().map(|s| s.shift.abs() as f64).sum::<f64>() / shifts.len() as f64
+        },
+        max_abs_shift: shifts.iter().map(|s| s.shift.abs()).max().unwrap_or(0),
+        rank_correlation: rank_correlation(&shifts),
+    };
+    shifts.sort_by(|a, b| {
+        b.shift
+            .abs()
+            .cmp(&a.shift.abs())
+            .then(a.current_rank.cmp(&b.current_rank))
+    });
+    shifts.truncate(limit);
+    for shift in &mut shifts {
+        shift.username = name(shift.user_id);
+    }
+    let board = |params: ScoringParams, ranked: &[LeaderboardEntry]| Leaderboard {
+        params,
+        entries: ranked
+            .iter()
+            .take(limit)
+            .map(|entry| LeaderboardEntry {
+                username: name(entry.user_id),
+                ..entry.clone()
+            })
+            .collect(),
+    };
+
+    Ok(BacktestReport {
+        min_resolved,
+        current: board(current, &current_scores),
+        candidate: board(candidate, &candidate_scores),
+        rank_shifts: shifts,
+        summary,
+    })
+}
+
+/// Every scored binary prediction with its probability history, from one
+/// read-only snapshot, plus the usernames of those who made them.
+async fn load_history(pool: &PgPool) -> Result<(Vec<ReplayPrediction>, HashMap<i32, String>)> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(&format!(
+        "{}, READ ONLY",
+        tx_backend().isolation_sql(Isolation::RepeatableRead)
+    ))
+    .execute(&mut *tx)
+    .await?;
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT p.id, p.user_id, p.event_id, u.username, won.yes AS outcome,
+               prob.probability, p.created_at::timestamptz AS created_at,
+               COALESCE(e.resolved_at::timestamptz, p.created_at::timestamptz) AS resolved_at
+        {}
+        JOIN users u ON u.id = p.user_id
+        WHERE p.outcome IN ('correct', 'incorrect')
+          AND won.yes IS NOT NULL AND prob.probability IS NOT NULL
+        ORDER BY p.id
+        "#,
+        SCORING_JOINS
+    ))
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut revisions: BTreeMap<i32, Vec<(DateTime<Utc>, f64)>> = BTreeMap::new();
+    for table in ["forecast_revisions", "forecast_revisions_archive"] {
+        let exists: bool = sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL")
+            .bind(table)
+            .fetch_one(&mut *tx)
+            .await?;
+        if !exists {
+            continue;
+        }
+        let history = sqlx::query_as::<_, (i32, DateTime<Utc>, f64)>(&format!(
+            "SELECT prediction_id, created_at, probability FROM {} ORDER BY created_at, id",
+            table
+        ))
+        .fetch_all(&mut *tx)
+        .await?;
+        for (prediction_id, at, probability) in history {
+            revisions
+                .entry(prediction_id)
+                .or_default()
+                .push((at, probability));
+        }
+    }
+    tx.commit().await?;
+
+    let mut usernames = HashMap::new();
+    let predictions = rows
+        .iter()
+        .map(|row| {
+            let user_id: i32 = row.get("user_id");
+            if let Some(username) = row.get::<Option<String>, _>("username") {
+                usernames.entry(user_id).or_insert(username);
+            }
+            let mut history = revisions
+                .remove(&row.get::<i32, _>("id"))
+                .unwrap_or_else(|| vec![(row.get("created_at"), row.get("probability"))]);
+            history.sort_by_key(|(at, _)| *at);
+            ReplayPrediction {
+                user_id,
+                event_id: row.get("event_id"),
+                outcome: row.get("outcome"),
+                revisions: history,
+                resolved_at: row.get("resolved_at"),
+            }
+        })
+        .collect();
+    Ok((predictions, usernames))
+}
+
+/// ln of the probability assigned to what happened, floored at `epsilon`.
+pub fn clipped_log_score(probability: f64, outcome: bool, epsilon: f64) -> f64 {
+    let p = probability.clamp(0.0, 1.0);
+    let assigned = if outcome { p } else { 1.0 - p };
+    assigned.max(epsilon).ln()
+}
+
+/// The slices' weights under `curve`, summing to 1 like the uniform ones.
+pub fn curve_weights(slices: &[ScoreSlice], curve: TimeWeightCurve) -> Vec<f64> {
+    let (Some(first), Some(last)) = (slices.first(), slices.last()) else {
+        return Vec::new();
+    };
+    let span_ms = (last.slice_end - first.slice_start).num_milliseconds() as f64;
+    if span_ms <= 0.0 || curve == TimeWeightCurve::Uniform {
+        return slices.iter().map(|s| s.time_weight).collect();
+    }
+    // Position of an instant in the span, 0 at the first forecast, 1 at the end.
+    let at = |t: DateTime<Utc>| (t - first.slice_start).num_milliseconds() as f64 / span_ms;
+    slices
+        .iter()
+        .map(|s| {
+            let (from, to) = (at(s.slice_start), at(s.slice_end));
+            match curve {
+                TimeWeightCurve::Uniform => s.time_weight,
+                TimeWeightCurve::Linear => to * to - from * from,
+                TimeWeightCurve::Exponential { half_life_hours } => {
+                    let span_halvings = span_ms / 3_600_000.0 / half_life_hours;
+                    let mass = |u: f64| (-(1.0 - u) * span_halvings * std::f64::consts::LN_2).exp();
+                    (mass(to) - mass(from)) / (1.0 - mass(0.0))
+                }
+            }
+        })
+        .collect()
+}
+
+/// One prediction's time-weighted log score under `params`, before the
+/// peer penalty.
+fn log_score_under(prediction: &ReplayPrediction, params: &ScoringParams) -> Option<f64> {
+    let slices = score_slices(
+        &prediction.revisions,
+        prediction.resolved_at,
+        Some(prediction.outcome),
+    );
+    if slices.is_empty() {
+        return None;
+    }
+    let weights = curve_weights(&slices, params.time_weight);
+    Some(
+        slices
+            .iter()
+            .zip(weights)
+            .map(|(s, w)| {
+                w * clipped_log_score(s.probability, prediction.outcome, params.clip_epsilon)
+            })
+            .sum(),
+    )
+}
+
+/// Each user's mean score and mean log score over their predictions.
+pub fn score_users(
+    predictions: &[ReplayPrediction],
+    params: &ScoringParams,
+) -> BTreeMap<i32, (f64, f64, usize)> {
+    let scored: Vec<(i32, i32, f64)> = predictions
+        .iter()
+        .filter_map(|p| Some((p.user_id, p.event_id, log_score_under(p, params)?)))
+        .collect();
+    let mut crowds: HashMap<i32, (f64, usize)> = HashMap::new();
+    for &(_, event_id, log) in &scored {
+        let crowd = crowds.entry(event_id).or_default();
+        crowd.0 += log;
+        crowd.1 += 1;
+    }
+
+    let mut users: BTreeMap<i32, (f64, f64, usize)> = BTreeMap::new();
+    for (user_id, event_id, log) in scored {
+        let (sum, count) = crowds[&event_id];
+        let peer_loss = (sum / count as f64 - log).max(0.0);
+        let user = users.entry(user_id).or_default();
+        user.0 += log - params.pll_penalty * peer_loss;
+        user.1 += log;
+        user.2 += 1;
+    }
+    for user in users.values_mut() {
+        user.0 /= user.2 as f64;
+        user.1 /= user.2 as f64;
+    }
+    users
+}
+
+/// Best score first; tied users share the better rank.
+pub fn rank(scores: &BTreeMap<i32, (f64, f64, usize)>, min_resolved: i64) -> Vec<LeaderboardEntry> {
+    let mut entries: Vec<LeaderboardEntry> = scores
+        .iter()
+        .filter(|(_, (_, _, n))| *n as i64 >= min_resolved)
+        .map(
+            |(&user_id, &(score, mean_log_score, predictions))| LeaderboardEntry {
+                rank: 0,
+                user_id,
+                username: None,
+                score,
+                mean_log_score,
+                predictions,
+            },
+        )
+        .collect();
+    entries.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.user_id.cmp(&b.user_id)));
+    for i in 0..entries.len() {
+        entries[i].rank = if i > 0 && entries[i].score == entries[i - 1].score {
+            entries[i - 1].rank
+        } else {
+            i + 1
+        };
+    }
+    entries
+}
+
+/// Every ranked user's move from the current ranking to the candidate's.
+pub fn rank_shifts(current: &[LeaderboardEntry], candidate: &[LeaderboardEntry]) -> Vec<RankShift> {
+    let candidate: HashMap<i32, &LeaderboardEntry> =
+        candidate.iter().map(|e| (e.user_id, e)).collect();
+    current
+        .iter()
+        .filter_map(|now| {
+            let then = candidate.get(&now.user_id)?;
+            Some(RankShift {
+                user_id: now.user_id,
+                username: None,
+                current_rank: now.rank,
+                candidate_rank: then.rank,
+                shift: now.rank as i64 - then.rank as i64,
+                current_score: now.score,
+                candidate_score: then.score,
+            })
+        })
+        .collect()
+}
+
+/// Pearson correlation of the two ranks (Spearman's rho, ties included).
+fn rank_correlation(shifts: &[RankShift]) -> Option<f64> {
+    if shifts.len() < 2 {
+        return None;
+    }
+    let n = shifts.len() as f64;
+    let mean =
+        |rank: fn(&RankShift) -> usize| shifts.iter().map(|s| rank(s) as f64).sum::<f64>() / n;
+    let (mx, my) = (mean(|s| s.current_rank), mean(|s| s.candidate_rank));
+    let (mut sxy, mut sxx, mut syy) = (0.0, 0.0, 0.0);
+    for s in shifts {
+        let (dx, dy) = (s.current_rank as f64 - mx, s.candidate_rank as f64 - my);
+        sxy += dx * dy;
+        sxx += dx * dx;
+        syy += dy * dy;
+    }
+    if sxx == 0.0 || syy == 0.0 {
+        return None;
+    }
+    Some(sxy / (sxx * syy).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hours: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_700_000_000 + hours * 3600, 0).unwrap()
+    }
+
+    fn prediction(user_id: i32, event_id: i32, revisions: &[(i64, f64)]) -> ReplayPrediction {
+        ReplayPrediction {
+            user_id,
+            event_id,
+            outcome: true,
+            revisions: revisions.iter().map(|&(h, p)| (at(h), p)).collect(),
+            resolved_at: at(10),
+        }
+    }
+
+    #[test]
+    fn current_params_score_like_production() {
+        let p = prediction(1, 1, &[(0, 0.2), (5, 0.9)]);
+        let expected = 0.5 * intellacc_math::scoring::log_score(0.2, true)
+            + 0.5 * intellacc_math::scoring::log_score(0.9, true);
+        let scores = score_users(&[p], &ScoringParams::current());
+        assert!((scores[&1].0 - expected).abs() < 1e-12);
+        assert_eq!(scores[&1].0, scores[&1].1);
+        assert_eq!(
+            clipped_log_score(0.0, true, LOG_SCORE_FLOOR),
+            LOG_SCORE_FLOOR.ln()
+        );
+        assert_eq!(clipped_log_score(0.0, true, 0.01), 0.01_f64.ln());
+    }
+
+    #[test]
+    fn curves_favour_later_slices_and_sum_to_one() {
+        let p = prediction(1, 1, &[(0, 0.2), (5, 0.9)]);
+        let slices = score_slices(&p.revisions, p.resolved_at, Some(true));
+        assert_eq!(curve_weights(&slices, TimeWeightCurve::Uniform), [0.5, 0.5]);
+        let linear = curve_weights(&slices, TimeWeightCurve::Linear);
+        assert!((linear[0] - 0.25).abs() < 1e-12 && (linear[1] - 0.75).abs() < 1e-12);
+        let exp = curve_weights(
+            &slices,
+            TimeWeightCurve::Exponential {
+                half_life_hours: 5.0,
+            },
+        );
+        // Half the span's mass decays per half-life: 1/3 before, 2/3 after.
+        assert!((exp[0] - 1.0 / 3.0).abs() < 1e-12 && (exp[1] - 2.0 / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn peer_penalty_reorders_and_shifts_are_reported() {
+        // User 1 is steady; user 2 is great on one event and poor on another.
+        let history = vec![
+            prediction(1, 1, &[(0, 0.7)]),
+            prediction(1, 2, &[(0, 0.7)]),
+            prediction(2, 1, &[(0, 0.99)]),
+            prediction(2, 2, &[(0, 0.5)]),
+            prediction(3, 1, &[(0, 0.5)]),
+            prediction(3, 2, &[(0, 0.5)]),
+        ];
+        let current = rank(&score_users(&history, &ScoringParams::current()), 2);
+        let order: Vec<i32> = current.iter().map(|e| e.user_id).collect();
+        assert_eq!(order, [2, 1, 3]);
+
+        let candidate = ScoringParams {
+            pll_penalty: 1.0,
+            ..ScoringParams::current()
+        };
+        let penalized = rank(&score_users(&history, &candidate), 2);
+        let order: Vec<i32> = penalized.iter().map(|e| e.user_id).collect();
+        assert_eq!(order, [1, 2, 3]);
+
+        let shifts = rank_shifts(&current, &penalized);
+        let moved: Vec<(i32, i64)> = shifts.iter().map(|s| (s.user_id, s.shift)).collect();
+        assert_eq!(moved, [(2, -1), (1, 1), (3, 0)]);
+        assert!(rank_correlation(&shifts).unwrap() < 1.0);
+        assert!(rank(&score_users(&history, &candidate), 3).is_empty());
+    }
+
+    #[test]
+    fn rejects_out_of_range_params() {
+        let bad = [
+            BacktestRequest {
+                clip_epsilon: Some(0.0),
+                ..Default::default()
+            },
+            BacktestRequest {
+                clip_epsilon: Some(0.5),
+                ..Default::default()
+            },
+            BacktestRequest {
+                pll_penalty: Some(-1.0),
+                ..Default::default()
+            },
+            BacktestRequest {
+                time_weight: Some(TimeWeightCurve::Exponential {
+                    half_life_hours: 0.0,
+                }),
+                ..Default::default()
+            },
+        ];
+        for request in bad {
+            assert!(request.candidate().is_err(), "{:?}", request);
+        }
+        assert_eq!(
+            BacktestRequest::default().candidate().unwrap(),
+            ScoringParams::current()
+        );
+    }
+}
@@ -0,0 +1,77 @@
+//! Binary entry point for the HTTP load test
+//! Run with: cargo run --bin load_test -- --url http://localhost:3001 [flags] [--report out.json]
+
+use anyhow::{anyhow, Result};
+use prediction_engine::load_test::{self, LoadTestConfig};
+use std::path::PathBuf;
+
+const USAGE: &str = "Usage: load_test [--url URL] [--token TOKEN] [--report FILE.json]
+  [--rps N] [--duration-secs S] [--max-in-flight N] [--timeout-ms MS]
+  [--mix market=30,kelly=15,events=10,trades=10,buy=25,sell=10]
+  [--users 1-100] [--events 1-50,75] [--ws-clients N] [--seed N]
+
+Drives a running engine over HTTP at a fixed request rate. LOAD_TEST_* env
+vars (URL, TOKEN, RPS, DURATION_SECS, MIX, USERS, EVENTS, WS_CLIENTS) set
+the defaults; flags override them. The token falls back to
+PREDICTION_ENGINE_AUTH_TOKEN. Trades act as the --users ids, which must
+exist; without --events, open binary events are taken from GET /events.
+The JSON report (per-endpoint throughput, latency and error breakdown plus
+WebSocket delivery counts) goes to --report, or stdout.";
+
+/// Config (env, then flag overrides) plus report destination.
+fn parse_args(args: &[String]) -> Result<(LoadTestConfig, Option<PathBuf>)> {
+    let mut config = LoadTestConfig::from_env()?;
+    let mut report_path = None;
+
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        if flag == "--help" || flag == "-h" {
+            println!("{}", USAGE);
+            std::process::exit(0);
+        }
+        let value = iter
+            .next()
+            .ok_or_else(|| anyhow!("missing value for {}\n\n{}", flag, USAGE))?;
+        match flag.as_str() {
+            "--report" => report_path = Some(PathBuf::from(value)),
+            _ => config
+                .apply_flag(flag, value)
+                .map_err(|e| anyhow!("{}\n\n{}", e, USAGE))?,
+        }
+    }
+    config.validate()?;
+    Ok((config, report_path))
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (config, report_path) = parse_args(&args)?;
+
+    tracing_subscriber::fmt()
+        .with_env_filter("info,prediction_engine=debug")
+        .init();
+
+    println!("🚀 LMSR Prediction Engine HTTP Load Test");
+    println!("========================================\n");
+    println!("Target: {}", config.base_url);
+
+    let report = load_test::run_load_test(&config).await?;
+    let report_json = serde_json::to_string_pretty(&report)?;
+    match report_path {
+        Some(path) => {
+            std::fs::write(&path, report_json)?;
+            println!("\n📄 Report written to {}", path.display());
+        }
+        None => println!("\n{}", report_json),
+    }
+
+    if report.ok == 0 {
+        return Err(anyhow!("no request succeeded; is the engine running?"));
+    }
+    println!(
+        "\n✅ Load test completed: {:.1} req/s achieved, {:.2}% errors",
+        report.achieved_rps, report.error_rate_pct
+    );
+    Ok(())
+}
@@ -0,0 +1,107 @@
+//! Binary entry point for the historical market replay
+//! Run with: cargo run --bin replay -- --event ID [--variant name:b=200,fee=0.02] [--report out.json]
+
+use anyhow::{anyhow, Result};
+use prediction_engine::replay::{self, ModelVariant};
+use sqlx::postgres::PgPoolOptions;
+use std::path::PathBuf;
+
+const USAGE: &str =
+    "Usage: replay --event ID [--variant NAME[:b=B][,fee=F]]... [--report FILE.json]
+
+Rebuilds a binary market's probability path from market_updates against
+DATABASE_URL and checks every journaled trade against the stored price and
+shares. Each --variant re-runs the same trades with a different liquidity b
+and/or a fee taken from every stake, reporting its price path, fees, shares
+issued and (for resolved events) the market maker's profit or subsidy.
+Binary sells are not journaled; they show up as gaps, where the replay
+re-anchors to the stored price. The JSON report goes to --report, or stdout.";
+
+struct Args {
+    event_id: i32,
+    variants: Vec<ModelVariant>,
+    report_path: Option<PathBuf>,
+}
+
+fn parse_args(args: &[String]) -> Result<Args> {
+    let mut event_id = None;
+    let mut variants = Vec::new();
+    let mut report_path = None;
+
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        if flag == "--help" || flag == "-h" {
+            println!("{}", USAGE);
+            std::process::exit(0);
+        }
+        let value = iter
+            .next()
+            .ok_or_else(|| anyhow!("missing value for {}\n\n{}", flag, USAGE))?;
+        match flag.as_str() {
+            "--event" => {
+                event_id = Some(
+                    value
+                        .parse::<i32>()
+                        .map_err(|_| anyhow!("invalid --event '{}'", value))?,
+                )
+            }
+            "--variant" => variants.push(ModelVariant::parse(value)?),
+            "--report" => report_path = Some(PathBuf::from(value)),
+            _ => return Err(anyhow!("unknown flag {}\n\n{}", flag, USAGE)),
+        }
+    }
+
+    Ok(Args {
+        event_id: event_id.ok_or_else(|| anyhow!("--event is required\n\n{}", USAGE))?,
+        variants,
+        report_path,
+    })
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args = parse_args(&args)?;
+
+    tracing_subscriber::fmt()
+        .with_env_filter("info,prediction_engine=debug")
+        .init();
+
+    println!("🔁 LMSR Market Replay");
+    println!("=====================\n");
+
+    let database_url = std::env::var("DATABASE_URL").unwrap_or_else(|_| {
+        "postgres://intellacc_user:supersecretpassword@db:5432/intellaccdb".to_string()
+    });
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await?;
+
+    let report = replay::replay_event(&pool, args.event_id, &args.variants).await?;
+    let report_json = serde_json::to_string_pretty(&report)?;
+    match args.report_path {
+        Some(path) => {
+            std::fs::write(&path, report_json)?;
+            println!("📄 Report written to {}", path.display());
+        }
+        None => println!("{}", report_json),
+    }
+
+    println!(
+        "\nEvent {}: {} trades, {} gaps, stored final prob {:.6}, replayed {:.6}",
+        report.event_id,
+        report.trades,
+        report.gaps,
+        report.stored_final_prob,
+        report.replayed_final_prob
+    );
+    if !report.ok() {
+        return Err(anyhow!(
+            "{} replayed step(s) disagree with the journal",
+            report.mismatches.len()
+        ));
+    }
+    println!("✅ Replay matches the stored market path");
+    Ok(())
+}
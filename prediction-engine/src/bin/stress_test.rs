@@ -1,15 +1,76 @@
 //! Binary entry point for running stress tests
-//! Run with: cargo run --bin stress_test
+//! Run with: cargo run --bin stress_test -- [--scenario file.toml] [flags] [--report out.json]
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use prediction_engine::config::Config;
-use prediction_engine::stress;
+use prediction_engine::db_adapter;
+use prediction_engine::stress::{self, soak, storm, StressScenario};
 use sqlx::postgres::PgPoolOptions;
+use std::path::PathBuf;
 use std::time::Duration;
 use tracing_subscriber;
 
+const USAGE: &str = "Usage: stress_test [--scenario FILE.toml] [--report FILE.json]
+  [--name NAME] [--users N] [--events N] [--trades-per-user N]
+  [--liquidity B] [--batch-size N] [--sell-ratio P] [--min-sell-shares X]
+  [--skill uniform[:min,max]|normal:mean,sd|bimodal:low,high,frac|fixed:v]
+  [--duration-secs S] [--seed N]
+  [--chaos-delay-rate P] [--chaos-max-delay-ms MS]
+  [--chaos-kill-rate P] [--chaos-serialization-rate P]
+  [--soak-hours H] [--soak-rate TPS] [--soak-sample-secs S]
+  [--storm-events N] [--storm-window-secs S] [--storm-predictions N]
+
+Without --scenario the STRESS_* env vars (or built-in defaults) are used;
+flags override either. The JSON report goes to --report, or stdout; it
+records the seed, so `--seed N` replays a run's random inputs exactly.
+The --chaos-* rates inject delays, connection kills and serialization
+failures into trade transactions (all 0 by default). Any --soak-* flag (or
+a [soak] table / STRESS_SOAK_HOURS) runs a steady-rate soak instead, failing
+if memory, cache size, pool waits or broadcast backlog keep growing. Any
+--storm-* flag (or a [storm] table / STRESS_STORM_EVENTS) runs a resolution
+storm: N events resolved inside the window while other markets keep trading,
+failing if scoring lags, the broadcast channel overflows or trading slows.";
+
+/// Scenario (file or env, then flag overrides) plus report destination.
+fn parse_args(args: &[String]) -> Result<(StressScenario, Option<PathBuf>)> {
+    let mut scenario_path = None;
+    let mut report_path = None;
+    let mut overrides = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        if flag == "--help" || flag == "-h" {
+            println!("{}", USAGE);
+            std::process::exit(0);
+        }
+        let value = iter
+            .next()
+            .ok_or_else(|| anyhow!("missing value for {}\n\n{}", flag, USAGE))?;
+        match flag.as_str() {
+            "--scenario" => scenario_path = Some(PathBuf::from(value)),
+            "--report" => report_path = Some(PathBuf::from(value)),
+            _ => overrides.push((flag.as_str(), value.as_str())),
+        }
+    }
+
+    let mut scenario = match scenario_path {
+        Some(path) => StressScenario::from_toml_file(&path)?,
+        None => StressScenario::from_env(),
+    };
+    for (flag, value) in overrides {
+        scenario
+            .apply_flag(flag, value)
+            .map_err(|e| anyhow!("{}\n\n{}", e, USAGE))?;
+    }
+    scenario.validate()?;
+    Ok((scenario, report_path))
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (scenario, report_path) = parse_args(&args)?;
+
     // Initialize logging
     tracing_subscriber::fmt()
         .with_env_filter("info,prediction_engine=debug")
@@ -42,15 +103,67 @@ async fn main() -> Result<()> {
         .acquire_timeout(Duration::from_secs(acquire_timeout_secs))
         .connect(&database_url)
         .await?;
+    let backend = db_adapter::configure_tx_backend(&pool, &config.database.dialect).await?;
+    println!("Database dialect: {:?}", backend.dialect());
 
     // Setup test database schema
     println!("\nSetting up test database schema...");
     stress::setup_test_database(&pool).await?;
 
+    if scenario.soak.is_some() {
+        println!("\nStarting soak scenario '{}'...", scenario.name);
+        let report = soak::run_soak(&pool, &config, &scenario).await?;
+        write_report(&serde_json::to_string_pretty(&report)?, report_path)?;
+        if !report.ok() {
+            return Err(anyhow!(
+                "{} soak check(s) failed (replay with --seed {})",
+                report.failures.len(),
+                report.seed
+            ));
+        }
+        println!("\n✅ Soak test completed successfully!");
+        return Ok(());
+    }
+
+    if scenario.storm.is_some() {
+        println!("\nStarting resolution storm '{}'...", scenario.name);
+        let report = storm::run_storm(&pool, &config, &scenario).await?;
+        write_report(&serde_json::to_string_pretty(&report)?, report_path)?;
+        if !report.ok() {
+            return Err(anyhow!(
+                "{} storm check(s) failed (replay with --seed {})",
+                report.failures.len(),
+                report.seed
+            ));
+        }
+        println!("\n✅ Resolution storm completed successfully!");
+        return Ok(());
+    }
+
     // Run the stress test
-    println!("\nStarting stress test...");
-    stress::run_stress_test(&pool, &config).await?;
+    println!("\nStarting stress test scenario '{}'...", scenario.name);
+    let report = stress::run_scenario(&pool, &config, &scenario).await?;
+    write_report(&serde_json::to_string_pretty(&report)?, report_path)?;
 
+    if !report.invariants_ok() {
+        return Err(anyhow!(
+            "{} invariant check(s) failed (replay with --seed {})",
+            report.invariant_failures.len(),
+            report.seed
+        ));
+    }
     println!("\n✅ Stress test completed successfully!");
     Ok(())
 }
+
+/// Writes the JSON report to `path`, or stdout when no path was given.
+fn write_report(report_json: &str, path: Option<PathBuf>) -> Result<()> {
+    match path {
+        Some(path) => {
+            std::fs::write(&path, report_json)?;
+            println!("\n📄 Report written to {}", path.display());
+        }
+        None => println!("\n{}", report_json),
+    }
+    Ok(())
+}
@@ -0,0 +1,276 @@
+//! Cache warming.
+//!
+//! Loads chosen entries ahead of traffic so the first reads after a start or
+//! deploy don't each pay for the full query: once at startup, before the
+//! listener binds, and on demand through `POST /admin/cache/warm`. Targets
+//! are `markets`, `leaderboards`, `dashboards` or one `market:<id>`,
+//! `leaderboard:<id>`, `dashboard:<id>`; see [`WarmTarget`]. One target
+//! failing doesn't stop the others.
+
+use anyhow::{anyhow, Result};
+use moka::future::Cache;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::time::Instant;
+
+use crate::competitions;
+use crate::dashboard;
+use crate::market_cache::MarketStateCache;
+use crate::score_quota::QuotaPolicy;
+
+/// What to load: the open markets with the most staked into the state cache,
+/// every running competition's leaderboard, or the top-ranked users'
+/// dashboards (each up to a limit), or one entry by id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarmTarget {
+    Markets,
+    Leaderboards,
+    Dashboards,
+    Market(i32),
+    Leaderboard(i32),
+    Dashboard(i32),
+}
+
+impl WarmTarget {
+    pub fn parse(raw: &str) -> Result<Self> {
+        let raw = raw.trim();
+        let target = match raw.split_once(':') {
+            None => match raw {
+                "markets" => Self::Markets,
+                "leaderboards" => Self::Leaderboards,
+                "dashboards" => Self::Dashboards,
+                _ => return Err(anyhow!("unknown cache warm target {:?}", raw)),
+            },
+            Some((kind, id)) => {
+                let id = id
+                    .trim()
+                    .parse::<i32>()
+                    .ok()
+                    .filter(|id| *id > 0)
+                    .ok_or_else(|| anyhow!("cache warm target {:?} must name a positive id", raw))?;
+                match kind.trim() {
+                    "market" => Self::Market(id),
+                    "leaderboard" => Self::Leaderboard(id),
+                    "dashboard" => Self::Dashboard(id),
+                    _ => return Err(anyhow!("unknown cache warm target {:?}", raw)),
+                }
+            }
+        };
+        Ok(target)
+    }
+
+    /// Parses a comma-separated list, dropping duplicates but keeping the
+    /// order. Fails naming every part that isn't a target.
+    pub fn parse_list(raw: &str) -> Result<Vec<Self>> {
+        let mut targets = Vec::new();
+        let mut invalid = Vec::new();
+        for part in raw.split(',').filter(|part| !part.trim().is_empty()) {
+            match Self::parse(part) {
+                Ok(target) if !targets.contains(&target) => targets.push(target),
+                Ok(_) => {}
+                Err(_) => invalid.push(format!("{:?}", part.trim())),
+            }
+        }
+        if !invalid.is_empty() {
+            return Err(anyhow!(
+                "invalid cache warm targets: {}",
+                invalid.join(", ")
+            ));
+        }
+        Ok(targets)
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::Markets => "markets".to_string(),
+            Self::Leaderboards => "leaderboards".to_string(),
+            Self::Dashboards => "dashboards".to_string(),
+            Self::Market(id) => format!("market:{}", id),
+            Self::Leaderboard(id) => format!("leaderboard:{}", id),
+            Self::Dashboard(id) => format!("dashboard:{}", id),
+        }
+    }
+}
+
+/// Where warmed entries go and how many of each group to load.
+pub struct WarmCaches<'a> {
+    pub responses: &'a Cache<String, String>,
+    pub markets: &'a MarketStateCache,
+    pub market_limit: i64,
+    pub dashboard_limit: i64,
+    /// The scoring policy dashboards are computed under.
+    pub score_quota: QuotaPolicy,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetReport {
+    pub target: String,
+    pub entries: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WarmReport {
+    pub targets: Vec<TargetReport>,
+    pub entries: usize,
+    pub failed: usize,
+    pub duration_ms: u64,
+}
+
+/// Loads each target in turn; the state cache reads from `db`, the
+/// response cache's reports from `analytics`, as their endpoints do.
+pub async fn warm(
+    db: &PgPool,
+    analytics: &PgPool,
+    caches: &WarmCaches<'_>,
+    targets: &[WarmTarget],
+) -> WarmReport {
+    let started = Instant::now();
+    let mut reports = Vec::with_capacity(targets.len());
+    for target in targets {
+        let (entries, error) = match warm_target(db, analytics, caches, *target).await {
+            Ok(entries) => (entries, None),
+            Err((entries, e)) => (entries, Some(e.to_string())),
+        };
+        reports.push(TargetReport {
+            target: target.name(),
+            entries,
+            error,
+        });
+    }
+    WarmReport {
+        entries: reports.iter().map(|r| r.entries).sum(),
+        failed: reports.iter().filter(|r| r.error.is_some()).count(),
+        targets: reports,
+        duration_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+/// Entries loaded, and on failure how many had been loaded before it.
+async fn warm_target(
+    db: &PgPool,
+    analytics: &PgPool,
+    caches: &WarmCaches<'_>,
+    target: WarmTarget,
+) -> std::result::Result<usize, (usize, anyhow::Error)> {
+    let ids = match target {
+        WarmTarget::Markets if !caches.markets.is_enabled() => Ok(Vec::new()),
+        WarmTarget::Markets => busiest_open_markets(db, caches.market_limit).await,
+        WarmTarget::Leaderboards => running_competitions(analytics).await,
+        WarmTarget::Dashboards => top_ranked_users(analytics, caches.dashboard_limit).await,
+        WarmTarget::Market(id) | WarmTarget::Leaderboard(id) | WarmTarget::Dashboard(id) => {
+            Ok(vec![id])
+        }
+    }
+    .map_err(|e| (0, e))?;
+
+    // Responses go in under the keys their endpoints read, so they live until
+    // the next trade or score update clears them, as if a client had asked.
+    let mut warmed = 0;
+    for id in ids {
+        let loaded = match target {
+            WarmTarget::Markets | WarmTarget::Market(_) => {
+                caches.markets.write_through(db, id).await
+            }
+            WarmTarget::Leaderboards | WarmTarget::Leaderboard(_) => {
+                match competitions::get_leaderboard(analytics, id).await {
+                    Ok(leaderboard) => {
+                        let key = competitions::leaderboard_cache_key(id);
+                        caches.responses.insert(key, leaderboard.to_string()).await;
+                        Ok(())
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            WarmTarget::Dashboards | WarmTarget::Dashboard(_) => {
+                match dashboard::get_dashboard(analytics, &caches.score_quota, id).await {
+                    Ok(dashboard) => {
+                        let key = dashboard::cache_key(id);
+                        caches.responses.insert(key, dashboard.to_string()).await;
+                        Ok(())
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+        };
+        loaded.map_err(|e| (warmed, e))?;
+        warmed += 1;
+    }
+    Ok(warmed)
+}
+
+async fn busiest_open_markets(pool: &PgPool, limit: i64) -> Result<Vec<i32>> {
+    Ok(sqlx::query_scalar(
+        "SELECT id FROM events
+         WHERE outcome IS NULL AND closed_at IS NULL AND hidden_at IS NULL
+         ORDER BY cumulative_stake DESC NULLS LAST, id
+         LIMIT $1",
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?)
+}
+
+async fn running_competitions(pool: &PgPool) -> Result<Vec<i32>> {
+    Ok(
+        sqlx::query_scalar("SELECT id FROM competitions WHERE ends_at > NOW() ORDER BY id")
+            .fetch_all(pool)
+            .await?,
+    )
+}
+
+/// The top of the reputation leaderboard, ranked as the dashboard ranks it.
+async fn top_ranked_users(pool: &PgPool, limit: i64) -> Result<Vec<i32>> {
+    Ok(sqlx::query_scalar(
+        r#"
+        SELECT u.id
+        FROM users u
+        LEFT JOIN (
+            SELECT user_id, COUNT(*) AS predictions FROM predictions GROUP BY user_id
+        ) p ON p.user_id = u.id
+        ORDER BY COALESCE(u.rp_balance_ledger, 0) + COALESCE(u.rp_staked_ledger, 0) DESC,
+                 COALESCE(p.predictions, 0) DESC,
+                 u.id ASC
+        LIMIT $1
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_groups_and_single_keys() {
+        let targets =
+            WarmTarget::parse_list(" markets, leaderboard:3,dashboard:7 ,markets,,dashboards")
+                .unwrap();
+        assert_eq!(
+            targets,
+            vec![
+                WarmTarget::Markets,
+                WarmTarget::Leaderboard(3),
+                WarmTarget::Dashboard(7),
+                WarmTarget::Dashboards,
+            ]
+        );
+        assert_eq!(targets[1].name(), "leaderboard:3");
+        assert!(WarmTarget::parse_list("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_unknown_targets_and_bad_ids() {
+        for raw in ["users", "market:", "market:0", "market:x", "events:4", "leaderboards:2"] {
+            assert!(WarmTarget::parse(raw).is_err(), "{}", raw);
+        }
+        let err = WarmTarget::parse_list("markets,users,dashboard:7,market:0").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"invalid cache warm targets: "users", "market:0""#
+        );
+    }
+}
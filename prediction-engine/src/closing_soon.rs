@@ -0,0 +1,235 @@
+//! Reminders for markets about to close.
+//!
+//! `GET /events/closing-soon` lists open markets whose closing_date falls
+//! inside a window, optionally only those a user holds a position in. The
+//! reminder sweep broadcasts `markets_closing_soon` once per market when it
+//! comes within the configured number of hours of closing; each market
+//! lists its holders so clients can tell their own users, except on
+//! anonymous markets, where holders are left out as they are from trades.
+//! `closing_reminders` records what has been sent, so a restart or a second
+//! engine doesn't repeat a reminder.
+
+use crate::api_error::{coded, ErrorCode};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+
+pub const MAX_WITHIN_HOURS: i64 = 30 * 24;
+pub const MAX_LIMIT: i64 = 500;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClosingMarket {
+    pub event_id: i32,
+    pub title: String,
+    pub category: Option<String>,
+    pub event_type: String,
+    pub closing_date: DateTime<Utc>,
+    pub hours_left: f64,
+    pub market_prob: Option<f64>,
+    pub holder_count: i64,
+    /// Users with a position; only set on reminders for non-anonymous markets.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub holders: Option<Vec<i32>>,
+}
+
+pub async fn ensure_reminder_table(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS closing_reminders (
+            event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+            hours_before DOUBLE PRECISION NOT NULL,
+            closing_date TIMESTAMPTZ NOT NULL,
+            sent_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            PRIMARY KEY (event_id, hours_before, closing_date)
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Parses a window such as `48h`, `90m` or `2d`; a bare number is hours.
+pub fn parse_within(raw: &str) -> Result<Duration> {
+    let raw = raw.trim().to_ascii_lowercase();
+    let (amount, minutes_per_unit) = match raw.char_indices().last() {
+        Some((i, 'm')) => (&raw[..i], 1),
+        Some((i, 'h')) => (&raw[..i], 60),
+        Some((i, 'd')) => (&raw[..i], 24 * 60),
+        _ => (raw.as_str(), 60),
+    };
+    let window = amount
+        .trim()
+        .parse::<i64>()
+        .ok()
+        .filter(|n| *n > 0)
+        .and_then(|n| n.checked_mul(minutes_per_unit))
+        .map(Duration::minutes)
+        .ok_or_else(|| {
+            coded(
+                ErrorCode::InvalidRequest,
+                "within must be a duration such as 48h, 90m or 2d",
+            )
+        })?;
+    if window > Duration::hours(MAX_WITHIN_HOURS) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("within must be at most {}h", MAX_WITHIN_HOURS),
+        ));
+    }
+    Ok(window)
+}
+
+fn closing_market(row: &sqlx::postgres::PgRow, now: DateTime<Utc>) -> ClosingMarket {
+    let closing_date: DateTime<Utc> = row.get("closing_date");
+    ClosingMarket {
+        event_id: row.get("id"),
+        title: row.get("title"),
+        category: row.get("category"),
+        event_type: row.get("event_type"),
+        closing_date,
+        hours_left: (closing_date - now).num_seconds() as f64 / 3600.0,
+        market_prob: row.get("market_prob"),
+        holder_count: row.get("holder_count"),
+        holders: None,
+    }
+}
+
+const OPEN_MARKET_COLUMNS: &str = r#"
+    e.id, e.title, e.category, COALESCE(e.event_type, 'binary') AS event_type,
+    e.closing_date::timestamptz AS closing_date,
+    e.market_prob::float8 AS market_prob,
+    (SELECT COUNT(*) FROM (
+        SELECT us.user_id FROM user_shares us
+        WHERE us.event_id = e.id AND (us.yes_shares > 0 OR us.no_shares > 0)
+        UNION
+        SELECT uos.user_id FROM user_outcome_shares uos
+        WHERE uos.event_id = e.id AND uos.shares > 0
+    ) h) AS holder_count
+"#;
+
+/// Open markets closing within `within`, soonest first. With `user_id`,
+/// only markets that user holds a position in.
+pub async fn closing_soon(
+    pool: &PgPool,
+    within: Duration,
+    user_id: Option<i32>,
+    limit: i64,
+) -> Result<Vec<ClosingMarket>> {
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("limit must be between 1 and {}", MAX_LIMIT),
+        ));
+    }
+    if let Some(user_id) = user_id {
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
+            .bind(user_id)
+            .fetch_one(pool)
+            .await?;
+        if !exists {
+            return Err(coded(ErrorCode::NotFound, "User not found"));
+        }
+    }
+
+    let now = Utc::now();
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT {}
+        FROM events e
+        WHERE e.outcome IS NULL
+          AND e.closed_at IS NULL
+          AND e.hidden_at IS NULL
+          AND e.closing_date::timestamptz > $1
+          AND e.closing_date::timestamptz <= $2
+          AND ($3::int IS NULL
+               OR EXISTS (SELECT 1 FROM user_shares us
+                          WHERE us.event_id = e.id AND us.user_id = $3
+                            AND (us.yes_shares > 0 OR us.no_shares > 0))
+               OR EXISTS (SELECT 1 FROM user_outcome_shares uos
+                          WHERE uos.event_id = e.id AND uos.user_id = $3
+                            AND uos.shares > 0))
+        ORDER BY e.closing_date ASC, e.id
+        LIMIT $4
+        "#,
+        OPEN_MARKET_COLUMNS
+    ))
+    .bind(now)
+    .bind(now + within)
+    .bind(user_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(|row| closing_market(row, now)).collect())
+}
+
+/// Markets that have come within `hours_before` of closing and haven't had
+/// a reminder for this closing_date, marked as reminded. Holders are listed
+/// on non-anonymous markets.
+pub async fn due_reminders(pool: &PgPool, hours_before: f64) -> Result<Vec<ClosingMarket>> {
+    let now = Utc::now();
+    let rows = sqlx::query(&format!(
+        r#"
+        WITH due AS (
+            INSERT INTO closing_reminders (event_id, hours_before, closing_date)
+            SELECT e.id, $2, e.closing_date::timestamptz
+            FROM events e
+            WHERE e.outcome IS NULL
+              AND e.closed_at IS NULL
+              AND e.hidden_at IS NULL
+              AND e.closing_date::timestamptz > $1
+              AND e.closing_date::timestamptz <= $1 + $2 * INTERVAL '1 hour'
+            ON CONFLICT DO NOTHING
+            RETURNING event_id
+        )
+        SELECT {},
+               e.anonymous_trading,
+               ARRAY(
+                   SELECT us.user_id FROM user_shares us
+                   WHERE us.event_id = e.id AND (us.yes_shares > 0 OR us.no_shares > 0)
+                   UNION
+                   SELECT uos.user_id FROM user_outcome_shares uos
+                   WHERE uos.event_id = e.id AND uos.shares > 0
+                   ORDER BY 1
+               ) AS holders
+        FROM due
+        JOIN events e ON e.id = due.event_id
+        ORDER BY e.closing_date ASC, e.id
+        "#,
+        OPEN_MARKET_COLUMNS
+    ))
+    .bind(now)
+    .bind(hours_before)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let mut market = closing_market(row, now);
+            if !row.get::<bool, _>("anonymous_trading") {
+                market.holders = Some(row.get("holders"));
+            }
+            market
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_parse_with_units_and_bounds() {
+        assert_eq!(parse_within("48h").unwrap(), Duration::hours(48));
+        assert_eq!(parse_within(" 90M ").unwrap(), Duration::minutes(90));
+        assert_eq!(parse_within("2d").unwrap(), Duration::days(2));
+        assert_eq!(parse_within("12").unwrap(), Duration::hours(12));
+        assert!(parse_within("0h").is_err());
+        assert!(parse_within("-3h").is_err());
+        assert!(parse_within("h").is_err());
+        assert!(parse_within("31d").is_err());
+    }
+}
@@ -0,0 +1,398 @@
+//! Price consistency across linked events.
+//!
+//! Members of a correlated cluster (see `exposure`) should imply one
+//! probability of the cluster resolving YES, and a mutually exclusive set's
+//! YES prices shouldn't sum past 1. `assess` measures how far they are off
+//! and the arbitrage that opens, at current marginal prices; `related`
+//! clusters carry no constraint. Sets off by more than `TOLERANCE` are
+//! flagged in the cluster report and logged after trades on their members.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::postgres::PgRow;
+use sqlx::{PgPool, Row};
+
+use crate::api_error::{coded, ErrorCode};
+use crate::exposure::ClusterRelation;
+
+/// How far a set may be off before it is flagged: a mutually exclusive
+/// sum past 1 + TOLERANCE, or a correlated spread wider than TOLERANCE.
+pub const TOLERANCE: f64 = 0.1;
+
+/// Gaps below this are rounding, not arbitrage.
+const GAP_EPSILON: f64 = 1e-9;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MemberPrice {
+    pub event_id: i32,
+    pub title: String,
+    pub inverse: bool,
+    pub market_prob: f64,
+    /// resolved_yes, resolved_no or resolved_na; None while open.
+    pub outcome: Option<String>,
+}
+
+impl MemberPrice {
+    /// Probability of resolving YES: the price while open, 1 or 0 once
+    /// resolved, None if resolved N/A.
+    fn yes_prob(&self) -> Option<f64> {
+        match self.outcome.as_deref() {
+            None => Some(self.market_prob),
+            Some("resolved_yes") => Some(1.0),
+            Some("resolved_no") => Some(0.0),
+            Some(_) => None,
+        }
+    }
+
+    /// Probability the member implies for its correlated cluster resolving
+    /// YES.
+    fn cluster_prob(&self) -> Option<f64> {
+        self.yes_prob()
+            .map(|p| if self.inverse { 1.0 - p } else { p })
+    }
+
+    fn is_open(&self) -> bool {
+        self.outcome.is_none()
+    }
+
+    /// Buying the member's YES or NO side at its current price.
+    fn leg(&self, yes: bool) -> ArbitrageLeg {
+        ArbitrageLeg {
+            event_id: self.event_id,
+            side: if yes { "yes" } else { "no" },
+            price: if yes {
+                self.market_prob
+            } else {
+                1.0 - self.market_prob
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ArbitrageLeg {
+    pub event_id: i32,
+    /// "yes" or "no": the side to buy.
+    pub side: &'static str,
+    pub price: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Assessment {
+    /// Members' YES probabilities summed; mutually exclusive sets only.
+    pub probability_sum: Option<f64>,
+    /// Guaranteed payout less cost of one share on each leg, at current
+    /// prices.
+    pub arbitrage_gap: f64,
+    pub legs: Vec<ArbitrageLeg>,
+    pub warnings: Vec<String>,
+}
+
+/// Checks a cluster's prices against its relation.
+pub fn assess(relation: ClusterRelation, members: &[MemberPrice]) -> Assessment {
+    match relation {
+        ClusterRelation::MutuallyExclusive => assess_exclusive(members),
+        ClusterRelation::Correlated => assess_correlated(members),
+        ClusterRelation::Related => Assessment::default(),
+    }
+}
+
+/// YES prices summing to S > 1: NO on each of the n open members costs
+/// n - S and pays at least n - 1, a gap of S - 1.
+fn assess_exclusive(members: &[MemberPrice]) -> Assessment {
+    let sum: f64 = members.iter().filter_map(|m| m.yes_prob()).sum();
+    let open: Vec<&MemberPrice> = members.iter().filter(|m| m.is_open()).collect();
+    let mut assessment = Assessment {
+        probability_sum: Some(sum),
+        ..Assessment::default()
+    };
+    let resolved_yes = members
+        .iter()
+        .filter(|m| m.outcome.as_deref() == Some("resolved_yes"))
+        .count();
+    if resolved_yes > 1 {
+        assessment.warnings.push(format!(
+            "{} mutually exclusive events resolved YES",
+            resolved_yes
+        ));
+    }
+    if sum > 1.0 + TOLERANCE {
+        assessment.warnings.push(format!(
+            "YES probabilities sum to {:.3} across mutually exclusive events",
+            sum
+        ));
+    }
+    if !open.is_empty() && sum - 1.0 > GAP_EPSILON {
+        assessment.arbitrage_gap = sum - 1.0;
+        assessment.legs = open.iter().map(|m| m.leg(false)).collect();
+    }
+    assessment
+}
+
+/// Implied probabilities from lo to hi: the cluster's YES on the cheapest
+/// member and its NO on the dearest cost 1 - (hi - lo) and pay 1.
+fn assess_correlated(members: &[MemberPrice]) -> Assessment {
+    let mut assessment = Assessment::default();
+    let implied: Vec<(&MemberPrice, f64)> = members
+        .iter()
+        .filter_map(|m| m.cluster_prob().map(|p| (m, p)))
+        .collect();
+    let (Some(&(low, lo)), Some(&(high, hi))) = (
+        implied.iter().min_by(|a, b| a.1.total_cmp(&b.1)),
+        implied.iter().max_by(|a, b| a.1.total_cmp(&b.1)),
+    ) else {
+        return assessment;
+    };
+    let settled = implied.iter().filter(|(m, _)| !m.is_open());
+    if settled.clone().any(|(_, p)| *p == 1.0) && settled.clone().any(|(_, p)| *p == 0.0) {
+        assessment
+            .warnings
+            .push("correlated events resolved different ways".to_string());
+    }
+    if hi - lo > TOLERANCE {
+        assessment.warnings.push(format!(
+            "correlated events imply cluster probabilities from {:.3} to {:.3}",
+            lo, hi
+        ));
+    }
+    if (low.is_open() || high.is_open()) && hi - lo > GAP_EPSILON {
+        assessment.arbitrage_gap = hi - lo;
+        // The cluster's YES on the cheap member, its NO on the dear one
+        assessment.legs = [(low, !low.inverse), (high, high.inverse)]
+            .into_iter()
+            .filter(|(m, _)| m.is_open())
+            .map(|(m, yes)| m.leg(yes))
+            .collect();
+    }
+    assessment
+}
+
+fn member_from(row: &PgRow) -> MemberPrice {
+    MemberPrice {
+        event_id: row.get("event_id"),
+        title: row.get("title"),
+        inverse: row.get("inverse"),
+        market_prob: row.get("market_prob"),
+        outcome: row.get("outcome"),
+    }
+}
+
+async fn load_members(pool: &PgPool, cluster_id: i32) -> Result<Vec<MemberPrice>> {
+    let rows = sqlx::query(
+        "SELECT m.event_id, m.inverse, e.title,
+                COALESCE(e.market_prob, 0.5) AS market_prob, e.outcome
+         FROM event_cluster_members m
+         JOIN events e ON e.id = m.event_id
+         WHERE m.cluster_id = $1
+         ORDER BY m.event_id",
+    )
+    .bind(cluster_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.iter().map(member_from).collect())
+}
+
+/// A cluster with its members at their current prices.
+#[derive(Debug, Clone)]
+pub struct PricedCluster {
+    pub cluster_id: i32,
+    pub name: String,
+    pub relation: ClusterRelation,
+    pub members: Vec<MemberPrice>,
+}
+
+/// Every cluster whose relation constrains prices, with a member still
+/// open.
+pub async fn load_constrained_clusters(pool: &PgPool) -> Result<Vec<PricedCluster>> {
+    let rows = sqlx::query(
+        "SELECT c.id, c.name, c.relation, m.event_id, m.inverse, e.title,
+                COALESCE(e.market_prob, 0.5) AS market_prob, e.outcome
+         FROM event_clusters c
+         JOIN event_cluster_members m ON m.cluster_id = c.id
+         JOIN events e ON e.id = m.event_id
+         WHERE c.relation <> 'related'
+           AND EXISTS (
+               SELECT 1 FROM event_cluster_members om
+               JOIN events oe ON oe.id = om.event_id
+               WHERE om.cluster_id = c.id AND oe.outcome IS NULL
+           )
+         ORDER BY c.id, m.event_id",
+    )
+    .fetch_all(pool)
+    .await?;
+    let mut clusters: Vec<PricedCluster> = Vec::new();
+    for row in &rows {
+        let cluster_id: i32 = row.get("id");
+        match clusters.last_mut() {
+            Some(cluster) if cluster.cluster_id == cluster_id => {
+                cluster.members.push(member_from(row))
+            }
+            _ => {
+                let Some(relation) = ClusterRelation::parse(row.get("relation")) else {
+                    continue;
+                };
+                clusters.push(PricedCluster {
+                    cluster_id,
+                    name: row.get("name"),
+                    relation,
+                    members: vec![member_from(row)],
+                });
+            }
+        }
+    }
+    Ok(clusters)
+}
+
+/// A cluster's members at their current prices, with any arbitrage gap
+/// between them and the warnings they raise.
+pub async fn get_cluster(pool: &PgPool, cluster_id: i32) -> Result<Value> {
+    let row = sqlx::query("SELECT name, relation FROM event_clusters WHERE id = $1")
+        .bind(cluster_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| coded(ErrorCode::NotFound, "Cluster not found"))?;
+    let relation_label: String = row.get("relation");
+    let relation = ClusterRelation::parse(&relation_label)
+        .ok_or_else(|| anyhow!("unknown cluster relation {}", relation_label))?;
+    let members = load_members(pool, cluster_id).await?;
+    let assessment = assess(relation, &members);
+
+    Ok(json!({
+        "cluster_id": cluster_id,
+        "name": row.get::<String, _>("name"),
+        "relation": relation,
+        "members": members,
+        "probability_sum": assessment.probability_sum,
+        "arbitrage": {
+            "gap": assessment.arbitrage_gap,
+            "legs": assessment.legs,
+        },
+        "consistent": assessment.warnings.is_empty(),
+        "warnings": assessment.warnings,
+    }))
+}
+
+/// Logs the warnings of every constrained cluster the event belongs to,
+/// once a trade on it has committed. Never fails the trade.
+pub fn warn_after_trade(pool: &PgPool, event_id: i32) {
+    let pool = pool.clone();
+    tokio::spawn(async move {
+        let clusters: Vec<(i32, String)> = match sqlx::query_as(
+            "SELECT c.id, c.relation
+             FROM event_clusters c
+             JOIN event_cluster_members m ON m.cluster_id = c.id
+             WHERE m.event_id = $1 AND c.relation <> 'related'",
+        )
+        .bind(event_id)
+        .fetch_all(&pool)
+        .await
+        {
+            Ok(clusters) => clusters,
+            Err(err) => {
+                tracing::warn!(event_id, error = %err, "cluster consistency check could not run");
+                return;
+            }
+        };
+        for (cluster_id, relation) in clusters {
+            let Some(relation) = ClusterRelation::parse(&relation) else {
+                continue;
+            };
+            match load_members(&pool, cluster_id).await {
+                Ok(members) => {
+                    for warning in assess(relation, &members).warnings {
+                        tracing::warn!(cluster_id, event_id, "{}", warning);
+                    }
+                }
+                Err(err) => {
+                    tracing::warn!(cluster_id, error = %err, "cluster consistency check could not run");
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(event_id: i32, prob: f64, inverse: bool, outcome: Option<&str>) -> MemberPrice {
+        MemberPrice {
+            event_id,
+            title: format!("Event {}", event_id),
+            inverse,
+            market_prob: prob,
+            outcome: outcome.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn exclusive_sets_summing_past_one_sell_every_member() {
+        let candidates = [
+            member(1, 0.5, false, None),
+            member(2, 0.4, false, None),
+            member(3, 0.3, false, None),
+        ];
+        let assessment = assess(ClusterRelation::MutuallyExclusive, &candidates);
+        assert!((assessment.probability_sum.unwrap() - 1.2).abs() < 1e-12);
+        assert!((assessment.arbitrage_gap - 0.2).abs() < 1e-12);
+        assert_eq!(assessment.legs.len(), 3);
+        assert!(assessment.legs.iter().all(|leg| leg.side == "no"));
+        assert_eq!(assessment.warnings.len(), 1);
+
+        // Under 1 is consistent: the set need not be exhaustive
+        let sparse = [member(1, 0.3, false, None), member(2, 0.2, false, None)];
+        let assessment = assess(ClusterRelation::MutuallyExclusive, &sparse);
+        assert_eq!(assessment.arbitrage_gap, 0.0);
+        assert!(assessment.legs.is_empty() && assessment.warnings.is_empty());
+    }
+
+    #[test]
+    fn exclusive_winner_leaves_the_rest_worth_nothing() {
+        let set = [
+            member(1, 0.9, false, Some("resolved_yes")),
+            member(2, 0.15, false, None),
+            member(3, 0.0, false, Some("resolved_na")),
+        ];
+        let assessment = assess(ClusterRelation::MutuallyExclusive, &set);
+        assert!((assessment.arbitrage_gap - 0.15).abs() < 1e-12);
+        assert_eq!(assessment.legs.len(), 1);
+        assert_eq!(
+            (assessment.legs[0].event_id, assessment.legs[0].side),
+            (2, "no")
+        );
+        assert!((assessment.legs[0].price - 0.85).abs() < 1e-12);
+        assert_eq!(assessment.warnings.len(), 1);
+    }
+
+    #[test]
+    fn correlated_spread_buys_cheap_side_and_sells_dear() {
+        // Event 2 resolves opposite: its 0.6 implies 0.4 for the cluster
+        let linked = [
+            member(1, 0.7, false, None),
+            member(2, 0.6, true, None),
+            member(3, 0.5, false, None),
+        ];
+        let assessment = assess(ClusterRelation::Correlated, &linked);
+        assert!(assessment.probability_sum.is_none());
+        assert!((assessment.arbitrage_gap - 0.3).abs() < 1e-12);
+        let legs: Vec<(i32, &str)> = assessment
+            .legs
+            .iter()
+            .map(|leg| (leg.event_id, leg.side))
+            .collect();
+        // Cluster YES on event 2 is its NO
+        assert_eq!(legs, vec![(2, "no"), (1, "no")]);
+        let cost: f64 = assessment.legs.iter().map(|leg| leg.price).sum();
+        assert!((1.0 - cost - assessment.arbitrage_gap).abs() < 1e-12);
+        assert_eq!(assessment.warnings.len(), 1);
+    }
+
+    #[test]
+    fn related_clusters_carry_no_constraint() {
+        let loose = [member(1, 0.9, false, None), member(2, 0.9, false, None)];
+        let assessment = assess(ClusterRelation::Related, &loose);
+        assert_eq!(assessment.arbitrage_gap, 0.0);
+        assert!(assessment.warnings.is_empty());
+    }
+}
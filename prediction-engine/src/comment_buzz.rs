@@ -0,0 +1,311 @@
+//! Comment activity ("buzz") next to market probabilities.
+//!
+//! Comments live in the Node backend, which pushes hourly per-event
+//! summaries here: how many comments landed in the hour and, when it
+//! scores them, their mean sentiment. Pushing the same hour again
+//! replaces it, so the backend can re-send a window after late comments
+//! or edits. Market state shows the last day's volume, velocity and
+//! sentiment beside the price, and resolution records the comment
+//! velocity with the consensus snapshot so research can ask whether buzzy
+//! markets are called better or worse.
+
+use std::collections::HashSet;
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Row, Transaction};
+
+use crate::api_error::{coded, ErrorCode};
+
+pub const MAX_BATCH: usize = 1000;
+
+/// One event's comments in one hour, as pushed by the backend.
+#[derive(Debug, Clone, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export, export_to = "../../shared/types/CommentSummary.ts")]
+pub struct CommentSummary {
+    pub event_id: i32,
+    /// Any time within the hour; stored truncated to the hour.
+    pub hour: DateTime<Utc>,
+    pub comment_count: i32,
+    /// Mean sentiment of the hour's comments, -1 (negative) to 1 (positive).
+    pub sentiment: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ts_rs::TS)]
+#[ts(export, export_to = "../../shared/types/CommentBuzz.ts")]
+pub struct CommentBuzz {
+    pub comments_24h: i64,
+    pub comments_prev_24h: i64,
+    /// Comments per hour over the last 24 hours.
+    pub velocity_per_hour: f64,
+    /// Relative change of the last 24 hours over the 24 before them;
+    /// `None` when the earlier day had no comments.
+    pub velocity_change: Option<f64>,
+    /// Comment-weighted mean sentiment over the last 24 hours.
+    pub sentiment_24h: Option<f64>,
+    pub last_comment_hour: Option<DateTime<Utc>>,
+}
+
+impl CommentBuzz {
+    fn from_counts(
+        comments_24h: i64,
+        comments_prev_24h: i64,
+        sentiment_24h: Option<f64>,
+        last_comment_hour: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self {
+            comments_24h,
+            comments_prev_24h,
+            velocity_per_hour: comments_24h as f64 / 24.0,
+            velocity_change: (comments_prev_24h > 0)
+                .then(|| comments_24h as f64 / comments_prev_24h as f64 - 1.0),
+            sentiment_24h,
+            last_comment_hour,
+        }
+    }
+}
+
+pub async fn ensure_comment_stats_table(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS event_comment_stats (
+            event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+            hour TIMESTAMPTZ NOT NULL,
+            comment_count INTEGER NOT NULL CHECK (comment_count >= 0),
+            sentiment DOUBLE PRECISION CHECK (sentiment BETWEEN -1 AND 1),
+            received_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            PRIMARY KEY (event_id, hour)
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    // Market state shows buzz, so new summaries evict cached state the same
+    // way trades do (the function comes from market_cache)
+    sqlx::query(
+        "CREATE OR REPLACE TRIGGER market_state_changed
+         AFTER INSERT OR UPDATE OR DELETE ON event_comment_stats
+         FOR EACH ROW EXECUTE FUNCTION notify_market_state_changed()",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+fn validate(summaries: &[CommentSummary]) -> Result<Vec<CommentSummary>> {
+    if summaries.is_empty() {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "summaries must not be empty",
+        ));
+    }
+    if summaries.len() > MAX_BATCH {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("summaries must hold at most {} entries", MAX_BATCH),
+        ));
+    }
+    let now = Utc::now();
+    let mut seen = HashSet::new();
+    summaries
+        .iter()
+        .map(|summary| {
+            if summary.comment_count < 0 {
+                return Err(coded(
+                    ErrorCode::InvalidRequest,
+                    "comment_count must not be negative",
+                ));
+            }
+            if let Some(sentiment) = summary.sentiment {
+                if !(-1.0..=1.0).contains(&sentiment) {
+                    return Err(coded(
+                        ErrorCode::InvalidRequest,
+                        "sentiment must be between -1 and 1",
+                    ));
+                }
+            }
+            if summary.hour > now {
+                return Err(coded(
+                    ErrorCode::InvalidRequest,
+                    "hour must not be in the future",
+                ));
+            }
+            let hour = summary.hour.duration_trunc(Duration::hours(1))?;
+            if !seen.insert((summary.event_id, hour)) {
+                return Err(coded(
+                    ErrorCode::InvalidRequest,
+                    format!(
+                        "summaries must not repeat event {} hour {}",
+                        summary.event_id, hour
+                    ),
+                ));
+            }
+            Ok(CommentSummary {
+                hour,
+                ..summary.clone()
+            })
+        })
+        .collect()
+}
+
+/// Stores a batch of hourly summaries, replacing any already held for the
+/// same event and hour. Summaries for unknown events are skipped; returns
+/// the ids of the events that were stored and of those skipped.
+pub async fn ingest(pool: &PgPool, summaries: &[CommentSummary]) -> Result<(Vec<i32>, Vec<i32>)> {
+    let summaries = validate(summaries)?;
+    let event_ids: Vec<i32> = summaries.iter().map(|s| s.event_id).collect();
+    let hours: Vec<DateTime<Utc>> = summaries.iter().map(|s| s.hour).collect();
+    let counts: Vec<i32> = summaries.iter().map(|s| s.comment_count).collect();
+    let sentiments: Vec<Option<f64>> = summaries.iter().map(|s| s.sentiment).collect();
+
+    let mut stored: Vec<i32> = sqlx::query_scalar(
+        r#"
+        INSERT INTO event_comment_stats (event_id, hour, comment_count, sentiment)
+        SELECT t.event_id, t.hour, t.comment_count, t.sentiment
+        FROM UNNEST($1::integer[], $2::timestamptz[], $3::integer[], $4::double precision[])
+             AS t(event_id, hour, comment_count, sentiment)
+        JOIN events e ON e.id = t.event_id
+        ON CONFLICT (event_id, hour) DO UPDATE
+        SET comment_count = EXCLUDED.comment_count,
+            sentiment = EXCLUDED.sentiment,
+            received_at = NOW()
+        RETURNING event_id
+        "#,
+    )
+    .bind(&event_ids)
+    .bind(&hours)
+    .bind(&counts)
+    .bind(&sentiments)
+    .fetch_all(pool)
+    .await?;
+    stored.sort_unstable();
+    stored.dedup();
+
+    let mut skipped: Vec<i32> = event_ids
+        .into_iter()
+        .filter(|id| stored.binary_search(id).is_err())
+        .collect();
+    skipped.sort_unstable();
+    skipped.dedup();
+    Ok((stored, skipped))
+}
+
+/// The event's comment activity over the last two days; `None` when no
+/// summaries have been pushed for it (or the table doesn't exist yet).
+pub async fn load_buzz(pool: &PgPool, event_id: i32) -> Result<Option<CommentBuzz>> {
+    let table_exists: bool =
+        sqlx::query_scalar("SELECT to_regclass('event_comment_stats') IS NOT NULL")
+            .fetch_one(pool)
+            .await?;
+    if !table_exists {
+        return Ok(None);
+    }
+    let row = sqlx::query(
+        r#"
+        SELECT COUNT(*) AS summaries,
+               COALESCE(SUM(comment_count) FILTER (WHERE hour > NOW() - INTERVAL '24 hours'), 0)
+                   ::bigint AS comments_24h,
+               COALESCE(SUM(comment_count) FILTER (
+                   WHERE hour <= NOW() - INTERVAL '24 hours'
+                     AND hour > NOW() - INTERVAL '48 hours'), 0)::bigint AS comments_prev_24h,
+               SUM(sentiment * comment_count) FILTER (WHERE hour > NOW() - INTERVAL '24 hours')
+                   / NULLIF(SUM(comment_count) FILTER (
+                       WHERE hour > NOW() - INTERVAL '24 hours' AND sentiment IS NOT NULL), 0)
+                   AS sentiment_24h,
+               MAX(hour) FILTER (WHERE comment_count > 0) AS last_comment_hour
+        FROM event_comment_stats
+        WHERE event_id = $1
+        "#,
+    )
+    .bind(event_id)
+    .fetch_one(pool)
+    .await?;
+    if row.get::<i64, _>("summaries") == 0 {
+        return Ok(None);
+    }
+    Ok(Some(CommentBuzz::from_counts(
+        row.get("comments_24h"),
+        row.get("comments_prev_24h"),
+        row.get("sentiment_24h"),
+        row.get("last_comment_hour"),
+    )))
+}
+
+/// Comments per hour over the 24 hours before now, for the consensus
+/// snapshot taken at resolution; `None` when the event has no summaries.
+pub(crate) async fn velocity_at_resolution(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+) -> Result<Option<f64>> {
+    let table_exists: bool =
+        sqlx::query_scalar("SELECT to_regclass('event_comment_stats') IS NOT NULL")
+            .fetch_one(&mut **tx)
+            .await?;
+    if !table_exists {
+        return Ok(None);
+    }
+    let velocity = sqlx::query_scalar(
+        r#"
+        SELECT CASE WHEN COUNT(*) = 0 THEN NULL
+                    ELSE COALESCE(SUM(comment_count) FILTER (
+                             WHERE hour > NOW() - INTERVAL '24 hours'), 0)::float8 / 24
+               END
+        FROM event_comment_stats
+        WHERE event_id = $1
+        "#,
+    )
+    .bind(event_id)
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(velocity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(event_id: i32, hour: DateTime<Utc>, sentiment: Option<f64>) -> CommentSummary {
+        CommentSummary {
+            event_id,
+            hour,
+            comment_count: 3,
+            sentiment,
+        }
+    }
+
+    #[test]
+    fn velocity_compares_the_last_two_days() {
+        let buzz = CommentBuzz::from_counts(48, 24, Some(0.25), None);
+        assert_eq!(buzz.velocity_per_hour, 2.0);
+        assert_eq!(buzz.velocity_change, Some(1.0));
+        assert_eq!(
+            CommentBuzz::from_counts(12, 0, None, None).velocity_change,
+            None
+        );
+    }
+
+    #[test]
+    fn batches_are_truncated_to_the_hour_and_checked() {
+        let hour = Utc::now() - Duration::hours(2);
+        let stored = validate(&[summary(1, hour, Some(0.5)), summary(2, hour, None)]).unwrap();
+        assert_eq!(
+            stored[0].hour,
+            hour.duration_trunc(Duration::hours(1)).unwrap()
+        );
+
+        let later_in_hour = stored[0].hour + Duration::minutes(30);
+        for batch in [
+            vec![],
+            vec![summary(1, hour, Some(1.5))],
+            vec![summary(1, Utc::now() + Duration::hours(2), None)],
+            vec![
+                summary(1, stored[0].hour, None),
+                summary(1, later_in_hour, None),
+            ],
+        ] {
+            let err = validate(&batch).unwrap_err();
+            assert!(err.to_string().contains("must"), "{}", err);
+        }
+    }
+}
@@ -0,0 +1,430 @@
+//! Trading competitions with fresh bankrolls.
+//!
+//! Every entrant gets the same starting bankroll in `competition_wallets`.
+//! Markets tagged with `events.competition_id` trade and settle against
+//! that wallet (see `db_adapter::Wallet`), never `users.rp_*_ledger`, so
+//! reputation can't be carried in. Entrants rank by balance plus stake,
+//! final once every market has resolved. Paged reads pin a snapshot of the
+//! ranking in `leaderboard_snapshots` so trades can't shift the pages.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use sqlx::{PgPool, Row};
+
+use crate::api_error::{coded, ErrorCode};
+use crate::lmsr_core::{from_ledger_units, to_ledger_units};
+
+/// Most entrants one leaderboard page returns.
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+#[derive(Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export, export_to = "../../shared/types/CreateCompetition.ts")]
+pub struct CreateCompetition {
+    pub name: String,
+    /// RP each entrant starts with.
+    pub starting_bankroll: f64,
+    pub ends_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export, export_to = "../../shared/types/Competition.ts")]
+pub struct Competition {
+    pub id: i32,
+    pub name: String,
+    pub starting_bankroll: f64,
+    pub starting_bankroll_ledger: i64,
+    pub ends_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+pub async fn ensure_competition_schema(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS competitions (
+            id SERIAL PRIMARY KEY,
+            name VARCHAR(255) NOT NULL,
+            starting_bankroll_ledger BIGINT NOT NULL CHECK (starting_bankroll_ledger > 0),
+            ends_at TIMESTAMPTZ NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "ALTER TABLE events ADD COLUMN IF NOT EXISTS competition_id INTEGER REFERENCES competitions(id)",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS competition_wallets (
+            competition_id INTEGER NOT NULL REFERENCES competitions(id) ON DELETE CASCADE,
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            balance_ledger BIGINT NOT NULL CHECK (balance_ledger >= 0),
+            staked_ledger BIGINT NOT NULL DEFAULT 0 CHECK (staked_ledger >= 0),
+            joined_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            PRIMARY KEY (competition_id, user_id)
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS leaderboard_snapshots (
+            id BIGSERIAL PRIMARY KEY,
+            competition_id INTEGER NOT NULL REFERENCES competitions(id) ON DELETE CASCADE,
+            version VARCHAR(64) NOT NULL,
+            leaderboard JSONB NOT NULL,
+            taken_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            expires_at TIMESTAMPTZ NOT NULL
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_leaderboard_snapshots_version
+         ON leaderboard_snapshots (competition_id, version)",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn create_competition(pool: &PgPool, request: &CreateCompetition) -> Result<Competition> {
+    let name = request.name.trim();
+    if name.is_empty() || name.chars().count() > 255 {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "name must be 1-255 characters",
+        ));
+    }
+    if !request.starting_bankroll.is_finite() || request.starting_bankroll <= 0.0 {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "starting_bankroll must be positive and finite",
+        ));
+    }
+    if request.ends_at <= Utc::now() {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "ends_at must be in the future",
+        ));
+    }
+    let starting_bankroll_ledger =
+        i64::try_from(to_ledger_units(request.starting_bankroll).map_err(|e| {
+            coded(
+                ErrorCode::InvalidRequest,
+                format!("starting_bankroll invalid: {}", e),
+            )
+        })?)
+        .map_err(|_| coded(ErrorCode::InvalidRequest, "starting_bankroll out of range"))?;
+
+    let row = sqlx::query(
+        "INSERT INTO competitions (name, starting_bankroll_ledger, ends_at)
+         VALUES ($1, $2, $3)
+         RETURNING id, created_at",
+    )
+    .bind(name)
+    .bind(starting_bankroll_ledger)
+    .bind(request.ends_at)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(Competition {
+        id: row.get("id"),
+        name: name.to_string(),
+        starting_bankroll: from_ledger_units(starting_bankroll_ledger as i128),
+        starting_bankroll_ledger,
+        ends_at: request.ends_at,
+        created_at: row.get("created_at"),
+    })
+}
+
+/// Enters `user_id` with a wallet holding the starting bankroll.
+pub async fn join_competition(pool: &PgPool, competition_id: i32, user_id: i32) -> Result<Value> {
+    let competition = sqlx::query(
+        "SELECT starting_bankroll_ledger, ends_at <= NOW() AS has_ended
+         FROM competitions WHERE id = $1",
+    )
+    .bind(competition_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| coded(ErrorCode::NotFound, "Competition not found"))?;
+    if competition.get::<bool, _>("has_ended") {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("Competition {} has ended", competition_id),
+        ));
+    }
+    let user_exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM users WHERE id = $1)")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+    if !user_exists {
+        return Err(coded(ErrorCode::NotFound, "User not found"));
+    }
+
+    let bankroll_ledger: i64 = competition.get("starting_bankroll_ledger");
+    let joined_at: Option<DateTime<Utc>> = sqlx::query_scalar(
+        "INSERT INTO competition_wallets (competition_id, user_id, balance_ledger)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (competition_id, user_id) DO NOTHING
+         RETURNING joined_at",
+    )
+    .bind(competition_id)
+    .bind(user_id)
+    .bind(bankroll_ledger)
+    .fetch_optional(pool)
+    .await?;
+    let joined_at = joined_at.ok_or_else(|| {
+        coded(
+            ErrorCode::InvalidRequest,
+            format!("Already entered in competition {}", competition_id),
+        )
+    })?;
+
+    Ok(json!({
+        "competition_id": competition_id,
+        "user_id": user_id,
+        "balance": from_ledger_units(bankroll_ledger as i128),
+        "balance_ledger": bankroll_ledger,
+        "joined_at": joined_at,
+    }))
+}
+
+/// Tags an untraded, unresolved market as part of the competition. Markets
+/// with positions are refused: those were paid for from users' RP, and
+/// settling them into competition wallets would move RP between the two.
+pub async fn add_market(pool: &PgPool, competition_id: i32, event_id: i32) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    let competition_exists: bool =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM competitions WHERE id = $1)")
+            .bind(competition_id)
+            .fetch_one(&mut *tx)
+            .await?;
+    if !competition_exists {
+        return Err(coded(ErrorCode::NotFound, "Competition not found"));
+    }
+    let event = sqlx::query("SELECT outcome, competition_id FROM events WHERE id = $1 FOR UPDATE")
+        .bind(event_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| coded(ErrorCode::NotFound, "Event not found"))?;
+    if event.get::<Option<String>, _>("outcome").is_some() {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "Event is already resolved",
+        ));
+    }
+    if let Some(existing) = event.get::<Option<i32>, _>("competition_id") {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("Event already belongs to competition {}", existing),
+        ));
+    }
+    let has_positions: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM user_shares WHERE event_id = $1)
+             OR EXISTS (SELECT 1 FROM user_outcome_shares WHERE event_id = $1)",
+    )
+    .bind(event_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if has_positions {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "Event already has positions; only untraded markets can join a competition",
+        ));
+    }
+
+    sqlx::query("UPDATE events SET competition_id = $1 WHERE id = $2")
+        .bind(competition_id)
+        .bind(event_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Cache key for a competition's full leaderboard in the engine's response
+/// cache.
+pub fn leaderboard_cache_key(competition_id: i32) -> String {
+    format!("leaderboard:{}", competition_id)
+}
+
+/// Entrants ranked by bankroll (balance + staked). `final` is set once the
+/// competition has markets and all of them have resolved.
+pub async fn get_leaderboard(pool: &PgPool, competition_id: i32) -> Result<Value> {
+    let competition = sqlx::query(
+        "SELECT c.name, c.starting_bankroll_ledger, c.ends_at,
+                COUNT(e.id) AS market_count,
+                COUNT(e.id) FILTER (WHERE e.outcome IS NULL) AS open_markets
+         FROM competitions c
+         LEFT JOIN events e ON e.competition_id = c.id
+         WHERE c.id = $1
+         GROUP BY c.id",
+    )
+    .bind(competition_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| coded(ErrorCode::NotFound, "Competition not found"))?;
+    let market_count: i64 = competition.get("market_count");
+    let open_markets: i64 = competition.get("open_markets");
+    let starting_ledger: i64 = competition.get("starting_bankroll_ledger");
+
+    let rows = sqlx::query(
+        r#"
+        SELECT w.user_id, u.username, w.balance_ledger, w.staked_ledger,
+               (w.balance_ledger + w.staked_ledger) AS bankroll_ledger,
+               RANK() OVER (ORDER BY w.balance_ledger + w.staked_ledger DESC) AS rank
+        FROM competition_wallets w
+        JOIN users u ON u.id = w.user_id
+        WHERE w.competition_id = $1
+        ORDER BY rank, w.joined_at, w.user_id
+        "#,
+    )
+    .bind(competition_id)
+    .fetch_all(pool)
+    .await?;
+
+    let entries: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            let bankroll_ledger: i64 = row.get("bankroll_ledger");
+            json!({
+                "rank": row.get::<i64, _>("rank"),
+                "user_id": row.get::<i32, _>("user_id"),
+                "username": row.get::<String, _>("username"),
+                "balance": from_ledger_units(row.get::<i64, _>("balance_ledger") as i128),
+                "staked": from_ledger_units(row.get::<i64, _>("staked_ledger") as i128),
+                "bankroll": from_ledger_units(bankroll_ledger as i128),
+                "bankroll_ledger": bankroll_ledger,
+                "return_pct": (bankroll_ledger - starting_ledger) as f64 / starting_ledger as f64 * 100.0,
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "competition_id": competition_id,
+        "name": competition.get::<String, _>("name"),
+        "starting_bankroll": from_ledger_units(starting_ledger as i128),
+        "ends_at": competition.get::<DateTime<Utc>, _>("ends_at"),
+        "market_count": market_count,
+        "open_markets": open_markets,
+        "final": market_count > 0 && open_markets == 0,
+        "entries": entries,
+    }))
+}
+
+/// Version of a leaderboard: a hash of its contents, so an unchanged
+/// ranking keeps its version.
+pub fn ranking_version(leaderboard: &Value) -> String {
+    hex::encode(Sha256::digest(leaderboard.to_string().as_bytes()))
+}
+
+/// One page of the leaderboard, read from `snapshot` if given and still
+/// live, otherwise from a snapshot of the current ranking that lives for
+/// `snapshot_secs`; an unchanged ranking reuses its live snapshot rather
+/// than storing another.
+pub async fn get_leaderboard_page(
+    pool: &PgPool,
+    competition_id: i32,
+    snapshot: Option<i64>,
+    limit: i64,
+    offset: i64,
+    snapshot_secs: u64,
+) -> Result<Value> {
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("limit must be between 1 and {}", MAX_PAGE_SIZE),
+        ));
+    }
+    if offset < 0 {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "offset must be non-negative",
+        ));
+    }
+
+    let row = match snapshot {
+        Some(snapshot_id) => sqlx::query(
+            "SELECT id, leaderboard, taken_at, expires_at FROM leaderboard_snapshots
+                 WHERE id = $1 AND competition_id = $2 AND expires_at > NOW()",
+        )
+        .bind(snapshot_id)
+        .bind(competition_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| {
+            coded(
+                ErrorCode::NotFound,
+                "Leaderboard snapshot not found or expired",
+            )
+        })?,
+        None => {
+            let leaderboard = get_leaderboard(pool, competition_id).await?;
+            let version = ranking_version(&leaderboard);
+            sqlx::query("DELETE FROM leaderboard_snapshots WHERE expires_at <= NOW()")
+                .execute(pool)
+                .await?;
+            let reused = sqlx::query(
+                "UPDATE leaderboard_snapshots
+                 SET expires_at = GREATEST(expires_at, NOW() + make_interval(secs => $3))
+                 WHERE id = (SELECT MAX(id) FROM leaderboard_snapshots
+                             WHERE competition_id = $1 AND version = $2 AND expires_at > NOW())
+                 RETURNING id, leaderboard, taken_at, expires_at",
+            )
+            .bind(competition_id)
+            .bind(&version)
+            .bind(snapshot_secs as f64)
+            .fetch_optional(pool)
+            .await?;
+            match reused {
+                Some(row) => row,
+                None => {
+                    sqlx::query(
+                        "INSERT INTO leaderboard_snapshots
+                             (competition_id, version, leaderboard, expires_at)
+                         VALUES ($1, $2, $3, NOW() + make_interval(secs => $4))
+                         RETURNING id, leaderboard, taken_at, expires_at",
+                    )
+                    .bind(competition_id)
+                    .bind(&version)
+                    .bind(&leaderboard)
+                    .bind(snapshot_secs as f64)
+                    .fetch_one(pool)
+                    .await?
+                }
+            }
+        }
+    };
+
+    let mut leaderboard: Value = row.get("leaderboard");
+    let entries = leaderboard["entries"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    let total = entries.len() as i64;
+    let page: Vec<Value> = entries
+        .into_iter()
+        .skip(offset as usize)
+        .take(limit as usize)
+        .collect();
+    let has_more = offset + (page.len() as i64) < total;
+    leaderboard["entries"] = json!(page);
+    leaderboard["total"] = json!(total);
+    leaderboard["limit"] = json!(limit);
+    leaderboard["offset"] = json!(offset);
+    leaderboard["has_more"] = json!(has_more);
+    leaderboard["snapshot"] = json!(row.get::<i64, _>("id"));
+    leaderboard["snapshot_taken_at"] = json!(row.get::<DateTime<Utc>, _>("taken_at"));
+    leaderboard["snapshot_expires_at"] = json!(row.get::<DateTime<Utc>, _>("expires_at"));
+    Ok(leaderboard)
+}
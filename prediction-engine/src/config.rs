@@ -4,11 +4,37 @@
 use serde::{Deserialize, Serialize};
 use std::env;
 
+use crate::score_quota::QuotaPolicy;
+
 /// Configuration for the prediction engine
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     /// Market configuration
     pub market: MarketConfig,
+
+    /// Connection pool configuration
+    #[serde(default)]
+    pub database: DatabaseConfig,
+
+    /// RP onboarding grants and top-ups
+    #[serde(default)]
+    pub faucet: FaucetConfig,
+
+    /// Encrypted message mailboxes
+    #[serde(default)]
+    pub messaging: MessagingConfig,
+
+    /// Outbound webhooks
+    #[serde(default)]
+    pub webhooks: WebhookConfig,
+
+    /// Caches loaded before serving
+    #[serde(default)]
+    pub cache_warming: CacheWarmConfig,
+
+    /// Daily limits on how much predictions count toward scores
+    #[serde(default)]
+    pub score_quota: QuotaPolicy,
 }
 
 /// Market-specific configuration parameters
@@ -25,6 +51,82 @@ pub struct MarketConfig {
 
     /// Maximum Kelly fraction allowed (default: 1.0)
     pub max_kelly_fraction: f64,
+
+    /// Hours after resolution during which it can be disputed and reverted (default: 48.0)
+    pub dispute_window_hours: f64,
+
+    /// Positions below this many shares are treated as dust and closed out on sell (default: 1e-6)
+    pub share_dust_epsilon: f64,
+
+    /// Seconds between sweeps that close markets past their closing_date; 0 disables (default: 60)
+    pub close_sweep_interval_secs: u64,
+
+    /// Hours before closing_date that a market's closing reminder goes out; 0 disables (default: 24.0)
+    pub closing_reminder_hours: f64,
+
+    /// Hours undelivered broadcasts are kept for backfill (default: 72.0)
+    pub dead_letter_retention_hours: f64,
+
+    /// Seconds a cached market state lives before reloading; 0 disables the cache (default: 30)
+    pub market_state_cache_ttl_secs: u64,
+
+    /// Seconds between compactions of settled forecast revisions; 0 disables (default: 3600)
+    pub forecast_compaction_interval_secs: u64,
+
+    /// Keep compacted forecast revisions in an archive table rather than deleting them (default: true)
+    pub archive_compacted_forecasts: bool,
+
+    /// Seconds between refreshes of the reputation-weighted consensus; 0 disables (default: 300)
+    pub weighted_prob_refresh_secs: u64,
+
+    /// Requests per minute allowed to an API key without a limit of its own (default: 120)
+    pub api_key_rate_limit_per_minute: u32,
+
+    /// Seconds between archival runs for long-resolved markets; 0 disables (default: 3600)
+    pub archive_interval_secs: u64,
+
+    /// Days after resolution before a market's trades and payouts are archived;
+    /// never shorter than the dispute window (default: 90)
+    pub archive_after_days: f64,
+
+    /// Convert market_updates to monthly partitions at startup (default: true)
+    pub partition_market_updates: bool,
+
+    /// Months of market_updates partitions kept created ahead of today (default: 3)
+    pub partition_months_ahead: u32,
+
+    /// Seconds between market_updates partition maintenance runs; 0 disables (default: 86400)
+    pub partition_maintenance_secs: u64,
+
+    /// Binary buys staking less than this many RP trade without locking the
+    /// market row, retrying locked under SERIALIZABLE on conflict; 0 disables (default: 5.0)
+    pub optimistic_stake_threshold: f64,
+
+    /// Seconds between per-category liquidity recommendation refreshes; 0 disables (default: 86400)
+    pub liquidity_recommendation_secs: u64,
+
+    /// Resolved, traded markets a category needs before liquidity is recommended for it (default: 5)
+    pub liquidity_recommendation_min_markets: u32,
+
+    /// Seconds between audits of users' score checksums; 0 disables (default: 3600)
+    pub score_audit_secs: u64,
+
+    /// Seconds a paged leaderboard's snapshot keeps later pages on the same ranking (default: 300)
+    pub leaderboard_snapshot_secs: u64,
+
+    /// Listen for resolved predictions and push score updates over the WebSocket (default: false)
+    pub live_score_updates: bool,
+
+    /// Seconds between scans of linked markets for arbitrage; 0 disables (default: 300)
+    pub arbitrage_scan_secs: u64,
+
+    /// Smallest gap, in probability, a scan reports as arbitrage (default: 0.05)
+    pub arbitrage_min_gap: f64,
+
+    /// Seconds between recomputations of open markets' activity heat; 0 disables (default: 300)
+    pub heat_refresh_secs: u64,
+    /// Seconds between re-ranks after sells and resolutions; 0 disables (default: 10)
+    pub ranking_update_secs: u64,
 }
 
 impl Default for MarketConfig {
@@ -34,6 +136,215 @@ impl Default for MarketConfig {
             hold_period_hours: 1.0,
             kelly_fraction: 0.25,
             max_kelly_fraction: 1.0,
+            dispute_window_hours: 48.0,
+            share_dust_epsilon: 1e-6,
+            close_sweep_interval_secs: 60,
+            closing_reminder_hours: 24.0,
+            dead_letter_retention_hours: 72.0,
+            market_state_cache_ttl_secs: 30,
+            forecast_compaction_interval_secs: 3600,
+            archive_compacted_forecasts: true,
+            weighted_prob_refresh_secs: 300,
+            api_key_rate_limit_per_minute: 120,
+            archive_interval_secs: 3600,
+            archive_after_days: 90.0,
+            partition_market_updates: true,
+            partition_months_ahead: 3,
+            partition_maintenance_secs: 86400,
+            optimistic_stake_threshold: 5.0,
+            liquidity_recommendation_secs: 86400,
+            liquidity_recommendation_min_markets: 5,
+            score_audit_secs: 3600,
+            leaderboard_snapshot_secs: 300,
+            live_score_updates: false,
+            arbitrage_scan_secs: 300,
+            arbitrage_min_gap: 0.05,
+            heat_refresh_secs: 300,
+            ranking_update_secs: 10,
+        }
+    }
+}
+
+/// Connection pools. Trades run on their own pool so long analytics scans
+/// (leaderboards, portfolios, invariant checks) can't hold every connection
+/// while a SERIALIZABLE trade waits for one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseConfig {
+    /// Connections reserved for trading and other writes (default: 16)
+    pub trading_max_connections: u32,
+
+    /// Seconds a trade waits for a connection before failing (default: 5)
+    pub trading_acquire_timeout_secs: u64,
+
+    /// Per-statement timeout on trading connections; 0 disables (default: 0)
+    pub trading_statement_timeout_secs: u64,
+
+    /// Connections for read-heavy reporting endpoints (default: 4)
+    pub analytics_max_connections: u32,
+
+    /// Seconds a report waits for a connection before failing (default: 30)
+    pub analytics_acquire_timeout_secs: u64,
+
+    /// Per-statement timeout on analytics connections; 0 disables (default: 60)
+    pub analytics_statement_timeout_secs: u64,
+
+    /// Backend transactions are written for: postgres, cockroachdb, or auto to
+    /// ask the server (default: auto)
+    pub dialect: String,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            trading_max_connections: 16,
+            trading_acquire_timeout_secs: 5,
+            trading_statement_timeout_secs: 0,
+            analytics_max_connections: 4,
+            analytics_acquire_timeout_secs: 30,
+            analytics_statement_timeout_secs: 60,
+            dialect: "auto".to_string(),
+        }
+    }
+}
+
+/// RP the engine hands out: a grant when a user first appears, and top-ups
+/// for users who still trade but have run out. Both are applied by a
+/// periodic sweep and journaled in `rp_faucet_grants`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FaucetConfig {
+    /// RP granted to each new user (default: 1000.0)
+    pub onboarding_grant_rp: f64,
+
+    /// Users whose balance plus stake is below this can be topped up (default: 10.0)
+    pub topup_threshold_rp: f64,
+
+    /// RP per top-up (default: 100.0)
+    pub topup_amount_rp: f64,
+
+    /// Minimum hours between two top-ups for the same user (default: 168.0)
+    pub topup_cooldown_hours: f64,
+
+    /// Users must have traded within this many days to be topped up (default: 14.0)
+    pub active_within_days: f64,
+
+    /// Most RP one user can receive from top-ups in total; 0 disables top-ups (default: 500.0)
+    pub topup_lifetime_cap_rp: f64,
+
+    /// Seconds between faucet sweeps; 0 disables (default: 60)
+    pub sweep_interval_secs: u64,
+}
+
+impl Default for FaucetConfig {
+    fn default() -> Self {
+        Self {
+            onboarding_grant_rp: 1000.0,
+            topup_threshold_rp: 10.0,
+            topup_amount_rp: 100.0,
+            topup_cooldown_hours: 168.0,
+            active_within_days: 14.0,
+            topup_lifetime_cap_rp: 500.0,
+            sweep_interval_secs: 60,
+        }
+    }
+}
+
+/// How long ciphertexts queued in `mls_mailbox_messages` are kept.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessagingConfig {
+    /// Days an unacked message waits for its recipient; 0 keeps it until acked (default: 30.0)
+    pub retention_days: f64,
+
+    /// Hours an acked message is kept before it is deleted (default: 0.0)
+    pub acked_retention_hours: f64,
+
+    /// Seconds between retention sweeps; 0 disables (default: 3600)
+    pub sweep_interval_secs: u64,
+}
+
+impl Default for MessagingConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: 30.0,
+            acked_retention_hours: 0.0,
+            sweep_interval_secs: 3600,
+        }
+    }
+}
+
+/// Where engine events are posted, and how hard delivery is tried. Each
+/// delivery is logged in `webhook_deliveries`; one still `pending` after
+/// `stale_pending_secs` (its sender died mid-retry) is sent again by the
+/// stale delivery sweep.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// Endpoints every event is posted to; none disables webhooks (default: none)
+    pub urls: Vec<String>,
+
+    /// HMAC-SHA256 signing secret; unset sends unsigned (default: unset)
+    pub secret: Option<String>,
+
+    /// Event types to send; empty sends every type (default: all)
+    pub events: Vec<String>,
+
+    /// Attempts per delivery, 1-20 (default: 5)
+    pub max_attempts: u32,
+
+    /// Delay before the first retry, doubling per attempt up to 60s (default: 1000)
+    pub base_backoff_ms: u64,
+
+    /// Per-request timeout, 1-120 (default: 10)
+    pub timeout_secs: u64,
+
+    /// Seconds a delivery may stay pending before it is presumed abandoned (default: 900)
+    pub stale_pending_secs: u64,
+
+    /// Seconds between stale delivery sweeps; 0 disables (default: 300)
+    pub sweep_interval_secs: u64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            urls: Vec::new(),
+            secret: None,
+            events: Vec::new(),
+            max_attempts: 5,
+            base_backoff_ms: 1_000,
+            timeout_secs: 10,
+            stale_pending_secs: 900,
+            sweep_interval_secs: 300,
+        }
+    }
+}
+
+impl WebhookConfig {
+    pub fn wants(&self, event_type: &str) -> bool {
+        !self.urls.is_empty()
+            && (self.events.is_empty() || self.events.iter().any(|e| e == event_type))
+    }
+}
+
+/// What is loaded into the caches at startup, before the listener binds,
+/// and by `POST /admin/cache/warm` when it names no targets. See
+/// `cache_warming` for the targets.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheWarmConfig {
+    /// Targets warmed at startup; none skips startup warming (default: markets, leaderboards)
+    pub targets: Vec<String>,
+
+    /// Most open markets the `markets` target loads, 1-10000 (default: 200)
+    pub market_limit: u32,
+
+    /// Most users the `dashboards` target loads, 1-1000 (default: 20)
+    pub dashboard_limit: u32,
+}
+
+impl Default for CacheWarmConfig {
+    fn default() -> Self {
+        Self {
+            targets: vec!["markets".to_string(), "leaderboards".to_string()],
+            market_limit: 200,
+            dashboard_limit: 20,
         }
     }
 }
@@ -42,6 +353,12 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             market: MarketConfig::default(),
+            database: DatabaseConfig::default(),
+            faucet: FaucetConfig::default(),
+            messaging: MessagingConfig::default(),
+            webhooks: WebhookConfig::default(),
+            cache_warming: CacheWarmConfig::default(),
+            score_quota: QuotaPolicy::default(),
         }
     }
 }
@@ -77,6 +394,324 @@ impl Config {
                 .unwrap_or(config.market.max_kelly_fraction);
         }
 
+        if let Ok(window_hours) = env::var("MARKET_DISPUTE_WINDOW_HOURS") {
+            config.market.dispute_window_hours = window_hours
+                .parse()
+                .unwrap_or(config.market.dispute_window_hours);
+        }
+
+        if let Ok(epsilon) = env::var("MARKET_SHARE_DUST_EPSILON") {
+            config.market.share_dust_epsilon =
+                epsilon.parse().unwrap_or(config.market.share_dust_epsilon);
+        }
+
+        if let Ok(interval) = env::var("MARKET_CLOSE_SWEEP_SECS") {
+            config.market.close_sweep_interval_secs = interval
+                .parse()
+                .unwrap_or(config.market.close_sweep_interval_secs);
+        }
+
+        if let Ok(hours) = env::var("MARKET_CLOSING_REMINDER_HOURS") {
+            config.market.closing_reminder_hours = hours
+                .parse()
+                .unwrap_or(config.market.closing_reminder_hours);
+        }
+
+        if let Ok(retention) = env::var("MARKET_DEAD_LETTER_RETENTION_HOURS") {
+            config.market.dead_letter_retention_hours = retention
+                .parse()
+                .unwrap_or(config.market.dead_letter_retention_hours);
+        }
+
+        if let Ok(ttl) = env::var("MARKET_STATE_CACHE_TTL_SECS") {
+            config.market.market_state_cache_ttl_secs = ttl
+                .parse()
+                .unwrap_or(config.market.market_state_cache_ttl_secs);
+        }
+
+        if let Ok(interval) = env::var("MARKET_FORECAST_COMPACTION_SECS") {
+            config.market.forecast_compaction_interval_secs = interval
+                .parse()
+                .unwrap_or(config.market.forecast_compaction_interval_secs);
+        }
+
+        if let Ok(archive) = env::var("MARKET_ARCHIVE_COMPACTED_FORECASTS") {
+            config.market.archive_compacted_forecasts = archive
+                .parse()
+                .unwrap_or(config.market.archive_compacted_forecasts);
+        }
+
+        if let Ok(interval) = env::var("MARKET_WEIGHTED_PROB_REFRESH_SECS") {
+            config.market.weighted_prob_refresh_secs = interval
+                .parse()
+                .unwrap_or(config.market.weighted_prob_refresh_secs);
+        }
+
+        if let Ok(limit) = env::var("MARKET_API_KEY_RATE_LIMIT_PER_MINUTE") {
+            config.market.api_key_rate_limit_per_minute = limit
+                .parse()
+                .unwrap_or(config.market.api_key_rate_limit_per_minute);
+        }
+
+        if let Ok(interval) = env::var("MARKET_ARCHIVE_INTERVAL_SECS") {
+            config.market.archive_interval_secs = interval
+                .parse()
+                .unwrap_or(config.market.archive_interval_secs);
+        }
+
+        if let Ok(days) = env::var("MARKET_ARCHIVE_AFTER_DAYS") {
+            config.market.archive_after_days =
+                days.parse().unwrap_or(config.market.archive_after_days);
+        }
+
+        if let Ok(partition) = env::var("MARKET_PARTITION_MARKET_UPDATES") {
+            config.market.partition_market_updates = partition
+                .parse()
+                .unwrap_or(config.market.partition_market_updates);
+        }
+
+        if let Ok(months) = env::var("MARKET_PARTITION_MONTHS_AHEAD") {
+            config.market.partition_months_ahead = months
+                .parse()
+                .unwrap_or(config.market.partition_months_ahead);
+        }
+
+        if let Ok(interval) = env::var("MARKET_PARTITION_MAINTENANCE_SECS") {
+            config.market.partition_maintenance_secs = interval
+                .parse()
+                .unwrap_or(config.market.partition_maintenance_secs);
+        }
+
+        if let Ok(threshold) = env::var("MARKET_OPTIMISTIC_STAKE_THRESHOLD") {
+            config.market.optimistic_stake_threshold = threshold
+                .parse()
+                .unwrap_or(config.market.optimistic_stake_threshold);
+        }
+
+        if let Ok(interval) = env::var("MARKET_LIQUIDITY_RECOMMENDATION_SECS") {
+            config.market.liquidity_recommendation_secs = interval
+                .parse()
+                .unwrap_or(config.market.liquidity_recommendation_secs);
+        }
+
+        if let Ok(markets) = env::var("MARKET_LIQUIDITY_RECOMMENDATION_MIN_MARKETS") {
+            config.market.liquidity_recommendation_min_markets = markets
+                .parse()
+                .unwrap_or(config.market.liquidity_recommendation_min_markets);
+        }
+
+        if let Ok(interval) = env::var("MARKET_SCORE_AUDIT_SECS") {
+            config.market.score_audit_secs =
+                interval.parse().unwrap_or(config.market.score_audit_secs);
+        }
+
+        if let Ok(secs) = env::var("MARKET_LEADERBOARD_SNAPSHOT_SECS") {
+            config.market.leaderboard_snapshot_secs = secs
+                .parse()
+                .unwrap_or(config.market.leaderboard_snapshot_secs);
+        }
+
+        if let Ok(live) = env::var("MARKET_LIVE_SCORE_UPDATES") {
+            config.market.live_score_updates =
+                live.parse().unwrap_or(config.market.live_score_updates);
+        }
+
+        if let Ok(interval) = env::var("MARKET_ARBITRAGE_SCAN_SECS") {
+            config.market.arbitrage_scan_secs = interval
+                .parse()
+                .unwrap_or(config.market.arbitrage_scan_secs);
+        }
+
+        if let Ok(gap) = env::var("MARKET_ARBITRAGE_MIN_GAP") {
+            config.market.arbitrage_min_gap =
+                gap.parse().unwrap_or(config.market.arbitrage_min_gap);
+        }
+
+        if let Ok(interval) = env::var("MARKET_HEAT_REFRESH_SECS") {
+            config.market.heat_refresh_secs = interval
+                .parse()
+                .unwrap_or(config.market.heat_refresh_secs);
+        }
+
+        if let Ok(interval) = env::var("MARKET_RANKING_UPDATE_SECS") {
+            config.market.ranking_update_secs = interval
+                .parse()
+                .unwrap_or(config.market.ranking_update_secs);
+        }
+
+        // Connection pool configuration from environment
+        if let Ok(max) = env::var("DB_TRADING_MAX_CONNECTIONS") {
+            config.database.trading_max_connections = max
+                .parse()
+                .unwrap_or(config.database.trading_max_connections);
+        }
+
+        if let Ok(timeout) = env::var("DB_TRADING_ACQUIRE_TIMEOUT_SECS") {
+            config.database.trading_acquire_timeout_secs = timeout
+                .parse()
+                .unwrap_or(config.database.trading_acquire_timeout_secs);
+        }
+
+        if let Ok(timeout) = env::var("DB_TRADING_STATEMENT_TIMEOUT_SECS") {
+            config.database.trading_statement_timeout_secs = timeout
+                .parse()
+                .unwrap_or(config.database.trading_statement_timeout_secs);
+        }
+
+        if let Ok(max) = env::var("DB_ANALYTICS_MAX_CONNECTIONS") {
+            config.database.analytics_max_connections = max
+                .parse()
+                .unwrap_or(config.database.analytics_max_connections);
+        }
+
+        if let Ok(timeout) = env::var("DB_ANALYTICS_ACQUIRE_TIMEOUT_SECS") {
+            config.database.analytics_acquire_timeout_secs = timeout
+                .parse()
+                .unwrap_or(config.database.analytics_acquire_timeout_secs);
+        }
+
+        if let Ok(timeout) = env::var("DB_ANALYTICS_STATEMENT_TIMEOUT_SECS") {
+            config.database.analytics_statement_timeout_secs = timeout
+                .parse()
+                .unwrap_or(config.database.analytics_statement_timeout_secs);
+        }
+
+        if let Ok(dialect) = env::var("DB_DIALECT") {
+            config.database.dialect = dialect;
+        }
+
+        // Faucet configuration from environment
+        if let Ok(grant) = env::var("FAUCET_ONBOARDING_GRANT_RP") {
+            config.faucet.onboarding_grant_rp =
+                grant.parse().unwrap_or(config.faucet.onboarding_grant_rp);
+        }
+
+        if let Ok(threshold) = env::var("FAUCET_TOPUP_THRESHOLD_RP") {
+            config.faucet.topup_threshold_rp = threshold
+                .parse()
+                .unwrap_or(config.faucet.topup_threshold_rp);
+        }
+
+        if let Ok(amount) = env::var("FAUCET_TOPUP_AMOUNT_RP") {
+            config.faucet.topup_amount_rp = amount.parse().unwrap_or(config.faucet.topup_amount_rp);
+        }
+
+        if let Ok(cooldown) = env::var("FAUCET_TOPUP_COOLDOWN_HOURS") {
+            config.faucet.topup_cooldown_hours = cooldown
+                .parse()
+                .unwrap_or(config.faucet.topup_cooldown_hours);
+        }
+
+        if let Ok(days) = env::var("FAUCET_ACTIVE_WITHIN_DAYS") {
+            config.faucet.active_within_days =
+                days.parse().unwrap_or(config.faucet.active_within_days);
+        }
+
+        if let Ok(cap) = env::var("FAUCET_TOPUP_LIFETIME_CAP_RP") {
+            config.faucet.topup_lifetime_cap_rp =
+                cap.parse().unwrap_or(config.faucet.topup_lifetime_cap_rp);
+        }
+
+        if let Ok(interval) = env::var("FAUCET_SWEEP_SECS") {
+            config.faucet.sweep_interval_secs = interval
+                .parse()
+                .unwrap_or(config.faucet.sweep_interval_secs);
+        }
+
+        // Messaging configuration from environment
+        if let Ok(days) = env::var("MESSAGING_RETENTION_DAYS") {
+            config.messaging.retention_days =
+                days.parse().unwrap_or(config.messaging.retention_days);
+        }
+
+        if let Ok(hours) = env::var("MESSAGING_ACKED_RETENTION_HOURS") {
+            config.messaging.acked_retention_hours = hours
+                .parse()
+                .unwrap_or(config.messaging.acked_retention_hours);
+        }
+
+        if let Ok(interval) = env::var("MESSAGING_SWEEP_SECS") {
+            config.messaging.sweep_interval_secs = interval
+                .parse()
+                .unwrap_or(config.messaging.sweep_interval_secs);
+        }
+
+        // Webhook configuration from environment
+        let list = |value: String| -> Vec<String> {
+            value
+                .split(',')
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+                .collect()
+        };
+
+        if let Ok(urls) = env::var("WEBHOOK_URLS") {
+            config.webhooks.urls = list(urls);
+        }
+
+        if let Ok(secret) = env::var("WEBHOOK_SECRET") {
+            config.webhooks.secret = Some(secret.trim().to_string()).filter(|v| !v.is_empty());
+        }
+
+        if let Ok(events) = env::var("WEBHOOK_EVENTS") {
+            config.webhooks.events = list(events);
+        }
+
+        if let Ok(attempts) = env::var("WEBHOOK_MAX_ATTEMPTS") {
+            config.webhooks.max_attempts =
+                attempts.parse().unwrap_or(config.webhooks.max_attempts);
+        }
+
+        if let Ok(backoff) = env::var("WEBHOOK_BACKOFF_MS") {
+            config.webhooks.base_backoff_ms =
+                backoff.parse().unwrap_or(config.webhooks.base_backoff_ms);
+        }
+
+        if let Ok(timeout) = env::var("WEBHOOK_TIMEOUT_SECS") {
+            config.webhooks.timeout_secs = timeout.parse().unwrap_or(config.webhooks.timeout_secs);
+        }
+
+        if let Ok(stale) = env::var("WEBHOOK_STALE_PENDING_SECS") {
+            config.webhooks.stale_pending_secs =
+                stale.parse().unwrap_or(config.webhooks.stale_pending_secs);
+        }
+
+        if let Ok(interval) = env::var("WEBHOOK_SWEEP_SECS") {
+            config.webhooks.sweep_interval_secs = interval
+                .parse()
+                .unwrap_or(config.webhooks.sweep_interval_secs);
+        }
+
+        // Cache warming configuration from environment
+        if let Ok(targets) = env::var("CACHE_WARM_TARGETS") {
+            config.cache_warming.targets = list(targets);
+        }
+
+        if let Ok(limit) = env::var("CACHE_WARM_MARKET_LIMIT") {
+            config.cache_warming.market_limit =
+                limit.parse().unwrap_or(config.cache_warming.market_limit);
+        }
+
+        if let Ok(limit) = env::var("CACHE_WARM_DASHBOARD_LIMIT") {
+            config.cache_warming.dashboard_limit =
+                limit.parse().unwrap_or(config.cache_warming.dashboard_limit);
+        }
+
+        // Score quota configuration from environment
+        if let Ok(cap) = env::var("SCORE_DAILY_CAP") {
+            config.score_quota.daily_cap = cap.parse().unwrap_or(config.score_quota.daily_cap);
+        }
+
+        if let Ok(full) = env::var("SCORE_FULL_WEIGHT_PER_DAY") {
+            config.score_quota.full_weight_per_day = full
+                .parse()
+                .unwrap_or(config.score_quota.full_weight_per_day);
+        }
+
+        if let Ok(decay) = env::var("SCORE_DAILY_DECAY") {
+            config.score_quota.decay = decay.parse().unwrap_or(config.score_quota.decay);
+        }
+
         // Validate configuration
         config.validate();
 
@@ -94,6 +729,244 @@ impl Config {
             self.market.hold_period_hours = 1.0;
         }
 
+        // Ensure dispute window is non-negative (0 disables disputes)
+        if self.market.dispute_window_hours.is_nan() || self.market.dispute_window_hours < 0.0 {
+            eprintln!(
+                "⚠️  Invalid dispute_window_hours: {}, using default",
+                self.market.dispute_window_hours
+            );
+            self.market.dispute_window_hours = 48.0;
+        }
+
+        // Ensure dust epsilon is a small non-negative share count (0 disables dust clearing)
+        if !self.market.share_dust_epsilon.is_finite()
+            || self.market.share_dust_epsilon < 0.0
+            || self.market.share_dust_epsilon >= 1.0
+        {
+            eprintln!(
+                "⚠️  Invalid share_dust_epsilon: {}, using default",
+                self.market.share_dust_epsilon
+            );
+            self.market.share_dust_epsilon = 1e-6;
+        }
+
+        // Ensure reminders go out at most 30 days ahead, or not at all
+        if !(0.0..=720.0).contains(&self.market.closing_reminder_hours) {
+            eprintln!(
+                "⚠️  Invalid closing_reminder_hours: {}, using default",
+                self.market.closing_reminder_hours
+            );
+            self.market.closing_reminder_hours = 24.0;
+        }
+
+        // Ensure dead letters are kept for a positive, finite time
+        if !self.market.dead_letter_retention_hours.is_finite()
+            || self.market.dead_letter_retention_hours <= 0.0
+        {
+            eprintln!(
+                "⚠️  Invalid dead_letter_retention_hours: {}, using default",
+                self.market.dead_letter_retention_hours
+            );
+            self.market.dead_letter_retention_hours = 72.0;
+        }
+
+        // Ensure API keys get a usable, bounded request budget
+        if !(1..=crate::api_keys::MAX_RATE_LIMIT_PER_MINUTE as u32)
+            .contains(&self.market.api_key_rate_limit_per_minute)
+        {
+            eprintln!(
+                "⚠️  Invalid api_key_rate_limit_per_minute: {}, using default",
+                self.market.api_key_rate_limit_per_minute
+            );
+            self.market.api_key_rate_limit_per_minute = 120;
+        }
+
+        // Ensure markets are only archived once no dispute can reopen them
+        if !self.market.archive_after_days.is_finite()
+            || self.market.archive_after_days * 24.0 < self.market.dispute_window_hours
+        {
+            eprintln!(
+                "⚠️  Invalid archive_after_days: {} (dispute window is {}h), using default",
+                self.market.archive_after_days, self.market.dispute_window_hours
+            );
+            self.market.archive_after_days = (self.market.dispute_window_hours / 24.0).max(90.0);
+        }
+
+        // Ensure partitions are kept at most two years ahead
+        if !(1..=24).contains(&self.market.partition_months_ahead) {
+            eprintln!(
+                "⚠️  Invalid partition_months_ahead: {}, using default",
+                self.market.partition_months_ahead
+            );
+            self.market.partition_months_ahead = 3;
+        }
+
+        // Ensure the optimistic trade threshold is a non-negative stake
+        if !self.market.optimistic_stake_threshold.is_finite()
+            || self.market.optimistic_stake_threshold < 0.0
+        {
+            eprintln!(
+                "⚠️  Invalid optimistic_stake_threshold: {}, using default",
+                self.market.optimistic_stake_threshold
+            );
+            self.market.optimistic_stake_threshold = 5.0;
+        }
+
+        // Ensure a recommendation rests on at least one resolved market
+        if self.market.liquidity_recommendation_min_markets == 0 {
+            eprintln!("⚠️  Invalid liquidity_recommendation_min_markets: 0, using default");
+            self.market.liquidity_recommendation_min_markets = 5;
+        }
+
+        // Ensure a snapshot outlives the page that took it
+        if self.market.leaderboard_snapshot_secs == 0 {
+            eprintln!("⚠️  Invalid leaderboard_snapshot_secs: 0, using default");
+            self.market.leaderboard_snapshot_secs = 300;
+        }
+
+        // Ensure each pool can hand out a connection and waits a bounded time for one
+        if self.database.trading_max_connections == 0 {
+            eprintln!("⚠️  Invalid trading_max_connections: 0, using default");
+            self.database.trading_max_connections = 16;
+        }
+        if self.database.analytics_max_connections == 0 {
+            eprintln!("⚠️  Invalid analytics_max_connections: 0, using default");
+            self.database.analytics_max_connections = 4;
+        }
+        if self.database.trading_acquire_timeout_secs == 0 {
+            eprintln!("⚠️  Invalid trading_acquire_timeout_secs: 0, using default");
+            self.database.trading_acquire_timeout_secs = 5;
+        }
+        if self.database.analytics_acquire_timeout_secs == 0 {
+            eprintln!("⚠️  Invalid analytics_acquire_timeout_secs: 0, using default");
+            self.database.analytics_acquire_timeout_secs = 30;
+        }
+
+        // Ensure every faucet amount and period is a finite, non-negative number
+        let defaults = FaucetConfig::default();
+        for (name, value, default) in [
+            (
+                "onboarding_grant_rp",
+                &mut self.faucet.onboarding_grant_rp,
+                defaults.onboarding_grant_rp,
+            ),
+            (
+                "topup_threshold_rp",
+                &mut self.faucet.topup_threshold_rp,
+                defaults.topup_threshold_rp,
+            ),
+            (
+                "topup_amount_rp",
+                &mut self.faucet.topup_amount_rp,
+                defaults.topup_amount_rp,
+            ),
+            (
+                "topup_cooldown_hours",
+                &mut self.faucet.topup_cooldown_hours,
+                defaults.topup_cooldown_hours,
+            ),
+            (
+                "active_within_days",
+                &mut self.faucet.active_within_days,
+                defaults.active_within_days,
+            ),
+            (
+                "topup_lifetime_cap_rp",
+                &mut self.faucet.topup_lifetime_cap_rp,
+                defaults.topup_lifetime_cap_rp,
+            ),
+        ] {
+            if !value.is_finite() || *value < 0.0 {
+                eprintln!("⚠️  Invalid {}: {}, using default", name, value);
+                *value = default;
+            }
+        }
+
+        let defaults = MessagingConfig::default();
+        for (name, value, default) in [
+            (
+                "retention_days",
+                &mut self.messaging.retention_days,
+                defaults.retention_days,
+            ),
+            (
+                "acked_retention_hours",
+                &mut self.messaging.acked_retention_hours,
+                defaults.acked_retention_hours,
+            ),
+        ] {
+            if !value.is_finite() || *value < 0.0 {
+                eprintln!("⚠️  Invalid {}: {}, using default", name, value);
+                *value = default;
+            }
+        }
+
+        // Ensure webhook retries are bounded and a delivery is only presumed
+        // abandoned once every attempt it could have made is over
+        self.webhooks.max_attempts = self.webhooks.max_attempts.clamp(1, 20);
+        self.webhooks.base_backoff_ms = self.webhooks.base_backoff_ms.clamp(10, 60_000);
+        self.webhooks.timeout_secs = self.webhooks.timeout_secs.clamp(1, 120);
+        let retry_span_secs = self.webhooks.max_attempts as u64 * (self.webhooks.timeout_secs + 60);
+        if self.webhooks.stale_pending_secs < retry_span_secs {
+            eprintln!(
+                "⚠️  Invalid stale_pending_secs: {} (retries can take {}s), using {}",
+                self.webhooks.stale_pending_secs, retry_span_secs, retry_span_secs
+            );
+            self.webhooks.stale_pending_secs = retry_span_secs;
+        }
+
+        // Ensure only known cache warm targets are kept, and bounded group sizes
+        self.cache_warming.targets.retain(|target| {
+            match crate::cache_warming::WarmTarget::parse(target) {
+                Ok(_) => true,
+                Err(e) => {
+                    eprintln!("⚠️  Invalid cache warm target: {}, skipping", e);
+                    false
+                }
+            }
+        });
+        if !(1..=10_000).contains(&self.cache_warming.market_limit) {
+            eprintln!(
+                "⚠️  Invalid cache warm market_limit: {}, using default",
+                self.cache_warming.market_limit
+            );
+            self.cache_warming.market_limit = 200;
+        }
+        if !(1..=1_000).contains(&self.cache_warming.dashboard_limit) {
+            eprintln!(
+                "⚠️  Invalid cache warm dashboard_limit: {}, using default",
+                self.cache_warming.dashboard_limit
+            );
+            self.cache_warming.dashboard_limit = 20;
+        }
+
+        // Ensure the score quota caps, then discounts, within the cap
+        if self.score_quota.daily_cap < 1 {
+            eprintln!(
+                "⚠️  Invalid score daily_cap: {}, using default",
+                self.score_quota.daily_cap
+            );
+            self.score_quota.daily_cap = 50;
+        }
+        if self.score_quota.full_weight_per_day < 0 {
+            eprintln!(
+                "⚠️  Invalid score full_weight_per_day: {}, using default",
+                self.score_quota.full_weight_per_day
+            );
+            self.score_quota.full_weight_per_day = 10;
+        }
+        self.score_quota.full_weight_per_day = self
+            .score_quota
+            .full_weight_per_day
+            .min(self.score_quota.daily_cap);
+        if !(0.0..=1.0).contains(&self.score_quota.decay) {
+            eprintln!(
+                "⚠️  Invalid score decay: {}, using default",
+                self.score_quota.decay
+            );
+            self.score_quota.decay = 0.8;
+        }
+
         // Ensure Kelly fraction is within bounds
         if self.market.kelly_fraction < 0.0
             || self.market.kelly_fraction > self.market.max_kelly_fraction
@@ -122,5 +995,147 @@ impl Config {
         println!("   Hold Period Hours: {}", self.market.hold_period_hours);
         println!("   Kelly Fraction: {}", self.market.kelly_fraction);
         println!("   Max Kelly Fraction: {}", self.market.max_kelly_fraction);
+        println!(
+            "   Dispute Window Hours: {}",
+            self.market.dispute_window_hours
+        );
+        println!("   Share Dust Epsilon: {}", self.market.share_dust_epsilon);
+        println!(
+            "   Close Sweep Interval Secs: {}",
+            self.market.close_sweep_interval_secs
+        );
+        println!(
+            "   Closing Reminder Hours: {}",
+            self.market.closing_reminder_hours
+        );
+        println!(
+            "   Dead Letter Retention Hours: {}",
+            self.market.dead_letter_retention_hours
+        );
+        println!(
+            "   Market State Cache TTL Secs: {}",
+            self.market.market_state_cache_ttl_secs
+        );
+        println!(
+            "   Forecast Compaction: every {}s, {}",
+            self.market.forecast_compaction_interval_secs,
+            if self.market.archive_compacted_forecasts {
+                "archiving revisions"
+            } else {
+                "deleting revisions"
+            }
+        );
+        println!(
+            "   Weighted Prob Refresh Secs: {}",
+            self.market.weighted_prob_refresh_secs
+        );
+        println!(
+            "   API Key Rate Limit Per Minute: {}",
+            self.market.api_key_rate_limit_per_minute
+        );
+        println!(
+            "   Market Archival: every {}s, {} days after resolution",
+            self.market.archive_interval_secs, self.market.archive_after_days
+        );
+        println!(
+            "   Market Updates Partitions: {}, {} months ahead, maintenance every {}s",
+            if self.market.partition_market_updates {
+                "monthly"
+            } else {
+                "off"
+            },
+            self.market.partition_months_ahead,
+            self.market.partition_maintenance_secs
+        );
+        println!(
+            "   Optimistic Stake Threshold: {} RP",
+            self.market.optimistic_stake_threshold
+        );
+        println!(
+            "   Liquidity Recommendations: every {}s, from {}+ resolved markets per category",
+            self.market.liquidity_recommendation_secs,
+            self.market.liquidity_recommendation_min_markets
+        );
+        println!(
+            "   Score Checksum Audit: every {}s",
+            self.market.score_audit_secs
+        );
+        println!(
+            "   Leaderboard Snapshots: {}s",
+            self.market.leaderboard_snapshot_secs
+        );
+        println!(
+            "   Live Score Updates: {}",
+            if self.market.live_score_updates {
+                "on"
+            } else {
+                "off"
+            }
+        );
+        println!(
+            "   Arbitrage Scan: every {}s, gaps of {}+",
+            self.market.arbitrage_scan_secs, self.market.arbitrage_min_gap
+        );
+        println!(
+            "   Market Heat Refresh: every {}s",
+            self.market.heat_refresh_secs
+        );
+        println!(
+            "   Trading Pool: {} connections, {}s acquire timeout, {}s statement timeout",
+            self.database.trading_max_connections,
+            self.database.trading_acquire_timeout_secs,
+            self.database.trading_statement_timeout_secs
+        );
+        println!(
+            "   Analytics Pool: {} connections, {}s acquire timeout, {}s statement timeout",
+            self.database.analytics_max_connections,
+            self.database.analytics_acquire_timeout_secs,
+            self.database.analytics_statement_timeout_secs
+        );
+        println!("   Database Dialect: {}", self.database.dialect);
+        println!(
+            "   Faucet: {} RP onboarding, {} RP top-ups below {} RP (every {}h, active within {}d, {} RP cap), sweep every {}s",
+            self.faucet.onboarding_grant_rp,
+            self.faucet.topup_amount_rp,
+            self.faucet.topup_threshold_rp,
+            self.faucet.topup_cooldown_hours,
+            self.faucet.active_within_days,
+            self.faucet.topup_lifetime_cap_rp,
+            self.faucet.sweep_interval_secs
+        );
+        println!(
+            "   Messaging: unacked messages kept {}d (0 = until acked), acked {}h, sweep every {}s",
+            self.messaging.retention_days,
+            self.messaging.acked_retention_hours,
+            self.messaging.sweep_interval_secs
+        );
+        println!(
+            "   Webhooks: {} endpoint(s), {}, {} attempts, stale after {}s, sweep every {}s",
+            self.webhooks.urls.len(),
+            if self.webhooks.secret.is_some() {
+                "signed"
+            } else {
+                "unsigned"
+            },
+            self.webhooks.max_attempts,
+            self.webhooks.stale_pending_secs,
+            self.webhooks.sweep_interval_secs
+        );
+        println!(
+            "   Cache Warming: {} ({} markets, {} dashboards)",
+            if self.cache_warming.targets.is_empty() {
+                "off at startup".to_string()
+            } else {
+                self.cache_warming.targets.join(", ")
+            },
+            self.cache_warming.market_limit,
+            self.cache_warming.dashboard_limit
+        );
+        println!(
+            "   Score Quota: {} full-weight then x{} per prediction, none past {} a day",
+            self.score_quota.full_weight_per_day,
+            self.score_quota.decay,
+            self.score_quota.daily_cap
+        );
     }
 }
@@ -0,0 +1,286 @@
+//! Reputation-weighted consensus probability for binary markets.
+//!
+//! The market price is moved by whoever trades last and largest; the
+//! weighted consensus instead pools what every participant believes,
+//! weighting each user by reputation (RP held plus RP staked). A user's
+//! belief is their stated forecast if they have one, otherwise the price
+//! their most recent buy moved the market to. The pooled probability is
+//! stored on `events.weighted_prob` by a periodic refresh and shown beside
+//! the market price in market state.
+//!
+//! When an event resolves, both the final market price and a fresh
+//! consensus (taken before payouts move anyone's RP) are recorded in
+//! `consensus_accuracy`, so research can compare how well each called the
+//! outcome. The event's comment velocity at that moment goes in too (see
+//! `comment_buzz`), so the comparison can be narrowed to buzzy markets.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use sqlx::{PgPool, Postgres, Row, Transaction};
+
+use crate::api_error::{coded, ErrorCode};
+use crate::paper_predictions::{brier_score, log_score};
+
+pub const MAX_LIMIT: i64 = 500;
+
+/// Each user's latest belief on each unresolved binary event and their
+/// reputation weight, pooled per event. `$1` narrows it to one event.
+const WEIGHTED_PROBS: &str = r#"
+    WITH beliefs AS (
+        SELECT DISTINCT ON (b.event_id, b.user_id) b.event_id, b.user_id, b.probability
+        FROM (
+            SELECT p.event_id, p.user_id, (p.prob_vector->>0)::float8 AS probability,
+                   0 AS source, p.created_at::timestamptz AS at
+            FROM predictions p
+            WHERE jsonb_typeof(p.prob_vector) = 'array'
+              AND jsonb_array_length(p.prob_vector) = 2
+              AND COALESCE(p.prediction_type, 'binary') = 'binary'
+            UNION ALL
+            SELECT mu.event_id, mu.user_id, mu.new_prob, 1, mu.created_at
+            FROM market_updates mu
+        ) b
+        JOIN events e ON e.id = b.event_id
+        WHERE e.outcome IS NULL
+          AND COALESCE(e.event_type, 'binary') = 'binary'
+          AND ($1::int IS NULL OR e.id = $1)
+          AND b.probability BETWEEN 0 AND 1
+        ORDER BY b.event_id, b.user_id, b.source, b.at DESC
+    )
+    SELECT b.event_id,
+           SUM(w.weight * b.probability) / NULLIF(SUM(w.weight), 0) AS weighted_prob,
+           COUNT(*) FILTER (WHERE w.weight > 0)::int AS contributors
+    FROM beliefs b
+    JOIN users u ON u.id = b.user_id
+    CROSS JOIN LATERAL (
+        SELECT GREATEST(COALESCE(u.rp_balance_ledger, 0) + COALESCE(u.rp_staked_ledger, 0), 0)
+                   ::float8 AS weight
+    ) w
+    GROUP BY b.event_id
+"#;
+
+pub async fn ensure_consensus_schema(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        "ALTER TABLE events
+             ADD COLUMN IF NOT EXISTS weighted_prob DOUBLE PRECISION,
+             ADD COLUMN IF NOT EXISTS weighted_prob_contributors INTEGER,
+             ADD COLUMN IF NOT EXISTS weighted_prob_updated_at TIMESTAMPTZ",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS consensus_accuracy (
+            event_id INTEGER PRIMARY KEY REFERENCES events(id) ON DELETE CASCADE,
+            outcome BOOLEAN NOT NULL,
+            market_prob DOUBLE PRECISION NOT NULL,
+            weighted_prob DOUBLE PRECISION,
+            contributors INTEGER NOT NULL,
+            recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "ALTER TABLE consensus_accuracy
+             ADD COLUMN IF NOT EXISTS comment_velocity DOUBLE PRECISION",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Recomputes `events.weighted_prob` for unresolved binary events (or just
+/// `event_id`), writing only the ones that changed. Returns how many did.
+pub async fn refresh_weighted_probs(pool: &PgPool, event_id: Option<i32>) -> Result<u64> {
+    let updated = sqlx::query(&format!(
+        r#"
+        WITH pooled AS ({})
+        UPDATE events e
+        SET weighted_prob = pooled.weighted_prob,
+            weighted_prob_contributors = pooled.contributors,
+            weighted_prob_updated_at = NOW()
+        FROM pooled
+        WHERE e.id = pooled.event_id
+          AND (e.weighted_prob IS DISTINCT FROM pooled.weighted_prob
+               OR e.weighted_prob_contributors IS DISTINCT FROM pooled.contributors)
+        "#,
+        WEIGHTED_PROBS
+    ))
+    .bind(event_id)
+    .execute(pool)
+    .await?;
+    Ok(updated.rows_affected())
+}
+
+/// Records the market price and a fresh consensus for a resolving event.
+/// Call before payouts; databases without the table are skipped.
+pub(crate) async fn record_resolution(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    outcome: bool,
+) -> Result<()> {
+    let table_exists: bool =
+        sqlx::query_scalar("SELECT to_regclass('consensus_accuracy') IS NOT NULL")
+            .fetch_one(&mut **tx)
+            .await?;
+    if !table_exists {
+        return Ok(());
+    }
+    let comment_velocity = crate::comment_buzz::velocity_at_resolution(tx, event_id).await?;
+    // The event is locked and still unresolved here, so the pooled query
+    // still sees it
+    sqlx::query(&format!(
+        r#"
+        WITH pooled AS ({})
+        INSERT INTO consensus_accuracy
+            (event_id, outcome, market_prob, weighted_prob, contributors, comment_velocity)
+        SELECT e.id, $2, COALESCE(e.market_prob, 0.5), pooled.weighted_prob,
+               COALESCE(pooled.contributors, 0), $3
+        FROM events e
+        LEFT JOIN pooled ON pooled.event_id = e.id
+        WHERE e.id = $1
+        ON CONFLICT (event_id) DO UPDATE
+        SET outcome = EXCLUDED.outcome,
+            market_prob = EXCLUDED.market_prob,
+            weighted_prob = EXCLUDED.weighted_prob,
+            contributors = EXCLUDED.contributors,
+            comment_velocity = EXCLUDED.comment_velocity,
+            recorded_at = NOW()
+        "#,
+        WEIGHTED_PROBS
+    ))
+    .bind(event_id)
+    .bind(outcome)
+    .bind(comment_velocity)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Drops the record of a resolution a dispute has reverted.
+pub(crate) async fn revert_resolution(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+) -> Result<()> {
+    let table_exists: bool =
+        sqlx::query_scalar("SELECT to_regclass('consensus_accuracy') IS NOT NULL")
+            .fetch_one(&mut **tx)
+            .await?;
+    if table_exists {
+        sqlx::query("DELETE FROM consensus_accuracy WHERE event_id = $1")
+            .bind(event_id)
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(())
+}
+
+fn scores(probs: &[(f64, bool)]) -> Value {
+    let n = probs.len() as f64;
+    let mean = |score: fn(f64, bool) -> f64| {
+        (n > 0.0).then(|| probs.iter().map(|&(p, o)| score(p, o)).sum::<f64>() / n)
+    };
+    json!({
+        "brier_score": mean(brier_score),
+        "log_score": mean(log_score),
+    })
+}
+
+/// Mean Brier and log scores of the market price and the weighted
+/// consensus over resolved events where both were recorded, with the most
+/// recent events. `min_comment_velocity` keeps only events that had at
+/// least that many comments per hour when they resolved.
+pub async fn get_accuracy(
+    pool: &PgPool,
+    category: Option<&str>,
+    min_comment_velocity: Option<f64>,
+    limit: i64,
+) -> Result<Value> {
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("limit must be between 1 and {}", MAX_LIMIT),
+        ));
+    }
+    if min_comment_velocity.is_some_and(|v| !v.is_finite() || v < 0.0) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "min_comment_velocity must be a non-negative number",
+        ));
+    }
+    let category = category.map(str::trim).filter(|c| !c.is_empty());
+    let rows = sqlx::query(
+        r#"
+        SELECT ca.event_id, e.title, ca.outcome, ca.market_prob, ca.weighted_prob,
+               ca.contributors, ca.comment_velocity, ca.recorded_at
+        FROM consensus_accuracy ca
+        JOIN events e ON e.id = ca.event_id
+        WHERE ca.weighted_prob IS NOT NULL
+          AND ($1::text IS NULL OR LOWER(e.category) = LOWER($1))
+          AND ($2::float8 IS NULL OR ca.comment_velocity >= $2)
+        ORDER BY ca.recorded_at DESC, ca.event_id DESC
+        "#,
+    )
+    .bind(category)
+    .bind(min_comment_velocity)
+    .fetch_all(pool)
+    .await?;
+
+    let mut market = Vec::with_capacity(rows.len());
+    let mut weighted = Vec::with_capacity(rows.len());
+    let mut weighted_better = 0;
+    for row in &rows {
+        let outcome: bool = row.get("outcome");
+        let market_prob: f64 = row.get("market_prob");
+        let weighted_prob: f64 = row.get("weighted_prob");
+        if brier_score(weighted_prob, outcome) < brier_score(market_prob, outcome) {
+            weighted_better += 1;
+        }
+        market.push((market_prob, outcome));
+        weighted.push((weighted_prob, outcome));
+    }
+
+    let recent: Vec<Value> = rows
+        .iter()
+        .take(limit as usize)
+        .map(|row| {
+            json!({
+                "event_id": row.get::<i32, _>("event_id"),
+                "title": row.get::<String, _>("title"),
+                "outcome": row.get::<bool, _>("outcome"),
+                "market_prob": row.get::<f64, _>("market_prob"),
+                "weighted_prob": row.get::<f64, _>("weighted_prob"),
+                "contributors": row.get::<i32, _>("contributors"),
+                "comment_velocity": row.get::<Option<f64>, _>("comment_velocity"),
+                "recorded_at": row.get::<DateTime<Utc>, _>("recorded_at"),
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "category": category,
+        "min_comment_velocity": min_comment_velocity,
+        "events": rows.len(),
+        "market": scores(&market),
+        "weighted": scores(&weighted),
+        "weighted_better": weighted_better,
+        "recent": recent,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_average_over_events() {
+        let report = scores(&[(0.8, true), (0.4, false)]);
+        let brier = (0.04 + 0.16) / 2.0;
+        let log = (0.8_f64.ln() + 0.6_f64.ln()) / 2.0;
+        assert!((report["brier_score"].as_f64().unwrap() - brier).abs() < 1e-12);
+        assert!((report["log_score"].as_f64().unwrap() - log).abs() < 1e-12);
+        assert!(scores(&[])["brier_score"].is_null());
+    }
+}
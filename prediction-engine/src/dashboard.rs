@@ -0,0 +1,153 @@
+//! The frontend dashboard's read model.
+//!
+//! The dashboard used to fetch accuracy, reputation, portfolio, a
+//! leaderboard slice, markets closing soon and score history from six
+//! endpoints, each with its own cache entry. `get_dashboard` assembles the
+//! same data in one response, running the independent reads concurrently.
+//! Reputation is the RP ledger (RP held plus RP staked), ranked the way the
+//! backend ranks profiles: by reputation, then prediction count, then id.
+//! Alongside it sits the user's daily scoring quota: how many more
+//! predictions today will count toward their scores, and for how much.
+//! Score history is realized P&L, one point per market in the order it was
+//! last realized, with the running total.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde_json::{json, Value};
+use sqlx::{PgPool, Row};
+
+use crate::api_error::{coded, ErrorCode};
+use crate::closing_soon;
+use crate::lmsr_core::from_ledger_units;
+use crate::rank_history;
+use crate::realized_pnl;
+use crate::score_quota::{self, QuotaPolicy};
+use crate::user_predictions;
+
+/// Users listed on each side of the user in the leaderboard slice.
+pub const LEADERBOARD_RADIUS: i64 = 2;
+pub const CLOSING_SOON_HOURS: i64 = 48;
+pub const CLOSING_SOON_LIMIT: i64 = 10;
+pub const HISTORY_POINTS: i64 = 30;
+
+/// Cache key for a user's dashboard in the engine's response cache.
+pub fn cache_key(user_id: i32) -> String {
+    format!("dashboard:{}", user_id)
+}
+
+pub async fn get_dashboard(pool: &PgPool, policy: &QuotaPolicy, user_id: i32) -> Result<Value> {
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Err(coded(ErrorCode::NotFound, "User not found"));
+    }
+
+    let (accuracy, portfolio, leaderboard, closing, history, quota) = tokio::try_join!(
+        user_predictions::population_context(pool, policy, user_id),
+        realized_pnl::get_portfolio(pool, user_id),
+        leaderboard_slice(pool, user_id),
+        closing_soon::closing_soon(
+            pool,
+            Duration::hours(CLOSING_SOON_HOURS),
+            Some(user_id),
+            CLOSING_SOON_LIMIT,
+        ),
+        score_history(pool, user_id),
+        score_quota::daily_quota(pool, policy, user_id),
+    )?;
+
+    Ok(json!({
+        "user_id": user_id,
+        "generated_at": Utc::now(),
+        "accuracy": accuracy,
+        "reputation": {
+            "rp_balance": portfolio["rp_balance"],
+            "rp_staked": portfolio["rp_staked"],
+            "total_reputation": portfolio["total_reputation"],
+            "rank": leaderboard["rank"],
+            "ranked_users": leaderboard["ranked_users"],
+            "scoring_quota": quota,
+        },
+        "portfolio": portfolio,
+        "leaderboard": leaderboard,
+        "closing_soon": closing,
+        "score_history": history,
+    }))
+}
+
+/// The user's rank and the users within `LEADERBOARD_RADIUS` places of it.
+async fn leaderboard_slice(pool: &PgPool, user_id: i32) -> Result<Value> {
+    let rows = sqlx::query(&format!(
+        "WITH ranked AS ({})
+         SELECT r.*
+         FROM ranked r, (SELECT rank FROM ranked WHERE id = $1) me
+         WHERE r.rank BETWEEN me.rank - $2 AND me.rank + $2
+         ORDER BY r.rank",
+        rank_history::RANKING
+    ))
+    .bind(user_id)
+    .bind(LEADERBOARD_RADIUS)
+    .fetch_all(pool)
+    .await?;
+
+    let rank = rows
+        .iter()
+        .find(|row| row.get::<i32, _>("id") == user_id)
+        .map(|row| row.get::<i64, _>("rank"));
+    let entries: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            json!({
+                "rank": row.get::<i64, _>("rank"),
+                "user_id": row.get::<i32, _>("id"),
+                "username": row.get::<String, _>("username"),
+                "total_reputation":
+                    from_ledger_units(row.get::<i64, _>("reputation_ledger") as i128),
+            })
+        })
+        .collect();
+    Ok(json!({
+        "rank": rank,
+        "ranked_users": rows.first().map_or(0, |row| row.get::<i64, _>("ranked_users")),
+        "entries": entries,
+    }))
+}
+
+/// The last `HISTORY_POINTS` markets the user realized P&L on, oldest first.
+async fn score_history(pool: &PgPool, user_id: i32) -> Result<Vec<Value>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT * FROM (
+            SELECT rp.event_id, e.title, rp.realized_pnl_ledger, rp.updated_at,
+                   SUM(rp.realized_pnl_ledger)
+                       OVER (ORDER BY rp.updated_at, rp.event_id)::BIGINT AS cumulative_ledger
+            FROM user_realized_pnl rp
+            JOIN events e ON e.id = rp.event_id
+            WHERE rp.user_id = $1 AND e.competition_id IS NULL
+            ORDER BY rp.updated_at DESC, rp.event_id DESC
+            LIMIT $2
+        ) latest
+        ORDER BY updated_at, event_id
+        "#,
+    )
+    .bind(user_id)
+    .bind(HISTORY_POINTS)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| {
+            json!({
+                "event_id": row.get::<i32, _>("event_id"),
+                "title": row.get::<String, _>("title"),
+                "at": row.get::<DateTime<Utc>, _>("updated_at"),
+                "realized_pnl": from_ledger_units(row.get::<i64, _>("realized_pnl_ledger") as i128),
+                "lifetime_realized_pnl":
+                    from_ledger_units(row.get::<i64, _>("cumulative_ledger") as i128),
+            })
+        })
+        .collect())
+}
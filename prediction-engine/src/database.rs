@@ -1,13 +1,53 @@
 use anyhow::Result;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
 use sqlx::PgPool;
+use std::str::FromStr;
+use std::time::Duration;
 
-pub async fn create_pool(database_url: &str) -> Result<PgPool> {
-    Ok(
-        sqlx::postgres::PgPoolOptions::new()
-            .max_connections(20)
-            .connect(database_url)
-            .await?,
-    )
+use crate::config::DatabaseConfig;
+use crate::event_metadata::EventMetadata;
+
+/// Separate pools so reporting scans never take the connections trades need.
+#[derive(Clone)]
+pub struct Pools {
+    pub trading: PgPool,
+    pub analytics: PgPool,
+}
+
+pub async fn create_pool(
+    database_url: &str,
+    max_connections: u32,
+    acquire_timeout_secs: u64,
+    statement_timeout_secs: u64,
+) -> Result<PgPool> {
+    let mut options = PgConnectOptions::from_str(database_url)?;
+    if statement_timeout_secs > 0 {
+        options = options.options([("statement_timeout", format!("{}s", statement_timeout_secs))]);
+    }
+    Ok(PgPoolOptions::new()
+        .max_connections(max_connections)
+        .acquire_timeout(Duration::from_secs(acquire_timeout_secs))
+        .connect_with(options)
+        .await?)
+}
+
+pub async fn create_pools(database_url: &str, config: &DatabaseConfig) -> Result<Pools> {
+    Ok(Pools {
+        trading: create_pool(
+            database_url,
+            config.trading_max_connections,
+            config.trading_acquire_timeout_secs,
+            config.trading_statement_timeout_secs,
+        )
+        .await?,
+        analytics: create_pool(
+            database_url,
+            config.analytics_max_connections,
+            config.analytics_acquire_timeout_secs,
+            config.analytics_statement_timeout_secs,
+        )
+        .await?,
+    })
 }
 
 #[derive(Debug, serde::Serialize, sqlx::FromRow, ts_rs::TS)]
@@ -23,6 +63,8 @@ pub struct MarketEvent {
     pub market_prob: f64,
     pub liquidity_b: f64,
     pub cumulative_stake: f64,
+    #[sqlx(flatten)]
+    pub metadata: EventMetadata,
 }
 
 pub async fn get_events(pool: &PgPool, limit: i64) -> Result<Vec<MarketEvent>> {
@@ -38,7 +80,13 @@ pub async fn get_events(pool: &PgPool, limit: i64) -> Result<Vec<MarketEvent>> {
           event_type,
           COALESCE(market_prob, 0.5) as market_prob,
           COALESCE(liquidity_b, 100.0) as liquidity_b,
-          COALESCE(cumulative_stake, 0.0) as cumulative_stake
+          COALESCE(cumulative_stake, 0.0) as cumulative_stake,
+          resolution_criteria,
+          fine_print,
+          value_unit,
+          value_lower_bound,
+          value_upper_bound,
+          source_url
         FROM events
         ORDER BY closing_date ASC NULLS LAST
         LIMIT $1
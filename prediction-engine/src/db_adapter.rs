@@ -1,10 +1,15 @@
 //! Database adapter layer for clean numeric conversions
-//! Eliminates scattered to_f64()/from_f64() calls throughout the codebase
+//! Eliminates scattered to_f64()/from_f64() calls throughout the codebase,
+//! and holds the transaction backend (Postgres or CockroachDB) the retry
+//! loops consult
 
+use crate::api_error::{coded, ErrorCode};
 use crate::lmsr_core::Side;
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
-use sqlx::Row;
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+use std::sync::OnceLock;
 use tracing::debug;
 
 /// Clean conversion helpers between database rows and core f64 math
@@ -22,6 +27,35 @@ impl DbAdapter {
     }
 }
 
+/// The bankroll a market's trades and settlement move: the user's own RP,
+/// or their wallet in the competition that owns the market.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wallet {
+    Main,
+    Competition(i32),
+}
+
+impl Wallet {
+    /// Wallet for a market from its events.competition_id.
+    pub fn for_market(competition_id: Option<i32>) -> Self {
+        competition_id.map_or(Wallet::Main, Wallet::Competition)
+    }
+
+    /// Looks up the market's competition_id; for paths that don't already
+    /// hold the events row.
+    pub async fn for_event(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        event_id: i32,
+    ) -> Result<Self> {
+        let competition_id: Option<Option<i32>> =
+            sqlx::query_scalar("SELECT competition_id FROM events WHERE id = $1")
+                .bind(event_id)
+                .fetch_optional(&mut **tx)
+                .await?;
+        Ok(Wallet::for_market(competition_id.flatten()))
+    }
+}
+
 /// Clean market state structure for f64 math
 #[derive(Debug)]
 pub struct MarketState {
@@ -61,6 +95,43 @@ impl DbAdapter {
         Ok(())
     }
 
+    /// update_market_state, but only if the market is still in the `prev`
+    /// state it was priced from and is still open. Returns false when another
+    /// trade (or a close or resolution) got there first.
+    pub async fn update_market_state_if_unchanged(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        event_id: i32,
+        new_prob: f64,
+        new_cost: f64,
+        q_yes: f64,
+        q_no: f64,
+        prev: &MarketState,
+    ) -> Result<bool> {
+        let rows_affected = sqlx::query(
+            "UPDATE events SET
+                market_prob = $1,
+                cumulative_stake = $2,
+                q_yes = $3,
+                q_no = $4
+             WHERE id = $5
+               AND q_yes = $6 AND q_no = $7 AND liquidity_b = $8
+               AND outcome IS NULL AND closed_at IS NULL
+               AND COALESCE(closing_date > NOW(), true)",
+        )
+        .bind(new_prob)
+        .bind(new_cost)
+        .bind(q_yes)
+        .bind(q_no)
+        .bind(event_id)
+        .bind(prev.q_yes)
+        .bind(prev.q_no)
+        .bind(prev.liquidity_b)
+        .execute(&mut **tx)
+        .await?
+        .rows_affected();
+        Ok(rows_affected == 1)
+    }
+
     /// Update user balance from ledger units (bypasses f64 conversion for single rounding boundary)
     pub async fn update_user_balance_ledger(
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
@@ -141,6 +212,115 @@ impl DbAdapter {
         Ok(rows_affected > 0)
     }
 
+    /// update_user_balance_ledger against `wallet`.
+    pub async fn update_wallet_balance_ledger(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        wallet: Wallet,
+        user_id: i32,
+        balance_delta_ledger: i64,
+        staked_delta_ledger: i64,
+    ) -> Result<u64> {
+        Self::update_wallet_balances_ledger_batch(
+            tx,
+            wallet,
+            &[user_id],
+            &[balance_delta_ledger],
+            &[staked_delta_ledger],
+        )
+        .await
+    }
+
+    /// update_user_balances_ledger_batch against `wallet`, with the same
+    /// non-negative guards.
+    pub async fn update_wallet_balances_ledger_batch(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        wallet: Wallet,
+        user_ids: &[i32],
+        balance_deltas: &[i64],
+        staked_deltas: &[i64],
+    ) -> Result<u64> {
+        let competition_id = match wallet {
+            Wallet::Main if user_ids.len() == 1 => {
+                return Self::update_user_balance_ledger(
+                    tx,
+                    user_ids[0],
+                    balance_deltas[0],
+                    staked_deltas[0],
+                )
+                .await
+            }
+            Wallet::Main => {
+                return Self::update_user_balances_ledger_batch(
+                    tx,
+                    user_ids,
+                    balance_deltas,
+                    staked_deltas,
+                )
+                .await
+            }
+            Wallet::Competition(competition_id) => competition_id,
+        };
+        if user_ids.is_empty() {
+            return Ok(0);
+        }
+        let rows_affected = sqlx::query(
+            "UPDATE competition_wallets w SET
+                balance_ledger = w.balance_ledger + t.balance_delta,
+                staked_ledger  = w.staked_ledger  + t.staked_delta
+             FROM UNNEST($2::int[], $3::bigint[], $4::bigint[])
+                  AS t(user_id, balance_delta, staked_delta)
+             WHERE w.competition_id = $1
+               AND w.user_id = t.user_id
+               AND (w.balance_ledger + t.balance_delta) >= 0
+               AND (w.staked_ledger  + t.staked_delta) >= 0",
+        )
+        .bind(competition_id)
+        .bind(user_ids)
+        .bind(balance_deltas)
+        .bind(staked_deltas)
+        .execute(&mut **tx)
+        .await?
+        .rows_affected();
+        Ok(rows_affected)
+    }
+
+    /// deduct_user_cost_ledger against `wallet`. Buying into a competition
+    /// market without having joined is an error, not insufficient funds.
+    pub async fn deduct_wallet_cost_ledger(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        wallet: Wallet,
+        user_id: i32,
+        cost_ledger: i64,
+    ) -> Result<bool> {
+        let Wallet::Competition(competition_id) = wallet else {
+            return Self::deduct_user_cost_ledger(tx, user_id, cost_ledger).await;
+        };
+        let rows =
+            Self::update_wallet_balance_ledger(tx, wallet, user_id, -cost_ledger, cost_ledger)
+                .await?;
+        if rows > 0 {
+            return Ok(true);
+        }
+        let joined: bool = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM competition_wallets
+                            WHERE competition_id = $1 AND user_id = $2)",
+        )
+        .bind(competition_id)
+        .bind(user_id)
+        .fetch_one(&mut **tx)
+        .await?;
+        if !joined {
+            return Err(coded(
+                ErrorCode::InvalidRequest,
+                format!(
+                    "Not entered in competition {}: join it to trade its markets",
+                    competition_id
+                ),
+            ));
+        }
+        Ok(false)
+    }
+
     /// Record market update with f64 values
     pub async fn record_market_update(
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
@@ -245,6 +425,73 @@ impl DbAdapter {
         Ok(())
     }
 
+    /// update_user_shares_ledger, but only if the position is still at
+    /// `expected_version` (`None`: the user had no position). Returns false
+    /// when another trade changed the position first.
+    pub async fn update_user_shares_ledger_if_version(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: i32,
+        event_id: i32,
+        side: Side,
+        shares_delta: f64,
+        cost_ledger: i64,
+        expected_version: Option<i32>,
+    ) -> Result<bool> {
+        let Some(version) = expected_version else {
+            let query = match side {
+                Side::Yes => {
+                    "INSERT INTO user_shares (user_id, event_id, yes_shares, no_shares, total_staked_ledger, staked_yes_ledger, staked_no_ledger, version)
+                     VALUES ($1, $2, $3, 0, $4, $4, 0, 1)
+                     ON CONFLICT (user_id, event_id) DO NOTHING"
+                }
+                Side::No => {
+                    "INSERT INTO user_shares (user_id, event_id, yes_shares, no_shares, total_staked_ledger, staked_yes_ledger, staked_no_ledger, version)
+                     VALUES ($1, $2, 0, $3, $4, 0, $4, 1)
+                     ON CONFLICT (user_id, event_id) DO NOTHING"
+                }
+            };
+            let rows_affected = sqlx::query(query)
+                .bind(user_id)
+                .bind(event_id)
+                .bind(shares_delta)
+                .bind(cost_ledger)
+                .execute(&mut **tx)
+                .await?
+                .rows_affected();
+            return Ok(rows_affected == 1);
+        };
+        let query = match side {
+            Side::Yes => {
+                "UPDATE user_shares SET
+                    yes_shares = yes_shares + $3,
+                    staked_yes_ledger = staked_yes_ledger + $4,
+                    total_staked_ledger = total_staked_ledger + $4,
+                    version = COALESCE(version, 1) + 1,
+                    last_updated = NOW()
+                 WHERE user_id = $1 AND event_id = $2 AND COALESCE(version, 1) = $5"
+            }
+            Side::No => {
+                "UPDATE user_shares SET
+                    no_shares = no_shares + $3,
+                    staked_no_ledger = staked_no_ledger + $4,
+                    total_staked_ledger = total_staked_ledger + $4,
+                    version = COALESCE(version, 1) + 1,
+                    last_updated = NOW()
+                 WHERE user_id = $1 AND event_id = $2 AND COALESCE(version, 1) = $5"
+            }
+        };
+        let rows_affected = sqlx::query(query)
+            .bind(user_id)
+            .bind(event_id)
+            .bind(shares_delta)
+            .bind(cost_ledger)
+            .bind(version)
+            .execute(&mut **tx)
+            .await?
+            .rows_affected();
+        Ok(rows_affected == 1)
+    }
+
     /// Update user shares with side-specific stake unwinding for sell operations
     pub async fn update_user_shares_with_side_unwind_ledger(
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
@@ -293,4 +540,310 @@ impl DbAdapter {
 
         Ok(())
     }
+
+    /// Zero one side of a position along with all of its stake, then drop the
+    /// row if nothing is left on either side.
+    pub async fn close_user_shares_side(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: i32,
+        event_id: i32,
+        side: Side,
+    ) -> Result<()> {
+        let query = match side {
+            Side::Yes => {
+                "UPDATE user_shares SET
+                    yes_shares = 0,
+                    total_staked_ledger = total_staked_ledger - staked_yes_ledger,
+                    staked_yes_ledger = 0,
+                    version = version + 1,
+                    last_updated = NOW()
+                 WHERE user_id = $1 AND event_id = $2"
+            }
+            Side::No => {
+                "UPDATE user_shares SET
+                    no_shares = 0,
+                    total_staked_ledger = total_staked_ledger - staked_no_ledger,
+                    staked_no_ledger = 0,
+                    version = version + 1,
+                    last_updated = NOW()
+                 WHERE user_id = $1 AND event_id = $2"
+            }
+        };
+        sqlx::query(query)
+            .bind(user_id)
+            .bind(event_id)
+            .execute(&mut **tx)
+            .await?;
+
+        sqlx::query(
+            "DELETE FROM user_shares
+             WHERE user_id = $1 AND event_id = $2
+               AND yes_shares = 0 AND no_shares = 0 AND total_staked_ledger = 0",
+        )
+        .bind(user_id)
+        .bind(event_id)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// The SQL backend transactions run against. Both speak the Postgres wire
+/// protocol; they differ in which isolation levels exist and in how
+/// contention surfaces as errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Dialect {
+    Postgres,
+    CockroachDb,
+}
+
+impl Dialect {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "postgres" | "postgresql" => Some(Dialect::Postgres),
+            "cockroachdb" | "cockroach" | "crdb" => Some(Dialect::CockroachDb),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Isolation {
+    ReadCommitted,
+    RepeatableRead,
+    Serializable,
+}
+
+/// SQLSTATE codes the retry loops care about.
+/// Reference: https://www.postgresql.org/docs/current/errcodes-appendix.html
+pub mod sqlstate {
+    // Class 40 — Transaction Rollback
+    pub const SERIALIZATION_FAILURE: &str = "40001";
+    pub const DEADLOCK_DETECTED: &str = "40P01";
+
+    // Class 55 — Object Not In Prerequisite State (lock_timeout expired)
+    pub const LOCK_NOT_AVAILABLE: &str = "55P03";
+
+    // Class 25 — Invalid Transaction State
+    pub const ACTIVE_SQL_TRANSACTION: &str = "25001";
+
+    // Class 23 — Integrity Constraint Violation (may indicate concurrent updates)
+    pub const UNIQUE_VIOLATION: &str = "23505";
+}
+
+/// How a backend opens transactions and which of its failures mean the
+/// whole transaction can simply be run again. The trade retry loops and the
+/// stress harness's fault injection go through this rather than assuming
+/// Postgres.
+pub trait TxBackend: Send + Sync + std::fmt::Debug {
+    fn dialect(&self) -> Dialect;
+
+    /// `SET TRANSACTION` for `isolation`, or for the nearest stronger level
+    /// when the backend lacks it.
+    fn isolation_sql(&self, isolation: Isolation) -> &'static str;
+
+    /// Whether a failure with SQLSTATE `code` is worth rerunning the
+    /// transaction for.
+    fn is_retryable(&self, code: &str) -> bool;
+
+    /// A statement that fails the transaction with a retryable error.
+    fn injected_failure_sql(&self) -> String;
+
+    /// Text in the message of `injected_failure_sql`'s error.
+    #[allow(dead_code)] // only the stress harness (lib) calls this, not the server binary
+    fn injected_failure_marker(&self) -> &'static str;
+
+    /// A statement that drops the connection it runs on.
+    fn kill_connection_sql(&self) -> &'static str;
+
+    /// A statement taking a lock on the key pair ($1, $2) held until the
+    /// transaction ends, or None when the backend has no advisory locks.
+    fn advisory_xact_lock_sql(&self) -> Option<&'static str>;
+}
+
+/// Message of the injected serialization failure on Postgres.
+pub const CHAOS_SERIALIZATION_FAILURE: &str = "chaos: injected serialization failure";
+
+#[derive(Debug)]
+pub struct PostgresBackend;
+
+impl TxBackend for PostgresBackend {
+    fn dialect(&self) -> Dialect {
+        Dialect::Postgres
+    }
+
+    fn isolation_sql(&self, isolation: Isolation) -> &'static str {
+        match isolation {
+            Isolation::ReadCommitted => "SET TRANSACTION ISOLATION LEVEL READ COMMITTED",
+            Isolation::RepeatableRead => "SET TRANSACTION ISOLATION LEVEL REPEATABLE READ",
+            Isolation::Serializable => "SET TRANSACTION ISOLATION LEVEL SERIALIZABLE",
+        }
+    }
+
+    fn is_retryable(&self, code: &str) -> bool {
+        matches!(
+            code,
+            sqlstate::SERIALIZATION_FAILURE
+                | sqlstate::DEADLOCK_DETECTED
+                | sqlstate::LOCK_NOT_AVAILABLE
+                | sqlstate::ACTIVE_SQL_TRANSACTION
+                | sqlstate::UNIQUE_VIOLATION
+        )
+    }
+
+    fn injected_failure_sql(&self) -> String {
+        format!(
+            "DO $$ BEGIN RAISE EXCEPTION '{}' USING ERRCODE = 'serialization_failure'; END $$",
+            CHAOS_SERIALIZATION_FAILURE
+        )
+    }
+
+    fn injected_failure_marker(&self) -> &'static str {
+        CHAOS_SERIALIZATION_FAILURE
+    }
+
+    fn kill_connection_sql(&self) -> &'static str {
+        "SELECT pg_terminate_backend(pg_backend_pid())"
+    }
+
+    fn advisory_xact_lock_sql(&self) -> Option<&'static str> {
+        Some("SELECT pg_advisory_xact_lock($1, $2)")
+    }
+}
+
+/// CockroachDB runs every transaction SERIALIZABLE unless the cluster has
+/// READ COMMITTED enabled, and reports all contention (including the
+/// deadlocks it breaks) as 40001 "restart transaction", often at COMMIT.
+/// 40003 (result ambiguous) is never retried: the commit may have applied.
+#[derive(Debug)]
+pub struct CockroachBackend {
+    /// `sql.txn.read_committed_isolation.enabled` is on
+    pub read_committed: bool,
+}
+
+impl TxBackend for CockroachBackend {
+    fn dialect(&self) -> Dialect {
+        Dialect::CockroachDb
+    }
+
+    fn isolation_sql(&self, isolation: Isolation) -> &'static str {
+        match isolation {
+            Isolation::ReadCommitted if self.read_committed => {
+                "SET TRANSACTION ISOLATION LEVEL READ COMMITTED"
+            }
+            _ => "SET TRANSACTION ISOLATION LEVEL SERIALIZABLE",
+        }
+    }
+
+    fn is_retryable(&self, code: &str) -> bool {
+        matches!(
+            code,
+            sqlstate::SERIALIZATION_FAILURE
+                | sqlstate::LOCK_NOT_AVAILABLE
+                | sqlstate::UNIQUE_VIOLATION
+        )
+    }
+
+    fn injected_failure_sql(&self) -> String {
+        "SELECT crdb_internal.force_retry('1h')".to_string()
+    }
+
+    fn injected_failure_marker(&self) -> &'static str {
+        "force_retry"
+    }
+
+    fn kill_connection_sql(&self) -> &'static str {
+        "CANCEL SESSION (SELECT session_id FROM [SHOW session_id])"
+    }
+
+    // No advisory locks here, so a user's concurrent trades still just
+    // retry against each other
+    fn advisory_xact_lock_sql(&self) -> Option<&'static str> {
+        None
+    }
+}
+
+static TX_BACKEND: OnceLock<Box<dyn TxBackend>> = OnceLock::new();
+
+/// The backend transactions run against: the configured one, or Postgres
+/// until `configure_tx_backend` runs.
+pub fn tx_backend() -> &'static dyn TxBackend {
+    match TX_BACKEND.get() {
+        Some(backend) => backend.as_ref(),
+        None => &PostgresBackend,
+    }
+}
+
+/// Picks the transaction backend for the process from `dialect` (postgres,
+/// cockroachdb, or auto to ask the server). Only the first call takes
+/// effect.
+pub async fn configure_tx_backend(pool: &PgPool, dialect: &str) -> Result<&'static dyn TxBackend> {
+    let dialect = match dialect.trim() {
+        "" | "auto" => {
+            let version: String = sqlx::query_scalar("SELECT version()")
+                .fetch_one(pool)
+                .await?;
+            if version.contains("CockroachDB") {
+                Dialect::CockroachDb
+            } else {
+                Dialect::Postgres
+            }
+        }
+        name => Dialect::parse(name).ok_or_else(|| {
+            anyhow!(
+                "Unknown database dialect '{}': use auto, postgres or cockroachdb",
+                name
+            )
+        })?,
+    };
+    let backend: Box<dyn TxBackend> = match dialect {
+        Dialect::Postgres => Box::new(PostgresBackend),
+        Dialect::CockroachDb => {
+            // Reading cluster settings needs privileges the engine may not
+            // have; without them, assume the SERIALIZABLE-only default
+            let read_committed: bool =
+                sqlx::query_scalar("SHOW CLUSTER SETTING sql.txn.read_committed_isolation.enabled")
+                    .fetch_one(pool)
+                    .await
+                    .unwrap_or(false);
+            Box::new(CockroachBackend { read_committed })
+        }
+    };
+    let _ = TX_BACKEND.set(backend);
+    Ok(tx_backend())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cockroach_falls_back_to_serializable_and_retries_only_restarts() {
+        let crdb = CockroachBackend {
+            read_committed: false,
+        };
+        assert_eq!(
+            crdb.isolation_sql(Isolation::ReadCommitted),
+            "SET TRANSACTION ISOLATION LEVEL SERIALIZABLE"
+        );
+        assert_eq!(
+            CockroachBackend {
+                read_committed: true
+            }
+            .isolation_sql(Isolation::ReadCommitted),
+            "SET TRANSACTION ISOLATION LEVEL READ COMMITTED"
+        );
+        assert!(crdb.is_retryable(sqlstate::SERIALIZATION_FAILURE));
+        assert!(!crdb.is_retryable("40003"));
+        assert!(PostgresBackend.is_retryable(sqlstate::DEADLOCK_DETECTED));
+        assert!(!PostgresBackend.is_retryable("23503"));
+        assert!(crdb.advisory_xact_lock_sql().is_none());
+        assert!(PostgresBackend.advisory_xact_lock_sql().is_some());
+
+        assert_eq!(Dialect::parse("CRDB"), Some(Dialect::CockroachDb));
+        assert_eq!(Dialect::parse("postgresql"), Some(Dialect::Postgres));
+        assert_eq!(Dialect::parse("mysql"), None);
+    }
 }
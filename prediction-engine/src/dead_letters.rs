@@ -0,0 +1,195 @@
+//! Dead-letter queue for real-time broadcasts.
+//!
+//! Broadcasts go out on a bounded tokio channel, which loses messages two
+//! ways: a send with no subscribers is dropped outright, and a subscriber
+//! that falls `BROADCAST_CAPACITY` messages behind has the oldest one
+//! evicted before it reads it. `InFlight` shadows the channel's buffer so
+//! the sender can tell when either is about to happen and hand the message
+//! back; it is then stored in `broadcast_dead_letters`, and clients that
+//! reconnect or were told they lagged catch up through `backfill`.
+//!
+//! Rows older than the configured retention are pruned as new ones arrive.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use sqlx::{PgPool, Row};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+/// Messages the broadcast channel holds for its slowest subscriber. A power
+/// of two, since tokio rounds a channel's capacity up to one.
+pub const BROADCAST_CAPACITY: usize = 128;
+
+pub const NO_SUBSCRIBERS: &str = "no_subscribers";
+pub const SUBSCRIBER_LAGGED: &str = "subscriber_lagged";
+
+/// Copies of the last `BROADCAST_CAPACITY` messages sent, oldest first.
+/// These are exactly the messages the channel still buffers, so when the
+/// slowest subscriber has all of them unread the front is the one the next
+/// send evicts.
+#[derive(Clone, Default)]
+pub struct InFlight(Arc<Mutex<VecDeque<String>>>);
+
+impl InFlight {
+    /// Sends `msg` and returns the messages that will not reach every
+    /// subscriber, with the reason for each.
+    pub fn send(&self, tx: &broadcast::Sender<String>, msg: String) -> Vec<(String, &'static str)> {
+        let mut buffered = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let mut undelivered = Vec::new();
+        if tx.len() >= BROADCAST_CAPACITY {
+            if let Some(evicted) = buffered.pop_front() {
+                undelivered.push((evicted, SUBSCRIBER_LAGGED));
+            }
+        }
+        match tx.send(msg.clone()) {
+            Ok(_) => {
+                buffered.push_back(msg);
+                while buffered.len() > BROADCAST_CAPACITY {
+                    buffered.pop_front();
+                }
+            }
+            Err(_) => undelivered.push((msg, NO_SUBSCRIBERS)),
+        }
+        undelivered
+    }
+}
+
+pub async fn ensure_dead_letter_table(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS broadcast_dead_letters (
+            id BIGSERIAL PRIMARY KEY,
+            message_type VARCHAR(64) NOT NULL,
+            message JSONB NOT NULL,
+            reason VARCHAR(32) NOT NULL,
+            sent_at TIMESTAMPTZ NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_broadcast_dead_letters_sent_at
+         ON broadcast_dead_letters(sent_at, id)",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Stores one undelivered broadcast and prunes rows past `retention_hours`.
+pub async fn record(
+    pool: &PgPool,
+    message: &str,
+    reason: &str,
+    retention_hours: f64,
+) -> Result<()> {
+    let message: Value = serde_json::from_str(message)?;
+    let message_type = message["type"].as_str().unwrap_or("unknown").to_string();
+    let sent_at = message["timestamp"]
+        .as_str()
+        .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+        .map(|ts| ts.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now);
+
+    sqlx::query(
+        "INSERT INTO broadcast_dead_letters (message_type, message, reason, sent_at)
+         VALUES ($1, $2, $3, $4)",
+    )
+    .bind(&message_type)
+    .bind(&message)
+    .bind(reason)
+    .bind(sent_at)
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "DELETE FROM broadcast_dead_letters
+         WHERE sent_at < NOW() - $1 * INTERVAL '1 hour'",
+    )
+    .bind(retention_hours)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Undelivered broadcasts sent after `since`, oldest first, as the original
+/// messages. `has_more` means the client should ask again from the last
+/// `sent_at` returned.
+pub async fn backfill(pool: &PgPool, since: DateTime<Utc>, limit: i64) -> Result<Value> {
+    let rows = sqlx::query(
+        "SELECT id, message, reason, sent_at
+         FROM broadcast_dead_letters
+         WHERE sent_at > $1
+         ORDER BY sent_at, id
+         LIMIT $2",
+    )
+    .bind(since)
+    .bind(limit + 1)
+    .fetch_all(pool)
+    .await?;
+
+    let has_more = rows.len() as i64 > limit;
+    let messages: Vec<Value> = rows
+        .iter()
+        .take(limit as usize)
+        .map(|row| {
+            json!({
+                "id": row.get::<i64, _>("id"),
+                "reason": row.get::<String, _>("reason"),
+                "sent_at": row.get::<DateTime<Utc>, _>("sent_at"),
+                "message": row.get::<Value, _>("message"),
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "since": since,
+        "count": messages.len(),
+        "has_more": has_more,
+        "messages": messages,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sends_without_subscribers_are_returned() {
+        let (tx, rx) = broadcast::channel::<String>(BROADCAST_CAPACITY);
+        drop(rx);
+        let in_flight = InFlight::default();
+        let undelivered = in_flight.send(&tx, "a".to_string());
+        assert_eq!(undelivered, vec![("a".to_string(), NO_SUBSCRIBERS)]);
+    }
+
+    #[test]
+    fn evicted_message_is_returned_once_a_subscriber_falls_behind() {
+        let (tx, mut rx) = broadcast::channel::<String>(BROADCAST_CAPACITY);
+        let in_flight = InFlight::default();
+        for i in 0..BROADCAST_CAPACITY {
+            assert!(in_flight.send(&tx, i.to_string()).is_empty());
+        }
+        let undelivered = in_flight.send(&tx, "next".to_string());
+        assert_eq!(undelivered, vec![("0".to_string(), SUBSCRIBER_LAGGED)]);
+        // The subscriber lost exactly the message handed back
+        assert!(matches!(
+            rx.try_recv(),
+            Err(broadcast::error::TryRecvError::Lagged(1))
+        ));
+        assert_eq!(rx.try_recv().unwrap(), "1");
+    }
+
+    #[test]
+    fn nothing_is_returned_while_subscribers_keep_up() {
+        let (tx, mut rx) = broadcast::channel::<String>(BROADCAST_CAPACITY);
+        let in_flight = InFlight::default();
+        for i in 0..BROADCAST_CAPACITY * 3 {
+            assert!(in_flight.send(&tx, i.to_string()).is_empty());
+            assert_eq!(rx.try_recv().unwrap(), i.to_string());
+        }
+    }
+}
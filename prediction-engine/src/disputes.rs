@@ -0,0 +1,455 @@
+// Resolution disputes: for a window after a binary market resolves
+// (MARKET_DISPUTE_WINDOW_HOURS, default 48), an admin can revert the
+// resolution and optionally re-resolve it with the corrected outcome.
+//
+// Reverting replays the payout journal backwards. Every binary resolution
+// writes one resolution_payouts row per position it settled: the shares
+// and stakes it cleared and the RP it credited. Reverting claws those
+// credits back, restores the positions (and the staked RP behind them) and
+// reopens the event. Revert and re-resolve share one transaction, and every
+// resolution, revert and re-resolution gets a resolution_audit row.
+//
+// v1 scope: binary events only. A revert is refused, with nothing changed,
+// when a paid-out user no longer holds the RP to return; that case needs a
+// manual settlement rather than a negative balance.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::{PgPool, Postgres, Row, Transaction};
+
+use crate::api_error::{coded, ErrorCode};
+use crate::config::Config;
+use crate::db_adapter::{DbAdapter, Wallet};
+use crate::lmsr_api::Resolution;
+
+pub const ACTION_RESOLVED: &str = "resolved";
+pub const ACTION_REVERTED: &str = "reverted";
+
+/// One settled position, as resolve_event_transaction cleared it.
+pub(crate) struct SettledPosition {
+    pub user_id: i32,
+    pub yes_shares: f64,
+    pub no_shares: f64,
+    pub staked_yes_ledger: i64,
+    pub staked_no_ledger: i64,
+    pub payout_ledger: i64,
+}
+
+pub(crate) struct AuditEntry<'a> {
+    pub action: &'static str,
+    pub outcome: Option<&'a str>,
+    pub previous_outcome: Option<&'a str>,
+    pub actor: Option<&'a str>,
+    pub reason: Option<&'a str>,
+    pub positions: i32,
+    pub amount_ledger: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DisputeResult {
+    pub event_id: i32,
+    pub previous_outcome: String,
+    pub resolved_at: DateTime<Utc>,
+    pub positions_restored: usize,
+    pub clawback_ledger: i64,
+    /// Set when the revert was followed by a re-resolution.
+    pub new_outcome: Option<String>,
+    /// Per-user settlement from the re-resolution, if any.
+    pub payouts: Vec<crate::lmsr_api::ResolutionPayout>,
+}
+
+pub async fn ensure_dispute_tables(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS resolution_payouts (
+            id BIGSERIAL PRIMARY KEY,
+            event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            outcome VARCHAR(50) NOT NULL,
+            yes_shares DOUBLE PRECISION NOT NULL,
+            no_shares DOUBLE PRECISION NOT NULL,
+            staked_yes_ledger BIGINT NOT NULL,
+            staked_no_ledger BIGINT NOT NULL,
+            payout_ledger BIGINT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            reverted_at TIMESTAMPTZ
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_resolution_payouts_open
+         ON resolution_payouts (event_id) WHERE reverted_at IS NULL",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS resolution_audit (
+            id BIGSERIAL PRIMARY KEY,
+            event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+            action VARCHAR(20) NOT NULL,
+            outcome VARCHAR(50),
+            previous_outcome VARCHAR(50),
+            actor TEXT,
+            reason TEXT,
+            positions INTEGER NOT NULL DEFAULT 0,
+            amount_ledger BIGINT NOT NULL DEFAULT 0,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub(crate) async fn journal_payout(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    outcome: &str,
+    position: &SettledPosition,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO resolution_payouts
+             (event_id, user_id, outcome, yes_shares, no_shares,
+              staked_yes_ledger, staked_no_ledger, payout_ledger)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+    )
+    .bind(event_id)
+    .bind(position.user_id)
+    .bind(outcome)
+    .bind(position.yes_shares)
+    .bind(position.no_shares)
+    .bind(position.staked_yes_ledger)
+    .bind(position.staked_no_ledger)
+    .bind(position.payout_ledger)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+pub(crate) async fn record_audit(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    entry: &AuditEntry<'_>,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO resolution_audit
+             (event_id, action, outcome, previous_outcome, actor, reason, positions, amount_ledger)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+    )
+    .bind(event_id)
+    .bind(entry.action)
+    .bind(entry.outcome)
+    .bind(entry.previous_outcome)
+    .bind(entry.actor)
+    .bind(entry.reason)
+    .bind(entry.positions)
+    .bind(entry.amount_ledger)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Whether a resolution at `resolved_at` can still be disputed at `now`.
+pub fn within_dispute_window(
+    resolved_at: DateTime<Utc>,
+    window_hours: f64,
+    now: DateTime<Utc>,
+) -> bool {
+    let window = Duration::milliseconds((window_hours * 3_600_000.0).round() as i64);
+    now < resolved_at + window
+}
+
+/// Reverts a binary event's resolution and, with `corrected_outcome`,
+/// re-resolves it, all in one transaction.
+pub async fn dispute_resolution(
+    pool: &PgPool,
+    config: &Config,
+    event_id: i32,
+    corrected_outcome: Option<Resolution>,
+    actor: &str,
+    reason: &str,
+) -> Result<DisputeResult> {
+    if actor.trim().is_empty() || reason.trim().is_empty() {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "actor and reason must be provided",
+        ));
+    }
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE")
+        .execute(&mut *tx)
+        .await?;
+
+    let event = sqlx::query(
+        "SELECT outcome, resolved_at, COALESCE(event_type, 'binary') AS event_type,
+                competition_id
+         FROM events WHERE id = $1 FOR UPDATE",
+    )
+    .bind(event_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| coded(ErrorCode::NotFound, "Event not found"))?;
+
+    let event_type: String = event.get("event_type");
+    if event_type != "binary" {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "Disputes only support binary events",
+        ));
+    }
+    // Clawback reads and debits users.rp_balance_ledger; competition
+    // payouts went to competition wallets instead.
+    if event.get::<Option<i32>, _>("competition_id").is_some() {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "Disputes do not support competition markets",
+        ));
+    }
+    let previous_outcome: String = event
+        .get::<Option<String>, _>("outcome")
+        .ok_or_else(|| coded(ErrorCode::InvalidRequest, "Event is not resolved"))?;
+    let resolved_at: DateTime<Utc> = event
+        .get::<Option<DateTime<Utc>>, _>("resolved_at")
+        .ok_or_else(|| coded(ErrorCode::InvalidRequest, "Event is not resolved"))?;
+    if !within_dispute_window(resolved_at, config.market.dispute_window_hours, Utc::now()) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!(
+                "Dispute window closed: resolved at {}, window is {} hours",
+                resolved_at.to_rfc3339(),
+                config.market.dispute_window_hours
+            ),
+        ));
+    }
+
+    let payouts = sqlx::query(
+        "SELECT rp.id, rp.user_id, rp.yes_shares, rp.no_shares,
+                rp.staked_yes_ledger, rp.staked_no_ledger, rp.payout_ledger,
+                u.rp_balance_ledger
+         FROM resolution_payouts rp
+         JOIN users u ON u.id = rp.user_id
+         WHERE rp.event_id = $1 AND rp.reverted_at IS NULL
+         ORDER BY rp.id
+         FOR UPDATE OF rp, u",
+    )
+    .bind(event_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let short: Vec<String> = payouts
+        .iter()
+        .filter(|row| row.get::<i64, _>("rp_balance_ledger") < row.get::<i64, _>("payout_ledger"))
+        .map(|row| row.get::<i32, _>("user_id").to_string())
+        .collect();
+    if !short.is_empty() {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!(
+                "Cannot claw back payouts: user(s) {} no longer hold the RP they were paid",
+                short.join(", ")
+            ),
+        ));
+    }
+
+    let mut clawback_ledger = 0i64;
+    for row in &payouts {
+        let user_id: i32 = row.get("user_id");
+        let staked_yes_ledger: i64 = row.get("staked_yes_ledger");
+        let staked_no_ledger: i64 = row.get("staked_no_ledger");
+        let payout_ledger: i64 = row.get("payout_ledger");
+
+        let updated = DbAdapter::update_user_balance_ledger(
+            &mut tx,
+            user_id,
+            -payout_ledger,
+            staked_yes_ledger + staked_no_ledger,
+        )
+        .await?;
+        if updated != 1 {
+            return Err(anyhow!("Failed to claw back payout for user {}", user_id));
+        }
+        // Undo the P&L the settlement realized; a re-resolution records its own
+        crate::realized_pnl::record(
+            &mut tx,
+            user_id,
+            event_id,
+            staked_yes_ledger + staked_no_ledger - payout_ledger,
+        )
+        .await?;
+        crate::system_accounts::book_house(
+            &mut tx,
+            Wallet::Main,
+            "clawback",
+            event_id,
+            user_id,
+            staked_yes_ledger + staked_no_ledger - payout_ledger,
+        )
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO user_shares
+                 (user_id, event_id, yes_shares, no_shares,
+                  staked_yes_ledger, staked_no_ledger, total_staked_ledger)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             ON CONFLICT (user_id, event_id) DO UPDATE SET
+                 yes_shares = EXCLUDED.yes_shares,
+                 no_shares = EXCLUDED.no_shares,
+                 staked_yes_ledger = EXCLUDED.staked_yes_ledger,
+                 staked_no_ledger = EXCLUDED.staked_no_ledger,
+                 total_staked_ledger = EXCLUDED.total_staked_ledger",
+        )
+        .bind(user_id)
+        .bind(event_id)
+        .bind(row.get::<f64, _>("yes_shares"))
+        .bind(row.get::<f64, _>("no_shares"))
+        .bind(staked_yes_ledger)
+        .bind(staked_no_ledger)
+        .bind(staked_yes_ledger + staked_no_ledger)
+        .execute(&mut *tx)
+        .await?;
+
+        clawback_ledger += payout_ledger;
+    }
+
+    sqlx::query(
+        "UPDATE resolution_payouts SET reverted_at = NOW()
+         WHERE event_id = $1 AND reverted_at IS NULL",
+    )
+    .bind(event_id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("UPDATE events SET outcome = NULL, resolved_at = NULL WHERE id = $1")
+        .bind(event_id)
+        .execute(&mut *tx)
+        .await?;
+    crate::forecasts::settle_event(&mut tx, event_id, None).await?;
+    crate::consensus::revert_resolution(&mut tx, event_id).await?;
+    crate::market_accuracy::revert_resolution(&mut tx, event_id).await?;
+    record_audit(
+        &mut tx,
+        event_id,
+        &AuditEntry {
+            action: ACTION_REVERTED,
+            outcome: None,
+            previous_outcome: Some(&previous_outcome),
+            actor: Some(actor),
+            reason: Some(reason),
+            positions: payouts.len() as i32,
+            amount_ledger: clawback_ledger,
+        },
+    )
+    .await?;
+
+    let mut new_payouts = Vec::new();
+    let new_outcome = match corrected_outcome {
+        Some(resolution) => {
+            new_payouts = crate::lmsr_api::resolve_event_transaction(
+                &mut tx,
+                event_id,
+                resolution,
+                Some(actor),
+                Some(reason),
+            )
+            .await?;
+            Some(resolution.as_outcome().to_string())
+        }
+        // A re-resolution reseals; a bare revert drops the event's inputs
+        None => {
+            crate::score_integrity::seal_event(&mut tx, event_id).await?;
+            None
+        }
+    };
+
+    tx.commit().await?;
+
+    Ok(DisputeResult {
+        event_id,
+        previous_outcome,
+        resolved_at,
+        positions_restored: payouts.len(),
+        clawback_ledger,
+        new_outcome,
+        payouts: new_payouts,
+    })
+}
+
+/// Audit trail and payout journal for one event, newest first.
+pub async fn get_resolution_history(pool: &PgPool, event_id: i32) -> Result<Value> {
+    let audit: Vec<Value> = sqlx::query(
+        "SELECT id, action, outcome, previous_outcome, actor, reason,
+                positions, amount_ledger, created_at
+         FROM resolution_audit WHERE event_id = $1
+         ORDER BY created_at DESC, id DESC",
+    )
+    .bind(event_id)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(|row| {
+        json!({
+            "id": row.get::<i64, _>("id"),
+            "action": row.get::<String, _>("action"),
+            "outcome": row.get::<Option<String>, _>("outcome"),
+            "previous_outcome": row.get::<Option<String>, _>("previous_outcome"),
+            "actor": row.get::<Option<String>, _>("actor"),
+            "reason": row.get::<Option<String>, _>("reason"),
+            "positions": row.get::<i32, _>("positions"),
+            "amount_ledger": row.get::<i64, _>("amount_ledger"),
+            "created_at": row.get::<DateTime<Utc>, _>("created_at"),
+        })
+    })
+    .collect();
+
+    // Anonymous markets keep payouts unattributed here too
+    let anonymous = crate::trade_privacy::is_anonymous(pool, event_id).await?;
+    let payouts: Vec<Value> = sqlx::query(
+        "SELECT user_id, outcome, yes_shares, no_shares, staked_yes_ledger,
+                staked_no_ledger, payout_ledger, created_at, reverted_at
+         FROM resolution_payouts WHERE event_id = $1
+         ORDER BY created_at DESC, id DESC",
+    )
+    .bind(event_id)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(|row| {
+        json!({
+            "user_id": (!anonymous).then(|| row.get::<i32, _>("user_id")),
+            "outcome": row.get::<String, _>("outcome"),
+            "yes_shares": row.get::<f64, _>("yes_shares"),
+            "no_shares": row.get::<f64, _>("no_shares"),
+            "staked_yes_ledger": row.get::<i64, _>("staked_yes_ledger"),
+            "staked_no_ledger": row.get::<i64, _>("staked_no_ledger"),
+            "payout_ledger": row.get::<i64, _>("payout_ledger"),
+            "created_at": row.get::<DateTime<Utc>, _>("created_at"),
+            "reverted_at": row.get::<Option<DateTime<Utc>>, _>("reverted_at"),
+        })
+    })
+    .collect();
+
+    Ok(json!({
+        "event_id": event_id,
+        "audit": audit,
+        "payouts": payouts,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dispute_window_is_measured_from_resolution() {
+        let resolved_at = Utc::now() - Duration::hours(47);
+        assert!(within_dispute_window(resolved_at, 48.0, Utc::now()));
+        assert!(!within_dispute_window(resolved_at, 46.5, Utc::now()));
+        assert!(!within_dispute_window(resolved_at, 0.0, Utc::now()));
+    }
+}
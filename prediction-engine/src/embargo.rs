@@ -0,0 +1,208 @@
+//! Trading embargo windows per market.
+//!
+//! Some markets have to stop trading around scheduled announcements, e.g.
+//! from ten minutes before a data release until it is out. Admins attach
+//! `[starts_at, ends_at)` windows to an event in `event_embargoes`; while
+//! one is open, buys and sells on the market fail with "Market embargoed".
+//! Windows are kept after they end so the admin listing shows what was
+//! paused and why. Market state carries the open and upcoming windows with
+//! their timestamps; its `active` flag is computed at load time, so a cached
+//! state can lag a window's start or end by the cache TTL, but the trade
+//! guard always reads the table.
+
+use crate::api_error::{coded, ErrorCode};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+
+pub const ERR_MARKET_EMBARGOED: &str = "Market embargoed";
+
+#[derive(Debug, Clone, Serialize, ts_rs::TS)]
+#[ts(export, export_to = "../../shared/types/EmbargoWindow.ts")]
+pub struct EmbargoWindow {
+    pub id: i64,
+    pub event_id: i32,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub reason: Option<String>,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What market state shows: whether trading is paused now, until when, and
+/// the windows that have not ended yet.
+#[derive(Debug, Clone, Serialize, ts_rs::TS)]
+#[ts(export, export_to = "../../shared/types/MarketEmbargo.ts")]
+pub struct MarketEmbargo {
+    pub active: bool,
+    pub until: Option<DateTime<Utc>>,
+    pub windows: Vec<EmbargoWindow>,
+}
+
+pub async fn ensure_embargo_table(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS event_embargoes (
+            id BIGSERIAL PRIMARY KEY,
+            event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+            starts_at TIMESTAMPTZ NOT NULL,
+            ends_at TIMESTAMPTZ NOT NULL,
+            reason TEXT,
+            created_by VARCHAR(255) NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            CHECK (ends_at > starts_at)
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_event_embargoes_event
+         ON event_embargoes(event_id, ends_at)",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+fn row_to_window(row: &sqlx::postgres::PgRow) -> EmbargoWindow {
+    EmbargoWindow {
+        id: row.get("id"),
+        event_id: row.get("event_id"),
+        starts_at: row.get("starts_at"),
+        ends_at: row.get("ends_at"),
+        reason: row.get("reason"),
+        created_by: row.get("created_by"),
+        created_at: row.get("created_at"),
+    }
+}
+
+/// Trade guard: fails with ERR_MARKET_EMBARGOED while a window is open,
+/// naming the latest end among the open windows.
+pub async fn ensure_not_embargoed(conn: &mut sqlx::PgConnection, event_id: i32) -> Result<()> {
+    let until: Option<DateTime<Utc>> = sqlx::query_scalar(
+        "SELECT MAX(ends_at) FROM event_embargoes
+         WHERE event_id = $1 AND starts_at <= NOW() AND ends_at > NOW()",
+    )
+    .bind(event_id)
+    .fetch_one(conn)
+    .await?;
+    match until {
+        Some(until) => Err(coded(
+            ErrorCode::MarketEmbargoed,
+            format!("{} until {}", ERR_MARKET_EMBARGOED, until.to_rfc3339()),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Open and upcoming windows for market state.
+pub async fn load_embargo(pool: &PgPool, event_id: i32) -> Result<MarketEmbargo> {
+    let windows: Vec<EmbargoWindow> = sqlx::query(
+        "SELECT id, event_id, starts_at, ends_at, reason, created_by, created_at
+         FROM event_embargoes
+         WHERE event_id = $1 AND ends_at > NOW()
+         ORDER BY starts_at, id",
+    )
+    .bind(event_id)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(row_to_window)
+    .collect();
+
+    let now = Utc::now();
+    let until = windows
+        .iter()
+        .filter(|w| w.starts_at <= now)
+        .map(|w| w.ends_at)
+        .max();
+    Ok(MarketEmbargo {
+        active: until.is_some(),
+        until,
+        windows,
+    })
+}
+
+/// Every window on the event, past ones included, newest start first.
+pub async fn list_windows(pool: &PgPool, event_id: i32) -> Result<Vec<EmbargoWindow>> {
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM events WHERE id = $1)")
+        .bind(event_id)
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Err(coded(ErrorCode::NotFound, "Event not found"));
+    }
+    Ok(sqlx::query(
+        "SELECT id, event_id, starts_at, ends_at, reason, created_by, created_at
+         FROM event_embargoes
+         WHERE event_id = $1
+         ORDER BY starts_at DESC, id DESC",
+    )
+    .bind(event_id)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(row_to_window)
+    .collect())
+}
+
+/// Schedules a window. It may start in the past (an immediate freeze) but
+/// must end in the future; overlapping windows are allowed.
+pub async fn add_window(
+    pool: &PgPool,
+    event_id: i32,
+    starts_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+    reason: Option<&str>,
+    actor: &str,
+) -> Result<EmbargoWindow> {
+    let actor = actor.trim();
+    if actor.is_empty() || actor.chars().count() > 255 {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "actor must be 1-255 characters",
+        ));
+    }
+    if ends_at <= starts_at {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "ends_at must be after starts_at",
+        ));
+    }
+    if ends_at <= Utc::now() {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "ends_at must be in the future",
+        ));
+    }
+    let reason = reason.map(str::trim).filter(|r| !r.is_empty());
+
+    let row = sqlx::query(
+        "INSERT INTO event_embargoes (event_id, starts_at, ends_at, reason, created_by)
+         SELECT id, $2, $3, $4, $5 FROM events WHERE id = $1
+         RETURNING id, event_id, starts_at, ends_at, reason, created_by, created_at",
+    )
+    .bind(event_id)
+    .bind(starts_at)
+    .bind(ends_at)
+    .bind(reason)
+    .bind(actor)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| coded(ErrorCode::NotFound, "Event not found"))?;
+    Ok(row_to_window(&row))
+}
+
+/// Removes a window, lifting it early if it is open. Returns whether the
+/// event had such a window.
+pub async fn remove_window(pool: &PgPool, event_id: i32, window_id: i64) -> Result<bool> {
+    let deleted = sqlx::query("DELETE FROM event_embargoes WHERE id = $1 AND event_id = $2")
+        .bind(window_id)
+        .bind(event_id)
+        .execute(pool)
+        .await?
+        .rows_affected();
+    Ok(deleted > 0)
+}
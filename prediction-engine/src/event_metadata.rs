@@ -0,0 +1,285 @@
+//! Structured event metadata: resolution criteria, fine print, the unit
+//! and bounds of the quantity a question asks about, and the source URL.
+//!
+//! Imports used to fold all of this into the free-text `details` blob,
+//! which clients can't pick apart. The importer now also records it in
+//! columns on `events`, filling only fields that are still empty so an
+//! admin's edits survive later syncs of the same market. `details` keeps
+//! its blob, which import dedup and Metaculus lookups still match against.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{FromRow, PgPool};
+
+use crate::api_error::{coded, ErrorCode};
+use crate::market_import::ImportedMarket;
+
+pub const MAX_TEXT_LENGTH: usize = 20_000;
+pub const MAX_UNIT_LENGTH: usize = 64;
+pub const MAX_URL_LENGTH: usize = 2048;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, FromRow, ts_rs::TS)]
+#[ts(export, export_to = "../../shared/types/EventMetadata.ts")]
+pub struct EventMetadata {
+    pub resolution_criteria: Option<String>,
+    pub fine_print: Option<String>,
+    /// Unit of the quantity a numeric question resolves to
+    pub value_unit: Option<String>,
+    pub value_lower_bound: Option<f64>,
+    pub value_upper_bound: Option<f64>,
+    pub source_url: Option<String>,
+}
+
+impl EventMetadata {
+    pub fn from_import(market: &ImportedMarket) -> Self {
+        let text = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        Self {
+            resolution_criteria: text(&market.resolution_criteria),
+            fine_print: text(&market.fine_print),
+            value_unit: text(&market.numeric_unit),
+            value_lower_bound: market.numeric_range_min,
+            value_upper_bound: market.numeric_range_max,
+            source_url: text(&Some(market.external_url.clone())),
+        }
+    }
+
+    /// Applies an admin edit: each field present in `patch` is replaced,
+    /// `null` clears it, and fields left out are kept.
+    pub fn apply_patch(&mut self, patch: &Value) -> Result<()> {
+        let fields = patch
+            .as_object()
+            .ok_or_else(|| coded(ErrorCode::InvalidRequest, "metadata must be a JSON object"))?;
+        for (field, value) in fields {
+            match field.as_str() {
+                "resolution_criteria" => {
+                    self.resolution_criteria = text_field(field, value, MAX_TEXT_LENGTH)?
+                }
+                "fine_print" => self.fine_print = text_field(field, value, MAX_TEXT_LENGTH)?,
+                "value_unit" => self.value_unit = text_field(field, value, MAX_UNIT_LENGTH)?,
+                "value_lower_bound" => self.value_lower_bound = number_field(field, value)?,
+                "value_upper_bound" => self.value_upper_bound = number_field(field, value)?,
+                "source_url" => self.source_url = text_field(field, value, MAX_URL_LENGTH)?,
+                other => {
+                    return Err(coded(ErrorCode::InvalidRequest, format!(
+                        "{} is not a metadata field: fields must be resolution_criteria, \
+                         fine_print, value_unit, value_lower_bound, value_upper_bound or source_url",
+                        other
+                    )))
+                }
+            }
+        }
+        self.validate()
+    }
+
+    fn validate(&self) -> Result<()> {
+        if let (Some(lower), Some(upper)) = (self.value_lower_bound, self.value_upper_bound) {
+            if lower >= upper {
+                return Err(coded(
+                    ErrorCode::InvalidRequest,
+                    "value_lower_bound must be below value_upper_bound",
+                ));
+            }
+        }
+        if let Some(url) = &self.source_url {
+            if !(url.starts_with("https://") || url.starts_with("http://")) {
+                return Err(coded(
+                    ErrorCode::InvalidRequest,
+                    "source_url must be an http(s) URL",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn text_field(field: &str, value: &Value, max_length: usize) -> Result<Option<String>> {
+    match value {
+        Value::Null => Ok(None),
+        Value::String(text) => {
+            let text = text.trim();
+            if text.chars().count() > max_length {
+                return Err(coded(
+                    ErrorCode::InvalidRequest,
+                    format!("{} must be at most {} characters", field, max_length),
+                ));
+            }
+            Ok((!text.is_empty()).then(|| text.to_string()))
+        }
+        _ => Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("{} must be a string or null", field),
+        )),
+    }
+}
+
+fn number_field(field: &str, value: &Value) -> Result<Option<f64>> {
+    match value {
+        Value::Null => Ok(None),
+        Value::Number(n) => n
+            .as_f64()
+            .filter(|v| v.is_finite())
+            .map(Some)
+            .ok_or_else(|| {
+                coded(
+                    ErrorCode::InvalidRequest,
+                    format!("{} must be a finite number", field),
+                )
+            }),
+        _ => Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("{} must be a number or null", field),
+        )),
+    }
+}
+
+pub async fn ensure_metadata_schema(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        "ALTER TABLE events
+             ADD COLUMN IF NOT EXISTS resolution_criteria TEXT,
+             ADD COLUMN IF NOT EXISTS fine_print TEXT,
+             ADD COLUMN IF NOT EXISTS value_unit TEXT,
+             ADD COLUMN IF NOT EXISTS value_lower_bound DOUBLE PRECISION,
+             ADD COLUMN IF NOT EXISTS value_upper_bound DOUBLE PRECISION,
+             ADD COLUMN IF NOT EXISTS source_url TEXT",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Records an imported market's metadata on its event, filling only the
+/// fields the event doesn't have yet.
+pub async fn record_import(pool: &PgPool, event_id: i32, market: &ImportedMarket) -> Result<()> {
+    let metadata = EventMetadata::from_import(market);
+    sqlx::query(
+        r#"
+        UPDATE events
+        SET resolution_criteria = COALESCE(resolution_criteria, $2),
+            fine_print = COALESCE(fine_print, $3),
+            value_unit = COALESCE(value_unit, $4),
+            value_lower_bound = COALESCE(value_lower_bound, $5),
+            value_upper_bound = COALESCE(value_upper_bound, $6),
+            source_url = COALESCE(source_url, $7)
+        WHERE id = $1
+          AND (resolution_criteria IS NULL OR fine_print IS NULL OR value_unit IS NULL
+               OR value_lower_bound IS NULL OR value_upper_bound IS NULL OR source_url IS NULL)
+        "#,
+    )
+    .bind(event_id)
+    .bind(&metadata.resolution_criteria)
+    .bind(&metadata.fine_print)
+    .bind(&metadata.value_unit)
+    .bind(metadata.value_lower_bound)
+    .bind(metadata.value_upper_bound)
+    .bind(&metadata.source_url)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn get_metadata(pool: &PgPool, event_id: i32) -> Result<EventMetadata> {
+    sqlx::query_as::<_, EventMetadata>(
+        "SELECT resolution_criteria, fine_print, value_unit, value_lower_bound,
+                value_upper_bound, source_url
+         FROM events WHERE id = $1",
+    )
+    .bind(event_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| coded(ErrorCode::NotFound, "Event not found"))
+}
+
+/// Applies an admin edit (see `EventMetadata::apply_patch`) and returns
+/// the event's metadata as stored.
+pub async fn update_metadata(pool: &PgPool, event_id: i32, patch: &Value) -> Result<EventMetadata> {
+    let mut tx = pool.begin().await?;
+    let mut metadata = sqlx::query_as::<_, EventMetadata>(
+        "SELECT resolution_criteria, fine_print, value_unit, value_lower_bound,
+                value_upper_bound, source_url
+         FROM events WHERE id = $1 FOR UPDATE",
+    )
+    .bind(event_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| coded(ErrorCode::NotFound, "Event not found"))?;
+    metadata.apply_patch(patch)?;
+
+    sqlx::query(
+        r#"
+        UPDATE events
+        SET resolution_criteria = $2, fine_print = $3, value_unit = $4,
+            value_lower_bound = $5, value_upper_bound = $6, source_url = $7,
+            updated_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(event_id)
+    .bind(&metadata.resolution_criteria)
+    .bind(&metadata.fine_print)
+    .bind(&metadata.value_unit)
+    .bind(metadata.value_lower_bound)
+    .bind(metadata.value_upper_bound)
+    .bind(&metadata.source_url)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(metadata)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn patches_set_clear_and_keep_fields() {
+        let mut metadata = EventMetadata {
+            resolution_criteria: Some("Resolves YES if the launch happens.".to_string()),
+            fine_print: Some("Delays count as NO.".to_string()),
+            value_lower_bound: Some(0.0),
+            ..Default::default()
+        };
+        metadata
+            .apply_patch(&json!({
+                "fine_print": null,
+                "value_upper_bound": 10,
+                "source_url": "  https://example.com/q/1  ",
+            }))
+            .unwrap();
+        assert_eq!(
+            metadata.resolution_criteria.as_deref(),
+            Some("Resolves YES if the launch happens.")
+        );
+        assert_eq!(metadata.fine_print, None);
+        assert_eq!(metadata.value_upper_bound, Some(10.0));
+        assert_eq!(
+            metadata.source_url.as_deref(),
+            Some("https://example.com/q/1")
+        );
+    }
+
+    #[test]
+    fn bad_patches_are_rejected() {
+        let metadata = EventMetadata {
+            value_upper_bound: Some(5.0),
+            ..Default::default()
+        };
+        for patch in [
+            json!({ "value_lower_bound": 5 }),
+            json!({ "source_url": "ftp://example.com" }),
+            json!({ "fine_print": 3 }),
+            json!({ "details": "free text" }),
+            json!(["resolution_criteria"]),
+        ] {
+            let err = metadata.clone().apply_patch(&patch).unwrap_err();
+            assert!(err.to_string().contains("must"), "{}: {}", patch, err);
+        }
+    }
+}
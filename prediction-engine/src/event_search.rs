@@ -0,0 +1,208 @@
+//! Full-text search over events.
+//!
+//! Matches `events.search_vector`, the generated title + details tsvector
+//! the backend's market matcher already indexes, against the query parsed
+//! with `websearch_to_tsquery`, so quoted phrases, `or` and `-word` work the
+//! way they do in a search box. Results are ranked by `ts_rank`, with title
+//! hits counted again so a match in the title beats one buried in the
+//! details. Hidden events and multi-outcome markets without a usable
+//! outcome set are left out, as in the backend's event listing.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use sqlx::{PgPool, Row};
+
+use crate::api_error::{coded, ErrorCode};
+
+pub const MAX_QUERY_CHARS: usize = 200;
+pub const MAX_LIMIT: i64 = 100;
+
+/// Which events a search covers, by lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// Unresolved and still trading
+    Open,
+    /// Past closing or closed, awaiting resolution
+    Closed,
+    Resolved,
+    All,
+}
+
+impl Status {
+    pub fn parse(raw: Option<&str>) -> Result<Self> {
+        match raw.map(|s| s.trim().to_ascii_lowercase()).as_deref() {
+            None | Some("") | Some("all") => Ok(Status::All),
+            Some("open") => Ok(Status::Open),
+            Some("closed") => Ok(Status::Closed),
+            Some("resolved") => Ok(Status::Resolved),
+            Some(other) => Err(coded(
+                ErrorCode::InvalidRequest,
+                format!(
+                    "status must be one of open, closed, resolved, all (got {})",
+                    other
+                ),
+            )),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Status::Open => "open",
+            Status::Closed => "closed",
+            Status::Resolved => "resolved",
+            Status::All => "all",
+        }
+    }
+}
+
+/// The search vector and moderation column the query relies on. Both come
+/// from backend migrations; this covers databases that predate them.
+pub async fn ensure_search_schema(pool: &PgPool) -> Result<()> {
+    sqlx::query("ALTER TABLE events ADD COLUMN IF NOT EXISTS hidden_at TIMESTAMPTZ")
+        .execute(pool)
+        .await?;
+    sqlx::query(
+        r#"
+        ALTER TABLE events
+        ADD COLUMN IF NOT EXISTS search_vector tsvector GENERATED ALWAYS AS (
+            to_tsvector('english', COALESCE(title, '') || ' ' || COALESCE(details, ''))
+        ) STORED
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_events_fts ON events USING GIN(search_vector)")
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Ranked events matching `q`, a page at a time, with the total match
+/// count. A page past the last match reports a total of 0.
+pub async fn search_events(
+    pool: &PgPool,
+    q: &str,
+    category: Option<&str>,
+    status: Status,
+    limit: i64,
+    offset: i64,
+) -> Result<Value> {
+    let q = q.trim();
+    if q.is_empty() || q.chars().count() > MAX_QUERY_CHARS {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("q must be 1-{} characters", MAX_QUERY_CHARS),
+        ));
+    }
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("limit must be between 1 and {}", MAX_LIMIT),
+        ));
+    }
+    if offset < 0 {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "offset must be non-negative",
+        ));
+    }
+    let category = category.map(str::trim).filter(|c| !c.is_empty());
+
+    let rows = sqlx::query(
+        r#"
+        WITH query AS (SELECT websearch_to_tsquery('english', $1) AS q),
+        matches AS (
+            SELECT e.id,
+                   ts_rank(e.search_vector, query.q)
+                       + ts_rank(to_tsvector('english', e.title), query.q) AS rank,
+                   COUNT(*) OVER () AS total
+            FROM events e, query
+            WHERE e.search_vector @@ query.q
+              AND e.hidden_at IS NULL
+              AND ($2::text IS NULL OR LOWER(e.category) = LOWER($2))
+              AND CASE $3::text
+                    WHEN 'open' THEN e.outcome IS NULL AND e.closed_at IS NULL
+                                     AND COALESCE(e.closing_date > NOW(), true)
+                    WHEN 'closed' THEN e.outcome IS NULL
+                                       AND (e.closed_at IS NOT NULL
+                                            OR COALESCE(e.closing_date <= NOW(), false))
+                    WHEN 'resolved' THEN e.outcome IS NOT NULL
+                    ELSE true
+                  END
+              AND (
+                  COALESCE(e.event_type, 'binary') = 'binary'
+                  OR EXISTS (
+                      SELECT 1 FROM event_outcomes eo
+                      WHERE eo.event_id = e.id AND eo.is_active = TRUE
+                      GROUP BY eo.event_id
+                      HAVING COUNT(*) >= 2
+                  )
+              )
+            ORDER BY rank DESC, e.closing_date ASC NULLS LAST, e.id
+            LIMIT $4 OFFSET $5
+        )
+        SELECT e.id, e.title, e.category, COALESCE(e.event_type, 'binary') AS event_type,
+               e.outcome, e.closing_date::timestamptz AS closing_date,
+               e.market_prob::float8 AS market_prob,
+               COALESCE(e.cumulative_stake, 0)::float8 AS cumulative_stake,
+               ts_headline('english', COALESCE(e.details, ''), query.q,
+                           'MaxWords=30, MinWords=12, MaxFragments=1') AS snippet,
+               m.rank::float8 AS rank, m.total
+        FROM matches m
+        JOIN events e ON e.id = m.id, query
+        ORDER BY m.rank DESC, e.closing_date ASC NULLS LAST, e.id
+        "#,
+    )
+    .bind(q)
+    .bind(category)
+    .bind(status.as_str())
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    let total = rows.first().map_or(0, |row| row.get::<i64, _>("total"));
+    let results: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            json!({
+                "id": row.get::<i32, _>("id"),
+                "title": row.get::<String, _>("title"),
+                "category": row.get::<Option<String>, _>("category"),
+                "event_type": row.get::<String, _>("event_type"),
+                "outcome": row.get::<Option<String>, _>("outcome"),
+                "closing_date": row.get::<Option<DateTime<Utc>>, _>("closing_date"),
+                "market_prob": row.get::<Option<f64>, _>("market_prob"),
+                "cumulative_stake": row.get::<f64, _>("cumulative_stake"),
+                "snippet": row.get::<String, _>("snippet"),
+                "rank": row.get::<f64, _>("rank"),
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "query": q,
+        "category": category,
+        "status": status.as_str(),
+        "total": total,
+        "limit": limit,
+        "offset": offset,
+        "has_more": offset + (results.len() as i64) < total,
+        "results": results,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_parses_case_insensitively_and_defaults_to_all() {
+        assert_eq!(Status::parse(None).unwrap(), Status::All);
+        assert_eq!(Status::parse(Some(" Open ")).unwrap(), Status::Open);
+        assert_eq!(Status::parse(Some("RESOLVED")).unwrap(), Status::Resolved);
+        let err = Status::parse(Some("pending")).unwrap_err();
+        assert!(err.to_string().contains("status must be"), "{}", err);
+    }
+}
@@ -0,0 +1,525 @@
+//! Cross-market exposure for a user's open positions.
+//!
+//! Each open binary position has two outcomes: it pays its YES shares if
+//! the event resolves YES and its NO shares otherwise, against the RP still
+//! staked in it. Positions are grouped two ways. By category, markets are
+//! treated as independent, so the worst case is the sum of each market's
+//! worse outcome. By cluster, an admin-defined set of events expected to
+//! resolve together (members marked `inverse` resolve opposite to the
+//! rest), so the cluster has just two scenarios and the worst case is the
+//! worse of them; a hedge across a cluster shows up as a small worst case
+//! where the category view would add the legs up. Clusters of other
+//! relations (mutually exclusive or loosely related events) have no joint
+//! scenarios and are left out here; `cluster_consistency` prices them.
+//!
+//! v1 scope: binary markets only. Competition markets are left out, since
+//! they trade a competition bankroll rather than the user's RP.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::{PgPool, Row};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::api_error::{coded, ErrorCode};
+use crate::lmsr_core::from_ledger_units;
+
+const UNCATEGORIZED: &str = "uncategorized";
+
+/// How a cluster's events are linked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClusterRelation {
+    /// Members resolve together, `inverse` ones the other way.
+    #[default]
+    Correlated,
+    /// At most one member resolves YES.
+    MutuallyExclusive,
+    /// Logically related, with no constraint on how they resolve.
+    Related,
+}
+
+impl ClusterRelation {
+    pub fn parse(relation: &str) -> Option<Self> {
+        match relation {
+            "correlated" => Some(Self::Correlated),
+            "mutually_exclusive" => Some(Self::MutuallyExclusive),
+            "related" => Some(Self::Related),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Correlated => "correlated",
+            Self::MutuallyExclusive => "mutually_exclusive",
+            Self::Related => "related",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCluster {
+    pub name: String,
+    #[serde(default)]
+    pub relation: ClusterRelation,
+    pub members: Vec<ClusterMember>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ClusterMember {
+    pub event_id: i32,
+    /// Resolves opposite to the cluster: its NO is the cluster's YES.
+    #[serde(default)]
+    pub inverse: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Position {
+    pub event_id: i32,
+    pub title: String,
+    pub category: String,
+    pub market_prob: f64,
+    pub yes_shares: f64,
+    pub no_shares: f64,
+    pub staked: f64,
+}
+
+impl Position {
+    pub fn pnl_if_yes(&self) -> f64 {
+        self.yes_shares - self.staked
+    }
+
+    pub fn pnl_if_no(&self) -> f64 {
+        self.no_shares - self.staked
+    }
+
+    /// Shares paying on YES less shares paying on NO.
+    pub fn net_shares(&self) -> f64 {
+        self.yes_shares - self.no_shares
+    }
+}
+
+/// RP lost in the worse of the outcomes, or 0 if every outcome is a gain.
+fn loss(worst_pnl: f64) -> f64 {
+    (-worst_pnl).max(0.0)
+}
+
+pub async fn ensure_cluster_tables(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS event_clusters (
+            id SERIAL PRIMARY KEY,
+            name VARCHAR(255) NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "ALTER TABLE event_clusters
+         ADD COLUMN IF NOT EXISTS relation VARCHAR(20) NOT NULL DEFAULT 'correlated'",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS event_cluster_members (
+            cluster_id INTEGER NOT NULL REFERENCES event_clusters(id) ON DELETE CASCADE,
+            event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+            inverse BOOLEAN NOT NULL DEFAULT FALSE,
+            PRIMARY KEY (cluster_id, event_id)
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Defines a cluster of at least two linked events.
+pub async fn create_cluster(pool: &PgPool, request: &CreateCluster) -> Result<Value> {
+    let name = request.name.trim();
+    if name.is_empty() || name.chars().count() > 255 {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "name must be 1-255 characters",
+        ));
+    }
+    let mut event_ids: Vec<i32> = request.members.iter().map(|m| m.event_id).collect();
+    event_ids.sort_unstable();
+    event_ids.dedup();
+    if event_ids.len() != request.members.len() {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "members must list each event once",
+        ));
+    }
+    if event_ids.len() < 2 {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "a cluster must link at least 2 events",
+        ));
+    }
+    if request.relation != ClusterRelation::Correlated && request.members.iter().any(|m| m.inverse)
+    {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "inverse must only be set on correlated clusters",
+        ));
+    }
+
+    let mut tx = pool.begin().await?;
+    let found: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM events WHERE id = ANY($1)")
+        .bind(&event_ids)
+        .fetch_one(&mut *tx)
+        .await?;
+    if found != event_ids.len() as i64 {
+        return Err(coded(ErrorCode::NotFound, "Event not found"));
+    }
+    let cluster_id: i32 = sqlx::query_scalar(
+        "INSERT INTO event_clusters (name, relation) VALUES ($1, $2) RETURNING id",
+    )
+    .bind(name)
+    .bind(request.relation.as_str())
+    .fetch_one(&mut *tx)
+    .await?;
+    let (member_ids, inverse): (Vec<i32>, Vec<bool>) = request
+        .members
+        .iter()
+        .map(|m| (m.event_id, m.inverse))
+        .unzip();
+    sqlx::query(
+        "INSERT INTO event_cluster_members (cluster_id, event_id, inverse)
+         SELECT $1, t.event_id, t.inverse
+         FROM UNNEST($2::integer[], $3::boolean[]) AS t(event_id, inverse)",
+    )
+    .bind(cluster_id)
+    .bind(&member_ids)
+    .bind(&inverse)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(json!({
+        "cluster_id": cluster_id,
+        "name": name,
+        "relation": request.relation,
+        "members": request.members,
+    }))
+}
+
+pub async fn list_clusters(pool: &PgPool) -> Result<Value> {
+    let rows = sqlx::query(
+        "SELECT c.id, c.name, c.relation, m.event_id, m.inverse
+         FROM event_clusters c
+         JOIN event_cluster_members m ON m.cluster_id = c.id
+         ORDER BY c.id, m.event_id",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut clusters: Vec<Value> = Vec::new();
+    for row in &rows {
+        let cluster_id: i32 = row.get("id");
+        let member = json!({
+            "event_id": row.get::<i32, _>("event_id"),
+            "inverse": row.get::<bool, _>("inverse"),
+        });
+        match clusters.last_mut() {
+            Some(cluster) if cluster["cluster_id"] == cluster_id => {
+                cluster["members"].as_array_mut().unwrap().push(member);
+            }
+            _ => clusters.push(json!({
+                "cluster_id": cluster_id,
+                "name": row.get::<String, _>("name"),
+                "relation": row.get::<String, _>("relation"),
+                "members": [member],
+            })),
+        }
+    }
+    Ok(json!({ "clusters": clusters }))
+}
+
+/// Net exposure and worst case per category, treating markets as independent.
+pub fn category_exposure(positions: &[Position]) -> Vec<Value> {
+    let mut by_category: BTreeMap<&str, Vec<&Position>> = BTreeMap::new();
+    for position in positions {
+        by_category
+            .entry(position.category.as_str())
+            .or_default()
+            .push(position);
+    }
+    by_category
+        .into_iter()
+        .map(|(category, members)| {
+            let worst_pnl: f64 = members
+                .iter()
+                .map(|p| p.pnl_if_yes().min(p.pnl_if_no()))
+                .sum();
+            json!({
+                "category": category,
+                "positions": members.len(),
+                "staked": members.iter().map(|p| p.staked).sum::<f64>(),
+                "net_directional_shares": members.iter().map(|p| p.net_shares()).sum::<f64>(),
+                "worst_case_loss": loss(worst_pnl),
+            })
+        })
+        .collect()
+}
+
+/// Exposure per cluster under its two joint scenarios. Clusters the user
+/// holds nothing in are omitted.
+pub fn cluster_exposure(
+    positions: &[Position],
+    clusters: &[(i32, String, Vec<ClusterMember>)],
+) -> Vec<Value> {
+    let by_event: HashMap<i32, &Position> = positions.iter().map(|p| (p.event_id, p)).collect();
+    clusters
+        .iter()
+        .filter_map(|(cluster_id, name, members)| {
+            let held: Vec<(&Position, bool)> = members
+                .iter()
+                .filter_map(|m| by_event.get(&m.event_id).map(|p| (*p, m.inverse)))
+                .collect();
+            if held.is_empty() {
+                return None;
+            }
+            let pnl_if_cluster_yes: f64 = held
+                .iter()
+                .map(|(p, inverse)| {
+                    if *inverse {
+                        p.pnl_if_no()
+                    } else {
+                        p.pnl_if_yes()
+                    }
+                })
+                .sum();
+            let pnl_if_cluster_no: f64 = held
+                .iter()
+                .map(|(p, inverse)| {
+                    if *inverse {
+                        p.pnl_if_yes()
+                    } else {
+                        p.pnl_if_no()
+                    }
+                })
+                .sum();
+            let net: f64 = held
+                .iter()
+                .map(|(p, inverse)| {
+                    if *inverse {
+                        -p.net_shares()
+                    } else {
+                        p.net_shares()
+                    }
+                })
+                .sum();
+            Some(json!({
+                "cluster_id": cluster_id,
+                "name": name,
+                "positions": held.len(),
+                "event_ids": held.iter().map(|(p, _)| p.event_id).collect::<Vec<_>>(),
+                "net_directional_shares": net,
+                "pnl_if_cluster_yes": pnl_if_cluster_yes,
+                "pnl_if_cluster_no": pnl_if_cluster_no,
+                "worst_case_loss": loss(pnl_if_cluster_yes.min(pnl_if_cluster_no)),
+            }))
+        })
+        .collect()
+}
+
+/// Open positions with per-market outcomes, grouped by category and by
+/// cluster.
+/// The user's open binary positions outside competitions, by event id.
+pub(crate) async fn load_positions(pool: &PgPool, user_id: i32) -> Result<Vec<Position>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT e.id AS event_id, e.title, e.category,
+               COALESCE(e.market_prob, 0.5) AS market_prob,
+               us.yes_shares, us.no_shares,
+               COALESCE(us.total_staked_ledger, 0)::BIGINT AS staked_ledger
+        FROM user_shares us
+        JOIN events e ON e.id = us.event_id
+        WHERE us.user_id = $1
+          AND (us.yes_shares > 0 OR us.no_shares > 0)
+          AND e.outcome IS NULL
+          AND e.competition_id IS NULL
+        ORDER BY e.id
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    let positions: Vec<Position> = rows
+        .iter()
+        .map(|row| Position {
+            event_id: row.get("event_id"),
+            title: row.get("title"),
+            category: row
+                .get::<Option<String>, _>("category")
+                .filter(|c| !c.trim().is_empty())
+                .unwrap_or_else(|| UNCATEGORIZED.to_string()),
+            market_prob: row.get("market_prob"),
+            yes_shares: row.get::<Option<f64>, _>("yes_shares").unwrap_or(0.0),
+            no_shares: row.get::<Option<f64>, _>("no_shares").unwrap_or(0.0),
+            staked: from_ledger_units(row.get::<i64, _>("staked_ledger") as i128),
+        })
+        .collect();
+    Ok(positions)
+}
+
+/// RP lost on the position's worse outcome.
+pub(crate) fn worst_case_loss(position: &Position) -> f64 {
+    loss(position.pnl_if_yes().min(position.pnl_if_no()))
+}
+
+pub async fn get_exposure(pool: &PgPool, user_id: i32) -> Result<Value> {
+    let user_exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM users WHERE id = $1)")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+    if !user_exists {
+        return Err(coded(ErrorCode::NotFound, "User not found"));
+    }
+    let positions = load_positions(pool, user_id).await?;
+
+    let event_ids: Vec<i32> = positions.iter().map(|p| p.event_id).collect();
+    let member_rows = sqlx::query(
+        "SELECT c.id, c.name, m.event_id, m.inverse
+         FROM event_clusters c
+         JOIN event_cluster_members m ON m.cluster_id = c.id
+         WHERE c.relation = 'correlated'
+           AND c.id IN (SELECT cluster_id FROM event_cluster_members WHERE event_id = ANY($1))
+         ORDER BY c.id, m.event_id",
+    )
+    .bind(&event_ids)
+    .fetch_all(pool)
+    .await?;
+    let mut clusters: Vec<(i32, String, Vec<ClusterMember>)> = Vec::new();
+    for row in &member_rows {
+        let cluster_id: i32 = row.get("id");
+        let member = ClusterMember {
+            event_id: row.get("event_id"),
+            inverse: row.get("inverse"),
+        };
+        match clusters.last_mut() {
+            Some((id, _, members)) if *id == cluster_id => members.push(member),
+            _ => clusters.push((cluster_id, row.get("name"), vec![member])),
+        }
+    }
+
+    let worst_pnl: f64 = positions
+        .iter()
+        .map(|p| p.pnl_if_yes().min(p.pnl_if_no()))
+        .sum();
+    let position_values: Vec<Value> = positions
+        .iter()
+        .map(|p| {
+            json!({
+                "event_id": p.event_id,
+                "title": p.title,
+                "category": p.category,
+                "market_prob": p.market_prob,
+                "yes_shares": p.yes_shares,
+                "no_shares": p.no_shares,
+                "staked": p.staked,
+                "net_shares": p.net_shares(),
+                "pnl_if_yes": p.pnl_if_yes(),
+                "pnl_if_no": p.pnl_if_no(),
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "user_id": user_id,
+        "positions": position_values,
+        "by_category": category_exposure(&positions),
+        "by_cluster": cluster_exposure(&positions, &clusters),
+        "totals": {
+            "positions": positions.len(),
+            "staked": positions.iter().map(|p| p.staked).sum::<f64>(),
+            "net_directional_shares": positions.iter().map(|p| p.net_shares()).sum::<f64>(),
+            "worst_case_loss": loss(worst_pnl),
+        },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(event_id: i32, category: &str, yes: f64, no: f64, staked: f64) -> Position {
+        Position {
+            event_id,
+            title: format!("Event {}", event_id),
+            category: category.to_string(),
+            market_prob: 0.5,
+            yes_shares: yes,
+            no_shares: no,
+            staked,
+        }
+    }
+
+    #[test]
+    fn categories_add_each_markets_worse_outcome() {
+        let positions = [
+            position(1, "politics", 30.0, 0.0, 20.0),
+            position(2, "politics", 0.0, 25.0, 15.0),
+            position(3, "science", 10.0, 0.0, 4.0),
+        ];
+        let categories = category_exposure(&positions);
+        assert_eq!(categories.len(), 2);
+        assert_eq!(categories[0]["category"], "politics");
+        assert_eq!(categories[0]["net_directional_shares"], 5.0);
+        // Each politics market can lose its whole stake: 20 + 15
+        assert_eq!(categories[0]["worst_case_loss"], 35.0);
+        assert_eq!(categories[1]["worst_case_loss"], 4.0);
+    }
+
+    #[test]
+    fn clusters_net_hedges_across_linked_markets() {
+        // YES on event 1 hedged by YES on event 2, which resolves the other way
+        let positions = [
+            position(1, "politics", 30.0, 0.0, 20.0),
+            position(2, "politics", 30.0, 0.0, 20.0),
+            position(3, "science", 10.0, 0.0, 4.0),
+        ];
+        let clusters = vec![
+            (
+                7,
+                "Election".to_string(),
+                vec![
+                    ClusterMember {
+                        event_id: 1,
+                        inverse: false,
+                    },
+                    ClusterMember {
+                        event_id: 2,
+                        inverse: true,
+                    },
+                ],
+            ),
+            (
+                8,
+                "Untouched".to_string(),
+                vec![ClusterMember {
+                    event_id: 99,
+                    inverse: false,
+                }],
+            ),
+        ];
+        let exposure = cluster_exposure(&positions, &clusters);
+        assert_eq!(exposure.len(), 1);
+        assert_eq!(exposure[0]["net_directional_shares"], 0.0);
+        assert_eq!(exposure[0]["pnl_if_cluster_yes"], -10.0);
+        assert_eq!(exposure[0]["pnl_if_cluster_no"], -10.0);
+        assert_eq!(exposure[0]["worst_case_loss"], 10.0);
+        // Independently, both legs could lose their full stake
+        assert_eq!(
+            category_exposure(&positions[..2])[0]["worst_case_loss"],
+            40.0
+        );
+    }
+}
@@ -0,0 +1,345 @@
+//! RP faucet: onboarding grants and top-ups.
+//!
+//! New accounts used to start with 1000 RP through the column default on
+//! `users.rp_balance_ledger`, which left no record of where the RP came
+//! from. The engine now owns that grant. On startup it journals the
+//! default every existing user received as their onboarding grant and sets
+//! the default to 0; from then on the sweep credits each user without an
+//! onboarding row the configured amount.
+//!
+//! The same sweep tops up users who are still trading but have next to
+//! nothing left, at most once per cooldown and up to a lifetime cap. Every
+//! credit is a row in `rp_faucet_grants`, so faucet RP can be told apart
+//! from RP earned in markets.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::{PgConnection, PgPool, Row};
+
+use crate::api_error::{coded, ErrorCode};
+use crate::config::FaucetConfig;
+use crate::lmsr_core::{from_ledger_units, to_ledger_units};
+use crate::system_accounts::{self, SystemAccount};
+
+pub const ONBOARDING: &str = "onboarding";
+pub const TOPUP: &str = "topup";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FaucetGrant {
+    pub user_id: i32,
+    pub kind: &'static str,
+    pub amount_rp: f64,
+    pub balance_after_rp: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FaucetSweep {
+    pub onboarded: Vec<FaucetGrant>,
+    pub topped_up: Vec<FaucetGrant>,
+}
+
+/// Creates the journal and, on first run, takes over onboarding from the
+/// column default. Run once at startup.
+pub async fn ensure_faucet_schema(pool: &PgPool) -> Result<()> {
+    ensure_grants_table(pool).await?;
+    take_over_onboarding(pool).await
+}
+
+pub(crate) async fn ensure_grants_table(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS rp_faucet_grants (
+            id BIGSERIAL PRIMARY KEY,
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            kind VARCHAR(16) NOT NULL CHECK (kind IN ('onboarding', 'topup')),
+            amount_ledger BIGINT NOT NULL CHECK (amount_ledger >= 0),
+            balance_after_ledger BIGINT,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_rp_faucet_grants_onboarding
+         ON rp_faucet_grants(user_id) WHERE kind = 'onboarding'",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_rp_faucet_grants_user
+         ON rp_faucet_grants(user_id, kind, created_at)",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Ledger units in a column default such as `1000000000` or
+/// `'1000000000'::bigint`; None when there is no numeric default.
+fn parse_column_default(default: Option<&str>) -> Option<i64> {
+    let digits: String = default?
+        .trim_start_matches(['(', '\''])
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+/// Journals the balance users got from the column default as their
+/// onboarding grant and zeroes the default. A no-op once the default is 0.
+async fn take_over_onboarding(pool: &PgPool) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    // Holds off signups until the default is gone, so every user is either
+    // journaled here or onboarded by the sweep, never both
+    sqlx::query("LOCK TABLE users IN SHARE ROW EXCLUSIVE MODE")
+        .execute(&mut *tx)
+        .await?;
+    let default: Option<String> = sqlx::query_scalar(
+        "SELECT column_default::text FROM information_schema.columns
+         WHERE table_schema = current_schema()
+           AND table_name = 'users' AND column_name = 'rp_balance_ledger'",
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .flatten();
+    let legacy_grant = match parse_column_default(default.as_deref()) {
+        Some(grant) if grant > 0 => grant,
+        _ => return Ok(()),
+    };
+
+    sqlx::query(
+        "INSERT INTO rp_faucet_grants (user_id, kind, amount_ledger, created_at)
+         SELECT u.id, 'onboarding', $1, COALESCE(u.created_at, NOW())
+         FROM users u
+         WHERE NOT EXISTS (
+             SELECT 1 FROM rp_faucet_grants g
+             WHERE g.user_id = u.id AND g.kind = 'onboarding'
+         )",
+    )
+    .bind(legacy_grant)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("ALTER TABLE users ALTER COLUMN rp_balance_ledger SET DEFAULT 0")
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub(crate) fn ledger(rp: f64) -> Result<i64> {
+    let units = to_ledger_units(rp).map_err(|e| anyhow!(e))?;
+    i64::try_from(units).map_err(|_| anyhow!("RP amount out of range: {}", rp))
+}
+
+pub(crate) fn grants(rows: &[sqlx::postgres::PgRow], kind: &'static str) -> Vec<FaucetGrant> {
+    rows.iter()
+        .map(|row| FaucetGrant {
+            user_id: row.get("user_id"),
+            kind,
+            amount_rp: from_ledger_units(row.get::<i64, _>("amount_ledger") as i128),
+            balance_after_rp: from_ledger_units(row.get::<i64, _>("balance_after_ledger") as i128),
+        })
+        .collect()
+}
+
+/// Locks the journal against concurrent sweeps and provisioning. Holders
+/// may then credit onboarding grants without paying anyone twice.
+pub(crate) async fn lock_grants(conn: &mut PgConnection) -> Result<()> {
+    sqlx::query("LOCK TABLE rp_faucet_grants IN SHARE ROW EXCLUSIVE MODE")
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// Credits the onboarding grant to every user without one, or only to
+/// `user_id`. Callers hold `lock_grants`.
+pub(crate) async fn credit_onboarding(
+    conn: &mut PgConnection,
+    amount_ledger: i64,
+    user_id: Option<i32>,
+) -> Result<Vec<sqlx::postgres::PgRow>> {
+    let credited = sqlx::query(
+        "WITH credited AS (
+             UPDATE users u
+             SET rp_balance_ledger = COALESCE(u.rp_balance_ledger, 0) + $1
+             WHERE ($2::INTEGER IS NULL OR u.id = $2)
+               AND NOT EXISTS (
+                   SELECT 1 FROM rp_faucet_grants g
+                   WHERE g.user_id = u.id AND g.kind = 'onboarding'
+               )
+             RETURNING u.id, u.rp_balance_ledger
+         )
+         INSERT INTO rp_faucet_grants (user_id, kind, amount_ledger, balance_after_ledger)
+         SELECT id, 'onboarding', $1, rp_balance_ledger FROM credited
+         RETURNING user_id, amount_ledger, balance_after_ledger",
+    )
+    .bind(amount_ledger)
+    .bind(user_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    book_grants(conn, ONBOARDING, &credited).await?;
+    Ok(credited)
+}
+
+/// Books credited grants against the faucet account.
+async fn book_grants(
+    conn: &mut PgConnection,
+    kind: &str,
+    credited: &[sqlx::postgres::PgRow],
+) -> Result<()> {
+    let user_ids: Vec<i32> = credited.iter().map(|row| row.get("user_id")).collect();
+    let amounts: Vec<i64> = credited
+        .iter()
+        .map(|row| row.get("amount_ledger"))
+        .collect();
+    system_accounts::book_batch(conn, SystemAccount::Faucet, kind, None, &user_ids, &amounts).await
+}
+
+/// Credits pending onboarding grants and due top-ups. Sweeps serialize on
+/// the journal, so two engines running one at once can't double-pay.
+pub async fn run_sweep(pool: &PgPool, config: &FaucetConfig) -> Result<FaucetSweep> {
+    let onboarding_grant = ledger(config.onboarding_grant_rp)?;
+    let topup_amount = ledger(config.topup_amount_rp)?;
+    let topup_cap = ledger(config.topup_lifetime_cap_rp)?;
+    let threshold = ledger(config.topup_threshold_rp)?;
+
+    let mut tx = pool.begin().await?;
+    lock_grants(&mut tx).await?;
+
+    let onboarded = credit_onboarding(&mut tx, onboarding_grant, None).await?;
+
+    let topped_up = if topup_amount > 0 && topup_cap > 0 {
+        sqlx::query(
+            "WITH given AS (
+                 SELECT user_id, SUM(amount_ledger) AS total, MAX(created_at) AS last_at
+                 FROM rp_faucet_grants WHERE kind = 'topup'
+                 GROUP BY user_id
+             ),
+             due AS (
+                 SELECT u.id, LEAST($1::BIGINT, $2::BIGINT - COALESCE(g.total, 0))::BIGINT AS amount
+                 FROM users u
+                 LEFT JOIN given g ON g.user_id = u.id
+                 WHERE COALESCE(u.rp_balance_ledger, 0) + COALESCE(u.rp_staked_ledger, 0) < $3
+                   AND COALESCE(g.total, 0) < $2
+                   AND (g.last_at IS NULL OR g.last_at <= NOW() - $4 * INTERVAL '1 hour')
+                   AND (
+                       EXISTS (
+                           SELECT 1 FROM market_updates mu
+                           WHERE mu.user_id = u.id
+                             AND mu.created_at >= NOW() - $5 * INTERVAL '1 day'
+                       )
+                       OR EXISTS (
+                           SELECT 1 FROM market_outcome_updates mou
+                           WHERE mou.user_id = u.id
+                             AND mou.created_at >= NOW() - $5 * INTERVAL '1 day'
+                       )
+                   )
+             ),
+             credited AS (
+                 UPDATE users u
+                 SET rp_balance_ledger = COALESCE(u.rp_balance_ledger, 0) + due.amount
+                 FROM due
+                 WHERE u.id = due.id
+                 RETURNING u.id, due.amount, u.rp_balance_ledger
+             )
+             INSERT INTO rp_faucet_grants (user_id, kind, amount_ledger, balance_after_ledger)
+             SELECT id, 'topup', amount, rp_balance_ledger FROM credited
+             RETURNING user_id, amount_ledger, balance_after_ledger",
+        )
+        .bind(topup_amount)
+        .bind(topup_cap)
+        .bind(threshold)
+        .bind(config.topup_cooldown_hours)
+        .bind(config.active_within_days)
+        .fetch_all(&mut *tx)
+        .await?
+    } else {
+        Vec::new()
+    };
+    book_grants(&mut tx, TOPUP, &topped_up).await?;
+    tx.commit().await?;
+
+    Ok(FaucetSweep {
+        onboarded: grants(&onboarded, ONBOARDING),
+        topped_up: grants(&topped_up, TOPUP),
+    })
+}
+
+/// A user's faucet journal, newest first, with what top-ups remain.
+pub async fn get_user_grants(pool: &PgPool, config: &FaucetConfig, user_id: i32) -> Result<Value> {
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Err(coded(ErrorCode::NotFound, "User not found"));
+    }
+
+    let rows = sqlx::query(
+        "SELECT id, kind, amount_ledger, balance_after_ledger, created_at
+         FROM rp_faucet_grants WHERE user_id = $1
+         ORDER BY created_at DESC, id DESC",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut topup_total = 0i64;
+    let mut last_topup: Option<DateTime<Utc>> = None;
+    let entries: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            let kind: String = row.get("kind");
+            let amount: i64 = row.get("amount_ledger");
+            let created_at: DateTime<Utc> = row.get("created_at");
+            if kind == TOPUP {
+                topup_total += amount;
+                last_topup = last_topup.max(Some(created_at));
+            }
+            json!({
+                "id": row.get::<i64, _>("id"),
+                "kind": kind,
+                "amount_rp": from_ledger_units(amount as i128),
+                "balance_after_rp": row
+                    .get::<Option<i64>, _>("balance_after_ledger")
+                    .map(|b| from_ledger_units(b as i128)),
+                "created_at": created_at,
+            })
+        })
+        .collect();
+
+    let topped_up_rp = from_ledger_units(topup_total as i128);
+    let next_topup_after = last_topup
+        .map(|at| at + chrono::Duration::seconds((config.topup_cooldown_hours * 3600.0) as i64));
+    Ok(json!({
+        "user_id": user_id,
+        "onboarded": rows.iter().any(|row| row.get::<String, _>("kind") == ONBOARDING),
+        "topped_up_rp": topped_up_rp,
+        "topup_remaining_rp": (config.topup_lifetime_cap_rp - topped_up_rp).max(0.0),
+        "next_topup_after": next_topup_after,
+        "grants": entries,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn column_defaults_parse_to_ledger_units() {
+        assert_eq!(
+            parse_column_default(Some("1000000000")),
+            Some(1_000_000_000)
+        );
+        assert_eq!(
+            parse_column_default(Some("'1000000000'::bigint")),
+            Some(1_000_000_000)
+        );
+        assert_eq!(parse_column_default(Some("0")), Some(0));
+        assert_eq!(parse_column_default(Some("nextval('x')")), None);
+        assert_eq!(parse_column_default(None), None);
+    }
+}
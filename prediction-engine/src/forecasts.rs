@@ -0,0 +1,927 @@
+// Forecast journal: a probability on an open binary event, recorded for
+// scoring with no RP at stake.
+//
+// The current forecast lives in `predictions`, in the shape the backend's
+// prediction flow writes (prediction_value yes/no, confidence, prob_vector
+// [P(yes), P(no)]), so accuracy stats and prediction lists pick it up
+// unchanged. Every submission and update is also appended to
+// `forecast_revisions` with its timestamp; that history, not the single
+// predictions row, is what says which probability the user held over which
+// interval. Resolution settles the predictions row (correct/incorrect, or
+// not_applicable when the event resolves N/A and nothing is scored), and
+// scores use the same log/Brier scoring as paper predictions.
+//
+// Time-weighted scoring slices the history: each revision is held from its
+// timestamp until the next one, the last until the event resolves (or, while
+// it is open, until now or its close). A slice's weight is its share of the
+// time from the first forecast to that end, so weights sum to 1 and a user
+// who revises is scored on what they believed for how long, not only on
+// their final answer. Time before the first forecast isn't scored;
+// `coverage` reports how much of the event's open life the forecasts span.
+// Durations are exact timestamp differences, so a question open for minutes
+// slices as precisely as one open for months. A revision replaced within
+// the minimum slice (SCORE_MIN_SLICE_SECS, default 1s) never counts on its
+// own: its time goes to the revision that replaced it, so rapid re-edits
+// can't split the score.
+// Resolution also stores the event's crowd scores (see `peer_scores`), and
+// history reports each forecast's peer score against them.
+//
+// v1 scope: binary events only. Forecasts are accepted until the event
+// closes or resolves.
+//
+// Once an event's dispute window has passed its scores can no longer
+// change, so compaction collapses each settled forecast into one row in
+// `forecast_score_summaries` and moves its revisions to
+// `forecast_revisions_archive` (or deletes them). Forecast history reads
+// the summary for compacted forecasts and slices whatever was archived.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::{PgPool, Postgres, Row, Transaction};
+use std::collections::BTreeMap;
+use std::env;
+
+use crate::api_error::{coded, ErrorCode};
+use crate::lmsr_api::Resolution;
+use crate::paper_predictions::{brier_score, log_score};
+
+/// Forecasts compacted per run.
+const COMPACTION_BATCH: i64 = 500;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ForecastResult {
+    pub prediction_id: i32,
+    pub user_id: i32,
+    pub event_id: i32,
+    pub probability: f64,
+    pub revision_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Set once the event resolves; scores are for the current forecast.
+    pub outcome: Option<bool>,
+    pub brier_score: Option<f64>,
+    pub log_score: Option<f64>,
+}
+
+/// One revision interval of a forecast history.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScoreSlice {
+    pub probability: f64,
+    pub slice_start: DateTime<Utc>,
+    pub slice_end: DateTime<Utc>,
+    /// Share of the scored span this revision was held for.
+    pub time_weight: f64,
+    pub log_score: Option<f64>,
+    pub brier_score: Option<f64>,
+}
+
+/// Shortest time a revision must be held to be scored on its own;
+/// SCORE_MIN_SLICE_SECS, default 1 second, 0 keeps every revision.
+fn min_slice() -> Duration {
+    let secs = env::var("SCORE_MIN_SLICE_SECS")
+        .ok()
+        .and_then(|v| v.parse::<f64>().ok())
+        .filter(|v| v.is_finite() && *v >= 0.0)
+        .unwrap_or(1.0);
+    Duration::milliseconds((secs * 1000.0).round() as i64)
+}
+
+/// Slices `revisions` (oldest first) into the intervals each was held for,
+/// ending at `end`. Revisions at or after `end` never took effect and are
+/// dropped, unless nothing earlier exists, in which case the first one is
+/// the whole history and gets weight 1.
+pub fn score_slices(
+    revisions: &[(DateTime<Utc>, f64)],
+    end: DateTime<Utc>,
+    outcome: Option<bool>,
+) -> Vec<ScoreSlice> {
+    slice_history(revisions, end, outcome, min_slice())
+}
+
+/// `score_slices` with an explicit minimum slice. A revision replaced in
+/// less than `min_slice` is folded into its replacement, which takes over
+/// its start; the last revision is always kept.
+fn slice_history(
+    revisions: &[(DateTime<Utc>, f64)],
+    end: DateTime<Utc>,
+    outcome: Option<bool>,
+    min_slice: Duration,
+) -> Vec<ScoreSlice> {
+    let in_effect: &[(DateTime<Utc>, f64)] =
+        match revisions.iter().position(|(at, _)| *at >= end) {
+            Some(0) => return slices_from(&[(end, revisions[0].1)], end, outcome),
+            Some(n) => &revisions[..n],
+            None => revisions,
+        };
+    let mut held: Vec<(DateTime<Utc>, f64)> = Vec::with_capacity(in_effect.len());
+    let mut folded_start = None;
+    for (i, &(at, probability)) in in_effect.iter().enumerate() {
+        let start = folded_start.take().unwrap_or(at);
+        match in_effect.get(i + 1) {
+            Some(&(replaced_at, _)) if replaced_at - at < min_slice => folded_start = Some(start),
+            _ => held.push((start, probability)),
+        }
+    }
+    slices_from(&held, end, outcome)
+}
+
+fn slices_from(
+    held: &[(DateTime<Utc>, f64)],
+    end: DateTime<Utc>,
+    outcome: Option<bool>,
+) -> Vec<ScoreSlice> {
+    let Some(&(first_at, _)) = held.first() else {
+        return Vec::new();
+    };
+    let span_ms = (end - first_at).num_milliseconds();
+
+    held.iter()
+        .enumerate()
+        .map(|(i, &(start, probability))| {
+            let slice_end = held.get(i + 1).map_or(end, |&(next, _)| next);
+            let time_weight = if span_ms > 0 {
+                (slice_end - start).num_milliseconds() as f64 / span_ms as f64
+            } else {
+                1.0 / held.len() as f64
+            };
+            ScoreSlice {
+                probability,
+                slice_start: start,
+                slice_end,
+                time_weight,
+                log_score: outcome.map(|o| log_score(probability, o)),
+                brier_score: outcome.map(|o| brier_score(probability, o)),
+            }
+        })
+        .collect()
+}
+
+/// What the event ran through `scored_until` did the forecasts span, 0-1.
+fn coverage(
+    opened_at: Option<DateTime<Utc>>,
+    scored_until: DateTime<Utc>,
+    slices: &[ScoreSlice],
+) -> f64 {
+    match (opened_at, slices.first()) {
+        (Some(opened_at), Some(first)) if scored_until > opened_at => {
+            let open_ms = (scored_until - opened_at).num_milliseconds() as f64;
+            ((scored_until - first.slice_start).num_milliseconds() as f64 / open_ms).clamp(0.0, 1.0)
+        }
+        _ => 0.0,
+    }
+}
+
+/// Σ weight · score over the slices; `None` until the event resolves.
+pub(crate) fn time_weighted(
+    slices: &[ScoreSlice],
+    score: impl Fn(&ScoreSlice) -> Option<f64>,
+) -> Option<f64> {
+    slices
+        .iter()
+        .map(|slice| score(slice).map(|s| s * slice.time_weight))
+        .sum()
+}
+
+pub async fn ensure_forecast_tables(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS forecast_revisions (
+            id BIGSERIAL PRIMARY KEY,
+            prediction_id INTEGER NOT NULL REFERENCES predictions(id) ON DELETE CASCADE,
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+            probability DOUBLE PRECISION NOT NULL CHECK (probability >= 0 AND probability <= 1),
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_forecast_revisions_user_event
+         ON forecast_revisions (user_id, event_id, created_at)",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS forecast_score_summaries (
+            prediction_id INTEGER PRIMARY KEY REFERENCES predictions(id) ON DELETE CASCADE,
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+            outcome BOOLEAN NOT NULL,
+            revision_count INTEGER NOT NULL,
+            first_forecast_at TIMESTAMPTZ NOT NULL,
+            last_forecast_at TIMESTAMPTZ NOT NULL,
+            final_probability DOUBLE PRECISION NOT NULL,
+            scored_until TIMESTAMPTZ NOT NULL,
+            coverage DOUBLE PRECISION NOT NULL,
+            time_weighted_log_score DOUBLE PRECISION NOT NULL,
+            time_weighted_brier_score DOUBLE PRECISION NOT NULL,
+            revisions_archived BOOLEAN NOT NULL,
+            compacted_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_forecast_score_summaries_user
+         ON forecast_score_summaries (user_id, event_id)",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS forecast_revisions_archive (
+            id BIGINT PRIMARY KEY,
+            prediction_id INTEGER NOT NULL REFERENCES predictions(id) ON DELETE CASCADE,
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+            probability DOUBLE PRECISION NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            archived_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_forecast_revisions_archive_prediction
+         ON forecast_revisions_archive (prediction_id, created_at)",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Backend convention: the favoured side and the percent put on it.
+fn prediction_value_and_confidence(probability: f64) -> (&'static str, i32) {
+    if probability >= 0.5 {
+        ("yes", (probability * 100.0).round() as i32)
+    } else {
+        ("no", ((1.0 - probability) * 100.0).round() as i32)
+    }
+}
+
+fn parse_binary_outcome(outcome: Option<&str>) -> Option<bool> {
+    match outcome {
+        Some("resolved_yes") => Some(true),
+        Some("resolved_no") => Some(false),
+        _ => None,
+    }
+}
+
+/// Locks the event against a concurrent resolution and checks it still
+/// takes forecasts; returns its title.
+async fn lock_open_event(tx: &mut Transaction<'_, Postgres>, event_id: i32) -> Result<String> {
+    let event = sqlx::query(
+        "SELECT title, COALESCE(event_type, 'binary') AS event_type, outcome,
+                (closed_at IS NOT NULL OR COALESCE(closing_date <= NOW(), false)) AS is_closed
+         FROM events WHERE id = $1 FOR SHARE",
+    )
+    .bind(event_id)
+    .fetch_optional(&mut **tx)
+    .await?
+    .ok_or_else(|| coded(ErrorCode::NotFound, "Event not found"))?;
+
+    if event.get::<String, _>("event_type") != "binary" {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "Forecasts only support binary events",
+        ));
+    }
+    if event.get::<Option<String>, _>("outcome").is_some() || event.get::<bool, _>("is_closed") {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "Event is closed to forecasts",
+        ));
+    }
+    Ok(event.get("title"))
+}
+
+async fn append_revision(
+    tx: &mut Transaction<'_, Postgres>,
+    prediction_id: i32,
+    user_id: i32,
+    event_id: i32,
+    probability: f64,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO forecast_revisions (prediction_id, user_id, event_id, probability)
+         VALUES ($1, $2, $3, $4)",
+    )
+    .bind(prediction_id)
+    .bind(user_id)
+    .bind(event_id)
+    .bind(probability)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+fn validate_probability(probability: f64) -> Result<()> {
+    if !probability.is_finite() || !(0.0..=1.0).contains(&probability) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "probability must be between 0 and 1",
+        ));
+    }
+    Ok(())
+}
+
+/// Records a user's first forecast on an event.
+pub async fn submit_forecast(
+    pool: &PgPool,
+    user_id: i32,
+    event_id: i32,
+    probability: f64,
+) -> Result<ForecastResult> {
+    validate_probability(probability)?;
+
+    let mut tx = pool.begin().await?;
+    let user_exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM users WHERE id = $1)")
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    if !user_exists {
+        return Err(coded(ErrorCode::NotFound, "User not found"));
+    }
+    let title = lock_open_event(&mut tx, event_id).await?;
+
+    let (prediction_value, confidence) = prediction_value_and_confidence(probability);
+    let prediction_id: i32 = sqlx::query_scalar(
+        r#"
+        INSERT INTO predictions
+            (user_id, event_id, event, prediction_value, confidence, prediction_type, prob_vector)
+        VALUES ($1, $2, $3, $4, $5, 'binary', $6)
+        ON CONFLICT (user_id, event_id) DO NOTHING
+        RETURNING id
+        "#,
+    )
+    .bind(user_id)
+    .bind(event_id)
+    .bind(&title)
+    .bind(prediction_value)
+    .bind(confidence)
+    .bind(serde_json::json!([probability, 1.0 - probability]))
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| {
+        coded(
+            ErrorCode::InvalidRequest,
+            "Forecast already recorded for this event; update it instead",
+        )
+    })?;
+    append_revision(&mut tx, prediction_id, user_id, event_id, probability).await?;
+
+    let result = load_forecast(&mut tx, user_id, event_id).await?;
+    tx.commit().await?;
+    Ok(result)
+}
+
+/// Replaces a user's forecast on an event, keeping the old one in the
+/// revision history. A prediction made before journaling existed gets its
+/// original probability and time recorded as the first revision.
+pub async fn update_forecast(
+    pool: &PgPool,
+    user_id: i32,
+    event_id: i32,
+    probability: f64,
+) -> Result<ForecastResult> {
+    validate_probability(probability)?;
+
+    let mut tx = pool.begin().await?;
+    lock_open_event(&mut tx, event_id).await?;
+    let existing = sqlx::query(
+        "SELECT p.id, (p.prob_vector->>0)::float8 AS probability,
+                p.created_at::timestamptz AS created_at,
+                EXISTS (SELECT 1 FROM forecast_revisions r WHERE r.prediction_id = p.id)
+                    AS has_revisions
+         FROM predictions p
+         WHERE p.user_id = $1 AND p.event_id = $2
+         FOR UPDATE OF p",
+    )
+    .bind(user_id)
+    .bind(event_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| coded(ErrorCode::NotFound, "Forecast not found"))?;
+    let prediction_id: i32 = existing.get("id");
+
+    if !existing.get::<bool, _>("has_revisions") {
+        if let (Some(original), Some(created_at)) = (
+            existing.get::<Option<f64>, _>("probability"),
+            existing.get::<Option<DateTime<Utc>>, _>("created_at"),
+        ) {
+            sqlx::query(
+                "INSERT INTO forecast_revisions
+                     (prediction_id, user_id, event_id, probability, created_at)
+                 VALUES ($1, $2, $3, $4, $5)",
+            )
+            .bind(prediction_id)
+            .bind(user_id)
+            .bind(event_id)
+            .bind(original.clamp(0.0, 1.0))
+            .bind(created_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    let (prediction_value, confidence) = prediction_value_and_confidence(probability);
+    sqlx::query(
+        "UPDATE predictions
+         SET prediction_value = $2, confidence = $3, prob_vector = $4
+         WHERE id = $1",
+    )
+    .bind(prediction_id)
+    .bind(prediction_value)
+    .bind(confidence)
+    .bind(serde_json::json!([probability, 1.0 - probability]))
+    .execute(&mut *tx)
+    .await?;
+    append_revision(&mut tx, prediction_id, user_id, event_id, probability).await?;
+
+    let result = load_forecast(&mut tx, user_id, event_id).await?;
+    tx.commit().await?;
+    Ok(result)
+}
+
+async fn load_forecast(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: i32,
+    event_id: i32,
+) -> Result<ForecastResult> {
+    let row = sqlx::query(
+        r#"
+        SELECT p.id, (p.prob_vector->>0)::float8 AS probability, e.outcome,
+               COUNT(r.id) AS revision_count,
+               MIN(r.created_at) AS created_at,
+               MAX(r.created_at) AS updated_at
+        FROM predictions p
+        JOIN events e ON e.id = p.event_id
+        JOIN forecast_revisions r ON r.prediction_id = p.id
+        WHERE p.user_id = $1 AND p.event_id = $2
+        GROUP BY p.id, e.outcome
+        "#,
+    )
+    .bind(user_id)
+    .bind(event_id)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    let probability: f64 = row.get("probability");
+    let outcome = parse_binary_outcome(row.get::<Option<String>, _>("outcome").as_deref());
+    Ok(ForecastResult {
+        prediction_id: row.get("id"),
+        user_id,
+        event_id,
+        probability,
+        revision_count: row.get("revision_count"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+        outcome,
+        brier_score: outcome.map(|o| brier_score(probability, o)),
+        log_score: outcome.map(|o| log_score(probability, o)),
+    })
+}
+
+/// A user's forecast revisions on an event with their score slices and
+/// time-weighted scores.
+pub async fn get_forecast_history(pool: &PgPool, user_id: i32, event_id: i32) -> Result<Value> {
+    let event = sqlx::query(
+        r#"
+        SELECT outcome, created_at::timestamptz AS opened_at,
+               CASE WHEN outcome IS NOT NULL THEN COALESCE(resolved_at::timestamptz, NOW())
+                    ELSE LEAST(NOW(),
+                               COALESCE(closed_at::timestamptz, NOW()),
+                               COALESCE(closing_date::timestamptz, NOW()))
+               END AS scored_until
+        FROM events WHERE id = $1
+        "#,
+    )
+    .bind(event_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| coded(ErrorCode::NotFound, "Event not found"))?;
+    let outcome = parse_binary_outcome(event.get::<Option<String>, _>("outcome").as_deref());
+    let opened_at: Option<DateTime<Utc>> = event.get("opened_at");
+    let scored_until: DateTime<Utc> = event.get("scored_until");
+
+    let prediction = sqlx::query(
+        "SELECT id, (prob_vector->>0)::float8 AS probability,
+                created_at::timestamptz AS created_at
+         FROM predictions WHERE user_id = $1 AND event_id = $2",
+    )
+    .bind(user_id)
+    .bind(event_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| coded(ErrorCode::NotFound, "Forecast not found"))?;
+    let prediction_id: i32 = prediction.get("id");
+
+    // A compacted forecast's scores are fixed in its summary; its revisions,
+    // if kept, are in the archive
+    let summary = sqlx::query(
+        "SELECT scored_until, coverage, time_weighted_log_score, time_weighted_brier_score
+         FROM forecast_score_summaries WHERE prediction_id = $1",
+    )
+    .bind(prediction_id)
+    .fetch_optional(pool)
+    .await?;
+    let revisions_sql = if summary.is_some() {
+        "SELECT created_at, probability FROM forecast_revisions_archive
+         WHERE prediction_id = $1
+         ORDER BY created_at, id"
+    } else {
+        "SELECT created_at, probability FROM forecast_revisions
+         WHERE prediction_id = $1
+         ORDER BY created_at, id"
+    };
+    let mut revisions: Vec<(DateTime<Utc>, f64)> = sqlx::query_as(revisions_sql)
+        .bind(prediction_id)
+        .fetch_all(pool)
+        .await?;
+    // Predictions made outside the journal are a single, never-revised forecast
+    if revisions.is_empty() && summary.is_none() {
+        if let (Some(created_at), Some(probability)) = (
+            prediction.get::<Option<DateTime<Utc>>, _>("created_at"),
+            prediction.get::<Option<f64>, _>("probability"),
+        ) {
+            revisions.push((created_at, probability.clamp(0.0, 1.0)));
+        }
+    }
+
+    let scored_until = summary
+        .as_ref()
+        .map_or(scored_until, |summary| summary.get("scored_until"));
+    let slices = score_slices(&revisions, scored_until, outcome);
+    let (coverage, log_score, brier_score) = match &summary {
+        Some(summary) => (
+            summary.get("coverage"),
+            summary.get("time_weighted_log_score"),
+            summary.get("time_weighted_brier_score"),
+        ),
+        None => (
+            coverage(opened_at, scored_until, &slices),
+            time_weighted(&slices, |s| s.log_score),
+            time_weighted(&slices, |s| s.brier_score),
+        ),
+    };
+
+    // Peer score: this forecast against the event's crowd
+    let crowd = crate::peer_scores::load_stats(pool, event_id).await?;
+    let peer_score = crowd
+        .as_ref()
+        .and_then(|crowd| Some(log_score? - crowd.crowd_log_score?));
+
+    Ok(json!({
+        "user_id": user_id,
+        "event_id": event_id,
+        "prediction_id": prediction_id,
+        "outcome": outcome,
+        "scored_until": scored_until,
+        "compacted": summary.is_some(),
+        "revisions": revisions
+            .iter()
+            .map(|(at, p)| json!({ "probability": p, "created_at": at }))
+            .collect::<Vec<_>>(),
+        "slices": slices,
+        "coverage": coverage,
+        "time_weighted_log_score": log_score,
+        "time_weighted_brier_score": brier_score,
+        "peer_score": peer_score,
+        "crowd": crowd,
+    }))
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Compaction {
+    pub predictions: usize,
+    pub revisions: u64,
+    /// False when the revisions were deleted rather than archived.
+    pub archived: bool,
+}
+
+/// Collapses the revisions of forecasts on events resolved more than
+/// `dispute_window_hours` ago into score summaries, archiving the
+/// revisions or deleting them. Runs in batches; call until it compacts
+/// nothing to catch up.
+pub async fn compact_settled(
+    pool: &PgPool,
+    dispute_window_hours: f64,
+    archive: bool,
+) -> Result<Compaction> {
+    let mut tx = pool.begin().await?;
+    let settled = sqlx::query(
+        r#"
+        SELECT p.id, p.user_id, p.event_id, e.outcome,
+               e.created_at::timestamptz AS opened_at,
+               e.resolved_at::timestamptz AS resolved_at
+        FROM predictions p
+        JOIN events e ON e.id = p.event_id
+        WHERE e.outcome IN ('resolved_yes', 'resolved_no')
+          AND e.resolved_at::timestamptz <= NOW() - $1 * INTERVAL '1 hour'
+          AND EXISTS (SELECT 1 FROM forecast_revisions r WHERE r.prediction_id = p.id)
+        ORDER BY p.id
+        LIMIT $2
+        FOR UPDATE OF p SKIP LOCKED
+        "#,
+    )
+    .bind(dispute_window_hours)
+    .bind(COMPACTION_BATCH)
+    .fetch_all(&mut *tx)
+    .await?;
+    if settled.is_empty() {
+        return Ok(Compaction {
+            archived: archive,
+            ..Compaction::default()
+        });
+    }
+
+    let ids: Vec<i32> = settled.iter().map(|row| row.get("id")).collect();
+    let mut revisions: BTreeMap<i32, Vec<(DateTime<Utc>, f64)>> = BTreeMap::new();
+    for (prediction_id, at, probability) in sqlx::query_as::<_, (i32, DateTime<Utc>, f64)>(
+        "SELECT prediction_id, created_at, probability FROM forecast_revisions
+         WHERE prediction_id = ANY($1)
+         ORDER BY prediction_id, created_at, id",
+    )
+    .bind(&ids)
+    .fetch_all(&mut *tx)
+    .await?
+    {
+        revisions
+            .entry(prediction_id)
+            .or_default()
+            .push((at, probability));
+    }
+
+    for row in &settled {
+        let prediction_id: i32 = row.get("id");
+        let Some(outcome) =
+            parse_binary_outcome(row.get::<Option<String>, _>("outcome").as_deref())
+        else {
+            continue;
+        };
+        let scored_until: DateTime<Utc> = row.get("resolved_at");
+        let history = revisions.remove(&prediction_id).unwrap_or_default();
+        let slices = score_slices(&history, scored_until, Some(outcome));
+        let (Some(&(first_at, _)), Some(&(last_at, final_probability))) =
+            (history.first(), history.last())
+        else {
+            continue;
+        };
+        sqlx::query(
+            r#"
+            INSERT INTO forecast_score_summaries
+                (prediction_id, user_id, event_id, outcome, revision_count,
+                 first_forecast_at, last_forecast_at, final_probability, scored_until,
+                 coverage, time_weighted_log_score, time_weighted_brier_score,
+                 revisions_archived)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            "#,
+        )
+        .bind(prediction_id)
+        .bind(row.get::<i32, _>("user_id"))
+        .bind(row.get::<i32, _>("event_id"))
+        .bind(outcome)
+        .bind(history.len() as i32)
+        .bind(first_at)
+        .bind(last_at)
+        .bind(final_probability)
+        .bind(scored_until)
+        .bind(coverage(row.get("opened_at"), scored_until, &slices))
+        .bind(time_weighted(&slices, |s| s.log_score))
+        .bind(time_weighted(&slices, |s| s.brier_score))
+        .bind(archive)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let moved = if archive {
+        sqlx::query(
+            r#"
+            WITH moved AS (
+                DELETE FROM forecast_revisions WHERE prediction_id = ANY($1)
+                RETURNING id, prediction_id, user_id, event_id, probability, created_at
+            )
+            INSERT INTO forecast_revisions_archive
+                (id, prediction_id, user_id, event_id, probability, created_at)
+            SELECT id, prediction_id, user_id, event_id, probability, created_at FROM moved
+            "#,
+        )
+    } else {
+        sqlx::query("DELETE FROM forecast_revisions WHERE prediction_id = ANY($1)")
+    }
+    .bind(&ids)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(Compaction {
+        predictions: ids.len(),
+        revisions: moved.rows_affected(),
+        archived: archive,
+    })
+}
+
+/// Undoes compaction for an event whose resolution is being reverted:
+/// archived revisions go back to the journal, and a forecast whose
+/// revisions were deleted gets its final probability back as one revision.
+async fn restore_compacted(tx: &mut Transaction<'_, Postgres>, event_id: i32) -> Result<()> {
+    let compacted: bool =
+        sqlx::query_scalar("SELECT to_regclass('forecast_score_summaries') IS NOT NULL")
+            .fetch_one(&mut **tx)
+            .await?;
+    if !compacted {
+        return Ok(());
+    }
+
+    sqlx::query(
+        r#"
+        WITH restored AS (
+            DELETE FROM forecast_revisions_archive WHERE event_id = $1
+            RETURNING id, prediction_id, user_id, event_id, probability, created_at
+        )
+        INSERT INTO forecast_revisions (id, prediction_id, user_id, event_id, probability, created_at)
+        SELECT id, prediction_id, user_id, event_id, probability, created_at FROM restored
+        "#,
+    )
+    .bind(event_id)
+    .execute(&mut **tx)
+    .await?;
+    sqlx::query(
+        r#"
+        INSERT INTO forecast_revisions (prediction_id, user_id, event_id, probability, created_at)
+        SELECT s.prediction_id, s.user_id, s.event_id, s.final_probability, s.last_forecast_at
+        FROM forecast_score_summaries s
+        WHERE s.event_id = $1
+          AND NOT EXISTS (
+              SELECT 1 FROM forecast_revisions r WHERE r.prediction_id = s.prediction_id
+          )
+        "#,
+    )
+    .bind(event_id)
+    .execute(&mut **tx)
+    .await?;
+    sqlx::query("DELETE FROM forecast_score_summaries WHERE event_id = $1")
+        .bind(event_id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// Settles journaled forecasts when an event resolves (`Some`) and reopens
+/// them when a dispute reverts the resolution (`None`). An N/A resolution
+/// marks them `not_applicable`, which no score or count includes.
+/// Predictions made outside the journal are left to the backend's own
+/// resolution flow.
+pub(crate) async fn settle_event(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    resolution: Option<Resolution>,
+) -> Result<()> {
+    let table_exists: bool =
+        sqlx::query_scalar("SELECT to_regclass('forecast_revisions') IS NOT NULL")
+            .fetch_one(&mut **tx)
+            .await?;
+    if !table_exists {
+        return Ok(());
+    }
+    if resolution.is_none() {
+        restore_compacted(tx, event_id).await?;
+    }
+    let outcome = resolution.and_then(Resolution::scored);
+
+    sqlx::query(
+        r#"
+        UPDATE predictions p
+        SET outcome = CASE
+                WHEN NOT $3 THEN NULL
+                WHEN $2::boolean IS NULL THEN 'not_applicable'
+                WHEN (p.prediction_value = 'yes') = $2 THEN 'correct'
+                ELSE 'incorrect'
+            END,
+            resolved_at = CASE WHEN $3 THEN NOW() END
+        WHERE p.event_id = $1
+          AND EXISTS (SELECT 1 FROM forecast_revisions r WHERE r.prediction_id = p.id)
+        "#,
+    )
+    .bind(event_id)
+    .bind(outcome)
+    .bind(resolution.is_some())
+    .execute(&mut **tx)
+    .await?;
+    crate::peer_scores::settle_event(tx, event_id, outcome).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hours: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap() + Duration::hours(hours)
+    }
+
+    fn at_secs(secs: i64) -> DateTime<Utc> {
+        at(0) + Duration::seconds(secs)
+    }
+
+    #[test]
+    fn slices_weight_each_revision_by_how_long_it_was_held() {
+        let revisions = [(at(0), 0.2), (at(6), 0.6), (at(8), 0.9)];
+        let slices = score_slices(&revisions, at(10), Some(true));
+        let weights: Vec<f64> = slices.iter().map(|s| s.time_weight).collect();
+        assert_eq!(weights, vec![0.6, 0.2, 0.2]);
+        assert_eq!(slices[1].slice_start, at(6));
+        assert_eq!(slices[1].slice_end, at(8));
+        assert_eq!(slices[2].slice_end, at(10));
+
+        let expected = 0.6 * 0.2_f64.ln() + 0.2 * 0.6_f64.ln() + 0.2 * 0.9_f64.ln();
+        let score = time_weighted(&slices, |s| s.log_score).unwrap();
+        assert!((score - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn unresolved_history_has_weights_but_no_scores() {
+        let slices = score_slices(&[(at(0), 0.5), (at(1), 0.7)], at(2), None);
+        assert_eq!(slices.len(), 2);
+        assert!(slices.iter().all(|s| s.log_score.is_none()));
+        assert_eq!(time_weighted(&slices, |s| s.log_score), None);
+    }
+
+    #[test]
+    fn revisions_after_the_end_never_took_effect() {
+        let slices = score_slices(&[(at(0), 0.4), (at(5), 0.95)], at(4), Some(false));
+        assert_eq!(slices.len(), 1);
+        assert_eq!(slices[0].time_weight, 1.0);
+
+        // A lone forecast at the end is the whole history
+        let slices = score_slices(&[(at(4), 0.4)], at(4), Some(false));
+        assert_eq!(slices.len(), 1);
+        assert_eq!(slices[0].time_weight, 1.0);
+        assert!(score_slices(&[], at(4), None).is_empty());
+    }
+
+    #[test]
+    fn questions_open_under_an_hour_slice_by_the_second() {
+        // Ten minutes open: held 3m, 6m30s and 30s.
+        let revisions = [(at_secs(0), 0.3), (at_secs(180), 0.7), (at_secs(570), 0.9)];
+        let slices = slice_history(&revisions, at_secs(600), Some(true), Duration::seconds(1));
+        let weights: Vec<f64> = slices.iter().map(|s| s.time_weight).collect();
+        assert_eq!(weights, vec![0.3, 0.65, 0.05]);
+        let expected = 0.3 * 0.3_f64.ln() + 0.65 * 0.7_f64.ln() + 0.05 * 0.9_f64.ln();
+        assert!((time_weighted(&slices, |s| s.log_score).unwrap() - expected).abs() < 1e-12);
+
+        // A 90-second question with one forecast 45s in
+        let slices = slice_history(&[(at_secs(45), 0.8)], at_secs(90), Some(false), Duration::seconds(1));
+        assert_eq!(slices.len(), 1);
+        assert_eq!(slices[0].time_weight, 1.0);
+        assert_eq!(slices[0].slice_end - slices[0].slice_start, Duration::seconds(45));
+    }
+
+    #[test]
+    fn irregular_intervals_weigh_exact_durations() {
+        // 1s, 59s and 3599s: nothing rounds to whole hours or minutes.
+        let revisions = [(at_secs(0), 0.5), (at_secs(1), 0.6), (at_secs(60), 0.7)];
+        let slices = slice_history(&revisions, at_secs(3659), None, Duration::zero());
+        let weights: Vec<f64> = slices.iter().map(|s| s.time_weight).collect();
+        assert_eq!(weights, vec![1.0 / 3659.0, 59.0 / 3659.0, 3599.0 / 3659.0]);
+        assert!((weights.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn revisions_replaced_within_the_minimum_slice_fold_into_their_replacement() {
+        // 0.1 lasted 500ms before the fix to 0.8: 0.8 is held from 0s.
+        let revisions = [
+            (at_secs(0), 0.1),
+            (at_secs(0) + Duration::milliseconds(500), 0.8),
+            (at_secs(30), 0.6),
+        ];
+        let slices = slice_history(&revisions, at_secs(60), Some(true), Duration::seconds(1));
+        assert_eq!(slices.len(), 2);
+        assert_eq!((slices[0].probability, slices[0].slice_start), (0.8, at_secs(0)));
+        assert_eq!(slices[0].time_weight, 0.5);
+        assert_eq!(slices[1].time_weight, 0.5);
+
+        // Without a minimum the flicker keeps its own, tiny slice
+        let slices = slice_history(&revisions, at_secs(60), Some(true), Duration::zero());
+        assert_eq!(slices.len(), 3);
+        assert!((slices[0].time_weight - 0.5 / 60.0).abs() < 1e-12);
+
+        // The final revision counts however late it came
+        let slices = slice_history(
+            &[(at_secs(0), 0.4), (at_secs(59) + Duration::milliseconds(900), 0.9)],
+            at_secs(60),
+            Some(true),
+            Duration::seconds(1),
+        );
+        assert_eq!(slices.len(), 2);
+        assert!(slices[1].time_weight > 0.0);
+    }
+}
@@ -0,0 +1,445 @@
+//! Directory of MLS groups by application group id.
+//!
+//! Members publish their group's latest GroupInfo (and ratchet tree, when
+//! it isn't in the GroupInfo's extension) under the application's group
+//! id, so someone joining by external commit can fetch it. The directory
+//! also keeps membership hints: the users it believes are in the group.
+//! They decide who may read the listing and publish to it, but the MLS
+//! group itself stays the authority on membership.
+//!
+//! Anyone else needs an invite: a capability token a member mints, shown
+//! once and stored only as its SHA-256 hash. A token can be bound to the
+//! invitee's user id; an unbound one binds to the first user who redeems
+//! it. Until it expires or is revoked, its holder may read the listing and
+//! the Welcomes posted to the group for their key package, and redeeming
+//! it adds them to the membership hints so they can publish the GroupInfo
+//! after their own commit.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use rand::RngCore;
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool, Row};
+
+use crate::api_error::{coded, ErrorCode};
+use crate::api_keys::hash_key;
+use crate::mls_delivery::{self, StoredWelcome};
+
+pub const TOKEN_PREFIX: &str = "gi_";
+pub const DEFAULT_INVITE_TTL_HOURS: i64 = 72;
+pub const MAX_INVITE_TTL_HOURS: i64 = 720;
+pub const MAX_APP_GROUP_ID_LENGTH: usize = 128;
+
+pub const ERR_NOT_MEMBER: &str = "not a member of this group";
+pub const ERR_INVALID_INVITE: &str = "invite token is invalid, expired or for another user";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DirectoryListing {
+    pub app_group_id: String,
+    /// Hex-encoded MLS group id.
+    pub mls_group_id: String,
+    pub epoch: i64,
+    /// Hex-encoded TLS GroupInfo.
+    pub group_info: String,
+    /// Hex-encoded TLS ratchet tree, if published apart from the GroupInfo.
+    pub ratchet_tree: Option<String>,
+    pub owner_user_id: Option<i32>,
+    pub member_user_ids: Vec<i32>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MintedInvite {
+    pub invite_id: i64,
+    pub app_group_id: String,
+    /// Shown only here; the directory keeps its hash.
+    pub token: String,
+    pub invitee_user_id: Option<i32>,
+    pub expires_at: DateTime<Utc>,
+}
+
+pub async fn ensure_directory_tables(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS mls_directory_groups (
+            app_group_id TEXT PRIMARY KEY,
+            mls_group_id BYTEA NOT NULL,
+            epoch BIGINT NOT NULL,
+            group_info BYTEA NOT NULL,
+            ratchet_tree BYTEA,
+            owner_user_id INTEGER REFERENCES users(id) ON DELETE SET NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS mls_directory_members (
+            app_group_id TEXT NOT NULL
+                REFERENCES mls_directory_groups(app_group_id) ON DELETE CASCADE,
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            added_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            PRIMARY KEY (app_group_id, user_id)
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS mls_directory_invites (
+            id BIGSERIAL PRIMARY KEY,
+            app_group_id TEXT NOT NULL
+                REFERENCES mls_directory_groups(app_group_id) ON DELETE CASCADE,
+            token_hash TEXT NOT NULL UNIQUE,
+            inviter_user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            invitee_user_id INTEGER REFERENCES users(id) ON DELETE CASCADE,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            expires_at TIMESTAMPTZ NOT NULL,
+            redeemed_at TIMESTAMPTZ,
+            revoked_at TIMESTAMPTZ
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+fn validate_app_group_id(app_group_id: &str) -> Result<()> {
+    if app_group_id.is_empty() || app_group_id.len() > MAX_APP_GROUP_ID_LENGTH {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!(
+                "app_group_id must be 1-{} characters",
+                MAX_APP_GROUP_ID_LENGTH
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("{}{}", TOKEN_PREFIX, hex::encode(bytes))
+}
+
+async fn is_member(conn: &mut PgConnection, app_group_id: &str, user_id: i32) -> Result<bool> {
+    Ok(sqlx::query_scalar(
+        "SELECT EXISTS(
+             SELECT 1 FROM mls_directory_members WHERE app_group_id = $1 AND user_id = $2
+         )",
+    )
+    .bind(app_group_id)
+    .bind(user_id)
+    .fetch_one(&mut *conn)
+    .await?)
+}
+
+async fn group_exists(conn: &mut PgConnection, app_group_id: &str) -> Result<()> {
+    let exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM mls_directory_groups WHERE app_group_id = $1)",
+    )
+    .bind(app_group_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    if !exists {
+        return Err(coded(ErrorCode::NotFound, "Group not found"));
+    }
+    Ok(())
+}
+
+/// Lets a member through, or redeems `token` for a non-member: binds it to
+/// them if unbound and adds them to the membership hints.
+async fn authorize(
+    conn: &mut PgConnection,
+    app_group_id: &str,
+    user_id: i32,
+    token: Option<&str>,
+) -> Result<()> {
+    group_exists(conn, app_group_id).await?;
+    if is_member(conn, app_group_id, user_id).await? {
+        return Ok(());
+    }
+    let Some(token) = token else {
+        return Err(coded(ErrorCode::Forbidden, ERR_NOT_MEMBER));
+    };
+    let redeemed: Option<i64> = sqlx::query_scalar(
+        "UPDATE mls_directory_invites
+         SET invitee_user_id = $3, redeemed_at = COALESCE(redeemed_at, NOW())
+         WHERE token_hash = $1
+           AND app_group_id = $2
+           AND revoked_at IS NULL
+           AND expires_at > NOW()
+           AND (invitee_user_id IS NULL OR invitee_user_id = $3)
+         RETURNING id",
+    )
+    .bind(hash_key(token))
+    .bind(app_group_id)
+    .bind(user_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    if redeemed.is_none() {
+        return Err(coded(ErrorCode::Forbidden, ERR_INVALID_INVITE));
+    }
+    sqlx::query(
+        "INSERT INTO mls_directory_members (app_group_id, user_id) VALUES ($1, $2)
+         ON CONFLICT DO NOTHING",
+    )
+    .bind(app_group_id)
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+async fn load_listing(conn: &mut PgConnection, app_group_id: &str) -> Result<DirectoryListing> {
+    let row = sqlx::query(
+        "SELECT g.*,
+                ARRAY(SELECT user_id FROM mls_directory_members m
+                      WHERE m.app_group_id = g.app_group_id ORDER BY user_id) AS member_user_ids
+         FROM mls_directory_groups g
+         WHERE g.app_group_id = $1",
+    )
+    .bind(app_group_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| coded(ErrorCode::NotFound, "Group not found"))?;
+    Ok(DirectoryListing {
+        app_group_id: row.get("app_group_id"),
+        mls_group_id: hex::encode(row.get::<Vec<u8>, _>("mls_group_id")),
+        epoch: row.get("epoch"),
+        group_info: hex::encode(row.get::<Vec<u8>, _>("group_info")),
+        ratchet_tree: row
+            .get::<Option<Vec<u8>>, _>("ratchet_tree")
+            .map(hex::encode),
+        owner_user_id: row.get("owner_user_id"),
+        member_user_ids: row.get("member_user_ids"),
+        updated_at: row.get("updated_at"),
+    })
+}
+
+/// Publishes a group's GroupInfo. The first publisher lists the group and
+/// owns it; after that only members may publish, for the same MLS group
+/// and no earlier epoch. `member_user_ids`, when given, replaces the
+/// membership hints (the publisher is always kept).
+pub async fn publish_group(
+    pool: &PgPool,
+    app_group_id: &str,
+    user_id: i32,
+    group_info: &[u8],
+    ratchet_tree: Option<&[u8]>,
+    member_user_ids: Option<&[i32]>,
+) -> Result<DirectoryListing> {
+    validate_app_group_id(app_group_id)?;
+    let (mls_group_id, epoch) = mls_delivery::group_info_header(group_info)?;
+
+    let mut tx = pool.begin().await?;
+    let current = sqlx::query(
+        "SELECT mls_group_id, epoch FROM mls_directory_groups
+         WHERE app_group_id = $1 FOR UPDATE",
+    )
+    .bind(app_group_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    match current {
+        None => {
+            sqlx::query(
+                "INSERT INTO mls_directory_groups
+                     (app_group_id, mls_group_id, epoch, group_info, ratchet_tree, owner_user_id)
+                 VALUES ($1, $2, $3, $4, $5, $6)",
+            )
+            .bind(app_group_id)
+            .bind(&mls_group_id)
+            .bind(epoch)
+            .bind(group_info)
+            .bind(ratchet_tree)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        Some(current) => {
+            if !is_member(&mut tx, app_group_id, user_id).await? {
+                return Err(coded(ErrorCode::Forbidden, ERR_NOT_MEMBER));
+            }
+            if current.get::<Vec<u8>, _>("mls_group_id") != mls_group_id {
+                return Err(coded(
+                    ErrorCode::InvalidRequest,
+                    "group_info must be for the listed MLS group",
+                ));
+            }
+            let current_epoch: i64 = current.get("epoch");
+            if epoch < current_epoch {
+                return Err(mls_delivery::epoch_conflict(current_epoch, epoch));
+            }
+            sqlx::query(
+                "UPDATE mls_directory_groups
+                 SET epoch = $2, group_info = $3, ratchet_tree = $4, updated_at = NOW()
+                 WHERE app_group_id = $1",
+            )
+            .bind(app_group_id)
+            .bind(epoch)
+            .bind(group_info)
+            .bind(ratchet_tree)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    if let Some(members) = member_user_ids {
+        sqlx::query("DELETE FROM mls_directory_members WHERE app_group_id = $1 AND user_id <> $2")
+            .bind(app_group_id)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "INSERT INTO mls_directory_members (app_group_id, user_id)
+             SELECT $1, u.id FROM users u WHERE u.id = ANY($2)
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(app_group_id)
+        .bind(members)
+        .execute(&mut *tx)
+        .await?;
+    }
+    sqlx::query(
+        "INSERT INTO mls_directory_members (app_group_id, user_id) VALUES ($1, $2)
+         ON CONFLICT DO NOTHING",
+    )
+    .bind(app_group_id)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    let listing = load_listing(&mut tx, app_group_id).await?;
+    tx.commit().await?;
+    Ok(listing)
+}
+
+/// The listing, for a member or the holder of a valid invite `token`.
+pub async fn get_listing(
+    pool: &PgPool,
+    app_group_id: &str,
+    user_id: i32,
+    token: Option<&str>,
+) -> Result<DirectoryListing> {
+    let mut tx = pool.begin().await?;
+    authorize(&mut tx, app_group_id, user_id, token).await?;
+    let listing = load_listing(&mut tx, app_group_id).await?;
+    tx.commit().await?;
+    Ok(listing)
+}
+
+/// Mints an invite token for the group; only members can invite.
+pub async fn mint_invite(
+    pool: &PgPool,
+    app_group_id: &str,
+    inviter_user_id: i32,
+    invitee_user_id: Option<i32>,
+    ttl_hours: i64,
+) -> Result<MintedInvite> {
+    if !(1..=MAX_INVITE_TTL_HOURS).contains(&ttl_hours) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("ttl_hours must be between 1 and {}", MAX_INVITE_TTL_HOURS),
+        ));
+    }
+    let mut conn = pool.acquire().await?;
+    group_exists(&mut conn, app_group_id).await?;
+    if !is_member(&mut conn, app_group_id, inviter_user_id).await? {
+        return Err(coded(ErrorCode::Forbidden, ERR_NOT_MEMBER));
+    }
+    if let Some(invitee) = invitee_user_id {
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
+            .bind(invitee)
+            .fetch_one(&mut *conn)
+            .await?;
+        if !exists {
+            return Err(coded(ErrorCode::NotFound, "User not found"));
+        }
+    }
+
+    let token = generate_token();
+    let expires_at = Utc::now() + Duration::hours(ttl_hours);
+    let invite_id: i64 = sqlx::query_scalar(
+        "INSERT INTO mls_directory_invites
+             (app_group_id, token_hash, inviter_user_id, invitee_user_id, expires_at)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING id",
+    )
+    .bind(app_group_id)
+    .bind(hash_key(&token))
+    .bind(inviter_user_id)
+    .bind(invitee_user_id)
+    .bind(expires_at)
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(MintedInvite {
+        invite_id,
+        app_group_id: app_group_id.to_string(),
+        token,
+        invitee_user_id,
+        expires_at,
+    })
+}
+
+/// Revokes an invite; its inviter or the group's owner may. Returns
+/// whether there was a live invite to revoke.
+pub async fn revoke_invite(
+    pool: &PgPool,
+    app_group_id: &str,
+    user_id: i32,
+    invite_id: i64,
+) -> Result<bool> {
+    Ok(sqlx::query(
+        "UPDATE mls_directory_invites i
+         SET revoked_at = NOW()
+         FROM mls_directory_groups g
+         WHERE i.id = $1
+           AND i.app_group_id = $2
+           AND g.app_group_id = i.app_group_id
+           AND i.revoked_at IS NULL
+           AND (i.inviter_user_id = $3 OR g.owner_user_id = $3)",
+    )
+    .bind(invite_id)
+    .bind(app_group_id)
+    .bind(user_id)
+    .execute(pool)
+    .await?
+    .rows_affected()
+        > 0)
+}
+
+/// Stores a Welcome a member posts for the group's new members.
+pub async fn post_welcome(
+    pool: &PgPool,
+    app_group_id: &str,
+    user_id: i32,
+    welcome: &[u8],
+) -> Result<Vec<String>> {
+    let mut conn = pool.acquire().await?;
+    group_exists(&mut conn, app_group_id).await?;
+    if !is_member(&mut conn, app_group_id, user_id).await? {
+        return Err(coded(ErrorCode::Forbidden, ERR_NOT_MEMBER));
+    }
+    drop(conn);
+    mls_delivery::store_welcome(pool, welcome, Some(app_group_id)).await
+}
+
+/// The group's Welcomes for a key package, for a member or the holder of a
+/// valid invite `token`.
+pub async fn get_welcomes(
+    pool: &PgPool,
+    app_group_id: &str,
+    user_id: i32,
+    token: Option<&str>,
+    key_package_ref: &[u8],
+) -> Result<Vec<StoredWelcome>> {
+    let mut tx = pool.begin().await?;
+    authorize(&mut tx, app_group_id, user_id, token).await?;
+    tx.commit().await?;
+    mls_delivery::get_welcomes(pool, key_package_ref, Some(app_group_id)).await
+}
@@ -0,0 +1,454 @@
+// Financial invariants, shared by the integration tests, the stress
+// harness and (with the `invariant-checks` cargo feature) the server, which
+// re-checks a sample of traded users and resolved events after commit.
+//
+// The per-user and per-event checks wrap the lmsr_api verify_* functions,
+// so the HTTP verify endpoints, the tests and production sampling all agree
+// on what "consistent" means. Production sampling never blocks or fails a
+// request: checks run on a spawned task, violations are logged with their
+// full context and counted, and the counts are served by
+// /lmsr/invariant-stats.
+//
+// EventBooks is the double-entry view of one event: users' RP on one side,
+// the market maker on the other, through trades, sells and a YES/NO or N/A
+// settlement.
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InvariantViolation {
+    pub invariant: &'static str,
+    pub user_id: Option<i32>,
+    pub event_id: Option<i32>,
+    pub message: String,
+    /// Ledger figures behind the verdict, as the verify function reported them.
+    pub context: Value,
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let subject = match (self.user_id, self.event_id) {
+            (Some(user_id), _) => format!("user {}", user_id),
+            (None, Some(event_id)) => format!("event {}", event_id),
+            (None, None) => "system".to_string(),
+        };
+        write!(
+            f,
+            "{} invariant failed for {}: {}",
+            self.invariant, subject, self.message
+        )
+    }
+}
+
+fn violation_from(
+    invariant: &'static str,
+    user_id: Option<i32>,
+    event_id: Option<i32>,
+    result: &Value,
+) -> Option<InvariantViolation> {
+    if result["valid"].as_bool().unwrap_or(false) {
+        return None;
+    }
+    Some(InvariantViolation {
+        invariant,
+        user_id,
+        event_id,
+        message: result["message"]
+            .as_str()
+            .unwrap_or("no message")
+            .to_string(),
+        context: result.get("details").cloned().unwrap_or(Value::Null),
+    })
+}
+
+/// Balance and staked invariants for one user.
+pub async fn check_user_ledger(pool: &PgPool, user_id: i32) -> Result<Vec<InvariantViolation>> {
+    let balance = crate::lmsr_api::verify_balance_invariant(pool, user_id).await?;
+    let staked = crate::lmsr_api::verify_staked_invariant(pool, user_id).await?;
+    Ok([
+        violation_from("Balance", Some(user_id), None, &balance),
+        violation_from("Staked", Some(user_id), None, &staked),
+    ]
+    .into_iter()
+    .flatten()
+    .collect())
+}
+
+/// Post-resolution invariant for one event: positions cleared, and
+/// resolved_at stamped by the transaction that settled the market.
+pub async fn check_event_resolution(
+    pool: &PgPool,
+    event_id: i32,
+) -> Result<Vec<InvariantViolation>> {
+    let result = crate::lmsr_api::verify_post_resolution_invariant(pool, event_id).await?;
+    let mut violations: Vec<InvariantViolation> =
+        violation_from("Post-resolution", None, Some(event_id), &result)
+            .into_iter()
+            .collect();
+
+    let resolved_at_stamped: Option<bool> =
+        sqlx::query_scalar("SELECT resolved_at IS NOT NULL FROM events WHERE id = $1")
+            .bind(event_id)
+            .fetch_optional(pool)
+            .await?;
+    if resolved_at_stamped == Some(false) {
+        violations.push(InvariantViolation {
+            invariant: "Post-resolution",
+            user_id: None,
+            event_id: Some(event_id),
+            message: "resolved_at not stamped".to_string(),
+            context: result.get("details").cloned().unwrap_or(Value::Null),
+        });
+    }
+    Ok(violations)
+}
+
+/// Conservation across users: each user's balance + staked must equal their
+/// starting total plus the changes the caller expects (trade deltas,
+/// resolution credits). Every map is keyed by user id, in ledger units.
+#[allow(dead_code)] // only the tests call this, not the server binary
+pub fn check_conservation(
+    initial: &HashMap<i32, (i64, i64)>,
+    current: &HashMap<i32, (i64, i64)>,
+    expected_changes: &HashMap<i32, i64>,
+) -> Vec<InvariantViolation> {
+    let mut user_ids: Vec<&i32> = current.keys().collect();
+    user_ids.sort();
+    user_ids
+        .into_iter()
+        .filter_map(|user_id| {
+            let (balance, staked) = current[user_id];
+            let (initial_balance, initial_staked) = initial.get(user_id).copied().unwrap_or((0, 0));
+            let change = expected_changes.get(user_id).copied().unwrap_or(0);
+            let expected = initial_balance + initial_staked + change;
+            let actual = balance + staked;
+            (actual != expected).then(|| InvariantViolation {
+                invariant: "Conservation",
+                user_id: Some(*user_id),
+                event_id: None,
+                message: format!(
+                    "expected {}, got {} (diff: {})",
+                    expected,
+                    actual,
+                    actual - expected
+                ),
+                context: json!({
+                    "initial_balance_ledger": initial_balance,
+                    "initial_staked_ledger": initial_staked,
+                    "balance_ledger": balance,
+                    "staked_ledger": staked,
+                    "expected_change_ledger": change,
+                }),
+            })
+        })
+        .collect()
+}
+
+// --- Double-entry books per event ---
+
+/// Where every ledger unit touched by one event sits at a point in its
+/// lifecycle. Whatever users pay in, the other side must hold.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct EventBooks {
+    /// Σ rp_balance_ledger over the event's traders. Staked RP is left out:
+    /// it records what the AMM already holds, so counting it would book
+    /// every stake twice.
+    pub users_ledger: i64,
+    /// Net RP held by the market maker: LMSR cost collected, less sell
+    /// proceeds and resolution payouts. Negative once the AMM has paid out
+    /// more than it took in, i.e. its subsidy.
+    pub amm_ledger: i64,
+}
+
+impl EventBooks {
+    pub fn total(&self) -> i64 {
+        self.users_ledger + self.amm_ledger
+    }
+}
+
+/// How far a binary market has settled, as its market maker sees it.
+#[allow(dead_code)] // only the tests build these, not the server binary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmmSettlement {
+    Open,
+    /// Resolved YES (true) or NO: each winning share issued pays 1 RP.
+    Resolved(bool),
+    /// Resolved N/A: no share redeems, the open stakes are refunded.
+    Voided {
+        refunded_ledger: i64,
+    },
+}
+
+/// Market-maker side of a binary market's books, from its share quantities
+/// and settlement: C(q_close) - C(q_open), less the winning shares issued
+/// since opening or the stakes refunded by a void.
+#[allow(dead_code)] // only the tests call this, not the server binary
+pub fn binary_amm_ledger(
+    q_open: (f64, f64),
+    q_close: (f64, f64),
+    liquidity_b: f64,
+    settlement: AmmSettlement,
+) -> Result<i64> {
+    let collected = crate::lmsr_core::cost(q_close.0, q_close.1, liquidity_b)
+        - crate::lmsr_core::cost(q_open.0, q_open.1, liquidity_b);
+    let (redeemed, refunded_ledger) = match settlement {
+        AmmSettlement::Open => (0.0, 0),
+        AmmSettlement::Resolved(true) => (q_close.0 - q_open.0, 0),
+        AmmSettlement::Resolved(false) => (q_close.1 - q_open.1, 0),
+        AmmSettlement::Voided { refunded_ledger } => (0.0, refunded_ledger),
+    };
+    let ledger =
+        crate::lmsr_core::to_ledger_units(collected - redeemed).map_err(|e| anyhow::anyhow!(e))?;
+    i64::try_from(ledger)
+        .ok()
+        .and_then(|ledger| ledger.checked_sub(refunded_ledger))
+        .ok_or_else(|| anyhow::anyhow!("AMM ledger out of i64 range"))
+}
+
+/// Double entry: an event's books must total the same at every point of its
+/// lifecycle. `tolerance_ledger` absorbs per-trade rounding to ledger units.
+#[allow(dead_code)] // only the tests call this, not the server binary
+pub fn check_double_entry(
+    event_id: i32,
+    opening: &EventBooks,
+    current: &EventBooks,
+    tolerance_ledger: i64,
+) -> Option<InvariantViolation> {
+    let drift = current.total() - opening.total();
+    (drift.abs() > tolerance_ledger).then(|| InvariantViolation {
+        invariant: "Double-entry",
+        user_id: None,
+        event_id: Some(event_id),
+        message: format!(
+            "books total {} but opened at {} (diff: {})",
+            current.total(),
+            opening.total(),
+            drift
+        ),
+        context: json!({
+            "opening": opening,
+            "current": current,
+            "tolerance_ledger": tolerance_ledger,
+        }),
+    })
+}
+
+/// LMSR bounds the market maker's loss on a binary market at b·ln 2; a
+/// bigger subsidy means RP was created somewhere.
+#[allow(dead_code)] // only the tests call this, not the server binary
+pub fn check_amm_subsidy(
+    event_id: i32,
+    books: &EventBooks,
+    liquidity_b: f64,
+    tolerance_ledger: i64,
+) -> Option<InvariantViolation> {
+    let max_subsidy = (liquidity_b * std::f64::consts::LN_2 * crate::lmsr_core::LEDGER_SCALE as f64)
+        .ceil() as i64
+        + tolerance_ledger;
+    let subsidy = -books.amm_ledger;
+    (subsidy > max_subsidy).then(|| InvariantViolation {
+        invariant: "AMM subsidy",
+        user_id: None,
+        event_id: Some(event_id),
+        message: format!(
+            "market maker paid {} net, over the b·ln2 bound of {}",
+            subsidy, max_subsidy
+        ),
+        context: json!({ "books": books, "liquidity_b": liquidity_b }),
+    })
+}
+
+// --- Production sampling ---
+
+static CHECKS_RUN: AtomicU64 = AtomicU64::new(0);
+static VIOLATIONS: AtomicU64 = AtomicU64::new(0);
+static CHECK_ERRORS: AtomicU64 = AtomicU64::new(0);
+
+/// Share of trades/resolutions re-checked; INVARIANT_SAMPLE_RATE, default 1%.
+fn sample_rate() -> f64 {
+    env::var("INVARIANT_SAMPLE_RATE")
+        .ok()
+        .and_then(|v| v.parse::<f64>().ok())
+        .filter(|v| v.is_finite())
+        .map(|v| v.clamp(0.0, 1.0))
+        .unwrap_or(0.01)
+}
+
+fn sampled() -> bool {
+    cfg!(feature = "invariant-checks") && rand::random::<f64>() < sample_rate()
+}
+
+fn record(kind: &'static str, operation: &str, result: Result<Vec<InvariantViolation>>) {
+    CHECKS_RUN.fetch_add(1, Ordering::Relaxed);
+    match result {
+        Ok(violations) => {
+            for violation in violations {
+                VIOLATIONS.fetch_add(1, Ordering::Relaxed);
+                tracing::error!(
+                    invariant = violation.invariant,
+                    user_id = violation.user_id,
+                    event_id = violation.event_id,
+                    operation,
+                    context = %violation.context,
+                    "❌ {} check: {}",
+                    kind,
+                    violation
+                );
+            }
+        }
+        Err(err) => {
+            CHECK_ERRORS.fetch_add(1, Ordering::Relaxed);
+            tracing::warn!(operation, error = %err, "{} invariant check could not run", kind);
+        }
+    }
+}
+
+/// Re-checks a sample of traded users' ledgers once the trade has
+/// committed. No-op unless built with `invariant-checks`.
+pub fn sample_after_trade(pool: &PgPool, user_id: i32, event_id: i32, operation: &'static str) {
+    if !sampled() {
+        return;
+    }
+    let pool = pool.clone();
+    tokio::spawn(async move {
+        let result = check_user_ledger(&pool, user_id).await.map(|violations| {
+            violations
+                .into_iter()
+                .map(|v| InvariantViolation {
+                    event_id: Some(event_id),
+                    ..v
+                })
+                .collect()
+        });
+        record("Post-trade", operation, result);
+    });
+}
+
+/// Re-checks a sample of resolved events. No-op unless built with
+/// `invariant-checks`.
+pub fn sample_after_resolution(pool: &PgPool, event_id: i32) {
+    if !sampled() {
+        return;
+    }
+    let pool = pool.clone();
+    tokio::spawn(async move {
+        let result = check_event_resolution(&pool, event_id).await;
+        record("Post-resolution", "resolve", result);
+    });
+}
+
+pub fn stats() -> Value {
+    json!({
+        "enabled": cfg!(feature = "invariant-checks"),
+        "sample_rate": if cfg!(feature = "invariant-checks") { sample_rate() } else { 0.0 },
+        "checks_run": CHECKS_RUN.load(Ordering::Relaxed),
+        "violations": VIOLATIONS.load(Ordering::Relaxed),
+        "check_errors": CHECK_ERRORS.load(Ordering::Relaxed),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conservation_flags_only_unexplained_changes() {
+        let initial = HashMap::from([(1, (1_000, 0)), (2, (1_000, 0))]);
+        let current = HashMap::from([(1, (700, 300)), (2, (900, 50))]);
+        let changes = HashMap::from([(2, -50)]);
+        let violations = check_conservation(&initial, &current, &changes);
+        assert!(violations.is_empty(), "{:?}", violations);
+
+        let current = HashMap::from([(1, (700, 301)), (2, (900, 50))]);
+        let violations = check_conservation(&initial, &current, &changes);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].user_id, Some(1));
+        assert_eq!(
+            violations[0].to_string(),
+            "Conservation invariant failed for user 1: expected 1000, got 1001 (diff: 1)"
+        );
+    }
+
+    #[test]
+    fn double_entry_traces_stakes_to_the_amm() {
+        let opening = EventBooks {
+            users_ledger: 3_000_000_000,
+            ..EventBooks::default()
+        };
+        // A trade moves 40 RP of stake to the AMM
+        let after_trade = EventBooks {
+            users_ledger: 2_960_000_000,
+            amm_ledger: 40_000_000,
+        };
+        assert!(check_double_entry(7, &opening, &after_trade, 0).is_none());
+
+        // RP taken from the user but never booked to the AMM is a leak
+        let leaked = EventBooks {
+            amm_ledger: 39_000_000,
+            ..after_trade
+        };
+        let violation = check_double_entry(7, &opening, &leaked, 2).unwrap();
+        assert_eq!(
+            violation.to_string(),
+            "Double-entry invariant failed for event 7: books total 2999000000 but opened at 3000000000 (diff: -1000000)"
+        );
+    }
+
+    #[test]
+    fn amm_side_follows_lmsr_cost_and_payouts() {
+        let b = 100.0;
+        let q_open = (0.0, 0.0);
+        let q_close = (80.0, 10.0);
+        let open_amm = binary_amm_ledger(q_open, q_close, b, AmmSettlement::Open).unwrap();
+        let expected = crate::lmsr_core::cost(80.0, 10.0, b) - crate::lmsr_core::cost(0.0, 0.0, b);
+        assert_eq!(open_amm, (expected * 1_000_000.0).round() as i64);
+
+        // YES wins: the AMM owes 80 RP against what it collected
+        let resolved =
+            binary_amm_ledger(q_open, q_close, b, AmmSettlement::Resolved(true)).unwrap();
+        assert_eq!(resolved, open_amm - 80_000_000);
+        // A void refunds the open stakes instead of redeeming shares
+        let voided = AmmSettlement::Voided {
+            refunded_ledger: 25_000_000,
+        };
+        assert_eq!(
+            binary_amm_ledger(q_open, q_close, b, voided).unwrap(),
+            open_amm - 25_000_000
+        );
+        let books = EventBooks {
+            amm_ledger: resolved,
+            ..EventBooks::default()
+        };
+        assert!(check_amm_subsidy(7, &books, b, 0).is_none());
+
+        let overpaid = EventBooks {
+            amm_ledger: -70_000_000,
+            ..EventBooks::default()
+        };
+        assert!(check_amm_subsidy(7, &overpaid, b, 0).is_some());
+    }
+
+    #[test]
+    fn verify_results_map_to_violations() {
+        let ok = json!({"valid": true, "message": "fine"});
+        assert!(violation_from("Staked", Some(3), None, &ok).is_none());
+        let bad = json!({
+            "valid": false,
+            "message": "Staked invariant FAILED",
+            "details": {"difference_ledger": 12}
+        });
+        let violation = violation_from("Staked", Some(3), None, &bad).unwrap();
+        assert_eq!(violation.context["difference_ledger"], 12);
+        assert_eq!(
+            violation.to_string(),
+            "Staked invariant failed for user 3: Staked invariant FAILED"
+        );
+    }
+}
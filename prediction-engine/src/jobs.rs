@@ -0,0 +1,268 @@
+//! Live progress of long-running admin jobs.
+//!
+//! Bulk imports, sync-all runs and score audits/reseals report their
+//! progress as `job_progress` broadcasts (a `JobEvent`) on the `jobs` topic,
+//! which the admin UI follows with `/ws?topic=jobs`. A job starts with
+//! `status: "started"`, reports after each unit of work (throttled to one
+//! per `EMIT_INTERVAL` within a stage) and ends with `"completed"` or
+//! `"failed"`.
+
+use serde::Serialize;
+use serde_json::json;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::version;
+
+/// The topic job progress is broadcast on.
+pub const JOBS_TOPIC: &str = "jobs";
+
+/// Least time between two reports within the same stage.
+const EMIT_INTERVAL: Duration = Duration::from_millis(1000);
+
+static NEXT_JOB: AtomicU64 = AtomicU64::new(1);
+
+type Sink = Box<dyn Fn(String) + Send + Sync>;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JobEvent {
+    pub job_id: String,
+    pub kind: String,
+    pub stage: String,
+    pub status: &'static str,
+    pub processed: u64,
+    /// None while unknown, e.g. a bulk import without a batch limit.
+    pub total: Option<u64>,
+    pub percent: Option<f64>,
+    /// Extrapolated from the stage's average pace so far.
+    pub eta_secs: Option<f64>,
+    pub elapsed_secs: f64,
+    /// Set on `"failed"`.
+    pub error: Option<String>,
+}
+
+#[derive(Default)]
+struct StageClock {
+    stage: String,
+    started: Option<Instant>,
+    last_emit: Option<Instant>,
+}
+
+/// Reports one job's progress to a sink (the broadcast channel, in the
+/// engine); an untracked job reports nowhere.
+pub struct JobProgress {
+    job_id: String,
+    kind: String,
+    started: Instant,
+    clock: Mutex<StageClock>,
+    sink: Option<Sink>,
+}
+
+impl JobProgress {
+    /// Starts tracking a job of `kind`, announcing it on the sink.
+    pub fn start(kind: &str, sink: impl Fn(String) + Send + Sync + 'static) -> Self {
+        let started = Instant::now();
+        let job_id = format!(
+            "{}-{}-{}",
+            kind,
+            chrono::Utc::now().timestamp_millis(),
+            NEXT_JOB.fetch_add(1, Ordering::Relaxed)
+        );
+        let progress = Self {
+            job_id,
+            kind: kind.to_string(),
+            started,
+            clock: Mutex::new(StageClock::default()),
+            sink: Some(Box::new(sink)),
+        };
+        progress.emit(progress.event("", "started", 0, None, None));
+        progress
+    }
+
+    /// For runs nobody watches, e.g. the scheduled ones.
+    pub fn untracked(kind: &str) -> Self {
+        Self {
+            job_id: String::new(),
+            kind: kind.to_string(),
+            started: Instant::now(),
+            clock: Mutex::new(StageClock::default()),
+            sink: None,
+        }
+    }
+
+    pub fn job_id(&self) -> &str {
+        &self.job_id
+    }
+
+    /// Reports `processed` of `total` units done in `stage`.
+    pub fn progress(&self, stage: &str, processed: u64, total: Option<u64>) {
+        if self.sink.is_none() {
+            return;
+        }
+        let now = Instant::now();
+        let stage_elapsed = {
+            let mut clock = self.clock.lock().unwrap_or_else(|e| e.into_inner());
+            if clock.stage != stage || clock.started.is_none() {
+                clock.stage = stage.to_string();
+                clock.started = Some(now);
+            } else if clock
+                .last_emit
+                .is_some_and(|last| now.duration_since(last) < EMIT_INTERVAL)
+            {
+                return;
+            }
+            clock.last_emit = Some(now);
+            clock.started.map(|started| now.duration_since(started))
+        };
+        let eta = total.and_then(|total| eta_secs(processed, total, stage_elapsed?));
+        self.emit(self.event(stage, "running", processed, total, eta));
+    }
+
+    /// Reports the end of the job, passing its result through.
+    pub fn finish<T>(&self, result: anyhow::Result<T>) -> anyhow::Result<T> {
+        if self.sink.is_some() {
+            let stage = self
+                .clock
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .stage
+                .clone();
+            let event = match &result {
+                Ok(_) => self.event(&stage, "completed", 0, None, None),
+                Err(e) => JobEvent {
+                    error: Some(e.to_string()),
+                    ..self.event(&stage, "failed", 0, None, None)
+                },
+            };
+            self.emit(event);
+        }
+        result
+    }
+
+    fn event(
+        &self,
+        stage: &str,
+        status: &'static str,
+        processed: u64,
+        total: Option<u64>,
+        eta_secs: Option<f64>,
+    ) -> JobEvent {
+        JobEvent {
+            job_id: self.job_id.clone(),
+            kind: self.kind.clone(),
+            stage: stage.to_string(),
+            status,
+            processed,
+            total,
+            percent: total.map(|total| percent(processed, total)),
+            eta_secs,
+            elapsed_secs: self.started.elapsed().as_secs_f64(),
+            error: None,
+        }
+    }
+
+    fn emit(&self, event: JobEvent) {
+        if let Some(sink) = &self.sink {
+            sink(message(&event));
+        }
+    }
+}
+
+/// The broadcast carrying `event`.
+pub fn message(event: &JobEvent) -> String {
+    json!({
+        "type": "job_progress",
+        "topic": JOBS_TOPIC,
+        "data": event,
+        "timestamp": chrono::Utc::now(),
+        "engine": version::BUILD
+    })
+    .to_string()
+}
+
+/// Share done, 0–100; an empty job is done.
+pub fn percent(processed: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 100.0;
+    }
+    (processed.min(total) as f64 / total as f64 * 100.0 * 10.0).round() / 10.0
+}
+
+/// Seconds left at the average pace so far; unknown before the first unit.
+pub fn eta_secs(processed: u64, total: u64, elapsed: Duration) -> Option<f64> {
+    if processed == 0 {
+        return None;
+    }
+    let remaining = total.saturating_sub(processed) as f64;
+    Some(elapsed.as_secs_f64() / processed as f64 * remaining)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use std::sync::Arc;
+
+    fn collecting(kind: &str) -> (JobProgress, Arc<Mutex<Vec<Value>>>) {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let sink = sent.clone();
+        let progress = JobProgress::start(kind, move |msg| {
+            sink.lock()
+                .unwrap()
+                .push(serde_json::from_str(&msg).unwrap())
+        });
+        (progress, sent)
+    }
+
+    #[test]
+    fn percent_and_eta_follow_the_pace_so_far() {
+        assert_eq!(percent(1, 3), 33.3);
+        assert_eq!(percent(5, 4), 100.0);
+        assert_eq!(percent(0, 0), 100.0);
+        assert_eq!(eta_secs(0, 10, Duration::from_secs(5)), None);
+        assert_eq!(eta_secs(2, 10, Duration::from_secs(5)), Some(20.0));
+        assert_eq!(eta_secs(10, 10, Duration::from_secs(5)), Some(0.0));
+    }
+
+    #[test]
+    fn reports_start_stage_changes_and_end_on_the_jobs_topic() {
+        let (progress, sent) = collecting("score_audit");
+        progress.progress("audit", 1000, Some(4000));
+        // Throttled: same stage, too soon after the last report.
+        progress.progress("audit", 2000, Some(4000));
+        progress.progress("summary", 0, None);
+        progress.finish(Ok(())).unwrap();
+
+        let sent = sent.lock().unwrap();
+        let statuses: Vec<_> = sent.iter().map(|m| m["data"]["status"].clone()).collect();
+        assert_eq!(statuses, ["started", "running", "running", "completed"]);
+        for msg in sent.iter() {
+            assert_eq!(msg["type"], "job_progress");
+            assert_eq!(msg["topic"], JOBS_TOPIC);
+            assert_eq!(msg["data"]["job_id"], progress.job_id());
+            assert_eq!(msg["data"]["kind"], "score_audit");
+        }
+        assert_eq!(sent[1]["data"]["stage"], "audit");
+        assert_eq!(sent[1]["data"]["percent"], 25.0);
+        assert!(sent[1]["data"]["eta_secs"].is_number());
+        assert!(sent[2]["data"]["percent"].is_null());
+        assert_eq!(sent[3]["data"]["stage"], "summary");
+    }
+
+    #[test]
+    fn failures_carry_the_error_and_untracked_jobs_send_nothing() {
+        let (progress, sent) = collecting("bulk_import");
+        assert!(progress
+            .finish::<()>(Err(anyhow::anyhow!("upstream 503")))
+            .is_err());
+        let last = sent.lock().unwrap().last().cloned().unwrap();
+        assert_eq!(last["data"]["status"], "failed");
+        assert_eq!(last["data"]["error"], "upstream 503");
+
+        let untracked = JobProgress::untracked("score_reseal");
+        untracked.progress("reseal", 1, Some(2));
+        assert_eq!(untracked.finish(Ok(7)).unwrap(), 7);
+        assert!(untracked.job_id().is_empty());
+    }
+}
@@ -3,14 +3,72 @@
 //! This library provides the core functionality for the LMSR prediction market engine.
 
 // Re-export modules for use in binaries
+pub mod admin_audit;
+pub mod api_error;
+pub mod api_keys;
+pub mod arbitrage;
+pub mod archive;
+pub mod backtest;
+pub mod cache_warming;
+pub mod closing_soon;
+pub mod cluster_consistency;
+pub mod comment_buzz;
+pub mod competitions;
 pub mod config;
+pub mod consensus;
+pub mod dashboard;
 pub mod database;
 pub mod db_adapter;
+pub mod dead_letters;
+pub mod disputes;
+pub mod embargo;
+pub mod event_metadata;
+pub mod event_search;
+pub mod exposure;
+pub mod faucet;
+pub mod forecasts;
+pub mod group_directory;
+pub mod invariants;
+pub mod jobs;
+pub mod limit_orders;
+pub mod liquidity_migration;
+pub mod liquidity_recommendations;
+pub mod live_scores;
 pub mod lmsr_api;
 pub mod lmsr_core;
 pub mod lmsr_multi_core;
+pub mod load_test;
+pub mod mailbox;
+pub mod market_accuracy;
+pub mod market_cache;
+pub mod market_close;
+pub mod market_heat;
 pub mod market_import;
+pub mod market_partitions;
 pub mod metaculus;
+pub mod mls_delivery;
+pub mod notifications;
 pub mod numeric_transform;
+pub mod paper_predictions;
+pub mod peer_scores;
+pub mod price_history;
+pub mod push;
+pub mod rank_history;
+pub mod realized_pnl;
+pub mod replay;
+pub mod resolution_preview;
 pub mod resolution_sync;
+pub mod risk;
+pub mod score_integrity;
+pub mod score_quota;
+pub mod source_status;
+pub mod sparklines;
+pub mod state_at;
 pub mod stress;
+pub mod system_accounts;
+pub mod trade_privacy;
+pub mod user_predictions;
+pub mod user_provisioning;
+pub mod version;
+pub mod webhooks;
+pub mod ws_snapshot;
@@ -0,0 +1,353 @@
+//! Resting limit orders on binary markets.
+//!
+//! "Buy YES with this stake once the price is at or below X" (or NO at or
+//! above X). The stake is reserved from the owner's wallet when the order
+//! is placed. A trade that moves the price across the trigger runs the
+//! matcher in `lmsr_api`, which spends only what takes the price back to
+//! the trigger; the rest stays reserved for the next cross. Cancelled,
+//! failed and expired orders return what they still hold.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool, Postgres, Transaction};
+
+use crate::api_error::{coded, ErrorCode};
+use crate::db_adapter::{DbAdapter, Wallet};
+use crate::lmsr_core::{to_ledger_units, Side};
+
+/// Open orders one user may have resting at a time.
+pub const MAX_OPEN_ORDERS_PER_USER: i64 = 100;
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct LimitOrder {
+    pub id: i64,
+    pub user_id: i32,
+    pub event_id: i32,
+    pub side: String,
+    pub trigger_prob: f64,
+    pub stake_ledger: i64,
+    /// Spent by fills so far
+    pub filled_ledger: i64,
+    /// Held back from the owner's wallet for the unfilled rest
+    pub reserved_ledger: i64,
+    /// open, filled, failed, cancelled or expired
+    pub status: String,
+    /// The latest fill's trade and the price it left
+    pub market_update_id: Option<i32>,
+    pub fill_prob: Option<f64>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub filled_at: Option<DateTime<Utc>>,
+    pub cancelled_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlaceOrder {
+    pub event_id: i32,
+    pub side: String,
+    pub trigger_prob: f64,
+    pub stake: f64,
+}
+
+pub async fn ensure_limit_orders_table(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS limit_orders (
+            id BIGSERIAL PRIMARY KEY,
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+            side VARCHAR(3) NOT NULL CHECK (side IN ('yes', 'no')),
+            trigger_prob DOUBLE PRECISION NOT NULL
+                CHECK (trigger_prob > 0 AND trigger_prob < 1),
+            stake_ledger BIGINT NOT NULL CHECK (stake_ledger > 0),
+            filled_ledger BIGINT NOT NULL DEFAULT 0,
+            reserved_ledger BIGINT NOT NULL DEFAULT 0 CHECK (reserved_ledger >= 0),
+            status VARCHAR(16) NOT NULL DEFAULT 'open',
+            market_update_id INTEGER,
+            fill_prob DOUBLE PRECISION,
+            error TEXT,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            filled_at TIMESTAMPTZ,
+            cancelled_at TIMESTAMPTZ
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    // Orders placed before stakes were reserved hold nothing
+    sqlx::query(
+        "ALTER TABLE limit_orders
+         ADD COLUMN IF NOT EXISTS filled_ledger BIGINT NOT NULL DEFAULT 0,
+         ADD COLUMN IF NOT EXISTS reserved_ledger BIGINT NOT NULL DEFAULT 0
+             CHECK (reserved_ledger >= 0)",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("ALTER TABLE limit_orders DROP CONSTRAINT IF EXISTS limit_orders_status_check")
+        .execute(pool)
+        .await?;
+    sqlx::query(
+        "ALTER TABLE limit_orders ADD CONSTRAINT limit_orders_status_check
+         CHECK (status IN ('open', 'filled', 'failed', 'cancelled', 'expired'))",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_limit_orders_open
+         ON limit_orders (event_id, created_at, id) WHERE status = 'open'",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_limit_orders_user
+         ON limit_orders (user_id, created_at DESC)",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Rows as listed: open orders on a market that can no longer trade read
+/// as expired until `release_expired` returns their reserve.
+const SELECT_ORDERS: &str = r#"
+    SELECT o.id, o.user_id, o.event_id, o.side, o.trigger_prob, o.stake_ledger,
+           o.filled_ledger, o.reserved_ledger,
+           CASE WHEN o.status = 'open'
+                 AND (e.outcome IS NOT NULL OR e.closed_at IS NOT NULL
+                      OR COALESCE(e.closing_date <= NOW(), false))
+                THEN 'expired' ELSE o.status END AS status,
+           o.market_update_id, o.fill_prob, o.error, o.created_at, o.filled_at,
+           o.cancelled_at
+    FROM limit_orders o
+    JOIN events e ON e.id = o.event_id
+"#;
+
+/// Moves `reserve_delta` from the user's `wallet` into an order's reserve,
+/// or back out of it when negative. False if the wallet can't cover it.
+pub(crate) async fn move_reserve(
+    tx: &mut Transaction<'_, Postgres>,
+    wallet: Wallet,
+    user_id: i32,
+    reserve_delta: i64,
+) -> Result<bool> {
+    if reserve_delta == 0 {
+        return Ok(true);
+    }
+    let rows =
+        DbAdapter::update_wallet_balance_ledger(tx, wallet, user_id, -reserve_delta, 0).await?;
+    Ok(rows > 0)
+}
+
+/// Stores a resting order, reserving its stake. It may already be
+/// triggered; the caller runs the matcher after placing.
+pub async fn place(pool: &PgPool, user_id: i32, order: &PlaceOrder) -> Result<LimitOrder> {
+    let side = Side::from_str(order.side.trim())
+        .map_err(|_| coded(ErrorCode::InvalidRequest, "side must be 'yes' or 'no'"))?;
+    if !(order.trigger_prob > 0.0 && order.trigger_prob < 1.0) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "trigger_prob must be between 0 and 1 (exclusive)",
+        ));
+    }
+    if !(order.stake.is_finite() && (0.01..=1_000_000.0).contains(&order.stake)) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "stake must be between 0.01 and 1,000,000 RP",
+        ));
+    }
+    let stake_ledger = to_ledger_units(order.stake)
+        .ok()
+        .and_then(|stake| i64::try_from(stake).ok())
+        .ok_or_else(|| {
+            coded(
+                ErrorCode::InvalidRequest,
+                "stake must be between 0.01 and 1,000,000 RP",
+            )
+        })?;
+
+    let user_exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+    if !user_exists {
+        return Err(coded(ErrorCode::NotFound, "User not found"));
+    }
+    let market: Option<(String, bool, Option<i32>)> = sqlx::query_as(
+        "SELECT event_type,
+                (outcome IS NOT NULL OR closed_at IS NOT NULL
+                 OR COALESCE(closing_date <= NOW(), false)) AS finished,
+                competition_id
+         FROM events WHERE id = $1",
+    )
+    .bind(order.event_id)
+    .fetch_optional(pool)
+    .await?;
+    let wallet = match market {
+        None => return Err(coded(ErrorCode::NotFound, "Event not found")),
+        Some((event_type, _, _)) if !event_type.eq_ignore_ascii_case("binary") => {
+            return Err(coded(
+                ErrorCode::InvalidRequest,
+                "Limit orders must be on a binary market",
+            ))
+        }
+        Some((_, true, _)) => return Err(coded(ErrorCode::MarketClosed, "Market closed")),
+        Some((_, false, competition_id)) => Wallet::for_market(competition_id),
+    };
+
+    let open: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM limit_orders WHERE user_id = $1 AND status = 'open'",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+    if open >= MAX_OPEN_ORDERS_PER_USER {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!(
+                "Open limit orders must stay under {} per user",
+                MAX_OPEN_ORDERS_PER_USER
+            ),
+        ));
+    }
+
+    let mut tx = pool.begin().await?;
+    if !move_reserve(&mut tx, wallet, user_id, stake_ledger).await? {
+        return Err(coded(
+            ErrorCode::InsufficientBalance,
+            "Insufficient RP balance to reserve the stake",
+        ));
+    }
+    let id: i64 = sqlx::query_scalar(
+        "INSERT INTO limit_orders
+             (user_id, event_id, side, trigger_prob, stake_ledger, reserved_ledger)
+         VALUES ($1, $2, $3, $4, $5, $5)
+         RETURNING id",
+    )
+    .bind(user_id)
+    .bind(order.event_id)
+    .bind(side.as_str())
+    .bind(order.trigger_prob)
+    .bind(stake_ledger)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    get(pool, id).await
+}
+
+pub async fn get(pool: &PgPool, order_id: i64) -> Result<LimitOrder> {
+    sqlx::query_as(&format!("{} WHERE o.id = $1", SELECT_ORDERS))
+        .bind(order_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| coded(ErrorCode::NotFound, "Limit order not found"))
+}
+
+/// Cancels one of the user's open orders, returning its reserve.
+pub async fn cancel(pool: &PgPool, user_id: i32, order_id: i64) -> Result<LimitOrder> {
+    let mut tx = pool.begin().await?;
+    let cancelled: Option<(i64, Option<i32>)> = sqlx::query_as(
+        "WITH cancelled AS (
+             SELECT o.id, o.reserved_ledger, e.competition_id
+             FROM limit_orders o
+             JOIN events e ON e.id = o.event_id
+             WHERE o.id = $1 AND o.user_id = $2 AND o.status = 'open'
+             FOR UPDATE OF o
+         )
+         UPDATE limit_orders o
+         SET status = 'cancelled', reserved_ledger = 0, cancelled_at = NOW()
+         FROM cancelled
+         WHERE o.id = cancelled.id
+         RETURNING cancelled.reserved_ledger, cancelled.competition_id",
+    )
+    .bind(order_id)
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some((reserved, competition_id)) = cancelled {
+        let wallet = Wallet::for_market(competition_id);
+        if !move_reserve(&mut tx, wallet, user_id, -reserved).await? {
+            return Err(anyhow!(
+                "Limit order {} reserve could not be returned",
+                order_id
+            ));
+        }
+    }
+    tx.commit().await?;
+    let order = get(pool, order_id).await?;
+    if order.user_id != user_id {
+        return Err(coded(ErrorCode::NotFound, "Limit order not found"));
+    }
+    if cancelled.is_none() {
+        return Err(coded(
+            ErrorCode::Conflict,
+            format!("Limit order is {}, not open", order.status),
+        ));
+    }
+    Ok(order)
+}
+
+/// Expires the open orders on markets that closed or resolved, returning
+/// their reserves. Returns how many expired.
+pub async fn release_expired(pool: &PgPool) -> Result<u64> {
+    let mut tx = pool.begin().await?;
+    let expired: Vec<(i32, i64, Option<i32>)> = sqlx::query_as(
+        "WITH expired AS (
+             SELECT o.id, o.user_id, o.reserved_ledger, e.competition_id
+             FROM limit_orders o
+             JOIN events e ON e.id = o.event_id
+             WHERE o.status = 'open'
+               AND (e.outcome IS NOT NULL OR e.closed_at IS NOT NULL
+                    OR COALESCE(e.closing_date <= NOW(), false))
+             FOR UPDATE OF o SKIP LOCKED
+         )
+         UPDATE limit_orders o
+         SET status = 'expired', reserved_ledger = 0
+         FROM expired
+         WHERE o.id = expired.id
+         RETURNING expired.user_id, expired.reserved_ledger, expired.competition_id",
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    for (user_id, reserved, competition_id) in &expired {
+        let wallet = Wallet::for_market(*competition_id);
+        if !move_reserve(&mut tx, wallet, *user_id, -reserved).await? {
+            return Err(anyhow!(
+                "Limit order reserve of user {} could not be returned",
+                user_id
+            ));
+        }
+    }
+    tx.commit().await?;
+    Ok(expired.len() as u64)
+}
+
+/// The user's orders, newest first, optionally by status and market.
+pub async fn list(
+    pool: &PgPool,
+    user_id: i32,
+    status: Option<&str>,
+    event_id: Option<i32>,
+) -> Result<Vec<LimitOrder>> {
+    if let Some(status) = status {
+        if !["open", "filled", "failed", "cancelled", "expired"].contains(&status) {
+            return Err(coded(
+                ErrorCode::InvalidRequest,
+                "status must be one of open, filled, failed, cancelled, expired",
+            ));
+        }
+    }
+    Ok(sqlx::query_as(&format!(
+        "SELECT * FROM ({} WHERE o.user_id = $1) listed
+         WHERE ($2::text IS NULL OR status = $2)
+           AND ($3::integer IS NULL OR event_id = $3)
+         ORDER BY created_at DESC, id DESC
+         LIMIT 500",
+        SELECT_ORDERS
+    ))
+    .bind(user_id)
+    .bind(status)
+    .bind(event_id)
+    .fetch_all(pool)
+    .await?)
+}
@@ -0,0 +1,212 @@
+//! Changing `liquidity_b` on a live binary market.
+//!
+//! Editing the column alone moves the price (p depends on q/b) and breaks
+//! `cumulative_stake = C(q, b)`. A migration instead rescales q_yes/q_no
+//! with `Market::with_liquidity`, which keeps the displayed probability and
+//! the expected payout of the shares outstanding, and rewrites the cost to
+//! match. Users' positions are untouched. The market row is locked for the
+//! rewrite, and every migration leaves a row in `liquidity_migrations` with
+//! the state before and after, who ran it and why.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+
+use crate::api_error::{coded, ErrorCode};
+use crate::db_adapter::DbAdapter;
+use crate::lmsr_core::Market;
+
+#[derive(Debug, Clone, Serialize, ts_rs::TS)]
+#[ts(export, export_to = "../../shared/types/LiquidityMigration.ts")]
+pub struct LiquidityMigration {
+    pub id: i64,
+    pub event_id: i32,
+    pub old_b: f64,
+    pub new_b: f64,
+    pub old_q_yes: f64,
+    pub old_q_no: f64,
+    pub new_q_yes: f64,
+    pub new_q_no: f64,
+    pub old_cost: f64,
+    pub new_cost: f64,
+    pub market_prob: f64,
+    pub actor: String,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+pub async fn ensure_migrations_table(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS liquidity_migrations (
+            id BIGSERIAL PRIMARY KEY,
+            event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+            old_b DOUBLE PRECISION NOT NULL,
+            new_b DOUBLE PRECISION NOT NULL,
+            old_q_yes DOUBLE PRECISION NOT NULL,
+            old_q_no DOUBLE PRECISION NOT NULL,
+            new_q_yes DOUBLE PRECISION NOT NULL,
+            new_q_no DOUBLE PRECISION NOT NULL,
+            old_cost DOUBLE PRECISION NOT NULL,
+            new_cost DOUBLE PRECISION NOT NULL,
+            market_prob DOUBLE PRECISION NOT NULL,
+            actor VARCHAR(255) NOT NULL,
+            reason TEXT,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_liquidity_migrations_event
+         ON liquidity_migrations(event_id, created_at)",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+const MIGRATION_COLUMNS: &str = "id, event_id, old_b, new_b, old_q_yes, old_q_no, new_q_yes,
+    new_q_no, old_cost, new_cost, market_prob, actor, reason, created_at";
+
+fn row_to_migration(row: &sqlx::postgres::PgRow) -> LiquidityMigration {
+    LiquidityMigration {
+        id: row.get("id"),
+        event_id: row.get("event_id"),
+        old_b: row.get("old_b"),
+        new_b: row.get("new_b"),
+        old_q_yes: row.get("old_q_yes"),
+        old_q_no: row.get("old_q_no"),
+        new_q_yes: row.get("new_q_yes"),
+        new_q_no: row.get("new_q_no"),
+        old_cost: row.get("old_cost"),
+        new_cost: row.get("new_cost"),
+        market_prob: row.get("market_prob"),
+        actor: row.get("actor"),
+        reason: row.get("reason"),
+        created_at: row.get("created_at"),
+    }
+}
+
+/// Moves an open binary market to liquidity `new_b`.
+pub async fn migrate_liquidity(
+    pool: &PgPool,
+    event_id: i32,
+    new_b: f64,
+    actor: &str,
+    reason: Option<&str>,
+) -> Result<LiquidityMigration> {
+    let actor = actor.trim();
+    if actor.is_empty() || actor.chars().count() > 255 {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "actor must be 1-255 characters",
+        ));
+    }
+    if !new_b.is_finite() || new_b <= 0.0 {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "liquidity_b must be positive and finite",
+        ));
+    }
+    let reason = reason.map(str::trim).filter(|r| !r.is_empty());
+
+    let mut tx = pool.begin().await?;
+    let row = sqlx::query(
+        "SELECT market_prob, liquidity_b, q_yes, q_no, event_type, outcome
+         FROM events WHERE id = $1 FOR UPDATE",
+    )
+    .bind(event_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| coded(ErrorCode::NotFound, "Event not found"))?;
+    if row.get::<Option<String>, _>("outcome").is_some() {
+        return Err(coded(ErrorCode::MarketResolved, "Market resolved"));
+    }
+    if !row
+        .get::<String, _>("event_type")
+        .eq_ignore_ascii_case("binary")
+    {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "liquidity migration must target a binary market",
+        ));
+    }
+    let state = DbAdapter::extract_market_state(&row)?;
+    if new_b == state.liquidity_b {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "liquidity_b must differ from the current value",
+        ));
+    }
+
+    let market = Market {
+        q_yes: state.q_yes,
+        q_no: state.q_no,
+        b: state.liquidity_b,
+    };
+    let migrated = market
+        .with_liquidity(new_b)
+        .map_err(|e| coded(ErrorCode::InvalidRequest, e))?;
+    let market_prob = migrated.prob_yes();
+    let new_cost = migrated.cost();
+
+    DbAdapter::update_market_state(
+        &mut tx,
+        event_id,
+        market_prob,
+        new_cost,
+        migrated.q_yes,
+        migrated.q_no,
+    )
+    .await?;
+    sqlx::query("UPDATE events SET liquidity_b = $1 WHERE id = $2")
+        .bind(new_b)
+        .bind(event_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let record = sqlx::query(&format!(
+        "INSERT INTO liquidity_migrations
+             (event_id, old_b, new_b, old_q_yes, old_q_no, new_q_yes, new_q_no,
+              old_cost, new_cost, market_prob, actor, reason)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+         RETURNING {}",
+        MIGRATION_COLUMNS
+    ))
+    .bind(event_id)
+    .bind(state.liquidity_b)
+    .bind(new_b)
+    .bind(state.q_yes)
+    .bind(state.q_no)
+    .bind(migrated.q_yes)
+    .bind(migrated.q_no)
+    .bind(market.cost())
+    .bind(new_cost)
+    .bind(market_prob)
+    .bind(actor)
+    .bind(reason)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(row_to_migration(&record))
+}
+
+/// Migrations run on one event, newest first.
+pub async fn list_migrations(pool: &PgPool, event_id: i32) -> Result<Vec<LiquidityMigration>> {
+    Ok(sqlx::query(&format!(
+        "SELECT {} FROM liquidity_migrations
+         WHERE event_id = $1
+         ORDER BY created_at DESC, id DESC",
+        MIGRATION_COLUMNS
+    ))
+    .bind(event_id)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(row_to_migration)
+    .collect())
+}
@@ -0,0 +1,312 @@
+//! Per-category `liquidity_b` recommendations learned from market accuracy.
+//!
+//! A refresh weighs each category's resolved, traded markets by how well
+//! their closing price called the outcome, adjusts for how far its trades
+//! move the price, and shrinks toward the mean over every category (see
+//! [`recommend`]). Nothing is applied until an admin turns on `auto_apply`
+//! for a category; `use_recommended_liquidity` on a create request
+//! overrides that either way.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::{PgPool, Row};
+use std::collections::BTreeMap;
+
+use crate::api_error::{coded, ErrorCode};
+
+/// Markets' worth of weight the all-category mean carries when shrinking
+/// a category's recommendation toward it.
+pub const PRIOR_MARKETS: f64 = 10.0;
+/// Most the volatility adjustment scales liquidity up or down by.
+pub const MAX_VOLATILITY_ADJUSTMENT: f64 = 2.0;
+
+/// One resolved market's evidence.
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    pub liquidity_b: f64,
+    pub log_score: f64,
+    /// Mean absolute price move per trade.
+    pub volatility: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Recommendation {
+    pub category: String,
+    pub recommended_b: f64,
+    pub markets: usize,
+    pub mean_log_score: f64,
+    pub volatility: Option<f64>,
+}
+
+/// Geometric mean of liquidity weighted by exp(log score), the probability
+/// the closing price gave the actual outcome, so depths that called
+/// outcomes well pull hardest.
+fn weighted_liquidity(samples: &[Sample]) -> Option<f64> {
+    let (mut weighted_ln, mut weights) = (0.0, 0.0);
+    for sample in samples {
+        let weight = sample.log_score.exp();
+        weighted_ln += weight * sample.liquidity_b.ln();
+        weights += weight;
+    }
+    (weights > 0.0).then(|| (weighted_ln / weights).exp())
+}
+
+fn mean_volatility(samples: &[Sample]) -> Option<f64> {
+    let moves: Vec<f64> = samples.iter().filter_map(|s| s.volatility).collect();
+    (!moves.is_empty()).then(|| moves.iter().sum::<f64>() / moves.len() as f64)
+}
+
+/// The recommendation for a category with `samples`, given the same
+/// weighted liquidity and volatility over every category. A category whose
+/// trades move the price more than average gets proportionally deeper
+/// liquidity (a calmer one shallower), then is shrunk toward the baseline,
+/// fully so with few markets and less as they accumulate.
+pub fn recommend(samples: &[Sample], baseline_b: f64, baseline_volatility: Option<f64>) -> f64 {
+    let Some(category_b) = weighted_liquidity(samples) else {
+        return baseline_b;
+    };
+    let adjustment = match (mean_volatility(samples), baseline_volatility) {
+        (Some(v), Some(baseline)) if baseline > 0.0 => {
+            (v / baseline).clamp(1.0 / MAX_VOLATILITY_ADJUSTMENT, MAX_VOLATILITY_ADJUSTMENT)
+        }
+        _ => 1.0,
+    };
+    let confidence = samples.len() as f64 / (samples.len() as f64 + PRIOR_MARKETS);
+    (confidence * (category_b * adjustment).ln() + (1.0 - confidence) * baseline_b.ln()).exp()
+}
+
+pub async fn ensure_recommendations_table(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS liquidity_recommendations (
+            category TEXT PRIMARY KEY,
+            recommended_b DOUBLE PRECISION NOT NULL CHECK (recommended_b > 0),
+            markets INTEGER NOT NULL,
+            mean_log_score DOUBLE PRECISION NOT NULL,
+            volatility DOUBLE PRECISION,
+            auto_apply BOOLEAN NOT NULL DEFAULT FALSE,
+            computed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Recomputes recommendations for every category with at least
+/// `min_markets` resolved, traded markets and stores them, keeping each
+/// category's `auto_apply` setting.
+pub async fn refresh(pool: &PgPool, min_markets: u32) -> Result<Vec<Recommendation>> {
+    let archived: bool = sqlx::query_scalar("SELECT to_regclass('market_updates_all') IS NOT NULL")
+        .fetch_one(pool)
+        .await?;
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT LOWER(TRIM(ma.category)) AS category, ma.liquidity_b, ma.log_score,
+               (SELECT AVG(ABS(mu.new_prob - mu.prev_prob))::float8
+                FROM {} mu WHERE mu.event_id = ma.event_id) AS volatility
+        FROM market_accuracy ma
+        WHERE ma.trades > 0 AND ma.liquidity_b > 0
+          AND NULLIF(TRIM(ma.category), '') IS NOT NULL
+        "#,
+        if archived {
+            "market_updates_all"
+        } else {
+            "market_updates"
+        }
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    let mut by_category: BTreeMap<String, Vec<Sample>> = BTreeMap::new();
+    for row in &rows {
+        by_category
+            .entry(row.get("category"))
+            .or_default()
+            .push(Sample {
+                liquidity_b: row.get("liquidity_b"),
+                log_score: row.get("log_score"),
+                volatility: row.get("volatility"),
+            });
+    }
+    let everything: Vec<Sample> = by_category.values().flatten().copied().collect();
+    let Some(baseline_b) = weighted_liquidity(&everything) else {
+        return Ok(Vec::new());
+    };
+    let baseline_volatility = mean_volatility(&everything);
+
+    let mut recommendations = Vec::new();
+    for (category, samples) in by_category {
+        if samples.len() < min_markets as usize {
+            continue;
+        }
+        let recommendation = Recommendation {
+            recommended_b: recommend(&samples, baseline_b, baseline_volatility),
+            markets: samples.len(),
+            mean_log_score: samples.iter().map(|s| s.log_score).sum::<f64>() / samples.len() as f64,
+            volatility: mean_volatility(&samples),
+            category,
+        };
+        sqlx::query(
+            r#"
+            INSERT INTO liquidity_recommendations
+                (category, recommended_b, markets, mean_log_score, volatility)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (category) DO UPDATE
+            SET recommended_b = EXCLUDED.recommended_b,
+                markets = EXCLUDED.markets,
+                mean_log_score = EXCLUDED.mean_log_score,
+                volatility = EXCLUDED.volatility,
+                computed_at = NOW()
+            "#,
+        )
+        .bind(&recommendation.category)
+        .bind(recommendation.recommended_b)
+        .bind(recommendation.markets as i32)
+        .bind(recommendation.mean_log_score)
+        .bind(recommendation.volatility)
+        .execute(pool)
+        .await?;
+        recommendations.push(recommendation);
+    }
+    Ok(recommendations)
+}
+
+/// Stored recommendations, by category.
+pub async fn list(pool: &PgPool) -> Result<Value> {
+    let rows = sqlx::query(
+        "SELECT category, recommended_b, markets, mean_log_score, volatility, auto_apply,
+                computed_at
+         FROM liquidity_recommendations
+         ORDER BY category",
+    )
+    .fetch_all(pool)
+    .await?;
+    let recommendations: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            json!({
+                "category": row.get::<String, _>("category"),
+                "recommended_b": row.get::<f64, _>("recommended_b"),
+                "markets": row.get::<i32, _>("markets"),
+                "mean_log_score": row.get::<f64, _>("mean_log_score"),
+                "volatility": row.get::<Option<f64>, _>("volatility"),
+                "auto_apply": row.get::<bool, _>("auto_apply"),
+                "computed_at": row.get::<DateTime<Utc>, _>("computed_at"),
+            })
+        })
+        .collect();
+    Ok(json!({ "recommendations": recommendations }))
+}
+
+/// Turns auto-apply on or off for a category. While on, markets created in
+/// it without an explicit liquidity (admin-created or imported) open at the
+/// recommendation. Errors if the category has no recommendation yet.
+pub async fn set_auto_apply(pool: &PgPool, category: &str, auto_apply: bool) -> Result<()> {
+    let updated = sqlx::query(
+        "UPDATE liquidity_recommendations SET auto_apply = $2 WHERE category = LOWER(TRIM($1))",
+    )
+    .bind(category)
+    .bind(auto_apply)
+    .execute(pool)
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Err(coded(
+            ErrorCode::NotFound,
+            "No liquidity recommendation for this category",
+        ));
+    }
+    Ok(())
+}
+
+/// The recommendation for `category`; only an auto-applied one unless
+/// `auto_applied_only` is off. Databases without the table have none.
+pub async fn recommended_liquidity(
+    pool: &PgPool,
+    category: Option<&str>,
+    auto_applied_only: bool,
+) -> Result<Option<f64>> {
+    let Some(category) = category else {
+        return Ok(None);
+    };
+    let table_exists: bool =
+        sqlx::query_scalar("SELECT to_regclass('liquidity_recommendations') IS NOT NULL")
+            .fetch_one(pool)
+            .await?;
+    if !table_exists {
+        return Ok(None);
+    }
+    Ok(sqlx::query_scalar(
+        "SELECT recommended_b FROM liquidity_recommendations
+         WHERE category = LOWER(TRIM($1)) AND (auto_apply OR NOT $2)",
+    )
+    .bind(category)
+    .bind(auto_applied_only)
+    .fetch_optional(pool)
+    .await?)
+}
+
+/// Opens a just-imported, untraded binary market at its category's
+/// auto-applied recommendation, if there is one. Returns the liquidity
+/// applied.
+pub async fn apply_to_new_market(pool: &PgPool, event_id: i32) -> Result<Option<f64>> {
+    let table_exists: bool =
+        sqlx::query_scalar("SELECT to_regclass('liquidity_recommendations') IS NOT NULL")
+            .fetch_one(pool)
+            .await?;
+    if !table_exists {
+        return Ok(None);
+    }
+    Ok(sqlx::query_scalar(
+        "UPDATE events e SET liquidity_b = r.recommended_b
+         FROM liquidity_recommendations r
+         WHERE e.id = $1 AND r.auto_apply
+           AND r.category = LOWER(TRIM(e.category))
+           AND COALESCE(e.event_type, 'binary') = 'binary'
+           AND COALESCE(e.q_yes, 0) = 0 AND COALESCE(e.q_no, 0) = 0
+         RETURNING e.liquidity_b::float8",
+    )
+    .bind(event_id)
+    .fetch_optional(pool)
+    .await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(liquidity_b: f64, log_score: f64, volatility: f64) -> Sample {
+        Sample {
+            liquidity_b,
+            log_score,
+            volatility: Some(volatility),
+        }
+    }
+
+    #[test]
+    fn recommendation_follows_accuracy_and_volatility() {
+        // Both depths equally accurate: their geometric mean
+        let even = [sample(100.0, -0.5, 0.02), sample(400.0, -0.5, 0.02)];
+        assert!((weighted_liquidity(&even).unwrap() - 200.0).abs() < 1e-9);
+        // The better-scoring depth pulls the mean toward it
+        let skewed = [sample(100.0, -0.1, 0.02), sample(400.0, -2.0, 0.02)];
+        assert!(weighted_liquidity(&skewed).unwrap() < 150.0);
+
+        // With few markets the recommendation stays near the baseline...
+        let few = recommend(&even, 1000.0, Some(0.02));
+        assert!(few > 200.0 && few < 1000.0);
+        // ...and with many it approaches the category's own depth
+        let many: Vec<Sample> = even.iter().cycle().take(1000).copied().collect();
+        assert!((recommend(&many, 1000.0, Some(0.02)) - 200.0).abs() < 5.0);
+
+        // Twice the average price move asks for twice the depth, capped
+        let swingy = recommend(&many, 1000.0, Some(0.01));
+        assert!((swingy / recommend(&many, 1000.0, Some(0.02)) - 2.0).abs() < 0.05);
+        let wild = recommend(&many, 1000.0, Some(0.001));
+        assert!((wild - swingy).abs() < 1e-9);
+        assert_eq!(recommend(&[], 1000.0, None), 1000.0);
+    }
+}
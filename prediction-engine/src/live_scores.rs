@@ -0,0 +1,234 @@
+//! Score updates pushed as predictions resolve.
+//!
+//! Scores are computed from `predictions` on read, so clients used to learn
+//! about new ones by polling or by waiting for a batch job. A trigger now
+//! NOTIFYs `CHANNEL` with the event id whenever a prediction's outcome
+//! changes, whoever makes the change (the engine settling a market, or the
+//! backend marking predictions itself). Postgres folds identical
+//! notifications within a transaction, so a resolution sends one per event,
+//! delivered once it commits. The listener, enabled by
+//! `market.live_score_updates`, scores the event's resolved predictions and
+//! hands the updates on for broadcast: each user's score on the event and
+//! their running accuracy and mean log score.
+//!
+//! If the listener's connection drops, notifications sent meanwhile are
+//! lost; on reconnecting it replays every event with a prediction resolved
+//! since the last one it handled. That point is kept in
+//! `live_score_watermarks`, so a restarted listener replays from it too.
+
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use intellacc_math::scoring::LOG_SCORE_FLOOR;
+use serde::Serialize;
+use sqlx::postgres::PgListener;
+use sqlx::{PgPool, Row};
+use std::future::Future;
+
+use crate::score_quota::{weighted_scoring_joins, QuotaPolicy};
+use crate::user_predictions::{log_score_sql, SCORING_JOINS};
+
+pub const CHANNEL: &str = "prediction_resolved";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScoreUpdate {
+    pub user_id: i32,
+    pub event_id: i32,
+    pub prediction_id: i32,
+    /// correct, incorrect or not_applicable.
+    pub outcome: String,
+    /// Binary predictions on events resolved YES or NO only.
+    pub brier_score: Option<f64>,
+    pub log_score: Option<f64>,
+    /// The user's predictions marked correct or incorrect, this one included.
+    pub resolved: i64,
+    /// Weighted by the daily quota, as `user_predictions` weights them.
+    pub accuracy: Option<f64>,
+    pub mean_log_score: Option<f64>,
+}
+
+/// Installs the trigger that NOTIFYs `CHANNEL` when a prediction's outcome
+/// changes.
+pub async fn ensure_resolution_trigger(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE OR REPLACE FUNCTION notify_prediction_resolved() RETURNS trigger AS $$
+        BEGIN
+            PERFORM pg_notify('prediction_resolved', NEW.event_id::text);
+            RETURN NULL;
+        END;
+        $$ LANGUAGE plpgsql;
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE OR REPLACE TRIGGER prediction_resolved
+         AFTER UPDATE OF outcome ON predictions
+         FOR EACH ROW
+         WHEN (NEW.outcome IS DISTINCT FROM OLD.outcome AND NEW.event_id IS NOT NULL)
+         EXECUTE FUNCTION notify_prediction_resolved()",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Creates the table holding how far each channel's listener has handled.
+pub async fn ensure_watermark_table(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS live_score_watermarks (
+            channel VARCHAR(64) PRIMARY KEY,
+            watermark TIMESTAMP NOT NULL
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Scores for every resolved prediction on the event, with each forecaster's
+/// running totals. Empty while none are resolved (after a dispute reverts
+/// the event, say).
+pub async fn score_updates(
+    pool: &PgPool,
+    policy: &QuotaPolicy,
+    event_id: i32,
+) -> Result<Vec<ScoreUpdate>> {
+    let rows = sqlx::query(&format!(
+        r#"
+        WITH scored AS (
+            SELECT p.id, p.user_id, p.outcome,
+                   POWER(prob.probability - won.yes::int, 2) AS brier_score,
+                   {log_score} AS log_score
+            {joins}
+            WHERE p.event_id = $1
+              AND p.outcome IN ('correct', 'incorrect', 'not_applicable')
+        ),
+        totals AS (
+            SELECT p.user_id,
+                   COUNT(*) AS resolved,
+                   SUM(quota.weight * (p.outcome = 'correct')::int)
+                       / NULLIF(SUM(quota.weight), 0) AS accuracy,
+                   SUM(quota.weight * {log_score})
+                       / NULLIF(SUM(quota.weight) FILTER (WHERE {log_score} IS NOT NULL), 0)
+                       AS mean_log
+            {weighted_joins}
+            WHERE p.outcome IN ('correct', 'incorrect')
+              AND p.user_id IN (SELECT user_id FROM scored)
+            GROUP BY p.user_id
+        )
+        SELECT s.*, COALESCE(t.resolved, 0) AS resolved, t.accuracy, t.mean_log
+        FROM scored s
+        LEFT JOIN totals t ON t.user_id = s.user_id
+        ORDER BY s.user_id, s.id
+        "#,
+        log_score = log_score_sql("$2"),
+        joins = SCORING_JOINS,
+        weighted_joins = weighted_scoring_joins(policy),
+    ))
+    .bind(event_id)
+    .bind(LOG_SCORE_FLOOR)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .iter()
+        .map(|row| ScoreUpdate {
+            user_id: row.get("user_id"),
+            event_id,
+            prediction_id: row.get("id"),
+            outcome: row.get("outcome"),
+            brier_score: row.get("brier_score"),
+            log_score: row.get("log_score"),
+            resolved: row.get("resolved"),
+            accuracy: row.get("accuracy"),
+            mean_log_score: row.get("mean_log"),
+        })
+        .collect())
+}
+
+async fn database_now(pool: &PgPool) -> Result<NaiveDateTime> {
+    Ok(sqlx::query_scalar("SELECT LOCALTIMESTAMP")
+        .fetch_one(pool)
+        .await?)
+}
+
+async fn stored_watermark(pool: &PgPool) -> Result<Option<NaiveDateTime>> {
+    Ok(
+        sqlx::query_scalar("SELECT watermark FROM live_score_watermarks WHERE channel = $1")
+            .bind(CHANNEL)
+            .fetch_optional(pool)
+            .await?,
+    )
+}
+
+// GREATEST so a listener that falls behind another never moves the stored
+// watermark back.
+async fn advance_watermark(pool: &PgPool, to: NaiveDateTime) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO live_score_watermarks (channel, watermark) VALUES ($1, $2)
+         ON CONFLICT (channel) DO UPDATE
+         SET watermark = GREATEST(live_score_watermarks.watermark, EXCLUDED.watermark)",
+    )
+    .bind(CHANNEL)
+    .bind(to)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Listens on `CHANNEL` and passes each notified event's score updates to
+/// `on_scores`, skipping events with nothing resolved. Starts by replaying
+/// from the stored watermark, if there is one.
+pub async fn listen_for_resolutions<F, Fut>(
+    pool: &PgPool,
+    policy: &QuotaPolicy,
+    mut on_scores: F,
+) -> Result<()>
+where
+    F: FnMut(i32, Vec<ScoreUpdate>) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(CHANNEL).await?;
+    let stored = stored_watermark(pool).await?;
+    let mut replay = stored.is_some();
+    let mut watermark = match stored {
+        Some(watermark) => watermark,
+        None => database_now(pool).await?,
+    };
+    loop {
+        // try_recv yields None after a reconnect; replay what resolved while
+        // the connection was down
+        let notification = if replay {
+            None
+        } else {
+            listener.try_recv().await?
+        };
+        replay = false;
+        // Taken before the replay so nothing resolving during it is skipped
+        let handled_to = database_now(pool).await?;
+        let event_ids: Vec<i32> = match notification {
+            Some(notification) => notification.payload().parse().into_iter().collect(),
+            None => {
+                sqlx::query_scalar(
+                    "SELECT DISTINCT event_id FROM predictions
+                     WHERE resolved_at >= $1 AND event_id IS NOT NULL
+                     ORDER BY event_id",
+                )
+                .bind(watermark)
+                .fetch_all(pool)
+                .await?
+            }
+        };
+        for event_id in event_ids {
+            let updates = score_updates(pool, policy, event_id).await?;
+            if !updates.is_empty() {
+                on_scores(event_id, updates).await;
+            }
+        }
+        advance_watermark(pool, handled_to).await?;
+        watermark = handled_to;
+    }
+}
@@ -1,15 +1,18 @@
 //! LMSR API layer using lmsr_core directly (DRY implementation)
 //! Eliminates the redundant lmsr.rs wrapper for clean architecture
 
+use crate::api_error::{coded, ErrorCode};
 use crate::config::Config;
-use crate::db_adapter::DbAdapter;
+use crate::db_adapter::{tx_backend, DbAdapter, Isolation, Wallet};
+use crate::event_metadata::EventMetadata;
 use crate::lmsr_core::{from_ledger_units, to_ledger_units, Market, Side};
 use crate::lmsr_multi_core::MultiMarket;
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Duration, Utc};
+use intellacc_math::kelly::kelly_stake;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
-use sqlx::{Error as SqlxError, Executor, PgPool, Row};
+use sqlx::{Acquire, Error as SqlxError, Executor, FromRow, PgPool, Row};
 use std::collections::BTreeMap;
 use std::time::Duration as StdDuration;
 use tokio::time::sleep;
@@ -18,25 +21,18 @@ use tracing::debug;
 // Configuration constants for concurrency control
 const MAX_RETRY_ATTEMPTS: u32 = 5;
 const BASE_RETRY_DELAY_MS: u64 = 10;
+/// Attempts an optimistic trade's SERIALIZABLE fallback gets: it runs on a
+/// market that is contended by definition
+const FALLBACK_MAX_ATTEMPTS: u32 = 12;
 const ERR_MARKET_RESOLVED: &str = "Market resolved";
 const ERR_MARKET_CLOSED: &str = "Market closed";
+const ERR_OPTIMISTIC_CONFLICT: &str = "Market changed during optimistic trade";
+const ERR_SLIPPAGE: &str = "Slippage limit exceeded";
+/// First key of the per-user trade locks; the second is the user id
+pub(crate) const USER_TRADE_LOCK_CLASS: i32 = 0x7472_6164;
 
-/// PostgreSQL SQLSTATE codes for retryable errors
-/// Reference: https://www.postgresql.org/docs/current/errcodes-appendix.html
-mod pg_error_codes {
-    // Class 40 — Transaction Rollback
-    pub const SERIALIZATION_FAILURE: &str = "40001";
-    pub const DEADLOCK_DETECTED: &str = "40P01";
-
-    // Class 25 — Invalid Transaction State
-    pub const ACTIVE_SQL_TRANSACTION: &str = "25001";
-
-    // Class 23 — Integrity Constraint Violation (may indicate concurrent updates)
-    pub const UNIQUE_VIOLATION: &str = "23505";
-}
-
-/// Determines if a database error is retryable based on PostgreSQL SQLSTATE codes
-/// This replaces fragile string-based error detection with reliable error code matching
+/// Determines if a database error is retryable from its SQLSTATE, as the
+/// configured transaction backend classifies it
 fn is_retryable_error(error: &anyhow::Error) -> bool {
     // Try to extract the root cause SqlxError
     let mut current_error: &dyn std::error::Error = error.as_ref();
@@ -49,13 +45,7 @@ fn is_retryable_error(error: &anyhow::Error) -> bool {
                     // SQLx provides SQLSTATE through the code() method
                     if let Some(sqlstate) = db_error.code() {
                         let sqlstate_str = sqlstate.as_ref();
-                        let is_retryable = matches!(
-                            sqlstate_str,
-                            pg_error_codes::SERIALIZATION_FAILURE
-                                | pg_error_codes::DEADLOCK_DETECTED
-                                | pg_error_codes::ACTIVE_SQL_TRANSACTION
-                                | pg_error_codes::UNIQUE_VIOLATION
-                        );
+                        let is_retryable = tx_backend().is_retryable(sqlstate_str);
 
                         if is_retryable {
                             debug!(
@@ -92,6 +82,12 @@ pub struct MarketUpdate {
     pub stake: f64,       // Amount of RP to stake - now f64 directly
     pub referral_post_id: Option<i32>,
     pub referral_click_id: Option<i32>,
+    /// Most RP the trade may cost; it aborts rather than fill above this
+    #[serde(default)]
+    pub max_cost: Option<f64>,
+    /// Fewest shares the trade may buy; it aborts rather than fill below this
+    #[serde(default)]
+    pub min_shares: Option<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ts_rs::TS)]
@@ -111,10 +107,201 @@ pub struct UpdateResult {
 #[ts(export, export_to = "../../shared/types/SellResult.ts")]
 pub struct SellResult {
     pub payout: f64,
+    pub shares_sold: f64,
+    pub new_prob: f64,
+    pub current_cost_c: f64,
+}
+
+/// A binary position closed out: both sides sold in one transaction.
+#[derive(Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export, export_to = "../../shared/types/ClosePositionResult.ts")]
+pub struct ClosePositionResult {
+    pub yes_shares_sold: f64,
+    pub no_shares_sold: f64,
+    /// RP paid out for both sides together
+    pub payout: f64,
+    /// payout less the stake the position had open
+    pub realized_pnl: f64,
     pub new_prob: f64,
     pub current_cost_c: f64,
 }
 
+/// A binary sell previewed without executing it.
+#[derive(Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export, export_to = "../../shared/types/SellQuote.ts")]
+pub struct SellQuote {
+    pub share_type: String,
+    /// Shares the sell would sell
+    pub shares: f64,
+    /// Shares of this side held now
+    pub shares_held: f64,
+    /// The sell closes the side, releasing all of its stake
+    pub closes_side: bool,
+    pub payout: f64,
+    /// Payout per share
+    pub avg_price: f64,
+    pub prob_before: f64,
+    pub prob_after: f64,
+    /// prob_after - prob_before
+    pub price_impact: f64,
+    /// Staked RP the sell would release
+    pub stake_unwound: f64,
+    /// payout - stake_unwound
+    pub realized_pnl: f64,
+    /// False while a recent purchase is still in its hold period
+    pub can_sell: bool,
+    /// When the last running hold expires
+    pub hold_until: Option<DateTime<Utc>>,
+}
+
+/// A binary trade previewed without executing it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export, export_to = "../../shared/types/TradeQuote.ts")]
+pub struct TradeQuote {
+    pub share_type: String,
+    pub target_prob: f64,
+    pub stake: f64,
+    /// Shares the stake would buy
+    pub shares: f64,
+    /// RP the trade would debit: the stake, to ledger precision
+    pub cost: f64,
+    /// Cost per share
+    pub avg_price: f64,
+    pub prob_before: f64,
+    pub prob_after: f64,
+    /// Whether the stake moves the price as far as the target
+    pub reaches_target: bool,
+    /// How much more than the pre-trade price of the side each share costs,
+    /// as a fraction of that price
+    pub slippage: f64,
+    /// What the shares pay if their side wins
+    pub payout_if_correct: f64,
+}
+
+/// What moving a binary market to one probability costs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export, export_to = "../../shared/types/DepthLevel.ts")]
+pub struct DepthLevel {
+    pub target_prob: f64,
+    /// RP a single buy reaching the target would debit
+    pub cost: f64,
+    /// Shares that buy gets
+    pub shares: f64,
+    /// Cost per share
+    pub avg_price: f64,
+}
+
+/// The cost curve of a binary market: what it takes to move the price to
+/// each probability on a grid, buying YES above the current price and NO
+/// below it, nearest target first.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export, export_to = "../../shared/types/MarketDepth.ts")]
+pub struct MarketDepth {
+    pub prob: f64,
+    pub liquidity_b: f64,
+    pub step: f64,
+    pub yes: Vec<DepthLevel>,
+    pub no: Vec<DepthLevel>,
+}
+
+/// One user's settlement when a binary market resolves.
+#[derive(Debug, Clone, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export, export_to = "../../shared/types/ResolutionPayout.ts")]
+pub struct ResolutionPayout {
+    pub user_id: i32,
+    /// Winning shares, redeemed at 1 RP each
+    pub shares_redeemed: f64,
+    pub rp_credited: f64,
+    pub stake_released: f64,
+    /// Balance of the wallet the market settles into (the competition
+    /// wallet for competition markets), after the payout
+    pub new_balance: f64,
+    /// Change in reputation (RP held plus staked): credited less released
+    pub score_delta: f64,
+}
+
+/// How a market settles. `NotApplicable` is Metaculus's "annulled": the
+/// question no longer has an answer, so every open position gets its stake
+/// back and the event is left out of scoring, accuracy and peer stats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    Yes,
+    No,
+    NotApplicable,
+}
+
+pub const OUTCOME_NOT_APPLICABLE: &str = "resolved_na";
+
+impl Resolution {
+    /// The `events.outcome` value it writes.
+    pub fn as_outcome(self) -> &'static str {
+        match self {
+            Resolution::Yes => "resolved_yes",
+            Resolution::No => "resolved_no",
+            Resolution::NotApplicable => OUTCOME_NOT_APPLICABLE,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Resolution::Yes => "YES",
+            Resolution::No => "NO",
+            Resolution::NotApplicable => "N/A",
+        }
+    }
+
+    /// The outcome scores are computed against; `None` for N/A.
+    pub fn scored(self) -> Option<bool> {
+        match self {
+            Resolution::Yes => Some(true),
+            Resolution::No => Some(false),
+            Resolution::NotApplicable => None,
+        }
+    }
+}
+
+impl From<bool> for Resolution {
+    fn from(outcome: bool) -> Self {
+        if outcome {
+            Resolution::Yes
+        } else {
+            Resolution::No
+        }
+    }
+}
+
+/// New binary market. Liquidity comes from exactly one of `liquidity_b`
+/// or `max_subsidy` (the most RP the market maker may lose), or else from
+/// the category's liquidity recommendation if it is auto-applied.
+/// `use_recommended_liquidity` overrides that: true takes the
+/// recommendation even if it isn't auto-applied, false never takes it.
+#[derive(Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export, export_to = "../../shared/types/CreateMarket.ts")]
+pub struct CreateMarket {
+    pub title: String,
+    pub details: Option<String>,
+    pub closing_date: DateTime<Utc>,
+    pub category: Option<String>,
+    pub liquidity_b: Option<f64>,
+    pub max_subsidy: Option<f64>,
+    #[serde(default)]
+    pub use_recommended_liquidity: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export, export_to = "../../shared/types/CreatedMarket.ts")]
+pub struct CreatedMarket {
+    pub event_id: i32,
+    pub title: String,
+    pub closing_date: DateTime<Utc>,
+    pub category: Option<String>,
+    pub liquidity_b: f64,
+    pub max_subsidy: f64,
+    pub market_prob: f64,
+    pub q_yes: f64,
+    pub q_no: f64,
+}
+
 #[derive(Debug, Serialize, Deserialize, ts_rs::TS)]
 #[ts(export, export_to = "../../shared/types/KellySuggestion.ts")]
 pub struct KellySuggestion {
@@ -172,33 +359,229 @@ pub struct OutcomeSellResult {
     pub outcomes: Vec<MarketOutcomeView>,
 }
 
-/// Macro for executing transactions with SERIALIZABLE isolation and retry logic
+tokio::task_local! {
+    /// Retry tally for the enclosing `with_retry_count` scope.
+    static TX_RETRY_COUNTER: std::cell::Cell<u32>;
+}
+
+fn note_tx_retry() {
+    let _ = TX_RETRY_COUNTER.try_with(|count| count.set(count.get() + 1));
+}
+
+/// Runs `fut` and reports how many transaction retries (serialization
+/// failures, deadlocks, ...) it needed. Used by the stress harness to track
+/// contention per trade; retries outside a scope are not counted anywhere.
+#[allow(dead_code)] // only the stress harness (lib) calls this, not the server binary
+pub async fn with_retry_count<F: std::future::Future>(fut: F) -> (F::Output, u32) {
+    TX_RETRY_COUNTER
+        .scope(std::cell::Cell::new(0), async move {
+            let output = fut.await;
+            (output, TX_RETRY_COUNTER.with(|count| count.get()))
+        })
+        .await
+}
+
+/// Fault injection rates for the stress harness's chaos mode. All zero (the
+/// default) disables it; production transactions never run inside a chaos
+/// scope, so the hook below is a no-op for them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChaosConfig {
+    /// Chance that a transaction sleeps before committing, holding its locks
+    /// and snapshot open while other trades run.
+    pub delay_rate: f64,
+    pub max_delay_ms: u64,
+    /// Chance that a transaction's backend is terminated before committing.
+    /// The trade fails and the pool has to replace the connection.
+    pub kill_connection_rate: f64,
+    /// Chance that a transaction raises a genuine SQLSTATE 40001 before
+    /// committing, which the retry macros must absorb.
+    pub serialization_failure_rate: f64,
+}
+
+impl Default for ChaosConfig {
+    fn default() -> Self {
+        Self {
+            delay_rate: 0.0,
+            max_delay_ms: 50,
+            kill_connection_rate: 0.0,
+            serialization_failure_rate: 0.0,
+        }
+    }
+}
+
+impl ChaosConfig {
+    #[allow(dead_code)] // only the stress harness (lib) calls this, not the server binary
+    pub fn is_enabled(&self) -> bool {
+        self.delay_rate > 0.0
+            || self.kill_connection_rate > 0.0
+            || self.serialization_failure_rate > 0.0
+    }
+}
+
+/// Whether `error` is a chaos-injected serialization failure that outlasted
+/// the retries, as opposed to any other failure.
+#[allow(dead_code)] // only the stress harness (lib) calls this, not the server binary
+pub fn is_injected_failure(error: &anyhow::Error) -> bool {
+    error
+        .to_string()
+        .contains(tx_backend().injected_failure_marker())
+}
+
+/// Faults injected inside one `with_chaos` scope.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct ChaosInjected {
+    pub delays: u32,
+    pub connection_kills: u32,
+    pub serialization_failures: u32,
+}
+
+struct ChaosState {
+    config: ChaosConfig,
+    rng: rand::rngs::StdRng,
+    injected: ChaosInjected,
+}
+
+tokio::task_local! {
+    /// Chaos settings and tally for the enclosing `with_chaos` scope.
+    static TX_CHAOS: std::cell::RefCell<ChaosState>;
+}
+
+/// Runs `fut` with chaos injection enabled for every transaction it opens.
+/// Draws come from an RNG seeded with `seed`, so a seeded stress run injects
+/// the same faults into the same trades on replay.
+#[allow(dead_code)] // only the stress harness (lib) calls this, not the server binary
+pub async fn with_chaos<F: std::future::Future>(
+    config: &ChaosConfig,
+    seed: u64,
+    fut: F,
+) -> (F::Output, ChaosInjected) {
+    use rand::SeedableRng;
+    let state = ChaosState {
+        config: config.clone(),
+        rng: rand::rngs::StdRng::seed_from_u64(seed),
+        injected: ChaosInjected::default(),
+    };
+    TX_CHAOS
+        .scope(std::cell::RefCell::new(state), async move {
+            let output = fut.await;
+            (output, TX_CHAOS.with(|state| state.borrow().injected))
+        })
+        .await
+}
+
+/// Called by the transaction macros once the body has succeeded, just
+/// before commit, so injected delays hold locks and injected failures
+/// discard real work.
+async fn inject_tx_chaos(tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<()> {
+    let Ok((delay_ms, kill, fail)) = TX_CHAOS.try_with(|state| {
+        let mut state = state.borrow_mut();
+        let ChaosState {
+            config,
+            rng,
+            injected,
+        } = &mut *state;
+        let delay_ms = (config.max_delay_ms > 0 && rng.gen_bool(config.delay_rate.clamp(0.0, 1.0)))
+            .then(|| rng.gen_range(1..=config.max_delay_ms));
+        let kill = rng.gen_bool(config.kill_connection_rate.clamp(0.0, 1.0));
+        let fail = !kill && rng.gen_bool(config.serialization_failure_rate.clamp(0.0, 1.0));
+        if delay_ms.is_some() {
+            injected.delays += 1;
+        }
+        if kill {
+            injected.connection_kills += 1;
+        }
+        if fail {
+            injected.serialization_failures += 1;
+        }
+        (delay_ms, kill, fail)
+    }) else {
+        return Ok(());
+    };
+
+    if let Some(ms) = delay_ms {
+        sleep(StdDuration::from_millis(ms)).await;
+    }
+    if kill {
+        sqlx::query(tx_backend().kill_connection_sql())
+            .execute(&mut **tx)
+            .await?;
+    }
+    if fail {
+        sqlx::query(&tx_backend().injected_failure_sql())
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Queues a user's trades behind one another, so two tabs trading at once
+/// wait for each other instead of conflicting on the same position and
+/// balance rows and retrying; other users' trades don't wait. Taken first
+/// in a READ COMMITTED trade, so the statements after it see the trade it
+/// waited for, and released at commit or rollback. Limit-order fills
+/// don't take it: they lock the market first, and taking it after would
+/// invert the order a direct trade locks in.
+async fn lock_user_trades(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: i32,
+) -> Result<()> {
+    if let Some(sql) = tx_backend().advisory_xact_lock_sql() {
+        sqlx::query(sql)
+            .bind(USER_TRADE_LOCK_CLASS)
+            .bind(user_id)
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Macro for executing transactions with SERIALIZABLE isolation and retry logic,
+/// optionally over more attempts than the backend's default
 macro_rules! with_serializable_tx {
-    ($pool:expr, $tx_var:ident, $body:block) => {{
+    ($pool:expr, $tx_var:ident, $body:block) => {
+        with_serializable_tx!($pool, $tx_var, MAX_RETRY_ATTEMPTS, $body)
+    };
+    ($pool:expr, $tx_var:ident, $max_attempts:expr, $body:block) => {{
+        let max_attempts: u32 = $max_attempts;
         let mut attempt = 1;
         loop {
             let mut $tx_var = $pool.begin().await?;
 
             // Set SERIALIZABLE isolation level
             $tx_var
-                .execute(sqlx::query("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE"))
+                .execute(sqlx::query(
+                    tx_backend().isolation_sql(Isolation::Serializable),
+                ))
                 .await?;
 
             let result: Result<_> = async { $body }.await;
-
-            match result {
-                Ok(value) => {
-                    $tx_var.commit().await?;
-                    break Ok(value);
-                }
+            let result = match result {
+                Ok(value) => inject_tx_chaos(&mut $tx_var).await.map(|_| value),
+                Err(e) => Err(e),
+            };
+            // A commit can fail on a conflict too (CockroachDB often does)
+            let result = match result {
+                Ok(value) => $tx_var
+                    .commit()
+                    .await
+                    .map(|_| value)
+                    .map_err(anyhow::Error::from),
                 Err(e) => {
                     $tx_var.rollback().await.ok();
+                    Err(e)
+                }
+            };
 
-                    // Check if this is a retryable error using PostgreSQL SQLSTATE codes
-                    if is_retryable_error(&e) && attempt < MAX_RETRY_ATTEMPTS {
+            match result {
+                Ok(value) => break Ok(value),
+                Err(e) => {
+                    // Check if this is a retryable error by its SQLSTATE
+                    if is_retryable_error(&e) && attempt < max_attempts {
                         // Exponential backoff with jitter
                         let jitter = rand::thread_rng().gen_range(0..10);
-                        let delay_ms = BASE_RETRY_DELAY_MS * (1 << (attempt - 1)) + jitter;
+                        let delay_ms = (BASE_RETRY_DELAY_MS << (attempt - 1).min(5)) + jitter;
+                        note_tx_retry();
                         sleep(StdDuration::from_millis(delay_ms)).await;
                         attempt += 1;
                         continue;
@@ -218,27 +601,39 @@ macro_rules! with_optimistic_tx {
         loop {
             let mut $tx_var = $pool.begin().await?;
 
-            // Set READ COMMITTED isolation level
+            // Set READ COMMITTED isolation level, or SERIALIZABLE where the
+            // backend lacks it; the optimistic checks hold under either
             $tx_var
                 .execute(sqlx::query(
-                    "SET TRANSACTION ISOLATION LEVEL READ COMMITTED",
+                    tx_backend().isolation_sql(Isolation::ReadCommitted),
                 ))
                 .await?;
 
             let result: Result<_> = async { $body }.await;
-
-            match result {
-                Ok(value) => {
-                    $tx_var.commit().await?;
-                    break Ok(value);
-                }
+            let result = match result {
+                Ok(value) => inject_tx_chaos(&mut $tx_var).await.map(|_| value),
+                Err(e) => Err(e),
+            };
+            let result = match result {
+                Ok(value) => $tx_var
+                    .commit()
+                    .await
+                    .map(|_| value)
+                    .map_err(anyhow::Error::from),
                 Err(e) => {
                     $tx_var.rollback().await.ok();
+                    Err(e)
+                }
+            };
 
-                    // Check if this is a retryable error using PostgreSQL SQLSTATE codes
+            match result {
+                Ok(value) => break Ok(value),
+                Err(e) => {
+                    // Check if this is a retryable error by its SQLSTATE
                     if is_retryable_error(&e) && attempt < MAX_RETRY_ATTEMPTS {
                         let jitter = rand::thread_rng().gen_range(0..5);
                         let delay_ms = BASE_RETRY_DELAY_MS * attempt as u64 + jitter;
+                        note_tx_retry();
                         sleep(StdDuration::from_millis(delay_ms)).await;
                         attempt += 1;
                         continue;
@@ -260,48 +655,117 @@ pub async fn update_market(
 ) -> Result<UpdateResult> {
     // Validate inputs first (outside transaction)
     if update.target_prob <= 0.0 || update.target_prob >= 1.0 {
-        return Err(anyhow!("Target probability must be between 0 and 1"));
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "Target probability must be between 0 and 1",
+        ));
     }
     if update.stake <= 0.0 {
-        return Err(anyhow!("Stake must be positive"));
+        return Err(coded(ErrorCode::InvalidRequest, "Stake must be positive"));
+    }
+    if update
+        .max_cost
+        .is_some_and(|max_cost| !(max_cost.is_finite() && max_cost > 0.0))
+    {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "max_cost must be positive",
+        ));
+    }
+    if update
+        .min_shares
+        .is_some_and(|min_shares| !(min_shares.is_finite() && min_shares >= 0.0))
+    {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "min_shares must not be negative",
+        ));
+    }
+
+    // Small stakes skip the market row lock and only commit if nothing moved
+    // underneath them; a conflict retries the trade locked under SERIALIZABLE
+    if update.stake < config.market.optimistic_stake_threshold {
+        let optimistic = with_optimistic_tx!(pool, tx, {
+            lock_user_trades(&mut tx, user_id).await?;
+            update_market_transaction(&mut tx, config, user_id, &update, false, None).await
+        });
+        match optimistic {
+            Err(e) if e.to_string() == ERR_OPTIMISTIC_CONFLICT => {
+                debug!(
+                    user_id,
+                    event_id = update.event_id,
+                    "optimistic trade conflicted"
+                );
+                note_tx_retry();
+            }
+            result => return result,
+        }
+        // A SERIALIZABLE trade that waits on the market row lock fails once
+        // the holder commits, so waits are cut short and both failures retry
+        // with backoff, over more attempts than usual
+        return with_serializable_tx!(pool, tx, FALLBACK_MAX_ATTEMPTS, {
+            sqlx::query("SET LOCAL lock_timeout = '100ms'")
+                .execute(tx.as_mut())
+                .await?;
+            update_market_transaction(&mut tx, config, user_id, &update, true, None).await
+        });
     }
 
     with_optimistic_tx!(pool, tx, {
-        update_market_transaction(&mut tx, config, user_id, &update).await
+        lock_user_trades(&mut tx, user_id).await?;
+        update_market_transaction(&mut tx, config, user_id, &update, true, None).await
     })
 }
 
-// Internal transaction logic extracted for concurrency control
+// Internal transaction logic extracted for concurrency control. Without
+// `lock` the market row is read unlocked, and the market and position
+// writes check that nothing changed since, failing with
+// ERR_OPTIMISTIC_CONFLICT if it did. The side bought follows the target
+// unless `side` fixes it, as a limit order does.
 async fn update_market_transaction(
     tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     config: &Config,
     user_id: i32,
     update: &MarketUpdate,
+    lock: bool,
+    side: Option<Side>,
 ) -> Result<UpdateResult> {
-    // Get current market state with row lock
-    let row = sqlx::query(
+    // Get current market state, with row lock unless optimistic
+    let row = sqlx::query(&format!(
         "SELECT market_prob, cumulative_stake, liquidity_b, q_yes, q_no, event_type, outcome,
-                COALESCE(closing_date <= NOW(), false) AS is_closed
+                competition_id,
+                (closed_at IS NOT NULL OR COALESCE(closing_date <= NOW(), false)) AS is_closed
          FROM events
-         WHERE id = $1
-         FOR UPDATE",
-    )
+         WHERE id = $1{}",
+        if lock { " FOR UPDATE" } else { "" }
+    ))
     .bind(update.event_id)
-    .fetch_one(tx.as_mut())
-    .await
-    .map_err(|_| anyhow!("Event not found or market not initialized"))?;
+    .fetch_optional(tx.as_mut())
+    .await?
+    .ok_or_else(|| {
+        coded(
+            ErrorCode::NotFound,
+            "Event not found or market not initialized",
+        )
+    })?;
 
     let outcome: Option<String> = row.get("outcome");
     let event_type: String = row.get("event_type");
     let is_closed: bool = row.get("is_closed");
+    let wallet = Wallet::for_market(row.get("competition_id"));
     if outcome.is_some() {
-        return Err(anyhow!(ERR_MARKET_RESOLVED));
+        return Err(coded(ErrorCode::MarketResolved, ERR_MARKET_RESOLVED));
     }
     if is_closed {
-        return Err(anyhow!(ERR_MARKET_CLOSED));
+        return Err(coded(ErrorCode::MarketClosed, ERR_MARKET_CLOSED));
     }
+    crate::embargo::ensure_not_embargoed(tx.as_mut(), update.event_id).await?;
+    crate::market_import::ensure_tradeable(tx.as_mut(), update.event_id).await?;
     if !event_type.eq_ignore_ascii_case("binary") {
-        return Err(anyhow!("Use outcome-based endpoint for non-binary markets"));
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "Use /events/:id/update-outcome for this market type",
+        ));
     }
 
     // Extract market state using clean adapter
@@ -318,24 +782,32 @@ async fn update_market_transaction(
         b: liquidity_b,
     };
 
-    let had_prior_position: bool = sqlx::query_scalar(
-        "SELECT EXISTS(
-           SELECT 1
-           FROM user_shares
-           WHERE user_id = $1 AND event_id = $2 AND (yes_shares > 0 OR no_shares > 0)
-        )",
+    let position = sqlx::query(
+        "SELECT COALESCE(yes_shares > 0 OR no_shares > 0, false) AS open,
+                COALESCE(version, 1) AS version
+         FROM user_shares
+         WHERE user_id = $1 AND event_id = $2",
     )
     .bind(user_id)
     .bind(update.event_id)
-    .fetch_one(tx.as_mut())
+    .fetch_optional(tx.as_mut())
     .await?;
+    let had_prior_position = position
+        .as_ref()
+        .is_some_and(|row| row.get::<bool, _>("open"));
+    let position_version: Option<i32> = position.map(|row| row.get("version"));
 
     // Convert stake to ledger units for exact computation
-    let stake_ledger =
-        to_ledger_units(update.stake).map_err(|e| anyhow!("Invalid stake value: {}", e))?;
+    let stake_ledger = to_ledger_units(update.stake).map_err(|e| {
+        coded(
+            ErrorCode::InvalidRequest,
+            format!("Invalid stake value: {}", e),
+        )
+    })?;
 
     // Execute trade based on target probability
-    let (shares_acquired, side, actual_cost_ledger) = if update.target_prob > prev_prob {
+    let buy_yes = side.map_or(update.target_prob > prev_prob, |side| side == Side::Yes);
+    let (shares_acquired, side, actual_cost_ledger) = if buy_yes {
         // Buy YES shares to increase probability
         let (shares, cost) = market
             .buy_yes(stake_ledger)
@@ -352,26 +824,68 @@ async fn update_market_transaction(
     // Keep actual_cost_ledger as i128, only convert for final result
     let actual_cost = from_ledger_units(actual_cost_ledger);
     let new_prob = market.prob_yes();
+
+    // The caller's bounds are checked against the price this transaction
+    // trades at, which may have moved since they were quoted
+    if let Some(max_cost) = update.max_cost {
+        if actual_cost > max_cost {
+            return Err(coded(
+                ErrorCode::SlippageExceeded,
+                format!(
+                    "{}: cost {} exceeds max_cost {}",
+                    ERR_SLIPPAGE, actual_cost, max_cost
+                ),
+            ));
+        }
+    }
+    if let Some(min_shares) = update.min_shares {
+        if shares_acquired < min_shares {
+            return Err(coded(
+                ErrorCode::SlippageExceeded,
+                format!(
+                    "{}: {} shares is below min_shares {}",
+                    ERR_SLIPPAGE, shares_acquired, min_shares
+                ),
+            ));
+        }
+    }
     let new_cumulative_cost = market.cost();
 
     // Update market state using clean adapter
-    DbAdapter::update_market_state(
+    if lock {
+        DbAdapter::update_market_state(
+            tx,
+            update.event_id,
+            new_prob,
+            new_cumulative_cost,
+            market.q_yes,
+            market.q_no,
+        )
+        .await?;
+    } else if !DbAdapter::update_market_state_if_unchanged(
         tx,
         update.event_id,
         new_prob,
         new_cumulative_cost,
         market.q_yes,
         market.q_no,
+        &market_state,
     )
-    .await?;
+    .await?
+    {
+        return Err(anyhow!(ERR_OPTIMISTIC_CONFLICT));
+    }
 
     // Deduct exact cost from user balance using ledger-native method (single rounding boundary)
     let cost_ledger_i64 = i64::try_from(actual_cost_ledger)
         .map_err(|_| anyhow!("actual_cost_ledger out of i64 range"))?;
     let has_sufficient_funds =
-        DbAdapter::deduct_user_cost_ledger(tx, user_id, cost_ledger_i64).await?;
+        DbAdapter::deduct_wallet_cost_ledger(tx, wallet, user_id, cost_ledger_i64).await?;
     if !has_sufficient_funds {
-        return Err(anyhow!("Insufficient RP balance"));
+        return Err(coded(
+            ErrorCode::InsufficientBalance,
+            "Insufficient RP balance",
+        ));
     }
 
     // Record the update with configurable hold period using clean adapter
@@ -402,17 +916,32 @@ async fn update_market_transaction(
         had_prior_position,
     )
     .await?;
+    crate::price_history::record(tx.as_mut(), update.event_id, prev_prob, new_prob).await?;
 
     // Update user shares using ledger-native method (single rounding boundary)
-    DbAdapter::update_user_shares_ledger(
+    if lock {
+        DbAdapter::update_user_shares_ledger(
+            tx,
+            user_id,
+            update.event_id,
+            side,
+            shares_acquired,
+            cost_ledger_i64,
+        )
+        .await?;
+    } else if !DbAdapter::update_user_shares_ledger_if_version(
         tx,
         user_id,
         update.event_id,
         side,
         shares_acquired,
         cost_ledger_i64,
+        position_version,
     )
-    .await?;
+    .await?
+    {
+        return Err(anyhow!(ERR_OPTIMISTIC_CONFLICT));
+    }
 
     // Calculate expected payouts for display
     let expected_if_yes = if side == Side::Yes {
@@ -438,6 +967,221 @@ async fn update_market_transaction(
     })
 }
 
+/// Triggered limit orders filled (or failed) in one matcher pass, at most.
+/// Each fill leaves the price at or past its order's trigger, so this only
+/// bounds a market with a long queue.
+const MAX_LIMIT_FILLS_PER_PASS: usize = 100;
+
+/// How far past its trigger the price must be for an order to fill: a
+/// bounded fill leaves the price on the trigger, give or take rounding.
+const LIMIT_TRIGGER_TOLERANCE: f64 = 1e-6;
+
+/// What the matcher did with one triggered limit order: the trade it
+/// placed, or why it couldn't.
+#[derive(Debug, Serialize)]
+pub struct LimitOrderFill {
+    pub order_id: i64,
+    pub user_id: i32,
+    pub event_id: i32,
+    pub side: String,
+    pub trigger_prob: f64,
+    pub stake_ledger: i64,
+    /// Spent by this fill
+    pub filled_ledger: i64,
+    /// Whether the whole stake is now spent; if not, the rest keeps resting
+    pub complete: bool,
+    pub trade: Option<UpdateResult>,
+    pub error: Option<String>,
+}
+
+/// Fills the resting limit orders on `event_id` that its current price
+/// triggers, oldest first (see `limit_orders`). Each fill moves the price,
+/// so the triggers are checked again before every order; an opposite order
+/// the fill pushes the price into fills in the same pass.
+pub async fn match_limit_orders(
+    pool: &PgPool,
+    config: &Config,
+    event_id: i32,
+) -> Result<Vec<LimitOrderFill>> {
+    let mut fills = Vec::new();
+    while fills.len() < MAX_LIMIT_FILLS_PER_PASS {
+        let fill = with_optimistic_tx!(pool, tx, {
+            fill_next_limit_order(&mut tx, config, event_id).await
+        })?;
+        match fill {
+            Some(fill) => fills.push(fill),
+            None => break,
+        }
+    }
+    Ok(fills)
+}
+
+// Locks the market so the price checked against the triggers is the one
+// the fill trades at. The order's reserve goes back to the wallet for the
+// trade, which spends at most what takes the price to the trigger, and
+// whatever is left of it is reserved again. A trade that fails for the
+// order's own reasons (funds, embargo) is rolled back to a savepoint and
+// the order marked failed, returning its reserve; retryable failures retry
+// the whole fill.
+async fn fill_next_limit_order(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    config: &Config,
+    event_id: i32,
+) -> Result<Option<LimitOrderFill>> {
+    let Some(row) = sqlx::query(
+        "SELECT market_prob, cumulative_stake, liquidity_b, q_yes, q_no, competition_id
+         FROM events
+         WHERE id = $1 AND outcome IS NULL AND closed_at IS NULL
+           AND NOT COALESCE(closing_date <= NOW(), false)
+         FOR UPDATE",
+    )
+    .bind(event_id)
+    .fetch_optional(tx.as_mut())
+    .await?
+    else {
+        return Ok(None);
+    };
+    let state = DbAdapter::extract_market_state(&row)?;
+    let wallet = Wallet::for_market(row.get("competition_id"));
+    let market = Market {
+        q_yes: state.q_yes,
+        q_no: state.q_no,
+        b: state.liquidity_b,
+    };
+    let prob = market.prob_yes();
+    let Some(order) = sqlx::query(
+        "SELECT id, user_id, side, trigger_prob, stake_ledger, filled_ledger, reserved_ledger
+         FROM limit_orders
+         WHERE event_id = $1 AND status = 'open'
+           AND ((side = 'yes' AND $2 < trigger_prob - $3)
+                OR (side = 'no' AND $2 > trigger_prob + $3))
+         ORDER BY created_at, id
+         LIMIT 1
+         FOR UPDATE SKIP LOCKED",
+    )
+    .bind(event_id)
+    .bind(prob)
+    .bind(LIMIT_TRIGGER_TOLERANCE)
+    .fetch_optional(tx.as_mut())
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let side_name: String = order.get("side");
+    let side = Side::from_str(&side_name).map_err(|e| anyhow!(e))?;
+    let reserved_ledger: i64 = order.get("reserved_ledger");
+    let mut fill = LimitOrderFill {
+        order_id: order.get("id"),
+        user_id: order.get("user_id"),
+        event_id,
+        side: side_name,
+        trigger_prob: order.get("trigger_prob"),
+        stake_ledger: order.get("stake_ledger"),
+        filled_ledger: 0,
+        complete: false,
+        trade: None,
+        error: None,
+    };
+    let remaining_ledger = fill.stake_ledger - order.get::<i64, _>("filled_ledger");
+    let (_, to_trigger) = move_to(&market, fill.trigger_prob);
+    let spend_ledger = to_ledger_units(to_trigger)
+        .ok()
+        .and_then(|cost| i64::try_from(cost).ok())
+        .unwrap_or(i64::MAX)
+        .min(remaining_ledger);
+    if spend_ledger <= 0 {
+        return Ok(None);
+    }
+    // The trade's own cost for this stake, to bound it and to book the fill
+    let mut probe = market;
+    let (_, cost_ledger) = probe
+        .apply_trade(side, spend_ledger as i128)
+        .map_err(|e| anyhow!("Trade execution failed: {}", e))?;
+    let cost_ledger =
+        i64::try_from(cost_ledger).map_err(|_| anyhow!("Limit order fill cost out of range"))?;
+    let update = MarketUpdate {
+        event_id,
+        target_prob: fill.trigger_prob,
+        stake: from_ledger_units(spend_ledger as i128),
+        referral_post_id: None,
+        referral_click_id: None,
+        max_cost: Some(from_ledger_units(cost_ledger as i128)),
+        min_shares: None,
+    };
+
+    let mut savepoint = tx.begin().await?;
+    let traded = async {
+        crate::limit_orders::move_reserve(&mut savepoint, wallet, fill.user_id, -reserved_ledger)
+            .await?;
+        let trade = update_market_transaction(
+            &mut savepoint,
+            config,
+            fill.user_id,
+            &update,
+            true,
+            Some(side),
+        )
+        .await?;
+        let reserved_after = reserved_ledger - reserved_ledger.min(cost_ledger);
+        if !crate::limit_orders::move_reserve(&mut savepoint, wallet, fill.user_id, reserved_after)
+            .await?
+        {
+            return Err(coded(
+                ErrorCode::InsufficientBalance,
+                "Insufficient RP balance",
+            ));
+        }
+        Ok((trade, reserved_after))
+    }
+    .await;
+    match traded {
+        Ok((trade, reserved_after)) => {
+            savepoint.commit().await?;
+            fill.filled_ledger = cost_ledger;
+            fill.complete = cost_ledger >= remaining_ledger;
+            sqlx::query(
+                "UPDATE limit_orders
+                 SET status = CASE WHEN $6 THEN 'filled' ELSE 'open' END,
+                     filled_ledger = filled_ledger + $4, reserved_ledger = $5,
+                     market_update_id = $2, fill_prob = $3, filled_at = NOW()
+                 WHERE id = $1",
+            )
+            .bind(fill.order_id)
+            .bind(trade.market_update_id)
+            .bind(trade.new_prob)
+            .bind(cost_ledger)
+            .bind(reserved_after)
+            .bind(fill.complete)
+            .execute(tx.as_mut())
+            .await?;
+            fill.trade = Some(trade);
+        }
+        Err(e) if is_retryable_error(&e) => return Err(e),
+        Err(e) => {
+            savepoint.rollback().await?;
+            if !crate::limit_orders::move_reserve(tx, wallet, fill.user_id, -reserved_ledger)
+                .await?
+            {
+                return Err(anyhow!(
+                    "Limit order {} reserve could not be returned",
+                    fill.order_id
+                ));
+            }
+            sqlx::query(
+                "UPDATE limit_orders SET status = 'failed', reserved_ledger = 0, error = $2
+                 WHERE id = $1",
+            )
+            .bind(fill.order_id)
+            .bind(e.to_string())
+            .execute(tx.as_mut())
+            .await?;
+            fill.error = Some(e.to_string());
+        }
+    }
+    Ok(Some(fill))
+}
+
 #[derive(Debug, Clone)]
 struct OutcomeStateRow {
     outcome_id: i64,
@@ -510,8 +1254,9 @@ async fn ensure_not_numeric_market(
             .fetch_optional(tx.as_mut())
             .await?;
     if has_numeric_config.is_some() {
-        return Err(anyhow!(
-            "This market trades as a distribution — use the numeric trading interface"
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "This market trades as a distribution — use the numeric trading interface",
         ));
     }
     Ok(())
@@ -538,7 +1283,10 @@ async fn ensure_not_multi_outcome_market(
     .fetch_one(tx.as_mut())
     .await?;
     if active_outcomes >= 2 {
-        return Err(anyhow!("multi-outcome market — resolve by outcome id"));
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "multi-outcome market — resolve by outcome id",
+        ));
     }
     Ok(())
 }
@@ -550,13 +1298,20 @@ pub async fn update_market_outcome(
     update: OutcomeMarketUpdate,
 ) -> Result<OutcomeUpdateResult> {
     if update.outcome_id <= 0 {
-        return Err(anyhow!("outcome_id must be positive"));
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "outcome_id must be positive",
+        ));
     }
     if update.stake <= 0.0 || !update.stake.is_finite() {
-        return Err(anyhow!("stake must be positive and finite"));
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "stake must be positive and finite",
+        ));
     }
 
     with_optimistic_tx!(pool, tx, {
+        lock_user_trades(&mut tx, user_id).await?;
         update_market_outcome_transaction(&mut tx, config, user_id, &update).await
     })
 }
@@ -578,29 +1333,39 @@ async fn update_market_outcome_transaction(
             q_yes,
             q_no,
             outcome,
-            COALESCE(closing_date <= NOW(), false) AS is_closed
+            competition_id,
+            (closed_at IS NOT NULL OR COALESCE(closing_date <= NOW(), false)) AS is_closed
         FROM events
         WHERE id = $1
         FOR UPDATE
         "#,
     )
     .bind(update.event_id)
-    .fetch_one(tx.as_mut())
-    .await
-    .map_err(|_| anyhow!("Event not found or market not initialized"))?;
+    .fetch_optional(tx.as_mut())
+    .await?
+    .ok_or_else(|| {
+        coded(
+            ErrorCode::NotFound,
+            "Event not found or market not initialized",
+        )
+    })?;
 
     let event_type: String = event_row.get("event_type");
     let outcome: Option<String> = event_row.get("outcome");
     let is_closed: bool = event_row.get("is_closed");
+    let wallet = Wallet::for_market(event_row.get("competition_id"));
     if outcome.is_some() {
-        return Err(anyhow!(ERR_MARKET_RESOLVED));
+        return Err(coded(ErrorCode::MarketResolved, ERR_MARKET_RESOLVED));
     }
     if is_closed {
-        return Err(anyhow!(ERR_MARKET_CLOSED));
+        return Err(coded(ErrorCode::MarketClosed, ERR_MARKET_CLOSED));
     }
+    crate::embargo::ensure_not_embargoed(tx.as_mut(), update.event_id).await?;
+    crate::market_import::ensure_tradeable(tx.as_mut(), update.event_id).await?;
     if event_type == "binary" {
-        return Err(anyhow!(
-            "Use legacy binary update endpoint for binary markets"
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "Use legacy binary update endpoint for binary markets",
         ));
     }
     ensure_not_numeric_market(tx, update.event_id).await?;
@@ -608,15 +1373,21 @@ async fn update_market_outcome_transaction(
     let liquidity_b: f64 = event_row.get("liquidity_b");
     let mut outcomes = fetch_outcome_state_rows(tx, update.event_id).await?;
     if outcomes.len() < 2 {
-        return Err(anyhow!(
-            "This market has no configured outcomes yet. Configure outcomes first."
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "This market has no configured outcomes yet. Configure outcomes first.",
         ));
     }
 
     let selected_idx = outcomes
         .iter()
         .position(|o| o.outcome_id == update.outcome_id)
-        .ok_or_else(|| anyhow!("Selected outcome is not active for this market"))?;
+        .ok_or_else(|| {
+            coded(
+                ErrorCode::InvalidRequest,
+                "Selected outcome is not active for this market",
+            )
+        })?;
 
     let q: Vec<f64> = outcomes.iter().map(|o| o.q_value).collect();
     let mut market = MultiMarket::new(q, liquidity_b)?;
@@ -632,9 +1403,12 @@ async fn update_market_outcome_transaction(
             .map_err(|_| anyhow!("actual_cost_ledger out of i64 range"))?;
 
     let has_sufficient_funds =
-        DbAdapter::deduct_user_cost_ledger(tx, user_id, actual_cost_ledger).await?;
+        DbAdapter::deduct_wallet_cost_ledger(tx, wallet, user_id, actual_cost_ledger).await?;
     if !has_sufficient_funds {
-        return Err(anyhow!("Insufficient RP balance"));
+        return Err(coded(
+            ErrorCode::InsufficientBalance,
+            "Insufficient RP balance",
+        ));
     }
 
     let hold_duration_hours = if config.market.enable_hold_period {
@@ -787,41 +1561,416 @@ async fn update_market_outcome_transaction(
     })
 }
 
-// Sell shares back to market using lmsr_core directly
-pub async fn sell_shares(
-    pool: &PgPool,
+// Latest hold still running on the user's buys in a binary market, if the
+// hold period is enabled. A hold runs the hold period from the buy, so only
+// buys that recent can hold one; bounding created_at lets a partitioned
+// market_updates skip older months.
+async fn active_hold_until(
+    conn: &mut sqlx::PgConnection,
     config: &Config,
     user_id: i32,
     event_id: i32,
-    share_type: &str,
-    amount: f64,
-) -> Result<SellResult> {
-    // Parse share_type at API boundary
-    let side = Side::from_str(share_type).map_err(|e| anyhow!("Invalid share type: {}", e))?;
-
-    // Basic validation outside transaction
-    if amount <= 0.0 {
-        return Err(anyhow!("Amount must be positive"));
+) -> Result<Option<DateTime<Utc>>> {
+    if !config.market.enable_hold_period {
+        return Ok(None);
     }
-
-    with_optimistic_tx!(pool, tx, {
-        sell_shares_transaction(&mut tx, config, user_id, event_id, side, amount).await
-    })
+    Ok(sqlx::query_scalar(
+        "SELECT MAX(hold_until) FROM market_updates
+         WHERE user_id = $1 AND event_id = $2 AND hold_until > $3
+           AND created_at > $3 - $4 * INTERVAL '1 hour' - INTERVAL '1 minute'",
+    )
+    .bind(user_id)
+    .bind(event_id)
+    .bind(Utc::now())
+    .bind(config.market.hold_period_hours)
+    .fetch_one(conn)
+    .await?)
 }
 
-// Internal transaction logic for sell_shares
-async fn sell_shares_transaction(
-    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
-    config: &Config,
-    user_id: i32,
+// Staked ledger a sale of `amount` of a side holding `shares_of_type` and
+// `stake_of_side_ledger` releases: all of it when the side closes,
+// otherwise the proportional share.
+fn stake_to_unwind(
+    amount: f64,
+    shares_of_type: f64,
+    stake_of_side_ledger: i64,
+    closes_side: bool,
+) -> Result<i64> {
+    if closes_side {
+        // Closing the side releases all of its stake, rounding remainder included
+        return Ok(stake_of_side_ledger);
+    }
+    if shares_of_type <= 0.0 || stake_of_side_ledger <= 0 {
+        return Ok(0);
+    }
+    // Pure integer arithmetic for proportional calculation (eliminates double rounding)
+    let amount_ledger =
+        to_ledger_units(amount).map_err(|e| anyhow!("Invalid sell amount: {}", e))?;
+    let shares_ledger =
+        to_ledger_units(shares_of_type).map_err(|e| anyhow!("Invalid shares amount: {}", e))?;
+
+    // Ensure shares_ledger is not zero to prevent division by zero
+    if shares_ledger == 0 {
+        return Err(anyhow!(
+            "Cannot calculate proportional stake for zero shares"
+        ));
+    }
+
+    // Pure integer proportional calculation with round-to-nearest: (stake * amount) / shares
+    // Safe arithmetic with overflow protection
+    let stake_of_side_i128 = stake_of_side_ledger as i128;
+    let amount_i128 = amount_ledger as i128;
+
+    let numer = stake_of_side_i128
+        .checked_mul(amount_i128)
+        .ok_or_else(|| anyhow!("Arithmetic overflow in proportional stake calculation"))?;
+    let stake_to_unwind = (numer + (shares_ledger / 2)) / shares_ledger; // Round to nearest
+    let clamped = stake_to_unwind.max(0).min(stake_of_side_i128);
+    i64::try_from(clamped).map_err(|_| anyhow!("stake_to_unwind_ledger out of i64 range"))
+}
+
+/// GET /events/:id/sell-quote — what selling `amount` shares of a side (the
+/// whole side when `amount` is None: the most the position can extract)
+/// would pay right now, by the same math the sell executes. Never locks,
+/// never writes; a running hold period is reported rather than refused.
+pub async fn get_sell_quote(
+    pool: &PgPool,
+    config: &Config,
+    user_id: i32,
     event_id: i32,
-    side: Side,
+    share_type: &str,
+    amount: Option<f64>,
+) -> Result<SellQuote> {
+    let side = Side::from_str(share_type).map_err(|e| {
+        coded(
+            ErrorCode::InvalidRequest,
+            format!("Invalid share type: {}", e),
+        )
+    })?;
+    if amount.is_some_and(|a| a <= 0.0) {
+        return Err(coded(ErrorCode::InvalidRequest, "Amount must be positive"));
+    }
+    let mut conn = pool.acquire().await?;
+    let event_row = sqlx::query(
+        "SELECT market_prob, liquidity_b, q_yes, q_no, outcome,
+                (closed_at IS NOT NULL OR COALESCE(closing_date <= NOW(), false)) AS is_closed
+         FROM events
+         WHERE id = $1",
+    )
+    .bind(event_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| coded(ErrorCode::NotFound, "Event not found"))?;
+    if event_row.get::<Option<String>, _>("outcome").is_some() {
+        return Err(coded(ErrorCode::MarketResolved, ERR_MARKET_RESOLVED));
+    }
+    if event_row.get::<bool, _>("is_closed") {
+        return Err(coded(ErrorCode::MarketClosed, ERR_MARKET_CLOSED));
+    }
+
+    let position = sqlx::query(
+        "SELECT yes_shares, no_shares, staked_yes_ledger, staked_no_ledger
+         FROM user_shares
+         WHERE user_id = $1 AND event_id = $2",
+    )
+    .bind(user_id)
+    .bind(event_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let (shares_of_type, stake_of_side_ledger): (f64, i64) = match (position, side) {
+        (Some(r), Side::Yes) => (r.get("yes_shares"), r.get("staked_yes_ledger")),
+        (Some(r), Side::No) => (r.get("no_shares"), r.get("staked_no_ledger")),
+        (None, _) => (0.0, 0),
+    };
+
+    // Same closing rule as the sell: a sale within dust of the holding
+    // closes the side
+    let closes_side =
+        amount.is_none_or(|a| (shares_of_type - a).abs() <= config.market.share_dust_epsilon);
+    let amount = amount.unwrap_or(shares_of_type);
+    if (!closes_side && shares_of_type < amount) || (closes_side && shares_of_type <= 0.0) {
+        return Err(coded(
+            ErrorCode::InsufficientShares,
+            format!("Insufficient {} shares", side.as_str().to_uppercase()),
+        ));
+    }
+    let amount = if closes_side { shares_of_type } else { amount };
+
+    let state = DbAdapter::extract_market_state(&event_row)?;
+    let mut market = Market {
+        q_yes: state.q_yes,
+        q_no: state.q_no,
+        b: state.liquidity_b,
+    };
+    let prob_before = market.prob_yes();
+    let payout_ledger = market
+        .apply_sell(side, amount)
+        .map_err(|e| anyhow!("Sell execution failed: {}", e))?;
+    let payout = from_ledger_units(payout_ledger);
+    let prob_after = market.prob_yes();
+    let stake_unwound_ledger =
+        stake_to_unwind(amount, shares_of_type, stake_of_side_ledger, closes_side)?;
+    let stake_unwound = from_ledger_units(stake_unwound_ledger as i128);
+    let hold_until = active_hold_until(&mut conn, config, user_id, event_id).await?;
+
+    Ok(SellQuote {
+        share_type: side.as_str().to_string(),
+        shares: amount,
+        shares_held: shares_of_type,
+        closes_side,
+        payout,
+        avg_price: payout / amount,
+        prob_before,
+        prob_after,
+        price_impact: prob_after - prob_before,
+        stake_unwound,
+        realized_pnl: payout - stake_unwound,
+        can_sell: hold_until.is_none(),
+        hold_until,
+    })
+}
+
+/// GET /events/:id/quote — what staking `stake` RP toward `target_prob`
+/// would buy on `market`, by the same math `update_market` executes. Pure:
+/// it reads and writes nothing, so it can't tell whether the market is open
+/// or the trader can afford it; the trade itself checks both.
+pub fn quote(market: &Market, target_prob: f64, stake: f64) -> Result<TradeQuote> {
+    if !(target_prob > 0.0 && target_prob < 1.0) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "Target probability must be between 0 and 1",
+        ));
+    }
+    if !(stake.is_finite() && stake > 0.0) {
+        return Err(coded(ErrorCode::InvalidRequest, "Stake must be positive"));
+    }
+    let stake_ledger = to_ledger_units(stake).map_err(|e| anyhow!("Invalid stake value: {}", e))?;
+    let mut after = *market;
+    let prob_before = market.prob_yes();
+    let side = if target_prob > prob_before {
+        Side::Yes
+    } else {
+        Side::No
+    };
+    let (shares, cost_ledger) = after
+        .apply_trade(side, stake_ledger)
+        .map_err(|e| anyhow!("Trade execution failed: {}", e))?;
+    let cost = from_ledger_units(cost_ledger);
+    let prob_after = after.prob_yes();
+    let avg_price = cost / shares;
+    let price_before = match side {
+        Side::Yes => prob_before,
+        Side::No => 1.0 - prob_before,
+    };
+    Ok(TradeQuote {
+        share_type: side.as_str().to_string(),
+        target_prob,
+        stake,
+        shares,
+        cost,
+        avg_price,
+        prob_before,
+        prob_after,
+        reaches_target: match side {
+            Side::Yes => prob_after >= target_prob,
+            Side::No => prob_after <= target_prob,
+        },
+        slippage: avg_price / price_before - 1.0,
+        payout_if_correct: shares,
+    })
+}
+
+pub const DEFAULT_DEPTH_STEP: f64 = 0.05;
+pub const MIN_DEPTH_STEP: f64 = 0.01;
+
+/// Shares bought and RP spent moving `market` to `target_prob`, from the
+/// LMSR cost function: reaching `p` takes the side being bought to
+/// `b * logit(p)` away from the other.
+pub fn move_to(market: &Market, target_prob: f64) -> (f64, f64) {
+    let gap = market.b * (target_prob / (1.0 - target_prob)).ln();
+    let (q_yes, q_no) = if target_prob > market.prob_yes() {
+        (market.q_no + gap, market.q_no)
+    } else {
+        (market.q_yes, market.q_yes - gap)
+    };
+    let shares = (q_yes - market.q_yes) + (q_no - market.q_no);
+    let cost = crate::lmsr_core::cost(q_yes, q_no, market.b) - market.cost();
+    (shares, cost)
+}
+
+/// The cost of moving `market` to every multiple of `step` strictly between
+/// 0 and 1 (see `move_to`).
+pub fn depth(market: &Market, step: f64) -> Result<MarketDepth> {
+    if !(MIN_DEPTH_STEP..=0.5).contains(&step) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("step must be between {} and 0.5", MIN_DEPTH_STEP),
+        ));
+    }
+    let prob = market.prob_yes();
+    let level = |target_prob: f64| -> Result<DepthLevel> {
+        let (shares, cost) = move_to(market, target_prob);
+        let cost_ledger =
+            to_ledger_units(cost).map_err(|e| anyhow!("Depth cost out of range: {}", e))?;
+        let cost = from_ledger_units(cost_ledger);
+        Ok(DepthLevel {
+            target_prob,
+            cost,
+            shares,
+            avg_price: cost / shares,
+        })
+    };
+
+    let targets: Vec<f64> = (1..)
+        .map(|k| (k as f64 * step * 1e9).round() / 1e9)
+        .take_while(|p| *p < 1.0)
+        .collect();
+    let yes = targets
+        .iter()
+        .filter(|p| **p > prob)
+        .map(|p| level(*p))
+        .collect::<Result<Vec<_>>>()?;
+    let no = targets
+        .iter()
+        .rev()
+        .filter(|p| **p < prob)
+        .map(|p| level(*p))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(MarketDepth {
+        prob,
+        liquidity_b: market.b,
+        step,
+        yes,
+        no,
+    })
+}
+
+/// The binary market a `get_market_state` snapshot describes.
+pub fn binary_market_from_state(state: &serde_json::Value) -> Result<Market> {
+    if !state["market_type"]
+        .as_str()
+        .is_some_and(|t| t.eq_ignore_ascii_case("binary"))
+    {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "Quotes are for binary markets; use numeric-quote",
+        ));
+    }
+    let q_value = |key: &str| {
+        state["outcomes"]
+            .as_array()
+            .and_then(|outcomes| outcomes.iter().find(|o| o["outcome_key"] == key))
+            .and_then(|o| o["q_value"].as_f64())
+            .ok_or_else(|| anyhow!("Market state has no {} quantity", key))
+    };
+    let b = state["liquidity_b"]
+        .as_f64()
+        .filter(|b| *b > 0.0 && b.is_finite())
+        .ok_or_else(|| anyhow!("Market state has no liquidity_b"))?;
+    Ok(Market {
+        q_yes: q_value("yes")?,
+        q_no: q_value("no")?,
+        b,
+    })
+}
+
+/// How much of a side a binary sell sells.
+#[derive(Debug, Clone, Copy)]
+enum SellSize {
+    Shares(f64),
+    /// The whole side
+    All,
+    /// As many shares as pay this much, in ledger units
+    Payout(i128),
+}
+
+// Sell shares back to market using lmsr_core directly.
+// With `sell_all` the whole side is closed and `amount` is ignored.
+pub async fn sell_shares(
+    pool: &PgPool,
+    config: &Config,
+    user_id: i32,
+    event_id: i32,
+    share_type: &str,
     amount: f64,
+    sell_all: bool,
+) -> Result<SellResult> {
+    // Parse share_type at API boundary
+    let side = Side::from_str(share_type).map_err(|e| {
+        coded(
+            ErrorCode::InvalidRequest,
+            format!("Invalid share type: {}", e),
+        )
+    })?;
+
+    // Basic validation outside transaction
+    if !sell_all && amount <= 0.0 {
+        return Err(coded(ErrorCode::InvalidRequest, "Amount must be positive"));
+    }
+    let size = if sell_all {
+        SellSize::All
+    } else {
+        SellSize::Shares(amount)
+    };
+
+    with_optimistic_tx!(pool, tx, {
+        lock_user_trades(&mut tx, user_id).await?;
+        sell_shares_transaction(&mut tx, config, user_id, event_id, side, size).await
+    })
+}
+
+/// Sells as many shares of a side as pay `payout` RP at the current price,
+/// solving for the share count under the event lock. The result reports
+/// the shares sold and the payout realized, which falls short of `payout`
+/// by at most a micro-RP (more only when the sale closes the side, which
+/// then sells its dust too).
+pub async fn sell_shares_for_payout(
+    pool: &PgPool,
+    config: &Config,
+    user_id: i32,
+    event_id: i32,
+    share_type: &str,
+    payout: f64,
+) -> Result<SellResult> {
+    let side = Side::from_str(share_type).map_err(|e| {
+        coded(
+            ErrorCode::InvalidRequest,
+            format!("Invalid share type: {}", e),
+        )
+    })?;
+    if !(payout.is_finite() && payout > 0.0) {
+        return Err(coded(ErrorCode::InvalidRequest, "Payout must be positive"));
+    }
+    let payout_ledger =
+        to_ledger_units(payout).map_err(|e| anyhow!("Invalid payout value: {}", e))?;
+
+    with_optimistic_tx!(pool, tx, {
+        lock_user_trades(&mut tx, user_id).await?;
+        sell_shares_transaction(
+            &mut tx,
+            config,
+            user_id,
+            event_id,
+            side,
+            SellSize::Payout(payout_ledger),
+        )
+        .await
+    })
+}
+
+// Internal transaction logic for sell_shares and sell_shares_for_payout
+async fn sell_shares_transaction(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    config: &Config,
+    user_id: i32,
+    event_id: i32,
+    side: Side,
+    size: SellSize,
 ) -> Result<SellResult> {
     // Get current market state FIRST (consistent lock order with buy path)
     let event_row = sqlx::query(
-        "SELECT market_prob, cumulative_stake, liquidity_b, q_yes, q_no, outcome,
-                COALESCE(closing_date <= NOW(), false) AS is_closed
+        "SELECT market_prob, cumulative_stake, liquidity_b, q_yes, q_no, outcome, competition_id,
+                (closed_at IS NOT NULL OR COALESCE(closing_date <= NOW(), false)) AS is_closed
          FROM events
          WHERE id = $1
          FOR UPDATE",
@@ -832,29 +1981,24 @@ async fn sell_shares_transaction(
 
     let outcome: Option<String> = event_row.get("outcome");
     let is_closed: bool = event_row.get("is_closed");
+    let wallet = Wallet::for_market(event_row.get("competition_id"));
     if outcome.is_some() {
-        return Err(anyhow!(ERR_MARKET_RESOLVED));
+        return Err(coded(ErrorCode::MarketResolved, ERR_MARKET_RESOLVED));
     }
     if is_closed {
-        return Err(anyhow!(ERR_MARKET_CLOSED));
+        return Err(coded(ErrorCode::MarketClosed, ERR_MARKET_CLOSED));
     }
+    crate::embargo::ensure_not_embargoed(tx.as_mut(), event_id).await?;
+    crate::market_import::ensure_tradeable(tx.as_mut(), event_id).await?;
 
-    // Check hold period (if enabled in config)
-    if config.market.enable_hold_period {
-        let now = Utc::now();
-        let active_holds: i64 = sqlx::query_scalar(
-            "SELECT COUNT(*) FROM market_updates 
-             WHERE user_id = $1 AND event_id = $2 AND hold_until > $3",
-        )
-        .bind(user_id)
-        .bind(event_id)
-        .bind(now)
-        .fetch_one(tx.as_mut())
-        .await?;
-
-        if active_holds > 0 {
-            return Err(anyhow!("Hold period not expired for recent purchases"));
-        }
+    if active_hold_until(tx.as_mut(), config, user_id, event_id)
+        .await?
+        .is_some()
+    {
+        return Err(coded(
+            ErrorCode::HoldActive,
+            "Hold period not expired for recent purchases",
+        ));
     }
 
     // Then get user shares with side-specific staked amounts (lock user_shares SECOND)
@@ -893,13 +2037,6 @@ async fn sell_shares_transaction(
         Side::No => no_shares,
     };
 
-    if shares_of_type < amount {
-        return Err(anyhow!(
-            "Insufficient {} shares",
-            side.as_str().to_uppercase()
-        ));
-    }
-
     let market_state = DbAdapter::extract_market_state(&event_row)?;
     let liquidity_b = market_state.liquidity_b;
     let q_yes = market_state.q_yes;
@@ -912,6 +2049,40 @@ async fn sell_shares_transaction(
         b: liquidity_b,
     };
 
+    let (amount, sell_all) = match size {
+        SellSize::Shares(amount) => (amount, false),
+        SellSize::All => (0.0, true),
+        SellSize::Payout(payout_ledger) => {
+            let (shares, _) = market
+                .shares_for_payout(side, payout_ledger, shares_of_type)
+                .map_err(|e| {
+                    coded(
+                        ErrorCode::InsufficientShares,
+                        format!(
+                            "Insufficient {} shares for that payout: {}",
+                            side.as_str().to_uppercase(),
+                            e
+                        ),
+                    )
+                })?;
+            (shares, false)
+        }
+    };
+
+    // Close the side outright on sell_all, or when the sale would leave (or
+    // overshoot the holding by) no more than dust; a float "sell everything"
+    // otherwise strands ~1e-9 shares and a sliver of staked ledger.
+    let closes_side =
+        sell_all || (shares_of_type - amount).abs() <= config.market.share_dust_epsilon;
+    if (!closes_side && shares_of_type < amount) || (closes_side && shares_of_type <= 0.0) {
+        return Err(coded(
+            ErrorCode::InsufficientShares,
+            format!("Insufficient {} shares", side.as_str().to_uppercase()),
+        ));
+    }
+    let amount = if closes_side { shares_of_type } else { amount };
+
+    let prev_prob = market.prob_yes();
     let payout_ledger = match side {
         Side::Yes => market
             .sell_yes(amount)
@@ -936,6 +2107,7 @@ async fn sell_shares_transaction(
         market.q_no,
     )
     .await?;
+    crate::price_history::record(tx.as_mut(), event_id, prev_prob, new_prob).await?;
 
     // Calculate side-specific stake to unwind directly in ledger units (single rounding boundary)
     let stake_of_side_ledger = match side {
@@ -943,60 +2115,143 @@ async fn sell_shares_transaction(
         Side::No => staked_no_ledger,
     };
 
-    let stake_to_unwind_ledger = if shares_of_type > 0.0 && stake_of_side_ledger > 0 {
-        // Pure integer arithmetic for proportional calculation (eliminates double rounding)
-        let amount_ledger =
-            to_ledger_units(amount).map_err(|e| anyhow!("Invalid sell amount: {}", e))?;
-        let shares_ledger =
-            to_ledger_units(shares_of_type).map_err(|e| anyhow!("Invalid shares amount: {}", e))?;
-
-        // Ensure shares_ledger is not zero to prevent division by zero
-        if shares_ledger == 0 {
-            return Err(anyhow!(
-                "Cannot calculate proportional stake for zero shares"
-            ));
-        }
-
-        // Pure integer proportional calculation with round-to-nearest: (stake * amount) / shares
-        // Safe arithmetic with overflow protection
-        let stake_of_side_i128 = stake_of_side_ledger as i128;
-        let amount_i128 = amount_ledger as i128;
-
-        let numer = stake_of_side_i128
-            .checked_mul(amount_i128)
-            .ok_or_else(|| anyhow!("Arithmetic overflow in proportional stake calculation"))?;
-        let stake_to_unwind = (numer + (shares_ledger / 2)) / shares_ledger; // Round to nearest
-        let clamped = stake_to_unwind.max(0).min(stake_of_side_i128);
-        i64::try_from(clamped).map_err(|_| anyhow!("stake_to_unwind_ledger out of i64 range"))?
-    } else {
-        0
-    };
+    let stake_to_unwind_ledger =
+        stake_to_unwind(amount, shares_of_type, stake_of_side_ledger, closes_side)?;
 
     // Update user balance using ledger-native method (single rounding boundary)
     let payout_ledger_i64 =
         i64::try_from(payout_ledger).map_err(|_| anyhow!("payout_ledger out of i64 range"))?;
     let stake_delta_ledger = -stake_to_unwind_ledger;
-    DbAdapter::update_user_balance_ledger(tx, user_id, payout_ledger_i64, stake_delta_ledger)
-        .await?;
-
-    // Update user shares using side-specific stake unwinding
-    DbAdapter::update_user_shares_with_side_unwind_ledger(
+    DbAdapter::update_wallet_balance_ledger(
+        tx,
+        wallet,
+        user_id,
+        payout_ledger_i64,
+        stake_delta_ledger,
+    )
+    .await?;
+    crate::realized_pnl::record(
         tx,
         user_id,
         event_id,
-        side,
-        -amount,                // Negative to subtract shares
-        stake_to_unwind_ledger, // Positive amount to unwind from side-specific stake
+        payout_ledger_i64 - stake_to_unwind_ledger,
+    )
+    .await?;
+    crate::system_accounts::book_house(
+        tx.as_mut(),
+        wallet,
+        "sell",
+        event_id,
+        user_id,
+        payout_ledger_i64 - stake_to_unwind_ledger,
     )
     .await?;
 
+    if closes_side {
+        // Zero the side exactly rather than trusting shares - shares to cancel
+        DbAdapter::close_user_shares_side(tx, user_id, event_id, side).await?;
+    } else {
+        // Update user shares using side-specific stake unwinding
+        DbAdapter::update_user_shares_with_side_unwind_ledger(
+            tx,
+            user_id,
+            event_id,
+            side,
+            -amount,                // Negative to subtract shares
+            stake_to_unwind_ledger, // Positive amount to unwind from side-specific stake
+        )
+        .await?;
+    }
+
     Ok(SellResult {
         payout,
+        shares_sold: amount,
         new_prob,
         current_cost_c: new_cumulative_cost,
     })
 }
 
+/// POST /events/:id/close — sells every YES and NO share the user holds on
+/// a binary market in one transaction, releasing the stake of both sides.
+/// Either both sides close or neither does.
+pub async fn close_position(
+    pool: &PgPool,
+    config: &Config,
+    user_id: i32,
+    event_id: i32,
+) -> Result<ClosePositionResult> {
+    with_optimistic_tx!(pool, tx, {
+        lock_user_trades(&mut tx, user_id).await?;
+        close_position_transaction(&mut tx, config, user_id, event_id).await
+    })
+}
+
+async fn close_position_transaction(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    config: &Config,
+    user_id: i32,
+    event_id: i32,
+) -> Result<ClosePositionResult> {
+    // Same lock order as a sell: the event, then the position
+    sqlx::query("SELECT id FROM events WHERE id = $1 FOR UPDATE")
+        .bind(event_id)
+        .fetch_optional(tx.as_mut())
+        .await?
+        .ok_or_else(|| coded(ErrorCode::NotFound, "Event not found"))?;
+    let position = sqlx::query(
+        "SELECT yes_shares, no_shares, staked_yes_ledger, staked_no_ledger
+         FROM user_shares
+         WHERE user_id = $1 AND event_id = $2
+         FOR UPDATE",
+    )
+    .bind(user_id)
+    .bind(event_id)
+    .fetch_optional(tx.as_mut())
+    .await?;
+    let (yes_shares, no_shares, staked_ledger): (f64, f64, i64) = match position {
+        Some(r) => (
+            r.get("yes_shares"),
+            r.get("no_shares"),
+            r.get::<i64, _>("staked_yes_ledger") + r.get::<i64, _>("staked_no_ledger"),
+        ),
+        None => (0.0, 0.0, 0),
+    };
+    if yes_shares <= 0.0 && no_shares <= 0.0 {
+        return Err(coded(
+            ErrorCode::InsufficientShares,
+            "Insufficient shares: no open position to close",
+        ));
+    }
+
+    let mut result = ClosePositionResult {
+        yes_shares_sold: 0.0,
+        no_shares_sold: 0.0,
+        payout: 0.0,
+        realized_pnl: 0.0,
+        new_prob: 0.0,
+        current_cost_c: 0.0,
+    };
+    let mut payout_ledger: i128 = 0;
+    for (side, shares) in [(Side::Yes, yes_shares), (Side::No, no_shares)] {
+        if shares <= 0.0 {
+            continue;
+        }
+        let sold =
+            sell_shares_transaction(tx, config, user_id, event_id, side, SellSize::All).await?;
+        match side {
+            Side::Yes => result.yes_shares_sold = sold.shares_sold,
+            Side::No => result.no_shares_sold = sold.shares_sold,
+        }
+        payout_ledger +=
+            to_ledger_units(sold.payout).map_err(|e| anyhow!("Invalid payout: {}", e))?;
+        result.new_prob = sold.new_prob;
+        result.current_cost_c = sold.current_cost_c;
+    }
+    result.payout = from_ledger_units(payout_ledger);
+    result.realized_pnl = from_ledger_units(payout_ledger - staked_ledger as i128);
+    Ok(result)
+}
+
 // Sell shares of one outcome back into an N-outcome market.
 pub async fn sell_outcome_shares(
     pool: &PgPool,
@@ -1007,13 +2262,17 @@ pub async fn sell_outcome_shares(
     amount: f64,
 ) -> Result<OutcomeSellResult> {
     if outcome_id <= 0 {
-        return Err(anyhow!("outcome_id must be positive"));
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "outcome_id must be positive",
+        ));
     }
     if !amount.is_finite() || amount <= 0.0 {
-        return Err(anyhow!("Amount must be positive"));
+        return Err(coded(ErrorCode::InvalidRequest, "Amount must be positive"));
     }
 
     with_optimistic_tx!(pool, tx, {
+        lock_user_trades(&mut tx, user_id).await?;
         sell_outcome_shares_transaction(&mut tx, config, user_id, event_id, outcome_id, amount)
             .await
     })
@@ -1036,28 +2295,40 @@ async fn sell_outcome_shares_transaction(
             q_yes,
             q_no,
             outcome,
-            COALESCE(closing_date <= NOW(), false) AS is_closed
+            competition_id,
+            (closed_at IS NOT NULL OR COALESCE(closing_date <= NOW(), false)) AS is_closed
         FROM events
         WHERE id = $1
         FOR UPDATE
         "#,
     )
     .bind(event_id)
-    .fetch_one(tx.as_mut())
-    .await
-    .map_err(|_| anyhow!("Event not found or market not initialized"))?;
+    .fetch_optional(tx.as_mut())
+    .await?
+    .ok_or_else(|| {
+        coded(
+            ErrorCode::NotFound,
+            "Event not found or market not initialized",
+        )
+    })?;
 
     let event_type: String = event_row.get("event_type");
     let outcome: Option<String> = event_row.get("outcome");
     let is_closed: bool = event_row.get("is_closed");
+    let wallet = Wallet::for_market(event_row.get("competition_id"));
     if outcome.is_some() {
-        return Err(anyhow!(ERR_MARKET_RESOLVED));
+        return Err(coded(ErrorCode::MarketResolved, ERR_MARKET_RESOLVED));
     }
     if is_closed {
-        return Err(anyhow!(ERR_MARKET_CLOSED));
+        return Err(coded(ErrorCode::MarketClosed, ERR_MARKET_CLOSED));
     }
+    crate::embargo::ensure_not_embargoed(tx.as_mut(), event_id).await?;
+    crate::market_import::ensure_tradeable(tx.as_mut(), event_id).await?;
     if event_type == "binary" {
-        return Err(anyhow!("Use legacy binary sell endpoint for binary markets"));
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "Use legacy binary sell endpoint for binary markets",
+        ));
     }
     ensure_not_numeric_market(tx, event_id).await?;
 
@@ -1072,7 +2343,10 @@ async fn sell_outcome_shares_transaction(
         .fetch_one(tx.as_mut())
         .await?;
         if active_holds > 0 {
-            return Err(anyhow!("Hold period not expired for recent purchases"));
+            return Err(coded(
+                ErrorCode::HoldActive,
+                "Hold period not expired for recent purchases",
+            ));
         }
     }
 
@@ -1094,20 +2368,29 @@ async fn sell_outcome_shares_transaction(
         None => (0.0, 0),
     };
     if held_shares < amount {
-        return Err(anyhow!("Insufficient shares in selected outcome"));
+        return Err(coded(
+            ErrorCode::InsufficientShares,
+            "Insufficient shares in selected outcome",
+        ));
     }
 
     let liquidity_b: f64 = event_row.get("liquidity_b");
     let mut outcomes = fetch_outcome_state_rows(tx, event_id).await?;
     if outcomes.len() < 2 {
-        return Err(anyhow!(
-            "This market has no configured outcomes yet. Configure outcomes first."
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "This market has no configured outcomes yet. Configure outcomes first.",
         ));
     }
     let selected_idx = outcomes
         .iter()
         .position(|o| o.outcome_id == outcome_id)
-        .ok_or_else(|| anyhow!("Selected outcome is not active for this market"))?;
+        .ok_or_else(|| {
+            coded(
+                ErrorCode::InvalidRequest,
+                "Selected outcome is not active for this market",
+            )
+        })?;
 
     let q: Vec<f64> = outcomes.iter().map(|o| o.q_value).collect();
     let mut market = MultiMarket::new(q, liquidity_b)?;
@@ -1197,8 +2480,9 @@ async fn sell_outcome_shares_transaction(
     .await?;
 
     // Credit payout, unwind staked total (balance += payout, staked -= unwind).
-    let rows = DbAdapter::update_user_balance_ledger(
+    let rows = DbAdapter::update_wallet_balance_ledger(
         tx,
+        wallet,
         user_id,
         payout_ledger_i64,
         -stake_to_unwind_ledger,
@@ -1207,11 +2491,28 @@ async fn sell_outcome_shares_transaction(
     if rows == 0 {
         return Err(anyhow!("Failed to update user balance"));
     }
+    crate::realized_pnl::record(
+        tx,
+        user_id,
+        event_id,
+        payout_ledger_i64 - stake_to_unwind_ledger,
+    )
+    .await?;
+    crate::system_accounts::book_house(
+        tx.as_mut(),
+        wallet,
+        "sell",
+        event_id,
+        user_id,
+        payout_ledger_i64 - stake_to_unwind_ledger,
+    )
+    .await?;
 
     sqlx::query(
         "UPDATE user_outcome_shares
          SET shares = shares - $4,
              staked_ledger = staked_ledger - $5,
+             realized_pnl_ledger = realized_pnl_ledger + $6,
              version = version + 1,
              updated_at = NOW()
          WHERE user_id = $1 AND event_id = $2 AND outcome_id = $3",
@@ -1221,6 +2522,7 @@ async fn sell_outcome_shares_transaction(
     .bind(outcome_id)
     .bind(amount)
     .bind(stake_to_unwind_ledger)
+    .bind(payout_ledger_i64 - stake_to_unwind_ledger)
     .execute(tx.as_mut())
     .await?;
 
@@ -1262,10 +2564,27 @@ async fn sell_outcome_shares_transaction(
 //     recomputed fresh (same alpha-solve on fresh p) and that ledger-rounded
 //     figure is the only number used for the debit / distribution_trades
 //     row / cumulative_stake delta.
-//  6. The 40*b log-odds span clamp surfaces as a plain-English 400 at the
-//     endpoint layer (see `numeric_error_response` in main.rs).
+//  6. The 40*b log-odds span clamp surfaces as a plain-English 400 (see
+//     `solve_numeric_budget`).
 // ---------------------------------------------------------------------
 
+/// `solve_alpha_for_budget`, with a target too far from the market for the
+/// log-odds span clamp (mandate 6) refused as a bad request.
+fn solve_numeric_budget(
+    p: &[f64],
+    target: &[f64],
+    b: f64,
+    budget_ledger: i64,
+) -> Result<(f64, i64, Vec<f64>)> {
+    crate::lmsr_multi_core::target_deltas(p, target, b).map_err(|_| {
+        coded(
+            ErrorCode::InvalidRequest,
+            "Market too extreme or target too concentrated for the current liquidity; reduce the requested move",
+        )
+    })?;
+    crate::lmsr_multi_core::solve_alpha_for_budget(p, target, b, budget_ledger)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ts_rs::TS)]
 #[ts(export, export_to = "../../shared/types/NumericQuoteResult.ts")]
 pub struct NumericQuoteResult {
@@ -1319,6 +2638,7 @@ struct NumericMarketRow {
     numeric_market_version: i64,
     is_resolved: bool,
     is_closed: bool,
+    wallet: Wallet,
     open_lower_bound: bool,
     open_upper_bound: bool,
 }
@@ -1340,7 +2660,8 @@ const NUMERIC_MARKET_ROW_QUERY: &str = r#"
         c.open_lower_bound,
         c.open_upper_bound,
         (e.outcome IS NOT NULL) AS is_resolved,
-        COALESCE(e.closing_date <= NOW(), false) AS is_closed
+        (e.closed_at IS NOT NULL OR COALESCE(e.closing_date <= NOW(), false)) AS is_closed,
+        e.competition_id
     FROM numeric_market_config c
     JOIN events e ON e.id = c.event_id
     WHERE c.event_id = $1
@@ -1353,6 +2674,7 @@ fn row_to_numeric_market(row: sqlx::postgres::PgRow) -> NumericMarketRow {
         numeric_market_version: row.get("numeric_market_version"),
         is_resolved: row.get("is_resolved"),
         is_closed: row.get("is_closed"),
+        wallet: Wallet::for_market(row.get("competition_id")),
         open_lower_bound: row.get("open_lower_bound"),
         open_upper_bound: row.get("open_upper_bound"),
     }
@@ -1365,7 +2687,12 @@ async fn fetch_numeric_market_row_pool(pool: &PgPool, event_id: i32) -> Result<N
         .bind(event_id)
         .fetch_optional(pool)
         .await?
-        .ok_or_else(|| anyhow!("No numeric market configured for this event"))?;
+        .ok_or_else(|| {
+            coded(
+                ErrorCode::NotFound,
+                "No numeric market configured for this event",
+            )
+        })?;
     Ok(row_to_numeric_market(row))
 }
 
@@ -1382,7 +2709,12 @@ async fn fetch_numeric_market_row_locked(
         .bind(event_id)
         .fetch_optional(tx.as_mut())
         .await?
-        .ok_or_else(|| anyhow!("No numeric market configured for this event"))?;
+        .ok_or_else(|| {
+            coded(
+                ErrorCode::NotFound,
+                "No numeric market configured for this event",
+            )
+        })?;
     Ok(row_to_numeric_market(row))
 }
 
@@ -1410,21 +2742,33 @@ async fn fetch_outcome_q_values_pool(pool: &PgPool, event_id: i32) -> Result<Vec
 /// floor-and-renormalize to repair bad input.
 fn validate_target(target: &[f64], outcome_count: usize) -> Result<()> {
     if target.len() != outcome_count {
-        return Err(anyhow!(
-            "target must have exactly {} entries, got {}",
-            outcome_count,
-            target.len()
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!(
+                "target must have exactly {} entries, got {}",
+                outcome_count,
+                target.len()
+            ),
         ));
     }
     if target.iter().any(|v| !v.is_finite()) {
-        return Err(anyhow!("target entries must all be finite"));
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "target entries must all be finite",
+        ));
     }
     if target.iter().any(|v| *v < 0.0) {
-        return Err(anyhow!("target entries must all be >= 0"));
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "target entries must all be >= 0",
+        ));
     }
     let sum: f64 = target.iter().sum();
     if !(sum > 0.0) {
-        return Err(anyhow!("target must sum to a positive value"));
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "target must sum to a positive value",
+        ));
     }
     Ok(())
 }
@@ -1438,32 +2782,38 @@ pub async fn get_numeric_quote(
     target: Vec<f64>,
 ) -> Result<NumericQuoteResult> {
     if budget_ledger <= 0 {
-        return Err(anyhow!("budget_ledger must be positive"));
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "budget_ledger must be positive",
+        ));
     }
 
     let market = fetch_numeric_market_row_pool(pool, event_id).await?;
     if market.is_resolved {
-        return Err(anyhow!(ERR_MARKET_RESOLVED));
+        return Err(coded(ErrorCode::MarketResolved, ERR_MARKET_RESOLVED));
     }
     if market.is_closed {
-        return Err(anyhow!(ERR_MARKET_CLOSED));
+        return Err(coded(ErrorCode::MarketClosed, ERR_MARKET_CLOSED));
     }
     let outcome_count = market.expected_outcome_count();
     validate_target(&target, outcome_count)?;
 
     let q = fetch_outcome_q_values_pool(pool, event_id).await?;
     if q.len() != outcome_count {
-        return Err(anyhow!(
-            "Numeric market outcome count ({}) does not match configured outcome count ({})",
-            q.len(),
-            outcome_count
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!(
+                "Numeric market outcome count ({}) does not match configured outcome count ({})",
+                q.len(),
+                outcome_count
+            ),
         ));
     }
 
     // Mandate 4: p is always derived fresh from q, never a stored prob float.
     let p = crate::lmsr_multi_core::probabilities(&q, market.b_numeric);
     let (alpha, cost_ledger, delta_q) =
-        crate::lmsr_multi_core::solve_alpha_for_budget(&p, &target, market.b_numeric, budget_ledger)?;
+        solve_numeric_budget(&p, &target, market.b_numeric, budget_ledger)?;
 
     let q_after: Vec<f64> = q.iter().zip(delta_q.iter()).map(|(qi, di)| qi + di).collect();
     let post_distribution = crate::lmsr_multi_core::probabilities(&q_after, market.b_numeric);
@@ -1488,13 +2838,20 @@ pub async fn numeric_trade(
     market_version: i64,
 ) -> Result<NumericTradeOutcome> {
     if budget_ledger <= 0 {
-        return Err(anyhow!("budget_ledger must be positive"));
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "budget_ledger must be positive",
+        ));
     }
     if max_cost_ledger <= 0 {
-        return Err(anyhow!("max_cost_ledger must be positive"));
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "max_cost_ledger must be positive",
+        ));
     }
 
     with_optimistic_tx!(pool, tx, {
+        lock_user_trades(&mut tx, user_id).await?;
         numeric_trade_transaction(
             &mut tx,
             user_id,
@@ -1519,21 +2876,26 @@ async fn numeric_trade_transaction(
 ) -> Result<NumericTradeOutcome> {
     let market = fetch_numeric_market_row_locked(tx, event_id).await?;
     if market.is_resolved {
-        return Err(anyhow!(ERR_MARKET_RESOLVED));
+        return Err(coded(ErrorCode::MarketResolved, ERR_MARKET_RESOLVED));
     }
     if market.is_closed {
-        return Err(anyhow!(ERR_MARKET_CLOSED));
+        return Err(coded(ErrorCode::MarketClosed, ERR_MARKET_CLOSED));
     }
+    crate::embargo::ensure_not_embargoed(tx.as_mut(), event_id).await?;
+    crate::market_import::ensure_tradeable(tx.as_mut(), event_id).await?;
 
     let outcome_count = market.expected_outcome_count();
     validate_target(target, outcome_count)?;
 
     let outcomes = fetch_outcome_state_rows(tx, event_id).await?;
     if outcomes.len() != outcome_count {
-        return Err(anyhow!(
-            "Numeric market outcome count ({}) does not match configured outcome count ({})",
-            outcomes.len(),
-            outcome_count
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!(
+                "Numeric market outcome count ({}) does not match configured outcome count ({})",
+                outcomes.len(),
+                outcome_count
+            ),
         ));
     }
 
@@ -1543,7 +2905,7 @@ async fn numeric_trade_transaction(
 
     // Mandate 5: the one authoritative quote, recomputed fresh under the lock.
     let (alpha, cost_ledger, delta_q) =
-        crate::lmsr_multi_core::solve_alpha_for_budget(&p, target, market.b_numeric, budget_ledger)?;
+        solve_numeric_budget(&p, target, market.b_numeric, budget_ledger)?;
 
     let q_after: Vec<f64> = q.iter().zip(delta_q.iter()).map(|(qi, di)| qi + di).collect();
     let post_distribution = crate::lmsr_multi_core::probabilities(&q_after, market.b_numeric);
@@ -1565,14 +2927,16 @@ async fn numeric_trade_transaction(
     // Mandate 1: a trade whose ledger-rounded cost is 0 would mint shares
     // for free — refuse rather than execute it.
     if cost_ledger == 0 {
-        return Err(anyhow!(
-            "Trade cost rounds to zero ledger units (minimum effective stake is 1 ledger unit); increase budget_ledger"
-        ));
+        return Err(coded(ErrorCode::InvalidRequest, "Trade cost rounds to zero ledger units (minimum effective stake is 1 ledger unit); increase budget_ledger"));
     }
 
-    let has_sufficient_funds = DbAdapter::deduct_user_cost_ledger(tx, user_id, cost_ledger).await?;
+    let has_sufficient_funds =
+        DbAdapter::deduct_wallet_cost_ledger(tx, market.wallet, user_id, cost_ledger).await?;
     if !has_sufficient_funds {
-        return Err(anyhow!("Insufficient RP balance"));
+        return Err(coded(
+            ErrorCode::InsufficientBalance,
+            "Insufficient RP balance",
+        ));
     }
 
     // Track the user's own cost basis for this event, independent of any
@@ -1707,6 +3071,7 @@ pub async fn numeric_sell(
     market_version: i64,
 ) -> Result<NumericSellOutcome> {
     with_optimistic_tx!(pool, tx, {
+        lock_user_trades(&mut tx, user_id).await?;
         numeric_sell_transaction(&mut tx, user_id, event_id, market_version).await
     })
 }
@@ -1719,11 +3084,13 @@ async fn numeric_sell_transaction(
 ) -> Result<NumericSellOutcome> {
     let market = fetch_numeric_market_row_locked(tx, event_id).await?;
     if market.is_resolved {
-        return Err(anyhow!(ERR_MARKET_RESOLVED));
+        return Err(coded(ErrorCode::MarketResolved, ERR_MARKET_RESOLVED));
     }
     if market.is_closed {
-        return Err(anyhow!(ERR_MARKET_CLOSED));
+        return Err(coded(ErrorCode::MarketClosed, ERR_MARKET_CLOSED));
     }
+    crate::embargo::ensure_not_embargoed(tx.as_mut(), event_id).await?;
+    crate::market_import::ensure_tradeable(tx.as_mut(), event_id).await?;
     if market_version != market.numeric_market_version {
         return Ok(NumericSellOutcome::StaleVersion {
             market_version: market.numeric_market_version,
@@ -1746,10 +3113,13 @@ async fn numeric_sell_transaction(
     let outcome_count = market.expected_outcome_count();
     let outcomes = fetch_outcome_state_rows(tx, event_id).await?;
     if outcomes.len() != outcome_count {
-        return Err(anyhow!(
-            "Numeric market outcome count ({}) does not match configured outcome count ({})",
-            outcomes.len(),
-            outcome_count
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!(
+                "Numeric market outcome count ({}) does not match configured outcome count ({})",
+                outcomes.len(),
+                outcome_count
+            ),
         ));
     }
 
@@ -1769,7 +3139,10 @@ async fn numeric_sell_transaction(
 
     let total_shares: f64 = holdings.iter().sum();
     if total_shares <= 0.0 {
-        return Err(anyhow!("No numeric position to sell for this event"));
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "No numeric position to sell for this event",
+        ));
     }
 
     let q: Vec<f64> = outcomes.iter().map(|o| o.q_value).collect();
@@ -1860,12 +3233,28 @@ async fn numeric_sell_transaction(
         }
     };
 
-    let rows =
-        DbAdapter::update_user_balance_ledger(tx, user_id, payout_ledger, -unstake_ledger).await?;
-    if rows == 0 {
-        return Err(anyhow!("Failed to update user balance"));
-    }
-
+    let rows = DbAdapter::update_wallet_balance_ledger(
+        tx,
+        market.wallet,
+        user_id,
+        payout_ledger,
+        -unstake_ledger,
+    )
+    .await?;
+    if rows == 0 {
+        return Err(anyhow!("Failed to update user balance"));
+    }
+    crate::realized_pnl::record(tx, user_id, event_id, payout_ledger - unstake_ledger).await?;
+    crate::system_accounts::book_house(
+        tx.as_mut(),
+        market.wallet,
+        "sell",
+        event_id,
+        user_id,
+        payout_ledger - unstake_ledger,
+    )
+    .await?;
+
     sqlx::query(
         "UPDATE numeric_position_basis SET basis_ledger = 0, updated_at = NOW()
          WHERE user_id = $1 AND event_id = $2",
@@ -1931,6 +3320,648 @@ async fn numeric_sell_transaction(
     }))
 }
 
+// ---------------------------------------------------------------------
+// Per-bucket trading on numeric markets.
+//
+// The distribution endpoints above move the whole q vector toward a target;
+// these buy or sell shares of a single bucket against the same b_numeric
+// LMSR, for traders who just want "CPI lands in 3.0–3.1". They keep the same
+// money-path rules: p comes from the q vector read under the row lock, the
+// caller's market_version must match, and staked money lives in
+// numeric_position_basis.
+// ---------------------------------------------------------------------
+
+#[derive(Debug, Clone, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export, export_to = "../../shared/types/NumericBucketTradeResult.ts")]
+pub struct NumericBucketTradeResult {
+    pub event_id: i32,
+    pub trade_id: i64,
+    pub outcome_id: i64,
+    pub shares: f64,
+    pub cost_ledger: i64,
+    pub market_version: i64,
+    pub post_distribution: Vec<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ts_rs::TS)]
+#[ts(export, export_to = "../../shared/types/NumericBucketSellResult.ts")]
+pub struct NumericBucketSellResult {
+    pub event_id: i32,
+    pub trade_id: i64,
+    pub outcome_id: i64,
+    pub shares_sold: f64,
+    pub payout_ledger: i64,
+    pub market_version: i64,
+    pub post_distribution: Vec<f64>,
+}
+
+pub enum NumericBucketOutcome<T> {
+    Executed(T),
+    StaleVersion { market_version: i64 },
+}
+
+/// Locks the numeric market and runs the guards every bucket trade shares,
+/// returning the market row and its outcomes in q-vector order.
+async fn lock_numeric_market_for_bucket_trade(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    event_id: i32,
+) -> Result<(NumericMarketRow, Vec<OutcomeStateRow>)> {
+    let market = fetch_numeric_market_row_locked(tx, event_id).await?;
+    if market.is_resolved {
+        return Err(coded(ErrorCode::MarketResolved, ERR_MARKET_RESOLVED));
+    }
+    if market.is_closed {
+        return Err(coded(ErrorCode::MarketClosed, ERR_MARKET_CLOSED));
+    }
+    crate::embargo::ensure_not_embargoed(tx.as_mut(), event_id).await?;
+    crate::market_import::ensure_tradeable(tx.as_mut(), event_id).await?;
+
+    let outcome_count = market.expected_outcome_count();
+    let outcomes = fetch_outcome_state_rows(tx, event_id).await?;
+    if outcomes.len() != outcome_count {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!(
+                "Numeric market outcome count ({}) does not match configured outcome count ({})",
+                outcomes.len(),
+                outcome_count
+            ),
+        ));
+    }
+    Ok((market, outcomes))
+}
+
+/// Writes the post-trade q vector and bumps the market version; returns the
+/// post-trade distribution and the new version.
+async fn write_numeric_bucket_state(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    event_id: i32,
+    market: &NumericMarketRow,
+    outcomes: &[OutcomeStateRow],
+    q_after: &[f64],
+) -> Result<(Vec<f64>, i64)> {
+    let outcome_ids: Vec<i64> = outcomes.iter().map(|o| o.outcome_id).collect();
+    let post_distribution = crate::lmsr_multi_core::probabilities(q_after, market.b_numeric);
+    sqlx::query(
+        r#"
+        INSERT INTO event_outcome_states (event_id, outcome_id, q_value, prob, updated_at)
+        SELECT $1, t.outcome_id, t.q_value, t.prob, NOW()
+        FROM UNNEST($2::bigint[], $3::double precision[], $4::double precision[])
+            AS t(outcome_id, q_value, prob)
+        ON CONFLICT (event_id, outcome_id) DO UPDATE SET
+            q_value = EXCLUDED.q_value,
+            prob = EXCLUDED.prob,
+            updated_at = NOW()
+        "#,
+    )
+    .bind(event_id)
+    .bind(&outcome_ids)
+    .bind(q_after)
+    .bind(&post_distribution)
+    .execute(tx.as_mut())
+    .await?;
+
+    let new_market_version = market.numeric_market_version + 1;
+    sqlx::query("UPDATE numeric_market_config SET numeric_market_version = $1 WHERE event_id = $2")
+        .bind(new_market_version)
+        .bind(event_id)
+        .execute(tx.as_mut())
+        .await?;
+    Ok((post_distribution, new_market_version))
+}
+
+/// Records a single-leg distribution_trades row for a bucket trade.
+async fn record_numeric_bucket_trade(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: i32,
+    event_id: i32,
+    outcome_id: i64,
+    total_cost_ledger: i64,
+    shares_delta: f64,
+    (pre_market_version, post_market_version): (i64, i64),
+) -> Result<i64> {
+    let trade_id: i64 = sqlx::query_scalar(
+        r#"
+        INSERT INTO distribution_trades
+            (user_id, event_id, total_cost_ledger, alpha, target_distribution,
+             pre_market_version, post_market_version, hold_until)
+        VALUES
+            ($1, $2, $3, NULL, NULL, $4, $5, NULL)
+        RETURNING id
+        "#,
+    )
+    .bind(user_id)
+    .bind(event_id)
+    .bind(total_cost_ledger)
+    .bind(pre_market_version)
+    .bind(post_market_version)
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO distribution_trade_legs (trade_id, outcome_id, shares_delta) VALUES ($1, $2, $3)",
+    )
+    .bind(trade_id)
+    .bind(outcome_id)
+    .bind(shares_delta)
+    .execute(tx.as_mut())
+    .await?;
+    Ok(trade_id)
+}
+
+fn bucket_index(outcomes: &[OutcomeStateRow], outcome_id: i64) -> Result<usize> {
+    outcomes
+        .iter()
+        .position(|o| o.outcome_id == outcome_id)
+        .ok_or_else(|| {
+            coded(
+                ErrorCode::InvalidRequest,
+                format!(
+                    "Bucket {} is not an active outcome of this market",
+                    outcome_id
+                ),
+            )
+        })
+}
+
+/// POST /events/:id/numeric-bucket-buy — spend `stake_ledger` on one bucket.
+pub async fn numeric_bucket_buy(
+    pool: &PgPool,
+    user_id: i32,
+    event_id: i32,
+    outcome_id: i64,
+    stake_ledger: i64,
+    market_version: i64,
+) -> Result<NumericBucketOutcome<NumericBucketTradeResult>> {
+    if stake_ledger <= 0 {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "stake_ledger must be positive",
+        ));
+    }
+    with_optimistic_tx!(pool, tx, {
+        lock_user_trades(&mut tx, user_id).await?;
+        numeric_bucket_buy_transaction(
+            &mut tx,
+            user_id,
+            event_id,
+            outcome_id,
+            stake_ledger,
+            market_version,
+        )
+        .await
+    })
+}
+
+async fn numeric_bucket_buy_transaction(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: i32,
+    event_id: i32,
+    outcome_id: i64,
+    stake_ledger: i64,
+    market_version: i64,
+) -> Result<NumericBucketOutcome<NumericBucketTradeResult>> {
+    let (market, outcomes) = lock_numeric_market_for_bucket_trade(tx, event_id).await?;
+    if market_version != market.numeric_market_version {
+        return Ok(NumericBucketOutcome::StaleVersion {
+            market_version: market.numeric_market_version,
+        });
+    }
+    let idx = bucket_index(&outcomes, outcome_id)?;
+
+    let q: Vec<f64> = outcomes.iter().map(|o| o.q_value).collect();
+    let stake = crate::lmsr_core::from_ledger_units(stake_ledger as i128);
+    // The LMSR cost of buying `shares` of one bucket is exactly the stake, so
+    // the ledger debit is the caller's stake_ledger — no second rounding.
+    let shares = crate::lmsr_multi_core::delta_q_for_stake(idx, &q, market.b_numeric, stake)?;
+    if !shares.is_finite() || shares <= 0.0 {
+        return Err(anyhow!("Failed to compute bucket shares for this stake"));
+    }
+
+    let has_sufficient_funds =
+        DbAdapter::deduct_wallet_cost_ledger(tx, market.wallet, user_id, stake_ledger).await?;
+    if !has_sufficient_funds {
+        return Err(coded(
+            ErrorCode::InsufficientBalance,
+            "Insufficient RP balance",
+        ));
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO numeric_position_basis (user_id, event_id, basis_ledger, updated_at)
+        VALUES ($1, $2, $3, NOW())
+        ON CONFLICT (user_id, event_id) DO UPDATE SET
+            basis_ledger = numeric_position_basis.basis_ledger + EXCLUDED.basis_ledger,
+            updated_at = NOW()
+        "#,
+    )
+    .bind(user_id)
+    .bind(event_id)
+    .bind(stake_ledger)
+    .execute(tx.as_mut())
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO user_outcome_shares (user_id, event_id, outcome_id, shares, staked_ledger, version, updated_at)
+        VALUES ($1, $2, $3, $4, 0, 1, NOW())
+        ON CONFLICT (user_id, event_id, outcome_id) DO UPDATE SET
+            shares = user_outcome_shares.shares + EXCLUDED.shares,
+            version = user_outcome_shares.version + 1,
+            updated_at = NOW()
+        "#,
+    )
+    .bind(user_id)
+    .bind(event_id)
+    .bind(outcome_id)
+    .bind(shares)
+    .execute(tx.as_mut())
+    .await?;
+
+    let mut q_after = q;
+    q_after[idx] += shares;
+    let (post_distribution, new_market_version) =
+        write_numeric_bucket_state(tx, event_id, &market, &outcomes, &q_after).await?;
+
+    sqlx::query("UPDATE events SET cumulative_stake = cumulative_stake + $1 WHERE id = $2")
+        .bind(stake)
+        .bind(event_id)
+        .execute(tx.as_mut())
+        .await?;
+
+    let trade_id = record_numeric_bucket_trade(
+        tx,
+        user_id,
+        event_id,
+        outcome_id,
+        stake_ledger,
+        shares,
+        (market.numeric_market_version, new_market_version),
+    )
+    .await?;
+
+    Ok(NumericBucketOutcome::Executed(NumericBucketTradeResult {
+        event_id,
+        trade_id,
+        outcome_id,
+        shares,
+        cost_ledger: stake_ledger,
+        market_version: new_market_version,
+        post_distribution,
+    }))
+}
+
+/// POST /events/:id/numeric-bucket-sell — sell `shares` of one bucket.
+///
+/// The basis released is the position's basis scaled by the share of the
+/// position's current close-out value this sale realizes, so a later sale
+/// of the rest releases what's left; selling the last shares releases it all.
+pub async fn numeric_bucket_sell(
+    pool: &PgPool,
+    user_id: i32,
+    event_id: i32,
+    outcome_id: i64,
+    shares: f64,
+    market_version: i64,
+) -> Result<NumericBucketOutcome<NumericBucketSellResult>> {
+    if !shares.is_finite() || shares <= 0.0 {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "shares must be positive and finite",
+        ));
+    }
+    with_optimistic_tx!(pool, tx, {
+        lock_user_trades(&mut tx, user_id).await?;
+        numeric_bucket_sell_transaction(
+            &mut tx,
+            user_id,
+            event_id,
+            outcome_id,
+            shares,
+            market_version,
+        )
+        .await
+    })
+}
+
+async fn numeric_bucket_sell_transaction(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: i32,
+    event_id: i32,
+    outcome_id: i64,
+    shares: f64,
+    market_version: i64,
+) -> Result<NumericBucketOutcome<NumericBucketSellResult>> {
+    let (market, outcomes) = lock_numeric_market_for_bucket_trade(tx, event_id).await?;
+    if market_version != market.numeric_market_version {
+        return Ok(NumericBucketOutcome::StaleVersion {
+            market_version: market.numeric_market_version,
+        });
+    }
+    let idx = bucket_index(&outcomes, outcome_id)?;
+
+    // Same lock order as numeric_sell: market rows first, then the position.
+    let position_rows = sqlx::query(
+        "SELECT outcome_id, shares
+         FROM user_outcome_shares
+         WHERE user_id = $1 AND event_id = $2
+         FOR UPDATE",
+    )
+    .bind(user_id)
+    .bind(event_id)
+    .fetch_all(tx.as_mut())
+    .await?;
+    let mut holdings = vec![0.0f64; outcomes.len()];
+    for row in &position_rows {
+        let held_id: i64 = row.get("outcome_id");
+        if let Some(i) = outcomes.iter().position(|o| o.outcome_id == held_id) {
+            holdings[i] = row.get("shares");
+        }
+    }
+    if holdings[idx] + 1e-9 < shares {
+        return Err(coded(
+            ErrorCode::InsufficientShares,
+            format!(
+                "Insufficient shares in this bucket: hold {:.6}, selling {:.6}",
+                holdings[idx], shares
+            ),
+        ));
+    }
+    // Selling within float noise of the holding closes the bucket exactly.
+    let shares = shares.min(holdings[idx]);
+    let closes_bucket = holdings[idx] - shares <= 1e-9;
+
+    let q: Vec<f64> = outcomes.iter().map(|o| o.q_value).collect();
+    let payout = crate::lmsr_multi_core::sell_payout(idx, &q, market.b_numeric, shares)?;
+    let payout_ledger = i64::try_from(
+        to_ledger_units(payout.max(0.0))
+            .map_err(|e| anyhow!("Invalid numeric sell payout: {}", e))?,
+    )
+    .map_err(|_| anyhow!("payout_ledger out of i64 range"))?;
+
+    let basis: i64 = sqlx::query_scalar(
+        "SELECT basis_ledger FROM numeric_position_basis
+         WHERE user_id = $1 AND event_id = $2
+         FOR UPDATE",
+    )
+    .bind(user_id)
+    .bind(event_id)
+    .fetch_optional(tx.as_mut())
+    .await?
+    .ok_or_else(|| {
+        anyhow!(
+            "No cost basis recorded for user {} on event {} despite an open numeric position; refusing to guess unstake amount",
+            user_id,
+            event_id
+        )
+    })?;
+    let remaining_elsewhere: f64 = holdings
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != idx)
+        .map(|(_, h)| h.max(0.0))
+        .sum();
+    let unstake_ledger = if closes_bucket && remaining_elsewhere <= 1e-9 {
+        basis
+    } else {
+        let close_out: Vec<f64> = holdings.iter().map(|h| -h.max(0.0)).collect();
+        let position_value =
+            -crate::lmsr_multi_core::apply_vector_cost(&q, &close_out, market.b_numeric);
+        if !position_value.is_finite() || position_value <= 0.0 {
+            return Err(anyhow!("Failed to value the numeric position for this sale"));
+        }
+        let fraction = (payout / position_value).clamp(0.0, 1.0);
+        ((basis as f64) * fraction).round().clamp(0.0, basis as f64) as i64
+    };
+
+    if closes_bucket {
+        sqlx::query(
+            "UPDATE user_outcome_shares
+             SET shares = 0, version = version + 1, updated_at = NOW()
+             WHERE user_id = $1 AND event_id = $2 AND outcome_id = $3",
+        )
+        .bind(user_id)
+        .bind(event_id)
+        .bind(outcome_id)
+        .execute(tx.as_mut())
+        .await?;
+    } else {
+        sqlx::query(
+            "UPDATE user_outcome_shares
+             SET shares = shares - $4, version = version + 1, updated_at = NOW()
+             WHERE user_id = $1 AND event_id = $2 AND outcome_id = $3",
+        )
+        .bind(user_id)
+        .bind(event_id)
+        .bind(outcome_id)
+        .bind(shares)
+        .execute(tx.as_mut())
+        .await?;
+    }
+
+    let rows = DbAdapter::update_wallet_balance_ledger(
+        tx,
+        market.wallet,
+        user_id,
+        payout_ledger,
+        -unstake_ledger,
+    )
+    .await?;
+    if rows == 0 {
+        return Err(anyhow!("Failed to update user balance"));
+    }
+    crate::realized_pnl::record(tx, user_id, event_id, payout_ledger - unstake_ledger).await?;
+    crate::system_accounts::book_house(
+        tx.as_mut(),
+        market.wallet,
+        "sell",
+        event_id,
+        user_id,
+        payout_ledger - unstake_ledger,
+    )
+    .await?;
+
+    sqlx::query(
+        "UPDATE numeric_position_basis SET basis_ledger = basis_ledger - $3, updated_at = NOW()
+         WHERE user_id = $1 AND event_id = $2",
+    )
+    .bind(user_id)
+    .bind(event_id)
+    .bind(unstake_ledger)
+    .execute(tx.as_mut())
+    .await?;
+
+    let mut q_after = q;
+    q_after[idx] -= shares;
+    let (post_distribution, new_market_version) =
+        write_numeric_bucket_state(tx, event_id, &market, &outcomes, &q_after).await?;
+
+    // As in numeric_sell, cumulative_stake gives back the basis released.
+    let released_rp = crate::lmsr_core::from_ledger_units(-(unstake_ledger as i128));
+    sqlx::query("UPDATE events SET cumulative_stake = cumulative_stake + $1 WHERE id = $2")
+        .bind(released_rp)
+        .bind(event_id)
+        .execute(tx.as_mut())
+        .await?;
+
+    let trade_id = record_numeric_bucket_trade(
+        tx,
+        user_id,
+        event_id,
+        outcome_id,
+        -payout_ledger,
+        -shares,
+        (market.numeric_market_version, new_market_version),
+    )
+    .await?;
+
+    Ok(NumericBucketOutcome::Executed(NumericBucketSellResult {
+        event_id,
+        trade_id,
+        outcome_id,
+        shares_sold: shares,
+        payout_ledger,
+        market_version: new_market_version,
+        post_distribution,
+    }))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DistributionBucket {
+    pub outcome_id: i64,
+    pub label: String,
+    pub bucket_kind: String,
+    pub lower_bound: Option<f64>,
+    pub upper_bound: Option<f64>,
+    pub prob: f64,
+    /// Probability per nominal unit; `None` for the open tails.
+    pub density: Option<f64>,
+    /// Probability at or below this bucket's upper bound.
+    pub cumulative: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NumericDistribution {
+    pub event_id: i32,
+    pub market_version: i64,
+    pub range_min: f64,
+    pub range_max: f64,
+    pub zero_point: Option<f64>,
+    pub unit: Option<String>,
+    pub buckets: Vec<DistributionBucket>,
+    pub summary: crate::numeric_transform::DistributionSummary,
+}
+
+/// GET /events/:id/distribution — the market's implied distribution over
+/// the range, with probabilities derived fresh from q as the trade paths do.
+pub async fn get_numeric_distribution(pool: &PgPool, event_id: i32) -> Result<NumericDistribution> {
+    use crate::numeric_transform::{BucketKind, NumericTransform};
+
+    let config = sqlx::query(
+        "SELECT range_min, range_max, zero_point, unit, b_numeric, numeric_market_version
+         FROM numeric_market_config WHERE event_id = $1",
+    )
+    .bind(event_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| {
+        coded(
+            ErrorCode::NotFound,
+            "No numeric market configured for this event",
+        )
+    })?;
+    let transform = NumericTransform {
+        range_min: config.get("range_min"),
+        range_max: config.get("range_max"),
+        zero_point: config.get("zero_point"),
+    };
+    let b_numeric: f64 = config.get("b_numeric");
+
+    let rows = sqlx::query(
+        r#"
+        SELECT eo.id AS outcome_id, eo.label, eo.bucket_kind, eo.lower_bound, eo.upper_bound,
+               COALESCE(eos.q_value, 0.0) AS q_value
+        FROM event_outcomes eo
+        LEFT JOIN event_outcome_states eos
+          ON eos.event_id = eo.event_id AND eos.outcome_id = eo.id
+        WHERE eo.event_id = $1 AND eo.is_active = TRUE
+        ORDER BY eo.sort_order ASC, eo.id ASC
+        "#,
+    )
+    .bind(event_id)
+    .fetch_all(pool)
+    .await?;
+    if rows.is_empty() {
+        return Err(coded(
+            ErrorCode::NotFound,
+            "No numeric market configured for this event",
+        ));
+    }
+    let q: Vec<f64> = rows.iter().map(|r| r.get("q_value")).collect();
+    let probs = crate::lmsr_multi_core::probabilities(&q, b_numeric);
+
+    // Order low to high: lower tail, inbound bins, upper tail.
+    let mut buckets: Vec<(BucketKind, DistributionBucket)> = rows
+        .iter()
+        .zip(probs)
+        .map(|(row, prob)| {
+            let kind: String = row.get("bucket_kind");
+            let lower_bound: Option<f64> = row.get("lower_bound");
+            let upper_bound: Option<f64> = row.get("upper_bound");
+            let density = match (lower_bound, upper_bound) {
+                (Some(lo), Some(hi)) if hi > lo => Some(prob / (hi - lo)),
+                _ => None,
+            };
+            (
+                BucketKind::parse(&kind),
+                DistributionBucket {
+                    outcome_id: row.get("outcome_id"),
+                    label: row.get("label"),
+                    bucket_kind: kind,
+                    lower_bound,
+                    upper_bound,
+                    prob,
+                    density,
+                    cumulative: 0.0,
+                },
+            )
+        })
+        .collect();
+    let rank = |kind: &BucketKind| match kind {
+        BucketKind::LowerTail => 0,
+        BucketKind::Inbound => 1,
+        BucketKind::UpperTail => 2,
+    };
+    buckets.sort_by_key(|(kind, _)| rank(kind));
+    let mut cumulative = 0.0;
+    for (_, bucket) in buckets.iter_mut() {
+        cumulative += bucket.prob;
+        bucket.cumulative = cumulative;
+    }
+
+    let tail = |want: BucketKind| -> f64 {
+        buckets.iter().filter(|(k, _)| *k == want).map(|(_, b)| b.prob).sum()
+    };
+    let inbound: Vec<f64> = buckets
+        .iter()
+        .filter(|(k, _)| *k == BucketKind::Inbound)
+        .map(|(_, b)| b.prob)
+        .collect();
+    let summary = transform.summarize(tail(BucketKind::LowerTail), &inbound, tail(BucketKind::UpperTail));
+
+    Ok(NumericDistribution {
+        event_id,
+        market_version: config.get("numeric_market_version"),
+        range_min: transform.range_min,
+        range_max: transform.range_max,
+        zero_point: transform.zero_point,
+        unit: config.get("unit"),
+        buckets: buckets.into_iter().map(|(_, b)| b).collect(),
+        summary,
+    })
+}
+
 // Kelly criterion suggestion
 pub fn kelly_suggestion(
     config: &Config,
@@ -1938,18 +3969,8 @@ pub fn kelly_suggestion(
     market_prob: f64,
     balance: f64,
 ) -> KellySuggestion {
-    // Calculate edge
-    let edge = if belief > market_prob {
-        (belief - market_prob) / (1.0 - market_prob)
-    } else {
-        (market_prob - belief) / market_prob
-    };
-
     // Configurable Kelly fraction for conservative betting
-    let kelly_fraction = config.market.kelly_fraction;
-    let suggestion = (edge * balance * kelly_fraction)
-        .max(0.0)
-        .min(balance * kelly_fraction);
+    let suggestion = kelly_stake(belief, market_prob, balance, config.market.kelly_fraction);
 
     KellySuggestion {
         kelly_suggestion: suggestion,
@@ -1959,10 +3980,159 @@ pub fn kelly_suggestion(
     }
 }
 
+// Create a binary market opened at 50% (q_yes = q_no = 0)
+pub async fn create_market(pool: &PgPool, request: &CreateMarket) -> Result<CreatedMarket> {
+    let title = request.title.trim();
+    if title.is_empty() || title.chars().count() > 255 {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "title must be 1-255 characters",
+        ));
+    }
+    if request.closing_date <= Utc::now() {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "closing_date must be in the future",
+        ));
+    }
+    let category = request
+        .category
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty());
+    if category.is_some_and(|c| c.chars().count() > 100) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "category must be at most 100 characters",
+        ));
+    }
+
+    let explicit = request.liquidity_b.is_some() || request.max_subsidy.is_some();
+    if explicit && request.use_recommended_liquidity == Some(true) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "use_recommended_liquidity must not be combined with liquidity_b or max_subsidy",
+        ));
+    }
+    let recommended = match request.use_recommended_liquidity {
+        Some(false) => None,
+        _ if explicit => None,
+        use_recommended => {
+            crate::liquidity_recommendations::recommended_liquidity(
+                pool,
+                category,
+                use_recommended.is_none(),
+            )
+            .await?
+        }
+    };
+
+    let liquidity_b = match (request.liquidity_b, request.max_subsidy, recommended) {
+        (Some(b), None, _) if b.is_finite() && b > 0.0 => b,
+        (Some(_), None, _) => {
+            return Err(coded(
+                ErrorCode::InvalidRequest,
+                "liquidity_b must be positive and finite",
+            ))
+        }
+        (None, Some(budget), _) => {
+            crate::lmsr_core::liquidity_for_max_subsidy(budget).map_err(|e| {
+                coded(
+                    ErrorCode::InvalidRequest,
+                    format!("max_subsidy invalid: {}", e),
+                )
+            })?
+        }
+        (None, None, Some(b)) => b,
+        (None, None, None) if request.use_recommended_liquidity == Some(true) => {
+            return Err(coded(
+                ErrorCode::InvalidRequest,
+                "No liquidity recommendation for this category",
+            ))
+        }
+        _ => {
+            return Err(coded(
+                ErrorCode::InvalidRequest,
+                "provide exactly one of liquidity_b or max_subsidy",
+            ))
+        }
+    };
+
+    let (q_yes, q_no) = (0.0, 0.0);
+    let market = Market {
+        q_yes,
+        q_no,
+        b: liquidity_b,
+    };
+    let market_prob = market.prob_yes();
+
+    let event_id: i32 = sqlx::query_scalar(
+        "INSERT INTO events
+             (title, details, closing_date, category, event_type,
+              liquidity_b, q_yes, q_no, market_prob, cumulative_stake)
+         VALUES ($1, $2, $3, $4, 'binary', $5, $6, $7, $8, $9)
+         RETURNING id",
+    )
+    .bind(title)
+    .bind(&request.details)
+    .bind(request.closing_date)
+    .bind(category)
+    .bind(liquidity_b)
+    .bind(q_yes)
+    .bind(q_no)
+    .bind(market_prob)
+    .bind(market.cost())
+    .fetch_one(pool)
+    .await?;
+
+    Ok(CreatedMarket {
+        event_id,
+        title: title.to_string(),
+        closing_date: request.closing_date,
+        category: category.map(str::to_string),
+        liquidity_b,
+        max_subsidy: crate::lmsr_core::max_subsidy_for_liquidity(liquidity_b),
+        market_prob,
+        q_yes,
+        q_no,
+    })
+}
+
 // Resolve event using lmsr_core principles (same as before, but with f64)
-pub async fn resolve_event(pool: &PgPool, event_id: i32, outcome: bool) -> Result<()> {
+pub async fn resolve_event(
+    pool: &PgPool,
+    event_id: i32,
+    outcome: bool,
+) -> Result<Vec<ResolutionPayout>> {
+    with_serializable_tx!(pool, tx, {
+        resolve_event_transaction(&mut tx, event_id, outcome.into(), None, None).await
+    })
+}
+
+/// Resolves an event N/A: binary positions are refunded through
+/// `resolve_event_transaction`, outcome and numeric positions through
+/// `resolve_event_by_outcome_transaction`. Payouts are listed for binary
+/// markets only.
+pub async fn annul_event(pool: &PgPool, event_id: i32) -> Result<Vec<ResolutionPayout>> {
     with_serializable_tx!(pool, tx, {
-        resolve_event_transaction(&mut tx, event_id, outcome).await
+        let event_type: String =
+            sqlx::query_scalar("SELECT COALESCE(event_type, 'binary') FROM events WHERE id = $1")
+                .bind(event_id)
+                .fetch_optional(tx.as_mut())
+                .await?
+                .ok_or_else(|| {
+                    coded(
+                        ErrorCode::InvalidRequest,
+                        "Event not found or already resolved",
+                    )
+                })?;
+        if event_type == "binary" {
+            resolve_event_transaction(&mut tx, event_id, Resolution::NotApplicable, None, None)
+                .await
+        } else {
+            resolve_event_by_outcome_transaction(&mut tx, event_id, None, None).await?;
+            Ok(Vec::new())
+        }
     })
 }
 
@@ -1973,7 +4143,8 @@ pub async fn resolve_event_by_outcome_id(
     numerical_outcome: Option<f64>,
 ) -> Result<()> {
     with_serializable_tx!(pool, tx, {
-        resolve_event_by_outcome_transaction(&mut tx, event_id, outcome_id, numerical_outcome).await
+        resolve_event_by_outcome_transaction(&mut tx, event_id, Some(outcome_id), numerical_outcome)
+            .await
     })
 }
 
@@ -1993,8 +4164,9 @@ pub async fn resolve_numeric_event(pool: &PgPool, event_id: i32, value: f64) ->
         .await?;
 
         if rows.is_empty() {
-            return Err(anyhow!(
-                "No numeric buckets configured for this event. Configure buckets first."
+            return Err(coded(
+                ErrorCode::InvalidRequest,
+                "No numeric buckets configured for this event. Configure buckets first.",
             ));
         }
 
@@ -2014,18 +4186,32 @@ pub async fn resolve_numeric_event(pool: &PgPool, event_id: i32, value: f64) ->
                 anyhow!("Numeric value does not fit configured buckets for this market")
             })?;
 
-        resolve_event_by_outcome_transaction(&mut tx, event_id, winner_outcome_id, Some(value))
+        resolve_event_by_outcome_transaction(&mut tx, event_id, Some(winner_outcome_id), Some(value))
             .await?;
         Ok(winner_outcome_id)
     })
 }
 
+fn shares_to_ledger(shares: f64) -> Result<i64> {
+    i64::try_from(
+        crate::lmsr_core::to_ledger_units(shares)
+            .map_err(|e| anyhow!("Invalid share value: {}", e))?,
+    )
+    .map_err(|_| anyhow!("share_value_ledger out of i64 range"))
+}
+
 // Internal transaction logic for resolve_event
-async fn resolve_event_transaction(
+/// Settles a binary event, journaling each payout so a dispute can revert
+/// it (see disputes.rs). `actor`/`reason` go to the audit trail when the
+/// resolution comes from a dispute. An N/A resolution pays each position
+/// back its stake.
+pub(crate) async fn resolve_event_transaction(
     tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     event_id: i32,
-    outcome: bool,
-) -> Result<()> {
+    resolution: Resolution,
+    actor: Option<&str>,
+    reason: Option<&str>,
+) -> Result<Vec<ResolutionPayout>> {
     // Lock the event row first so a concurrent resolve can't race, and so we
     // can reject events that don't actually settle through the binary
     // user_shares ledger this path pays out of. This mirrors the
@@ -2037,7 +4223,10 @@ async fn resolve_event_transaction(
             .fetch_optional(tx.as_mut())
             .await?;
     if market_exists.is_none() {
-        return Err(anyhow!("Event not found or already resolved"));
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "Event not found or already resolved",
+        ));
     }
     // Numeric (distribution) markets trade via event_outcome_states/q_value
     // and pay out user_outcome_shares, not user_shares — reject them here
@@ -2050,6 +4239,13 @@ async fn resolve_event_transaction(
     // path only ever reads/pays user_shares, so resolving an MC event here
     // would mark it resolved while stranding every outcome position.
     ensure_not_multi_outcome_market(tx, event_id).await?;
+    let wallet = Wallet::for_event(tx, event_id).await?;
+    // Before payouts move the RP the consensus is weighted by. N/A has no
+    // outcome to be accurate about.
+    if let Some(outcome) = resolution.scored() {
+        crate::consensus::record_resolution(tx, event_id, outcome).await?;
+        crate::market_accuracy::record_resolution(tx, event_id, outcome).await?;
+    }
 
     // Get all user positions with side-specific stake data in single query
     // FOR UPDATE prevents race conditions during resolution (e.g., concurrent sell operations)
@@ -2064,6 +4260,10 @@ async fn resolve_event_transaction(
     .fetch_all(tx.as_mut())
     .await?;
 
+    let outcome_str = resolution.as_outcome();
+    let mut paid_out_ledger = 0i64;
+    let mut payouts = Vec::with_capacity(user_shares.len());
+
     // Calculate payout for each user
     for row in &user_shares {
         let user_id: i32 = row.get("user_id");
@@ -2073,34 +4273,91 @@ async fn resolve_event_transaction(
         let staked_no_ledger: i64 = row.get("staked_no_ledger");
 
         // Calculate final share value based on outcome
-        let share_value_f64 = if outcome {
-            yes_shares // YES outcome: YES shares worth 1, NO shares worth 0
-        } else {
-            no_shares // NO outcome: NO shares worth 1, YES shares worth 0
+        let total_staked_ledger = staked_yes_ledger + staked_no_ledger;
+        let (share_value_f64, share_value_ledger) = match resolution {
+            // YES outcome: YES shares worth 1, NO shares worth 0
+            Resolution::Yes => (yes_shares, shares_to_ledger(yes_shares)?),
+            // NO outcome: NO shares worth 1, YES shares worth 0
+            Resolution::No => (no_shares, shares_to_ledger(no_shares)?),
+            // N/A: no shares redeem, the stake comes back
+            Resolution::NotApplicable => (0.0, total_staked_ledger),
         };
 
         // Update user balance with share value and clear exact staked amount using ledger-native method
-        let total_staked_ledger = staked_yes_ledger + staked_no_ledger;
-        let share_value_ledger = i64::try_from(
-            crate::lmsr_core::to_ledger_units(share_value_f64)
-                .map_err(|e| anyhow!("Invalid share value: {}", e))?,
-        )
-        .map_err(|_| anyhow!("share_value_ledger out of i64 range"))?;
-        DbAdapter::update_user_balance_ledger(
+        DbAdapter::update_wallet_balance_ledger(
             tx,
+            wallet,
             user_id,
             share_value_ledger,
             -total_staked_ledger,
         )
         .await?;
+        crate::disputes::journal_payout(
+            tx,
+            event_id,
+            outcome_str,
+            &crate::disputes::SettledPosition {
+                user_id,
+                yes_shares,
+                no_shares,
+                staked_yes_ledger,
+                staked_no_ledger,
+                payout_ledger: share_value_ledger,
+            },
+        )
+        .await?;
+        crate::realized_pnl::record(
+            tx,
+            user_id,
+            event_id,
+            share_value_ledger - total_staked_ledger,
+        )
+        .await?;
+        crate::system_accounts::book_house(
+            tx.as_mut(),
+            wallet,
+            "settlement",
+            event_id,
+            user_id,
+            share_value_ledger - total_staked_ledger,
+        )
+        .await?;
+        paid_out_ledger += share_value_ledger;
+        payouts.push(ResolutionPayout {
+            user_id,
+            shares_redeemed: share_value_f64,
+            rp_credited: from_ledger_units(share_value_ledger as i128),
+            stake_released: from_ledger_units(total_staked_ledger as i128),
+            new_balance: 0.0,
+            score_delta: from_ledger_units((share_value_ledger - total_staked_ledger) as i128),
+        });
+    }
+
+    let user_ids: Vec<i32> = payouts.iter().map(|p| p.user_id).collect();
+    let balances: BTreeMap<i32, i64> = match wallet {
+        Wallet::Main => sqlx::query_as(
+            "SELECT id, COALESCE(rp_balance_ledger, 0)::BIGINT FROM users WHERE id = ANY($1)",
+        )
+        .bind(&user_ids)
+        .fetch_all(tx.as_mut())
+        .await?,
+        Wallet::Competition(competition_id) => sqlx::query_as(
+            "SELECT user_id, balance_ledger FROM competition_wallets
+             WHERE competition_id = $1 AND user_id = ANY($2)",
+        )
+        .bind(competition_id)
+        .bind(&user_ids)
+        .fetch_all(tx.as_mut())
+        .await?,
+    }
+    .into_iter()
+    .collect();
+    for payout in &mut payouts {
+        let balance = balances.get(&payout.user_id).copied().unwrap_or_default();
+        payout.new_balance = from_ledger_units(balance as i128);
     }
 
     // Mark event as resolved
-    let outcome_str = if outcome {
-        "resolved_yes"
-    } else {
-        "resolved_no"
-    };
     sqlx::query("UPDATE events SET outcome = $1, resolved_at = NOW() WHERE id = $2")
         .bind(outcome_str)
         .bind(event_id)
@@ -2113,13 +4370,34 @@ async fn resolve_event_transaction(
         .execute(tx.as_mut())
         .await?;
 
-    Ok(())
+    crate::disputes::record_audit(
+        tx,
+        event_id,
+        &crate::disputes::AuditEntry {
+            action: crate::disputes::ACTION_RESOLVED,
+            outcome: Some(outcome_str),
+            previous_outcome: None,
+            actor,
+            reason,
+            positions: user_shares.len() as i32,
+            amount_ledger: paid_out_ledger,
+        },
+    )
+    .await?;
+    // Paper predictions scored against a since-disputed outcome
+    crate::paper_predictions::rescore_event(tx, event_id, resolution.scored()).await?;
+    crate::forecasts::settle_event(tx, event_id, Some(resolution)).await?;
+    crate::score_integrity::seal_event(tx, event_id).await?;
+
+    Ok(payouts)
 }
 
+/// Settles a multiple-choice or numeric event on `outcome_id`, or N/A when
+/// it is `None`, which pays back each position's stake and numeric basis.
 async fn resolve_event_by_outcome_transaction(
     tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     event_id: i32,
-    outcome_id: i64,
+    outcome_id: Option<i64>,
     numerical_outcome: Option<f64>,
 ) -> Result<()> {
     let market_exists: Option<i32> =
@@ -2128,18 +4406,27 @@ async fn resolve_event_by_outcome_transaction(
             .fetch_optional(tx.as_mut())
             .await?;
     if market_exists.is_none() {
-        return Err(anyhow!("Event not found or already resolved"));
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "Event not found or already resolved",
+        ));
     }
+    let wallet = Wallet::for_event(tx, event_id).await?;
 
-    let winner_exists: Option<i64> = sqlx::query_scalar(
-        "SELECT id FROM event_outcomes WHERE id = $1 AND event_id = $2 AND is_active = TRUE",
-    )
-    .bind(outcome_id)
-    .bind(event_id)
-    .fetch_optional(tx.as_mut())
-    .await?;
-    if winner_exists.is_none() {
-        return Err(anyhow!("Invalid winning outcome for this event"));
+    if let Some(outcome_id) = outcome_id {
+        let winner_exists: Option<i64> = sqlx::query_scalar(
+            "SELECT id FROM event_outcomes WHERE id = $1 AND event_id = $2 AND is_active = TRUE",
+        )
+        .bind(outcome_id)
+        .bind(event_id)
+        .fetch_optional(tx.as_mut())
+        .await?;
+        if winner_exists.is_none() {
+            return Err(coded(
+                ErrorCode::InvalidRequest,
+                "Invalid winning outcome for this event",
+            ));
+        }
     }
 
     let rows = sqlx::query(
@@ -2162,15 +4449,21 @@ async fn resolve_event_by_outcome_transaction(
         let shares: f64 = row.get("shares");
         let staked_ledger: i64 = row.get("staked_ledger");
 
-        let payout_shares = if row_outcome_id == outcome_id {
-            shares
-        } else {
-            0.0
+        let payout_ledger = match outcome_id {
+            Some(outcome_id) => {
+                let payout_shares = if row_outcome_id == outcome_id {
+                    shares
+                } else {
+                    0.0
+                };
+                i64::try_from(
+                    to_ledger_units(payout_shares)
+                        .map_err(|e| anyhow!("Invalid payout value: {}", e))?,
+                )
+                .map_err(|_| anyhow!("payout_ledger out of i64 range"))?
+            }
+            None => staked_ledger,
         };
-        let payout_ledger = i64::try_from(
-            to_ledger_units(payout_shares).map_err(|e| anyhow!("Invalid payout value: {}", e))?,
-        )
-        .map_err(|_| anyhow!("payout_ledger out of i64 range"))?;
 
         let entry = deltas.entry(user_id).or_insert((0, 0));
         entry.0 = entry
@@ -2218,14 +4511,25 @@ async fn resolve_event_by_outcome_transaction(
             .1
             .checked_sub(basis_ledger)
             .ok_or_else(|| anyhow!("staked delta overflow"))?;
+        if outcome_id.is_none() {
+            entry.0 = entry
+                .0
+                .checked_add(basis_ledger)
+                .ok_or_else(|| anyhow!("balance delta overflow"))?;
+        }
     }
 
     let user_ids: Vec<i32> = deltas.keys().copied().collect();
     let balance_deltas: Vec<i64> = deltas.values().map(|d| d.0).collect();
     let staked_deltas: Vec<i64> = deltas.values().map(|d| d.1).collect();
-    let affected =
-        DbAdapter::update_user_balances_ledger_batch(tx, &user_ids, &balance_deltas, &staked_deltas)
-            .await?;
+    let affected = DbAdapter::update_wallet_balances_ledger_batch(
+        tx,
+        wallet,
+        &user_ids,
+        &balance_deltas,
+        &staked_deltas,
+    )
+    .await?;
     if affected != user_ids.len() as u64 {
         return Err(anyhow!(
             "settlement balance update applied to {} of {} users on event {} — aborting resolution",
@@ -2234,6 +4538,18 @@ async fn resolve_event_by_outcome_transaction(
             event_id
         ));
     }
+    // Settlement realizes payout minus the basis it unstakes
+    let realized: Vec<i64> = deltas.values().map(|d| d.0 + d.1).collect();
+    crate::realized_pnl::record_batch(tx, event_id, &user_ids, &realized).await?;
+    crate::system_accounts::book_house_batch(
+        tx.as_mut(),
+        wallet,
+        "settlement",
+        event_id,
+        &user_ids,
+        &realized,
+    )
+    .await?;
 
     sqlx::query(
         "UPDATE numeric_position_basis SET basis_ledger = 0, updated_at = NOW()
@@ -2254,7 +4570,10 @@ async fn resolve_event_by_outcome_transaction(
         .execute(tx.as_mut())
         .await?;
 
-    let outcome_marker = format!("resolved_outcome_{}", outcome_id);
+    let outcome_marker = outcome_id.map_or_else(
+        || OUTCOME_NOT_APPLICABLE.to_string(),
+        |outcome_id| format!("resolved_outcome_{}", outcome_id),
+    );
     sqlx::query(
         "UPDATE events
          SET outcome = $1,
@@ -2269,6 +4588,7 @@ async fn resolve_event_by_outcome_transaction(
     .bind(event_id)
     .execute(tx.as_mut())
     .await?;
+    crate::score_integrity::seal_event(tx, event_id).await?;
 
     Ok(())
 }
@@ -2281,6 +4601,15 @@ pub async fn get_market_state(pool: &PgPool, event_id: i32) -> Result<serde_json
             e.title,
             e.event_type,
             e.market_prob,
+            e.weighted_prob,
+            e.weighted_prob_contributors,
+            e.weighted_prob_updated_at,
+            e.resolution_criteria,
+            e.fine_print,
+            e.value_unit,
+            e.value_lower_bound,
+            e.value_upper_bound,
+            e.source_url,
             e.cumulative_stake,
             e.liquidity_b,
             e.q_yes,
@@ -2289,17 +4618,17 @@ pub async fn get_market_state(pool: &PgPool, event_id: i32) -> Result<serde_json
                 SELECT COUNT(DISTINCT combined.user_id)
                 FROM (
                     SELECT mu.user_id
-                    FROM market_updates mu
+                    FROM market_updates_all mu
                     WHERE mu.event_id = e.id
                     UNION
                     SELECT mou.user_id
-                    FROM market_outcome_updates mou
+                    FROM market_outcome_updates_all mou
                     WHERE mou.event_id = e.id
                 ) combined
             ) AS unique_traders,
             (
-                COALESCE((SELECT COUNT(*) FROM market_updates mu WHERE mu.event_id = e.id), 0)
-                + COALESCE((SELECT COUNT(*) FROM market_outcome_updates mou WHERE mou.event_id = e.id), 0)
+                COALESCE((SELECT COUNT(*) FROM market_updates_all mu WHERE mu.event_id = e.id), 0)
+                + COALESCE((SELECT COUNT(*) FROM market_outcome_updates_all mou WHERE mou.event_id = e.id), 0)
             ) AS total_trades,
             (
                 SELECT c.numeric_market_version
@@ -2445,30 +4774,57 @@ pub async fn get_market_state(pool: &PgPool, event_id: i32) -> Result<serde_json
                 }
             }
 
+            let buzz = crate::comment_buzz::load_buzz(pool, event_id).await?;
+            let embargo = crate::embargo::load_embargo(pool, event_id).await?;
+
             Ok(serde_json::json!({
                 "event_id": row.get::<i32, _>("id"),
                 "title": row.get::<String, _>("title"),
                 "market_type": market_type,
                 "market_prob": market_prob,
+                "weighted_prob": row.get::<Option<f64>, _>("weighted_prob"),
+                "weighted_prob_contributors": row.get::<Option<i32>, _>("weighted_prob_contributors"),
+                "weighted_prob_updated_at": row.get::<Option<DateTime<Utc>>, _>("weighted_prob_updated_at"),
                 "cumulative_stake": row.get::<f64, _>("cumulative_stake"),
                 "liquidity_b": row.get::<f64, _>("liquidity_b"),
                 "unique_traders": row.get::<i64, _>("unique_traders"),
                 "total_trades": row.get::<i64, _>("total_trades"),
                 "numeric_market_version": row.get::<Option<i64>, _>("numeric_market_version"),
                 "numeric_config": numeric_config,
+                "metadata": EventMetadata::from_row(&row)?,
+                "buzz": buzz,
+                "embargo": embargo,
                 "outcomes": outcomes
             }))
         }
-        None => Err(anyhow!("Event not found")),
+        None => Err(coded(ErrorCode::NotFound, "Event not found")),
     }
 }
 
-// Get recent trades for an event
+// Get recent trades for an event; traders are hidden on anonymous markets
 pub async fn get_event_trades(
     pool: &PgPool,
     event_id: i32,
     limit: i32,
 ) -> Result<serde_json::Value> {
+    let anonymous = crate::trade_privacy::is_anonymous(pool, event_id).await?;
+    load_event_trades(pool, event_id, limit, !anonymous).await
+}
+
+// Trade tape with usernames only when `reveal_users` is set
+pub(crate) async fn load_event_trades(
+    pool: &PgPool,
+    event_id: i32,
+    limit: i32,
+    reveal_users: bool,
+) -> Result<serde_json::Value> {
+    let user = |row: &sqlx::postgres::PgRow| {
+        if reveal_users {
+            Some(row.get::<String, _>("username"))
+        } else {
+            None
+        }
+    };
     let rows = sqlx::query(
         r#"
         SELECT
@@ -2480,7 +4836,7 @@ pub async fn get_event_trades(
             mu.new_prob,
             mu.shares_acquired,
             mu.created_at
-        FROM market_updates mu
+        FROM market_updates_all mu
         JOIN users u ON mu.user_id = u.id
         WHERE mu.event_id = $1
         ORDER BY mu.created_at DESC
@@ -2504,7 +4860,7 @@ pub async fn get_event_trades(
 
             serde_json::json!({
                 "id": row.get::<i32, _>("id"),
-                "user": row.get::<String, _>("username"),
+                "user": user(row),
                 "direction": share_type.to_uppercase(),
                 "amount": stake_amount,
                 "shares_acquired": shares_acquired,
@@ -2526,7 +4882,7 @@ pub async fn get_event_trades(
             mou.new_prob,
             mou.shares_acquired,
             mou.created_at
-        FROM market_outcome_updates mou
+        FROM market_outcome_updates_all mou
         JOIN users u ON mou.user_id = u.id
         JOIN event_outcomes eo ON eo.id = mou.outcome_id
         WHERE mou.event_id = $1
@@ -2545,7 +4901,7 @@ pub async fn get_event_trades(
         let created_at: DateTime<Utc> = row.get("created_at");
         merged.push(serde_json::json!({
             "id": row.get::<i64, _>("id"),
-            "user": row.get::<String, _>("username"),
+            "user": user(&row),
             "direction": row.get::<String, _>("label"),
             "amount": row.get::<f64, _>("stake_amount"),
             "shares_acquired": row.get::<f64, _>("shares_acquired"),
@@ -2571,6 +4927,7 @@ pub async fn get_event_trades(
 
     Ok(serde_json::json!({
         "event_id": event_id,
+        "anonymous": !reveal_users,
         "trades": merged,
         "count": merged.len()
     }))
@@ -2582,6 +4939,9 @@ pub async fn get_user_shares(
     user_id: i32,
     event_id: i32,
 ) -> Result<serde_json::Value> {
+    let realized_pnl = from_ledger_units(
+        crate::realized_pnl::market_realized_pnl(pool, user_id, event_id).await? as i128,
+    );
     let outcome_rows = sqlx::query(
         r#"
         SELECT
@@ -2636,7 +4996,8 @@ pub async fn get_user_shares(
         return Ok(serde_json::json!({
             "yes_shares": yes_shares,
             "no_shares": no_shares,
-            "outcome_shares": outcome_shares
+            "outcome_shares": outcome_shares,
+            "realized_pnl": realized_pnl
         }));
     }
 
@@ -2654,12 +5015,14 @@ pub async fn get_user_shares(
         Some(row) => Ok(serde_json::json!({
             "yes_shares": row.get::<f64, _>("yes_shares"),
             "no_shares": row.get::<f64, _>("no_shares"),
-            "outcome_shares": []
+            "outcome_shares": [],
+            "realized_pnl": realized_pnl
         })),
         None => Ok(serde_json::json!({
             "yes_shares": 0.0,
             "no_shares": 0.0,
-            "outcome_shares": []
+            "outcome_shares": [],
+            "realized_pnl": realized_pnl
         })),
     }
 }
@@ -2768,8 +5131,13 @@ async fn verify_staked_invariant_transaction(
             .fetch_one(tx.as_mut())
             .await?;
 
+    // Competition markets stake out of competition_wallets, not
+    // users.rp_staked_ledger, so every term below leaves them out.
     let binary_staked_ledger: i64 = sqlx::query_scalar(
-        "SELECT COALESCE(SUM(total_staked_ledger), 0)::BIGINT FROM user_shares WHERE user_id = $1",
+        "SELECT COALESCE(SUM(us.total_staked_ledger), 0)::BIGINT
+         FROM user_shares us
+         JOIN events e ON e.id = us.event_id
+         WHERE us.user_id = $1 AND e.competition_id IS NULL",
     )
     .bind(user_id)
     .fetch_one(tx.as_mut())
@@ -2780,7 +5148,10 @@ async fn verify_staked_invariant_transaction(
     // distribution_trades instead — see numeric_trade_transaction), so this
     // term only actually contributes for multiple_choice positions.
     let outcome_staked_ledger: i64 = sqlx::query_scalar(
-        "SELECT COALESCE(SUM(staked_ledger), 0)::BIGINT FROM user_outcome_shares WHERE user_id = $1",
+        "SELECT COALESCE(SUM(uos.staked_ledger), 0)::BIGINT
+         FROM user_outcome_shares uos
+         JOIN events e ON e.id = uos.event_id
+         WHERE uos.user_id = $1 AND e.competition_id IS NULL",
     )
     .bind(user_id)
     .fetch_one(tx.as_mut())
@@ -2802,7 +5173,7 @@ async fn verify_staked_invariant_transaction(
         SELECT COALESCE(SUM(npb.basis_ledger), 0)::BIGINT
         FROM numeric_position_basis npb
         JOIN events e ON e.id = npb.event_id
-        WHERE npb.user_id = $1 AND e.outcome IS NULL
+        WHERE npb.user_id = $1 AND e.outcome IS NULL AND e.competition_id IS NULL
         "#,
     )
     .bind(user_id)
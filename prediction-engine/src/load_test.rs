@@ -0,0 +1,663 @@
+//! HTTP load test for a running prediction engine
+//!
+//! Unlike [`crate::stress`], which calls `lmsr_api` directly, this drives
+//! the real endpoints over HTTP, so routing, JSON extraction, the auth
+//! guard and CORS layers are all in the measured path. Requests are
+//! open-loop at a fixed rate with a weighted mix of workloads, while a set
+//! of WebSocket clients stays subscribed to /ws and counts the updates the
+//! trades fan out.
+//!
+//! The engine has no leaderboard route (the backend builds leaderboards
+//! from its own tables); the `events` and `trades` list reads are the
+//! read-heavy stand-ins.
+
+use anyhow::{anyhow, Result};
+use futures_util::StreamExt;
+use hdrhistogram::Histogram;
+use rand::prelude::*;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tracing::{info, warn};
+
+use crate::stress::{latency_histogram, record_latency, LatencySummary};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Workload {
+    /// GET /events/:id/market
+    Market,
+    /// GET /events/:id/kelly
+    Kelly,
+    /// GET /events
+    Events,
+    /// GET /events/:id/trades
+    Trades,
+    /// POST /events/:id/update
+    Buy,
+    /// POST /events/:id/sell
+    Sell,
+}
+
+impl Workload {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.trim() {
+            "market" => Ok(Self::Market),
+            "kelly" => Ok(Self::Kelly),
+            "events" => Ok(Self::Events),
+            "trades" => Ok(Self::Trades),
+            "buy" => Ok(Self::Buy),
+            "sell" => Ok(Self::Sell),
+            other => Err(anyhow!(
+                "unknown workload '{}' (expected market, kelly, events, trades, buy or sell)",
+                other
+            )),
+        }
+    }
+}
+
+/// Parses a weighted mix like `market=40,kelly=20,buy=30,sell=10`.
+pub fn parse_mix(spec: &str) -> Result<Vec<(Workload, u32)>> {
+    let mut mix = Vec::new();
+    for part in spec.split(',').filter(|part| !part.trim().is_empty()) {
+        let (name, weight) = part
+            .split_once('=')
+            .ok_or_else(|| anyhow!("mix entry '{}' must be workload=weight", part))?;
+        let weight: u32 = weight
+            .trim()
+            .parse()
+            .map_err(|_| anyhow!("invalid weight in mix entry '{}'", part))?;
+        if weight > 0 {
+            mix.push((Workload::parse(name)?, weight));
+        }
+    }
+    if mix.is_empty() {
+        return Err(anyhow!("workload mix needs at least one positive weight"));
+    }
+    Ok(mix)
+}
+
+/// Parses an id list like `1-100,205,300-310`.
+pub fn parse_ids(spec: &str) -> Result<Vec<i32>> {
+    let mut ids = Vec::new();
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let invalid = || anyhow!("invalid id range '{}'", part);
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let start: i32 = start.trim().parse().map_err(|_| invalid())?;
+                let end: i32 = end.trim().parse().map_err(|_| invalid())?;
+                if start <= 0 || end < start {
+                    return Err(invalid());
+                }
+                ids.extend(start..=end);
+            }
+            None => ids.push(part.parse().map_err(|_| invalid())?),
+        }
+    }
+    if ids.is_empty() {
+        return Err(anyhow!("id list '{}' is empty", spec));
+    }
+    Ok(ids)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadTestConfig {
+    pub base_url: String,
+    /// Sent as x-engine-token; never written to the report.
+    #[serde(skip)]
+    pub token: Option<String>,
+    pub rps: f64,
+    pub duration_secs: u64,
+    /// Ticks that find this many requests outstanding are dropped and
+    /// counted, so a slow server shows up as drops instead of a queue.
+    pub max_in_flight: usize,
+    pub request_timeout_ms: u64,
+    pub mix: Vec<(Workload, u32)>,
+    /// Users the trade workloads act as; they must already exist.
+    pub user_ids: Vec<i32>,
+    /// Events to target; None discovers open events via GET /events.
+    pub event_ids: Option<Vec<i32>>,
+    pub ws_clients: usize,
+    pub seed: Option<u64>,
+}
+
+impl Default for LoadTestConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "http://localhost:3001".to_string(),
+            token: None,
+            rps: 50.0,
+            duration_secs: 60,
+            max_in_flight: 256,
+            request_timeout_ms: 10_000,
+            mix: vec![
+                (Workload::Market, 30),
+                (Workload::Kelly, 15),
+                (Workload::Events, 10),
+                (Workload::Trades, 10),
+                (Workload::Buy, 25),
+                (Workload::Sell, 10),
+            ],
+            user_ids: (1..=100).collect(),
+            event_ids: None,
+            ws_clients: 10,
+            seed: None,
+        }
+    }
+}
+
+impl LoadTestConfig {
+    /// Defaults overridden by LOAD_TEST_* env vars; the token falls back to
+    /// the server's own PREDICTION_ENGINE_AUTH_TOKEN.
+    pub fn from_env() -> Result<Self> {
+        let mut config = Self {
+            token: std::env::var("LOAD_TEST_TOKEN")
+                .or_else(|_| std::env::var("PREDICTION_ENGINE_AUTH_TOKEN"))
+                .ok(),
+            ..Self::default()
+        };
+        for (var, flag) in [
+            ("LOAD_TEST_URL", "--url"),
+            ("LOAD_TEST_RPS", "--rps"),
+            ("LOAD_TEST_DURATION_SECS", "--duration-secs"),
+            ("LOAD_TEST_MIX", "--mix"),
+            ("LOAD_TEST_USERS", "--users"),
+            ("LOAD_TEST_EVENTS", "--events"),
+            ("LOAD_TEST_WS_CLIENTS", "--ws-clients"),
+        ] {
+            if let Ok(value) = std::env::var(var) {
+                config.apply_flag(flag, &value)?;
+            }
+        }
+        Ok(config)
+    }
+
+    /// Applies one `--flag value` CLI override.
+    pub fn apply_flag(&mut self, flag: &str, value: &str) -> Result<()> {
+        fn num<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T> {
+            value
+                .parse::<T>()
+                .map_err(|_| anyhow!("invalid value '{}' for {}", value, flag))
+        }
+        match flag {
+            "--url" => self.base_url = value.trim_end_matches('/').to_string(),
+            "--token" => self.token = Some(value.to_string()),
+            "--rps" => self.rps = num(flag, value)?,
+            "--duration-secs" => self.duration_secs = num(flag, value)?,
+            "--max-in-flight" => self.max_in_flight = num(flag, value)?,
+            "--timeout-ms" => self.request_timeout_ms = num(flag, value)?,
+            "--mix" => self.mix = parse_mix(value)?,
+            "--users" => self.user_ids = parse_ids(value)?,
+            "--events" => self.event_ids = Some(parse_ids(value)?),
+            "--ws-clients" => self.ws_clients = num(flag, value)?,
+            "--seed" => self.seed = Some(num(flag, value)?),
+            other => return Err(anyhow!("unknown flag {}", other)),
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if !self.base_url.starts_with("http://") && !self.base_url.starts_with("https://") {
+            return Err(anyhow!("url must start with http:// or https://"));
+        }
+        if !self.rps.is_finite() || self.rps <= 0.0 {
+            return Err(anyhow!("rps must be positive"));
+        }
+        if self.duration_secs == 0 || self.max_in_flight == 0 || self.request_timeout_ms == 0 {
+            return Err(anyhow!(
+                "duration_secs, max_in_flight and request_timeout_ms must be positive"
+            ));
+        }
+        if self.mix.is_empty() || self.user_ids.is_empty() {
+            return Err(anyhow!("mix and user_ids must not be empty"));
+        }
+        Ok(())
+    }
+
+    fn ws_url(&self) -> String {
+        let rest = self
+            .base_url
+            .strip_prefix("https://")
+            .map(|rest| format!("wss://{}", rest))
+            .or_else(|| {
+                self.base_url
+                    .strip_prefix("http://")
+                    .map(|rest| format!("ws://{}", rest))
+            })
+            .unwrap_or_else(|| self.base_url.clone());
+        format!("{}/ws", rest)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointStats {
+    pub workload: Workload,
+    pub requests: u64,
+    pub ok: u64,
+    /// Failures by kind: `http_<status>`, `timeout`, `connect` or `other`.
+    pub errors: BTreeMap<String, u64>,
+    pub latency: LatencySummary,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WsStats {
+    pub clients: usize,
+    pub connected: u64,
+    pub connect_failures: u64,
+    pub messages_received: u64,
+    /// Connections the server closed before the run ended.
+    pub dropped_connections: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadTestReport {
+    pub config: LoadTestConfig,
+    pub seed: u64,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub duration_secs: f64,
+    pub target_rps: f64,
+    pub achieved_rps: f64,
+    pub requests: u64,
+    pub ok: u64,
+    /// Ticks skipped because max_in_flight requests were outstanding.
+    pub dropped: u64,
+    pub error_rate_pct: f64,
+    pub latency: LatencySummary,
+    pub endpoints: Vec<EndpointStats>,
+    pub websocket: WsStats,
+}
+
+struct EndpointTally {
+    requests: u64,
+    ok: u64,
+    errors: BTreeMap<String, u64>,
+    latency: Histogram<u64>,
+}
+
+impl EndpointTally {
+    fn new() -> Self {
+        Self {
+            requests: 0,
+            ok: 0,
+            errors: BTreeMap::new(),
+            latency: latency_histogram(),
+        }
+    }
+}
+
+fn classify_error(err: &reqwest::Error) -> String {
+    if err.is_timeout() {
+        "timeout".to_string()
+    } else if err.is_connect() {
+        "connect".to_string()
+    } else {
+        "other".to_string()
+    }
+}
+
+fn pick_workload(mix: &[(Workload, u32)], rng: &mut StdRng) -> Workload {
+    let total: u32 = mix.iter().map(|(_, weight)| weight).sum();
+    let mut roll = rng.gen_range(0..total);
+    for (workload, weight) in mix {
+        if roll < *weight {
+            return *workload;
+        }
+        roll -= weight;
+    }
+    mix[mix.len() - 1].0
+}
+
+/// Builds the request for one workload; random draws happen here, before
+/// any await, so a seed replays the same request sequence.
+fn build_request(
+    client: &reqwest::Client,
+    config: &LoadTestConfig,
+    events: &[i32],
+    workload: Workload,
+    rng: &mut StdRng,
+) -> reqwest::RequestBuilder {
+    let base = &config.base_url;
+    let event_id = events[rng.gen_range(0..events.len())];
+    let user_id = config.user_ids[rng.gen_range(0..config.user_ids.len())];
+    let request = match workload {
+        Workload::Market => client.get(format!("{}/events/{}/market", base, event_id)),
+        Workload::Kelly => client.get(format!(
+            "{}/events/{}/kelly?belief={:.3}&user_id={}",
+            base,
+            event_id,
+            rng.gen_range(0.05..0.95),
+            user_id
+        )),
+        Workload::Events => client.get(format!("{}/events", base)),
+        Workload::Trades => client.get(format!("{}/events/{}/trades", base, event_id)),
+        Workload::Buy => client
+            .post(format!("{}/events/{}/update", base, event_id))
+            .json(&serde_json::json!({
+                "user_id": user_id,
+                "target_prob": rng.gen_range(0.05..0.95),
+                "stake": rng.gen_range(1.0..10.0),
+            })),
+        // Users without shares on the event get a 400; that shows up as
+        // http_400 in the sell breakdown rather than as a failure mode.
+        Workload::Sell => client
+            .post(format!("{}/events/{}/sell", base, event_id))
+            .json(&serde_json::json!({
+                "user_id": user_id,
+                "share_type": if rng.gen_bool(0.5) { "yes" } else { "no" },
+                "amount": rng.gen_range(0.1..2.0),
+            })),
+    };
+    match &config.token {
+        Some(token) => request.header("x-engine-token", token),
+        None => request,
+    }
+}
+
+/// Open binary events to target, from GET /events.
+async fn discover_events(client: &reqwest::Client, config: &LoadTestConfig) -> Result<Vec<i32>> {
+    let events: Vec<serde_json::Value> = client
+        .get(format!("{}/events?limit=200", config.base_url))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let ids: Vec<i32> = events
+        .iter()
+        .filter(|event| event["outcome"].is_null())
+        .filter(|event| event["event_type"].as_str().unwrap_or("binary") == "binary")
+        .filter_map(|event| event["id"].as_i64())
+        .map(|id| id as i32)
+        .collect();
+    if ids.is_empty() {
+        return Err(anyhow!(
+            "GET /events returned no open binary events; pass --events explicitly"
+        ));
+    }
+    Ok(ids)
+}
+
+async fn run_ws_client(
+    url: String,
+    token: Option<String>,
+    stats: Arc<Mutex<WsStats>>,
+    stop: Arc<AtomicBool>,
+) {
+    let request = url.into_client_request().map(|mut request| {
+        if let Some(token) = token.as_deref().and_then(|t| t.parse().ok()) {
+            request.headers_mut().insert("x-engine-token", token);
+        }
+        request
+    });
+    let connection = match request {
+        Ok(request) => tokio_tungstenite::connect_async(request).await,
+        Err(e) => Err(e),
+    };
+    let mut socket = match connection {
+        Ok((socket, _)) => socket,
+        Err(e) => {
+            warn!("WebSocket connect failed: {}", e);
+            if let Ok(mut stats) = stats.lock() {
+                stats.connect_failures += 1;
+            }
+            return;
+        }
+    };
+    if let Ok(mut stats) = stats.lock() {
+        stats.connected += 1;
+    }
+
+    let mut messages = 0u64;
+    let mut dropped = false;
+    while !stop.load(Ordering::Relaxed) {
+        match tokio::time::timeout(Duration::from_millis(250), socket.next()).await {
+            Ok(Some(Ok(message))) if message.is_text() => messages += 1,
+            Ok(Some(Ok(_))) | Err(_) => {}
+            Ok(Some(Err(_))) | Ok(None) => {
+                dropped = true;
+                break;
+            }
+        }
+    }
+    let _ = socket.close(None).await;
+    if let Ok(mut stats) = stats.lock() {
+        stats.messages_received += messages;
+        if dropped {
+            stats.dropped_connections += 1;
+        }
+    }
+}
+
+/// Runs the load test against `config.base_url` and returns its report.
+/// Request errors are tallied per endpoint, not returned.
+pub async fn run_load_test(config: &LoadTestConfig) -> Result<LoadTestReport> {
+    config.validate()?;
+    let seed = config.seed.unwrap_or_else(rand::random);
+    info!("🎲 Load test seed: {} (replay with --seed {})", seed, seed);
+    let started_at = chrono::Utc::now();
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(config.request_timeout_ms))
+        .user_agent("Intellacc-LoadTest/1.0")
+        .build()?;
+
+    let events = match &config.event_ids {
+        Some(ids) => ids.clone(),
+        None => discover_events(&client, config).await?,
+    };
+    info!(
+        "Targeting {} events with {} users at {:.1} req/s for {}s",
+        events.len(),
+        config.user_ids.len(),
+        config.rps,
+        config.duration_secs
+    );
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let ws_stats = Arc::new(Mutex::new(WsStats {
+        clients: config.ws_clients,
+        ..WsStats::default()
+    }));
+    let ws_handles: Vec<_> = (0..config.ws_clients)
+        .map(|_| {
+            tokio::spawn(run_ws_client(
+                config.ws_url(),
+                config.token.clone(),
+                Arc::clone(&ws_stats),
+                Arc::clone(&stop),
+            ))
+        })
+        .collect();
+
+    let tallies: Arc<Mutex<BTreeMap<Workload, EndpointTally>>> =
+        Arc::new(Mutex::new(BTreeMap::new()));
+    let config_arc = Arc::new(config.clone());
+    let events = Arc::new(events);
+    let permits = Arc::new(Semaphore::new(config.max_in_flight));
+    let dropped = Arc::new(AtomicU64::new(0));
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / config.rps));
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    let start_time = Instant::now();
+    let deadline = start_time + Duration::from_secs(config.duration_secs);
+    let mut next_progress = start_time + Duration::from_secs(10);
+    while Instant::now() < deadline {
+        ticker.tick().await;
+        let Ok(permit) = Arc::clone(&permits).try_acquire_owned() else {
+            dropped.fetch_add(1, Ordering::Relaxed);
+            continue;
+        };
+        let workload = pick_workload(&config.mix, &mut rng);
+        let request = build_request(&client, &config_arc, &events, workload, &mut rng);
+        let task_tallies = Arc::clone(&tallies);
+
+        tokio::spawn(async move {
+            let tallies = task_tallies;
+            let started = Instant::now();
+            let outcome = match request.send().await {
+                Ok(response) if response.status().is_success() => {
+                    // Read the body so transfer time counts toward latency.
+                    response
+                        .bytes()
+                        .await
+                        .map(|_| ())
+                        .map_err(|e| classify_error(&e))
+                }
+                Ok(response) => Err(format!("http_{}", response.status().as_u16())),
+                Err(e) => Err(classify_error(&e)),
+            };
+            let latency = started.elapsed();
+            if let Ok(mut tallies) = tallies.lock() {
+                let tally = tallies.entry(workload).or_insert_with(EndpointTally::new);
+                tally.requests += 1;
+                match outcome {
+                    Ok(()) => {
+                        tally.ok += 1;
+                        record_latency(&mut tally.latency, latency);
+                    }
+                    Err(kind) => *tally.errors.entry(kind).or_default() += 1,
+                }
+            }
+            // Release the tally handle before the permit, so the drain
+            // below finds it unshared.
+            drop(tallies);
+            drop(permit);
+        });
+
+        if Instant::now() >= next_progress {
+            let (requests, ok) = tallies
+                .lock()
+                .map(|t| {
+                    t.values()
+                        .fold((0, 0), |(r, o), t| (r + t.requests, o + t.ok))
+                })
+                .unwrap_or((0, 0));
+            info!(
+                "   t={:.0}s | {} requests, {} ok, {} dropped",
+                start_time.elapsed().as_secs_f64(),
+                requests,
+                ok,
+                dropped.load(Ordering::Relaxed)
+            );
+            next_progress += Duration::from_secs(10);
+        }
+    }
+
+    let _drain = permits
+        .acquire_many(config.max_in_flight as u32)
+        .await
+        .map_err(|e| anyhow!("load test permits closed: {}", e))?;
+    let duration = start_time.elapsed();
+    stop.store(true, Ordering::Relaxed);
+    for handle in ws_handles {
+        let _ = handle.await;
+    }
+
+    let tallies = Arc::try_unwrap(tallies)
+        .ok()
+        .and_then(|tallies| tallies.into_inner().ok())
+        .ok_or_else(|| anyhow!("load test tallies still shared after drain"))?;
+    let mut overall = latency_histogram();
+    let (mut requests, mut ok) = (0u64, 0u64);
+    let endpoints: Vec<EndpointStats> = tallies
+        .into_iter()
+        .map(|(workload, tally)| {
+            requests += tally.requests;
+            ok += tally.ok;
+            let _ = overall.add(&tally.latency);
+            EndpointStats {
+                workload,
+                requests: tally.requests,
+                ok: tally.ok,
+                errors: tally.errors,
+                latency: LatencySummary::from_histogram(&tally.latency),
+            }
+        })
+        .collect();
+    let websocket = ws_stats.lock().map(|s| s.clone()).unwrap_or_default();
+    let error_rate_pct = if requests == 0 {
+        0.0
+    } else {
+        (requests - ok) as f64 / requests as f64 * 100.0
+    };
+
+    info!("\n🏁 Load test finished in {:.2?}", duration);
+    for endpoint in &endpoints {
+        info!(
+            "   - {:?}: {} requests, {} ok | p50 {:.2}ms p99 {:.2}ms | errors {:?}",
+            endpoint.workload,
+            endpoint.requests,
+            endpoint.ok,
+            endpoint.latency.p50_ms,
+            endpoint.latency.p99_ms,
+            endpoint.errors
+        );
+    }
+    info!(
+        "   - WebSocket: {}/{} connected, {} messages, {} dropped",
+        websocket.connected,
+        websocket.clients,
+        websocket.messages_received,
+        websocket.dropped_connections
+    );
+
+    Ok(LoadTestReport {
+        config: config.clone(),
+        seed,
+        started_at,
+        duration_secs: duration.as_secs_f64(),
+        target_rps: config.rps,
+        achieved_rps: requests as f64 / duration.as_secs_f64(),
+        requests,
+        ok,
+        dropped: dropped.load(Ordering::Relaxed),
+        error_rate_pct,
+        latency: LatencySummary::from_histogram(&overall),
+        endpoints,
+        websocket,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mix_and_id_specs_parse() {
+        let mix = parse_mix("market=40, buy=20,sell=0,kelly=5").unwrap();
+        assert_eq!(
+            mix,
+            vec![
+                (Workload::Market, 40),
+                (Workload::Buy, 20),
+                (Workload::Kelly, 5)
+            ]
+        );
+        assert!(parse_mix("leaderboard=10").is_err());
+        assert!(parse_mix("market=0").is_err());
+
+        assert_eq!(parse_ids("1-3,7").unwrap(), vec![1, 2, 3, 7]);
+        assert!(parse_ids("5-2").is_err());
+        assert!(parse_ids("").is_err());
+    }
+
+    #[test]
+    fn workload_picks_follow_weights() {
+        let mix = vec![(Workload::Market, 3), (Workload::Buy, 1)];
+        let mut rng = StdRng::seed_from_u64(11);
+        let buys = (0..4_000)
+            .filter(|_| pick_workload(&mix, &mut rng) == Workload::Buy)
+            .count();
+        assert!((850..1150).contains(&buys), "{} buys", buys);
+
+        let config = LoadTestConfig {
+            base_url: "https://engine.example".to_string(),
+            ..LoadTestConfig::default()
+        };
+        assert_eq!(config.ws_url(), "wss://engine.example/ws");
+    }
+}
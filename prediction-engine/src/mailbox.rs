@@ -0,0 +1,262 @@
+//! Per-recipient mailboxes for MLS ciphertexts.
+//!
+//! The Node relay kept undelivered messages in memory and lost them on
+//! restart. The backend now enqueues each ciphertext here, one row per
+//! recipient, and clients page through their mailbox and acknowledge what
+//! they've processed. Delivery is at-least-once: a message is served on
+//! every fetch until it's acked, so a client that crashes mid-page just
+//! sees it again. Acks are idempotent.
+//!
+//! Retention is set by `MessagingConfig`: an unacked message expires
+//! `retention_days` after it was enqueued (never, when that is 0), and an
+//! acked one is deleted `acked_retention_hours` after its ack. Expiry is
+//! fixed at enqueue time, so a retention change applies to new messages.
+//! The engine only ever sees ciphertext.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::postgres::PgRow;
+use sqlx::{PgPool, Row};
+
+use crate::api_error::{coded, ErrorCode};
+use crate::config::MessagingConfig;
+
+pub const MAX_PAGE: i64 = 200;
+pub const MAX_RECIPIENTS: usize = 1000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueuedMessage {
+    pub id: i64,
+    pub recipient_user_id: i32,
+    pub sender_user_id: Option<i32>,
+    /// Hex-encoded MLS group id, when the sender gave one.
+    pub group_id: Option<String>,
+    /// Hex-encoded ciphertext, as enqueued.
+    pub message: String,
+    pub enqueued_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Fetches that have served this message, this one included.
+    pub delivery_count: i32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MailboxPage {
+    pub messages: Vec<QueuedMessage>,
+    /// Pass as `after` to fetch the next page.
+    pub next_after: Option<i64>,
+    pub has_more: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RetentionSweep {
+    /// Unacked messages dropped after `retention_days`.
+    pub expired: u64,
+    /// Acked messages deleted after `acked_retention_hours`.
+    pub acked: u64,
+}
+
+pub async fn ensure_mailbox_table(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS mls_mailbox_messages (
+            id BIGSERIAL PRIMARY KEY,
+            recipient_user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            sender_user_id INTEGER REFERENCES users(id) ON DELETE SET NULL,
+            group_id BYTEA,
+            message BYTEA NOT NULL,
+            enqueued_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            expires_at TIMESTAMPTZ,
+            delivery_count INTEGER NOT NULL DEFAULT 0,
+            last_delivered_at TIMESTAMPTZ,
+            acked_at TIMESTAMPTZ
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_mls_mailbox_pending
+         ON mls_mailbox_messages(recipient_user_id, id) WHERE acked_at IS NULL",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+fn queued_message(row: &PgRow) -> QueuedMessage {
+    QueuedMessage {
+        id: row.get("id"),
+        recipient_user_id: row.get("recipient_user_id"),
+        sender_user_id: row.get("sender_user_id"),
+        group_id: row.get::<Option<Vec<u8>>, _>("group_id").map(hex::encode),
+        message: hex::encode(row.get::<Vec<u8>, _>("message")),
+        enqueued_at: row.get("enqueued_at"),
+        expires_at: row.get("expires_at"),
+        delivery_count: row.get("delivery_count"),
+    }
+}
+
+async fn ensure_user(pool: &PgPool, user_id: i32) -> Result<()> {
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Err(coded(ErrorCode::NotFound, "User not found"));
+    }
+    Ok(())
+}
+
+/// Queues `message` once for each recipient, in one transaction.
+pub async fn enqueue(
+    pool: &PgPool,
+    config: &MessagingConfig,
+    sender_user_id: Option<i32>,
+    group_id: Option<&[u8]>,
+    recipient_user_ids: &[i32],
+    message: &[u8],
+) -> Result<Vec<QueuedMessage>> {
+    if message.is_empty() {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "message must not be empty",
+        ));
+    }
+    let mut recipients = recipient_user_ids.to_vec();
+    recipients.sort_unstable();
+    recipients.dedup();
+    if recipients.is_empty() || recipients.len() > MAX_RECIPIENTS {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("recipient_user_ids must name 1 to {} users", MAX_RECIPIENTS),
+        ));
+    }
+    let known: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE id = ANY($1)")
+        .bind(&recipients)
+        .fetch_one(pool)
+        .await?;
+    if known != recipients.len() as i64 {
+        return Err(coded(ErrorCode::NotFound, "User not found"));
+    }
+    if let Some(sender) = sender_user_id {
+        ensure_user(pool, sender).await?;
+    }
+
+    let retention_secs = (config.retention_days > 0.0).then_some(config.retention_days * 86400.0);
+    let rows = sqlx::query(
+        "INSERT INTO mls_mailbox_messages
+             (recipient_user_id, sender_user_id, group_id, message, expires_at)
+         SELECT recipient, $2, $3, $4, NOW() + make_interval(secs => $5)
+         FROM UNNEST($1::INTEGER[]) AS recipient
+         RETURNING *",
+    )
+    .bind(&recipients)
+    .bind(sender_user_id)
+    .bind(group_id)
+    .bind(message)
+    .bind(retention_secs)
+    .fetch_all(pool)
+    .await?;
+    let mut queued: Vec<QueuedMessage> = rows.iter().map(queued_message).collect();
+    queued.sort_by_key(|message| message.id);
+    Ok(queued)
+}
+
+/// The user's unacked, unexpired messages after id `after`, oldest first.
+/// Each message served counts as a delivery attempt.
+pub async fn fetch(pool: &PgPool, user_id: i32, after: i64, limit: i64) -> Result<MailboxPage> {
+    if !(1..=MAX_PAGE).contains(&limit) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("limit must be between 1 and {}", MAX_PAGE),
+        ));
+    }
+    ensure_user(pool, user_id).await?;
+    let rows = sqlx::query(
+        r#"
+        WITH page AS (
+            SELECT id FROM mls_mailbox_messages
+            WHERE recipient_user_id = $1
+              AND acked_at IS NULL
+              AND (expires_at IS NULL OR expires_at > NOW())
+              AND id > $2
+            ORDER BY id
+            LIMIT $3
+        )
+        UPDATE mls_mailbox_messages m
+        SET delivery_count = m.delivery_count + 1, last_delivered_at = NOW()
+        FROM page
+        WHERE m.id = page.id
+        RETURNING m.*
+        "#,
+    )
+    .bind(user_id)
+    .bind(after)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    let mut messages: Vec<QueuedMessage> = rows.iter().map(queued_message).collect();
+    messages.sort_by_key(|message| message.id);
+
+    let next_after = messages.last().map(|message| message.id);
+    let has_more: bool = sqlx::query_scalar(
+        "SELECT EXISTS(
+             SELECT 1 FROM mls_mailbox_messages
+             WHERE recipient_user_id = $1
+               AND acked_at IS NULL
+               AND (expires_at IS NULL OR expires_at > NOW())
+               AND id > $2
+         )",
+    )
+    .bind(user_id)
+    .bind(next_after.unwrap_or(after))
+    .fetch_one(pool)
+    .await?;
+    Ok(MailboxPage {
+        messages,
+        next_after,
+        has_more,
+    })
+}
+
+/// Acknowledges the user's messages among `message_ids`. Returns how many
+/// weren't acked already; other users' ids are ignored.
+pub async fn ack(pool: &PgPool, user_id: i32, message_ids: &[i64]) -> Result<u64> {
+    if message_ids.is_empty() || message_ids.len() > MAX_PAGE as usize {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("message_ids must name 1 to {} messages", MAX_PAGE),
+        ));
+    }
+    ensure_user(pool, user_id).await?;
+    Ok(sqlx::query(
+        "UPDATE mls_mailbox_messages SET acked_at = NOW()
+         WHERE recipient_user_id = $1 AND id = ANY($2) AND acked_at IS NULL",
+    )
+    .bind(user_id)
+    .bind(message_ids)
+    .execute(pool)
+    .await?
+    .rows_affected())
+}
+
+/// Deletes expired messages and acked ones past their retention.
+pub async fn sweep_retention(pool: &PgPool, config: &MessagingConfig) -> Result<RetentionSweep> {
+    let expired = sqlx::query(
+        "DELETE FROM mls_mailbox_messages
+         WHERE acked_at IS NULL AND expires_at <= NOW()",
+    )
+    .execute(pool)
+    .await?
+    .rows_affected();
+    let acked = sqlx::query(
+        "DELETE FROM mls_mailbox_messages
+         WHERE acked_at <= NOW() - make_interval(secs => $1)",
+    )
+    .bind(config.acked_retention_hours * 3600.0)
+    .execute(pool)
+    .await?
+    .rows_affected();
+    Ok(RetentionSweep { expired, acked })
+}
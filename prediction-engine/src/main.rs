@@ -1,16 +1,18 @@
 // Import the things we need
+use api_error::{api_error, ErrorCode};
 use axum::body::Body;
 use axum::extract::ws::{Message, WebSocket};
-use axum::http::{Method, Request, StatusCode};
+use axum::http::{HeaderMap, Method, Request, StatusCode};
 use axum::middleware::{self, Next};
 use axum::{
     extract::{Json as ExtractJson, Path, Query, State, WebSocketUpgrade},
     response::{IntoResponse, Json, Response},
-    routing::{get, post},
+    routing::{delete, get, post, put},
     Router,
 };
 use chrono;
 use futures_util::{sink::SinkExt, stream::StreamExt};
+use intellacc_math::scoring::log_loss;
 use moka::future::Cache;
 use serde::Deserialize;
 use serde_json::{json, Value};
@@ -22,19 +24,79 @@ use tokio::sync::broadcast;
 use tower_http::cors::CorsLayer;
 
 // Import our modules
+mod admin_audit;
+mod api_error;
+mod api_keys;
+mod arbitrage;
+mod archive;
+mod backtest;
+mod cache_warming;
+mod closing_soon;
+mod cluster_consistency;
+mod comment_buzz;
+mod competitions;
 mod config;
+mod consensus;
+mod dashboard;
 mod database;
 mod db_adapter;
+mod dead_letters;
+mod disputes;
+mod embargo;
+mod event_metadata;
+mod event_search;
+mod exposure;
+mod faucet;
+mod forecasts;
+mod group_directory;
+mod invariants;
+mod jobs;
+mod limit_orders;
+mod liquidity_migration;
+mod liquidity_recommendations;
+mod live_scores;
 mod lmsr_api; // Clean LMSR API using lmsr_core directly
 mod lmsr_core;
 mod lmsr_multi_core;
+mod mailbox;
+mod market_accuracy;
+mod market_cache;
+mod market_close;
+mod market_heat;
 mod market_import;
+mod market_partitions;
 mod metaculus; // Configuration management
+mod mls_delivery;
+mod notifications;
 mod numeric_transform;
+mod paper_predictions;
+mod peer_scores;
+mod price_history;
+mod push;
+mod rank_history;
+mod realized_pnl;
+mod resolution_preview;
 mod resolution_sync;
+mod risk;
+mod score_integrity;
+mod score_quota;
+mod source_status;
+mod sparklines;
+mod state_at;
+mod system_accounts;
+mod trade_privacy;
+mod user_predictions;
+mod user_provisioning;
+mod version;
+mod webhooks;
+mod ws_snapshot;
 
+#[cfg(test)]
+mod api_contract_tests;
 #[cfg(test)]
 mod integration_tests;
+#[cfg(test)]
+mod mls_e2e_tests;
 // Removed outdated tests.rs - lmsr_core.rs has comprehensive property-based tests
 
 // DRY helper types and functions
@@ -43,27 +105,31 @@ type ApiResult<T> = Result<Json<T>, (axum::http::StatusCode, Json<Value>)>;
 // Common error response helper
 fn internal_error(message: &str) -> (axum::http::StatusCode, Json<Value>) {
     eprintln!("{}", message);
-    (
-        axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-        Json(json!({"error": "Internal server error"})),
-    )
+    api_error(ErrorCode::Internal, "Internal server error")
+}
+
+// An engine error answers with the code it carries (insufficient balance,
+// market closed, a retryable 503 for lock contention, ...), and any other
+// as an internal error under `context`
+fn engine_error(context: &str, e: &anyhow::Error) -> (axum::http::StatusCode, Json<Value>) {
+    match api_error::coded_response(e) {
+        Some(response) => {
+            eprintln!("{}: {}", context, e);
+            response
+        }
+        None => internal_error(&format!("{}: {}", context, e)),
+    }
 }
 
 // User not found error
 fn not_found_error(entity: &str) -> (axum::http::StatusCode, Json<Value>) {
-    (
-        axum::http::StatusCode::NOT_FOUND,
-        Json(json!({"error": format!("{} not found", entity)})),
-    )
+    api_error(ErrorCode::NotFound, &format!("{} not found", entity))
 }
 
 // Bad request error for validation failures
 fn bad_request_error(message: &str) -> (axum::http::StatusCode, Json<Value>) {
     eprintln!("❌ Bad request: {}", message);
-    (
-        axum::http::StatusCode::BAD_REQUEST,
-        Json(json!({"error": message})),
-    )
+    api_error(ErrorCode::InvalidRequest, message)
 }
 
 async fn auth_guard(State(app_state): State<AppState>, req: Request<Body>, next: Next) -> Response {
@@ -80,104 +146,352 @@ async fn auth_guard(State(app_state): State<AppState>, req: Request<Body>, next:
         }
     }
 
-    (
-        StatusCode::UNAUTHORIZED,
-        Json(json!({"error": "Unauthorized"})),
+    // 2. Check for x-api-key (bot traders acting as their own user)
+    if let Some(raw) = req.headers().get("x-api-key").and_then(|v| v.to_str().ok()) {
+        let default_limit = app_state.config.market.api_key_rate_limit_per_minute;
+        match api_keys::authenticate(&app_state.db, raw, default_limit).await {
+            Ok(Some(key)) => return api_key_guard(app_state, key, req, next).await,
+            Ok(None) => {}
+            Err(e) => {
+                let (status, body) = engine_error("❌ API key lookup failed", &e);
+                return (status, body).into_response();
+            }
+        }
+    }
+
+    api_error(ErrorCode::Unauthorized, "Unauthorized").into_response()
+}
+
+// Lets a verified API key through if its scope covers the route, it is
+// under its rate limit, and every user the request names is the key's own.
+// The JSON body is read for its user_id and handed on unchanged.
+async fn api_key_guard(
+    app_state: AppState,
+    key: api_keys::ApiKey,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let record = |rejected: bool| {
+        let pool = app_state.db.clone();
+        let key_id = key.id;
+        tokio::spawn(async move {
+            if let Err(e) = api_keys::record_usage(&pool, key_id, rejected).await {
+                eprintln!("❌ Failed to record API key usage: {}", e);
+            }
+        });
+    };
+    let forbidden = |message: String| {
+        record(true);
+        api_error(ErrorCode::Forbidden, &message).into_response()
+    };
+
+    let path = req.uri().path().to_string();
+    match api_keys::required_scope(req.method(), &path) {
+        Some(scope) if scope <= key.scope => {}
+        Some(_) => return forbidden("This API key is read-only".to_string()),
+        None => return forbidden("This endpoint is not available to API keys".to_string()),
+    }
+
+    let Some(remaining) = app_state.api_key_limiter.check(&key).await else {
+        record(true);
+        return api_error(
+            ErrorCode::RateLimited,
+            &format!(
+                "API key rate limit: {} requests per minute",
+                key.rate_limit_per_minute
+            ),
+        )
+        .into_response();
+    };
+
+    let is_owner = |id: &str| id.trim().parse::<i32>().ok() == Some(key.user_id);
+    let not_owner = || forbidden(format!("This API key acts only for user {}", key.user_id));
+    if api_keys::path_user_id(&path).is_some_and(|id| !is_owner(id)) {
+        return not_owner();
+    }
+    let query_user = Query::<HashMap<String, String>>::try_from_uri(req.uri())
+        .ok()
+        .and_then(|Query(params)| params.get("user_id").cloned());
+    if query_user.is_some_and(|id| !is_owner(&id)) {
+        return not_owner();
+    }
+
+    let req = if req.method() == Method::GET {
+        req
+    } else {
+        let (parts, body) = req.into_parts();
+        let bytes = match axum::body::to_bytes(body, 1024 * 1024).await {
+            Ok(bytes) => bytes,
+            Err(_) => return forbidden("Request body is too large".to_string()),
+        };
+        let body_user = serde_json::from_slice::<Value>(&bytes)
+            .ok()
+            .and_then(|body| body.get("user_id").and_then(|id| id.as_i64()));
+        if body_user != Some(key.user_id as i64) {
+            return not_owner();
+        }
+        Request::from_parts(parts, Body::from(bytes))
+    };
+
+    record(false);
+    let mut response = next.run(req).await;
+    let headers = response.headers_mut();
+    headers.insert("x-ratelimit-limit", key.rate_limit_per_minute.into());
+    headers.insert("x-ratelimit-remaining", remaining.into());
+    response
+}
+
+// Logs admin actions to admin_audit with what they targeted before and
+// after. The JSON body is read for the log and handed on unchanged.
+async fn admin_audit_guard(
+    State(app_state): State<AppState>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let Some((action, target)) = admin_audit::admin_action(req.method(), req.uri().path()) else {
+        return next.run(req).await;
+    };
+    let (parts, body) = req.into_parts();
+    let bytes = match axum::body::to_bytes(body, 1024 * 1024).await {
+        Ok(bytes) => bytes,
+        Err(_) => return bad_request_error("Request body is too large").into_response(),
+    };
+    let params = serde_json::from_slice::<Value>(&bytes).ok();
+    let method = parts.method.to_string();
+    let path = parts.uri.path().to_string();
+    let query = parts.uri.query().map(str::to_string);
+    let actor = admin_audit::request_actor(
+        parts
+            .headers
+            .get("x-admin-actor")
+            .and_then(|v| v.to_str().ok()),
+        params.as_ref(),
+        query.as_deref(),
     )
-        .into_response()
+    .map(str::to_string);
+    let before = match admin_audit::snapshot(&app_state.db, &target, query.as_deref()).await {
+        Ok(before) => before,
+        Err(e) => return engine_error("Admin audit snapshot error", &e).into_response(),
+    };
+
+    // Fails closed before the action runs: one that can't be logged doesn't run
+    let pending = admin_audit::Pending {
+        action,
+        actor: actor.as_deref(),
+        method: &method,
+        path: &path,
+        query: query.as_deref(),
+        params: params.as_ref(),
+        target: &target,
+        before: before.as_ref(),
+    };
+    let id = match admin_audit::open(&app_state.db, &pending).await {
+        Ok(id) => id,
+        Err(e) => return engine_error("Admin audit error", &e).into_response(),
+    };
+
+    let response = next
+        .run(Request::from_parts(parts, Body::from(bytes)))
+        .await;
+    // Once it has run, its response stands; the entry stays pending if it
+    // can't be completed
+    let status = response.status().as_u16();
+    if let Err(e) = admin_audit::finish(&app_state.db, id, &target, query.as_deref(), status).await
+    {
+        eprintln!(
+            "❌ Admin action {} on {} finished with status {} but audit entry {} is still pending: {}",
+            action, path, status, id, e
+        );
+    }
+    response
 }
 
 // Cache and broadcast helper for score updates
 fn invalidate_and_broadcast(app_state: &AppState, event_type: &str, data: Value) {
+    ws_snapshot::invalidate();
     app_state.cache.invalidate_all();
     let msg = json!({
         "type": event_type,
         "data": data,
-        "timestamp": chrono::Utc::now()
+        "timestamp": chrono::Utc::now(),
+        "engine": version::BUILD
     })
     .to_string();
-    let _ = app_state.tx.send(msg);
+    publish(app_state, msg);
+}
+
+// Tracks a long-running admin job, broadcasting its progress on the jobs topic
+fn start_job(app_state: &AppState, kind: &str) -> jobs::JobProgress {
+    let app_state = app_state.clone();
+    jobs::JobProgress::start(kind, move |msg| publish(&app_state, msg))
+}
+
+// Send to every connection, dead-lettering what no receiver took
+fn publish(app_state: &AppState, msg: String) {
+    let undelivered = app_state.in_flight.send(&app_state.tx, msg);
+    if !undelivered.is_empty() {
+        let pool = app_state.analytics_db.clone();
+        let retention_hours = app_state.config.market.dead_letter_retention_hours;
+        tokio::spawn(async move {
+            for (msg, reason) in undelivered {
+                if let Err(e) = dead_letters::record(&pool, &msg, reason, retention_hours).await {
+                    eprintln!("❌ Failed to store undelivered broadcast: {}", e);
+                }
+            }
+        });
+    }
+}
+
+// Send each user their own notice on their topic, unless they have turned
+// `kind` off. A failed preference lookup tells no one rather than risk
+// ignoring an opt-out. Returns who was told, for webhook payloads.
+async fn notify_users(
+    app_state: &AppState,
+    kind: notifications::Notification,
+    event_type: &str,
+    notices: Vec<(i32, Value)>,
+) -> Vec<i32> {
+    let user_ids: Vec<i32> = notices.iter().map(|(user_id, _)| *user_id).collect();
+    let recipients = match notifications::recipients(&app_state.db, kind, &user_ids).await {
+        Ok(recipients) => recipients,
+        Err(e) => {
+            eprintln!("❌ Notification preference lookup failed: {}", e);
+            return Vec::new();
+        }
+    };
+    for (user_id, data) in notices {
+        if recipients.binary_search(&user_id).is_err() {
+            continue;
+        }
+        let msg = json!({
+            "type": event_type,
+            "topic": notifications::user_topic(user_id),
+            "data": data,
+            "timestamp": chrono::Utc::now(),
+            "engine": version::BUILD
+        })
+        .to_string();
+        publish(app_state, msg);
+    }
+    recipients
+}
+
+// Trade broadcasts name the trader except on anonymous markets. If the flag
+// can't be read the trader is left out rather than risk exposing them. Runs
+// once the trade has committed, so the new market state is written through
+// to the cache first and clients reacting to the broadcast read it.
+async fn broadcast_trade(app_state: &AppState, event_type: &str, mut data: Value) {
+    let event_id = data["event_id"].as_i64().unwrap_or_default() as i32;
+    if let Err(e) = app_state
+        .market_cache
+        .write_through(&app_state.db, event_id)
+        .await
+    {
+        eprintln!(
+            "❌ Market state write-through failed for event {}: {}",
+            event_id, e
+        );
+    }
+    let anonymous = trade_privacy::is_anonymous(&app_state.db, event_id)
+        .await
+        .unwrap_or(true);
+    if anonymous {
+        if let Some(fields) = data.as_object_mut() {
+            fields.remove("user_id");
+        }
+    }
+    invalidate_and_broadcast(app_state, event_type, data);
+    cluster_consistency::warn_after_trade(&app_state.analytics_db, event_id);
+}
+
+// Resolution broadcasts carry each holder's settlement, which names them and
+// their position, so anonymous markets leave the payouts out. Each holder
+// who takes resolution notices also gets one on their own topic, with their
+// payout unless the market is anonymous. Returns who was told.
+async fn broadcast_resolution(
+    app_state: &AppState,
+    event_type: &str,
+    event_id: i32,
+    mut data: Value,
+    payouts: &[lmsr_api::ResolutionPayout],
+) -> Vec<i32> {
+    let anonymous = trade_privacy::is_anonymous(&app_state.db, event_id)
+        .await
+        .unwrap_or(true);
+    let notices = payouts
+        .iter()
+        .map(|payout| {
+            let mut notice = data.clone();
+            if !anonymous {
+                notice["payout"] = json!(payout);
+            }
+            (payout.user_id, notice)
+        })
+        .collect();
+    if !anonymous {
+        if let Some(fields) = data.as_object_mut() {
+            fields.insert("payouts".to_string(), json!(payouts));
+        }
+    }
+    invalidate_and_broadcast(app_state, event_type, data);
+    notify_users(
+        app_state,
+        notifications::Notification::Resolution,
+        event_type,
+        notices,
+    )
+    .await
+}
+
+// A resolution's scores as they land: a count for everyone (naming no one),
+// and each forecaster's own update on their topic
+async fn broadcast_score_updates(
+    app_state: AppState,
+    event_id: i32,
+    updates: Vec<live_scores::ScoreUpdate>,
+) {
+    invalidate_and_broadcast(
+        &app_state,
+        "scoresUpdated",
+        json!({ "event_id": event_id, "scored_predictions": updates.len() }),
+    );
+    let notices = updates
+        .into_iter()
+        .map(|update| (update.user_id, json!(update)))
+        .collect();
+    notify_users(
+        &app_state,
+        notifications::Notification::Resolution,
+        "scoreUpdate",
+        notices,
+    )
+    .await;
 }
 
 // Global state for WebSocket broadcasting and caching
 #[derive(Clone)]
 struct AppState {
+    // Trades and other writes
     db: PgPool,
+    // Read-heavy reports, sized and timed out separately
+    analytics_db: PgPool,
     tx: broadcast::Sender<String>,
+    in_flight: dead_letters::InFlight,
     cache: Cache<String, String>,
+    market_cache: market_cache::MarketStateCache,
+    sparkline_cache: sparklines::SparklineCache,
     config: config::Config,
     auth_token: Option<String>,
+    api_key_limiter: api_keys::RateLimiter,
 }
 
-// This is our main function - but notice the #[tokio::main] attribute!
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    // Load environment variables from .env file
-    dotenv::dotenv().ok();
-
-    // Install tracing subscriber for structured logging
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
-        )
-        .init();
-
-    println!("🦀 Starting Prediction Engine...");
-
-    // Load configuration from environment
-    let config = config::Config::from_env();
-    config.print_config();
-
-    // Get database URL from environment variable
-    let database_url = std::env::var("DATABASE_URL").unwrap_or_else(|_| {
-        "postgres://intellacc_user:supersecretpassword@db:5432/intellaccdb".to_string()
-    });
-
-    println!(
-        "🔌 Connecting to database: {}",
-        database_url.replace(
-            &std::env::var("POSTGRES_PASSWORD").unwrap_or_default(),
-            "***"
-        )
-    );
-
-    // Connect to PostgreSQL database
-    let pool = database::create_pool(&database_url).await?;
-
-    // Create broadcast channel for real-time updates
-    let (tx, _rx) = broadcast::channel::<String>(100);
-
-    // Create cache for performance optimization
-    let cache = Cache::builder()
-        .max_capacity(1000)
-        .time_to_live(Duration::from_secs(300)) // 5 minutes TTL
-        .time_to_idle(Duration::from_secs(60)) // 1 minute idle timeout
-        .build();
-
-    // Create shared app state
-    let auth_token = std::env::var("PREDICTION_ENGINE_AUTH_TOKEN")
-        .ok()
-        .map(|value| value.trim().to_string())
-        .filter(|value| !value.is_empty());
-    if auth_token.is_none() {
-        return Err(anyhow::anyhow!(
-            "PREDICTION_ENGINE_AUTH_TOKEN is required for prediction-engine startup"
-        ));
-    }
-
-    let app_state = AppState {
-        db: pool,
-        tx: tx.clone(),
-        cache,
-        config,
-        auth_token,
-    };
-
-    // Create our web application routes with shared state.
-    let app = Router::new()
+// Every route plus auth and CORS layers; split out of main so the API
+// contract tests can drive the same router in-process.
+fn build_router(app_state: AppState) -> Router {
+    Router::new()
         .route("/", get(hello_world))
         .route("/health", get(health_check))
+        .route("/version", get(version_endpoint))
         .route(
             "/persuasion/score-mature-episodes",
             post(score_mature_persuasion_episodes_endpoint),
@@ -190,45 +504,258 @@ async fn main() -> anyhow::Result<()> {
             get(manual_limited_import_endpoint),
         )
         .route("/metaculus/sync-categories", get(manual_category_sync))
+        .route(
+            "/metaculus/bulk-import/pause",
+            post(pause_bulk_import_endpoint),
+        )
+        .route(
+            "/metaculus/import-progress",
+            get(metaculus_import_progress_endpoint),
+        )
         .route("/imports/sync-all", post(sync_all_imports_endpoint))
         .route("/resolutions/sync", post(resolution_sync_endpoint))
+        .route(
+            "/resolutions/backfill-metaculus",
+            post(metaculus_backfill_endpoint),
+        )
         .route(
             "/imports/sync/:provider",
             post(sync_provider_import_endpoint),
         )
         .route("/imports/status", get(import_status_endpoint))
+        .route("/imports/source-status", get(source_status_report_endpoint))
+        .route("/webhooks/deliveries", get(webhook_deliveries_endpoint))
         // LMSR Market API endpoints
         .route("/events", get(get_events_endpoint))
+        .route("/events/search", get(search_events_endpoint))
+        .route("/events/closing-soon", get(closing_soon_endpoint))
+        .route("/events/stream/backfill", get(stream_backfill_endpoint))
+        .route("/markets", post(create_market_endpoint))
+        .route("/markets/close-sweep", post(close_sweep_endpoint))
+        .route("/markets/sparklines", get(sparklines_endpoint))
+        .route("/markets/trending", get(trending_markets_endpoint))
+        .route("/faucet/sweep", post(faucet_sweep_endpoint))
+        .route("/users/provision", post(provision_user_endpoint))
+        .route("/mls/groups", post(register_mls_group_endpoint))
+        .route("/mls/messages", post(submit_mls_message_endpoint))
+        .route(
+            "/mls/groups/:group_id/commits",
+            get(mls_group_commits_endpoint).post(sequence_mls_commit_endpoint),
+        )
+        .route("/mls/welcomes", post(store_mls_welcome_endpoint))
+        .route("/messages/enqueue", post(enqueue_messages_endpoint))
+        .route(
+            "/messages/retention/sweep",
+            post(mailbox_retention_sweep_endpoint),
+        )
+        .route("/users/:id/mailbox", get(user_mailbox_endpoint))
+        .route("/users/:id/mailbox/ack", post(ack_mailbox_endpoint))
+        .route("/push/dispatch", post(push_dispatch_endpoint))
+        .route(
+            "/users/:id/limit-orders",
+            get(list_limit_orders_endpoint).post(place_limit_order_endpoint),
+        )
+        .route(
+            "/users/:id/limit-orders/:order_id",
+            delete(cancel_limit_order_endpoint),
+        )
+        .route(
+            "/users/:id/push-devices",
+            get(list_push_devices_endpoint).post(register_push_device_endpoint),
+        )
+        .route(
+            "/users/:id/push-devices/:device_id",
+            delete(remove_push_device_endpoint),
+        )
+        .route(
+            "/mls/welcomes/:key_package_ref",
+            get(mls_welcomes_endpoint),
+        )
+        .route(
+            "/directory/groups/:app_group_id",
+            get(directory_listing_endpoint).post(publish_directory_group_endpoint),
+        )
+        .route(
+            "/directory/groups/:app_group_id/invites",
+            post(mint_directory_invite_endpoint),
+        )
+        .route(
+            "/directory/groups/:app_group_id/invites/:invite_id",
+            delete(revoke_directory_invite_endpoint),
+        )
+        .route(
+            "/directory/groups/:app_group_id/welcomes",
+            post(post_directory_welcome_endpoint),
+        )
+        .route(
+            "/directory/groups/:app_group_id/welcomes/:key_package_ref",
+            get(directory_welcomes_endpoint),
+        )
+        .route("/forecasts/compact", post(forecast_compaction_endpoint))
+        .route("/consensus/refresh", post(consensus_refresh_endpoint))
+        .route("/consensus/accuracy", get(consensus_accuracy_endpoint))
+        .route("/analytics/market-accuracy", get(market_accuracy_endpoint))
+        .route("/analytics/arbitrage", get(arbitrage_endpoint))
+        .route(
+            "/liquidity/recommendations",
+            get(liquidity_recommendations_endpoint),
+        )
+        .route(
+            "/liquidity/recommendations/refresh",
+            post(liquidity_recommendations_refresh_endpoint),
+        )
+        .route(
+            "/liquidity/recommendations/:category",
+            put(liquidity_auto_apply_endpoint),
+        )
+        .route("/scores/audit", post(score_audit_endpoint))
+        .route("/scores/reseal", post(score_reseal_endpoint))
+        .route("/comments/ingest", post(comment_ingest_endpoint))
+        .route("/admin/audit", get(admin_audit_endpoint))
+        .route("/admin/cache/warm", post(cache_warm_endpoint))
+        .route("/admin/backtest", post(backtest_endpoint))
+        .route("/archive/run", post(archive_run_endpoint))
+        .route("/archive/status", get(archive_status_endpoint))
+        .route("/partitions/market-updates", get(partition_status_endpoint))
+        .route(
+            "/partitions/market-updates/maintain",
+            post(partition_maintenance_endpoint),
+        )
         .route("/events/:id/market", get(get_market_state_endpoint))
         .route("/events/:id/trades", get(get_event_trades_endpoint))
+        .route("/events/:id/state-at", get(market_state_at_endpoint))
+        .route("/events/:id/history", get(price_history_endpoint))
+        .route(
+            "/events/:id/trades/identified",
+            post(identified_trades_endpoint),
+        )
+        .route(
+            "/events/:id/metadata",
+            get(get_event_metadata_endpoint).put(update_event_metadata_endpoint),
+        )
+        .route("/events/:id/privacy", put(set_event_privacy_endpoint))
+        .route("/events/:id/archive", post(archive_event_endpoint))
+        .route("/events/:id/archive/restore", post(restore_event_endpoint))
+        .route(
+            "/events/:id/privacy/audit",
+            get(trade_identity_audit_endpoint),
+        )
+        .route(
+            "/events/:id/embargoes",
+            get(list_embargoes_endpoint).post(add_embargo_endpoint),
+        )
+        .route(
+            "/events/:id/embargoes/:embargo_id",
+            delete(remove_embargo_endpoint),
+        )
+        .route("/events/:id/liquidity", post(migrate_liquidity_endpoint))
+        .route(
+            "/events/:id/liquidity/migrations",
+            get(liquidity_migrations_endpoint),
+        )
+        .route(
+            "/events/:id/source-status",
+            get(event_source_status_endpoint),
+        )
         .route("/events/:id/update", post(update_market_endpoint))
+        .route("/events/:id/trade", post(update_market_endpoint))
         .route(
             "/events/:id/update-outcome",
             post(update_market_outcome_endpoint),
         )
         .route("/events/:id/kelly", get(kelly_suggestion_endpoint))
         .route("/events/:id/sell", post(sell_shares_endpoint))
+        .route("/events/:id/close", post(close_position_endpoint))
+        .route("/events/:id/sell-quote", get(sell_quote_endpoint))
+        .route("/events/:id/quote", get(trade_quote_endpoint))
+        .route("/events/:id/depth", get(market_depth_endpoint))
         .route(
             "/events/:id/sell-outcome",
             post(sell_outcome_shares_endpoint),
         )
+        .route("/events/:id/numeric-quote", get(numeric_quote_endpoint))
+        .route("/events/:id/numeric-trade", post(numeric_trade_endpoint))
+        .route("/events/:id/numeric-sell", post(numeric_sell_endpoint))
+        .route("/events/:id/numeric-bucket-buy", post(numeric_bucket_buy_endpoint))
+        .route("/events/:id/numeric-bucket-sell", post(numeric_bucket_sell_endpoint))
+        .route("/events/:id/distribution", get(numeric_distribution_endpoint))
         .route(
-            "/events/:id/numeric-quote",
-            get(numeric_quote_endpoint),
+            "/events/:id/market-resolve",
+            post(resolve_market_event_endpoint),
         )
         .route(
-            "/events/:id/numeric-trade",
-            post(numeric_trade_endpoint),
+            "/events/:id/market-resolve/preview",
+            post(preview_resolution_endpoint).get(pending_resolution_endpoint),
         )
         .route(
-            "/events/:id/numeric-sell",
-            post(numeric_sell_endpoint),
+            "/events/:id/market-resolve/commit",
+            post(commit_resolution_endpoint),
         )
         .route(
-            "/events/:id/market-resolve",
-            post(resolve_market_event_endpoint),
+            "/events/:id/market-resolve/cancel",
+            post(cancel_resolution_endpoint),
+        )
+        .route("/events/:id/dispute", post(dispute_resolution_endpoint))
+        .route(
+            "/events/:id/resolution-history",
+            get(resolution_history_endpoint),
         )
         .route("/events/:id/shares", get(get_user_shares_endpoint))
+        .route(
+            "/events/:id/paper-prediction",
+            post(paper_prediction_endpoint),
+        )
+        .route(
+            "/users/:id/paper-predictions",
+            get(user_paper_predictions_endpoint),
+        )
+        .route(
+            "/events/:id/forecast",
+            post(submit_forecast_endpoint).put(update_forecast_endpoint),
+        )
+        .route(
+            "/user/:id/events/:event_id/forecast-history",
+            get(forecast_history_endpoint),
+        )
+        .route("/users/:id/portfolio", get(user_portfolio_endpoint))
+        .route("/users/:id/pnl", get(user_pnl_endpoint))
+        .route(
+            "/users/:id/shares/:event_id",
+            get(user_event_shares_endpoint),
+        )
+        .route("/user/:id/dashboard", get(user_dashboard_endpoint))
+        .route("/user/:id/exposure", get(user_exposure_endpoint))
+        .route("/user/:id/faucet", get(user_faucet_endpoint))
+        .route(
+            "/user/:id/preferences",
+            get(user_preferences_endpoint).put(update_user_preferences_endpoint),
+        )
+        .route("/user/:id/predictions", get(user_predictions_endpoint))
+        .route("/user/:id/rank-history", get(user_rank_history_endpoint))
+        .route("/user/:id/risk", get(user_risk_endpoint))
+        .route(
+            "/users/:id/api-keys",
+            get(list_api_keys_endpoint).post(create_api_key_endpoint),
+        )
+        .route(
+            "/users/:id/api-keys/:key_id",
+            delete(revoke_api_key_endpoint),
+        )
+        .route(
+            "/event-clusters",
+            get(list_event_clusters_endpoint).post(create_event_cluster_endpoint),
+        )
+        .route("/clusters/:id", get(cluster_endpoint))
+        .route("/competitions", post(create_competition_endpoint))
+        .route("/competitions/:id/join", post(join_competition_endpoint))
+        .route(
+            "/competitions/:id/markets",
+            post(add_competition_market_endpoint),
+        )
+        .route(
+            "/competitions/:id/leaderboard",
+            get(competition_leaderboard_endpoint),
+        )
         .route("/lmsr/test-invariants", get(test_lmsr_invariants_endpoint))
         // Invariant verification endpoints
         .route(
@@ -247,6 +774,12 @@ async fn main() -> anyhow::Result<()> {
             "/lmsr/verify-consistency",
             post(verify_consistency_endpoint),
         )
+        .route("/lmsr/invariant-stats", get(invariant_stats_endpoint))
+        .route("/lmsr/solvency", get(solvency_endpoint))
+        .layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            admin_audit_guard,
+        ))
         .layer(middleware::from_fn_with_state(
             app_state.clone(),
             auth_guard,
@@ -257,39 +790,595 @@ async fn main() -> anyhow::Result<()> {
                 .allow_methods(tower_http::cors::Any)
                 .allow_headers(tower_http::cors::Any),
         )
-        .with_state(app_state); // Share app state with all routes
+        .with_state(app_state) // Share app state with all routes
+}
 
-    // Define the address to listen on - bind to all interfaces in Docker
-    let addr = SocketAddr::from(([0, 0, 0, 0], 3001));
+// This is our main function - but notice the #[tokio::main] attribute!
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    // Load environment variables from .env file
+    dotenv::dotenv().ok();
 
-    println!("🚀 Server running on http://{}", addr);
-    println!("📊 Available endpoints (LMSR + persuasion services):");
-    println!("  GET /health - Health check");
-    println!("  POST /persuasion/score-mature-episodes - Score mature persuasive-alpha episode components");
-    println!("  GET /metaculus/sync - Manual sync with Metaculus API (150 recent questions)");
-    println!("  GET /metaculus/bulk-import - Complete import of ALL Metaculus questions");
-    println!("  GET /metaculus/sync-categories - Manual category sync");
-    println!("  POST /imports/sync-all - Sync all configured external market providers");
-    println!(
-        "  POST /imports/sync/:provider - Sync one provider (metaculus|manifold|polymarket|kalshi)"
-    );
-    println!("  GET /imports/status - Recent provider sync runs");
-    println!("  GET /events/:id/market - Get market state for event");
-    println!("  GET /events/:id/trades - Get recent trades for event");
-    println!("  POST /events/:id/update - Update market with stake");
-    println!("  POST /events/:id/update-outcome - Update N-outcome market with stake");
-    println!("  GET /events/:id/kelly - Get Kelly criterion suggestion");
-    println!("  POST /events/:id/sell - Sell shares back to market");
-    println!("  POST /events/:id/sell-outcome - Sell shares of an N-outcome market outcome");
-    println!("  GET /events/:id/numeric-quote - Read-only quote for a numeric-market target distribution");
+    // Install tracing subscriber for structured logging
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+        )
+        .init();
+
+    println!("🦀 Starting Prediction Engine...");
+
+    // Load configuration from environment
+    let config = config::Config::from_env();
+    config.print_config();
+    webhooks::configure(config.webhooks.clone());
+
+    // Get database URL from environment variable
+    let database_url = std::env::var("DATABASE_URL").unwrap_or_else(|_| {
+        "postgres://intellacc_user:supersecretpassword@db:5432/intellaccdb".to_string()
+    });
+
+    println!(
+        "🔌 Connecting to database: {}",
+        database_url.replace(
+            &std::env::var("POSTGRES_PASSWORD").unwrap_or_default(),
+            "***"
+        )
+    );
+
+    // Connect to PostgreSQL database
+    let pools = database::create_pools(&database_url, &config.database).await?;
+    let pool = pools.trading;
+    let backend = db_adapter::configure_tx_backend(&pool, &config.database.dialect).await?;
+    println!("🗄️ Transactions run on {:?}", backend.dialect());
+
+    // Create broadcast channel for real-time updates
+    let (tx, _rx) = broadcast::channel::<String>(dead_letters::BROADCAST_CAPACITY);
+
+    // Create cache for performance optimization
+    let cache = Cache::builder()
+        .max_capacity(1000)
+        .time_to_live(Duration::from_secs(300)) // 5 minutes TTL
+        .time_to_idle(Duration::from_secs(60)) // 1 minute idle timeout
+        .build();
+
+    // Create shared app state
+    let auth_token = std::env::var("PREDICTION_ENGINE_AUTH_TOKEN")
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty());
+    if auth_token.is_none() {
+        return Err(anyhow::anyhow!(
+            "PREDICTION_ENGINE_AUTH_TOKEN is required for prediction-engine startup"
+        ));
+    }
+
+    // Trade guards read events.closed_at, so it must exist before serving
+    market_close::ensure_closed_at_column(&pool).await?;
+    // ...and events.competition_id, which picks the wallet a trade settles to
+    competitions::ensure_competition_schema(&pool).await?;
+    // ...and events.anonymous_trading, read before every trade broadcast
+    trade_privacy::ensure_privacy_schema(&pool).await?;
+    // ...and event_embargoes, which pause trading around announcements
+    embargo::ensure_embargo_table(&pool).await?;
+    // ...and events.forecast_only, set on imports that carry no market
+    market_import::ensure_forecast_only_column(&pool).await?;
+    dead_letters::ensure_dead_letter_table(&pool).await?;
+    market_cache::ensure_notify_triggers(&pool).await?;
+    live_scores::ensure_resolution_trigger(&pool).await?;
+    live_scores::ensure_watermark_table(&pool).await?;
+    sparklines::ensure_sparkline_index(&pool).await?;
+    event_search::ensure_search_schema(&pool).await?;
+    // /events and market state read the metadata columns
+    event_metadata::ensure_metadata_schema(&pool).await?;
+    closing_soon::ensure_reminder_table(&pool).await?;
+    // Resolution writes crowd stats, so the table must exist before serving
+    peer_scores::ensure_stats_table(&pool).await?;
+    // Every forecast records a revision, and scoring reads them back
+    forecasts::ensure_forecast_tables(&pool).await?;
+    // ...and consensus_accuracy, and market state reads events.weighted_prob
+    consensus::ensure_consensus_schema(&pool).await?;
+    // ...and reads comment velocity; its trigger needs market_cache's function
+    comment_buzz::ensure_comment_stats_table(&pool).await?;
+    resolution_preview::ensure_pending_table(&pool).await?;
+    // The auth guard looks keys up on every keyed request
+    api_keys::ensure_api_key_tables(&pool).await?;
+    faucet::ensure_faucet_schema(&pool).await?;
+    // The matcher reads it after every binary trade, and its reserves count
+    // as users' RP in the opening balance
+    limit_orders::ensure_limit_orders_table(&pool).await?;
+    // Books whatever users hold by now as the house's opening balance
+    system_accounts::ensure_system_accounts(&pool).await?;
+    // Sells, settlements and dispute reverts add to it
+    realized_pnl::ensure_realized_pnl_table(&pool).await?;
+    // Trade tapes, market state and risk read the archive views, which need
+    // resolution_payouts in place first
+    disputes::ensure_dispute_tables(&pool).await?;
+    // Rebuilds market_updates and the views over it, so it runs once they exist
+    if config.market.partition_market_updates {
+        if let Some(conversion) =
+            market_partitions::ensure_partitioned(&pool, config.market.partition_months_ahead)
+                .await?
+        {
+            println!("🗂️ Partitioned market_updates by month");
+            for index in &conversion.widened_unique {
+                println!("   {} now includes created_at", index);
+            }
+            for dropped in &conversion.dropped_foreign_keys {
+                eprintln!(
+                    "⚠️  Dropped foreign key {} on {} ({}): a partitioned market_updates can't be referenced by id",
+                    dropped.constraint, dropped.table, dropped.definition
+                );
+            }
+        }
+    }
+    archive::ensure_archive_schema(&pool).await?;
+    // Its backfill counts trades through the archive views
+    market_accuracy::ensure_market_accuracy_table(&pool).await?;
+    // ...as does this one's, which loads the buys journaled before it
+    price_history::ensure_price_history(&pool).await?;
+    liquidity_recommendations::ensure_recommendations_table(&pool).await?;
+    liquidity_migration::ensure_migrations_table(&pool).await?;
+    notifications::ensure_preferences_table(&pool).await?;
+    score_integrity::ensure_checksums_table(&pool).await?;
+    mls_delivery::ensure_delivery_tables(&pool).await?;
+    mailbox::ensure_mailbox_table(&pool).await?;
+    push::ensure_push_tables(&pool).await?;
+    group_directory::ensure_directory_tables(&pool).await?;
+    exposure::ensure_cluster_tables(&pool).await?;
+    // Its rows reference event_clusters, created just above
+    arbitrage::ensure_arbitrage_table(&pool).await?;
+    market_heat::ensure_market_heat_table(&pool).await?;
+    admin_audit::ensure_admin_audit_table(&pool).await?;
+    // Snapshot ranks so the first resolution records who it moved
+    rank_history::ensure_rank_history_tables(&pool).await?;
+    rank_history::update_global_rankings(&pool, None).await?;
+
+    let app_state = AppState {
+        db: pool,
+        analytics_db: pools.analytics,
+        tx: tx.clone(),
+        in_flight: dead_letters::InFlight::default(),
+        cache,
+        market_cache: market_cache::MarketStateCache::new(
+            config.market.market_state_cache_ttl_secs,
+        ),
+        sparkline_cache: sparklines::SparklineCache::default(),
+        config,
+        auth_token,
+        api_key_limiter: api_keys::RateLimiter::default(),
+    };
+
+    // Evict cached market state when events change outside this process
+    if app_state.config.market.market_state_cache_ttl_secs > 0 {
+        let listener_pool = app_state.db.clone();
+        let listener_cache = app_state.market_cache.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) =
+                    market_cache::listen_for_changes(&listener_pool, listener_cache.clone()).await
+                {
+                    eprintln!("❌ Market state listener stopped: {}", e);
+                }
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+        });
+    }
+
+    // Push score updates as predictions resolve
+    if app_state.config.market.live_score_updates {
+        let listener_state = app_state.clone();
+        tokio::spawn(async move {
+            loop {
+                let result = live_scores::listen_for_resolutions(
+                    &listener_state.db,
+                    &listener_state.config.score_quota,
+                    |event_id, updates| {
+                        broadcast_score_updates(listener_state.clone(), event_id, updates)
+                    },
+                )
+                .await;
+                if let Err(e) = result {
+                    eprintln!("❌ Live score listener stopped: {}", e);
+                }
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+        });
+    }
+
+    // Close markets as they pass their closing_date
+    let sweep_secs = app_state.config.market.close_sweep_interval_secs;
+    if sweep_secs > 0 {
+        let sweep_state = app_state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(sweep_secs));
+            loop {
+                interval.tick().await;
+                if let Err(e) = run_close_sweep(&sweep_state).await {
+                    eprintln!("❌ Market close sweep failed: {}", e);
+                }
+                if let Err(e) = run_closing_reminders(&sweep_state).await {
+                    eprintln!("❌ Closing reminders failed: {}", e);
+                }
+            }
+        });
+    }
+
+    // Credit onboarding grants and faucet top-ups
+    let faucet_secs = app_state.config.faucet.sweep_interval_secs;
+    if faucet_secs > 0 {
+        let faucet_state = app_state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(faucet_secs));
+            loop {
+                interval.tick().await;
+                if let Err(e) = run_faucet_sweep(&faucet_state).await {
+                    eprintln!("❌ Faucet sweep failed: {}", e);
+                }
+            }
+        });
+    }
+
+    // Drop expired and long-acked mailbox messages
+    let mailbox_secs = app_state.config.messaging.sweep_interval_secs;
+    if mailbox_secs > 0 {
+        let mailbox_state = app_state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(mailbox_secs));
+            loop {
+                interval.tick().await;
+                let config = &mailbox_state.config.messaging;
+                if let Err(e) = mailbox::sweep_retention(&mailbox_state.db, config).await {
+                    eprintln!("❌ Mailbox retention sweep failed: {}", e);
+                }
+            }
+        });
+    }
+
+    // Send again webhook deliveries a crashed process left pending
+    let webhook_sweep_secs = app_state.config.webhooks.sweep_interval_secs;
+    if webhook_sweep_secs > 0 && !app_state.config.webhooks.urls.is_empty() {
+        let webhook_state = app_state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(webhook_sweep_secs));
+            loop {
+                interval.tick().await;
+                let config = &webhook_state.config.webhooks;
+                match webhooks::sweep_stale_deliveries(&webhook_state.db, config).await {
+                    Ok(0) => {}
+                    Ok(count) => println!("📨 Redelivered {} stale webhook deliveries", count),
+                    Err(e) => eprintln!("❌ Webhook delivery sweep failed: {}", e),
+                }
+            }
+        });
+    }
+
+    // Push offline users' devices about mailbox messages they haven't fetched
+    match push::WebhookSender::new(push::config()) {
+        Ok(Some(sender)) => {
+            let push_state = app_state.clone();
+            tokio::spawn(async move {
+                let cfg = push::config();
+                let period = Duration::from_secs(cfg.batch_window_secs);
+                let mut interval = tokio::time::interval(period);
+                loop {
+                    interval.tick().await;
+                    if let Err(e) = push::dispatch(&push_state.db, cfg, &sender).await {
+                        eprintln!("❌ Push dispatch failed: {}", e);
+                    }
+                }
+            });
+        }
+        Ok(None) => {}
+        Err(e) => eprintln!("❌ Push notices disabled: {}", e),
+    }
+
+    // Collapse settled forecast histories into score summaries
+    let compaction_secs = app_state.config.market.forecast_compaction_interval_secs;
+    if compaction_secs > 0 {
+        let compaction_state = app_state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(compaction_secs));
+            loop {
+                interval.tick().await;
+                if let Err(e) = run_forecast_compaction(&compaction_state).await {
+                    eprintln!("❌ Forecast compaction failed: {}", e);
+                }
+            }
+        });
+    }
+
+    // Move long-resolved markets' trades and payouts to cold storage
+    let archive_secs = app_state.config.market.archive_interval_secs;
+    if archive_secs > 0 {
+        let archive_state = app_state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(archive_secs));
+            loop {
+                interval.tick().await;
+                if let Err(e) = run_archival(&archive_state).await {
+                    eprintln!("❌ Market archival failed: {}", e);
+                }
+            }
+        });
+    }
+
+    // Keep market_updates partitions created ahead of the calendar
+    let partition_secs = app_state.config.market.partition_maintenance_secs;
+    if partition_secs > 0 {
+        let partition_state = app_state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(partition_secs));
+            loop {
+                interval.tick().await;
+                if let Err(e) = run_partition_maintenance(&partition_state).await {
+                    eprintln!("❌ Partition maintenance failed: {}", e);
+                }
+            }
+        });
+    }
+
+    // Recompute the reputation-weighted consensus shown beside market prices
+    let consensus_secs = app_state.config.market.weighted_prob_refresh_secs;
+    if consensus_secs > 0 {
+        let consensus_state = app_state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(consensus_secs));
+            loop {
+                interval.tick().await;
+                if let Err(e) = consensus::refresh_weighted_probs(&consensus_state.db, None).await {
+                    eprintln!("❌ Weighted consensus refresh failed: {}", e);
+                }
+            }
+        });
+    }
+
+    // Relearn per-category liquidity from resolved markets
+    let liquidity_secs = app_state.config.market.liquidity_recommendation_secs;
+    if liquidity_secs > 0 {
+        let liquidity_state = app_state.clone();
+        let min_markets = app_state.config.market.liquidity_recommendation_min_markets;
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(liquidity_secs));
+            loop {
+                interval.tick().await;
+                if let Err(e) =
+                    liquidity_recommendations::refresh(&liquidity_state.analytics_db, min_markets)
+                        .await
+                {
+                    eprintln!("❌ Liquidity recommendation refresh failed: {}", e);
+                }
+            }
+        });
+    }
+
+    // Recompute users' score checksums and report any tampering
+    let score_audit_secs = app_state.config.market.score_audit_secs;
+    if score_audit_secs > 0 {
+        let audit_state = app_state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(score_audit_secs));
+            loop {
+                interval.tick().await;
+                match score_integrity::audit(&audit_state.analytics_db, None).await {
+                    Ok(audit) => report_score_audit(&audit_state, &audit),
+                    Err(e) => eprintln!("❌ Score checksum audit failed: {}", e),
+                }
+            }
+        });
+    }
+
+    // Look for price gaps across linked markets
+    let arbitrage_secs = app_state.config.market.arbitrage_scan_secs;
+    if arbitrage_secs > 0 {
+        let arbitrage_state = app_state.clone();
+        let min_gap = app_state.config.market.arbitrage_min_gap;
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(arbitrage_secs));
+            loop {
+                interval.tick().await;
+                match arbitrage::scan(&arbitrage_state.analytics_db, min_gap).await {
+                    Ok(opportunities) => {
+                        for opportunity in opportunities.iter().filter(|o| o.new) {
+                            println!(
+                                "💱 Arbitrage in cluster {} ({}): gap {:.3} across {} legs",
+                                opportunity.cluster_id,
+                                opportunity.name,
+                                opportunity.gap,
+                                opportunity.legs.len()
+                            );
+                        }
+                    }
+                    Err(e) => eprintln!("❌ Arbitrage scan failed: {}", e),
+                }
+            }
+        });
+    }
+
+    // Rescore open markets' last-day activity for the trending list
+    let heat_secs = app_state.config.market.heat_refresh_secs;
+    if heat_secs > 0 {
+        let heat_state = app_state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(heat_secs));
+            loop {
+                interval.tick().await;
+                if let Err(e) = market_heat::refresh(&heat_state.db).await {
+                    eprintln!("❌ Market heat refresh failed: {}", e);
+                }
+            }
+        });
+    }
+
+    // Re-rank users once for however many sells and resolutions arrived
+    let ranking_secs = app_state.config.market.ranking_update_secs;
+    if ranking_secs > 0 {
+        let ranking_state = app_state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(ranking_secs));
+            loop {
+                interval.tick().await;
+                if let Err(e) = run_ranking_update(&ranking_state).await {
+                    eprintln!("❌ Global ranking update failed: {}", e);
+                }
+            }
+        });
+    }
+
+    // Load busy markets and leaderboards before the first request can miss
+    // Config validation already dropped (and logged) any invalid target
+    let startup_targets =
+        cache_warming::WarmTarget::parse_list(&app_state.config.cache_warming.targets.join(","))?;
+    if !startup_targets.is_empty() {
+        run_cache_warm(&app_state, &startup_targets).await;
+    }
+
+    // Create our web application routes with shared state.
+    let app = build_router(app_state);
+
+    // Define the address to listen on - bind to all interfaces in Docker
+    let addr = SocketAddr::from(([0, 0, 0, 0], 3001));
+
+    println!("🚀 Server running on http://{}", addr);
+    println!("📊 Available endpoints (LMSR + persuasion services):");
+    println!("  GET /health - Health check");
+    println!("  GET /version - Engine version, commit, build time, features and schema version");
+    println!("  POST /persuasion/score-mature-episodes - Score mature persuasive-alpha episode components");
+    println!("  GET /metaculus/sync - Manual sync with Metaculus API (150 recent questions, ?dry_run=true to preview)");
+    println!("  GET /metaculus/bulk-import - Complete import of ALL Metaculus questions (resumable, ?restart=true, ?dry_run=true&batches=N to preview)");
+    println!("  POST /metaculus/bulk-import/pause - Pause a running bulk import");
+    println!("  GET /metaculus/import-progress - Bulk import progress and request budget");
+    println!("  GET /metaculus/sync-categories - Manual category sync");
+    println!("  POST /imports/sync-all - Sync all configured external market providers (?dry_run=true to preview)");
+    println!(
+        "  POST /imports/sync/:provider - Sync one provider (metaculus|manifold|polymarket|kalshi)"
+    );
+    println!("  GET /imports/status - Recent provider sync runs");
+    println!("  GET /imports/source-status - Sync status and divergence for imported open events (?provider=&min_divergence=&limit=)");
+    println!("  POST /resolutions/backfill-metaculus - Backfill outcomes of resolved Metaculus imports (?limit=)");
+    println!("  GET /webhooks/deliveries - Recent outbound webhook deliveries");
+    println!("  GET /events/search - Ranked full-text event search (?q=&category=&status=&limit=&offset=)");
+    println!("  GET /events/closing-soon - Open markets closing within a window (?within=48h&user_id=&limit=)");
+    println!("  GET /events/stream/backfill - Broadcasts no client received since a time (?since=&limit=)");
+    println!("  POST /markets - Create a binary market (liquidity_b or max_subsidy)");
+    println!("  GET /events/:id/market - Get market state for event");
+    println!("  GET /events/:id/trades - Get recent trades for event");
+    println!("  GET /events/:id/state-at?ts= - q_yes/q_no/probability as of a past moment");
+    println!("  GET /events/:id/history?resolution=1h - OHLC price candles of a binary market");
+    println!("  POST /events/:id/trades/identified - Trade tape with usernames (admin, audited)");
+    println!("  GET /events/:id/metadata - Resolution criteria, fine print, units, bounds, source");
+    println!("  PUT /events/:id/metadata - Edit an event's structured metadata (admin)");
+    println!("  PUT /events/:id/privacy - Turn anonymous trading on or off for a market");
+    println!("  GET /events/:id/privacy/audit - Anonymity changes and identified-tape views");
+    println!("  GET /events/:id/embargoes - Trading embargo windows, past ones included");
+    println!("  POST /events/:id/embargoes - Pause trading between starts_at and ends_at (admin)");
+    println!("  DELETE /events/:id/embargoes/:embargo_id - Remove or lift an embargo window (admin)");
+    println!("  POST /events/:id/liquidity - Change liquidity_b, keeping price and share values (admin)");
+    println!("  GET /events/:id/liquidity/migrations - Audit trail of liquidity_b changes");
+    println!("  GET /events/:id/source-status - Provider sync status and forecast divergence");
+    println!("  POST /events/:id/update - Update market with stake");
+    println!("  POST /events/:id/trade - Same as /update");
+    println!("  POST /events/:id/update-outcome - Update N-outcome market with stake");
+    println!("  GET /events/:id/kelly - Get Kelly criterion suggestion");
+    println!("  POST /events/:id/sell - Sell shares back to market, by share count or RP payout");
+    println!("  POST /events/:id/close - Sell a user's whole binary position, both sides at once");
+    println!("  GET /events/:id/sell-quote - Preview a sell: payout, price impact, hold, stake");
+    println!("  GET /events/:id/quote - Preview a binary trade: shares, cost, new probability, slippage");
+    println!("  GET /events/:id/depth - Cost to move a binary market to each target on a grid");
+    println!("  POST /events/:id/sell-outcome - Sell shares of an N-outcome market outcome");
+    println!("  GET /events/:id/numeric-quote - Read-only quote for a numeric-market target distribution");
     println!("  POST /events/:id/numeric-trade - Trade toward a target distribution on a numeric market");
     println!("  POST /events/:id/numeric-sell - Sell a user's entire numeric-market position");
+    println!("  POST /events/:id/numeric-bucket-buy - Buy shares of one bucket of a numeric market");
+    println!("  POST /events/:id/numeric-bucket-sell - Sell shares of one bucket of a numeric market");
+    println!("  GET /events/:id/distribution - Implied distribution of a numeric market");
     println!("  POST /events/:id/market-resolve - Resolve market event");
+    println!("  POST /events/:id/market-resolve/preview - Dry-run a binary resolution and keep the report (GET reads it)");
+    println!("  POST /events/:id/market-resolve/commit - Apply the previewed resolution if positions still match");
+    println!("  POST /events/:id/market-resolve/cancel - Discard the previewed resolution");
+    println!("  POST /events/:id/dispute - Revert a resolution inside the dispute window, optionally re-resolving");
+    println!("  GET /events/:id/resolution-history - Resolution audit trail and payout journal");
+    println!("  POST /events/:id/paper-prediction - Score an unstaked practice forecast on a resolved event");
+    println!("  GET /users/:id/paper-predictions - A user's paper predictions and mean scores");
+    println!("  POST /events/:id/forecast - Journal an unstaked forecast on an open event");
+    println!("  PUT /events/:id/forecast - Revise a journaled forecast, keeping its history");
+    println!("  GET /user/:id/events/:event_id/forecast-history - Forecast revisions with time-weighted scores");
+    println!("  POST /forecasts/compact - Collapse settled forecast histories into summaries");
+    println!("  POST /archive/run - Archive trades and payouts of long-resolved markets");
+    println!("  GET /archive/status - Archived events and rows held in cold storage");
+    println!("  GET /partitions/market-updates - market_updates partitions, rows and sizes");
+    println!("  POST /partitions/market-updates/maintain - Create upcoming monthly partitions");
+    println!("  POST /events/:id/archive - Archive a resolved market now");
+    println!("  POST /events/:id/archive/restore - Bring an archived market back for an audit");
+    println!("  POST /consensus/refresh - Recompute reputation-weighted market probabilities");
+    println!("  GET /consensus/accuracy - Weighted consensus vs market price on resolved events (?category=&min_comment_velocity=&limit=)");
+    println!("  GET /analytics/market-accuracy - Closing-price scores by category and liquidity");
+    println!("  GET /analytics/arbitrage - Price gaps across linked markets, widest first");
+    println!("  GET /liquidity/recommendations - Per-category liquidity_b learned from history");
+    println!("  POST /liquidity/recommendations/refresh - Relearn liquidity recommendations now");
+    println!("  PUT /liquidity/recommendations/:category - Auto-apply a category's recommendation");
+    println!("  POST /scores/audit - Recompute score checksums and report tampering (?user_id=)");
+    println!("  POST /scores/reseal - Reseal score checksums over current inputs (?user_id=)");
+    println!("  POST /comments/ingest - Store hourly comment counts and sentiment");
+    println!("  GET /users/:id/shares/:event_id - The user's position in one market");
+    println!("  GET /users/:id/portfolio - Reputation, scoring quota, positions and realized P&L per market and lifetime");
+    println!("  GET /users/:id/pnl - Realized and unrealized P&L per market, open positions marked at current prices");
+    println!("  GET /user/:id/dashboard - Accuracy, reputation, portfolio, leaderboard slice, closing soon and score history in one read");
+    println!("  GET /user/:id/exposure - Worst-case loss by category and event cluster");
+    println!("  GET /user/:id/faucet - Onboarding grant and faucet top-ups received");
+    println!("  GET /user/:id/preferences - Which engine notifications the user receives");
+    println!("  PUT /user/:id/preferences - Turn resolution, rank or closing notices on/off");
+    println!("  GET /user/:id/predictions - Scored predictions (?status=&category=&type=&sort=)");
+    println!("  GET /user/:id/rank-history - Global rank transitions and what caused them (?limit=)");
+    println!("  GET /user/:id/risk - Stake share, exposure, Kelly ratios, drawdown (?days=90)");
+    println!("  POST /users/:id/api-keys - Issue a bot API key (scope: trade or read-only, rate_limit_per_minute)");
+    println!("  GET /users/:id/api-keys - A user's API keys with usage counts");
+    println!("  DELETE /users/:id/api-keys/:key_id - Revoke an API key");
+    println!("  POST /event-clusters - Link correlated, mutually exclusive or related events (admin)");
+    println!("  GET /event-clusters - List event clusters and their members");
+    println!("  GET /clusters/:id - A cluster's prices, probability sum and arbitrage gap");
+    println!("  POST /competitions - Create a trading competition with a starting bankroll");
+    println!("  POST /competitions/:id/join - Enter a user with a fresh competition bankroll");
+    println!("  POST /competitions/:id/markets - Add an untraded market to a competition");
+    println!("  GET /competitions/:id/leaderboard - Ranked entrants (?limit=&offset=&snapshot=)");
     println!("  GET /events/:id/shares - Get user's shares for event");
     println!("  POST /lmsr/verify-balance-invariant - Verify balance invariant");
     println!("  POST /lmsr/verify-staked-invariant - Verify staked invariant");
     println!("  POST /lmsr/verify-post-resolution - Verify post-resolution invariant");
     println!("  POST /lmsr/verify-consistency - Verify system consistency");
+    println!("  GET /lmsr/invariant-stats - Sampled invariant check counters");
+    println!("  GET /lmsr/solvency - Users' RP against the house, fee pool and faucet accounts");
+    println!("  POST /markets/close-sweep - Close markets past their closing_date now");
+    println!("  POST /faucet/sweep - Credit pending onboarding grants and faucet top-ups now");
+    println!("  POST /users/provision - Create a signed-up user and credit its onboarding grant");
+    println!("  POST /mls/groups - Verify a group's handshake messages from its GroupInfo on");
+    println!("  POST /mls/messages - Accept a commit or proposal at its group's current epoch");
+    println!("  POST /mls/groups/:group_id/commits - Sequence a commit at the group's current epoch");
+    println!("  GET /mls/groups/:group_id/commits - A group's commits in sequence order (?since=&limit=)");
+    println!("  POST /mls/welcomes - Store a Welcome for each new member it names");
+    println!("  GET /mls/welcomes/:key_package_ref - Welcomes stored for a key package");
+    println!("  POST /messages/enqueue - Queue a ciphertext in each recipient's mailbox");
+    println!("  POST /messages/retention/sweep - Drop expired and long-acked mailbox messages now");
+    println!("  GET /users/:id/mailbox - Unacked messages, oldest first (?after=&limit=)");
+    println!("  POST /users/:id/mailbox/ack - Acknowledge processed messages");
+    println!("  POST /push/dispatch - Push devices about unfetched mailbox messages now");
+    println!("  POST /users/:id/limit-orders - Rest an order that buys YES at or below (NO at or above) a trigger price");
+    println!("  GET /users/:id/limit-orders - A user's limit orders (?status=, ?event_id=)");
+    println!("  DELETE /users/:id/limit-orders/:order_id - Cancel an open limit order");
+    println!("  POST /users/:id/push-devices - Register a push token (platform: apns, fcm or web)");
+    println!("  GET /users/:id/push-devices - A user's registered push devices");
+    println!("  DELETE /users/:id/push-devices/:device_id - Unregister a push device");
+    println!("  POST /directory/groups/:app_group_id - Publish a group's GroupInfo (members only)");
+    println!("  GET /directory/groups/:app_group_id - A group's listing (?user_id=, X-Invite-Token)");
+    println!("  POST /directory/groups/:app_group_id/invites - Mint an invite token (members only)");
+    println!("  DELETE /directory/groups/:app_group_id/invites/:invite_id - Revoke an invite (?user_id=)");
+    println!("  POST /directory/groups/:app_group_id/welcomes - Store a Welcome for the group's invitees");
+    println!("  GET /directory/groups/:app_group_id/welcomes/:key_package_ref - The group's Welcomes for a key package");
+    println!("  GET /markets/sparklines - Probability series for charts (?event_ids=&points=24)");
+    println!("  GET /markets/trending - Open markets by last-day activity (?limit=20&category=)");
+    println!("  POST /admin/cache/warm - Load markets, leaderboards and dashboards into the caches (?targets=)");
+    println!("  POST /admin/backtest - Replay resolved predictions under alternative scoring parameters");
 
     // Start the server
     let listener = tokio::net::TcpListener::bind(addr).await?;
@@ -314,19 +1403,75 @@ async fn health_check() -> Json<Value> {
     }))
 }
 
-// WebSocket handler for real-time updates
-async fn websocket_handler(ws: WebSocketUpgrade, State(app_state): State<AppState>) -> Response {
-    ws.on_upgrade(move |socket| websocket_connection(socket, app_state))
+// Build and schema identity, for compatibility checks by the backend
+async fn version_endpoint(State(app_state): State<AppState>) -> ApiResult<Value> {
+    match version::get_version(&app_state.analytics_db).await {
+        Ok(info) => Ok(Json(info)),
+        Err(e) => Err(engine_error("Version error", &e)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct WebSocketQuery {
+    user_id: Option<i32>,
+    topic: Option<String>,
+}
+
+// WebSocket handler for real-time updates. With ?user_id= the connection
+// gets only that user's notices out of the per-user topics, with ?topic=jobs
+// only admin job progress and with ?topic=ranks only rank milestones;
+// without either, every topic (for the backend to fan out). Each connection
+// opens with a snapshot of the topics it gets (see ws_snapshot).
+async fn websocket_handler(
+    ws: WebSocketUpgrade,
+    State(app_state): State<AppState>,
+    Query(params): Query<WebSocketQuery>,
+) -> Response {
+    let narrowable = [jobs::JOBS_TOPIC, rank_history::RANKS_TOPIC];
+    let topic = params
+        .user_id
+        .map(notifications::user_topic)
+        .or(params
+            .topic
+            .filter(|topic| narrowable.contains(&topic.as_str())));
+    ws.on_upgrade(move |socket| websocket_connection(socket, app_state, topic))
 }
 
 // Handle individual WebSocket connections
-async fn websocket_connection(socket: WebSocket, app_state: AppState) {
+async fn websocket_connection(socket: WebSocket, app_state: AppState, topic: Option<String>) {
     let (mut sender, mut receiver) = socket.split();
     let mut rx = app_state.tx.subscribe();
 
-    // Spawn task to send updates to client
+    // Start the client off with the current state of what it subscribed to;
+    // broadcasts sent meanwhile wait in rx and follow.
+    let snapshots =
+        ws_snapshot::messages(&app_state.analytics_db, &app_state.cache, topic.as_deref()).await;
+    for snapshot in snapshots {
+        if sender.send(Message::Text(snapshot)).await.is_err() {
+            return;
+        }
+    }
+
+    // Spawn task to send updates to client. Messages a slow client misses
+    // are dead-lettered; tell it so it can fetch them from the backfill.
     let send_task = tokio::spawn(async move {
-        while let Ok(msg) = rx.recv().await {
+        loop {
+            let msg = match rx.recv().await {
+                Ok(msg) => msg,
+                Err(broadcast::error::RecvError::Lagged(missed)) => json!({
+                    "type": "stream_lagged",
+                    "data": { "missed": missed, "backfill": "/events/stream/backfill" },
+                    "timestamp": chrono::Utc::now()
+                })
+                .to_string(),
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            if topic
+                .as_deref()
+                .is_some_and(|topic| !notifications::delivers_to(&msg, topic))
+            {
+                continue;
+            }
             if sender.send(Message::Text(msg)).await.is_err() {
                 break;
             }
@@ -348,8 +1493,21 @@ async fn websocket_connection(socket: WebSocket, app_state: AppState) {
     }
 }
 
-// Manual Metaculus sync endpoint
-async fn manual_metaculus_sync(State(app_state): State<AppState>) -> ApiResult<Value> {
+// Manual Metaculus sync endpoint (?dry_run=true previews without storing)
+async fn manual_metaculus_sync(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> ApiResult<Value> {
+    if params.get("dry_run").map(|v| v == "true").unwrap_or(false) {
+        return match metaculus::preview_sync(&app_state.db).await {
+            Ok(preview) => Ok(Json(json!({
+                "success": true,
+                "dry_run": true,
+                "preview": preview
+            }))),
+            Err(e) => Err(engine_error("Metaculus sync preview error", &e)),
+        };
+    }
     match metaculus::manual_sync(&app_state.db).await {
         Ok(count) => {
             invalidate_and_broadcast(&app_state, "metaculus_sync", json!({"count": count}));
@@ -359,15 +1517,37 @@ async fn manual_metaculus_sync(State(app_state): State<AppState>) -> ApiResult<V
                 "count": count
             })))
         }
-        Err(e) => Err(internal_error(&format!("Metaculus sync error: {}", e))),
+        Err(e) => Err(engine_error("Metaculus sync error", &e)),
     }
 }
 
-// Manual Metaculus bulk import endpoint
-async fn manual_bulk_import_endpoint(State(app_state): State<AppState>) -> ApiResult<Value> {
-    println!("🚀 Bulk import endpoint called");
+// Manual Metaculus bulk import endpoint (resumes a paused/interrupted run
+// unless ?restart=true; ?dry_run=true previews the next ?batches=N pages,
+// default 1, without storing them or moving the saved progress)
+async fn manual_bulk_import_endpoint(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> ApiResult<Value> {
+    let restart = params.get("restart").map(|v| v == "true").unwrap_or(false);
+    if params.get("dry_run").map(|v| v == "true").unwrap_or(false) {
+        let batches: u32 = params
+            .get("batches")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(1);
+        return match metaculus::preview_bulk_import(&app_state.db, batches, restart).await {
+            Ok(preview) => Ok(Json(json!({
+                "success": true,
+                "dry_run": true,
+                "batches": batches,
+                "preview": preview
+            }))),
+            Err(e) => Err(engine_error("Metaculus bulk import preview error", &e)),
+        };
+    }
+    println!("🚀 Bulk import endpoint called (restart: {})", restart);
 
-    match metaculus::manual_bulk_import(&app_state.db).await {
+    let job = start_job(&app_state, "bulk_import");
+    match job.finish(metaculus::manual_bulk_import(&app_state.db, restart, &job).await) {
         Ok(count) => {
             invalidate_and_broadcast(
                 &app_state,
@@ -378,13 +1558,11 @@ async fn manual_bulk_import_endpoint(State(app_state): State<AppState>) -> ApiRe
                 "success": true,
                 "message": format!("Successfully imported {} questions from Metaculus (bulk import)", count),
                 "count": count,
-                "type": "bulk_import"
+                "type": "bulk_import",
+                "job_id": job.job_id()
             })))
         }
-        Err(e) => Err(internal_error(&format!(
-            "Metaculus bulk import error: {}",
-            e
-        ))),
+        Err(e) => Err(engine_error("Metaculus bulk import error", &e)),
     }
 }
 
@@ -397,13 +1575,16 @@ async fn manual_limited_import_endpoint(
         .get("batches")
         .and_then(|s| s.parse().ok())
         .unwrap_or(5); // Default to 5 batches for testing
+    let restart = params.get("restart").map(|v| v == "true").unwrap_or(false);
 
     println!(
         "🚀 Limited import endpoint called with max_batches: {}",
         max_batches
     );
 
-    match metaculus::manual_limited_import(&app_state.db, max_batches).await {
+    let job = start_job(&app_state, "limited_import");
+    let result = metaculus::manual_limited_import(&app_state.db, max_batches, restart, &job).await;
+    match job.finish(result) {
         Ok(count) => {
             invalidate_and_broadcast(
                 &app_state,
@@ -419,13 +1600,30 @@ async fn manual_limited_import_endpoint(
                 "message": format!("Successfully imported {} questions from Metaculus (limited to {} batches)", count, max_batches),
                 "count": count,
                 "max_batches": max_batches,
-                "type": "limited_import"
+                "type": "limited_import",
+                "job_id": job.job_id()
             })))
         }
-        Err(e) => Err(internal_error(&format!(
-            "Metaculus limited import error: {}",
-            e
-        ))),
+        Err(e) => Err(engine_error("Metaculus limited import error", &e)),
+    }
+}
+
+// Ask a running Metaculus bulk import to pause after its current batch
+async fn pause_bulk_import_endpoint(State(app_state): State<AppState>) -> ApiResult<Value> {
+    match metaculus::request_bulk_import_pause(&app_state.db).await {
+        Ok(()) => Ok(Json(json!({
+            "success": true,
+            "message": "Pause requested; the import stops after its current batch"
+        }))),
+        Err(e) => Err(engine_error("Metaculus pause error", &e)),
+    }
+}
+
+// Metaculus bulk import progress and request budget usage
+async fn metaculus_import_progress_endpoint(State(app_state): State<AppState>) -> ApiResult<Value> {
+    match metaculus::get_import_progress(&app_state.analytics_db).await {
+        Ok(progress) => Ok(Json(json!({ "success": true, "progress": progress }))),
+        Err(e) => Err(engine_error("Metaculus progress error", &e)),
     }
 }
 
@@ -455,7 +1653,7 @@ async fn manual_category_sync(
                 "count": count
             })))
         }
-        Err(e) => Err(internal_error(&format!("Category sync error: {}", e))),
+        Err(e) => Err(engine_error("Category sync error", &e)),
     }
 }
 
@@ -467,6 +1665,7 @@ struct ImportStatusQuery {
 #[derive(Debug, Deserialize)]
 struct ImportSyncQuery {
     full: Option<bool>,
+    dry_run: Option<bool>,
 }
 
 async fn resolution_sync_endpoint(State(app_state): State<AppState>) -> ApiResult<Value> {
@@ -479,7 +1678,25 @@ async fn resolution_sync_endpoint(State(app_state): State<AppState>) -> ApiResul
             );
             Ok(Json(json!({ "success": true, "stats": stats.to_json() })))
         }
-        Err(err) => Err(internal_error(&format!("Resolution sync error: {}", err))),
+        Err(err) => Err(engine_error("Resolution sync error", &err)),
+    }
+}
+
+async fn metaculus_backfill_endpoint(
+    State(app_state): State<AppState>,
+    Query(params): Query<ImportStatusQuery>,
+) -> ApiResult<Value> {
+    let limit = params.limit.unwrap_or(100);
+    match resolution_sync::backfill_metaculus_resolutions(&app_state.db, limit).await {
+        Ok(stats) => {
+            invalidate_and_broadcast(
+                &app_state,
+                "resolution_backfill",
+                json!({ "backfilled": stats.backfilled }),
+            );
+            Ok(Json(json!({ "success": true, "stats": stats.to_json() })))
+        }
+        Err(err) => Err(engine_error("Metaculus backfill error", &err)),
     }
 }
 
@@ -488,7 +1705,47 @@ async fn sync_all_imports_endpoint(
     Query(params): Query<ImportSyncQuery>,
 ) -> ApiResult<Value> {
     let full = params.full.unwrap_or(false);
-    match market_import::sync_all_markets(&app_state.db, full).await {
+    if params.dry_run.unwrap_or(false) {
+        return match market_import::preview_all_markets(&app_state.db, full).await {
+            Ok(previews) => {
+                let summary = previews.iter().fold(
+                    json!({
+                        "fetched_count": 0,
+                        "would_create_count": 0,
+                        "would_merge_count": 0,
+                        "would_link_count": 0,
+                        "would_update_count": 0,
+                        "would_exclude_count": 0,
+                        "error_count": 0
+                    }),
+                    |mut acc, preview| {
+                        for (key, value) in [
+                            ("fetched_count", preview.fetched_count),
+                            ("would_create_count", preview.would_create_count),
+                            ("would_merge_count", preview.would_merge_count),
+                            ("would_link_count", preview.would_link_count),
+                            ("would_update_count", preview.would_update_count),
+                            ("would_exclude_count", preview.would_exclude_count),
+                            ("error_count", preview.error_count),
+                        ] {
+                            acc[key] = json!(acc[key].as_i64().unwrap_or(0) + value as i64);
+                        }
+                        acc
+                    },
+                );
+                Ok(Json(json!({
+                    "success": true,
+                    "dry_run": true,
+                    "full": full,
+                    "previews": previews,
+                    "summary": summary
+                })))
+            }
+            Err(e) => Err(engine_error("External import sync-all preview error", &e)),
+        };
+    }
+    let job = start_job(&app_state, "import_sync_all");
+    match job.finish(market_import::sync_all_markets(&app_state.db, full, &job).await) {
         Ok(runs) => {
             invalidate_and_broadcast(
                 &app_state,
@@ -528,13 +1785,11 @@ async fn sync_all_imports_endpoint(
                 "success": true,
                 "full": full,
                 "runs": runs,
-                "summary": summary
+                "summary": summary,
+                "job_id": job.job_id()
             })))
         }
-        Err(e) => Err(internal_error(&format!(
-            "External import sync-all error: {}",
-            e
-        ))),
+        Err(e) => Err(engine_error("External import sync-all error", &e)),
     }
 }
 
@@ -544,6 +1799,20 @@ async fn sync_provider_import_endpoint(
     Query(params): Query<ImportSyncQuery>,
 ) -> ApiResult<Value> {
     let full = params.full.unwrap_or(false);
+    if params.dry_run.unwrap_or(false) {
+        return match market_import::preview_provider_named(&app_state.db, &provider, full).await {
+            Ok(preview) => Ok(Json(json!({
+                "success": true,
+                "dry_run": true,
+                "full": full,
+                "preview": preview
+            }))),
+            Err(e) => Err(engine_error(
+                "External import sync-provider preview error",
+                &e,
+            )),
+        };
+    }
     match market_import::sync_provider_named(&app_state.db, &provider, full).await {
         Ok(run) => {
             invalidate_and_broadcast(
@@ -557,10 +1826,7 @@ async fn sync_provider_import_endpoint(
                 "run": run
             })))
         }
-        Err(e) => Err(internal_error(&format!(
-            "External import sync-provider error: {}",
-            e
-        ))),
+        Err(e) => Err(engine_error("External import sync-provider error", &e)),
     }
 }
 
@@ -569,78 +1835,263 @@ async fn import_status_endpoint(
     Query(params): Query<ImportStatusQuery>,
 ) -> ApiResult<Value> {
     let limit = params.limit.unwrap_or(25).clamp(1, 200);
-    match market_import::get_recent_import_runs(&app_state.db, limit).await {
+    match market_import::get_recent_import_runs(&app_state.analytics_db, limit).await {
         Ok(runs) => Ok(Json(json!({
             "success": true,
             "limit": limit,
             "runs": runs
         }))),
-        Err(e) => Err(internal_error(&format!(
-            "External import status error: {}",
-            e
-        ))),
+        Err(e) => Err(engine_error("External import status error", &e)),
     }
 }
 
 #[derive(Debug, Deserialize)]
-struct ScoreMatureEpisodesRequest {
-    #[serde(default)]
-    episode_ids: Option<Vec<i32>>,
+struct SourceStatusQuery {
+    provider: Option<String>,
+    min_divergence: Option<f64>,
+    limit: Option<i64>,
 }
 
-// Score mature persuasive-alpha episode components directly in prediction-engine.
-// Backend callers only persist + mint from these engine-produced scores.
-async fn score_mature_persuasion_episodes_endpoint(
+async fn source_status_report_endpoint(
     State(app_state): State<AppState>,
-    ExtractJson(payload): ExtractJson<ScoreMatureEpisodesRequest>,
+    Query(params): Query<SourceStatusQuery>,
 ) -> ApiResult<Value> {
-    match score_mature_persuasion_episodes(&app_state.db, payload.episode_ids.as_deref()).await {
-        Ok((processed_episodes, updated_components)) => Ok(Json(json!({
+    let provider = match params.provider.as_deref() {
+        Some(raw) => Some(
+            market_import::ImportProvider::try_from(raw)
+                .map_err(|e| bad_request_error(&e.to_string()))?
+                .as_str(),
+        ),
+        None => None,
+    };
+    let limit = params.limit.unwrap_or(100);
+    match source_status::get_source_status_report(
+        &app_state.analytics_db,
+        provider,
+        params.min_divergence,
+        limit,
+    )
+    .await
+    {
+        Ok(report) => Ok(Json(report)),
+        Err(e) => Err(engine_error("Source status report error", &e)),
+    }
+}
+
+async fn event_source_status_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+) -> ApiResult<Value> {
+    if event_id <= 0 {
+        return Err(bad_request_error("Invalid event_id: must be positive"));
+    }
+    match source_status::get_event_source_status(&app_state.analytics_db, event_id).await {
+        Ok(sources) if sources.is_empty() => Err(not_found_error("Source mapping")),
+        Ok(sources) => Ok(Json(json!({
+            "event_id": event_id,
+            "sources": sources
+        }))),
+        Err(e) => Err(engine_error("Source status error", &e)),
+    }
+}
+
+async fn webhook_deliveries_endpoint(
+    State(app_state): State<AppState>,
+    Query(params): Query<ImportStatusQuery>,
+) -> ApiResult<Value> {
+    let limit = params.limit.unwrap_or(25).clamp(1, 200);
+    match webhooks::get_recent_deliveries(&app_state.analytics_db, limit).await {
+        Ok(deliveries) => Ok(Json(json!({
             "success": true,
-            "processed_episodes": processed_episodes,
-            "updated_components": updated_components
+            "limit": limit,
+            "deliveries": deliveries
         }))),
-        Err(e) => Err(internal_error(&format!(
-            "Persuasion mature scoring error: {}",
-            e
-        ))),
+        Err(e) => Err(engine_error("Webhook delivery status error", &e)),
     }
 }
 
-async fn score_mature_persuasion_episodes(
-    pool: &PgPool,
-    episode_ids: Option<&[i32]>,
-) -> Result<(i32, i32), anyhow::Error> {
-    let rows = if let Some(ids) = episode_ids {
-        if ids.is_empty() {
-            Vec::new()
-        } else {
-            sqlx::query(
-                r#"
-                SELECT
-                  pse.id,
-                  pse.event_id,
-                  pse.market_update_id,
-                  pse.p_before,
-                  pse.p_after,
-                  pse.s_early,
-                  pse.s_mid,
-                  pse.s_final,
-                  mu.created_at AS update_ts,
-                  e.closing_date AT TIME ZONE 'UTC' AS closing_date,
-                  e.outcome,
-                  e.market_prob AS fallback_prob
-                FROM post_signal_episodes pse
-                JOIN market_updates mu ON mu.id = pse.market_update_id
-                JOIN events e ON e.id = pse.event_id
-                WHERE pse.is_meaningful = TRUE
-                  AND pse.id = ANY($1)
-                  AND (pse.s_early IS NULL OR pse.s_mid IS NULL OR (pse.s_final IS NULL AND e.outcome IS NOT NULL))
-                ORDER BY pse.id ASC
-                "#,
-            )
-            .bind(ids)
-            .fetch_all(pool)
+#[derive(Debug, Deserialize)]
+struct BackfillQuery {
+    since: Option<String>,
+    limit: Option<i64>,
+}
+
+// Undelivered broadcasts for clients catching up after a reconnect or lag
+async fn stream_backfill_endpoint(
+    State(app_state): State<AppState>,
+    Query(params): Query<BackfillQuery>,
+) -> ApiResult<Value> {
+    let since = params
+        .since
+        .as_deref()
+        .ok_or_else(|| bad_request_error("Missing since"))?;
+    let since = chrono::DateTime::parse_from_rfc3339(since)
+        .map_err(|_| bad_request_error("since must be an RFC 3339 timestamp"))?
+        .with_timezone(&chrono::Utc);
+    let limit = params.limit.unwrap_or(100).clamp(1, 500);
+
+    match dead_letters::backfill(&app_state.analytics_db, since, limit).await {
+        Ok(backfill) => Ok(Json(backfill)),
+        Err(e) => Err(engine_error("Stream backfill error", &e)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SparklinesQuery {
+    event_ids: Option<String>,
+    points: Option<u32>,
+    interval_minutes: Option<u32>,
+}
+
+// Down-sampled probability series for many markets at once
+async fn sparklines_endpoint(
+    State(app_state): State<AppState>,
+    Query(params): Query<SparklinesQuery>,
+) -> ApiResult<Value> {
+    let event_ids = params
+        .event_ids
+        .as_deref()
+        .ok_or_else(|| bad_request_error("Missing event_ids"))?;
+    let event_ids =
+        sparklines::parse_event_ids(event_ids).map_err(|e| bad_request_error(&e.to_string()))?;
+
+    match sparklines::get_sparklines(
+        &app_state.analytics_db,
+        &app_state.market_cache,
+        &app_state.sparkline_cache,
+        &event_ids,
+        params.points.unwrap_or(24),
+        params.interval_minutes.unwrap_or(60),
+    )
+    .await
+    {
+        Ok(sparklines) => Ok(Json(sparklines)),
+        Err(e) => Err(engine_error("Sparklines error", &e)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TrendingQuery {
+    limit: Option<i64>,
+    category: Option<String>,
+}
+
+// Open markets by heat: trades, traders, price movement and stake over the last day
+async fn trending_markets_endpoint(
+    State(app_state): State<AppState>,
+    Query(params): Query<TrendingQuery>,
+) -> ApiResult<Value> {
+    match market_heat::trending(
+        &app_state.analytics_db,
+        params.limit.unwrap_or(20),
+        params.category.as_deref(),
+    )
+    .await
+    {
+        Ok(markets) => Ok(Json(json!({
+            "window_hours": market_heat::WINDOW_HOURS,
+            "category": params.category,
+            "count": markets.len(),
+            "markets": markets,
+        }))),
+        Err(e) => Err(engine_error("Trending markets error", &e)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ScoreMatureEpisodesRequest {
+    #[serde(default)]
+    episode_ids: Option<Vec<i32>>,
+}
+
+// Score mature persuasive-alpha episode components directly in prediction-engine.
+// Backend callers only persist + mint from these engine-produced scores.
+async fn score_mature_persuasion_episodes_endpoint(
+    State(app_state): State<AppState>,
+    ExtractJson(payload): ExtractJson<ScoreMatureEpisodesRequest>,
+) -> ApiResult<Value> {
+    match score_mature_persuasion_episodes(&app_state.db, payload.episode_ids.as_deref()).await {
+        Ok((processed_episodes, updated_components, scored_episodes)) => {
+            if updated_components > 0 {
+                // Each author whose episodes were scored may have moved rank
+                let authors = notifications::episode_authors(&app_state.db, &scored_episodes)
+                    .await
+                    .unwrap_or_else(|e| {
+                        eprintln!("❌ Failed to look up scored episode authors: {}", e);
+                        Vec::new()
+                    });
+                let notices = authors
+                    .into_iter()
+                    .map(|(user_id, episode_ids)| {
+                        let notice = json!({
+                            "source": "persuasion_scoring",
+                            "episode_ids": episode_ids
+                        });
+                        (user_id, notice)
+                    })
+                    .collect();
+                let notified = notify_users(
+                    &app_state,
+                    notifications::Notification::RankChange,
+                    "rank_changed",
+                    notices,
+                )
+                .await;
+                webhooks::emit(
+                    &app_state.db,
+                    webhooks::RANKING_UPDATED,
+                    json!({
+                        "source": "persuasion_scoring",
+                        "processed_episodes": processed_episodes,
+                        "updated_components": updated_components,
+                        "notify_user_ids": notified
+                    }),
+                );
+            }
+            Ok(Json(json!({
+                "success": true,
+                "processed_episodes": processed_episodes,
+                "updated_components": updated_components
+            })))
+        }
+        Err(e) => Err(engine_error("Persuasion mature scoring error", &e)),
+    }
+}
+
+async fn score_mature_persuasion_episodes(
+    pool: &PgPool,
+    episode_ids: Option<&[i32]>,
+) -> Result<(i32, i32, Vec<i32>), anyhow::Error> {
+    let rows = if let Some(ids) = episode_ids {
+        if ids.is_empty() {
+            Vec::new()
+        } else {
+            sqlx::query(
+                r#"
+                SELECT
+                  pse.id,
+                  pse.event_id,
+                  pse.market_update_id,
+                  pse.p_before,
+                  pse.p_after,
+                  pse.s_early,
+                  pse.s_mid,
+                  pse.s_final,
+                  mu.created_at AS update_ts,
+                  e.closing_date AT TIME ZONE 'UTC' AS closing_date,
+                  e.outcome,
+                  e.market_prob AS fallback_prob
+                FROM post_signal_episodes pse
+                JOIN market_updates mu ON mu.id = pse.market_update_id
+                JOIN events e ON e.id = pse.event_id
+                WHERE pse.is_meaningful = TRUE
+                  AND pse.id = ANY($1)
+                  AND (pse.s_early IS NULL OR pse.s_mid IS NULL OR (pse.s_final IS NULL AND e.outcome IS NOT NULL))
+                ORDER BY pse.id ASC
+                "#,
+            )
+            .bind(ids)
+            .fetch_all(pool)
             .await?
         }
     } else {
@@ -674,6 +2125,7 @@ async fn score_mature_persuasion_episodes(
 
     let mut processed_episodes = 0_i32;
     let mut updated_components = 0_i32;
+    let mut scored_episodes = Vec::new();
     let now = chrono::Utc::now();
 
     for row in rows {
@@ -743,10 +2195,11 @@ async fn score_mature_persuasion_episodes(
                 q = q.bind(value);
             }
             q.bind(episode_id).execute(pool).await?;
+            scored_episodes.push(episode_id);
         }
     }
 
-    Ok((processed_episodes, updated_components))
+    Ok((processed_episodes, updated_components, scored_episodes))
 }
 
 async fn get_market_prob_at_or_before(
@@ -758,7 +2211,7 @@ async fn get_market_prob_at_or_before(
     let row = sqlx::query(
         r#"
         SELECT new_prob
-        FROM market_updates
+        FROM market_updates_all
         WHERE event_id = $1
           AND created_at <= $2
         ORDER BY created_at DESC
@@ -788,15 +2241,7 @@ fn parse_final_target(outcome: Option<&str>) -> Option<f64> {
 }
 
 fn episode_log_score_delta(target: f64, p_before: f64, p_after: f64) -> f64 {
-    let floor = 0.0001_f64;
-    let clamp01 = |p: f64| p.clamp(0.0, 1.0);
-    let t = clamp01(target);
-    let pb = clamp01(p_before);
-    let pa = clamp01(p_after);
-
-    let ll_before = -(t * (pb.max(floor)).ln() + (1.0 - t) * ((1.0 - pb).max(floor)).ln());
-    let ll_after = -(t * (pa.max(floor)).ln() + (1.0 - t) * ((1.0 - pa).max(floor)).ln());
-    (ll_before - ll_after).max(0.0)
+    (log_loss(target, p_before) - log_loss(target, p_after)).max(0.0)
 }
 
 // ============================================================================
@@ -818,248 +2263,2532 @@ async fn get_events_endpoint(
 
     match database::get_events(&app_state.db, limit).await {
         Ok(events) => Ok(Json(json!(events))),
-        Err(e) => Err(internal_error(&format!("Events fetch error: {}", e))),
+        Err(e) => Err(engine_error("Events fetch error", &e)),
     }
 }
 
-// Get market state for an event
-async fn get_market_state_endpoint(
+#[derive(Debug, Deserialize)]
+struct EventSearchQuery {
+    q: Option<String>,
+    category: Option<String>,
+    status: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+// Full-text event search, ranked, with category and status filters
+async fn search_events_endpoint(
     State(app_state): State<AppState>,
-    Path(event_id): Path<i32>,
+    Query(params): Query<EventSearchQuery>,
 ) -> ApiResult<Value> {
-    match lmsr_api::get_market_state(&app_state.db, event_id).await {
-        Ok(market_state) => Ok(Json(market_state)),
-        Err(e) => Err(internal_error(&format!("Market state error: {}", e))),
+    let q = params
+        .q
+        .as_deref()
+        .ok_or_else(|| bad_request_error("Missing q"))?;
+    let status = event_search::Status::parse(params.status.as_deref())
+        .map_err(|e| bad_request_error(&e.to_string()))?;
+
+    match event_search::search_events(
+        &app_state.analytics_db,
+        q,
+        params.category.as_deref(),
+        status,
+        params.limit.unwrap_or(20),
+        params.offset.unwrap_or(0),
+    )
+    .await
+    {
+        Ok(results) => Ok(Json(results)),
+        Err(e) => Err(engine_error("Event search error", &e)),
     }
 }
 
-// Get recent trades for an event
-async fn get_event_trades_endpoint(
+#[derive(Debug, Deserialize)]
+struct ClosingSoonQuery {
+    within: Option<String>,
+    user_id: Option<i32>,
+    limit: Option<i64>,
+}
+
+// Open markets closing soon, optionally only those a user holds
+async fn closing_soon_endpoint(
     State(app_state): State<AppState>,
-    Path(event_id): Path<i32>,
-    Query(params): Query<HashMap<String, String>>,
+    Query(params): Query<ClosingSoonQuery>,
 ) -> ApiResult<Value> {
-    let limit: i32 = params
-        .get("limit")
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(50);
-
-    // Cap at 100 trades max
-    let limit = limit.min(100);
-
-    match lmsr_api::get_event_trades(&app_state.db, event_id, limit).await {
-        Ok(trades) => Ok(Json(trades)),
-        Err(e) => Err(internal_error(&format!("Trades fetch error: {}", e))),
+    let within = params.within.as_deref().unwrap_or("48h");
+    let window =
+        closing_soon::parse_within(within).map_err(|e| bad_request_error(&e.to_string()))?;
+
+    match closing_soon::closing_soon(
+        &app_state.analytics_db,
+        window,
+        params.user_id,
+        params.limit.unwrap_or(100),
+    )
+    .await
+    {
+        Ok(markets) => Ok(Json(json!({
+            "within_hours": window.num_minutes() as f64 / 60.0,
+            "user_id": params.user_id,
+            "count": markets.len(),
+            "markets": markets,
+        }))),
+        Err(e) => Err(engine_error("Closing soon error", &e)),
     }
 }
 
-// Update market with new stake
-async fn update_market_endpoint(
+// Create a binary market; b comes from liquidity_b or from a max_subsidy budget
+async fn create_market_endpoint(
     State(app_state): State<AppState>,
-    Path(event_id): Path<i32>,
     ExtractJson(payload): ExtractJson<serde_json::Value>,
 ) -> ApiResult<Value> {
-    // Validate event_id
-    if event_id <= 0 {
-        return Err(bad_request_error("Invalid event_id: must be positive"));
+    let request: lmsr_api::CreateMarket = serde_json::from_value(payload)
+        .map_err(|e| bad_request_error(&format!("Invalid market: {}", e)))?;
+
+    match lmsr_api::create_market(&app_state.db, &request).await {
+        Ok(market) => {
+            invalidate_and_broadcast(
+                &app_state,
+                "market_created",
+                json!({
+                    "event_id": market.event_id,
+                    "title": market.title,
+                    "category": market.category,
+                    "closing_date": market.closing_date,
+                    "liquidity_b": market.liquidity_b,
+                    "market_prob": market.market_prob
+                }),
+            );
+            webhooks::emit(
+                &app_state.db,
+                webhooks::MARKET_CREATED,
+                json!({
+                    "event_id": market.event_id,
+                    "source": "api",
+                    "title": market.title,
+                    "event_type": "binary",
+                    "close_time": market.closing_date,
+                }),
+            );
+            Ok(Json(json!({ "success": true, "market": market })))
+        }
+        Err(e) => Err(engine_error("Market creation error", &e)),
     }
+}
 
-    // Validate user_id - require explicit value, no defaults
-    let user_id = payload
-        .get("user_id")
-        .and_then(|v| v.as_i64())
-        .ok_or_else(|| {
-            bad_request_error("Missing or invalid user_id: must be a positive integer")
-        })? as i32;
-    if user_id <= 0 {
-        return Err(bad_request_error("Invalid user_id: must be positive"));
+// Close past-due markets and tell clients and webhooks about each one
+async fn run_close_sweep(app_state: &AppState) -> anyhow::Result<Vec<market_close::ClosedMarket>> {
+    let closed = market_close::close_due_markets(&app_state.db).await?;
+    for market in &closed {
+        println!("🔒 Closed market {} ({})", market.event_id, market.title);
+        invalidate_and_broadcast(
+            app_state,
+            "market_closed",
+            json!({
+                "event_id": market.event_id,
+                "title": market.title,
+                "closing_date": market.closing_date,
+                "closed_at": market.closed_at
+            }),
+        );
+        webhooks::emit(
+            &app_state.db,
+            webhooks::MARKET_CLOSED,
+            json!({
+                "event_id": market.event_id,
+                "title": market.title,
+                "close_time": market.closing_date,
+                "closed_at": market.closed_at,
+            }),
+        );
     }
+    // Orders on markets that closed or resolved since give back their reserve
+    let expired = limit_orders::release_expired(&app_state.db).await?;
+    if expired > 0 {
+        println!("⌛ Expired {} limit order(s)", expired);
+    }
+    Ok(closed)
+}
 
-    // Validate target_prob - require explicit value, no defaults
-    let target_prob = payload
-        .get("target_prob")
-        .and_then(|v| v.as_f64())
-        .ok_or_else(|| {
-            bad_request_error("Missing or invalid target_prob: must be a finite number")
-        })?;
-    if !target_prob.is_finite() {
-        return Err(bad_request_error("Invalid target_prob: must be finite"));
+// Tell clients which markets have come within the reminder window, once
+// each, and every listed holder who takes reminders on their own topic
+async fn run_closing_reminders(app_state: &AppState) -> anyhow::Result<usize> {
+    let hours = app_state.config.market.closing_reminder_hours;
+    if hours <= 0.0 {
+        return Ok(0);
     }
-    if target_prob <= 0.0 || target_prob >= 1.0 {
-        return Err(bad_request_error(
-            "Invalid target_prob: must be between 0 and 1 (exclusive)",
-        ));
+    let markets = closing_soon::due_reminders(&app_state.db, hours).await?;
+    if !markets.is_empty() {
+        println!("⏰ {} market(s) closing within {}h", markets.len(), hours);
+        let notices = markets
+            .iter()
+            .flat_map(|market| {
+                market.holders.iter().flatten().map(move |&user_id| {
+                    let mut notice = json!(market);
+                    if let Some(fields) = notice.as_object_mut() {
+                        fields.remove("holders");
+                        fields.insert("hours".to_string(), json!(hours));
+                    }
+                    (user_id, notice)
+                })
+            })
+            .collect();
+        invalidate_and_broadcast(
+            app_state,
+            "markets_closing_soon",
+            json!({ "hours": hours, "markets": markets }),
+        );
+        notify_users(
+            app_state,
+            notifications::Notification::ClosingReminder,
+            "market_closing_soon",
+            notices,
+        )
+        .await;
     }
+    Ok(markets.len())
+}
 
-    // Validate stake - require explicit value, no defaults
-    let stake = payload
-        .get("stake")
-        .and_then(|v| v.as_f64())
-        .ok_or_else(|| bad_request_error("Missing or invalid stake: must be a finite number"))?;
-    if !stake.is_finite() {
-        return Err(bad_request_error("Invalid stake: must be finite"));
+// Run the close sweep on demand (cron or admin)
+async fn close_sweep_endpoint(State(app_state): State<AppState>) -> ApiResult<Value> {
+    match run_close_sweep(&app_state).await {
+        Ok(closed) => Ok(Json(json!({
+            "success": true,
+            "closed": closed.len(),
+            "markets": closed
+        }))),
+        Err(e) => Err(engine_error("Close sweep error", &e)),
     }
-    if stake <= 0.0 {
-        return Err(bad_request_error("Invalid stake: must be positive"));
+}
+
+// Credit faucet grants and tell each user's clients their new balance
+async fn run_faucet_sweep(app_state: &AppState) -> anyhow::Result<faucet::FaucetSweep> {
+    let sweep = faucet::run_sweep(&app_state.db, &app_state.config.faucet).await?;
+    for grant in sweep.onboarded.iter().chain(&sweep.topped_up) {
+        invalidate_and_broadcast(app_state, "rp_granted", json!(grant));
     }
-    if stake > 1_000_000.0 {
-        // 1M RP max per trade
-        return Err(bad_request_error(
-            "Invalid stake: exceeds maximum allowed (1,000,000 RP)",
-        ));
+    if !sweep.topped_up.is_empty() {
+        println!("🚰 Faucet topped up {} users", sweep.topped_up.len());
     }
-    if stake < 0.01 {
-        // Minimum 0.01 RP
-        return Err(bad_request_error(
-            "Invalid stake: below minimum allowed (0.01 RP)",
-        ));
+    Ok(sweep)
+}
+
+// Run the faucet sweep on demand (cron, admin, or right after signup)
+async fn faucet_sweep_endpoint(State(app_state): State<AppState>) -> ApiResult<Value> {
+    match run_faucet_sweep(&app_state).await {
+        Ok(sweep) => Ok(Json(json!({
+            "success": true,
+            "onboarded": sweep.onboarded.len(),
+            "topped_up": sweep.topped_up.len(),
+            "grants": sweep.onboarded.iter().chain(&sweep.topped_up).collect::<Vec<_>>()
+        }))),
+        Err(e) => Err(engine_error("Faucet sweep error", &e)),
     }
+}
 
-    let update = lmsr_api::MarketUpdate {
-        event_id,
-        target_prob,
-        stake,
-        referral_post_id: payload
-            .get("referral_post_id")
-            .and_then(|value| value.as_i64())
-            .filter(|value| *value > 0)
-            .map(|value| value as i32),
-        referral_click_id: payload
-            .get("referral_click_id")
-            .and_then(|value| value.as_i64())
-            .filter(|value| *value > 0)
-            .map(|value| value as i32),
+// Create a user the backend just signed up, with its onboarding RP, so its
+// first trade never races the faucet sweep. Safe to retry.
+async fn provision_user_endpoint(
+    State(app_state): State<AppState>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    let field = |name: &str| {
+        payload
+            .get(name)
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| bad_request_error(&format!("Missing {}", name)))
     };
-
-    match lmsr_api::update_market(&app_state.db, &app_state.config, user_id, update).await {
-        Ok(result) => {
-            invalidate_and_broadcast(
-                &app_state,
-                "market_updated",
-                json!({
-                    "event_id": event_id,
-                    "user_id": user_id,
-                    "new_prob": result.new_prob,
-                    "shares_acquired": result.shares_acquired
-                }),
-            );
-            Ok(Json(json!(result)))
-        }
-        Err(e) => {
-            let msg = e.to_string();
-            let msg_lower = msg.to_lowercase();
-            if msg_lower.contains("market resolved") {
-                return Err(bad_request_error("Market resolved"));
-            }
-            if msg_lower.contains("market closed") {
-                return Err(bad_request_error("Market closed"));
-            }
-            if msg_lower.contains("outcome-based endpoint") {
-                return Err(bad_request_error(
-                    "Use /events/:id/update-outcome for this market type",
-                ));
+    let (username, email, password_hash) =
+        (field("username")?, field("email")?, field("password_hash")?);
+    let config = &app_state.config.faucet;
+    match user_provisioning::provision_user(&app_state.db, config, username, email, password_hash)
+        .await
+    {
+        Ok(user) => {
+            if let Some(grant) = &user.onboarding_grant {
+                invalidate_and_broadcast(&app_state, "rp_granted", json!(grant));
             }
-            Err(internal_error(&format!("Market update error: {}", msg)))
+            Ok(Json(json!({ "success": true, "user": user })))
         }
+        Err(e) => Err(engine_error("User provisioning error", &e)),
     }
 }
 
-// Update market for an explicit outcome (multiple choice / numeric buckets)
-async fn update_market_outcome_endpoint(
+// Register a group with the MLS delivery service so its commits and
+// proposals are verified, not just ordered
+async fn register_mls_group_endpoint(
     State(app_state): State<AppState>,
-    Path(event_id): Path<i32>,
     ExtractJson(payload): ExtractJson<serde_json::Value>,
 ) -> ApiResult<Value> {
-    if event_id <= 0 {
-        return Err(bad_request_error("Invalid event_id: must be positive"));
+    let group_info = payload
+        .get("group_info")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| bad_request_error("Missing group_info"))?;
+    let decoded = mls_delivery::decode_hex("group_info", group_info).and_then(|group_info| {
+        let ratchet_tree = payload
+            .get("ratchet_tree")
+            .and_then(|v| v.as_str())
+            .map(|tree| mls_delivery::decode_hex("ratchet_tree", tree))
+            .transpose()?;
+        Ok((group_info, ratchet_tree))
+    });
+    let (group_info, ratchet_tree) = decoded.map_err(|e| bad_request_error(&e.to_string()))?;
+    match mls_delivery::register_group(&app_state.db, &group_info, ratchet_tree.as_deref()).await
+    {
+        Ok(group) => Ok(Json(json!({ "success": true, "group": group }))),
+        Err(e) => Err(engine_error("MLS group error", &e)),
     }
+}
 
-    let user_id = payload
-        .get("user_id")
-        .and_then(|v| v.as_i64())
-        .ok_or_else(|| {
-            bad_request_error("Missing or invalid user_id: must be a positive integer")
-        })? as i32;
-    if user_id <= 0 {
-        return Err(bad_request_error("Invalid user_id: must be positive"));
+// Accept a handshake message for fan-out only at its group's current epoch
+async fn submit_mls_message_endpoint(
+    State(app_state): State<AppState>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    let message = payload
+        .get("message")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| bad_request_error("Missing message"))?;
+    let message = mls_delivery::decode_hex("message", message)
+        .map_err(|e| bad_request_error(&e.to_string()))?;
+    match mls_delivery::submit_message(&app_state.db, &message).await {
+        Ok(accepted) => Ok(Json(json!({ "success": true, "accepted": accepted }))),
+        Err(e) => Err(engine_error("MLS message error", &e)),
     }
+}
 
+// Give a commit the group's next sequence number, or refuse it as stale
+async fn sequence_mls_commit_endpoint(
+    State(app_state): State<AppState>,
+    Path(group_id): Path<String>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    let message = payload
+        .get("message")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| bad_request_error("Missing message"))?;
+    let decoded = mls_delivery::decode_hex("group_id", &group_id).and_then(|group_id| {
+        Ok((group_id, mls_delivery::decode_hex("message", message)?))
+    });
+    let (group_id, message) = decoded.map_err(|e| bad_request_error(&e.to_string()))?;
+    match mls_delivery::submit_commit(&app_state.db, &group_id, &message).await {
+        Ok(accepted) => Ok(Json(json!({ "success": true, "accepted": accepted }))),
+        Err(e) => Err(engine_error("MLS commit error", &e)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MlsCommitsQuery {
+    since: Option<i64>,
+    limit: Option<i64>,
+}
+
+// Commits a member missed, after the last sequence number it applied
+async fn mls_group_commits_endpoint(
+    State(app_state): State<AppState>,
+    Path(group_id): Path<String>,
+    Query(params): Query<MlsCommitsQuery>,
+) -> ApiResult<Value> {
+    let group_id = mls_delivery::decode_hex("group_id", &group_id)
+        .map_err(|e| bad_request_error(&e.to_string()))?;
+    let since = params.since.unwrap_or(0);
+    let limit = params.limit.unwrap_or(100);
+    match mls_delivery::get_commits(&app_state.db, &group_id, since, limit).await {
+        Ok(backlog) => Ok(Json(json!(backlog))),
+        Err(e) => Err(engine_error("MLS commits error", &e)),
+    }
+}
+
+async fn store_mls_welcome_endpoint(
+    State(app_state): State<AppState>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    let welcome = payload
+        .get("welcome")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| bad_request_error("Missing welcome"))?;
+    let welcome = mls_delivery::decode_hex("welcome", welcome)
+        .map_err(|e| bad_request_error(&e.to_string()))?;
+    match mls_delivery::store_welcome(&app_state.db, &welcome, None).await {
+        Ok(recipients) => Ok(Json(json!({ "success": true, "key_package_refs": recipients }))),
+        Err(e) => Err(engine_error("MLS welcome error", &e)),
+    }
+}
+
+async fn mls_welcomes_endpoint(
+    State(app_state): State<AppState>,
+    Path(key_package_ref): Path<String>,
+) -> ApiResult<Value> {
+    let key_package_ref = mls_delivery::decode_hex("key_package_ref", &key_package_ref)
+        .map_err(|e| bad_request_error(&e.to_string()))?;
+    match mls_delivery::get_welcomes(&app_state.db, &key_package_ref, None).await {
+        Ok(welcomes) => Ok(Json(json!({ "welcomes": welcomes }))),
+        Err(e) => Err(engine_error("MLS welcomes error", &e)),
+    }
+}
+
+// Queue a ciphertext for its recipients until they fetch and ack it
+async fn enqueue_messages_endpoint(
+    State(app_state): State<AppState>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    let recipients: Vec<i32> = payload
+        .get("recipient_user_ids")
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .ok_or_else(|| bad_request_error("Missing or invalid recipient_user_ids: must be an array of user ids"))?;
+    let message = payload
+        .get("message")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| bad_request_error("Missing message"))?;
+    let sender = payload
+        .get("sender_user_id")
+        .and_then(|v| v.as_i64())
+        .map(|id| id as i32);
+    let decoded = mls_delivery::decode_hex("message", message).and_then(|message| {
+        let group_id = payload
+            .get("group_id")
+            .and_then(|v| v.as_str())
+            .map(|group_id| mls_delivery::decode_hex("group_id", group_id))
+            .transpose()?;
+        Ok((message, group_id))
+    });
+    let (message, group_id) = decoded.map_err(|e| bad_request_error(&e.to_string()))?;
+    let config = &app_state.config.messaging;
+    match mailbox::enqueue(
+        &app_state.db,
+        config,
+        sender,
+        group_id.as_deref(),
+        &recipients,
+        &message,
+    )
+    .await
+    {
+        Ok(queued) => {
+            let ids: Vec<i64> = queued.iter().map(|message| message.id).collect();
+            Ok(Json(json!({ "success": true, "message_ids": ids })))
+        }
+        Err(e) => Err(engine_error("Message enqueue error", &e)),
+    }
+}
+
+async fn mailbox_retention_sweep_endpoint(State(app_state): State<AppState>) -> ApiResult<Value> {
+    match mailbox::sweep_retention(&app_state.db, &app_state.config.messaging).await {
+        Ok(sweep) => Ok(Json(json!({ "success": true, "sweep": sweep }))),
+        Err(e) => Err(engine_error("Mailbox retention error", &e)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MailboxQuery {
+    after: Option<i64>,
+    limit: Option<i64>,
+}
+
+// A page of the user's unacked messages; each fetch counts as a delivery
+async fn user_mailbox_endpoint(
+    State(app_state): State<AppState>,
+    Path(user_id): Path<i32>,
+    Query(params): Query<MailboxQuery>,
+) -> ApiResult<Value> {
+    if user_id <= 0 {
+        return Err(bad_request_error("Invalid user_id: must be positive"));
+    }
+    let after = params.after.unwrap_or(0);
+    let limit = params.limit.unwrap_or(50);
+    match mailbox::fetch(&app_state.db, user_id, after, limit).await {
+        Ok(page) => Ok(Json(json!(page))),
+        Err(e) => Err(engine_error("Mailbox error", &e)),
+    }
+}
+
+async fn ack_mailbox_endpoint(
+    State(app_state): State<AppState>,
+    Path(user_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    if user_id <= 0 {
+        return Err(bad_request_error("Invalid user_id: must be positive"));
+    }
+    let message_ids: Vec<i64> = payload
+        .get("message_ids")
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .ok_or_else(|| bad_request_error("Missing or invalid message_ids: must be an array of message ids"))?;
+    match mailbox::ack(&app_state.db, user_id, &message_ids).await {
+        Ok(acked) => Ok(Json(json!({ "success": true, "acked": acked }))),
+        Err(e) => Err(engine_error("Mailbox ack error", &e)),
+    }
+}
+
+// Run the push dispatcher now instead of waiting for its next batch
+async fn push_dispatch_endpoint(State(app_state): State<AppState>) -> ApiResult<Value> {
+    let cfg = push::config();
+    let sender = match push::WebhookSender::new(cfg) {
+        Ok(Some(sender)) => sender,
+        Ok(None) => return Err(bad_request_error("PUSH_WEBHOOK_URL must be set")),
+        Err(e) => return Err(engine_error("Push client error", &e)),
+    };
+    match push::dispatch(&app_state.db, cfg, &sender).await {
+        Ok(dispatch) => Ok(Json(json!({ "success": true, "dispatch": dispatch }))),
+        Err(e) => Err(engine_error("Push dispatch error", &e)),
+    }
+}
+
+async fn register_push_device_endpoint(
+    State(app_state): State<AppState>,
+    Path(user_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    let field = |name: &str| {
+        payload
+            .get(name)
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| bad_request_error(&format!("Missing {}", name)))
+    };
+    let (platform, token) = (field("platform")?, field("token")?);
+    match push::register_device(&app_state.db, user_id, platform, token).await {
+        Ok(device) => Ok(Json(json!({ "success": true, "device": device }))),
+        Err(e) => Err(engine_error("Push device error", &e)),
+    }
+}
+
+async fn list_push_devices_endpoint(
+    State(app_state): State<AppState>,
+    Path(user_id): Path<i32>,
+) -> ApiResult<Value> {
+    match push::list_devices(&app_state.db, user_id).await {
+        Ok(devices) => Ok(Json(json!({ "devices": devices }))),
+        Err(e) => Err(engine_error("Push device error", &e)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LimitOrderListQuery {
+    status: Option<String>,
+    event_id: Option<i32>,
+}
+
+// A user's limit orders, newest first
+async fn list_limit_orders_endpoint(
+    State(app_state): State<AppState>,
+    Path(user_id): Path<i32>,
+    Query(params): Query<LimitOrderListQuery>,
+) -> ApiResult<Value> {
+    match limit_orders::list(
+        &app_state.db,
+        user_id,
+        params.status.as_deref(),
+        params.event_id,
+    )
+    .await
+    {
+        Ok(orders) => Ok(Json(json!({ "user_id": user_id, "orders": orders }))),
+        Err(e) => Err(engine_error("Limit order error", &e)),
+    }
+}
+
+// Rest a limit order. One the current price already triggers fills before
+// the response, which shows the order as it ends up.
+async fn place_limit_order_endpoint(
+    State(app_state): State<AppState>,
+    Path(user_id): Path<i32>,
+    ExtractJson(order): ExtractJson<limit_orders::PlaceOrder>,
+) -> ApiResult<Value> {
+    let placed = limit_orders::place(&app_state.db, user_id, &order)
+        .await
+        .map_err(|e| engine_error("Limit order error", &e))?;
+    run_limit_order_matcher(&app_state, placed.event_id).await;
+    match limit_orders::get(&app_state.db, placed.id).await {
+        Ok(order) => Ok(Json(json!(order))),
+        Err(e) => Err(engine_error("Limit order error", &e)),
+    }
+}
+
+async fn cancel_limit_order_endpoint(
+    State(app_state): State<AppState>,
+    Path((user_id, order_id)): Path<(i32, i64)>,
+) -> ApiResult<Value> {
+    match limit_orders::cancel(&app_state.db, user_id, order_id).await {
+        Ok(order) => Ok(Json(json!(order))),
+        Err(e) => Err(engine_error("Limit order error", &e)),
+    }
+}
+
+// Fill the limit orders a price move triggered. Each fill is broadcast as
+// a trade, and owners who take order notices hear about their fills and
+// failures on their own topic.
+async fn run_limit_order_matcher(app_state: &AppState, event_id: i32) {
+    let fills =
+        match lmsr_api::match_limit_orders(&app_state.db, &app_state.config, event_id).await {
+            Ok(fills) => fills,
+            Err(e) => {
+                eprintln!("❌ Limit order matching failed for event {}: {}", event_id, e);
+                return;
+            }
+        };
+    let mut filled = Vec::new();
+    let mut failed = Vec::new();
+    for fill in fills {
+        match &fill.trade {
+            Some(trade) => {
+                broadcast_trade(
+                    app_state,
+                    "market_updated",
+                    json!({
+                        "event_id": event_id,
+                        "user_id": fill.user_id,
+                        "new_prob": trade.new_prob,
+                        "shares_acquired": trade.shares_acquired,
+                        "limit_order_id": fill.order_id
+                    }),
+                )
+                .await;
+                invariants::sample_after_trade(
+                    &app_state.analytics_db,
+                    fill.user_id,
+                    event_id,
+                    "buy",
+                );
+                filled.push((fill.user_id, json!(fill)));
+            }
+            None => failed.push((fill.user_id, json!(fill))),
+        }
+    }
+    let kind = notifications::Notification::OrderFill;
+    notify_users(app_state, kind, "limit_order_filled", filled).await;
+    notify_users(app_state, kind, "limit_order_failed", failed).await;
+}
+
+// Matching after a trade runs once the trader has their response
+fn spawn_limit_order_matcher(app_state: &AppState, event_id: i32) {
+    let app_state = app_state.clone();
+    tokio::spawn(async move { run_limit_order_matcher(&app_state, event_id).await });
+}
+
+// Re-rank after a market moves reputation (a resolution or a sell) and
+// announce anyone entering the top 10 or top 100 on the ranks topic
+// Re-ranks if a sell or resolution marked the ranking dirty, broadcasting
+// the milestones
+async fn run_ranking_update(app_state: &AppState) -> anyhow::Result<()> {
+    let Some(event_id) = rank_history::take_dirty() else {
+        return Ok(());
+    };
+    let transitions =
+        match rank_history::update_global_rankings(&app_state.analytics_db, Some(event_id)).await
+        {
+            Ok(transitions) => transitions,
+            Err(e) => {
+                // Retried on the next tick
+                rank_history::mark_dirty(event_id);
+                return Err(e);
+            }
+        };
+    for transition in &transitions {
+        if let Some(milestone) = transition.milestone() {
+            publish(
+                app_state,
+                rank_history::milestone_message(transition, milestone),
+            );
+        }
+    }
+    Ok(())
+}
+
+async fn remove_push_device_endpoint(
+    State(app_state): State<AppState>,
+    Path((user_id, device_id)): Path<(i32, i64)>,
+) -> ApiResult<Value> {
+    match push::remove_device(&app_state.db, user_id, device_id).await {
+        Ok(true) => Ok(Json(json!({ "success": true, "removed": device_id }))),
+        Ok(false) => Err(not_found_error("Push device")),
+        Err(e) => Err(engine_error("Push device error", &e)),
+    }
+}
+
+fn directory_user_id(user_id: Option<i64>) -> Result<i32, (StatusCode, Json<Value>)> {
+    match user_id {
+        Some(id) if id > 0 && id <= i32::MAX as i64 => Ok(id as i32),
+        _ => Err(bad_request_error("Missing or invalid user_id: must be positive")),
+    }
+}
+
+fn invite_token(headers: &HeaderMap) -> Option<&str> {
+    headers.get("x-invite-token").and_then(|v| v.to_str().ok())
+}
+
+#[derive(Debug, Deserialize)]
+struct DirectoryQuery {
+    user_id: Option<i64>,
+}
+
+// Publish a group's GroupInfo for external joins; the first publisher owns the listing
+async fn publish_directory_group_endpoint(
+    State(app_state): State<AppState>,
+    Path(app_group_id): Path<String>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    let user_id = directory_user_id(payload.get("user_id").and_then(|v| v.as_i64()))?;
+    let group_info = payload
+        .get("group_info")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| bad_request_error("Missing group_info"))?;
+    let member_user_ids: Option<Vec<i32>> = match payload.get("member_user_ids") {
+        None | Some(Value::Null) => None,
+        Some(v) => Some(serde_json::from_value(v.clone()).map_err(|_| {
+            bad_request_error("Invalid member_user_ids: must be an array of user ids")
+        })?),
+    };
+    let decoded = mls_delivery::decode_hex("group_info", group_info).and_then(|group_info| {
+        let ratchet_tree = payload
+            .get("ratchet_tree")
+            .and_then(|v| v.as_str())
+            .map(|tree| mls_delivery::decode_hex("ratchet_tree", tree))
+            .transpose()?;
+        Ok((group_info, ratchet_tree))
+    });
+    let (group_info, ratchet_tree) = decoded.map_err(|e| bad_request_error(&e.to_string()))?;
+    match group_directory::publish_group(
+        &app_state.db,
+        &app_group_id,
+        user_id,
+        &group_info,
+        ratchet_tree.as_deref(),
+        member_user_ids.as_deref(),
+    )
+    .await
+    {
+        Ok(listing) => Ok(Json(json!({ "success": true, "group": listing }))),
+        Err(e) => Err(engine_error("Group directory error", &e)),
+    }
+}
+
+// Members read freely; anyone else needs an invite token, which this redeems
+async fn directory_listing_endpoint(
+    State(app_state): State<AppState>,
+    Path(app_group_id): Path<String>,
+    Query(params): Query<DirectoryQuery>,
+    headers: HeaderMap,
+) -> ApiResult<Value> {
+    let user_id = directory_user_id(params.user_id)?;
+    match group_directory::get_listing(
+        &app_state.db,
+        &app_group_id,
+        user_id,
+        invite_token(&headers),
+    )
+    .await
+    {
+        Ok(listing) => Ok(Json(json!(listing))),
+        Err(e) => Err(engine_error("Group directory error", &e)),
+    }
+}
+
+async fn mint_directory_invite_endpoint(
+    State(app_state): State<AppState>,
+    Path(app_group_id): Path<String>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    let user_id = directory_user_id(payload.get("user_id").and_then(|v| v.as_i64()))?;
+    let invitee = payload
+        .get("invitee_user_id")
+        .and_then(|v| v.as_i64())
+        .map(|id| id as i32);
+    let ttl_hours = payload
+        .get("ttl_hours")
+        .and_then(|v| v.as_i64())
+        .unwrap_or(group_directory::DEFAULT_INVITE_TTL_HOURS);
+    match group_directory::mint_invite(&app_state.db, &app_group_id, user_id, invitee, ttl_hours)
+        .await
+    {
+        Ok(invite) => Ok(Json(json!({ "success": true, "invite": invite }))),
+        Err(e) => Err(engine_error("Group invite error", &e)),
+    }
+}
+
+async fn revoke_directory_invite_endpoint(
+    State(app_state): State<AppState>,
+    Path((app_group_id, invite_id)): Path<(String, i64)>,
+    Query(params): Query<DirectoryQuery>,
+) -> ApiResult<Value> {
+    let user_id = directory_user_id(params.user_id)?;
+    match group_directory::revoke_invite(&app_state.db, &app_group_id, user_id, invite_id).await {
+        Ok(true) => Ok(Json(json!({ "success": true, "revoked": invite_id }))),
+        Ok(false) => Err(not_found_error("Invite")),
+        Err(e) => Err(engine_error("Group invite error", &e)),
+    }
+}
+
+async fn post_directory_welcome_endpoint(
+    State(app_state): State<AppState>,
+    Path(app_group_id): Path<String>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    let user_id = directory_user_id(payload.get("user_id").and_then(|v| v.as_i64()))?;
+    let welcome = payload
+        .get("welcome")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| bad_request_error("Missing welcome"))?;
+    let welcome = mls_delivery::decode_hex("welcome", welcome)
+        .map_err(|e| bad_request_error(&e.to_string()))?;
+    match group_directory::post_welcome(&app_state.db, &app_group_id, user_id, &welcome).await {
+        Ok(recipients) => Ok(Json(json!({ "success": true, "key_package_refs": recipients }))),
+        Err(e) => Err(engine_error("MLS welcome error", &e)),
+    }
+}
+
+async fn directory_welcomes_endpoint(
+    State(app_state): State<AppState>,
+    Path((app_group_id, key_package_ref)): Path<(String, String)>,
+    Query(params): Query<DirectoryQuery>,
+    headers: HeaderMap,
+) -> ApiResult<Value> {
+    let user_id = directory_user_id(params.user_id)?;
+    let key_package_ref = mls_delivery::decode_hex("key_package_ref", &key_package_ref)
+        .map_err(|e| bad_request_error(&e.to_string()))?;
+    match group_directory::get_welcomes(
+        &app_state.db,
+        &app_group_id,
+        user_id,
+        invite_token(&headers),
+        &key_package_ref,
+    )
+    .await
+    {
+        Ok(welcomes) => Ok(Json(json!({ "welcomes": welcomes }))),
+        Err(e) => Err(engine_error("MLS welcomes error", &e)),
+    }
+}
+
+// Compact settled forecasts, a batch at a time until none are left
+async fn run_forecast_compaction(app_state: &AppState) -> anyhow::Result<forecasts::Compaction> {
+    let market = &app_state.config.market;
+    let mut total = forecasts::Compaction {
+        archived: market.archive_compacted_forecasts,
+        ..Default::default()
+    };
+    loop {
+        let batch = forecasts::compact_settled(
+            &app_state.db,
+            market.dispute_window_hours,
+            market.archive_compacted_forecasts,
+        )
+        .await?;
+        if batch.predictions == 0 {
+            break;
+        }
+        total.predictions += batch.predictions;
+        total.revisions += batch.revisions;
+    }
+    if total.predictions > 0 {
+        println!(
+            "🗜️ Compacted {} forecasts ({} revisions)",
+            total.predictions, total.revisions
+        );
+    }
+    Ok(total)
+}
+
+// Run forecast compaction on demand (cron or admin)
+async fn forecast_compaction_endpoint(State(app_state): State<AppState>) -> ApiResult<Value> {
+    match run_forecast_compaction(&app_state).await {
+        Ok(compaction) => Ok(Json(json!({ "success": true, "compaction": compaction }))),
+        Err(e) => Err(engine_error("Forecast compaction error", &e)),
+    }
+}
+
+// Archive long-resolved markets, a batch at a time until none are left
+async fn run_archival(app_state: &AppState) -> anyhow::Result<archive::Archival> {
+    let mut total = archive::Archival::default();
+    loop {
+        let batch =
+            archive::archive_resolved(&app_state.db, app_state.config.market.archive_after_days)
+                .await?;
+        if batch.events == 0 {
+            break;
+        }
+        total.events += batch.events;
+        total.rows += batch.rows;
+    }
+    if total.events > 0 {
+        println!(
+            "🧊 Archived {} resolved markets ({} rows)",
+            total.events, total.rows
+        );
+    }
+    Ok(total)
+}
+
+// Run market archival on demand (cron or admin)
+async fn archive_run_endpoint(State(app_state): State<AppState>) -> ApiResult<Value> {
+    match run_archival(&app_state).await {
+        Ok(archival) => Ok(Json(json!({ "success": true, "archival": archival }))),
+        Err(e) => Err(engine_error("Market archival error", &e)),
+    }
+}
+
+// Load the given targets into the caches, logging what each loaded
+async fn run_cache_warm(
+    app_state: &AppState,
+    targets: &[cache_warming::WarmTarget],
+) -> cache_warming::WarmReport {
+    let config = &app_state.config.cache_warming;
+    let caches = cache_warming::WarmCaches {
+        responses: &app_state.cache,
+        markets: &app_state.market_cache,
+        market_limit: config.market_limit as i64,
+        dashboard_limit: config.dashboard_limit as i64,
+        score_quota: app_state.config.score_quota,
+    };
+    let report =
+        cache_warming::warm(&app_state.db, &app_state.analytics_db, &caches, targets).await;
+    for target in &report.targets {
+        match &target.error {
+            None => println!("🔥 Warmed {} ({} entries)", target.target, target.entries),
+            Some(e) => eprintln!(
+                "❌ Cache warming {} failed after {} entries: {}",
+                target.target, target.entries, e
+            ),
+        }
+    }
+    report
+}
+
+#[derive(Debug, Deserialize)]
+struct CacheWarmQuery {
+    targets: Option<String>,
+}
+
+// Warm the caches after a deploy; defaults to the startup targets
+async fn cache_warm_endpoint(
+    State(app_state): State<AppState>,
+    Query(params): Query<CacheWarmQuery>,
+) -> ApiResult<Value> {
+    let raw = params
+        .targets
+        .unwrap_or_else(|| app_state.config.cache_warming.targets.join(","));
+    let targets = cache_warming::WarmTarget::parse_list(&raw)
+        .map_err(|e| bad_request_error(&e.to_string()))?;
+    if targets.is_empty() {
+        return Err(bad_request_error("targets must name at least one cache warm target"));
+    }
+    let report = run_cache_warm(&app_state, &targets).await;
+    Ok(Json(json!({
+        "success": report.failed == 0,
+        "warming": report,
+    })))
+}
+
+// Compare the leaderboard under candidate scoring parameters with today's
+async fn backtest_endpoint(
+    State(app_state): State<AppState>,
+    ExtractJson(request): ExtractJson<backtest::BacktestRequest>,
+) -> ApiResult<Value> {
+    match backtest::run(&app_state.analytics_db, &request).await {
+        Ok(report) => Ok(Json(json!(report))),
+        Err(e) => Err(engine_error("Backtest error", &e)),
+    }
+}
+
+// How much is in cold storage
+async fn archive_status_endpoint(State(app_state): State<AppState>) -> ApiResult<Value> {
+    match archive::get_status(&app_state.analytics_db).await {
+        Ok(status) => Ok(Json(status)),
+        Err(e) => Err(engine_error("Archive status error", &e)),
+    }
+}
+
+// Create upcoming market_updates partitions and drain the default one
+async fn run_partition_maintenance(
+    app_state: &AppState,
+) -> anyhow::Result<market_partitions::Maintenance> {
+    let report = market_partitions::maintain(
+        &app_state.db,
+        app_state.config.market.partition_months_ahead,
+    )
+    .await?;
+    if !report.partitions_created.is_empty() {
+        println!(
+            "🗂️ Created market_updates partitions {} ({} rows moved)",
+            report.partitions_created.join(", "),
+            report.rows_moved
+        );
+    }
+    Ok(report)
+}
+
+// Run partition maintenance on demand (cron or admin)
+async fn partition_maintenance_endpoint(State(app_state): State<AppState>) -> ApiResult<Value> {
+    match run_partition_maintenance(&app_state).await {
+        Ok(report) => Ok(Json(json!({ "success": true, "maintenance": report }))),
+        Err(e) => Err(engine_error("Partition maintenance error", &e)),
+    }
+}
+
+// market_updates partitions with their bounds and sizes
+async fn partition_status_endpoint(State(app_state): State<AppState>) -> ApiResult<Value> {
+    match market_partitions::get_status(&app_state.analytics_db).await {
+        Ok(status) => Ok(Json(status)),
+        Err(e) => Err(engine_error("Partition status error", &e)),
+    }
+}
+
+// Archive one resolved market now, e.g. again after an audit restored it
+async fn archive_event_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+) -> ApiResult<Value> {
+    let window_hours = app_state.config.market.dispute_window_hours;
+    match archive::archive_event(&app_state.db, event_id, window_hours).await {
+        Ok(report) => Ok(Json(json!({ "success": true, "archive": report }))),
+        Err(e) => Err(engine_error("Archive error", &e)),
+    }
+}
+
+// Move an archived market's rows back to the hot tables for an audit
+async fn restore_event_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    let actor = payload.get("actor").and_then(|v| v.as_str());
+    let reason = payload.get("reason").and_then(|v| v.as_str());
+    match archive::restore_event(&app_state.db, event_id, actor, reason).await {
+        Ok(report) => Ok(Json(json!({ "success": true, "restore": report }))),
+        Err(e) => Err(engine_error("Archive error", &e)),
+    }
+}
+
+// Recompute weighted consensus probabilities on demand (cron or admin)
+async fn consensus_refresh_endpoint(State(app_state): State<AppState>) -> ApiResult<Value> {
+    match consensus::refresh_weighted_probs(&app_state.db, None).await {
+        Ok(updated) => Ok(Json(json!({ "success": true, "updated": updated }))),
+        Err(e) => Err(engine_error("Consensus refresh error", &e)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ConsensusAccuracyQuery {
+    category: Option<String>,
+    min_comment_velocity: Option<f64>,
+    limit: Option<i64>,
+}
+
+// How the weighted consensus has scored against the market price
+async fn consensus_accuracy_endpoint(
+    State(app_state): State<AppState>,
+    Query(params): Query<ConsensusAccuracyQuery>,
+) -> ApiResult<Value> {
+    match consensus::get_accuracy(
+        &app_state.analytics_db,
+        params.category.as_deref(),
+        params.min_comment_velocity,
+        params.limit.unwrap_or(50),
+    )
+    .await
+    {
+        Ok(report) => Ok(Json(report)),
+        Err(e) => Err(engine_error("Consensus accuracy error", &e)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MarketAccuracyQuery {
+    category: Option<String>,
+    min_trades: Option<i32>,
+    liquidity_edges: Option<String>,
+}
+
+// How well closing prices called resolved events, for tuning liquidity
+async fn market_accuracy_endpoint(
+    State(app_state): State<AppState>,
+    Query(params): Query<MarketAccuracyQuery>,
+) -> ApiResult<Value> {
+    let edges = market_accuracy::parse_liquidity_edges(params.liquidity_edges.as_deref())
+        .map_err(|e| bad_request_error(&e.to_string()))?;
+    match market_accuracy::get_accuracy(
+        &app_state.analytics_db,
+        params.category.as_deref(),
+        params.min_trades.unwrap_or(1),
+        &edges,
+    )
+    .await
+    {
+        Ok(report) => Ok(Json(report)),
+        Err(e) => Err(engine_error("Market accuracy error", &e)),
+    }
+}
+
+// Linked markets whose prices leave a gap, as of the last scan
+async fn arbitrage_endpoint(State(app_state): State<AppState>) -> ApiResult<Value> {
+    match arbitrage::report(&app_state.analytics_db).await {
+        Ok(report) => Ok(Json(report)),
+        Err(e) => Err(engine_error("Arbitrage report error", &e)),
+    }
+}
+
+// Per-category liquidity recommendations and whether each is auto-applied
+async fn liquidity_recommendations_endpoint(State(app_state): State<AppState>) -> ApiResult<Value> {
+    match liquidity_recommendations::list(&app_state.analytics_db).await {
+        Ok(report) => Ok(Json(report)),
+        Err(e) => Err(engine_error("Liquidity recommendations error", &e)),
+    }
+}
+
+async fn liquidity_recommendations_refresh_endpoint(
+    State(app_state): State<AppState>,
+) -> ApiResult<Value> {
+    match liquidity_recommendations::refresh(
+        &app_state.analytics_db,
+        app_state.config.market.liquidity_recommendation_min_markets,
+    )
+    .await
+    {
+        Ok(recommendations) => Ok(Json(json!({
+            "success": true,
+            "recommendations": recommendations,
+        }))),
+        Err(e) => Err(engine_error("Liquidity recommendation refresh error", &e)),
+    }
+}
+
+// Turn auto-apply of a category's recommendation on or off
+async fn liquidity_auto_apply_endpoint(
+    State(app_state): State<AppState>,
+    Path(category): Path<String>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    let auto_apply = payload
+        .get("auto_apply")
+        .and_then(|v| v.as_bool())
+        .ok_or_else(|| bad_request_error("Missing or invalid auto_apply flag"))?;
+    match liquidity_recommendations::set_auto_apply(&app_state.db, &category, auto_apply).await {
+        Ok(()) => Ok(Json(json!({
+            "success": true,
+            "category": category,
+            "auto_apply": auto_apply,
+        }))),
+        Err(e) => Err(engine_error("Liquidity auto-apply error", &e)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ScoreIntegrityQuery {
+    user_id: Option<i32>,
+}
+
+// Log and send a webhook for checksums that no longer match their inputs
+fn report_score_audit(app_state: &AppState, audit: &score_integrity::ScoreAudit) {
+    if audit.mismatches.is_empty() {
+        return;
+    }
+    webhooks::emit(
+        &app_state.db,
+        webhooks::SCORE_INTEGRITY_FAILED,
+        json!({
+            "user_ids": audit.mismatches.iter().map(|m| m.user_id).collect::<Vec<_>>(),
+            "mismatches": audit.mismatches,
+            "audited_at": audit.audited_at,
+        }),
+    );
+}
+
+// Recompute sealed score checksums and report any that changed out of band
+async fn score_audit_endpoint(
+    State(app_state): State<AppState>,
+    Query(params): Query<ScoreIntegrityQuery>,
+) -> ApiResult<Value> {
+    if params.user_id.is_some_and(|user_id| user_id <= 0) {
+        return Err(bad_request_error("Invalid user_id: must be positive"));
+    }
+    let job = start_job(&app_state, "score_audit");
+    let result =
+        score_integrity::audit_with_progress(&app_state.analytics_db, params.user_id, &job).await;
+    match job.finish(result) {
+        Ok(audit) => {
+            report_score_audit(&app_state, &audit);
+            Ok(Json(json!({
+                "valid": audit.mismatches.is_empty(),
+                "audit": audit,
+                "job_id": job.job_id(),
+            })))
+        }
+        Err(e) => Err(engine_error("Score audit error", &e)),
+    }
+}
+
+// Accept the current scoring inputs, e.g. to backfill or after an explained mismatch
+async fn score_reseal_endpoint(
+    State(app_state): State<AppState>,
+    Query(params): Query<ScoreIntegrityQuery>,
+) -> ApiResult<Value> {
+    if params.user_id.is_some_and(|user_id| user_id <= 0) {
+        return Err(bad_request_error("Invalid user_id: must be positive"));
+    }
+    let job = start_job(&app_state, "score_reseal");
+    let result = score_integrity::reseal_with_progress(&app_state.db, params.user_id, &job).await;
+    match job.finish(result) {
+        Ok(sealed) => Ok(Json(json!({
+            "success": true,
+            "sealed": sealed,
+            "job_id": job.job_id(),
+        }))),
+        Err(e) => Err(engine_error("Score reseal error", &e)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AdminAuditQuery {
+    action: Option<String>,
+    actor: Option<String>,
+    event_id: Option<i32>,
+    before_id: Option<i64>,
+    limit: Option<i64>,
+}
+
+// The admin action log, newest first; page back with before_id
+async fn admin_audit_endpoint(
+    State(app_state): State<AppState>,
+    Query(params): Query<AdminAuditQuery>,
+) -> ApiResult<Value> {
+    let filter = admin_audit::AuditFilter {
+        action: params.action.as_deref(),
+        actor: params.actor.as_deref(),
+        event_id: params.event_id,
+        before_id: params.before_id,
+    };
+    match admin_audit::list(
+        &app_state.analytics_db,
+        &filter,
+        params.limit.unwrap_or(100),
+    )
+    .await
+    {
+        Ok(entries) => Ok(Json(json!({
+            "count": entries.len(),
+            "next_before_id": entries.last().map(|entry| entry.id),
+            "entries": entries,
+        }))),
+        Err(e) => Err(engine_error("Admin audit error", &e)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CommentIngestRequest {
+    summaries: Vec<comment_buzz::CommentSummary>,
+}
+
+// Hourly comment summaries from the backend, shown as buzz in market state
+async fn comment_ingest_endpoint(
+    State(app_state): State<AppState>,
+    ExtractJson(payload): ExtractJson<CommentIngestRequest>,
+) -> ApiResult<Value> {
+    match comment_buzz::ingest(&app_state.db, &payload.summaries).await {
+        Ok((stored, skipped)) => {
+            if !stored.is_empty() {
+                invalidate_and_broadcast(
+                    &app_state,
+                    "comment_buzz_updated",
+                    json!({ "event_ids": stored }),
+                );
+            }
+            Ok(Json(json!({
+                "success": true,
+                "event_ids": stored,
+                "unknown_event_ids": skipped,
+            })))
+        }
+        Err(e) => Err(engine_error("Comment ingest error", &e)),
+    }
+}
+
+// Get market state for an event
+async fn get_market_state_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+) -> ApiResult<Value> {
+    match app_state.market_cache.get(&app_state.db, event_id).await {
+        Ok(market_state) => Ok(Json(market_state)),
+        Err(e) => Err(engine_error("Market state error", &e)),
+    }
+}
+
+// Get recent trades for an event
+async fn get_event_trades_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    Query(params): Query<HashMap<String, String>>,
+) -> ApiResult<Value> {
+    let limit: i32 = params
+        .get("limit")
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(50);
+
+    // Cap at 100 trades max
+    let limit = limit.min(100);
+
+    match lmsr_api::get_event_trades(&app_state.analytics_db, event_id, limit).await {
+        Ok(trades) => Ok(Json(trades)),
+        Err(e) => Err(engine_error("Trades fetch error", &e)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct StateAtQuery {
+    ts: Option<String>,
+}
+
+// A binary market's state at a past moment, rebuilt from the trade journal
+async fn market_state_at_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    Query(params): Query<StateAtQuery>,
+) -> ApiResult<Value> {
+    let ts = params
+        .ts
+        .as_deref()
+        .ok_or_else(|| bad_request_error("Missing ts"))?;
+    let at = chrono::DateTime::parse_from_rfc3339(ts)
+        .map_err(|_| bad_request_error("ts must be an RFC 3339 timestamp"))?
+        .with_timezone(&chrono::Utc);
+
+    match state_at::state_at(&app_state.analytics_db, event_id, at).await {
+        Ok(state) => Ok(Json(json!(state))),
+        Err(e) => Err(engine_error("Market state error", &e)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PriceHistoryQuery {
+    resolution: Option<String>,
+    buckets: Option<u32>,
+}
+
+// Price candles of a binary market for charting
+async fn price_history_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    Query(params): Query<PriceHistoryQuery>,
+) -> ApiResult<Value> {
+    let resolution = params.resolution.as_deref().unwrap_or("1h");
+    let buckets = params.buckets.unwrap_or(price_history::DEFAULT_BUCKETS);
+    match price_history::get_history(&app_state.analytics_db, event_id, resolution, buckets).await {
+        Ok(history) => Ok(Json(json!(history))),
+        Err(e) => Err(engine_error("Price history error", &e)),
+    }
+}
+
+// Admin view of the trade tape with usernames; each view is audited
+async fn identified_trades_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    let actor = payload
+        .get("actor")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| bad_request_error("Missing actor"))?;
+    let reason = payload
+        .get("reason")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| bad_request_error("Missing reason"))?;
+    let limit = payload
+        .get("limit")
+        .and_then(|v| v.as_i64())
+        .unwrap_or(50)
+        .clamp(1, 100) as i32;
+
+    match trade_privacy::get_identified_trades(
+        &app_state.analytics_db,
+        event_id,
+        limit,
+        actor,
+        reason,
+    )
+    .await
+    {
+        Ok(trades) => {
+            println!(
+                "🕵️ {} viewed trader identities on event {}",
+                actor.trim(),
+                event_id
+            );
+            Ok(Json(trades))
+        }
+        Err(e) => Err(engine_error("Trade privacy error", &e)),
+    }
+}
+
+// Toggle anonymous trading for a market
+async fn set_event_privacy_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    let anonymous = payload
+        .get("anonymous")
+        .and_then(|v| v.as_bool())
+        .ok_or_else(|| bad_request_error("Missing or invalid anonymous flag"))?;
+    let actor = payload
+        .get("actor")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| bad_request_error("Missing actor"))?;
+    let reason = payload.get("reason").and_then(|v| v.as_str());
+
+    match trade_privacy::set_anonymous(&app_state.db, event_id, anonymous, actor, reason).await {
+        Ok(result) => {
+            if result["changed"] == true {
+                invalidate_and_broadcast(
+                    &app_state,
+                    "market_privacy_changed",
+                    json!({ "event_id": event_id, "anonymous": anonymous }),
+                );
+            }
+            Ok(Json(json!({ "success": true, "privacy": result })))
+        }
+        Err(e) => Err(engine_error("Trade privacy error", &e)),
+    }
+}
+
+// Structured resolution criteria, fine print, unit/bounds and source URL
+async fn get_event_metadata_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+) -> ApiResult<Value> {
+    match event_metadata::get_metadata(&app_state.analytics_db, event_id).await {
+        Ok(metadata) => Ok(Json(json!({ "event_id": event_id, "metadata": metadata }))),
+        Err(e) => Err(engine_error("Event metadata error", &e)),
+    }
+}
+
+// Admin edit of an event's metadata; fields left out are kept, null clears
+async fn update_event_metadata_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    match event_metadata::update_metadata(&app_state.db, event_id, &payload).await {
+        Ok(metadata) => {
+            invalidate_and_broadcast(
+                &app_state,
+                "event_metadata_updated",
+                json!({ "event_id": event_id, "metadata": metadata }),
+            );
+            Ok(Json(
+                json!({ "success": true, "event_id": event_id, "metadata": metadata }),
+            ))
+        }
+        Err(e) => Err(engine_error("Metadata update error", &e)),
+    }
+}
+
+// Anonymity changes and identified-tape views for a market
+async fn trade_identity_audit_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+) -> ApiResult<Value> {
+    match trade_privacy::get_identity_audit(&app_state.analytics_db, event_id).await {
+        Ok(audit) => Ok(Json(audit)),
+        Err(e) => Err(engine_error("Trade privacy error", &e)),
+    }
+}
+
+// Trading embargo windows on a market, newest first
+async fn list_embargoes_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+) -> ApiResult<Value> {
+    match embargo::list_windows(&app_state.analytics_db, event_id).await {
+        Ok(windows) => Ok(Json(json!({ "event_id": event_id, "windows": windows }))),
+        Err(e) => Err(engine_error("Embargo error", &e)),
+    }
+}
+
+// Pause trading on a market for a window; starts_at defaults to now
+async fn add_embargo_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    let timestamp = |field: &str| -> Result<Option<chrono::DateTime<chrono::Utc>>, _> {
+        match payload.get(field).and_then(|v| v.as_str()) {
+            Some(raw) => chrono::DateTime::parse_from_rfc3339(raw)
+                .map(|at| Some(at.with_timezone(&chrono::Utc)))
+                .map_err(|_| bad_request_error(&format!("{} must be an RFC 3339 timestamp", field))),
+            None => Ok(None),
+        }
+    };
+    let starts_at = timestamp("starts_at")?.unwrap_or_else(chrono::Utc::now);
+    let ends_at = timestamp("ends_at")?.ok_or_else(|| bad_request_error("Missing ends_at"))?;
+    let actor = payload
+        .get("actor")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| bad_request_error("Missing actor"))?;
+    let reason = payload.get("reason").and_then(|v| v.as_str());
+
+    match embargo::add_window(&app_state.db, event_id, starts_at, ends_at, reason, actor).await {
+        Ok(window) => {
+            app_state.market_cache.invalidate(event_id).await;
+            invalidate_and_broadcast(
+                &app_state,
+                "market_embargo_changed",
+                json!({ "event_id": event_id, "added": window }),
+            );
+            Ok(Json(json!({ "success": true, "window": window })))
+        }
+        Err(e) => Err(engine_error("Embargo error", &e)),
+    }
+}
+
+// Delete an embargo window, lifting it early if it is open
+async fn remove_embargo_endpoint(
+    State(app_state): State<AppState>,
+    Path((event_id, embargo_id)): Path<(i32, i64)>,
+) -> ApiResult<Value> {
+    match embargo::remove_window(&app_state.db, event_id, embargo_id).await {
+        Ok(true) => {
+            app_state.market_cache.invalidate(event_id).await;
+            invalidate_and_broadcast(
+                &app_state,
+                "market_embargo_changed",
+                json!({ "event_id": event_id, "removed": embargo_id }),
+            );
+            Ok(Json(json!({ "success": true, "removed": embargo_id })))
+        }
+        Ok(false) => Err(not_found_error("Embargo window")),
+        Err(e) => Err(engine_error("Embargo error", &e)),
+    }
+}
+
+// Move a live binary market to a new liquidity_b without moving its price
+async fn migrate_liquidity_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    let new_b = payload
+        .get("liquidity_b")
+        .and_then(|v| v.as_f64())
+        .ok_or_else(|| bad_request_error("Missing or invalid liquidity_b"))?;
+    let actor = payload
+        .get("actor")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| bad_request_error("Missing actor"))?;
+    let reason = payload.get("reason").and_then(|v| v.as_str());
+
+    match liquidity_migration::migrate_liquidity(&app_state.db, event_id, new_b, actor, reason)
+        .await
+    {
+        Ok(migration) => {
+            if let Err(e) = app_state
+                .market_cache
+                .write_through(&app_state.db, event_id)
+                .await
+            {
+                eprintln!(
+                    "❌ Market state write-through failed for event {}: {}",
+                    event_id, e
+                );
+            }
+            invalidate_and_broadcast(
+                &app_state,
+                "market_liquidity_changed",
+                json!({
+                    "event_id": event_id,
+                    "liquidity_b": migration.new_b,
+                    "market_prob": migration.market_prob,
+                }),
+            );
+            Ok(Json(json!({ "success": true, "migration": migration })))
+        }
+        Err(e) => Err(engine_error("Liquidity migration error", &e)),
+    }
+}
+
+// Liquidity changes on a market, newest first
+async fn liquidity_migrations_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+) -> ApiResult<Value> {
+    match liquidity_migration::list_migrations(&app_state.analytics_db, event_id).await {
+        Ok(migrations) => Ok(Json(
+            json!({ "event_id": event_id, "migrations": migrations }),
+        )),
+        Err(e) => Err(engine_error("Liquidity migrations error", &e)),
+    }
+}
+
+// Update market with new stake
+async fn update_market_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    // Validate event_id
+    if event_id <= 0 {
+        return Err(bad_request_error("Invalid event_id: must be positive"));
+    }
+
+    // Validate user_id - require explicit value, no defaults
+    let user_id = payload
+        .get("user_id")
+        .and_then(|v| v.as_i64())
+        .ok_or_else(|| {
+            bad_request_error("Missing or invalid user_id: must be a positive integer")
+        })? as i32;
+    if user_id <= 0 {
+        return Err(bad_request_error("Invalid user_id: must be positive"));
+    }
+
+    // Validate target_prob - require explicit value, no defaults
+    let target_prob = payload
+        .get("target_prob")
+        .and_then(|v| v.as_f64())
+        .ok_or_else(|| {
+            bad_request_error("Missing or invalid target_prob: must be a finite number")
+        })?;
+    if !target_prob.is_finite() {
+        return Err(bad_request_error("Invalid target_prob: must be finite"));
+    }
+    if target_prob <= 0.0 || target_prob >= 1.0 {
+        return Err(bad_request_error(
+            "Invalid target_prob: must be between 0 and 1 (exclusive)",
+        ));
+    }
+
+    // Validate stake - require explicit value, no defaults
+    let stake = payload
+        .get("stake")
+        .and_then(|v| v.as_f64())
+        .ok_or_else(|| bad_request_error("Missing or invalid stake: must be a finite number"))?;
+    if !stake.is_finite() {
+        return Err(bad_request_error("Invalid stake: must be finite"));
+    }
+    if stake <= 0.0 {
+        return Err(bad_request_error("Invalid stake: must be positive"));
+    }
+    if stake > 1_000_000.0 {
+        // 1M RP max per trade
+        return Err(bad_request_error(
+            "Invalid stake: exceeds maximum allowed (1,000,000 RP)",
+        ));
+    }
+    if stake < 0.01 {
+        // Minimum 0.01 RP
+        return Err(bad_request_error(
+            "Invalid stake: below minimum allowed (0.01 RP)",
+        ));
+    }
+
+    // Optional slippage bounds; the trade aborts rather than fill past them
+    let bound = |name: &str| -> Result<Option<f64>, (StatusCode, Json<Value>)> {
+        match payload.get(name) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => value
+                .as_f64()
+                .filter(|v| v.is_finite())
+                .map(Some)
+                .ok_or_else(|| {
+                    bad_request_error(&format!("Invalid {}: must be a finite number", name))
+                }),
+        }
+    };
+    let max_cost = bound("max_cost")?;
+    if max_cost.is_some_and(|max_cost| max_cost <= 0.0) {
+        return Err(bad_request_error("Invalid max_cost: must be positive"));
+    }
+    let min_shares = bound("min_shares")?;
+    if min_shares.is_some_and(|min_shares| min_shares < 0.0) {
+        return Err(bad_request_error("Invalid min_shares: must not be negative"));
+    }
+
+    let update = lmsr_api::MarketUpdate {
+        event_id,
+        target_prob,
+        stake,
+        referral_post_id: payload
+            .get("referral_post_id")
+            .and_then(|value| value.as_i64())
+            .filter(|value| *value > 0)
+            .map(|value| value as i32),
+        referral_click_id: payload
+            .get("referral_click_id")
+            .and_then(|value| value.as_i64())
+            .filter(|value| *value > 0)
+            .map(|value| value as i32),
+        max_cost,
+        min_shares,
+    };
+
+    match lmsr_api::update_market(&app_state.db, &app_state.config, user_id, update).await {
+        Ok(result) => {
+            broadcast_trade(
+                &app_state,
+                "market_updated",
+                json!({
+                    "event_id": event_id,
+                    "user_id": user_id,
+                    "new_prob": result.new_prob,
+                    "shares_acquired": result.shares_acquired
+                }),
+            )
+            .await;
+            invariants::sample_after_trade(&app_state.analytics_db, user_id, event_id, "buy");
+            spawn_limit_order_matcher(&app_state, event_id);
+            Ok(Json(json!(result)))
+        }
+        Err(e) => Err(engine_error("Market update error", &e)),
+    }
+}
+
+// Update market for an explicit outcome (multiple choice / numeric buckets)
+async fn update_market_outcome_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    if event_id <= 0 {
+        return Err(bad_request_error("Invalid event_id: must be positive"));
+    }
+
+    let user_id = payload
+        .get("user_id")
+        .and_then(|v| v.as_i64())
+        .ok_or_else(|| {
+            bad_request_error("Missing or invalid user_id: must be a positive integer")
+        })? as i32;
+    if user_id <= 0 {
+        return Err(bad_request_error("Invalid user_id: must be positive"));
+    }
+
+    let outcome_id = payload
+        .get("outcome_id")
+        .and_then(|v| v.as_i64())
+        .ok_or_else(|| bad_request_error("Missing or invalid outcome_id"))?;
+    if outcome_id <= 0 {
+        return Err(bad_request_error("Invalid outcome_id: must be positive"));
+    }
+
+    let stake = payload
+        .get("stake")
+        .and_then(|v| v.as_f64())
+        .ok_or_else(|| bad_request_error("Missing or invalid stake: must be a finite number"))?;
+    if !stake.is_finite() {
+        return Err(bad_request_error("Invalid stake: must be finite"));
+    }
+    if !(0.01..=1_000_000.0).contains(&stake) {
+        return Err(bad_request_error(
+            "Invalid stake: must be within [0.01, 1,000,000] RP",
+        ));
+    }
+
+    let update = lmsr_api::OutcomeMarketUpdate {
+        event_id,
+        outcome_id,
+        stake,
+        referral_post_id: payload
+            .get("referral_post_id")
+            .and_then(|value| value.as_i64())
+            .filter(|value| *value > 0)
+            .map(|value| value as i32),
+        referral_click_id: payload
+            .get("referral_click_id")
+            .and_then(|value| value.as_i64())
+            .filter(|value| *value > 0)
+            .map(|value| value as i32),
+    };
+
+    match lmsr_api::update_market_outcome(&app_state.db, &app_state.config, user_id, update).await {
+        Ok(result) => {
+            broadcast_trade(
+                &app_state,
+                "market_updated",
+                json!({
+                    "event_id": event_id,
+                    "user_id": user_id,
+                    "new_prob": result.market_prob,
+                    "outcome_id": result.outcome_id
+                }),
+            )
+            .await;
+            invariants::sample_after_trade(
+                &app_state.analytics_db,
+                user_id,
+                event_id,
+                "buy_outcome",
+            );
+            Ok(Json(json!(result)))
+        }
+        Err(e) => Err(engine_error("Market outcome update error", &e)),
+    }
+}
+
+// Sell shares of an explicit outcome (multiple choice / numeric buckets)
+async fn sell_outcome_shares_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    if event_id <= 0 {
+        return Err(bad_request_error("Invalid event_id: must be positive"));
+    }
+
+    let user_id = payload
+        .get("user_id")
+        .and_then(|v| v.as_i64())
+        .ok_or_else(|| {
+            bad_request_error("Missing or invalid user_id: must be a positive integer")
+        })? as i32;
+    if user_id <= 0 {
+        return Err(bad_request_error("Invalid user_id: must be positive"));
+    }
+
+    let outcome_id = payload
+        .get("outcome_id")
+        .and_then(|v| v.as_i64())
+        .ok_or_else(|| bad_request_error("Missing or invalid outcome_id"))?;
+    if outcome_id <= 0 {
+        return Err(bad_request_error("Invalid outcome_id: must be positive"));
+    }
+
+    let amount = payload
+        .get("amount")
+        .and_then(|v| v.as_f64())
+        .ok_or_else(|| bad_request_error("Missing or invalid amount: must be a finite number"))?;
+    if !amount.is_finite() || amount <= 0.0 {
+        return Err(bad_request_error("Invalid amount: must be positive and finite"));
+    }
+    if amount > 10_000_000.0 {
+        return Err(bad_request_error(
+            "Invalid amount: exceeds maximum allowed (10,000,000 shares)",
+        ));
+    }
+    if amount < 0.000001 {
+        return Err(bad_request_error(
+            "Invalid amount: below minimum allowed (0.000001 shares)",
+        ));
+    }
+
+    match lmsr_api::sell_outcome_shares(
+        &app_state.db,
+        &app_state.config,
+        user_id,
+        event_id,
+        outcome_id,
+        amount,
+    )
+    .await
+    {
+        Ok(result) => {
+            broadcast_trade(
+                &app_state,
+                "shares_sold",
+                json!({
+                    "event_id": event_id,
+                    "user_id": user_id,
+                    "outcome_id": outcome_id,
+                    "amount": amount,
+                    "payout": result.payout,
+                    "new_prob": result.market_prob,
+                    "cumulative_stake": result.current_cost_c
+                }),
+            )
+            .await;
+            invariants::sample_after_trade(
+                &app_state.analytics_db,
+                user_id,
+                event_id,
+                "sell_outcome",
+            );
+            rank_history::mark_dirty(event_id);
+            Ok(Json(json!(result)))
+        }
+        Err(e) => Err(engine_error("Outcome sell error", &e)),
+    }
+}
+
+// ============================================================================
+// NUMERIC MARKET ENDPOINTS (50-bin dense-vector LMSR trading)
+// ============================================================================
+
+/// Parse the `target` query/body param into `Vec<f64>`. Shape validation
+/// (exact outcome count, finite, >= 0, sum > 0) happens in lmsr_api once the
+/// market's configured outcome count (inbound bins + open tails) is known;
+/// this only parses the wire format.
+fn parse_target_query_param(
+    params: &HashMap<String, String>,
+) -> Result<Vec<f64>, (axum::http::StatusCode, Json<Value>)> {
+    let raw = params
+        .get("target")
+        .ok_or_else(|| bad_request_error("Missing target: comma-separated floats required"))?;
+    let mut target = Vec::new();
+    for part in raw.split(',') {
+        let trimmed = part.trim();
+        if trimmed.is_empty() {
+            return Err(bad_request_error(
+                "Invalid target: empty entry in comma-separated list",
+            ));
+        }
+        let value: f64 = trimmed
+            .parse()
+            .map_err(|_| bad_request_error("Invalid target: all entries must be numbers"))?;
+        target.push(value);
+    }
+    Ok(target)
+}
+
+/// Shared error mapping for the numeric market endpoints. The two
+/// "expected rejection" cases (stale market_version, cost exceeding
+/// max_cost_ledger) never reach here — they come back as typed `Ok(..)`
+/// variants from lmsr_api and are mapped to 409 directly by each handler.
+fn numeric_error_response(e: &anyhow::Error) -> (axum::http::StatusCode, Json<Value>) {
+    engine_error("Numeric market error", e)
+}
+
+#[cfg(test)]
+mod numeric_error_response_tests {
+    use super::*;
+    use anyhow::anyhow;
+    use axum::http::StatusCode;
+
+    // Validation failures are coded by lmsr_api, so a length mismatch (the
+    // "outcome count" case tail outcomes introduced) answers 400 with the
+    // domain's message, while the same text uncoded is an engine failure.
+    #[test]
+    fn outcome_count_mismatch_maps_to_400() {
+        let message = "target must have exactly 52 entries, got 50";
+        let (status, Json(body)) =
+            numeric_error_response(&api_error::coded(ErrorCode::InvalidRequest, message));
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            (body["code"].as_str(), body["error"].as_str()),
+            (Some("INVALID_REQUEST"), Some(message))
+        );
+        let (status, _) = numeric_error_response(&anyhow!(message));
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn unexpected_error_maps_to_500() {
+        let (status, _) = numeric_error_response(&anyhow!("connection reset by peer"));
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn rejections_carry_their_error_code() {
+        let (status, Json(body)) = numeric_error_response(&api_error::coded(
+            ErrorCode::InsufficientBalance,
+            "Insufficient RP balance",
+        ));
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "INSUFFICIENT_BALANCE");
+        // The code comes from the error, not from what its message says
+        let (status, Json(body)) = numeric_error_response(&anyhow!("Insufficient RP balance"));
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["code"], "INTERNAL");
+    }
+}
+
+// Read-only quote for a target distribution + budget on a numeric market.
+async fn numeric_quote_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    Query(params): Query<HashMap<String, String>>,
+) -> ApiResult<Value> {
+    if event_id <= 0 {
+        return Err(bad_request_error("Invalid event_id: must be positive"));
+    }
+
+    let budget_ledger = params
+        .get("budget_ledger")
+        .and_then(|s| s.parse::<i64>().ok())
+        .ok_or_else(|| {
+            bad_request_error("Missing or invalid budget_ledger: must be an integer")
+        })?;
+    if budget_ledger <= 0 {
+        return Err(bad_request_error("Invalid budget_ledger: must be positive"));
+    }
+
+    let target = parse_target_query_param(&params)?;
+
+    match lmsr_api::get_numeric_quote(&app_state.db, event_id, budget_ledger, target).await {
+        Ok(quote) => Ok(Json(json!(quote))),
+        Err(e) => Err(numeric_error_response(&e)),
+    }
+}
+
+// Trade toward a target distribution on a numeric market.
+async fn numeric_trade_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    if event_id <= 0 {
+        return Err(bad_request_error("Invalid event_id: must be positive"));
+    }
+
+    let user_id = payload
+        .get("user_id")
+        .and_then(|v| v.as_i64())
+        .ok_or_else(|| {
+            bad_request_error("Missing or invalid user_id: must be a positive integer")
+        })? as i32;
+    if user_id <= 0 {
+        return Err(bad_request_error("Invalid user_id: must be positive"));
+    }
+
+    let target_values = payload
+        .get("target")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| bad_request_error("Missing or invalid target: must be an array of numbers"))?;
+    let mut target = Vec::with_capacity(target_values.len());
+    for entry in target_values {
+        let value = entry
+            .as_f64()
+            .ok_or_else(|| bad_request_error("Invalid target: all entries must be numbers"))?;
+        target.push(value);
+    }
+
+    let budget_ledger = payload
+        .get("budget_ledger")
+        .and_then(|v| v.as_i64())
+        .ok_or_else(|| {
+            bad_request_error("Missing or invalid budget_ledger: must be an integer")
+        })?;
+    if budget_ledger <= 0 {
+        return Err(bad_request_error("Invalid budget_ledger: must be positive"));
+    }
+
+    let max_cost_ledger = payload
+        .get("max_cost_ledger")
+        .and_then(|v| v.as_i64())
+        .ok_or_else(|| {
+            bad_request_error("Missing or invalid max_cost_ledger: must be an integer")
+        })?;
+    if max_cost_ledger <= 0 {
+        return Err(bad_request_error(
+            "Invalid max_cost_ledger: must be positive",
+        ));
+    }
+
+    let market_version = payload
+        .get("market_version")
+        .and_then(|v| v.as_i64())
+        .ok_or_else(|| {
+            bad_request_error("Missing or invalid market_version: must be an integer")
+        })?;
+    if market_version < 0 {
+        return Err(bad_request_error(
+            "Invalid market_version: must be non-negative",
+        ));
+    }
+
+    match lmsr_api::numeric_trade(
+        &app_state.db,
+        user_id,
+        event_id,
+        target,
+        budget_ledger,
+        max_cost_ledger,
+        market_version,
+    )
+    .await
+    {
+        Ok(lmsr_api::NumericTradeOutcome::Executed(result)) => {
+            broadcast_trade(
+                &app_state,
+                "numeric_market_traded",
+                json!({
+                    "event_id": event_id,
+                    "user_id": user_id,
+                    "cost_ledger": result.cost_ledger,
+                    "market_version": result.market_version
+                }),
+            )
+            .await;
+            invariants::sample_after_trade(
+                &app_state.analytics_db,
+                user_id,
+                event_id,
+                "numeric_trade",
+            );
+            Ok(Json(json!(result)))
+        }
+        Ok(lmsr_api::NumericTradeOutcome::StaleVersion(quote)) => Err((
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "market_version is stale; retry with the fresh quote",
+                "code": ErrorCode::StaleVersion,
+                "quote": quote
+            })),
+        )),
+        Ok(lmsr_api::NumericTradeOutcome::CostExceeded(quote)) => Err((
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "recomputed cost exceeds max_cost_ledger; retry with the fresh quote",
+                "code": ErrorCode::SlippageExceeded,
+                "quote": quote
+            })),
+        )),
+        Err(e) => Err(numeric_error_response(&e)),
+    }
+}
+
+// Sell a user's entire numeric-market position.
+async fn numeric_sell_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    if event_id <= 0 {
+        return Err(bad_request_error("Invalid event_id: must be positive"));
+    }
+
+    let user_id = payload
+        .get("user_id")
+        .and_then(|v| v.as_i64())
+        .ok_or_else(|| {
+            bad_request_error("Missing or invalid user_id: must be a positive integer")
+        })? as i32;
+    if user_id <= 0 {
+        return Err(bad_request_error("Invalid user_id: must be positive"));
+    }
+
+    let market_version = payload
+        .get("market_version")
+        .and_then(|v| v.as_i64())
+        .ok_or_else(|| {
+            bad_request_error("Missing or invalid market_version: must be an integer")
+        })?;
+    if market_version < 0 {
+        return Err(bad_request_error(
+            "Invalid market_version: must be non-negative",
+        ));
+    }
+
+    match lmsr_api::numeric_sell(&app_state.db, user_id, event_id, market_version).await {
+        Ok(lmsr_api::NumericSellOutcome::Executed(result)) => {
+            broadcast_trade(
+                &app_state,
+                "numeric_market_sold",
+                json!({
+                    "event_id": event_id,
+                    "user_id": user_id,
+                    "payout_ledger": result.payout_ledger,
+                    "market_version": result.market_version
+                }),
+            )
+            .await;
+            invariants::sample_after_trade(
+                &app_state.analytics_db,
+                user_id,
+                event_id,
+                "numeric_sell",
+            );
+            rank_history::mark_dirty(event_id);
+            Ok(Json(json!(result)))
+        }
+        Ok(lmsr_api::NumericSellOutcome::StaleVersion { market_version }) => Err((
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "market_version is stale; retry with the current version",
+                "code": ErrorCode::StaleVersion,
+                "market_version": market_version
+            })),
+        )),
+        Err(e) => Err(numeric_error_response(&e)),
+    }
+}
+
+// Buy shares of one bucket of a numeric market.
+async fn numeric_bucket_buy_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    if event_id <= 0 {
+        return Err(bad_request_error("Invalid event_id: must be positive"));
+    }
+    let (user_id, outcome_id, market_version) = parse_numeric_bucket_payload(&payload)?;
+
+    let stake_ledger = payload
+        .get("stake_ledger")
+        .and_then(|v| v.as_i64())
+        .ok_or_else(|| {
+            bad_request_error("Missing or invalid stake_ledger: must be an integer")
+        })?;
+    if stake_ledger <= 0 {
+        return Err(bad_request_error("Invalid stake_ledger: must be positive"));
+    }
+
+    match lmsr_api::numeric_bucket_buy(
+        &app_state.db,
+        user_id,
+        event_id,
+        outcome_id,
+        stake_ledger,
+        market_version,
+    )
+    .await
+    {
+        Ok(lmsr_api::NumericBucketOutcome::Executed(result)) => {
+            broadcast_trade(
+                &app_state,
+                "numeric_market_traded",
+                json!({
+                    "event_id": event_id,
+                    "user_id": user_id,
+                    "outcome_id": outcome_id,
+                    "cost_ledger": result.cost_ledger,
+                    "market_version": result.market_version
+                }),
+            )
+            .await;
+            invariants::sample_after_trade(
+                &app_state.analytics_db,
+                user_id,
+                event_id,
+                "numeric_bucket_buy",
+            );
+            Ok(Json(json!(result)))
+        }
+        Ok(lmsr_api::NumericBucketOutcome::StaleVersion { market_version }) => {
+            Err(stale_numeric_version(market_version))
+        }
+        Err(e) => Err(numeric_error_response(&e)),
+    }
+}
+
+// Sell shares of one bucket of a numeric market.
+async fn numeric_bucket_sell_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    if event_id <= 0 {
+        return Err(bad_request_error("Invalid event_id: must be positive"));
+    }
+    let (user_id, outcome_id, market_version) = parse_numeric_bucket_payload(&payload)?;
+
+    let shares = payload
+        .get("shares")
+        .and_then(|v| v.as_f64())
+        .ok_or_else(|| bad_request_error("Missing or invalid shares: must be a number"))?;
+    if !shares.is_finite() || shares <= 0.0 {
+        return Err(bad_request_error("Invalid shares: must be positive"));
+    }
+
+    match lmsr_api::numeric_bucket_sell(
+        &app_state.db,
+        user_id,
+        event_id,
+        outcome_id,
+        shares,
+        market_version,
+    )
+    .await
+    {
+        Ok(lmsr_api::NumericBucketOutcome::Executed(result)) => {
+            broadcast_trade(
+                &app_state,
+                "numeric_market_sold",
+                json!({
+                    "event_id": event_id,
+                    "user_id": user_id,
+                    "outcome_id": outcome_id,
+                    "payout_ledger": result.payout_ledger,
+                    "market_version": result.market_version
+                }),
+            )
+            .await;
+            invariants::sample_after_trade(
+                &app_state.analytics_db,
+                user_id,
+                event_id,
+                "numeric_bucket_sell",
+            );
+            rank_history::mark_dirty(event_id);
+            Ok(Json(json!(result)))
+        }
+        Ok(lmsr_api::NumericBucketOutcome::StaleVersion { market_version }) => {
+            Err(stale_numeric_version(market_version))
+        }
+        Err(e) => Err(numeric_error_response(&e)),
+    }
+}
+
+/// user_id, outcome_id and market_version, which both bucket endpoints take.
+fn parse_numeric_bucket_payload(
+    payload: &Value,
+) -> Result<(i32, i64, i64), (StatusCode, Json<Value>)> {
+    let user_id = payload
+        .get("user_id")
+        .and_then(|v| v.as_i64())
+        .filter(|id| *id > 0 && *id <= i32::MAX as i64)
+        .ok_or_else(|| {
+            bad_request_error("Missing or invalid user_id: must be a positive integer")
+        })? as i32;
     let outcome_id = payload
         .get("outcome_id")
         .and_then(|v| v.as_i64())
-        .ok_or_else(|| bad_request_error("Missing or invalid outcome_id"))?;
-    if outcome_id <= 0 {
-        return Err(bad_request_error("Invalid outcome_id: must be positive"));
+        .filter(|id| *id > 0)
+        .ok_or_else(|| {
+            bad_request_error("Missing or invalid outcome_id: must be a positive integer")
+        })?;
+    let market_version = payload
+        .get("market_version")
+        .and_then(|v| v.as_i64())
+        .ok_or_else(|| {
+            bad_request_error("Missing or invalid market_version: must be an integer")
+        })?;
+    if market_version < 0 {
+        return Err(bad_request_error(
+            "Invalid market_version: must be non-negative",
+        ));
+    }
+    Ok((user_id, outcome_id, market_version))
+}
+
+fn stale_numeric_version(market_version: i64) -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::CONFLICT,
+        Json(json!({
+            "error": "market_version is stale; retry with the current version",
+            "code": ErrorCode::StaleVersion,
+            "market_version": market_version
+        })),
+    )
+}
+
+// Implied distribution of a numeric market.
+async fn numeric_distribution_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+) -> ApiResult<Value> {
+    if event_id <= 0 {
+        return Err(bad_request_error("Invalid event_id: must be positive"));
+    }
+    match lmsr_api::get_numeric_distribution(&app_state.db, event_id).await {
+        Ok(distribution) => Ok(Json(json!(distribution))),
+        Err(e) => Err(numeric_error_response(&e)),
+    }
+}
+
+// Get Kelly criterion betting suggestion
+async fn kelly_suggestion_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    Query(params): Query<HashMap<String, String>>,
+) -> ApiResult<Value> {
+    // Validate event_id
+    if event_id <= 0 {
+        return Err(bad_request_error("Invalid event_id: must be positive"));
+    }
+
+    // Validate belief probability - require explicit value, no defaults
+    let belief = params
+        .get("belief")
+        .and_then(|s| s.parse::<f64>().ok())
+        .ok_or_else(|| bad_request_error("Missing or invalid belief: must be a finite number"))?;
+    if !belief.is_finite() {
+        return Err(bad_request_error("Invalid belief: must be finite"));
+    }
+    if belief <= 0.0 || belief >= 1.0 {
+        return Err(bad_request_error(
+            "Invalid belief: must be between 0 and 1 (exclusive)",
+        ));
+    }
+
+    // Validate user_id - require explicit value, no defaults
+    let user_id = params
+        .get("user_id")
+        .and_then(|s| s.parse::<i32>().ok())
+        .ok_or_else(|| {
+            bad_request_error("Missing or invalid user_id: must be a positive integer")
+        })?;
+    if user_id <= 0 {
+        return Err(bad_request_error("Invalid user_id: must be positive"));
+    }
+
+    // Get current market probability
+    let market_prob = match app_state.market_cache.get(&app_state.db, event_id).await {
+        Ok(state) => state["market_prob"].as_f64().unwrap_or(0.5),
+        Err(_) => return Err(not_found_error("Event")),
+    };
+
+    // Get user balance from ledger
+    let balance_ledger_result: Result<i64, sqlx::Error> =
+        sqlx::query_scalar("SELECT rp_balance_ledger FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&app_state.db)
+            .await;
+
+    let balance = match balance_ledger_result {
+        Ok(bal) => lmsr_core::from_ledger_units(bal as i128),
+        Err(_) => return Err(not_found_error("User")),
+    };
+
+    let suggestion = lmsr_api::kelly_suggestion(&app_state.config, belief, market_prob, balance);
+    Ok(Json(json!(suggestion)))
+}
+
+// Sell shares back to market
+async fn sell_shares_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    // Validate event_id
+    if event_id <= 0 {
+        return Err(bad_request_error("Invalid event_id: must be positive"));
+    }
+
+    // Validate user_id - require explicit value, no defaults
+    let user_id = payload
+        .get("user_id")
+        .and_then(|v| v.as_i64())
+        .ok_or_else(|| {
+            bad_request_error("Missing or invalid user_id: must be a positive integer")
+        })? as i32;
+    if user_id <= 0 {
+        return Err(bad_request_error("Invalid user_id: must be positive"));
     }
 
-    let stake = payload
-        .get("stake")
-        .and_then(|v| v.as_f64())
-        .ok_or_else(|| bad_request_error("Missing or invalid stake: must be a finite number"))?;
-    if !stake.is_finite() {
-        return Err(bad_request_error("Invalid stake: must be finite"));
-    }
-    if !(0.01..=1_000_000.0).contains(&stake) {
+    // Validate share_type - require explicit value, no defaults
+    let share_type = payload
+        .get("share_type")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| bad_request_error("Missing or invalid share_type: must be 'yes' or 'no'"))?;
+    if share_type != "yes" && share_type != "no" {
         return Err(bad_request_error(
-            "Invalid stake: must be within [0.01, 1,000,000] RP",
+            "Invalid share_type: must be 'yes' or 'no'",
         ));
     }
 
-    let update = lmsr_api::OutcomeMarketUpdate {
-        event_id,
-        outcome_id,
-        stake,
-        referral_post_id: payload
-            .get("referral_post_id")
-            .and_then(|value| value.as_i64())
-            .filter(|value| *value > 0)
-            .map(|value| value as i32),
-        referral_click_id: payload
-            .get("referral_click_id")
-            .and_then(|value| value.as_i64())
-            .filter(|value| *value > 0)
-            .map(|value| value as i32),
+    // sell_all closes the whole side; amount is then optional and ignored
+    let sell_all = match payload.get("sell_all") {
+        None | Some(Value::Null) => false,
+        Some(v) => v
+            .as_bool()
+            .ok_or_else(|| bad_request_error("Invalid sell_all: must be a boolean"))?,
     };
 
-    match lmsr_api::update_market_outcome(&app_state.db, &app_state.config, user_id, update).await {
+    // payout sells by RP value: as many shares as pay that much
+    let payout = match payload.get("payout") {
+        None | Some(Value::Null) => None,
+        Some(v) => Some(
+            v.as_f64()
+                .ok_or_else(|| bad_request_error("Invalid payout: must be a finite number"))?,
+        ),
+    };
+    if let Some(payout) = payout {
+        if sell_all || payload.get("amount").is_some_and(|v| !v.is_null()) {
+            return Err(bad_request_error(
+                "Invalid payout: give payout, amount or sell_all, not several",
+            ));
+        }
+        if !payout.is_finite() || payout <= 0.0 {
+            return Err(bad_request_error(
+                "Invalid payout: must be positive and finite",
+            ));
+        }
+        if payout < 0.000001 {
+            return Err(bad_request_error(
+                "Invalid payout: below minimum allowed (0.000001 RP)",
+            ));
+        }
+    }
+
+    // Validate amount - require explicit value, no defaults
+    let amount = if sell_all || payout.is_some() {
+        0.0
+    } else {
+        payload
+            .get("amount")
+            .and_then(|v| v.as_f64())
+            .ok_or_else(|| {
+                bad_request_error("Missing or invalid amount: must be a finite number")
+            })?
+    };
+    if !sell_all && payout.is_none() {
+        if !amount.is_finite() {
+            return Err(bad_request_error("Invalid amount: must be finite"));
+        }
+        if amount <= 0.0 {
+            return Err(bad_request_error("Invalid amount: must be positive"));
+        }
+        if amount > 10_000_000.0 {
+            // 10M shares max per sale
+            return Err(bad_request_error(
+                "Invalid amount: exceeds maximum allowed (10,000,000 shares)",
+            ));
+        }
+        if amount < 0.000001 {
+            // Minimum 0.000001 shares (1 micro-share)
+            return Err(bad_request_error(
+                "Invalid amount: below minimum allowed (0.000001 shares)",
+            ));
+        }
+    }
+
+    let sold = match payout {
+        Some(payout) => {
+            lmsr_api::sell_shares_for_payout(
+                &app_state.db,
+                &app_state.config,
+                user_id,
+                event_id,
+                share_type,
+                payout,
+            )
+            .await
+        }
+        None => {
+            lmsr_api::sell_shares(
+                &app_state.db,
+                &app_state.config,
+                user_id,
+                event_id,
+                share_type,
+                amount,
+                sell_all,
+            )
+            .await
+        }
+    };
+    match sold {
         Ok(result) => {
-            invalidate_and_broadcast(
+            broadcast_trade(
                 &app_state,
-                "market_updated",
+                "shares_sold",
                 json!({
                     "event_id": event_id,
                     "user_id": user_id,
-                    "new_prob": result.market_prob,
-                    "outcome_id": result.outcome_id
+                    "share_type": share_type,
+                    "amount": result.shares_sold,
+                    "payout": result.payout,
+                    "new_prob": result.new_prob,
+                    "cumulative_stake": result.current_cost_c
                 }),
-            );
-            Ok(Json(json!(result)))
-        }
-        Err(e) => {
-            let msg = e.to_string();
-            let msg_lower = msg.to_lowercase();
-            if msg_lower.contains("market resolved") {
-                return Err(bad_request_error("Market resolved"));
-            }
-            if msg_lower.contains("market closed") {
-                return Err(bad_request_error("Market closed"));
-            }
-            if msg_lower.contains("no configured outcomes")
-                || msg_lower.contains("selected outcome")
-                || msg_lower.contains("binary markets")
-            {
-                return Err(bad_request_error(&msg));
-            }
-            Err(internal_error(&format!(
-                "Market outcome update error: {}",
-                msg
-            )))
+            )
+            .await;
+            invariants::sample_after_trade(&app_state.analytics_db, user_id, event_id, "sell");
+            spawn_limit_order_matcher(&app_state, event_id);
+            rank_history::mark_dirty(event_id);
+            Ok(Json(json!({
+                "success": true,
+                "payout": result.payout,
+                "new_prob": result.new_prob,
+                "cumulative_stake": result.current_cost_c,
+                "shares_sold": result.shares_sold,
+                "message": format!("Sold {} {} shares for {} RP", result.shares_sold, share_type, result.payout)
+            })))
         }
+        Err(e) => Err(engine_error("Share sale error", &e)),
     }
 }
 
-// Sell shares of an explicit outcome (multiple choice / numeric buckets)
-async fn sell_outcome_shares_endpoint(
+// Close a binary position: sell both sides in one transaction
+async fn close_position_endpoint(
     State(app_state): State<AppState>,
     Path(event_id): Path<i32>,
     ExtractJson(payload): ExtractJson<serde_json::Value>,
@@ -1067,7 +4796,6 @@ async fn sell_outcome_shares_endpoint(
     if event_id <= 0 {
         return Err(bad_request_error("Invalid event_id: must be positive"));
     }
-
     let user_id = payload
         .get("user_id")
         .and_then(|v| v.as_i64())
@@ -1078,543 +4806,683 @@ async fn sell_outcome_shares_endpoint(
         return Err(bad_request_error("Invalid user_id: must be positive"));
     }
 
-    let outcome_id = payload
-        .get("outcome_id")
-        .and_then(|v| v.as_i64())
-        .ok_or_else(|| bad_request_error("Missing or invalid outcome_id"))?;
-    if outcome_id <= 0 {
-        return Err(bad_request_error("Invalid outcome_id: must be positive"));
-    }
-
-    let amount = payload
-        .get("amount")
-        .and_then(|v| v.as_f64())
-        .ok_or_else(|| bad_request_error("Missing or invalid amount: must be a finite number"))?;
-    if !amount.is_finite() || amount <= 0.0 {
-        return Err(bad_request_error("Invalid amount: must be positive and finite"));
-    }
-    if amount > 10_000_000.0 {
-        return Err(bad_request_error(
-            "Invalid amount: exceeds maximum allowed (10,000,000 shares)",
-        ));
-    }
-    if amount < 0.000001 {
-        return Err(bad_request_error(
-            "Invalid amount: below minimum allowed (0.000001 shares)",
-        ));
-    }
-
-    match lmsr_api::sell_outcome_shares(
-        &app_state.db,
-        &app_state.config,
-        user_id,
-        event_id,
-        outcome_id,
-        amount,
-    )
-    .await
-    {
+    match lmsr_api::close_position(&app_state.db, &app_state.config, user_id, event_id).await {
         Ok(result) => {
-            invalidate_and_broadcast(
+            broadcast_trade(
                 &app_state,
-                "shares_sold",
+                "position_closed",
                 json!({
                     "event_id": event_id,
                     "user_id": user_id,
-                    "outcome_id": outcome_id,
-                    "amount": amount,
+                    "yes_shares_sold": result.yes_shares_sold,
+                    "no_shares_sold": result.no_shares_sold,
                     "payout": result.payout,
-                    "new_prob": result.market_prob,
+                    "new_prob": result.new_prob,
                     "cumulative_stake": result.current_cost_c
                 }),
-            );
+            )
+            .await;
+            invariants::sample_after_trade(&app_state.analytics_db, user_id, event_id, "close");
+            spawn_limit_order_matcher(&app_state, event_id);
+            rank_history::mark_dirty(event_id);
             Ok(Json(json!(result)))
         }
-        Err(e) => {
-            let msg = e.to_string();
-            let msg_lower = msg.to_lowercase();
-            if msg_lower.contains("market resolved") {
-                return Err(bad_request_error("Market resolved"));
-            }
-            if msg_lower.contains("market closed") {
-                return Err(bad_request_error("Market closed"));
-            }
-            if msg_lower.contains("insufficient shares")
-                || msg_lower.contains("hold period")
-                || msg_lower.contains("no configured outcomes")
-                || msg_lower.contains("selected outcome")
-                || msg_lower.contains("binary markets")
-            {
-                return Err(bad_request_error(&msg));
-            }
-            Err(internal_error(&format!("Outcome sell error: {}", msg)))
-        }
+        Err(e) => Err(engine_error("Position close error", &e)),
     }
 }
 
-// ============================================================================
-// NUMERIC MARKET ENDPOINTS (50-bin dense-vector LMSR trading)
-// ============================================================================
+#[derive(Debug, Deserialize)]
+struct SellQuoteQuery {
+    user_id: Option<i32>,
+    side: Option<String>,
+    amount: Option<f64>,
+}
 
-/// Parse the `target` query/body param into `Vec<f64>`. Shape validation
-/// (exact outcome count, finite, >= 0, sum > 0) happens in lmsr_api once the
-/// market's configured outcome count (inbound bins + open tails) is known;
-/// this only parses the wire format.
-fn parse_target_query_param(
-    params: &HashMap<String, String>,
-) -> Result<Vec<f64>, (axum::http::StatusCode, Json<Value>)> {
-    let raw = params
-        .get("target")
-        .ok_or_else(|| bad_request_error("Missing target: comma-separated floats required"))?;
-    let mut target = Vec::new();
-    for part in raw.split(',') {
-        let trimmed = part.trim();
-        if trimmed.is_empty() {
+// Preview a binary sell; without amount, the whole side
+async fn sell_quote_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    Query(params): Query<SellQuoteQuery>,
+) -> ApiResult<Value> {
+    if event_id <= 0 {
+        return Err(bad_request_error("Invalid event_id: must be positive"));
+    }
+    let user_id = params.user_id.ok_or_else(|| {
+        bad_request_error("Missing or invalid user_id: must be a positive integer")
+    })?;
+    if user_id <= 0 {
+        return Err(bad_request_error("Invalid user_id: must be positive"));
+    }
+    let side = params.side.as_deref().unwrap_or_default();
+    if side != "yes" && side != "no" {
+        return Err(bad_request_error("Invalid side: must be 'yes' or 'no'"));
+    }
+    if let Some(amount) = params.amount {
+        if !amount.is_finite() || amount <= 0.0 {
             return Err(bad_request_error(
-                "Invalid target: empty entry in comma-separated list",
+                "Invalid amount: must be positive and finite",
             ));
         }
-        let value: f64 = trimmed
-            .parse()
-            .map_err(|_| bad_request_error("Invalid target: all entries must be numbers"))?;
-        target.push(value);
     }
-    Ok(target)
-}
 
-/// Shared error-message mapping for the numeric market endpoints (400 vs 500).
-/// The two "expected rejection" cases (stale market_version, cost exceeding
-/// max_cost_ledger) never reach here — they come back as typed `Ok(..)`
-/// variants from lmsr_api and are mapped to 409 directly by each handler.
-fn numeric_error_response(e: &anyhow::Error) -> (axum::http::StatusCode, Json<Value>) {
-    let msg = e.to_string();
-    let msg_lower = msg.to_lowercase();
-    if msg_lower.contains("market resolved") {
-        return bad_request_error("Market resolved");
-    }
-    if msg_lower.contains("market closed") {
-        return bad_request_error("Market closed");
-    }
-    // Mandate 6: the 40*b log-odds span clamp maps to a human-readable 400.
-    if msg_lower.contains("log-odds span") {
-        return bad_request_error(
-            "Market too extreme or target too concentrated for the current liquidity; reduce the requested move",
-        );
-    }
-    if msg_lower.contains("insufficient rp balance") {
-        return bad_request_error("Insufficient RP balance");
-    }
-    if msg_lower.contains("no numeric market configured")
-        || msg_lower.contains("bin_count")
-        || msg_lower.contains("outcome count")
-        || msg_lower.contains("target must")
-        || msg_lower.contains("target entries")
-        || msg_lower.contains("budget_ledger must")
-        || msg_lower.contains("max_cost_ledger must")
-        || msg_lower.contains("rounds to zero")
-        || msg_lower.contains("no numeric position")
+    match lmsr_api::get_sell_quote(
+        &app_state.db,
+        &app_state.config,
+        user_id,
+        event_id,
+        side,
+        params.amount,
+    )
+    .await
     {
-        return bad_request_error(&msg);
+        Ok(quote) => Ok(Json(json!(quote))),
+        Err(e) => Err(engine_error("Sell quote error", &e)),
     }
-    internal_error(&format!("Numeric market error: {}", msg))
 }
 
-#[cfg(test)]
-mod numeric_error_response_tests {
-    use super::*;
-    use anyhow::anyhow;
-    use axum::http::StatusCode;
+#[derive(Debug, Deserialize)]
+struct TradeQuoteQuery {
+    target_prob: Option<f64>,
+    stake: Option<f64>,
+}
 
-    // Characterization tests: pin that validation-shaped errors map to 400 and
-    // genuinely unexpected errors map to 500. The "outcome count" wording was
-    // introduced when tail outcomes made bin_count != outcome_count — without
-    // this branch a length mismatch would silently 500 instead of 400.
-    #[test]
-    fn outcome_count_mismatch_maps_to_400() {
-        let (status, _) =
-            numeric_error_response(&anyhow!("target must have exactly 52 entries, got 50"));
-        assert_eq!(status, StatusCode::BAD_REQUEST);
-        let (status, _) = numeric_error_response(&anyhow!(
-            "Numeric market outcome count (52) does not match configured outcome count (50)"
-        ));
-        assert_eq!(status, StatusCode::BAD_REQUEST);
+// Preview a binary trade against the cached market state; writes nothing
+async fn trade_quote_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    Query(params): Query<TradeQuoteQuery>,
+) -> ApiResult<Value> {
+    if event_id <= 0 {
+        return Err(bad_request_error("Invalid event_id: must be positive"));
     }
-
-    #[test]
-    fn legacy_bin_count_wording_still_maps_to_400() {
-        let (status, _) = numeric_error_response(&anyhow!("does not match configured bin_count (50)"));
-        assert_eq!(status, StatusCode::BAD_REQUEST);
+    let target_prob = params
+        .target_prob
+        .filter(|p| *p > 0.0 && *p < 1.0)
+        .ok_or_else(|| bad_request_error("Invalid target_prob: must be between 0 and 1"))?;
+    let stake = params
+        .stake
+        .filter(|s| s.is_finite() && *s > 0.0)
+        .ok_or_else(|| bad_request_error("Invalid stake: must be positive and finite"))?;
+
+    let state = match app_state.market_cache.get(&app_state.db, event_id).await {
+        Ok(state) => state,
+        Err(e) => return Err(engine_error("Market state error", &e)),
+    };
+    let market = lmsr_api::binary_market_from_state(&state)
+        .map_err(|e| bad_request_error(&e.to_string()))?;
+    match lmsr_api::quote(&market, target_prob, stake) {
+        Ok(quote) => Ok(Json(json!(quote))),
+        Err(e) => Err(bad_request_error(&e.to_string())),
     }
+}
 
-    #[test]
-    fn unexpected_error_maps_to_500() {
-        let (status, _) = numeric_error_response(&anyhow!("connection reset by peer"));
-        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
-    }
+#[derive(Debug, Deserialize)]
+struct MarketDepthQuery {
+    step: Option<f64>,
 }
 
-// Read-only quote for a target distribution + budget on a numeric market.
-async fn numeric_quote_endpoint(
+// Cost curve of a binary market (?step= spaces the target grid, default
+// 0.05), from the cached market state; writes nothing
+async fn market_depth_endpoint(
     State(app_state): State<AppState>,
     Path(event_id): Path<i32>,
-    Query(params): Query<HashMap<String, String>>,
+    Query(params): Query<MarketDepthQuery>,
 ) -> ApiResult<Value> {
     if event_id <= 0 {
         return Err(bad_request_error("Invalid event_id: must be positive"));
     }
-
-    let budget_ledger = params
-        .get("budget_ledger")
-        .and_then(|s| s.parse::<i64>().ok())
-        .ok_or_else(|| {
-            bad_request_error("Missing or invalid budget_ledger: must be an integer")
-        })?;
-    if budget_ledger <= 0 {
-        return Err(bad_request_error("Invalid budget_ledger: must be positive"));
+    let state = match app_state.market_cache.get(&app_state.db, event_id).await {
+        Ok(state) => state,
+        Err(e) => return Err(engine_error("Market state error", &e)),
+    };
+    let market = lmsr_api::binary_market_from_state(&state)
+        .map_err(|e| bad_request_error(&e.to_string()))?;
+    let step = params.step.unwrap_or(lmsr_api::DEFAULT_DEPTH_STEP);
+    match lmsr_api::depth(&market, step) {
+        Ok(depth) => Ok(Json(json!(depth))),
+        Err(e) => Err(bad_request_error(&e.to_string())),
     }
+}
 
-    let target = parse_target_query_param(&params)?;
+// Unstaked practice forecast on an already-resolved event, scored on submit
+async fn paper_prediction_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    if event_id <= 0 {
+        return Err(bad_request_error("Invalid event_id: must be positive"));
+    }
+    let user_id = payload
+        .get("user_id")
+        .and_then(|v| v.as_i64())
+        .and_then(|v| i32::try_from(v).ok())
+        .filter(|v| *v > 0)
+        .ok_or_else(|| bad_request_error("Missing or invalid user_id"))?;
+    let probability = payload
+        .get("probability")
+        .and_then(|v| v.as_f64())
+        .ok_or_else(|| bad_request_error("Missing or invalid probability"))?;
 
-    match lmsr_api::get_numeric_quote(&app_state.db, event_id, budget_ledger, target).await {
-        Ok(quote) => Ok(Json(json!(quote))),
-        Err(e) => Err(numeric_error_response(&e)),
+    match paper_predictions::submit_paper_prediction(&app_state.db, user_id, event_id, probability)
+        .await
+    {
+        Ok(result) => Ok(Json(json!({ "success": true, "paper_prediction": result }))),
+        Err(e) => Err(engine_error("Paper prediction error", &e)),
     }
 }
 
-// Trade toward a target distribution on a numeric market.
-async fn numeric_trade_endpoint(
+async fn user_paper_predictions_endpoint(
     State(app_state): State<AppState>,
-    Path(event_id): Path<i32>,
-    ExtractJson(payload): ExtractJson<serde_json::Value>,
+    Path(user_id): Path<i32>,
+    Query(params): Query<ImportStatusQuery>,
 ) -> ApiResult<Value> {
+    let limit = params.limit.unwrap_or(50);
+    match paper_predictions::get_user_paper_predictions(&app_state.analytics_db, user_id, limit)
+        .await
+    {
+        Ok(report) => Ok(Json(report)),
+        Err(e) => Err(engine_error("Paper predictions error", &e)),
+    }
+}
+
+// user_id and probability from a forecast request body
+fn parse_forecast_payload(
+    event_id: i32,
+    payload: &Value,
+) -> Result<(i32, f64), (axum::http::StatusCode, Json<Value>)> {
     if event_id <= 0 {
         return Err(bad_request_error("Invalid event_id: must be positive"));
     }
-
     let user_id = payload
         .get("user_id")
         .and_then(|v| v.as_i64())
-        .ok_or_else(|| {
-            bad_request_error("Missing or invalid user_id: must be a positive integer")
-        })? as i32;
-    if user_id <= 0 {
-        return Err(bad_request_error("Invalid user_id: must be positive"));
+        .and_then(|v| i32::try_from(v).ok())
+        .filter(|v| *v > 0)
+        .ok_or_else(|| bad_request_error("Missing or invalid user_id"))?;
+    let probability = payload
+        .get("probability")
+        .and_then(|v| v.as_f64())
+        .ok_or_else(|| bad_request_error("Missing or invalid probability"))?;
+    Ok((user_id, probability))
+}
+
+// Journal a user's first forecast on an open event (no RP staked)
+async fn submit_forecast_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    let (user_id, probability) = parse_forecast_payload(event_id, &payload)?;
+    match forecasts::submit_forecast(&app_state.db, user_id, event_id, probability).await {
+        Ok(forecast) => Ok(Json(json!({ "success": true, "forecast": forecast }))),
+        Err(e) => Err(engine_error("Forecast error", &e)),
     }
+}
 
-    let target_values = payload
-        .get("target")
-        .and_then(|v| v.as_array())
-        .ok_or_else(|| bad_request_error("Missing or invalid target: must be an array of numbers"))?;
-    let mut target = Vec::with_capacity(target_values.len());
-    for entry in target_values {
-        let value = entry
-            .as_f64()
-            .ok_or_else(|| bad_request_error("Invalid target: all entries must be numbers"))?;
-        target.push(value);
+// Revise a journaled forecast; the previous one stays in the revision history
+async fn update_forecast_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    let (user_id, probability) = parse_forecast_payload(event_id, &payload)?;
+    match forecasts::update_forecast(&app_state.db, user_id, event_id, probability).await {
+        Ok(forecast) => Ok(Json(json!({ "success": true, "forecast": forecast }))),
+        Err(e) => Err(engine_error("Forecast error", &e)),
     }
+}
 
-    let budget_ledger = payload
-        .get("budget_ledger")
-        .and_then(|v| v.as_i64())
-        .ok_or_else(|| {
-            bad_request_error("Missing or invalid budget_ledger: must be an integer")
-        })?;
-    if budget_ledger <= 0 {
-        return Err(bad_request_error("Invalid budget_ledger: must be positive"));
+// Forecast revisions on one event, scored per interval each was held
+async fn forecast_history_endpoint(
+    State(app_state): State<AppState>,
+    Path((user_id, event_id)): Path<(i32, i32)>,
+) -> ApiResult<Value> {
+    match forecasts::get_forecast_history(&app_state.analytics_db, user_id, event_id).await {
+        Ok(history) => Ok(Json(history)),
+        Err(e) => Err(engine_error("Forecast error", &e)),
     }
+}
 
-    let max_cost_ledger = payload
-        .get("max_cost_ledger")
-        .and_then(|v| v.as_i64())
-        .ok_or_else(|| {
-            bad_request_error("Missing or invalid max_cost_ledger: must be an integer")
-        })?;
-    if max_cost_ledger <= 0 {
-        return Err(bad_request_error(
-            "Invalid max_cost_ledger: must be positive",
-        ));
+// Reputation, today's scoring quota, open positions and realized P&L per market and lifetime
+async fn user_portfolio_endpoint(
+    State(app_state): State<AppState>,
+    Path(user_id): Path<i32>,
+) -> ApiResult<Value> {
+    if user_id <= 0 {
+        return Err(bad_request_error("Invalid user_id: must be positive"));
     }
+    let pool = &app_state.analytics_db;
+    let portfolio = tokio::try_join!(
+        realized_pnl::get_portfolio(pool, user_id),
+        score_quota::daily_quota(pool, &app_state.config.score_quota, user_id),
+    );
+    match portfolio {
+        Ok((mut portfolio, quota)) => {
+            portfolio["scoring_quota"] = json!(quota);
+            Ok(Json(portfolio))
+        }
+        Err(e) => Err(engine_error("Portfolio error", &e)),
+    }
+}
 
-    let market_version = payload
-        .get("market_version")
-        .and_then(|v| v.as_i64())
-        .ok_or_else(|| {
-            bad_request_error("Missing or invalid market_version: must be an integer")
-        })?;
-    if market_version < 0 {
-        return Err(bad_request_error(
-            "Invalid market_version: must be non-negative",
-        ));
+// Realized P&L plus open positions marked at current prices
+async fn user_pnl_endpoint(
+    State(app_state): State<AppState>,
+    Path(user_id): Path<i32>,
+) -> ApiResult<Value> {
+    if user_id <= 0 {
+        return Err(bad_request_error("Invalid user_id: must be positive"));
     }
+    match realized_pnl::get_pnl(&app_state.analytics_db, user_id).await {
+        Ok(pnl) => Ok(Json(pnl)),
+        Err(e) => Err(engine_error("P&L error", &e)),
+    }
+}
 
-    match lmsr_api::numeric_trade(
-        &app_state.db,
+// Everything the frontend dashboard shows, cached until the next score update
+async fn user_dashboard_endpoint(
+    State(app_state): State<AppState>,
+    Path(user_id): Path<i32>,
+) -> ApiResult<Value> {
+    if user_id <= 0 {
+        return Err(bad_request_error("Invalid user_id: must be positive"));
+    }
+    let key = dashboard::cache_key(user_id);
+    if let Some(cached) = app_state.cache.get(&key).await {
+        if let Ok(dashboard) = serde_json::from_str(&cached) {
+            return Ok(Json(dashboard));
+        }
+    }
+    match dashboard::get_dashboard(
+        &app_state.analytics_db,
+        &app_state.config.score_quota,
         user_id,
-        event_id,
-        target,
-        budget_ledger,
-        max_cost_ledger,
-        market_version,
     )
     .await
     {
-        Ok(lmsr_api::NumericTradeOutcome::Executed(result)) => {
-            invalidate_and_broadcast(
-                &app_state,
-                "numeric_market_traded",
-                json!({
-                    "event_id": event_id,
-                    "user_id": user_id,
-                    "cost_ledger": result.cost_ledger,
-                    "market_version": result.market_version
-                }),
-            );
-            Ok(Json(json!(result)))
+        Ok(dashboard) => {
+            app_state.cache.insert(key, dashboard.to_string()).await;
+            Ok(Json(dashboard))
         }
-        Ok(lmsr_api::NumericTradeOutcome::StaleVersion(quote)) => Err((
-            StatusCode::CONFLICT,
-            Json(json!({
-                "error": "market_version is stale; retry with the fresh quote",
-                "quote": quote
-            })),
-        )),
-        Ok(lmsr_api::NumericTradeOutcome::CostExceeded(quote)) => Err((
-            StatusCode::CONFLICT,
-            Json(json!({
-                "error": "recomputed cost exceeds max_cost_ledger; retry with the fresh quote",
-                "quote": quote
-            })),
-        )),
-        Err(e) => Err(numeric_error_response(&e)),
+        Err(e) => Err(engine_error("Dashboard error", &e)),
     }
 }
 
-// Sell a user's entire numeric-market position.
-async fn numeric_sell_endpoint(
+#[derive(Debug, Deserialize)]
+struct RankHistoryQuery {
+    limit: Option<i64>,
+}
+
+// The user's global rank and their rank transitions, newest first
+async fn user_rank_history_endpoint(
     State(app_state): State<AppState>,
-    Path(event_id): Path<i32>,
-    ExtractJson(payload): ExtractJson<serde_json::Value>,
+    Path(user_id): Path<i32>,
+    Query(params): Query<RankHistoryQuery>,
 ) -> ApiResult<Value> {
-    if event_id <= 0 {
-        return Err(bad_request_error("Invalid event_id: must be positive"));
+    if user_id <= 0 {
+        return Err(bad_request_error("Invalid user_id: must be positive"));
+    }
+    let limit = params.limit.unwrap_or(100).clamp(1, 500);
+    match rank_history::history(&app_state.analytics_db, user_id, limit).await {
+        Ok((rank, transitions)) => Ok(Json(json!({
+            "user_id": user_id,
+            "rank": rank,
+            "transitions": transitions,
+        }))),
+        Err(e) => Err(engine_error("Rank history error", &e)),
     }
+}
 
-    let user_id = payload
-        .get("user_id")
-        .and_then(|v| v.as_i64())
-        .ok_or_else(|| {
-            bad_request_error("Missing or invalid user_id: must be a positive integer")
-        })? as i32;
+// Open positions grouped by category and by correlated event cluster
+async fn user_exposure_endpoint(
+    State(app_state): State<AppState>,
+    Path(user_id): Path<i32>,
+) -> ApiResult<Value> {
     if user_id <= 0 {
         return Err(bad_request_error("Invalid user_id: must be positive"));
     }
+    match exposure::get_exposure(&app_state.analytics_db, user_id).await {
+        Ok(report) => Ok(Json(report)),
+        Err(e) => Err(engine_error("Exposure error", &e)),
+    }
+}
 
-    let market_version = payload
-        .get("market_version")
-        .and_then(|v| v.as_i64())
-        .ok_or_else(|| {
-            bad_request_error("Missing or invalid market_version: must be an integer")
-        })?;
-    if market_version < 0 {
-        return Err(bad_request_error(
-            "Invalid market_version: must be non-negative",
-        ));
+#[derive(Debug, Deserialize)]
+struct RiskQuery {
+    days: Option<i64>,
+}
+
+// Inputs for the responsible-forecasting panel
+async fn user_risk_endpoint(
+    State(app_state): State<AppState>,
+    Path(user_id): Path<i32>,
+    Query(params): Query<RiskQuery>,
+) -> ApiResult<Value> {
+    if user_id <= 0 {
+        return Err(bad_request_error("Invalid user_id: must be positive"));
+    }
+    let days = params.days.unwrap_or(90);
+    match risk::get_risk(&app_state.analytics_db, &app_state.config, user_id, days).await {
+        Ok(report) => Ok(Json(report)),
+        Err(e) => Err(engine_error("Risk metrics error", &e)),
     }
+}
 
-    match lmsr_api::numeric_sell(&app_state.db, user_id, event_id, market_version).await {
-        Ok(lmsr_api::NumericSellOutcome::Executed(result)) => {
-            invalidate_and_broadcast(
-                &app_state,
-                "numeric_market_sold",
-                json!({
-                    "event_id": event_id,
-                    "user_id": user_id,
-                    "payout_ledger": result.payout_ledger,
-                    "market_version": result.market_version
-                }),
-            );
-            Ok(Json(json!(result)))
-        }
-        Ok(lmsr_api::NumericSellOutcome::StaleVersion { market_version }) => Err((
-            StatusCode::CONFLICT,
-            Json(json!({
-                "error": "market_version is stale; retry with the current version",
-                "market_version": market_version
-            })),
-        )),
-        Err(e) => Err(numeric_error_response(&e)),
+#[derive(Debug, Deserialize)]
+struct UserPredictionsQuery {
+    status: Option<String>,
+    category: Option<String>,
+    #[serde(rename = "type")]
+    prediction_type: Option<String>,
+    resolved_from: Option<String>,
+    resolved_to: Option<String>,
+    sort: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+// A user's predictions with details and scores, filtered and paged
+async fn user_predictions_endpoint(
+    State(app_state): State<AppState>,
+    Path(user_id): Path<i32>,
+    Query(params): Query<UserPredictionsQuery>,
+) -> ApiResult<Value> {
+    if user_id <= 0 {
+        return Err(bad_request_error("Invalid user_id: must be positive"));
+    }
+    let parse_ts = |raw: Option<&str>, name: &str| {
+        raw.map(|ts| {
+            chrono::DateTime::parse_from_rfc3339(ts)
+                .map(|at| at.with_timezone(&chrono::Utc))
+                .map_err(|_| bad_request_error(&format!("{} must be an RFC 3339 timestamp", name)))
+        })
+        .transpose()
+    };
+    let filters = user_predictions::PredictionFilters {
+        status: user_predictions::Status::parse(params.status.as_deref())
+            .map_err(|e| bad_request_error(&e.to_string()))?,
+        category: params.category,
+        prediction_type: params.prediction_type,
+        resolved_from: parse_ts(params.resolved_from.as_deref(), "resolved_from")?,
+        resolved_to: parse_ts(params.resolved_to.as_deref(), "resolved_to")?,
+        sort: user_predictions::Sort::parse(params.sort.as_deref())
+            .map_err(|e| bad_request_error(&e.to_string()))?,
+    };
+    let limit = params.limit.unwrap_or(20);
+    let offset = params.offset.unwrap_or(0);
+    match user_predictions::get_user_predictions(
+        &app_state.analytics_db,
+        &app_state.config.score_quota,
+        user_id,
+        &filters,
+        limit,
+        offset,
+    )
+    .await
+    {
+        Ok(page) => Ok(Json(page)),
+        Err(e) => Err(engine_error("User predictions error", &e)),
     }
 }
 
-// Get Kelly criterion betting suggestion
-async fn kelly_suggestion_endpoint(
+// Onboarding grant and top-ups one user has received
+async fn user_faucet_endpoint(
     State(app_state): State<AppState>,
-    Path(event_id): Path<i32>,
-    Query(params): Query<HashMap<String, String>>,
+    Path(user_id): Path<i32>,
 ) -> ApiResult<Value> {
-    // Validate event_id
-    if event_id <= 0 {
-        return Err(bad_request_error("Invalid event_id: must be positive"));
+    if user_id <= 0 {
+        return Err(bad_request_error("Invalid user_id: must be positive"));
+    }
+    let config = &app_state.config.faucet;
+    match faucet::get_user_grants(&app_state.analytics_db, config, user_id).await {
+        Ok(grants) => Ok(Json(grants)),
+        Err(e) => Err(engine_error("Faucet history error", &e)),
     }
+}
 
-    // Validate belief probability - require explicit value, no defaults
-    let belief = params
-        .get("belief")
-        .and_then(|s| s.parse::<f64>().ok())
-        .ok_or_else(|| bad_request_error("Missing or invalid belief: must be a finite number"))?;
-    if !belief.is_finite() {
-        return Err(bad_request_error("Invalid belief: must be finite"));
+// Which engine notifications a user receives
+async fn user_preferences_endpoint(
+    State(app_state): State<AppState>,
+    Path(user_id): Path<i32>,
+) -> ApiResult<Value> {
+    if user_id <= 0 {
+        return Err(bad_request_error("Invalid user_id: must be positive"));
     }
-    if belief <= 0.0 || belief >= 1.0 {
-        return Err(bad_request_error(
-            "Invalid belief: must be between 0 and 1 (exclusive)",
-        ));
+    match notifications::get_preferences(&app_state.db, user_id).await {
+        Ok(preferences) => Ok(Json(json!(preferences))),
+        Err(e) => Err(engine_error("Preferences error", &e)),
     }
+}
 
-    // Validate user_id - require explicit value, no defaults
-    let user_id = params
-        .get("user_id")
-        .and_then(|s| s.parse::<i32>().ok())
-        .ok_or_else(|| {
-            bad_request_error("Missing or invalid user_id: must be a positive integer")
-        })?;
+// Turn notification kinds on or off; fields left out are unchanged
+async fn update_user_preferences_endpoint(
+    State(app_state): State<AppState>,
+    Path(user_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<notifications::PreferencesUpdate>,
+) -> ApiResult<Value> {
+    if user_id <= 0 {
+        return Err(bad_request_error("Invalid user_id: must be positive"));
+    }
+    match notifications::update_preferences(&app_state.db, user_id, &payload).await {
+        Ok(preferences) => Ok(Json(json!(preferences))),
+        Err(e) => Err(engine_error("Preferences update error", &e)),
+    }
+}
+
+// Issue an API key for a bot trading as this user; the key is only shown here
+async fn create_api_key_endpoint(
+    State(app_state): State<AppState>,
+    Path(user_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
     if user_id <= 0 {
         return Err(bad_request_error("Invalid user_id: must be positive"));
     }
+    let name = payload
+        .get("name")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| bad_request_error("Missing name"))?;
+    let scope = match payload.get("scope").and_then(|v| v.as_str()) {
+        Some(scope) => api_keys::Scope::parse(scope).ok_or_else(|| {
+            bad_request_error("Invalid scope: must be \"trade\" or \"read-only\"")
+        })?,
+        None => api_keys::Scope::ReadOnly,
+    };
+    let rate_limit = match payload.get("rate_limit_per_minute") {
+        None | Some(Value::Null) => None,
+        Some(v) => Some(
+            v.as_i64()
+                .and_then(|l| i32::try_from(l).ok())
+                .ok_or_else(|| {
+                    bad_request_error("Invalid rate_limit_per_minute: must be an integer")
+                })?,
+        ),
+    };
+
+    match api_keys::create_key(&app_state.db, user_id, name, scope, rate_limit).await {
+        Ok(key) => Ok(Json(json!({ "success": true, "key": key }))),
+        Err(e) => Err(engine_error("API key creation error", &e)),
+    }
+}
 
-    // Get current market probability
-    let market_prob_result: Result<f64, sqlx::Error> =
-        sqlx::query_scalar("SELECT market_prob FROM events WHERE id = $1")
-            .bind(event_id)
-            .fetch_one(&app_state.db)
-            .await;
+// A user's API keys with their request counts
+async fn list_api_keys_endpoint(
+    State(app_state): State<AppState>,
+    Path(user_id): Path<i32>,
+) -> ApiResult<Value> {
+    let default_limit = app_state.config.market.api_key_rate_limit_per_minute;
+    match api_keys::list_keys(&app_state.analytics_db, user_id, default_limit).await {
+        Ok(keys) => Ok(Json(keys)),
+        Err(e) => Err(engine_error("API key listing error", &e)),
+    }
+}
 
-    let market_prob = match market_prob_result {
-        Ok(prob) => prob,
-        Err(_) => return Err(not_found_error("Event")),
-    };
+async fn revoke_api_key_endpoint(
+    State(app_state): State<AppState>,
+    Path((user_id, key_id)): Path<(i32, i32)>,
+) -> ApiResult<Value> {
+    match api_keys::revoke_key(&app_state.db, user_id, key_id).await {
+        Ok(true) => Ok(Json(json!({ "success": true, "revoked": key_id }))),
+        Ok(false) => Err(not_found_error("API key")),
+        Err(e) => Err(engine_error("API key revocation error", &e)),
+    }
+}
 
-    // Get user balance from ledger
-    let balance_ledger_result: Result<i64, sqlx::Error> =
-        sqlx::query_scalar("SELECT rp_balance_ledger FROM users WHERE id = $1")
-            .bind(user_id)
-            .fetch_one(&app_state.db)
-            .await;
+// Admin-defined linkage between events: correlated, mutually exclusive or
+// related
+async fn create_event_cluster_endpoint(
+    State(app_state): State<AppState>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    let request: exposure::CreateCluster = serde_json::from_value(payload)
+        .map_err(|e| bad_request_error(&format!("Invalid cluster: {}", e)))?;
 
-    let balance = match balance_ledger_result {
-        Ok(bal) => lmsr_core::from_ledger_units(bal as i128),
-        Err(_) => return Err(not_found_error("User")),
-    };
+    match exposure::create_cluster(&app_state.db, &request).await {
+        Ok(cluster) => {
+            println!("🔗 Created event cluster {}", cluster["cluster_id"]);
+            Ok(Json(json!({ "success": true, "cluster": cluster })))
+        }
+        Err(e) => Err(engine_error("Cluster creation error", &e)),
+    }
+}
 
-    let suggestion = lmsr_api::kelly_suggestion(&app_state.config, belief, market_prob, balance);
-    Ok(Json(json!(suggestion)))
+async fn list_event_clusters_endpoint(State(app_state): State<AppState>) -> ApiResult<Value> {
+    match exposure::list_clusters(&app_state.analytics_db).await {
+        Ok(clusters) => Ok(Json(clusters)),
+        Err(e) => Err(engine_error("Cluster list error", &e)),
+    }
 }
 
-// Sell shares back to market
-async fn sell_shares_endpoint(
+// A cluster's members at current prices, with sanity warnings and the
+// arbitrage gap their prices leave open
+async fn cluster_endpoint(
+    State(app_state): State<AppState>,
+    Path(cluster_id): Path<i32>,
+) -> ApiResult<Value> {
+    match cluster_consistency::get_cluster(&app_state.analytics_db, cluster_id).await {
+        Ok(cluster) => Ok(Json(cluster)),
+        Err(e) => Err(engine_error("Cluster error", &e)),
+    }
+}
+
+// Create a competition; entrants trade its markets from a separate bankroll
+async fn create_competition_endpoint(
     State(app_state): State<AppState>,
-    Path(event_id): Path<i32>,
     ExtractJson(payload): ExtractJson<serde_json::Value>,
 ) -> ApiResult<Value> {
-    // Validate event_id
-    if event_id <= 0 {
-        return Err(bad_request_error("Invalid event_id: must be positive"));
+    let request: competitions::CreateCompetition = serde_json::from_value(payload)
+        .map_err(|e| bad_request_error(&format!("Invalid competition: {}", e)))?;
+
+    match competitions::create_competition(&app_state.db, &request).await {
+        Ok(competition) => {
+            println!(
+                "🏆 Created competition {} ({})",
+                competition.id, competition.name
+            );
+            Ok(Json(json!({ "success": true, "competition": competition })))
+        }
+        Err(e) => Err(engine_error("Competition creation error", &e)),
     }
+}
 
-    // Validate user_id - require explicit value, no defaults
+// Enter a user into a competition with a fresh bankroll
+async fn join_competition_endpoint(
+    State(app_state): State<AppState>,
+    Path(competition_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
     let user_id = payload
         .get("user_id")
         .and_then(|v| v.as_i64())
-        .ok_or_else(|| {
-            bad_request_error("Missing or invalid user_id: must be a positive integer")
-        })? as i32;
-    if user_id <= 0 {
-        return Err(bad_request_error("Invalid user_id: must be positive"));
-    }
+        .and_then(|v| i32::try_from(v).ok())
+        .filter(|v| *v > 0)
+        .ok_or_else(|| bad_request_error("Missing or invalid user_id"))?;
 
-    // Validate share_type - require explicit value, no defaults
-    let share_type = payload
-        .get("share_type")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| bad_request_error("Missing or invalid share_type: must be 'yes' or 'no'"))?;
-    if share_type != "yes" && share_type != "no" {
-        return Err(bad_request_error(
-            "Invalid share_type: must be 'yes' or 'no'",
-        ));
+    match competitions::join_competition(&app_state.db, competition_id, user_id).await {
+        Ok(wallet) => {
+            invalidate_and_broadcast(
+                &app_state,
+                "competition_joined",
+                json!({ "competition_id": competition_id, "user_id": user_id }),
+            );
+            Ok(Json(json!({ "success": true, "wallet": wallet })))
+        }
+        Err(e) => Err(engine_error("Competition join error", &e)),
     }
+}
 
-    // Validate amount - require explicit value, no defaults
-    let amount = payload
-        .get("amount")
-        .and_then(|v| v.as_f64())
-        .ok_or_else(|| bad_request_error("Missing or invalid amount: must be a finite number"))?;
-    if !amount.is_finite() {
-        return Err(bad_request_error("Invalid amount: must be finite"));
-    }
-    if amount <= 0.0 {
-        return Err(bad_request_error("Invalid amount: must be positive"));
-    }
-    if amount > 10_000_000.0 {
-        // 10M shares max per sale
-        return Err(bad_request_error(
-            "Invalid amount: exceeds maximum allowed (10,000,000 shares)",
-        ));
-    }
-    if amount < 0.000001 {
-        // Minimum 0.000001 shares (1 micro-share)
-        return Err(bad_request_error(
-            "Invalid amount: below minimum allowed (0.000001 shares)",
-        ));
-    }
+// Tag an untraded market as a competition market
+async fn add_competition_market_endpoint(
+    State(app_state): State<AppState>,
+    Path(competition_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    let event_id = payload
+        .get("event_id")
+        .and_then(|v| v.as_i64())
+        .and_then(|v| i32::try_from(v).ok())
+        .filter(|v| *v > 0)
+        .ok_or_else(|| bad_request_error("Missing or invalid event_id"))?;
 
-    match lmsr_api::sell_shares(
-        &app_state.db,
-        &app_state.config,
-        user_id,
-        event_id,
-        share_type,
-        amount,
-    )
-    .await
-    {
-        Ok(result) => {
+    match competitions::add_market(&app_state.db, competition_id, event_id).await {
+        Ok(()) => {
             invalidate_and_broadcast(
                 &app_state,
-                "shares_sold",
-                json!({
-                    "event_id": event_id,
-                    "user_id": user_id,
-                    "share_type": share_type,
-                    "amount": amount,
-                    "payout": result.payout,
-                    "new_prob": result.new_prob,
-                    "cumulative_stake": result.current_cost_c
-                }),
+                "competition_market_added",
+                json!({ "competition_id": competition_id, "event_id": event_id }),
             );
             Ok(Json(json!({
                 "success": true,
-                "payout": result.payout,
-                "new_prob": result.new_prob,
-                "cumulative_stake": result.current_cost_c,
-                "message": format!("Sold {} {} shares for {} RP", amount, share_type, result.payout)
+                "competition_id": competition_id,
+                "event_id": event_id
             })))
         }
-        Err(e) => {
-            let msg = e.to_string();
-            let msg_lower = msg.to_lowercase();
-            if msg_lower.contains("hold period not expired") {
-                return Err(bad_request_error(
-                    "Hold period not expired for recent purchases",
-                ));
-            }
-            if msg_lower.contains("market resolved") {
-                return Err(bad_request_error("Market resolved"));
-            }
-            if msg_lower.contains("market closed") {
-                return Err(bad_request_error("Market closed"));
+        Err(e) => Err(engine_error("Competition market error", &e)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LeaderboardQuery {
+    snapshot: Option<i64>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+// Entrants ranked by competition bankroll; paged reads pin a snapshot, and
+// the full ranking is cached until the next trade or score update
+async fn competition_leaderboard_endpoint(
+    State(app_state): State<AppState>,
+    Path(competition_id): Path<i32>,
+    Query(params): Query<LeaderboardQuery>,
+) -> ApiResult<Value> {
+    let result = if params.snapshot.is_none() && params.limit.is_none() && params.offset.is_none() {
+        let key = competitions::leaderboard_cache_key(competition_id);
+        if let Some(cached) = app_state.cache.get(&key).await {
+            if let Ok(leaderboard) = serde_json::from_str(&cached) {
+                return Ok(Json(leaderboard));
             }
-            Err(internal_error(&format!("Share sale error: {}", msg)))
         }
+        let leaderboard =
+            competitions::get_leaderboard(&app_state.analytics_db, competition_id).await;
+        if let Ok(leaderboard) = &leaderboard {
+            app_state.cache.insert(key, leaderboard.to_string()).await;
+        }
+        leaderboard
+    } else {
+        competitions::get_leaderboard_page(
+            &app_state.analytics_db,
+            competition_id,
+            params.snapshot,
+            params.limit.unwrap_or(50),
+            params.offset.unwrap_or(0),
+            app_state.config.market.leaderboard_snapshot_secs,
+        )
+        .await
+    };
+    match result {
+        Ok(leaderboard) => Ok(Json(leaderboard)),
+        Err(e) => Err(engine_error("Leaderboard error", &e)),
     }
 }
 
@@ -1631,7 +5499,25 @@ async fn get_user_shares_endpoint(
 
     match lmsr_api::get_user_shares(&app_state.db, user_id, event_id).await {
         Ok(shares) => Ok(Json(shares)),
-        Err(e) => Err(internal_error(&format!("User shares error: {}", e))),
+        Err(e) => Err(engine_error("User shares error", &e)),
+    }
+}
+
+// The user's position in one market, with the user in the path so API keys
+// can be checked against it
+async fn user_event_shares_endpoint(
+    State(app_state): State<AppState>,
+    Path((user_id, event_id)): Path<(i32, i32)>,
+) -> ApiResult<Value> {
+    if user_id <= 0 {
+        return Err(bad_request_error("Invalid user_id: must be positive"));
+    }
+    if event_id <= 0 {
+        return Err(bad_request_error("Invalid event_id: must be positive"));
+    }
+    match lmsr_api::get_user_shares(&app_state.db, user_id, event_id).await {
+        Ok(shares) => Ok(Json(shares)),
+        Err(e) => Err(engine_error("User shares error", &e)),
     }
 }
 
@@ -1662,6 +5548,13 @@ async fn resolve_market_event_endpoint(
                         "timestamp": chrono::Utc::now().to_rfc3339()
                     }),
                 );
+                webhooks::emit(
+                    &app_state.db,
+                    webhooks::EVENT_RESOLVED,
+                    json!({ "event_id": event_id, "outcome_id": outcome_id }),
+                );
+                invariants::sample_after_resolution(&app_state.analytics_db, event_id);
+                rank_history::mark_dirty(event_id);
                 return Ok(Json(json!({
                     "success": true,
                     "event_id": event_id,
@@ -1669,7 +5562,7 @@ async fn resolve_market_event_endpoint(
                     "message": format!("Market event {} resolved with outcome {}", event_id, outcome_id)
                 })));
             }
-            Err(e) => return Err(internal_error(&format!("Market resolution error: {}", e))),
+            Err(e) => return Err(engine_error("Market resolution error", &e)),
         }
     }
 
@@ -1689,6 +5582,17 @@ async fn resolve_market_event_endpoint(
                         "timestamp": chrono::Utc::now().to_rfc3339()
                     }),
                 );
+                webhooks::emit(
+                    &app_state.db,
+                    webhooks::EVENT_RESOLVED,
+                    json!({
+                        "event_id": event_id,
+                        "outcome_id": outcome_id,
+                        "numerical_outcome": numerical_outcome
+                    }),
+                );
+                invariants::sample_after_resolution(&app_state.analytics_db, event_id);
+                rank_history::mark_dirty(event_id);
                 return Ok(Json(json!({
                     "success": true,
                     "event_id": event_id,
@@ -1697,41 +5601,238 @@ async fn resolve_market_event_endpoint(
                     "message": format!("Numeric market {} resolved into bucket {}", event_id, outcome_id)
                 })));
             }
-            Err(e) => {
-                return Err(internal_error(&format!(
-                    "Numeric market resolution error: {}",
-                    e
-                )))
-            }
+            Err(e) => return Err(engine_error("Numeric market resolution error", &e)),
+        }
+    }
+
+    let resolution = payload.get("outcome").and_then(parse_resolution).ok_or_else(|| {
+        bad_request_error(
+            "Provide one of: outcome (bool or \"not_applicable\"), outcome_id, or numerical_outcome",
+        )
+    })?;
+
+    let result = match resolution.scored() {
+        Some(outcome) => lmsr_api::resolve_event(&app_state.db, event_id, outcome).await,
+        None => lmsr_api::annul_event(&app_state.db, event_id).await,
+    };
+    match result {
+        Ok(payouts) => {
+            announce_binary_resolution(&app_state, event_id, resolution, &payouts).await;
+            Ok(Json(json!({
+                "success": true,
+                "event_id": event_id,
+                "outcome": resolution.scored(),
+                "resolution": resolution.as_outcome(),
+                "payouts": payouts,
+                "message": format!("Market event {} resolved as {}", event_id, resolution.label())
+            })))
+        }
+        Err(e) => Err(engine_error("Market resolution error", &e)),
+    }
+}
+
+// An outcome as requests give it: true/false, or "not_applicable" for N/A
+fn parse_resolution(value: &Value) -> Option<lmsr_api::Resolution> {
+    match value {
+        Value::Bool(outcome) => Some((*outcome).into()),
+        Value::String(s) if s.eq_ignore_ascii_case("not_applicable") => {
+            Some(lmsr_api::Resolution::NotApplicable)
         }
+        _ => None,
     }
+}
+
+// Tell clients, webhooks and the invariant sampler a binary market resolved
+async fn announce_binary_resolution(
+    app_state: &AppState,
+    event_id: i32,
+    resolution: lmsr_api::Resolution,
+    payouts: &[lmsr_api::ResolutionPayout],
+) {
+    let outcome = resolution.scored();
+    let notified = broadcast_resolution(
+        app_state,
+        "marketResolved",
+        event_id,
+        json!({
+            "eventId": event_id,
+            "outcome": outcome,
+            "resolution": resolution.as_outcome(),
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        }),
+        payouts,
+    )
+    .await;
+    webhooks::emit(
+        &app_state.db,
+        webhooks::EVENT_RESOLVED,
+        json!({
+            "event_id": event_id,
+            "outcome": outcome,
+            "resolution": resolution.as_outcome(),
+            "notify_user_ids": notified
+        }),
+    );
+    invariants::sample_after_resolution(&app_state.analytics_db, event_id);
+    rank_history::mark_dirty(event_id);
+}
 
+// Dry-run a binary resolution and keep the report for commit or cancel
+async fn preview_resolution_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
     let outcome = payload
         .get("outcome")
         .and_then(|v| v.as_bool())
-        .ok_or_else(|| {
-            bad_request_error("Provide one of: outcome (bool), outcome_id, or numerical_outcome")
-        })?;
+        .ok_or_else(|| bad_request_error("outcome must be a boolean"))?;
+    let actor = payload.get("actor").and_then(|v| v.as_str());
+    let reason = payload.get("reason").and_then(|v| v.as_str());
 
-    match lmsr_api::resolve_event(&app_state.db, event_id, outcome).await {
-        Ok(()) => {
-            invalidate_and_broadcast(
+    match resolution_preview::preview(&app_state.db, event_id, outcome, actor, reason).await {
+        Ok(report) => Ok(Json(json!({ "success": true, "pending": report }))),
+        Err(e) => Err(engine_error("Resolution preview error", &e)),
+    }
+}
+
+// The pending resolution report, if one was previewed
+async fn pending_resolution_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+) -> ApiResult<Value> {
+    match resolution_preview::get_pending(&app_state.analytics_db, event_id).await {
+        Ok(Some(report)) => Ok(Json(json!(report))),
+        Ok(None) => Err(not_found_error("Pending resolution")),
+        Err(e) => Err(engine_error("Resolution preview error", &e)),
+    }
+}
+
+// Apply a previewed resolution, if positions still match the preview
+async fn commit_resolution_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+) -> ApiResult<Value> {
+    match resolution_preview::commit(&app_state.db, event_id).await {
+        Ok(report) => {
+            announce_binary_resolution(
                 &app_state,
-                "marketResolved",
-                json!({
-                    "eventId": event_id,
-                    "outcome": outcome,
-                    "timestamp": chrono::Utc::now().to_rfc3339()
-                }),
-            );
+                event_id,
+                report.outcome.into(),
+                &report.payouts,
+            )
+            .await;
             Ok(Json(json!({
                 "success": true,
                 "event_id": event_id,
-                "outcome": outcome,
-                "message": format!("Market event {} resolved as {}", event_id, if outcome { "YES" } else { "NO" })
+                "outcome": report.outcome,
+                "resolution": report
             })))
         }
-        Err(e) => Err(internal_error(&format!("Market resolution error: {}", e))),
+        Err(e) => Err(engine_error("Resolution preview error", &e)),
+    }
+}
+
+// Discard a previewed resolution
+async fn cancel_resolution_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+) -> ApiResult<Value> {
+    match resolution_preview::cancel(&app_state.db, event_id).await {
+        Ok(true) => Ok(Json(json!({ "success": true, "event_id": event_id }))),
+        Ok(false) => Err(not_found_error("Pending resolution")),
+        Err(e) => Err(engine_error("Resolution preview error", &e)),
+    }
+}
+
+// Revert a binary resolution inside the dispute window; with "outcome" it is
+// re-resolved in the same transaction
+async fn dispute_resolution_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    ExtractJson(payload): ExtractJson<serde_json::Value>,
+) -> ApiResult<Value> {
+    if event_id <= 0 {
+        return Err(bad_request_error("Invalid event_id: must be positive"));
+    }
+    let actor = payload
+        .get("actor")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| bad_request_error("Missing actor"))?;
+    let reason = payload
+        .get("reason")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| bad_request_error("Missing reason"))?;
+    let corrected_outcome = match payload.get("outcome") {
+        None | Some(Value::Null) => None,
+        Some(v) => Some(parse_resolution(v).ok_or_else(|| {
+            bad_request_error("outcome must be a boolean or \"not_applicable\"")
+        })?),
+    };
+
+    match disputes::dispute_resolution(
+        &app_state.db,
+        &app_state.config,
+        event_id,
+        corrected_outcome,
+        actor,
+        reason,
+    )
+    .await
+    {
+        Ok(result) => {
+            let notified = broadcast_resolution(
+                &app_state,
+                "resolution_reverted",
+                event_id,
+                json!({
+                    "event_id": event_id,
+                    "previous_outcome": result.previous_outcome,
+                    "new_outcome": result.new_outcome
+                }),
+                &result.payouts,
+            )
+            .await;
+            webhooks::emit(
+                &app_state.db,
+                webhooks::RESOLUTION_REVERTED,
+                json!({
+                    "event_id": event_id,
+                    "previous_outcome": result.previous_outcome,
+                    "positions_restored": result.positions_restored,
+                    "clawback_ledger": result.clawback_ledger,
+                    "actor": actor,
+                    "reason": reason,
+                    "notify_user_ids": notified
+                }),
+            );
+            if let Some(resolution) = corrected_outcome {
+                webhooks::emit(
+                    &app_state.db,
+                    webhooks::EVENT_RESOLVED,
+                    json!({
+                        "event_id": event_id,
+                        "outcome": resolution.scored(),
+                        "resolution": resolution.as_outcome(),
+                        "notify_user_ids": notified
+                    }),
+                );
+                invariants::sample_after_resolution(&app_state.analytics_db, event_id);
+                rank_history::mark_dirty(event_id);
+            }
+            Ok(Json(json!({ "success": true, "dispute": result })))
+        }
+        Err(e) => Err(engine_error("Dispute error", &e)),
+    }
+}
+
+async fn resolution_history_endpoint(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+) -> ApiResult<Value> {
+    match disputes::get_resolution_history(&app_state.analytics_db, event_id).await {
+        Ok(history) => Ok(Json(history)),
+        Err(e) => Err(engine_error("Resolution history error", &e)),
     }
 }
 
@@ -1867,12 +5968,9 @@ async fn verify_balance_invariant_endpoint(
         return Err(bad_request_error("Invalid user_id: must be positive"));
     }
 
-    match lmsr_api::verify_balance_invariant(&app_state.db, user_id).await {
+    match lmsr_api::verify_balance_invariant(&app_state.analytics_db, user_id).await {
         Ok(result) => Ok(Json(result)),
-        Err(e) => Err(internal_error(&format!(
-            "Balance invariant verification error: {}",
-            e
-        ))),
+        Err(e) => Err(engine_error("Balance invariant verification error", &e)),
     }
 }
 
@@ -1892,12 +5990,9 @@ async fn verify_staked_invariant_endpoint(
         return Err(bad_request_error("Invalid user_id: must be positive"));
     }
 
-    match lmsr_api::verify_staked_invariant(&app_state.db, user_id).await {
+    match lmsr_api::verify_staked_invariant(&app_state.analytics_db, user_id).await {
         Ok(result) => Ok(Json(result)),
-        Err(e) => Err(internal_error(&format!(
-            "Staked invariant verification error: {}",
-            e
-        ))),
+        Err(e) => Err(engine_error("Staked invariant verification error", &e)),
     }
 }
 
@@ -1917,12 +6012,12 @@ async fn verify_post_resolution_endpoint(
         return Err(bad_request_error("Invalid event_id: must be positive"));
     }
 
-    match lmsr_api::verify_post_resolution_invariant(&app_state.db, event_id).await {
+    match lmsr_api::verify_post_resolution_invariant(&app_state.analytics_db, event_id).await {
         Ok(result) => Ok(Json(result)),
-        Err(e) => Err(internal_error(&format!(
-            "Post-resolution invariant verification error: {}",
-            e
-        ))),
+        Err(e) => Err(engine_error(
+            "Post-resolution invariant verification error",
+            &e,
+        )),
     }
 }
 
@@ -1942,11 +6037,21 @@ async fn verify_consistency_endpoint(
         return Err(bad_request_error("Invalid event_id: must be positive"));
     }
 
-    match lmsr_api::verify_system_consistency(&app_state.db, event_id).await {
+    match lmsr_api::verify_system_consistency(&app_state.analytics_db, event_id).await {
         Ok(result) => Ok(Json(result)),
-        Err(e) => Err(internal_error(&format!(
-            "System consistency verification error: {}",
-            e
-        ))),
+        Err(e) => Err(engine_error("System consistency verification error", &e)),
+    }
+}
+
+// Counters for the sampled production invariant checks (see invariants.rs)
+async fn invariant_stats_endpoint(State(_app_state): State<AppState>) -> ApiResult<Value> {
+    Ok(Json(invariants::stats()))
+}
+
+// Global conservation: users' RP plus the system accounts must total 0
+async fn solvency_endpoint(State(app_state): State<AppState>) -> ApiResult<Value> {
+    match system_accounts::audit(&app_state.db).await {
+        Ok(audit) => Ok(Json(json!(audit))),
+        Err(e) => Err(engine_error("Solvency audit error", &e)),
     }
 }
@@ -0,0 +1,334 @@
+//! How well the market maker's own prices forecast outcomes.
+//!
+//! When a binary event resolves, its closing probability (the market price
+//! at resolution, before payouts), liquidity, category and trade count are
+//! recorded in `market_accuracy` with the Brier and log scores of that
+//! price. `GET /analytics/market-accuracy` averages them by category and by
+//! liquidity bucket, so `liquidity_b` can be tuned on evidence: a thin
+//! market that swings on every trade and a deep one that barely moves
+//! should show up as different scores.
+//!
+//! Events resolved before the table existed are backfilled from their
+//! final `market_prob` when it is created. Untraded markets only ever show
+//! their opening (or imported) price, so the report leaves them out unless
+//! asked with `min_trades=0`.
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use sqlx::{PgPool, Postgres, Row, Transaction};
+use std::collections::BTreeMap;
+
+use crate::api_error::{coded, ErrorCode};
+use crate::paper_predictions::{brier_score, log_score};
+
+/// Default liquidity bucket edges: below 1000, 1000–5000, 5000–10000 and
+/// 10000 up. New markets default to a `liquidity_b` of 5000.
+pub const DEFAULT_LIQUIDITY_EDGES: [f64; 3] = [1000.0, 5000.0, 10000.0];
+pub const MAX_LIQUIDITY_EDGES: usize = 20;
+
+/// An event's closing probability, what it is grouped by and, once
+/// resolved, its outcome, counting trades in `trades_table`.
+fn closing_state(trades_table: &str) -> String {
+    format!(
+        r#"
+        SELECT e.id AS event_id, e.category, e.liquidity_b::float8 AS liquidity_b,
+               COALESCE(e.market_prob, 0.5)::float8 AS closing_prob,
+               (SELECT COUNT(*)::int FROM {} mu WHERE mu.event_id = e.id) AS trades,
+               e.outcome = 'resolved_yes' AS resolved_yes
+        FROM events e
+        "#,
+        trades_table
+    )
+}
+
+pub async fn ensure_market_accuracy_table(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS market_accuracy (
+            event_id INTEGER PRIMARY KEY REFERENCES events(id) ON DELETE CASCADE,
+            category TEXT,
+            outcome BOOLEAN NOT NULL,
+            closing_prob DOUBLE PRECISION NOT NULL,
+            liquidity_b DOUBLE PRECISION,
+            trades INTEGER NOT NULL DEFAULT 0,
+            brier_score DOUBLE PRECISION NOT NULL,
+            log_score DOUBLE PRECISION NOT NULL,
+            recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    backfill(pool).await?;
+    Ok(())
+}
+
+async fn insert(
+    tx: &mut Transaction<'_, Postgres>,
+    row: &sqlx::postgres::PgRow,
+    outcome: bool,
+) -> Result<()> {
+    let closing_prob: f64 = row.get("closing_prob");
+    sqlx::query(
+        r#"
+        INSERT INTO market_accuracy
+            (event_id, category, outcome, closing_prob, liquidity_b, trades,
+             brier_score, log_score)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (event_id) DO UPDATE
+        SET category = EXCLUDED.category,
+            outcome = EXCLUDED.outcome,
+            closing_prob = EXCLUDED.closing_prob,
+            liquidity_b = EXCLUDED.liquidity_b,
+            trades = EXCLUDED.trades,
+            brier_score = EXCLUDED.brier_score,
+            log_score = EXCLUDED.log_score,
+            recorded_at = NOW()
+        "#,
+    )
+    .bind(row.get::<i32, _>("event_id"))
+    .bind(row.get::<Option<String>, _>("category"))
+    .bind(outcome)
+    .bind(closing_prob)
+    .bind(row.get::<Option<f64>, _>("liquidity_b"))
+    .bind(row.get::<i32, _>("trades"))
+    .bind(brier_score(closing_prob, outcome))
+    .bind(log_score(closing_prob, outcome))
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Records resolved binary events that have no row yet. Returns how many.
+/// Trades of archived events are counted through `market_updates_all`.
+pub async fn backfill(pool: &PgPool) -> Result<u64> {
+    let mut tx = pool.begin().await?;
+    let archived: bool = sqlx::query_scalar("SELECT to_regclass('market_updates_all') IS NOT NULL")
+        .fetch_one(&mut *tx)
+        .await?;
+    let rows = sqlx::query(&format!(
+        r#"{}
+        WHERE e.outcome IN ('resolved_yes', 'resolved_no')
+          AND COALESCE(e.event_type, 'binary') = 'binary'
+          AND NOT EXISTS (SELECT 1 FROM market_accuracy ma WHERE ma.event_id = e.id)
+        "#,
+        closing_state(if archived {
+            "market_updates_all"
+        } else {
+            "market_updates"
+        })
+    ))
+    .fetch_all(&mut *tx)
+    .await?;
+    for row in &rows {
+        let outcome = row.get::<Option<bool>, _>("resolved_yes").unwrap_or(false);
+        insert(&mut tx, row, outcome).await?;
+    }
+    tx.commit().await?;
+    Ok(rows.len() as u64)
+}
+
+/// Records the closing price of a resolving binary event. Call before
+/// payouts; databases without the table are skipped.
+pub(crate) async fn record_resolution(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    outcome: bool,
+) -> Result<()> {
+    let table_exists: bool =
+        sqlx::query_scalar("SELECT to_regclass('market_accuracy') IS NOT NULL")
+            .fetch_one(&mut **tx)
+            .await?;
+    if !table_exists {
+        return Ok(());
+    }
+    let row = sqlx::query(&format!(
+        "{} WHERE e.id = $1",
+        closing_state("market_updates")
+    ))
+    .bind(event_id)
+    .fetch_one(&mut **tx)
+    .await?;
+    insert(tx, &row, outcome).await
+}
+
+/// Drops the record of a resolution a dispute has reverted.
+pub(crate) async fn revert_resolution(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+) -> Result<()> {
+    let table_exists: bool =
+        sqlx::query_scalar("SELECT to_regclass('market_accuracy') IS NOT NULL")
+            .fetch_one(&mut **tx)
+            .await?;
+    if table_exists {
+        sqlx::query("DELETE FROM market_accuracy WHERE event_id = $1")
+            .bind(event_id)
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Running totals for one group of resolved events.
+#[derive(Debug, Default, Clone, Copy)]
+struct Tally {
+    events: u64,
+    yes: u64,
+    prob: f64,
+    brier: f64,
+    log: f64,
+}
+
+impl Tally {
+    fn add(&mut self, row: &sqlx::postgres::PgRow) {
+        self.events += 1;
+        self.yes += row.get::<bool, _>("outcome") as u64;
+        self.prob += row.get::<f64, _>("closing_prob");
+        self.brier += row.get::<f64, _>("brier_score");
+        self.log += row.get::<f64, _>("log_score");
+    }
+
+    fn to_json(self) -> Value {
+        let n = self.events as f64;
+        let mean = |total: f64| (self.events > 0).then(|| total / n);
+        json!({
+            "events": self.events,
+            "brier_score": mean(self.brier),
+            "log_score": mean(self.log),
+            "mean_closing_prob": mean(self.prob),
+            "yes_rate": mean(self.yes as f64),
+        })
+    }
+}
+
+/// Parses `1000,5000,10000` into strictly increasing positive edges.
+pub fn parse_liquidity_edges(edges: Option<&str>) -> Result<Vec<f64>> {
+    let Some(edges) = edges.map(str::trim).filter(|e| !e.is_empty()) else {
+        return Ok(DEFAULT_LIQUIDITY_EDGES.to_vec());
+    };
+    let parsed = edges
+        .split(',')
+        .map(|edge| edge.trim().parse::<f64>())
+        .collect::<Result<Vec<f64>, _>>()
+        .map_err(|_| {
+            coded(
+                ErrorCode::InvalidRequest,
+                "liquidity_edges must be a comma-separated list of numbers",
+            )
+        })?;
+    if parsed.len() > MAX_LIQUIDITY_EDGES {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!(
+                "liquidity_edges must have at most {} edges",
+                MAX_LIQUIDITY_EDGES
+            ),
+        ));
+    }
+    if parsed.iter().any(|e| !e.is_finite() || *e <= 0.0)
+        || parsed.windows(2).any(|pair| pair[0] >= pair[1])
+    {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "liquidity_edges must be positive and strictly increasing",
+        ));
+    }
+    Ok(parsed)
+}
+
+/// Index of the bucket `b` falls in: bucket `i` is `[edges[i-1], edges[i])`.
+fn bucket_index(edges: &[f64], b: f64) -> usize {
+    edges.partition_point(|&edge| edge <= b)
+}
+
+/// Mean scores of the closing price over resolved binary events with at
+/// least `min_trades` trades, overall, by category and by liquidity bucket.
+/// Events with no recorded liquidity are left out of the buckets.
+pub async fn get_accuracy(
+    pool: &PgPool,
+    category: Option<&str>,
+    min_trades: i32,
+    liquidity_edges: &[f64],
+) -> Result<Value> {
+    if min_trades < 0 {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "min_trades must be non-negative",
+        ));
+    }
+    let category = category.map(str::trim).filter(|c| !c.is_empty());
+    let rows = sqlx::query(
+        r#"
+        SELECT category, outcome, closing_prob, liquidity_b, brier_score, log_score
+        FROM market_accuracy
+        WHERE trades >= $1
+          AND ($2::text IS NULL OR LOWER(category) = LOWER($2))
+        "#,
+    )
+    .bind(min_trades)
+    .bind(category)
+    .fetch_all(pool)
+    .await?;
+
+    let mut overall = Tally::default();
+    let mut by_category: BTreeMap<Option<String>, Tally> = BTreeMap::new();
+    let mut by_liquidity = vec![Tally::default(); liquidity_edges.len() + 1];
+    for row in &rows {
+        overall.add(row);
+        by_category.entry(row.get("category")).or_default().add(row);
+        if let Some(b) = row.get::<Option<f64>, _>("liquidity_b") {
+            by_liquidity[bucket_index(liquidity_edges, b)].add(row);
+        }
+    }
+
+    let by_category: Vec<Value> = by_category
+        .into_iter()
+        .map(|(category, tally)| {
+            let mut entry = tally.to_json();
+            entry["category"] = json!(category);
+            entry
+        })
+        .collect();
+    let by_liquidity: Vec<Value> = by_liquidity
+        .into_iter()
+        .enumerate()
+        .map(|(i, tally)| {
+            let mut entry = tally.to_json();
+            entry["min_liquidity_b"] = json!(i.checked_sub(1).map(|lo| liquidity_edges[lo]));
+            entry["max_liquidity_b"] = json!(liquidity_edges.get(i));
+            entry
+        })
+        .collect();
+
+    Ok(json!({
+        "category": category,
+        "min_trades": min_trades,
+        "liquidity_edges": liquidity_edges,
+        "overall": overall.to_json(),
+        "by_category": by_category,
+        "by_liquidity": by_liquidity,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn liquidity_buckets_are_half_open() {
+        let edges = parse_liquidity_edges(None).unwrap();
+        assert_eq!(bucket_index(&edges, 500.0), 0);
+        assert_eq!(bucket_index(&edges, 1000.0), 1);
+        assert_eq!(bucket_index(&edges, 5000.0), 2);
+        assert_eq!(bucket_index(&edges, 25000.0), 3);
+
+        assert_eq!(
+            parse_liquidity_edges(Some(" 50, 200 ")).unwrap(),
+            vec![50.0, 200.0]
+        );
+        assert!(parse_liquidity_edges(Some("200,50")).is_err());
+        assert!(parse_liquidity_edges(Some("0,50")).is_err());
+        assert!(parse_liquidity_edges(Some("lots")).is_err());
+    }
+}
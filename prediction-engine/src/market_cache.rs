@@ -0,0 +1,140 @@
+//! Per-event market state cache.
+//!
+//! Market-state and quote reads are served from an in-process moka cache
+//! keyed by event id instead of re-running the market-state query on every
+//! request. Trades write through: once a trade commits, its handler reloads
+//! the event's state into the cache, so the trader's next read sees it.
+//! Writes the engine doesn't make itself (the backend resolving or editing
+//! an event, another engine instance trading) are picked up through a
+//! trigger that NOTIFYs `market_state_changed` with the event id; the
+//! listener evicts that entry. The engine's own trades fire the trigger
+//! too, which costs at most one extra reload after a write-through. If the
+//! listener's connection drops, notifications may have been missed, so the
+//! whole cache is cleared; the TTL bounds staleness if it stays down.
+
+use anyhow::{anyhow, Result};
+use moka::future::Cache;
+use serde_json::Value;
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use std::time::Duration;
+
+use crate::lmsr_api;
+
+pub const CHANNEL: &str = "market_state_changed";
+
+#[derive(Clone)]
+pub struct MarketStateCache {
+    /// None when caching is disabled (TTL of 0).
+    states: Option<Cache<i32, Value>>,
+}
+
+impl MarketStateCache {
+    pub fn new(ttl_secs: u64) -> Self {
+        let states = (ttl_secs > 0).then(|| {
+            Cache::builder()
+                .max_capacity(10_000)
+                .time_to_live(Duration::from_secs(ttl_secs))
+                .build()
+        });
+        Self { states }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.states.is_some()
+    }
+
+    /// The event's market state, loaded on a miss.
+    pub async fn get(&self, pool: &PgPool, event_id: i32) -> Result<Value> {
+        let Some(states) = &self.states else {
+            return lmsr_api::get_market_state(pool, event_id).await;
+        };
+        states
+            .try_get_with(event_id, lmsr_api::get_market_state(pool, event_id))
+            .await
+            .map_err(|e| anyhow!("{}", e))
+    }
+
+    /// Reloads the event's state after a committed write.
+    pub async fn write_through(&self, pool: &PgPool, event_id: i32) -> Result<()> {
+        if let Some(states) = &self.states {
+            // Evict first so a failed reload can't leave the old state behind
+            states.invalidate(&event_id).await;
+            let state = lmsr_api::get_market_state(pool, event_id).await?;
+            states.insert(event_id, state).await;
+        }
+        Ok(())
+    }
+
+    pub async fn invalidate(&self, event_id: i32) {
+        if let Some(states) = &self.states {
+            states.invalidate(&event_id).await;
+        }
+    }
+
+    pub fn invalidate_all(&self) {
+        if let Some(states) = &self.states {
+            states.invalidate_all();
+        }
+    }
+}
+
+/// Installs the triggers that NOTIFY `CHANNEL` when an event's market state
+/// changes.
+pub async fn ensure_notify_triggers(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE OR REPLACE FUNCTION notify_market_state_changed() RETURNS trigger AS $$
+        DECLARE
+            changed_id INTEGER;
+        BEGIN
+            IF TG_TABLE_NAME = 'events' AND TG_OP = 'DELETE' THEN
+                changed_id := OLD.id;
+            ELSIF TG_TABLE_NAME = 'events' THEN
+                changed_id := NEW.id;
+            ELSIF TG_OP = 'DELETE' THEN
+                changed_id := OLD.event_id;
+            ELSE
+                changed_id := NEW.event_id;
+            END IF;
+            PERFORM pg_notify('market_state_changed', changed_id::text);
+            RETURN NULL;
+        END;
+        $$ LANGUAGE plpgsql;
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    for table in ["events", "event_outcome_states", "event_outcomes"] {
+        sqlx::query(&format!(
+            "CREATE OR REPLACE TRIGGER market_state_changed
+             AFTER INSERT OR UPDATE OR DELETE ON {}
+             FOR EACH ROW EXECUTE FUNCTION notify_market_state_changed()",
+            table
+        ))
+        .execute(pool)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Listens on `CHANNEL` and evicts each event named in a notification.
+pub async fn listen_for_changes(pool: &PgPool, cache: MarketStateCache) -> Result<()> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(CHANNEL).await?;
+    loop {
+        // try_recv yields None after a reconnect, when notifications sent
+        // while the connection was down are lost
+        match listener.try_recv().await {
+            Ok(Some(notification)) => match notification.payload().parse::<i32>() {
+                Ok(event_id) => cache.invalidate(event_id).await,
+                Err(_) => cache.invalidate_all(),
+            },
+            Ok(None) => cache.invalidate_all(),
+            Err(e) => {
+                cache.invalidate_all();
+                return Err(e.into());
+            }
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! Closing markets at their closing_date.
+//!
+//! Every trade path already refuses to trade once `closing_date` has passed,
+//! but nothing recorded the transition, so clients only learned a market had
+//! closed by having a trade rejected. The sweep stamps `events.closed_at` on
+//! unresolved markets that are past due and returns them so the server can
+//! broadcast the status change. Trade guards treat a stamped market as closed
+//! even if its closing_date is later moved; reopening means clearing
+//! `closed_at` as well.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClosedMarket {
+    pub event_id: i32,
+    pub title: String,
+    pub closing_date: DateTime<Utc>,
+    pub closed_at: DateTime<Utc>,
+}
+
+pub async fn ensure_closed_at_column(pool: &PgPool) -> Result<()> {
+    sqlx::query("ALTER TABLE events ADD COLUMN IF NOT EXISTS closed_at TIMESTAMPTZ")
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Marks every unresolved, past-due market closed and returns the ones this
+/// call transitioned. Markets closed by an earlier sweep are not returned again.
+pub async fn close_due_markets(pool: &PgPool) -> Result<Vec<ClosedMarket>> {
+    let rows = sqlx::query(
+        "UPDATE events
+         SET closed_at = NOW()
+         WHERE closed_at IS NULL
+           AND outcome IS NULL
+           AND closing_date <= NOW()
+         RETURNING id, title, closing_date::timestamptz AS closing_date, closed_at",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| ClosedMarket {
+            event_id: row.get("id"),
+            title: row.get("title"),
+            closing_date: row.get("closing_date"),
+            closed_at: row.get("closed_at"),
+        })
+        .collect())
+}
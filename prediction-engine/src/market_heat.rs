@@ -0,0 +1,247 @@
+//! Market heat: how busy each open market has been over the last day.
+//!
+//! Cumulative stake only grows, so ordering by it keeps markets that were
+//! busy once on top long after trading moved elsewhere. Heat looks at the
+//! last `WINDOW_HOURS` instead: trades, distinct traders, how far the price
+//! moved (net, from the window's first trade to its last; on multi-outcome
+//! markets the outcome that moved most) and stake per hour. Each count is
+//! log-damped so a single whale's burst can't outweigh a crowd, and the
+//! move counts directly: a ten-point swing adds as much as a couple of
+//! extra traders.
+//!
+//! A refresh recomputes every open market's heat into `market_heat`
+//! (replacing the last run, so closed markets drop out) on the engine's
+//! schedule; `GET /markets/trending` lists the hottest from there.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool, Row};
+
+use crate::api_error::{coded, ErrorCode};
+
+/// Hours of activity a refresh looks back over.
+pub const WINDOW_HOURS: i64 = 24;
+pub const MAX_LIMIT: i64 = 100;
+
+const TRADE_WEIGHT: f64 = 1.0;
+const TRADER_WEIGHT: f64 = 2.0;
+const MOVE_WEIGHT: f64 = 10.0;
+const STAKE_WEIGHT: f64 = 1.0;
+
+/// One market's activity over the window.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Activity {
+    pub trades: i64,
+    pub unique_traders: i64,
+    /// Net probability move, 0–1.
+    pub prob_move: f64,
+    /// RP staked per hour.
+    pub stake_per_hour: f64,
+}
+
+impl Activity {
+    pub fn heat(&self) -> f64 {
+        TRADE_WEIGHT * (self.trades as f64).ln_1p()
+            + TRADER_WEIGHT * (self.unique_traders as f64).ln_1p()
+            + MOVE_WEIGHT * self.prob_move
+            + STAKE_WEIGHT * self.stake_per_hour.ln_1p()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct TrendingMarket {
+    pub event_id: i32,
+    pub title: String,
+    pub category: Option<String>,
+    pub event_type: String,
+    pub market_prob: Option<f64>,
+    pub heat: f64,
+    pub trades_24h: i64,
+    pub unique_traders_24h: i64,
+    pub prob_move_24h: f64,
+    pub stake_per_hour: f64,
+    pub computed_at: DateTime<Utc>,
+}
+
+pub async fn ensure_market_heat_table(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS market_heat (
+            event_id INTEGER PRIMARY KEY REFERENCES events(id) ON DELETE CASCADE,
+            heat DOUBLE PRECISION NOT NULL,
+            trades_24h BIGINT NOT NULL,
+            unique_traders_24h BIGINT NOT NULL,
+            prob_move_24h DOUBLE PRECISION NOT NULL,
+            stake_per_hour DOUBLE PRECISION NOT NULL,
+            computed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_market_heat_heat ON market_heat (heat DESC)")
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Recomputes the heat of every open market; returns how many were scored.
+pub async fn refresh(pool: &PgPool) -> Result<usize> {
+    ensure_market_heat_table(pool).await?;
+    let since = Utc::now() - Duration::hours(WINDOW_HOURS);
+    let rows = sqlx::query(
+        r#"
+        WITH trades AS (
+            SELECT event_id, user_id, stake_amount, prev_prob, new_prob, created_at,
+                   id::bigint AS id, 0::bigint AS series
+            FROM market_updates
+            WHERE created_at > $1
+            UNION ALL
+            SELECT event_id, user_id, stake_amount, prev_prob, new_prob, created_at,
+                   id, outcome_id AS series
+            FROM market_outcome_updates
+            WHERE created_at > $1
+        ),
+        activity AS (
+            SELECT event_id, COUNT(*) AS trades, COUNT(DISTINCT user_id) AS unique_traders,
+                   SUM(stake_amount) AS stake
+            FROM trades
+            GROUP BY event_id
+        ),
+        moves AS (
+            SELECT event_id, MAX(net_move) AS prob_move
+            FROM (
+                SELECT event_id,
+                       ABS((ARRAY_AGG(new_prob ORDER BY created_at DESC, id DESC))[1]
+                           - (ARRAY_AGG(prev_prob ORDER BY created_at, id))[1]) AS net_move
+                FROM trades
+                GROUP BY event_id, series
+            ) series
+            GROUP BY event_id
+        )
+        SELECT e.id,
+               COALESCE(a.trades, 0) AS trades,
+               COALESCE(a.unique_traders, 0) AS unique_traders,
+               COALESCE(m.prob_move, 0)::float8 AS prob_move,
+               COALESCE(a.stake, 0)::float8 AS stake
+        FROM events e
+        LEFT JOIN activity a ON a.event_id = e.id
+        LEFT JOIN moves m ON m.event_id = e.id
+        WHERE e.outcome IS NULL
+          AND e.closed_at IS NULL
+          AND e.hidden_at IS NULL
+          AND COALESCE(e.closing_date::timestamptz > NOW(), true)
+        "#,
+    )
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+
+    let mut event_ids = Vec::with_capacity(rows.len());
+    let mut activities = Vec::with_capacity(rows.len());
+    for row in &rows {
+        event_ids.push(row.get::<i32, _>("id"));
+        activities.push(Activity {
+            trades: row.get("trades"),
+            unique_traders: row.get("unique_traders"),
+            prob_move: row.get("prob_move"),
+            stake_per_hour: row.get::<f64, _>("stake") / WINDOW_HOURS as f64,
+        });
+    }
+    let heats: Vec<f64> = activities.iter().map(Activity::heat).collect();
+    let trades: Vec<i64> = activities.iter().map(|a| a.trades).collect();
+    let traders: Vec<i64> = activities.iter().map(|a| a.unique_traders).collect();
+    let moves: Vec<f64> = activities.iter().map(|a| a.prob_move).collect();
+    let stakes: Vec<f64> = activities.iter().map(|a| a.stake_per_hour).collect();
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM market_heat")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        r#"
+        INSERT INTO market_heat
+            (event_id, heat, trades_24h, unique_traders_24h, prob_move_24h, stake_per_hour)
+        SELECT * FROM UNNEST($1::integer[], $2::float8[], $3::bigint[], $4::bigint[],
+                             $5::float8[], $6::float8[])
+        "#,
+    )
+    .bind(&event_ids)
+    .bind(&heats)
+    .bind(&trades)
+    .bind(&traders)
+    .bind(&moves)
+    .bind(&stakes)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(event_ids.len())
+}
+
+/// The hottest markets still open as of the last refresh, optionally in
+/// one category. Markets with no activity in the window are left out.
+pub async fn trending(
+    pool: &PgPool,
+    limit: i64,
+    category: Option<&str>,
+) -> Result<Vec<TrendingMarket>> {
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("limit must be between 1 and {}", MAX_LIMIT),
+        ));
+    }
+    ensure_market_heat_table(pool).await?;
+    Ok(sqlx::query_as(
+        r#"
+        SELECT e.id AS event_id, e.title, e.category,
+               COALESCE(e.event_type, 'binary') AS event_type,
+               e.market_prob::float8 AS market_prob,
+               h.heat, h.trades_24h, h.unique_traders_24h, h.prob_move_24h,
+               h.stake_per_hour, h.computed_at
+        FROM market_heat h
+        JOIN events e ON e.id = h.event_id
+        WHERE h.heat > 0
+          AND e.outcome IS NULL
+          AND e.closed_at IS NULL
+          AND e.hidden_at IS NULL
+          AND ($2::text IS NULL OR e.category = $2)
+        ORDER BY h.heat DESC, e.id
+        LIMIT $1
+        "#,
+    )
+    .bind(limit)
+    .bind(category)
+    .fetch_all(pool)
+    .await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_crowd_outweighs_one_whale() {
+        let whale = Activity {
+            trades: 1,
+            unique_traders: 1,
+            prob_move: 0.05,
+            stake_per_hour: 5000.0 / 24.0,
+        };
+        let crowd = Activity {
+            trades: 30,
+            unique_traders: 20,
+            prob_move: 0.05,
+            stake_per_hour: 300.0 / 24.0,
+        };
+        assert!(crowd.heat() > whale.heat());
+        assert_eq!(Activity::default().heat(), 0.0);
+
+        let swing = Activity {
+            prob_move: 0.25,
+            ..crowd
+        };
+        assert!((swing.heat() - crowd.heat() - 2.0).abs() < 1e-12);
+    }
+}
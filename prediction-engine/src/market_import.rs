@@ -8,6 +8,11 @@ use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use std::env;
 
+use crate::api_error::{coded, ErrorCode};
+use crate::event_metadata;
+use crate::jobs::JobProgress;
+use crate::liquidity_recommendations;
+
 #[derive(Debug, Clone)]
 pub struct ImportedMarket {
     pub source: String,
@@ -15,19 +20,28 @@ pub struct ImportedMarket {
     pub external_url: String,
     pub title: String,
     pub description: String,
+    // How the provider says the question resolves, and its fine print,
+    // where it publishes them separately from the description.
+    pub resolution_criteria: Option<String>,
+    pub fine_print: Option<String>,
     pub close_time: Option<DateTime<Utc>>,
     pub category: String,
     pub event_type: String,
     pub status: String,
     pub outcomes: Vec<ImportedOutcome>,
-    // Numeric range metadata (Phase B consumes these to seed 50-bin LMSR
-    // markets); populated only for numeric questions, defaulted elsewhere.
+    // Numeric range metadata, recorded on the forecast-only event (and the
+    // shape `seed_numeric_bins_if_missing` bins); populated only for numeric
+    // questions, defaulted elsewhere.
     pub numeric_range_min: Option<f64>,
     pub numeric_range_max: Option<f64>,
     pub numeric_zero_point: Option<f64>,
     pub numeric_open_lower: bool,
     pub numeric_open_upper: bool,
     pub numeric_unit: Option<String>,
+    // Provider's own current forecast for YES (community prediction /
+    // last price), binary only; compared against market_prob in
+    // source_status.
+    pub community_prob: Option<f64>,
 }
 
 #[derive(Debug, Clone)]
@@ -51,6 +65,83 @@ pub struct ImportRunStats {
     pub errors: Vec<String>,
 }
 
+/// Dry-run counterpart of `ImportRunStats`: what a sync would do, without writing.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportPreview {
+    pub provider: String,
+    pub fetched_count: i32,
+    pub would_create_count: i32,
+    pub would_merge_count: i32,
+    pub would_link_count: i32,
+    /// Links that would also change the existing event (metadata backfill or
+    /// outcome seeding); a subset of `would_link_count`.
+    pub would_update_count: i32,
+    pub would_exclude_count: i32,
+    pub error_count: i32,
+    pub errors: Vec<String>,
+    pub create_samples: Vec<ImportPreviewSample>,
+    pub merge_samples: Vec<ImportPreviewSample>,
+    pub link_samples: Vec<ImportPreviewSample>,
+    pub update_samples: Vec<ImportPreviewSample>,
+    pub exclude_samples: Vec<ImportPreviewSample>,
+}
+
+impl ImportPreview {
+    pub fn new(provider: &str) -> Self {
+        Self {
+            provider: provider.to_string(),
+            fetched_count: 0,
+            would_create_count: 0,
+            would_merge_count: 0,
+            would_link_count: 0,
+            would_update_count: 0,
+            would_exclude_count: 0,
+            error_count: 0,
+            errors: Vec::new(),
+            create_samples: Vec::new(),
+            merge_samples: Vec::new(),
+            link_samples: Vec::new(),
+            update_samples: Vec::new(),
+            exclude_samples: Vec::new(),
+        }
+    }
+
+    pub(crate) fn record_error(&mut self, err: &anyhow::Error) {
+        self.error_count += 1;
+        if self.errors.len() < 25 {
+            self.errors.push(err.to_string());
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportPreviewSample {
+    pub external_id: String,
+    pub title: String,
+    pub close_time: Option<DateTime<Utc>>,
+    /// Existing event the market would be merged into / linked to.
+    pub event_id: Option<i32>,
+}
+
+const PREVIEW_SAMPLE_LIMIT: usize = 10;
+
+pub(crate) fn push_sample(samples: &mut Vec<ImportPreviewSample>, sample: ImportPreviewSample) {
+    if samples.len() < PREVIEW_SAMPLE_LIMIT {
+        samples.push(sample);
+    }
+}
+
+impl ImportPreviewSample {
+    pub(crate) fn of(market: &ImportedMarket, event_id: Option<i32>) -> Self {
+        Self {
+            external_id: market.external_id.clone(),
+            title: market.title.clone(),
+            close_time: market.close_time,
+            event_id,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum ImportProvider {
     Metaculus,
@@ -93,17 +184,119 @@ impl TryFrom<&str> for ImportProvider {
     }
 }
 
+/// Trade guard error for events flagged `forecast_only`.
+pub const ERR_FORECAST_ONLY: &str = "Market is forecast-only";
+
+/// How the importer treats an incoming question, decided from its type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ImportDisposition {
+    /// Tradeable: binary, multiple choice, or numeric with a usable range
+    /// (seeded as bucketed LMSR bins).
+    Market,
+    /// Stored as an event people can forecast on, flagged
+    /// `events.forecast_only` so no trade path accepts it, with no outcomes
+    /// or liquidity seeded. Date questions, and numeric questions without a
+    /// range the bins can be cut from, import this way.
+    ForecastOnly,
+    /// Not imported; the reason is logged and the market counts as excluded.
+    Skip(String),
+}
+
+pub(crate) fn classify_import(market: &ImportedMarket) -> ImportDisposition {
+    let raw = market.event_type.trim().to_lowercase();
+    if raw.contains("group") || raw.contains("conditional") {
+        return ImportDisposition::Skip(format!("unsupported question type {}", raw));
+    }
+    match normalize_event_type(&market.event_type).as_str() {
+        "multiple_choice" if market.outcomes.len() < 2 => {
+            ImportDisposition::Skip("multiple_choice question with fewer than 2 options".to_string())
+        }
+        "numeric" if has_bucketable_range(market) => ImportDisposition::Market,
+        "numeric" | "date" => ImportDisposition::ForecastOnly,
+        _ => ImportDisposition::Market,
+    }
+}
+
+/// Whether `seed_numeric_bins_if_missing` can cut bins from the question's
+/// range: both bounds present and a transform shape it accepts.
+fn has_bucketable_range(market: &ImportedMarket) -> bool {
+    match (market.numeric_range_min, market.numeric_range_max) {
+        (Some(range_min), Some(range_max)) => crate::numeric_transform::NumericTransform {
+            range_min,
+            range_max,
+            zero_point: market.numeric_zero_point,
+        }
+        .validate()
+        .is_ok(),
+        _ => false,
+    }
+}
+
+/// Extra details lines recording the import mode and any captured range.
+pub(crate) fn import_metadata_lines(market: &ImportedMarket, disposition: &ImportDisposition) -> String {
+    let mut lines = String::new();
+    if *disposition == ImportDisposition::ForecastOnly {
+        lines.push_str("\nImport mode: forecast-only");
+    }
+    if let (Some(min), Some(max)) = (market.numeric_range_min, market.numeric_range_max) {
+        lines.push_str(&format!("\nRange: {} to {}", min, max));
+        if let Some(unit) = &market.numeric_unit {
+            lines.push_str(&format!(" {}", unit));
+        }
+        if let Some(zero_point) = market.numeric_zero_point {
+            lines.push_str(&format!(" (log scale, zero point {})", zero_point));
+        }
+        if market.numeric_open_lower || market.numeric_open_upper {
+            lines.push_str(&format!(
+                "\nOpen bounds: lower={}, upper={}",
+                market.numeric_open_lower, market.numeric_open_upper
+            ));
+        }
+    }
+    lines
+}
+
+pub async fn ensure_forecast_only_column(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        "ALTER TABLE events ADD COLUMN IF NOT EXISTS forecast_only BOOLEAN NOT NULL DEFAULT FALSE",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Trade guard: fails with ERR_FORECAST_ONLY on a forecast-only event.
+pub async fn ensure_tradeable(conn: &mut sqlx::PgConnection, event_id: i32) -> Result<()> {
+    let forecast_only: Option<bool> =
+        sqlx::query_scalar("SELECT forecast_only FROM events WHERE id = $1")
+            .bind(event_id)
+            .fetch_optional(conn)
+            .await?;
+    if forecast_only == Some(true) {
+        return Err(coded(ErrorCode::MarketForecastOnly, ERR_FORECAST_ONLY));
+    }
+    Ok(())
+}
+
 pub trait MarketImportProvider {
     fn source_name(&self) -> &'static str;
 }
 
-pub async fn sync_all_markets(pool: &PgPool, full: bool) -> Result<Vec<ImportRunStats>> {
+/// Syncs every enabled provider in turn, reporting each one as a stage.
+pub async fn sync_all_markets(
+    pool: &PgPool,
+    full: bool,
+    progress: &JobProgress,
+) -> Result<Vec<ImportRunStats>> {
     ensure_import_tables(pool).await?;
-    let mut results = Vec::new();
-    for provider in ImportProvider::all()
+    let providers: Vec<ImportProvider> = ImportProvider::all()
         .into_iter()
         .filter(|provider| provider_enabled_for_sync_all(*provider))
-    {
+        .collect();
+    let total = Some(providers.len() as u64);
+    let mut results = Vec::new();
+    for provider in providers {
+        progress.progress(provider.as_str(), results.len() as u64, total);
         match sync_provider(pool, provider, full).await {
             Ok(stats) => results.push(stats),
             Err(err) => {
@@ -122,6 +315,7 @@ pub async fn sync_all_markets(pool: &PgPool, full: bool) -> Result<Vec<ImportRun
     }
     // Nightly piggyback: pull provider resolutions for past-close events
     // (imports only fetch status=open, so outcomes never arrive otherwise).
+    progress.progress("resolutions", results.len() as u64, total);
     if let Err(err) = crate::resolution_sync::sync_resolutions(pool).await {
         println!("\u{26a0}\u{fe0f} Resolution sync failed: {}", err);
     }
@@ -134,6 +328,21 @@ pub async fn sync_provider_named(pool: &PgPool, provider: &str, full: bool) -> R
     sync_provider(pool, ImportProvider::try_from(provider)?, full).await
 }
 
+pub async fn preview_all_markets(pool: &PgPool, full: bool) -> Result<Vec<ImportPreview>> {
+    let mut results = Vec::new();
+    for provider in ImportProvider::all()
+        .into_iter()
+        .filter(|provider| provider_enabled_for_sync_all(*provider))
+    {
+        results.push(preview_provider(pool, provider, full).await?);
+    }
+    Ok(results)
+}
+
+pub async fn preview_provider_named(pool: &PgPool, provider: &str, full: bool) -> Result<ImportPreview> {
+    preview_provider(pool, ImportProvider::try_from(provider)?, full).await
+}
+
 pub async fn get_recent_import_runs(pool: &PgPool, limit: i64) -> Result<Vec<Value>> {
     ensure_import_tables(pool).await?;
     let limit = limit.clamp(1, 200);
@@ -184,14 +393,7 @@ async fn sync_provider(pool: &PgPool, provider: ImportProvider, full: bool) -> R
         errors: Vec::new(),
     };
 
-    let markets = match provider {
-        ImportProvider::Metaculus => crate::metaculus::fetch_open_markets(provider_fetch_limit(provider, full)).await,
-        ImportProvider::Manifold => fetch_manifold_markets(provider_fetch_limit(provider, full)).await,
-        ImportProvider::Polymarket => fetch_polymarket_markets(provider_fetch_limit(provider, full)).await,
-        ImportProvider::Kalshi => fetch_kalshi_markets(provider_fetch_limit(provider, full)).await,
-    };
-
-    let markets = match markets {
+    let markets = match fetch_provider_markets(Some(pool), provider, full).await {
         Ok(items) => items,
         Err(err) => {
             stats.error_count += 1;
@@ -201,11 +403,38 @@ async fn sync_provider(pool: &PgPool, provider: ImportProvider, full: bool) -> R
         }
     };
 
-    stats.fetched_count = markets.len() as i32;
+    import_markets(pool, markets, &mut stats).await?;
+
+    let success = stats.error_count == 0;
+    write_import_run(pool, &stats, started_at, success).await?;
+    crate::webhooks::emit(
+        pool,
+        crate::webhooks::SYNC_COMPLETED,
+        json!({ "success": success, "full": full, "stats": &stats }),
+    );
+    Ok(stats)
+}
+
+/// Persists already-fetched markets into `stats`: skips, links, merges and
+/// creates, as `preview_markets` would report them.
+pub(crate) async fn import_markets(
+    pool: &PgPool,
+    markets: Vec<ImportedMarket>,
+    stats: &mut ImportRunStats,
+) -> Result<()> {
+    stats.fetched_count += markets.len() as i32;
     let has_pgvector = has_pgvector_extension(pool).await.unwrap_or(false);
     let topic_id = ensure_external_import_topic(pool).await?;
 
     for market in markets {
+        if let ImportDisposition::Skip(reason) = classify_import(&market) {
+            println!(
+                "⏭️ Skipping {} market {} ({}): {}",
+                market.source, market.external_id, reason, market.title
+            );
+            stats.excluded_count += 1;
+            continue;
+        }
         match upsert_market(pool, topic_id, &market, has_pgvector).await {
             Ok(PersistOutcome::LinkedExisting) => {
                 stats.linked_count += 1;
@@ -213,8 +442,20 @@ async fn sync_provider(pool: &PgPool, provider: ImportProvider, full: bool) -> R
             Ok(PersistOutcome::Merged) => {
                 stats.merged_count += 1;
             }
-            Ok(PersistOutcome::Created) => {
+            Ok(PersistOutcome::Created(event_id)) => {
                 stats.created_count += 1;
+                crate::webhooks::emit(
+                    pool,
+                    crate::webhooks::MARKET_CREATED,
+                    json!({
+                        "event_id": event_id,
+                        "source": market.source,
+                        "external_id": market.external_id,
+                        "title": market.title,
+                        "event_type": normalize_event_type(&market.event_type),
+                        "close_time": market.close_time,
+                    }),
+                );
             }
             Err(err) => {
                 stats.error_count += 1;
@@ -224,13 +465,140 @@ async fn sync_provider(pool: &PgPool, provider: ImportProvider, full: bool) -> R
             }
         }
     }
+    Ok(())
+}
 
-    let success = stats.error_count == 0;
-    write_import_run(pool, &stats, started_at, success).await?;
-    Ok(stats)
+/// Runs the same fetch + dedup classification as `sync_provider` but writes
+/// nothing: no events, mappings, outcomes or run rows, no DDL, and Metaculus
+/// requests are counted against the in-process budget rather than the
+/// persisted one.
+async fn preview_provider(pool: &PgPool, provider: ImportProvider, full: bool) -> Result<ImportPreview> {
+    match fetch_provider_markets(None, provider, full).await {
+        Ok(markets) => preview_markets(pool, provider.as_str(), markets).await,
+        Err(err) => {
+            let mut preview = ImportPreview::new(provider.as_str());
+            preview.record_error(&err);
+            Ok(preview)
+        }
+    }
+}
+
+/// Classifies already-fetched markets the way `upsert_market` would, reading
+/// only. Before the first sync has created the mapping table no market can
+/// link by provider ID, so that lookup is skipped rather than the table made.
+pub(crate) async fn preview_markets(
+    pool: &PgPool,
+    provider: &str,
+    markets: Vec<ImportedMarket>,
+) -> Result<ImportPreview> {
+    let mut preview = ImportPreview::new(provider);
+    preview.fetched_count = markets.len() as i32;
+    let has_pgvector = has_pgvector_extension(pool).await.unwrap_or(false);
+    let has_mappings: bool =
+        sqlx::query_scalar("SELECT to_regclass('event_external_sources') IS NOT NULL")
+            .fetch_one(pool)
+            .await?;
+
+    for market in markets {
+        if let ImportDisposition::Skip(_) = classify_import(&market) {
+            preview.would_exclude_count += 1;
+            push_sample(&mut preview.exclude_samples, ImportPreviewSample::of(&market, None));
+            continue;
+        }
+        let plan = match plan_market(pool, &market, has_pgvector, has_mappings).await {
+            Ok((plan, _)) => plan,
+            Err(err) => {
+                preview.record_error(&err);
+                continue;
+            }
+        };
+        match plan {
+            MarketPlan::Link(event_id) => {
+                preview.would_link_count += 1;
+                let sample = ImportPreviewSample::of(&market, Some(event_id));
+                match link_would_update(pool, event_id, &market).await {
+                    Ok(true) => {
+                        preview.would_update_count += 1;
+                        push_sample(&mut preview.update_samples, sample.clone());
+                    }
+                    Ok(false) => {}
+                    Err(err) => preview.record_error(&err),
+                }
+                push_sample(&mut preview.link_samples, sample);
+            }
+            MarketPlan::Merge(event_id) => {
+                preview.would_merge_count += 1;
+                push_sample(
+                    &mut preview.merge_samples,
+                    ImportPreviewSample::of(&market, Some(event_id)),
+                );
+            }
+            MarketPlan::Create => {
+                preview.would_create_count += 1;
+                push_sample(&mut preview.create_samples, ImportPreviewSample::of(&market, None));
+            }
+        }
+    }
+
+    Ok(preview)
 }
 
-async fn ensure_import_tables(pool: &PgPool) -> Result<()> {
+/// Whether linking `market` to `event_id` would change the event: a
+/// metadata column `event_metadata::record_import` would backfill, or
+/// multiple-choice outcomes `seed_outcomes_if_missing` would seed.
+async fn link_would_update(pool: &PgPool, event_id: i32, market: &ImportedMarket) -> Result<bool> {
+    let metadata = event_metadata::EventMetadata::from_import(market);
+    let Some(row) = sqlx::query(
+        r#"
+        SELECT outcome,
+               (resolution_criteria IS NULL AND $2::text IS NOT NULL)
+               OR (fine_print IS NULL AND $3::text IS NOT NULL)
+               OR (value_unit IS NULL AND $4::text IS NOT NULL)
+               OR (value_lower_bound IS NULL AND $5::float8 IS NOT NULL)
+               OR (value_upper_bound IS NULL AND $6::float8 IS NOT NULL)
+               OR (source_url IS NULL AND $7::text IS NOT NULL) AS backfills,
+               (SELECT COUNT(*) FROM event_outcomes
+                WHERE event_id = events.id AND is_active = TRUE) AS active_outcomes
+        FROM events
+        WHERE id = $1
+        "#,
+    )
+    .bind(event_id)
+    .bind(&metadata.resolution_criteria)
+    .bind(&metadata.fine_print)
+    .bind(&metadata.value_unit)
+    .bind(metadata.value_lower_bound)
+    .bind(metadata.value_upper_bound)
+    .bind(&metadata.source_url)
+    .fetch_optional(pool)
+    .await?
+    else {
+        return Ok(false);
+    };
+    let seeds_outcomes = normalize_event_type(&market.event_type) == "multiple_choice"
+        && market.outcomes.len() >= 2
+        && row.get::<i64, _>("active_outcomes") < 2
+        && row.get::<Option<String>, _>("outcome").is_none();
+    Ok(row.get::<bool, _>("backfills") || seeds_outcomes)
+}
+
+/// `budget_pool`, when given, is where Metaculus requests are counted
+/// against the daily budget.
+async fn fetch_provider_markets(
+    budget_pool: Option<&PgPool>,
+    provider: ImportProvider,
+    full: bool,
+) -> Result<Vec<ImportedMarket>> {
+    let limit = provider_fetch_limit(provider, full);
+    match provider {
+        ImportProvider::Metaculus => crate::metaculus::fetch_open_markets(budget_pool, limit).await,
+        ImportProvider::Manifold => fetch_manifold_markets(limit).await,
+        ImportProvider::Polymarket => fetch_polymarket_markets(limit).await,
+        ImportProvider::Kalshi => fetch_kalshi_markets(limit).await,
+    }
+}
+
+pub(crate) async fn ensure_import_tables(pool: &PgPool) -> Result<()> {
     sqlx::query(
         r#"
         CREATE TABLE IF NOT EXISTS event_external_sources (
@@ -307,7 +675,7 @@ async fn write_import_run(
 enum PersistOutcome {
     LinkedExisting,
     Merged,
-    Created,
+    Created(i32),
 }
 
 #[derive(Debug, Clone)]
@@ -319,23 +687,31 @@ struct Candidate {
     text_sim: f64,
 }
 
-async fn upsert_market(
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MarketPlan {
+    Link(i32),
+    Merge(i32),
+    Create,
+}
+
+/// Read-only dedup decision for an incoming market. Returns the embedding
+/// computed along the way so the create path does not have to re-embed.
+pub(crate) async fn plan_market(
     pool: &PgPool,
-    topic_id: i32,
     market: &ImportedMarket,
     has_pgvector: bool,
-) -> Result<PersistOutcome> {
+    has_mappings: bool,
+) -> Result<(MarketPlan, Option<Vec<f64>>)> {
     // Fast idempotent path by exact provider ID.
-    if let Some(existing_event_id) =
-        find_event_by_source_id(pool, &market.source, &market.external_id).await?
-    {
-        upsert_source_mapping(pool, existing_event_id, market).await?;
-        seed_outcomes_if_missing(pool, existing_event_id, market).await?;
-        return Ok(PersistOutcome::LinkedExisting);
+    if has_mappings {
+        if let Some(existing_event_id) =
+            find_event_by_source_id(pool, &market.source, &market.external_id).await?
+        {
+            return Ok((MarketPlan::Link(existing_event_id), None));
+        }
     }
 
     let normalized_text = normalized_market_text(market);
-    let event_type = normalize_event_type(&market.event_type);
 
     let mut maybe_embedding: Option<Vec<f64>> = None;
     if has_pgvector {
@@ -361,11 +737,41 @@ async fn upsert_market(
     if let Some(best) = candidates.first() {
         let score = dedup_score(market, best);
         if score >= dedup_threshold() && is_merge_semantically_compatible(market, best) {
-            upsert_source_mapping(pool, best.event_id, market).await?;
+            return Ok((MarketPlan::Merge(best.event_id), maybe_embedding));
+        }
+    }
+
+    Ok((MarketPlan::Create, maybe_embedding))
+}
+
+async fn upsert_market(
+    pool: &PgPool,
+    topic_id: i32,
+    market: &ImportedMarket,
+    has_pgvector: bool,
+) -> Result<PersistOutcome> {
+    let (plan, mut maybe_embedding) = plan_market(pool, market, has_pgvector, true).await?;
+    let forecast_only = classify_import(market) == ImportDisposition::ForecastOnly;
+    match plan {
+        MarketPlan::Link(existing_event_id) => {
+            upsert_source_mapping(pool, existing_event_id, market).await?;
+            if !forecast_only {
+                seed_outcomes_if_missing(pool, existing_event_id, market).await?;
+            }
+            // Backfills events imported before metadata had columns
+            event_metadata::record_import(pool, existing_event_id, market).await?;
+            return Ok(PersistOutcome::LinkedExisting);
+        }
+        MarketPlan::Merge(event_id) => {
+            upsert_source_mapping(pool, event_id, market).await?;
             return Ok(PersistOutcome::Merged);
         }
+        MarketPlan::Create => {}
     }
 
+    let normalized_text = normalized_market_text(market);
+    let event_type = normalize_event_type(&market.event_type);
+
     let fallback_close = Utc::now() + Duration::days(90);
     let close_time = market.close_time.unwrap_or(fallback_close).naive_utc();
     let category = truncate(&market.category, 100);
@@ -382,11 +788,18 @@ async fn upsert_market(
         &category,
         &event_type,
         &domain,
+        forecast_only,
     )
     .await?;
+    if !forecast_only {
+        liquidity_recommendations::apply_to_new_market(pool, inserted_event_id).await?;
+    }
 
     upsert_source_mapping(pool, inserted_event_id, market).await?;
-    seed_outcomes_if_missing(pool, inserted_event_id, market).await?;
+    if !forecast_only {
+        seed_outcomes_if_missing(pool, inserted_event_id, market).await?;
+    }
+    event_metadata::record_import(pool, inserted_event_id, market).await?;
 
     if has_pgvector {
         if maybe_embedding.is_none() {
@@ -404,10 +817,10 @@ async fn upsert_market(
         }
     }
 
-    Ok(PersistOutcome::Created)
+    Ok(PersistOutcome::Created(inserted_event_id))
 }
 
-async fn seed_outcomes_if_missing(
+pub(crate) async fn seed_outcomes_if_missing(
     pool: &PgPool,
     event_id: i32,
     market: &ImportedMarket,
@@ -590,6 +1003,8 @@ pub(crate) async fn seed_numeric_bins_if_missing(
 
     let mut tx = pool.begin().await?;
 
+    // Numeric questions imported before they could be traded were flagged
+    // forecast-only; seeding their bins is what makes them markets.
     sqlx::query(
         r#"
         UPDATE events
@@ -598,6 +1013,7 @@ pub(crate) async fn seed_numeric_bins_if_missing(
             market_prob = $1,
             q_yes = 0.0,
             q_no = 0.0,
+            forecast_only = FALSE,
             updated_at = NOW()
         WHERE id = $2
         "#,
@@ -742,7 +1158,8 @@ async fn collect_candidates(
     if !search_query.is_empty() {
         let rows = sqlx::query(
             r#"
-            SELECT id, title, COALESCE(details, '') AS details, event_type, closing_date,
+            SELECT id, title, COALESCE(details, '') AS details, event_type,
+                   closing_date::timestamptz AS closing_date,
                    ts_rank(search_vector, websearch_to_tsquery('english', $1)) AS text_rank
             FROM events
             WHERE search_vector @@ websearch_to_tsquery('english', $1)
@@ -756,8 +1173,7 @@ async fn collect_candidates(
 
         for row in rows {
             let event_id: i32 = row.get("id");
-            let close_naive: NaiveDateTime = row.get("closing_date");
-            let close_time = DateTime::<Utc>::from_naive_utc_and_offset(close_naive, Utc);
+            let close_time: DateTime<Utc> = row.get("closing_date");
             let title: String = row.get("title");
             let details: String = row.get("details");
             let text_rank: f32 = row.get::<f32, _>("text_rank");
@@ -783,7 +1199,8 @@ async fn collect_candidates(
             let vector_literal = embedding_to_vector_literal(embedding);
             let rows = sqlx::query(
                 r#"
-                SELECT id, title, COALESCE(details, '') AS details, event_type, closing_date,
+                SELECT id, title, COALESCE(details, '') AS details, event_type,
+                   closing_date::timestamptz AS closing_date,
                        (1 - (embedding <=> $1::vector)) AS embed_sim
                 FROM events
                 WHERE embedding IS NOT NULL
@@ -797,8 +1214,7 @@ async fn collect_candidates(
 
             for row in rows {
                 let event_id: i32 = row.get("id");
-                let close_naive: NaiveDateTime = row.get("closing_date");
-                let close_time = DateTime::<Utc>::from_naive_utc_and_offset(close_naive, Utc);
+                let close_time: DateTime<Utc> = row.get("closing_date");
                 let entry = map.entry(event_id).or_insert_with(|| Candidate {
                     event_id,
                     event_type: row.get("event_type"),
@@ -902,6 +1318,15 @@ async fn upsert_source_mapping(
         "category": market.category,
         "event_type": market.event_type,
         "status": market.status,
+        "community_prob": market.community_prob,
+        "numeric_range": {
+            "min": market.numeric_range_min,
+            "max": market.numeric_range_max,
+            "zero_point": market.numeric_zero_point,
+            "open_lower": market.numeric_open_lower,
+            "open_upper": market.numeric_open_upper,
+            "unit": market.numeric_unit,
+        },
         "outcomes": market.outcomes.iter().map(|o| json!({
             "key": o.key,
             "label": o.label,
@@ -941,11 +1366,12 @@ async fn insert_event(
     category: &str,
     event_type: &str,
     domain: &str,
+    forecast_only: bool,
 ) -> Result<i32> {
     let result = sqlx::query(
         r#"
-        INSERT INTO events (topic_id, title, details, closing_date, outcome, category, event_type, domain)
-        VALUES ($1, $2, $3, $4, NULL, $5, $6, $7)
+        INSERT INTO events (topic_id, title, details, closing_date, outcome, category, event_type, domain, forecast_only)
+        VALUES ($1, $2, $3, $4, NULL, $5, $6, $7, $8)
         RETURNING id
         "#,
     )
@@ -956,6 +1382,7 @@ async fn insert_event(
     .bind(category)
     .bind(event_type)
     .bind(domain)
+    .bind(forecast_only)
     .fetch_one(pool)
     .await;
 
@@ -964,8 +1391,8 @@ async fn insert_event(
         Err(_) => {
             let fallback = sqlx::query(
                 r#"
-                INSERT INTO events (topic_id, title, details, closing_date, outcome, category, event_type)
-                VALUES ($1, $2, $3, $4, NULL, $5, $6)
+                INSERT INTO events (topic_id, title, details, closing_date, outcome, category, event_type, forecast_only)
+                VALUES ($1, $2, $3, $4, NULL, $5, $6, $7)
                 RETURNING id
                 "#,
             )
@@ -975,6 +1402,7 @@ async fn insert_event(
             .bind(close_time)
             .bind(category)
             .bind(event_type)
+            .bind(forecast_only)
             .fetch_one(pool)
             .await?;
             Ok(fallback.get("id"))
@@ -1048,13 +1476,14 @@ fn text_similarity(a: &str, b: &str) -> f64 {
 
 fn market_details_blob(market: &ImportedMarket) -> String {
     format!(
-        "{}\n\nSource: {}\nExternal ID: {}\nExternal URL: {}\nCategory: {}\nType: {}",
+        "{}\n\nSource: {}\nExternal ID: {}\nExternal URL: {}\nCategory: {}\nType: {}{}",
         market.description,
         market.source,
         market.external_id,
         market.external_url,
         market.category,
-        market.event_type
+        market.event_type,
+        import_metadata_lines(market, &classify_import(market))
     )
 }
 
@@ -1092,14 +1521,18 @@ fn normalize_domain(category: &str) -> String {
     }
 }
 
-fn normalize_event_type(raw: &str) -> String {
+pub(crate) fn normalize_event_type(raw: &str) -> String {
     let value = raw.trim().to_lowercase();
     if value.contains("binary") || value == "yesno" || value == "yes_no" {
         "binary".to_string()
     } else if value.contains("multi") || value.contains("choice") || value.contains("free_response")
     {
         "multiple_choice".to_string()
-    } else if value.contains("numeric") || value.contains("number") || value.contains("scalar") {
+    } else if value.contains("numeric")
+        || value.contains("number")
+        || value.contains("scalar")
+        || value.contains("discrete")
+    {
         "numeric".to_string()
     } else if value.contains("date") || value.contains("time") {
         "date".to_string()
@@ -1267,6 +1700,11 @@ async fn fetch_manifold_markets(max_markets: Option<usize>) -> Result<Vec<Import
             );
             let event_type =
                 value_to_string(row.get("outcomeType")).unwrap_or_else(|| "binary".to_string());
+            let community_prob = if normalize_event_type(&event_type) == "binary" {
+                row.get("probability").and_then(|v| v.as_f64())
+            } else {
+                None
+            };
             let mut outcomes = parse_manifold_outcomes(row);
             if outcomes.len() < 2 && !normalize_event_type(&event_type).eq("binary") {
                 if let Ok(Some(detail)) = fetch_manifold_market_details(&client, &id).await {
@@ -1289,6 +1727,8 @@ async fn fetch_manifold_markets(max_markets: Option<usize>) -> Result<Vec<Import
                 external_url,
                 title,
                 description,
+                resolution_criteria: None,
+                fine_print: None,
                 close_time,
                 category,
                 event_type,
@@ -1300,6 +1740,7 @@ async fn fetch_manifold_markets(max_markets: Option<usize>) -> Result<Vec<Import
                 numeric_open_lower: false,
                 numeric_open_upper: false,
                 numeric_unit: None,
+                community_prob,
             });
         }
 
@@ -1353,8 +1794,9 @@ async fn fetch_polymarket_markets(max_markets: Option<usize>) -> Result<Vec<Impo
             let title = value_to_string(row.get("question"))
                 .or_else(|| value_to_string(row.get("title")))
                 .unwrap_or_else(|| "Untitled".to_string());
-            let description =
-                value_to_string(row.get("description")).unwrap_or_else(|| title.clone());
+            // Polymarket's description is the resolution rules
+            let resolution_criteria = value_to_string(row.get("description"));
+            let description = resolution_criteria.clone().unwrap_or_else(|| title.clone());
             let close_time = parse_datetime_value(
                 row.get("endDate")
                     .or_else(|| row.get("end_date"))
@@ -1373,6 +1815,13 @@ async fn fetch_polymarket_markets(max_markets: Option<usize>) -> Result<Vec<Impo
                 let slug = value_to_string(row.get("slug")).unwrap_or_else(|| id.clone());
                 format!("https://polymarket.com/event/{}", slug)
             });
+            // outcomePrices is a JSON-encoded string ordered like `outcomes`
+            // (["Yes", "No"]); the first entry is the YES price.
+            let community_prob = row
+                .get("outcomePrices")
+                .and_then(|v| v.as_str())
+                .and_then(|raw| serde_json::from_str::<Vec<String>>(raw).ok())
+                .and_then(|prices| prices.first().and_then(|p| p.parse::<f64>().ok()));
 
             output.push(ImportedMarket {
                 source: "polymarket".to_string(),
@@ -1380,6 +1829,8 @@ async fn fetch_polymarket_markets(max_markets: Option<usize>) -> Result<Vec<Impo
                 external_url,
                 title,
                 description,
+                resolution_criteria,
+                fine_print: None,
                 close_time,
                 category,
                 event_type: "binary".to_string(),
@@ -1391,6 +1842,7 @@ async fn fetch_polymarket_markets(max_markets: Option<usize>) -> Result<Vec<Impo
                 numeric_open_lower: false,
                 numeric_open_upper: false,
                 numeric_unit: None,
+                community_prob,
             });
         }
 
@@ -1468,6 +1920,12 @@ async fn fetch_kalshi_markets(max_markets: Option<usize>) -> Result<Vec<Imported
                 value_to_string(row.get("category")).unwrap_or_else(|| "general".to_string());
             let external_url = value_to_string(row.get("url"))
                 .unwrap_or_else(|| format!("https://kalshi.com/markets/{}", id));
+            // last_price is in cents; 0 means no trade yet.
+            let community_prob = row
+                .get("last_price")
+                .and_then(|v| v.as_f64())
+                .filter(|cents| *cents > 0.0)
+                .map(|cents| cents / 100.0);
 
             output.push(ImportedMarket {
                 source: "kalshi".to_string(),
@@ -1475,6 +1933,8 @@ async fn fetch_kalshi_markets(max_markets: Option<usize>) -> Result<Vec<Imported
                 external_url,
                 title,
                 description,
+                resolution_criteria: value_to_string(row.get("rules_primary")),
+                fine_print: value_to_string(row.get("rules_secondary")),
                 close_time,
                 category,
                 event_type: "binary".to_string(),
@@ -1486,6 +1946,7 @@ async fn fetch_kalshi_markets(max_markets: Option<usize>) -> Result<Vec<Imported
                 numeric_open_lower: false,
                 numeric_open_upper: false,
                 numeric_unit: None,
+                community_prob,
             });
         }
 
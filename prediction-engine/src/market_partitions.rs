@@ -0,0 +1,444 @@
+//! Monthly partitions for `market_updates`, by `created_at` in UTC months.
+//!
+//! `ensure_partitioned` converts the table once at startup (when
+//! `partition_market_updates` is on), keeping its rows, indexes and views;
+//! the primary key and unique indexes gain `created_at`, and foreign keys
+//! referencing it are dropped and reported. Partitions are named
+//! `market_updates_pYYYY_MM` with a default partition for anything outside
+//! them, which `maintain` drains as it creates the months ahead.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::{PgPool, Postgres, Row, Transaction};
+
+const DEFAULT_PARTITION: &str = "market_updates_default";
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Maintenance {
+    pub partitions_created: Vec<String>,
+    /// Rows moved out of the default partition.
+    pub rows_moved: u64,
+}
+
+/// A foreign key referencing `market_updates` that the conversion dropped.
+#[derive(Debug, Clone, Serialize)]
+pub struct DroppedForeignKey {
+    pub table: String,
+    pub constraint: String,
+    /// As `pg_get_constraintdef` gives it, to restore by hand if needed.
+    pub definition: String,
+}
+
+/// What converting `market_updates` changed beyond its layout.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Conversion {
+    /// Unique indexes and constraints rebuilt with `created_at` added.
+    pub widened_unique: Vec<String>,
+    pub dropped_foreign_keys: Vec<DroppedForeignKey>,
+}
+
+/// A unique index other than the primary key, and the constraint it backs.
+#[derive(sqlx::FromRow)]
+struct UniqueIndex {
+    name: String,
+    constraint_name: Option<String>,
+    method: String,
+    keys: Vec<String>,
+    included: Vec<String>,
+    predicate: Option<String>,
+}
+
+impl UniqueIndex {
+    /// The index or constraint on the partitioned table, with `created_at`
+    /// appended to the key if it isn't in it.
+    fn definition(&self) -> String {
+        let mut keys = self.keys.clone();
+        if !keys.iter().any(|key| key == "created_at") {
+            keys.push("created_at".to_string());
+        }
+        let included = if self.included.is_empty() {
+            String::new()
+        } else {
+            format!(" INCLUDE ({})", self.included.join(", "))
+        };
+        match &self.constraint_name {
+            Some(constraint) => format!(
+                "ALTER TABLE market_updates ADD CONSTRAINT \"{}\" UNIQUE ({}){}",
+                constraint,
+                keys.join(", "),
+                included
+            ),
+            None => format!(
+                "CREATE UNIQUE INDEX \"{}\" ON market_updates USING {} ({}){}{}",
+                self.name,
+                self.method,
+                keys.join(", "),
+                included,
+                self.predicate
+                    .as_ref()
+                    .map(|predicate| format!(" WHERE {}", predicate))
+                    .unwrap_or_default()
+            ),
+        }
+    }
+}
+
+/// The first day of the month `months` after the one `at` falls in.
+fn month_start(at: DateTime<Utc>, months: i32) -> NaiveDate {
+    let index = at.year() * 12 + at.month0() as i32 + months;
+    NaiveDate::from_ymd_opt(index.div_euclid(12), index.rem_euclid(12) as u32 + 1, 1)
+        .expect("first of the month is a valid date")
+}
+
+fn partition_name(month: NaiveDate) -> String {
+    format!("market_updates_p{}_{:02}", month.year(), month.month())
+}
+
+/// The UTC bounds of `month`, for `FOR VALUES FROM .. TO ..`.
+fn month_bounds(month: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    let start = Utc.from_utc_datetime(&month.and_hms_opt(0, 0, 0).unwrap());
+    let end = Utc.from_utc_datetime(&month_start(start, 1).and_hms_opt(0, 0, 0).unwrap());
+    (start, end)
+}
+
+pub async fn is_partitioned(pool: &PgPool) -> Result<bool> {
+    Ok(sqlx::query_scalar(
+        "SELECT COALESCE((SELECT relkind = 'p' FROM pg_class
+                          WHERE oid = to_regclass('market_updates')), false)",
+    )
+    .fetch_one(pool)
+    .await?)
+}
+
+async fn create_partition(tx: &mut Transaction<'_, Postgres>, month: NaiveDate) -> Result<()> {
+    let (start, end) = month_bounds(month);
+    sqlx::query(&format!(
+        "CREATE TABLE {} PARTITION OF market_updates FOR VALUES FROM ('{}') TO ('{}')",
+        partition_name(month),
+        start.to_rfc3339(),
+        end.to_rfc3339()
+    ))
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Converts `market_updates` to a partitioned table if it isn't one yet.
+/// Returns what the conversion changed, or None if there was nothing to do.
+pub async fn ensure_partitioned(pool: &PgPool, months_ahead: u32) -> Result<Option<Conversion>> {
+    if is_partitioned(pool).await? {
+        return Ok(None);
+    }
+    let mut tx = pool.begin().await?;
+    sqlx::query("LOCK TABLE market_updates IN ACCESS EXCLUSIVE MODE")
+        .execute(&mut *tx)
+        .await?;
+    // Captured before the rename so they still name market_updates
+    let index_defs: Vec<String> = sqlx::query_scalar(
+        "SELECT pg_get_indexdef(indexrelid) FROM pg_index
+         WHERE indrelid = 'market_updates'::regclass AND NOT indisprimary AND NOT indisunique",
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let unique: Vec<UniqueIndex> = sqlx::query_as(
+        "SELECT c.relname::text AS name, con.conname::text AS constraint_name,
+                am.amname::text AS method,
+                ARRAY(SELECT pg_get_indexdef(i.indexrelid, k, true)
+                      FROM generate_series(1, i.indnkeyatts) k ORDER BY k) AS keys,
+                ARRAY(SELECT pg_get_indexdef(i.indexrelid, k, true)
+                      FROM generate_series(i.indnkeyatts + 1, i.indnatts) k ORDER BY k)
+                    AS included,
+                pg_get_expr(i.indpred, i.indrelid) AS predicate
+         FROM pg_index i
+         JOIN pg_class c ON c.oid = i.indexrelid
+         JOIN pg_am am ON am.oid = c.relam
+         LEFT JOIN pg_constraint con ON con.conindid = i.indexrelid AND con.contype = 'u'
+         WHERE i.indrelid = 'market_updates'::regclass AND i.indisunique AND NOT i.indisprimary
+         ORDER BY c.relname",
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    // Neither can include the partition key, so neither could be kept
+    let blocking: Vec<String> = sqlx::query_scalar(
+        "SELECT c.relname::text FROM pg_index i JOIN pg_class c ON c.oid = i.indexrelid
+         WHERE i.indrelid = 'market_updates'::regclass AND i.indisunique
+           AND i.indexprs IS NOT NULL
+         UNION ALL
+         SELECT conname::text FROM pg_constraint
+         WHERE conrelid = 'market_updates'::regclass AND contype = 'x'
+         ORDER BY 1",
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    if !blocking.is_empty() {
+        return Err(anyhow!(
+            "market_updates can't be partitioned while it has unique expression indexes or exclusion constraints ({}); drop them or turn partition_market_updates off",
+            blocking.join(", ")
+        ));
+    }
+    let foreign_keys: Vec<(String, String)> = sqlx::query_as(
+        "SELECT conname::text, pg_get_constraintdef(oid) FROM pg_constraint
+         WHERE conrelid = 'market_updates'::regclass AND contype = 'f'",
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let sequence: Option<String> =
+        sqlx::query_scalar("SELECT pg_get_serial_sequence('market_updates', 'id')")
+            .fetch_one(&mut *tx)
+            .await?;
+    // Views over the table (market_summary, the archive view) are rebuilt on
+    // the new one. A view built on one of those would block the drop below
+    // and roll the conversion back rather than be lost.
+    let views: Vec<(String, String)> = sqlx::query_as(
+        "SELECT DISTINCT v.oid::regclass::text, pg_get_viewdef(v.oid)
+         FROM pg_depend d
+         JOIN pg_rewrite r ON r.oid = d.objid
+         JOIN pg_class v ON v.oid = r.ev_class
+         WHERE d.refobjid = 'market_updates'::regclass
+           AND v.oid <> 'market_updates'::regclass AND v.relkind = 'v'",
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let referencing: Vec<(String, String, String)> = sqlx::query_as(
+        "SELECT conrelid::regclass::text, conname::text, pg_get_constraintdef(oid)
+         FROM pg_constraint
+         WHERE confrelid = 'market_updates'::regclass AND contype = 'f'
+         ORDER BY 1, 2",
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    sqlx::query("UPDATE market_updates SET created_at = hold_until WHERE created_at IS NULL")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("ALTER TABLE market_updates RENAME TO market_updates_unpartitioned")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        "CREATE TABLE market_updates
+             (LIKE market_updates_unpartitioned INCLUDING DEFAULTS INCLUDING CONSTRAINTS)
+         PARTITION BY RANGE (created_at)",
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("ALTER TABLE market_updates ALTER COLUMN created_at SET NOT NULL")
+        .execute(&mut *tx)
+        .await?;
+    for (name, definition) in &foreign_keys {
+        sqlx::query(&format!(
+            "ALTER TABLE market_updates ADD CONSTRAINT \"{}\" {}",
+            name, definition
+        ))
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let oldest: Option<DateTime<Utc>> =
+        sqlx::query_scalar("SELECT MIN(created_at) FROM market_updates_unpartitioned")
+            .fetch_one(&mut *tx)
+            .await?;
+    let now = Utc::now();
+    let mut month = month_start(oldest.unwrap_or(now).min(now), 0);
+    let last = month_start(now, months_ahead as i32);
+    while month <= last {
+        create_partition(&mut tx, month).await?;
+        month = month_start(month_bounds(month).0, 1);
+    }
+    sqlx::query(&format!(
+        "CREATE TABLE {} PARTITION OF market_updates DEFAULT",
+        DEFAULT_PARTITION
+    ))
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("INSERT INTO market_updates SELECT * FROM market_updates_unpartitioned")
+        .execute(&mut *tx)
+        .await?;
+    if let Some(sequence) = &sequence {
+        sqlx::query(&format!(
+            "ALTER SEQUENCE {} OWNED BY market_updates.id",
+            sequence
+        ))
+        .execute(&mut *tx)
+        .await?;
+    }
+    for (view, _) in &views {
+        sqlx::query(&format!("DROP VIEW {}", view))
+            .execute(&mut *tx)
+            .await?;
+    }
+    for (table, constraint, _) in &referencing {
+        sqlx::query(&format!(
+            "ALTER TABLE {} DROP CONSTRAINT \"{}\"",
+            table, constraint
+        ))
+        .execute(&mut *tx)
+        .await?;
+    }
+    sqlx::query("DROP TABLE market_updates_unpartitioned")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("ALTER TABLE market_updates ADD PRIMARY KEY (id, created_at)")
+        .execute(&mut *tx)
+        .await?;
+    for definition in &index_defs {
+        sqlx::query(definition).execute(&mut *tx).await?;
+    }
+    for index in &unique {
+        sqlx::query(&index.definition()).execute(&mut *tx).await?;
+    }
+    for (view, definition) in &views {
+        sqlx::query(&format!("CREATE VIEW {} AS {}", view, definition))
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(Some(Conversion {
+        widened_unique: unique
+            .into_iter()
+            .filter(|index| !index.keys.iter().any(|key| key == "created_at"))
+            .map(|index| index.constraint_name.unwrap_or(index.name))
+            .collect(),
+        dropped_foreign_keys: referencing
+            .into_iter()
+            .map(|(table, constraint, definition)| DroppedForeignKey {
+                table,
+                constraint,
+                definition,
+            })
+            .collect(),
+    }))
+}
+
+/// Creates partitions for this month and `months_ahead` more, and for any
+/// month with rows in the default partition, moving those rows over.
+pub async fn maintain(pool: &PgPool, months_ahead: u32) -> Result<Maintenance> {
+    let mut report = Maintenance::default();
+    if !is_partitioned(pool).await? {
+        return Ok(report);
+    }
+    let now = Utc::now();
+    let mut months: Vec<NaiveDate> = (0..=months_ahead as i32)
+        .map(|ahead| month_start(now, ahead))
+        .collect();
+    let stray: Vec<DateTime<Utc>> = sqlx::query_scalar(&format!(
+        "SELECT DISTINCT date_trunc('month', created_at, 'UTC') FROM {}",
+        DEFAULT_PARTITION
+    ))
+    .fetch_all(pool)
+    .await?;
+    months.extend(stray.into_iter().map(|at| month_start(at, 0)));
+    months.sort_unstable();
+    months.dedup();
+
+    for month in months {
+        let name = partition_name(month);
+        let exists: bool = sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL")
+            .bind(&name)
+            .fetch_one(pool)
+            .await?;
+        if exists {
+            continue;
+        }
+        // A new partition can't be attached while the default partition
+        // holds rows for its range, so they move first
+        let (start, end) = month_bounds(month);
+        let mut tx = pool.begin().await?;
+        sqlx::query(&format!(
+            "CREATE TABLE {} (LIKE market_updates INCLUDING DEFAULTS INCLUDING CONSTRAINTS)",
+            name
+        ))
+        .execute(&mut *tx)
+        .await?;
+        let moved = sqlx::query(&format!(
+            "WITH moved AS (
+                 DELETE FROM {} WHERE created_at >= $1 AND created_at < $2 RETURNING *
+             )
+             INSERT INTO {} SELECT * FROM moved",
+            DEFAULT_PARTITION, name
+        ))
+        .bind(start)
+        .bind(end)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(&format!(
+            "ALTER TABLE market_updates ATTACH PARTITION {} FOR VALUES FROM ('{}') TO ('{}')",
+            name,
+            start.to_rfc3339(),
+            end.to_rfc3339()
+        ))
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        report.rows_moved += moved.rows_affected();
+        report.partitions_created.push(name);
+    }
+    Ok(report)
+}
+
+/// Each partition's bounds, estimated rows and size.
+pub async fn get_status(pool: &PgPool) -> Result<Value> {
+    let partitioned = is_partitioned(pool).await?;
+    let partitions: Vec<Value> = if partitioned {
+        sqlx::query(
+            "SELECT c.relname::text AS name,
+                    pg_get_expr(c.relpartbound, c.oid) AS bounds,
+                    GREATEST(c.reltuples, 0)::bigint AS estimated_rows,
+                    pg_total_relation_size(c.oid) AS bytes
+             FROM pg_inherits i
+             JOIN pg_class c ON c.oid = i.inhrelid
+             WHERE i.inhparent = 'market_updates'::regclass
+             ORDER BY c.relname",
+        )
+        .fetch_all(pool)
+        .await?
+        .iter()
+        .map(|row| {
+            json!({
+                "name": row.get::<String, _>("name"),
+                "bounds": row.get::<String, _>("bounds"),
+                "estimated_rows": row.get::<i64, _>("estimated_rows"),
+                "bytes": row.get::<i64, _>("bytes"),
+            })
+        })
+        .collect()
+    } else {
+        Vec::new()
+    };
+    Ok(json!({
+        "table": "market_updates",
+        "partitioned": partitioned,
+        "partitions": partitions,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn months_roll_over_year_ends() {
+        let at = Utc.with_ymd_and_hms(2026, 11, 20, 15, 0, 0).unwrap();
+        assert_eq!(
+            month_start(at, 0),
+            NaiveDate::from_ymd_opt(2026, 11, 1).unwrap()
+        );
+        assert_eq!(
+            month_start(at, 2),
+            NaiveDate::from_ymd_opt(2027, 1, 1).unwrap()
+        );
+        assert_eq!(
+            month_start(at, -11),
+            NaiveDate::from_ymd_opt(2025, 12, 1).unwrap()
+        );
+        let (start, end) = month_bounds(month_start(at, 1));
+        assert_eq!(start.to_rfc3339(), "2026-12-01T00:00:00+00:00");
+        assert_eq!(end.to_rfc3339(), "2027-01-01T00:00:00+00:00");
+        assert_eq!(
+            partition_name(month_start(at, 2)),
+            "market_updates_p2027_01"
+        );
+    }
+}
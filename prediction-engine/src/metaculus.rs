@@ -1,11 +1,27 @@
 // Metaculus API integration for fetching prediction questions
-use crate::market_import::{ImportedMarket, ImportedOutcome, MarketImportProvider};
+use crate::event_metadata;
+use crate::jobs::JobProgress;
+use crate::liquidity_recommendations;
+use crate::market_import::{
+    classify_import, import_metadata_lines, normalize_event_type, push_sample,
+    seed_outcomes_if_missing, ImportDisposition, ImportPreview, ImportPreviewSample,
+    ImportedMarket, ImportedOutcome, MarketImportProvider,
+};
 use anyhow::Result;
-use chrono::{DateTime, Utc};
-use reqwest::Client;
+use chrono::{DateTime, NaiveDate, Utc};
+use reqwest::header::HeaderMap;
+use reqwest::{Client, StatusCode};
 use serde::Deserialize;
+use serde_json::{json, Value};
 use sqlx::{PgPool, Row};
 use std::env;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+/// Job key for the resumable bulk import in `metaculus_import_progress`.
+const BULK_IMPORT_JOB: &str = "bulk_import";
+/// Attempts per page before a transient failure (429/5xx/network) is fatal.
+const MAX_REQUEST_ATTEMPTS: u32 = 4;
 
 // Metaculus API response structures for /api/posts/
 #[derive(Debug, Deserialize)]
@@ -14,11 +30,23 @@ struct MetaculusResponse {
     next: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, Default)]
 struct MetaculusPost {
+    #[serde(default)]
+    id: Option<i32>,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    status: Option<String>,
     #[serde(default)]
     categories: Vec<String>,
     question: Option<MetaculusQuestion>,
+    // Group and conditional posts carry their sub-questions here instead of
+    // in `question`; we only need to know they are present.
+    #[serde(default)]
+    group_of_questions: Option<Value>,
+    #[serde(default)]
+    conditional: Option<Value>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -42,6 +70,10 @@ struct MetaculusQuestion {
     status: String,
     #[serde(default)]
     description: Option<String>,
+    #[serde(default)]
+    resolution_criteria: Option<String>,
+    #[serde(default)]
+    fine_print: Option<String>,
     // multiple_choice: list of option labels, in API order.
     #[serde(default)]
     options: Option<Vec<String>>,
@@ -58,12 +90,123 @@ struct MetaculusQuestion {
     open_upper_bound: Option<bool>,
     #[serde(default)]
     unit: Option<String>,
+    // Community prediction; for binary questions
+    // aggregations.recency_weighted.latest.centers[0] is P(yes). Often null
+    // at our token's access level, like `resolution`.
+    #[serde(default)]
+    aggregations: Option<Value>,
+}
+
+/// Returned (wrapped in anyhow) when the configured daily request budget
+/// is spent; bulk import treats it as "pause and resume tomorrow".
+#[derive(Debug)]
+pub struct RequestBudgetExhausted {
+    pub budget: u32,
+}
+
+impl std::fmt::Display for RequestBudgetExhausted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Metaculus daily request budget of {} exhausted",
+            self.budget
+        )
+    }
+}
+
+impl std::error::Error for RequestBudgetExhausted {}
+
+/// Inter-request pacing that adapts to what the API tells us: it backs off
+/// on 429/5xx (honouring Retry-After), slows down when the rate-limit
+/// headers say we are close to the cap, and decays back towards the floor
+/// after clean responses.
+#[derive(Debug, Clone)]
+struct AdaptiveRateLimiter {
+    delay_ms: u64,
+    min_delay_ms: u64,
+    max_delay_ms: u64,
+    consecutive_failures: u32,
+}
+
+impl AdaptiveRateLimiter {
+    fn from_env() -> Self {
+        let min_delay_ms = env::var("METACULUS_MIN_DELAY_MS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(750)
+            .clamp(50, 60_000);
+        let max_delay_ms = env::var("METACULUS_MAX_DELAY_MS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(120_000)
+            .max(min_delay_ms);
+        Self::new(min_delay_ms, max_delay_ms)
+    }
+
+    fn new(min_delay_ms: u64, max_delay_ms: u64) -> Self {
+        Self {
+            delay_ms: min_delay_ms,
+            min_delay_ms,
+            max_delay_ms,
+            consecutive_failures: 0,
+        }
+    }
+
+    fn delay(&self) -> Duration {
+        Duration::from_millis(self.delay_ms)
+    }
+
+    fn on_success(&mut self, headers: &HeaderMap) {
+        self.consecutive_failures = 0;
+        // Decay 20% per clean response towards the floor.
+        let mut next = (self.delay_ms * 4 / 5).max(self.min_delay_ms);
+        let remaining = header_u64(headers, "x-ratelimit-remaining");
+        let reset_secs = header_u64(headers, "x-ratelimit-reset");
+        if let (Some(remaining), Some(reset_secs)) = (remaining, reset_secs) {
+            // Spread what is left of the window over the time until reset.
+            let spread = reset_secs.saturating_mul(1_000) / remaining.max(1);
+            next = next.max(spread);
+        } else if remaining.is_some_and(|r| r < 5) {
+            next = next.max(self.delay_ms.saturating_mul(2));
+        }
+        self.delay_ms = next.min(self.max_delay_ms);
+    }
+
+    fn on_failure(&mut self, retry_after: Option<Duration>) {
+        self.consecutive_failures += 1;
+        let doubled = self.delay_ms.saturating_mul(2);
+        let hinted = retry_after.map(|d| d.as_millis() as u64).unwrap_or(0);
+        self.delay_ms = doubled
+            .max(hinted)
+            .clamp(self.min_delay_ms, self.max_delay_ms);
+    }
+}
+
+fn header_u64(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .filter(|v| v.is_finite() && *v >= 0.0)
+        .map(|v| v.ceil() as u64)
+}
+
+/// 0 means unlimited.
+fn daily_request_budget() -> u32 {
+    env::var("METACULUS_DAILY_REQUEST_BUDGET")
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(0)
 }
 
 #[derive(Clone)]
 pub struct MetaculusClient {
     client: Client,
     base_url: String,
+    limiter: Arc<Mutex<AdaptiveRateLimiter>>,
+    // When set, the daily request budget is counted in Postgres so it holds
+    // across restarts; otherwise an in-process counter is used.
+    pool: Option<PgPool>,
 }
 
 impl MarketImportProvider for MetaculusClient {
@@ -78,6 +221,66 @@ impl MetaculusClient {
         Self {
             client: Client::new(),
             base_url: "https://www.metaculus.com/api".to_string(),
+            limiter: Arc::new(Mutex::new(AdaptiveRateLimiter::from_env())),
+            pool: None,
+        }
+    }
+
+    pub fn with_pool(mut self, pool: &PgPool) -> Self {
+        self.pool = Some(pool.clone());
+        self
+    }
+
+    fn pacing_delay(&self) -> Duration {
+        self.limiter.lock().expect("rate limiter poisoned").delay()
+    }
+
+    // Count one request against today's budget, failing once it is spent.
+    async fn reserve_request(&self) -> Result<()> {
+        let budget = daily_request_budget();
+        if budget == 0 {
+            return Ok(());
+        }
+        let allowed = match &self.pool {
+            Some(pool) => {
+                ensure_progress_tables(pool).await?;
+                sqlx::query_scalar::<_, i32>(
+                    r#"
+                    INSERT INTO metaculus_request_budget (day, request_count)
+                    VALUES (CURRENT_DATE, 1)
+                    ON CONFLICT (day) DO UPDATE
+                    SET request_count = metaculus_request_budget.request_count + 1
+                    WHERE metaculus_request_budget.request_count < $1
+                    RETURNING request_count
+                    "#,
+                )
+                .bind(budget as i32)
+                .fetch_optional(pool)
+                .await?
+                .is_some()
+            }
+            None => {
+                static COUNTER: OnceLock<Mutex<(NaiveDate, u32)>> = OnceLock::new();
+                let today = Utc::now().date_naive();
+                let mut counter = COUNTER
+                    .get_or_init(|| Mutex::new((today, 0)))
+                    .lock()
+                    .expect("budget counter poisoned");
+                if counter.0 != today {
+                    *counter = (today, 0);
+                }
+                if counter.1 < budget {
+                    counter.1 += 1;
+                    true
+                } else {
+                    false
+                }
+            }
+        };
+        if allowed {
+            Ok(())
+        } else {
+            Err(RequestBudgetExhausted { budget }.into())
         }
     }
 
@@ -86,22 +289,72 @@ impl MetaculusClient {
             .map_err(|_| anyhow::anyhow!("METACULUS_API_TOKEN environment variable not set"))
     }
 
-    // DRY helper: Common API request pattern
+    // DRY helper: Common API request pattern. Transient failures (429, 5xx,
+    // network) are retried with the limiter's backoff; every attempt counts
+    // against the daily budget.
     async fn make_api_request(&self, url: &str) -> Result<MetaculusResponse> {
         let token = self.get_api_token()?;
-        let response: MetaculusResponse = self
-            .client
-            .get(url)
-            .header("User-Agent", "Intellacc-PredictionEngine/1.0")
-            .header("Authorization", format!("Token {}", token))
-            .send()
-            .await?
-            .json()
-            .await?;
-        Ok(response)
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            self.reserve_request().await?;
+            let result = self
+                .client
+                .get(url)
+                .header("User-Agent", "Intellacc-PredictionEngine/1.0")
+                .header("Authorization", format!("Token {}", token))
+                .send()
+                .await;
+
+            let (error, retry_after) = match result {
+                Ok(response) if response.status().is_success() => {
+                    self.limiter
+                        .lock()
+                        .expect("rate limiter poisoned")
+                        .on_success(response.headers());
+                    return Ok(response.json().await?);
+                }
+                Ok(response)
+                    if response.status() == StatusCode::TOO_MANY_REQUESTS
+                        || response.status().is_server_error() =>
+                {
+                    let retry_after =
+                        header_u64(response.headers(), "retry-after").map(Duration::from_secs);
+                    (
+                        anyhow::anyhow!("Metaculus API returned {}", response.status()),
+                        retry_after,
+                    )
+                }
+                Ok(response) => {
+                    return Err(anyhow::anyhow!(
+                        "Metaculus API returned {} for {}",
+                        response.status(),
+                        url
+                    ));
+                }
+                Err(err) => (err.into(), None),
+            };
+
+            let delay = {
+                let mut limiter = self.limiter.lock().expect("rate limiter poisoned");
+                limiter.on_failure(retry_after);
+                limiter.delay()
+            };
+            if attempt >= MAX_REQUEST_ATTEMPTS {
+                return Err(error);
+            }
+            println!(
+                "⚠️ Metaculus request failed (attempt {}/{}): {}; retrying in {:?}",
+                attempt, MAX_REQUEST_ATTEMPTS, error, delay
+            );
+            tokio::time::sleep(delay).await;
+        }
     }
 
-    // DRY helper: Extract questions from API response
+    // DRY helper: Extract questions from API response. Group and conditional
+    // posts have no top-level question; they are surfaced as placeholder
+    // questions typed after the post kind so the importer can skip them
+    // explicitly instead of dropping them unseen.
     fn extract_questions_from_response(
         &self,
         response: MetaculusResponse,
@@ -109,10 +362,41 @@ impl MetaculusClient {
         response
             .results
             .into_iter()
-            .filter_map(|post| post.question.clone().map(|question| (question, post)))
+            .filter_map(|post| match post.question.clone() {
+                Some(question) => Some((question, post)),
+                None => self
+                    .placeholder_for_compound_post(&post)
+                    .map(|question| (question, post)),
+            })
             .collect()
     }
 
+    fn placeholder_for_compound_post(&self, post: &MetaculusPost) -> Option<MetaculusQuestion> {
+        let question_type = if post.group_of_questions.is_some() {
+            "group_of_questions"
+        } else if post.conditional.is_some() {
+            "conditional"
+        } else {
+            return None;
+        };
+        Some(MetaculusQuestion {
+            id: post.id?,
+            title: post.title.clone().unwrap_or_default(),
+            scheduled_close_time: None,
+            question_type: question_type.to_string(),
+            status: post.status.clone().unwrap_or_else(|| "open".to_string()),
+            description: None,
+            resolution_criteria: None,
+            fine_print: None,
+            options: None,
+            scaling: None,
+            open_lower_bound: None,
+            open_upper_bound: None,
+            unit: None,
+            aggregations: None,
+        })
+    }
+
     // Fetch open questions from Metaculus with proper pagination
     async fn fetch_open_questions(
         &self,
@@ -150,7 +434,7 @@ impl MetaculusClient {
             url = next_url.unwrap().replace("http://", "https://");
 
             // Rate limiting - be respectful to Metaculus API
-            tokio::time::sleep(tokio::time::Duration::from_millis(750)).await;
+            tokio::time::sleep(self.pacing_delay()).await;
         }
 
         // If we have a limit, ensure we don't exceed it
@@ -237,7 +521,10 @@ impl MetaculusClient {
             numeric_open_lower,
             numeric_open_upper,
             numeric_unit,
-        ) = if question.question_type == "numeric" {
+        ) = if matches!(
+            question.question_type.as_str(),
+            "numeric" | "discrete" | "date"
+        ) {
             let scaling = question.scaling.as_ref();
             let unit = question
                 .unit
@@ -257,12 +544,24 @@ impl MetaculusClient {
             (None, None, None, false, false, None)
         };
 
+        let community_prob = if question.question_type == "binary" {
+            question
+                .aggregations
+                .as_ref()
+                .and_then(|a| a.pointer("/recency_weighted/latest/centers/0"))
+                .and_then(|v| v.as_f64())
+        } else {
+            None
+        };
+
         ImportedMarket {
             source: "metaculus".to_string(),
             external_id: question.id.to_string(),
             external_url: format!("https://www.metaculus.com/questions/{}/", question.id),
             title: question.title.clone(),
             description,
+            resolution_criteria: question.resolution_criteria.clone(),
+            fine_print: question.fine_print.clone(),
             close_time,
             category,
             event_type: question.question_type.clone(),
@@ -274,6 +573,7 @@ impl MetaculusClient {
             numeric_open_lower,
             numeric_open_upper,
             numeric_unit,
+            community_prob,
         }
     }
 
@@ -290,21 +590,16 @@ impl MetaculusClient {
 
         for (question, post) in questions_with_posts {
             let market = self.convert_to_imported_market(&question, &post);
+            let disposition = classify_import(&market);
+            if let ImportDisposition::Skip(reason) = &disposition {
+                println!(
+                    "⏭️ Skipping question (ID: {}, {}): {}",
+                    market.external_id, reason, market.title
+                );
+                continue;
+            }
 
-            // Check if we already have this question by Metaculus ID (more reliable)
-            let metaculus_id_pattern = format!("Metaculus ID: {}", market.external_id);
-            let source_pattern = format!("Source: {}", market.source);
-            let external_id_pattern = format!("External ID: {}", market.external_id);
-            let existing = sqlx::query(
-                "SELECT id FROM events WHERE details LIKE $1 OR (details LIKE $2 AND details LIKE $3)",
-            )
-                .bind(format!("%{}%", metaculus_id_pattern))
-                .bind(format!("%{}%", source_pattern))
-                .bind(format!("%{}%", external_id_pattern))
-                .fetch_optional(pool)
-                .await?;
-
-            if existing.is_some() {
+            if find_stored_question(pool, &market).await?.is_some() {
                 println!(
                     "📝 Skipping existing question (ID: {}): {}",
                     market.external_id, market.title
@@ -320,7 +615,7 @@ impl MetaculusClient {
             };
 
             // Create details with Metaculus metadata
-            let enhanced_details = format!(
+            let mut enhanced_details = format!(
                 "{}\n\nSource: {}\nExternal ID: {}\nExternal URL: {}\nMetaculus ID: {}\nMetaculus URL: {}\nCategory: {}\nType: {}",
                 market.description,
                 market.source,
@@ -331,13 +626,16 @@ impl MetaculusClient {
                 market.category,
                 market.event_type
             );
+            enhanced_details.push_str(&import_metadata_lines(&market, &disposition));
 
             // Insert new event with category
             let result = sqlx::query(
                 r#"
                 INSERT INTO events (
-                    topic_id, title, details, closing_date, outcome, category
-                ) VALUES ($1, $2, $3, $4, $5, $6)
+                    topic_id, title, details, closing_date, outcome, category, event_type,
+                    forecast_only
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                RETURNING id
                 "#,
             )
             .bind(topic_id)
@@ -350,13 +648,35 @@ impl MetaculusClient {
                 None
             })
             .bind(&market.category)
-            .execute(pool)
+            .bind(normalize_event_type(&market.event_type))
+            .bind(disposition == ImportDisposition::ForecastOnly)
+            .fetch_one(pool)
             .await;
 
             match result {
-                Ok(_) => {
+                Ok(row) => {
                     println!("✅ Stored: {}", truncated_title);
                     stored_count += 1;
+                    let event_id: i32 = row.get("id");
+                    if let Err(e) = event_metadata::record_import(pool, event_id, &market).await {
+                        eprintln!(
+                            "⚠️ Failed to record metadata for {}: {}",
+                            truncated_title, e
+                        );
+                    }
+                    if disposition == ImportDisposition::Market {
+                        if let Err(e) =
+                            liquidity_recommendations::apply_to_new_market(pool, event_id).await
+                        {
+                            eprintln!(
+                                "⚠️ Failed to apply recommended liquidity to {}: {}",
+                                truncated_title, e
+                            );
+                        }
+                        if let Err(e) = seed_outcomes_if_missing(pool, event_id, &market).await {
+                            eprintln!("⚠️ Failed to seed market for {}: {}", truncated_title, e);
+                        }
+                    }
                 }
                 Err(e) => {
                     eprintln!("❌ Failed to store {}: {}", truncated_title, e);
@@ -367,6 +687,34 @@ impl MetaculusClient {
         Ok(stored_count)
     }
 
+    // Dry-run counterpart of store_questions_in_db: stored questions are
+    // skipped there, never updated, so they count as links only.
+    async fn preview_questions(
+        &self,
+        pool: &PgPool,
+        questions_with_posts: Vec<(MetaculusQuestion, MetaculusPost)>,
+        preview: &mut ImportPreview,
+    ) -> Result<()> {
+        preview.fetched_count += questions_with_posts.len() as i32;
+        for (question, post) in questions_with_posts {
+            let market = self.convert_to_imported_market(&question, &post);
+            if let ImportDisposition::Skip(_) = classify_import(&market) {
+                preview.would_exclude_count += 1;
+                push_sample(&mut preview.exclude_samples, ImportPreviewSample::of(&market, None));
+            } else if let Some(event_id) = find_stored_question(pool, &market).await? {
+                preview.would_link_count += 1;
+                push_sample(
+                    &mut preview.link_samples,
+                    ImportPreviewSample::of(&market, Some(event_id)),
+                );
+            } else {
+                preview.would_create_count += 1;
+                push_sample(&mut preview.create_samples, ImportPreviewSample::of(&market, None));
+            }
+        }
+        Ok(())
+    }
+
     // Ensure we have a topic for Metaculus imports
     async fn ensure_metaculus_topic(&self, pool: &PgPool) -> Result<i32> {
         // Check if "Metaculus Imports" topic exists
@@ -391,37 +739,134 @@ impl MetaculusClient {
     }
 
     // Complete initial import - fetch ALL open questions from Metaculus in batches
-    pub async fn complete_initial_import(&self, pool: &PgPool) -> Result<usize> {
-        self.complete_initial_import_with_limit(pool, None).await
+    pub async fn complete_initial_import(
+        &self,
+        pool: &PgPool,
+        restart: bool,
+        progress: &JobProgress,
+    ) -> Result<usize> {
+        self.complete_initial_import_with_limit(pool, None, restart, progress)
+            .await
     }
 
-    // Complete initial import with optional batch limit for testing
+    // Complete initial import with optional batch limit for testing. Progress
+    // (next page URL, page number, running total) is saved after every page,
+    // so an interrupted, paused or budget-limited run picks up where it left
+    // off on the next call unless `restart` is set. `progress` hears about
+    // every page stored in this run.
     pub async fn complete_initial_import_with_limit(
         &self,
         pool: &PgPool,
         max_batches: Option<u32>,
+        restart: bool,
+        progress: &JobProgress,
     ) -> Result<usize> {
+        ensure_progress_tables(pool).await?;
+        let first_url = format!(
+            "{}/posts/?status=open&order_by=-id&limit=100",
+            self.base_url
+        );
+        let saved = load_progress(pool, BULK_IMPORT_JOB).await?;
+        let (mut url, mut page, mut total_stored) = match saved {
+            Some(progress) if !restart && progress.status != "completed" => {
+                match progress.next_url {
+                    Some(next_url) => {
+                        println!(
+                            "⏯️ Resuming Metaculus import at batch {} ({} stored so far)",
+                            progress.page + 1,
+                            progress.total_stored
+                        );
+                        (
+                            next_url,
+                            progress.page as u32 + 1,
+                            progress.total_stored as usize,
+                        )
+                    }
+                    None => (first_url, 1, 0),
+                }
+            }
+            _ => (first_url, 1, 0),
+        };
+        let run_start_page = page;
+        // A pause requested while nothing was running must not stop this run.
+        sqlx::query("UPDATE metaculus_import_progress SET pause_requested = FALSE WHERE job = $1")
+            .bind(BULK_IMPORT_JOB)
+            .execute(pool)
+            .await?;
+
         println!("🚀 Starting complete Metaculus import...");
         if let Some(limit) = max_batches {
             println!("📊 Limited to {} batches for testing", limit);
         }
-
-        let mut total_stored = 0;
-        let mut url = format!(
-            "{}/posts/?status=open&order_by=-id&limit=100",
-            self.base_url
-        );
-        let mut page = 1;
+        save_progress(
+            pool,
+            BULK_IMPORT_JOB,
+            Some(&url),
+            page - 1,
+            total_stored,
+            "running",
+            None,
+        )
+        .await?;
 
         loop {
+            if pause_requested(pool, BULK_IMPORT_JOB).await? {
+                println!("⏸️ Pause requested. Import paused before batch {}", page);
+                save_progress(
+                    pool,
+                    BULK_IMPORT_JOB,
+                    Some(&url),
+                    page - 1,
+                    total_stored,
+                    "paused",
+                    None,
+                )
+                .await?;
+                return Ok(total_stored);
+            }
+
             println!("📄 Processing batch {} from: {}", page, url);
 
-            let response = self.make_api_request(&url).await?;
-            let next_url = response.next.clone();
+            let response = match self.make_api_request(&url).await {
+                Ok(response) => response,
+                Err(err) => {
+                    let budget_hit = err.downcast_ref::<RequestBudgetExhausted>().is_some();
+                    let status = if budget_hit { "paused" } else { "failed" };
+                    save_progress(
+                        pool,
+                        BULK_IMPORT_JOB,
+                        Some(&url),
+                        page - 1,
+                        total_stored,
+                        status,
+                        Some(&err.to_string()),
+                    )
+                    .await?;
+                    if budget_hit {
+                        println!("⏸️ {}. Import paused before batch {}", err, page);
+                        return Ok(total_stored);
+                    }
+                    return Err(err);
+                }
+            };
+            let next_url = response
+                .next
+                .clone()
+                .map(|next| next.replace("http://", "https://"));
             let questions = self.extract_questions_from_response(response);
 
             if questions.is_empty() {
                 println!("✅ No more questions found. Import complete!");
+                save_progress(
+                    pool,
+                    BULK_IMPORT_JOB,
+                    None,
+                    page,
+                    total_stored,
+                    "completed",
+                    None,
+                )
+                .await?;
                 break;
             }
 
@@ -434,35 +879,66 @@ impl MetaculusClient {
             // Store this batch in database immediately
             let stored_count = self.store_questions_in_db(pool, questions).await?;
             total_stored += stored_count;
+            progress.progress(
+                "import",
+                (page + 1 - run_start_page) as u64,
+                max_batches.map(u64::from),
+            );
 
             println!(
                 "💾 Stored {} new questions from batch {} (total so far: {})",
                 stored_count, page, total_stored
             );
 
+            // Check if there's a next page
+            let Some(next_url) = next_url else {
+                println!("📄 Reached last page. Import complete!");
+                save_progress(
+                    pool,
+                    BULK_IMPORT_JOB,
+                    None,
+                    page,
+                    total_stored,
+                    "completed",
+                    None,
+                )
+                .await?;
+                break;
+            };
+            save_progress(
+                pool,
+                BULK_IMPORT_JOB,
+                Some(&next_url),
+                page,
+                total_stored,
+                "running",
+                None,
+            )
+            .await?;
+
             // Check if we've reached the batch limit
             if let Some(max_batches) = max_batches {
-                if page >= max_batches {
-                    println!(
-                        "📊 Reached batch limit of {}. Stopping import.",
-                        max_batches
-                    );
+                if page + 1 - run_start_page >= max_batches {
+                    println!("📊 Reached batch limit of {}. Pausing import.", max_batches);
+                    save_progress(
+                        pool,
+                        BULK_IMPORT_JOB,
+                        Some(&next_url),
+                        page,
+                        total_stored,
+                        "paused",
+                        None,
+                    )
+                    .await?;
                     break;
                 }
             }
 
-            // Check if there's a next page
-            if next_url.is_none() {
-                println!("📄 Reached last page. Import complete!");
-                break;
-            }
-
-            // Use the next URL from the response, but ensure it uses HTTPS
-            url = next_url.unwrap().replace("http://", "https://");
+            url = next_url;
             page += 1;
 
             // Rate limiting - be respectful during bulk import
-            tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+            tokio::time::sleep(self.pacing_delay()).await;
         }
 
         println!(
@@ -508,9 +984,34 @@ impl MetaculusClient {
     }
 }
 
-/// Provider-neutral open market fetch used by multi-source import orchestration.
-pub async fn fetch_open_markets(limit: Option<usize>) -> Result<Vec<ImportedMarket>> {
-    let client = MetaculusClient::new();
+// Event a question was already stored as, matched by the Metaculus ID in
+// its details (more reliable than the title)
+async fn find_stored_question(pool: &PgPool, market: &ImportedMarket) -> Result<Option<i32>> {
+    let metaculus_id_pattern = format!("Metaculus ID: {}", market.external_id);
+    let source_pattern = format!("Source: {}", market.source);
+    let external_id_pattern = format!("External ID: {}", market.external_id);
+    let existing = sqlx::query_scalar(
+        "SELECT id FROM events WHERE details LIKE $1 OR (details LIKE $2 AND details LIKE $3)",
+    )
+    .bind(format!("%{}%", metaculus_id_pattern))
+    .bind(format!("%{}%", source_pattern))
+    .bind(format!("%{}%", external_id_pattern))
+    .fetch_optional(pool)
+    .await?;
+    Ok(existing)
+}
+
+/// Provider-neutral open market fetch used by multi-source import
+/// orchestration. Requests count against the persisted daily budget when
+/// `budget_pool` is given, the in-process one otherwise.
+pub async fn fetch_open_markets(
+    budget_pool: Option<&PgPool>,
+    limit: Option<usize>,
+) -> Result<Vec<ImportedMarket>> {
+    let client = match budget_pool {
+        Some(pool) => MetaculusClient::new().with_pool(pool),
+        None => MetaculusClient::new(),
+    };
     let limit_u32 = limit.map(|v| v.min(u32::MAX as usize) as u32);
     let rows = client.fetch_open_questions(limit_u32).await?;
     let mut markets = Vec::with_capacity(rows.len());
@@ -521,31 +1022,254 @@ pub async fn fetch_open_markets(limit: Option<usize>) -> Result<Vec<ImportedMark
 }
 
 // Manual bulk import function for initial setup
-pub async fn manual_bulk_import(pool: &PgPool) -> Result<usize> {
-    let client = MetaculusClient::new();
-    client.complete_initial_import(pool).await
+pub async fn manual_bulk_import(
+    pool: &PgPool,
+    restart: bool,
+    progress: &JobProgress,
+) -> Result<usize> {
+    let client = MetaculusClient::new().with_pool(pool);
+    client.complete_initial_import(pool, restart, progress).await
 }
 
 // Manual limited import function for testing
-pub async fn manual_limited_import(pool: &PgPool, max_batches: u32) -> Result<usize> {
-    let client = MetaculusClient::new();
+pub async fn manual_limited_import(
+    pool: &PgPool,
+    max_batches: u32,
+    restart: bool,
+    progress: &JobProgress,
+) -> Result<usize> {
+    let client = MetaculusClient::new().with_pool(pool);
     client
-        .complete_initial_import_with_limit(pool, Some(max_batches))
+        .complete_initial_import_with_limit(pool, Some(max_batches), restart, progress)
         .await
 }
 
 // Manual sync function for testing
 pub async fn manual_sync(pool: &PgPool) -> Result<usize> {
-    let client = MetaculusClient::new();
+    let client = MetaculusClient::new().with_pool(pool);
     client.daily_sync(pool).await
 }
 
+// What manual_sync would store, without writing anything. The client has no
+// pool, so its requests count against the in-process budget.
+pub async fn preview_sync(pool: &PgPool) -> Result<ImportPreview> {
+    let client = MetaculusClient::new();
+    let mut preview = ImportPreview::new(client.source_name());
+    match client.fetch_open_questions(Some(150)).await {
+        Ok(questions) => client.preview_questions(pool, questions, &mut preview).await?,
+        Err(err) => preview.record_error(&err),
+    }
+    Ok(preview)
+}
+
+// What the next `batches` pages of a bulk import would store, starting where
+// the import would resume (or from the first page with `restart`). Saved
+// progress is read but never moved.
+pub async fn preview_bulk_import(
+    pool: &PgPool,
+    batches: u32,
+    restart: bool,
+) -> Result<ImportPreview> {
+    let client = MetaculusClient::new();
+    let has_progress: bool =
+        sqlx::query_scalar("SELECT to_regclass('metaculus_import_progress') IS NOT NULL")
+            .fetch_one(pool)
+            .await?;
+    let saved = if has_progress && !restart {
+        load_progress(pool, BULK_IMPORT_JOB).await?
+    } else {
+        None
+    };
+    let mut url = saved
+        .filter(|progress| progress.status != "completed")
+        .and_then(|progress| progress.next_url)
+        .unwrap_or_else(|| format!("{}/posts/?status=open&order_by=-id&limit=100", client.base_url));
+
+    let mut preview = ImportPreview::new(client.source_name());
+    for _ in 0..batches.max(1) {
+        let response = match client.make_api_request(&url).await {
+            Ok(response) => response,
+            Err(err) => {
+                preview.record_error(&err);
+                break;
+            }
+        };
+        let next_url = response
+            .next
+            .clone()
+            .map(|next| next.replace("http://", "https://"));
+        let questions = client.extract_questions_from_response(response);
+        client.preview_questions(pool, questions, &mut preview).await?;
+        match next_url {
+            Some(next_url) => url = next_url,
+            None => break,
+        }
+    }
+    Ok(preview)
+}
+
 // Sync specific categories manually
 pub async fn manual_category_sync(pool: &PgPool, categories: Vec<&str>) -> Result<usize> {
-    let client = MetaculusClient::new();
+    let client = MetaculusClient::new().with_pool(pool);
     client.sync_categories(pool, categories).await
 }
 
+// Ask a running bulk import to stop after its current page.
+pub async fn request_bulk_import_pause(pool: &PgPool) -> Result<()> {
+    ensure_progress_tables(pool).await?;
+    sqlx::query(
+        r#"
+        INSERT INTO metaculus_import_progress (job, pause_requested, updated_at)
+        VALUES ($1, TRUE, NOW())
+        ON CONFLICT (job) DO UPDATE SET pause_requested = TRUE, updated_at = NOW()
+        "#,
+    )
+    .bind(BULK_IMPORT_JOB)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+// Bulk import progress plus today's request budget usage.
+pub async fn get_import_progress(pool: &PgPool) -> Result<Value> {
+    ensure_progress_tables(pool).await?;
+    let progress = load_progress(pool, BULK_IMPORT_JOB).await?;
+    let requests_today: i32 = sqlx::query_scalar(
+        "SELECT COALESCE((SELECT request_count FROM metaculus_request_budget WHERE day = CURRENT_DATE), 0)",
+    )
+    .fetch_one(pool)
+    .await?;
+    let budget = daily_request_budget();
+    Ok(json!({
+        "bulk_import": progress.map(|p| json!({
+            "status": p.status,
+            "page": p.page,
+            "total_stored": p.total_stored,
+            "next_url": p.next_url,
+            "pause_requested": p.pause_requested,
+            "last_error": p.last_error,
+            "updated_at": p.updated_at,
+        })),
+        "request_budget": {
+            "daily_budget": if budget == 0 { None } else { Some(budget) },
+            "requests_today": requests_today,
+        }
+    }))
+}
+
+struct ImportProgress {
+    next_url: Option<String>,
+    page: i32,
+    total_stored: i32,
+    status: String,
+    pause_requested: bool,
+    last_error: Option<String>,
+    updated_at: DateTime<Utc>,
+}
+
+async fn ensure_progress_tables(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS metaculus_import_progress (
+            job VARCHAR(64) PRIMARY KEY,
+            next_url TEXT,
+            page INTEGER NOT NULL DEFAULT 0,
+            total_stored INTEGER NOT NULL DEFAULT 0,
+            status VARCHAR(16) NOT NULL DEFAULT 'idle',
+            pause_requested BOOLEAN NOT NULL DEFAULT FALSE,
+            last_error TEXT,
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS metaculus_request_budget (
+            day DATE PRIMARY KEY,
+            request_count INTEGER NOT NULL DEFAULT 0
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+async fn load_progress(pool: &PgPool, job: &str) -> Result<Option<ImportProgress>> {
+    let row = sqlx::query(
+        r#"
+        SELECT next_url, page, total_stored, status, pause_requested, last_error, updated_at
+        FROM metaculus_import_progress
+        WHERE job = $1
+        "#,
+    )
+    .bind(job)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|row| ImportProgress {
+        next_url: row.get("next_url"),
+        page: row.get("page"),
+        total_stored: row.get("total_stored"),
+        status: row.get("status"),
+        pause_requested: row.get("pause_requested"),
+        last_error: row.get("last_error"),
+        updated_at: row.get("updated_at"),
+    }))
+}
+
+async fn pause_requested(pool: &PgPool, job: &str) -> Result<bool> {
+    let requested: Option<bool> =
+        sqlx::query_scalar("SELECT pause_requested FROM metaculus_import_progress WHERE job = $1")
+            .bind(job)
+            .fetch_optional(pool)
+            .await?;
+    Ok(requested.unwrap_or(false))
+}
+
+// Saving any state other than "running" clears a pending pause request, so
+// the next call resumes normally.
+async fn save_progress(
+    pool: &PgPool,
+    job: &str,
+    next_url: Option<&str>,
+    page: u32,
+    total_stored: usize,
+    status: &str,
+    last_error: Option<&str>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO metaculus_import_progress
+            (job, next_url, page, total_stored, status, pause_requested, last_error, updated_at)
+        VALUES ($1, $2, $3, $4, $5, FALSE, $6, NOW())
+        ON CONFLICT (job) DO UPDATE SET
+            next_url = EXCLUDED.next_url,
+            page = EXCLUDED.page,
+            total_stored = EXCLUDED.total_stored,
+            status = EXCLUDED.status,
+            pause_requested = CASE
+                WHEN EXCLUDED.status = 'running' THEN metaculus_import_progress.pause_requested
+                ELSE FALSE
+            END,
+            last_error = EXCLUDED.last_error,
+            updated_at = NOW()
+        "#,
+    )
+    .bind(job)
+    .bind(next_url)
+    .bind(page as i32)
+    .bind(total_stored as i32)
+    .bind(status)
+    .bind(last_error)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -583,8 +1307,8 @@ mod tests {
         let question: MetaculusQuestion = serde_json::from_str(question_json)
             .expect("fixture should deserialize into MetaculusQuestion");
         let post = MetaculusPost {
-            categories: Vec::new(),
             question: Some(question.clone()),
+            ..Default::default()
         };
         (question, post)
     }
@@ -593,6 +1317,8 @@ mod tests {
         MetaculusClient {
             client: Client::new(),
             base_url: "https://www.metaculus.com/api".to_string(),
+            limiter: Arc::new(Mutex::new(AdaptiveRateLimiter::new(750, 120_000))),
+            pool: None,
         }
     }
 
@@ -641,10 +1367,134 @@ mod tests {
         let mut question: MetaculusQuestion = serde_json::from_str(NUMERIC_QUESTION_JSON).unwrap();
         question.unit = Some("  USD  ".to_string());
         let post = MetaculusPost {
-            categories: Vec::new(),
             question: Some(question.clone()),
+            ..Default::default()
         };
         let market = client().convert_to_imported_market(&question, &post);
         assert_eq!(market.numeric_unit, Some("USD".to_string()));
     }
+
+    #[test]
+    fn group_and_conditional_posts_surface_as_skipped_placeholders() {
+        let response: MetaculusResponse = serde_json::from_str(
+            r#"{
+                "next": null,
+                "results": [
+                    {"id": 1, "title": "Group", "status": "open", "group_of_questions": {"questions": []}},
+                    {"id": 2, "title": "Conditional", "status": "open", "conditional": {}},
+                    {"id": 3, "title": "Notebook", "status": "open"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let client = client();
+        let rows = client.extract_questions_from_response(response);
+
+        // Notebooks have no question at all and are still dropped.
+        assert_eq!(rows.len(), 2);
+        for (question, post) in &rows {
+            let market = client.convert_to_imported_market(question, post);
+            assert!(matches!(
+                classify_import(&market),
+                ImportDisposition::Skip(_)
+            ));
+        }
+        assert_eq!(rows[0].0.question_type, "group_of_questions");
+        assert_eq!(rows[1].0.question_type, "conditional");
+    }
+
+    #[test]
+    fn question_types_map_to_import_dispositions() {
+        let (question, post) = make_post(MC_QUESTION_JSON);
+        let market = client().convert_to_imported_market(&question, &post);
+        assert_eq!(classify_import(&market), ImportDisposition::Market);
+
+        // Numeric questions with a range become bucketed markets; without
+        // one there are no bins to cut, so they stay forecast-only.
+        let (question, post) = make_post(NUMERIC_QUESTION_JSON);
+        let market = client().convert_to_imported_market(&question, &post);
+        assert!(market.numeric_range_min.is_some());
+        assert_eq!(classify_import(&market), ImportDisposition::Market);
+
+        let mut question: MetaculusQuestion = serde_json::from_str(NUMERIC_QUESTION_JSON).unwrap();
+        question.scaling = None;
+        let post = MetaculusPost {
+            question: Some(question.clone()),
+            ..Default::default()
+        };
+        let market = client().convert_to_imported_market(&question, &post);
+        assert_eq!(classify_import(&market), ImportDisposition::ForecastOnly);
+
+        // Date questions keep their scaling bounds but never get a market.
+        let mut question: MetaculusQuestion = serde_json::from_str(NUMERIC_QUESTION_JSON).unwrap();
+        question.question_type = "date".to_string();
+        question.scaling = Some(MetaculusScaling {
+            range_min: Some(1_767_225_600.0),
+            range_max: Some(1_798_761_600.0),
+            zero_point: None,
+        });
+        let post = MetaculusPost {
+            question: Some(question.clone()),
+            ..Default::default()
+        };
+        let market = client().convert_to_imported_market(&question, &post);
+        assert_eq!(market.numeric_range_min, Some(1_767_225_600.0));
+        assert_eq!(classify_import(&market), ImportDisposition::ForecastOnly);
+        assert!(
+            import_metadata_lines(&market, &ImportDisposition::ForecastOnly)
+                .contains("Import mode: forecast-only")
+        );
+    }
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(*name, value.parse().unwrap());
+        }
+        map
+    }
+
+    #[test]
+    fn limiter_backs_off_on_failure_and_decays_on_success() {
+        let mut limiter = AdaptiveRateLimiter::new(750, 10_000);
+        limiter.on_failure(None);
+        assert_eq!(limiter.delay(), Duration::from_millis(1_500));
+        limiter.on_failure(None);
+        assert_eq!(limiter.delay(), Duration::from_millis(3_000));
+
+        limiter.on_success(&HeaderMap::new());
+        assert_eq!(limiter.delay(), Duration::from_millis(2_400));
+        assert_eq!(limiter.consecutive_failures, 0);
+        for _ in 0..20 {
+            limiter.on_success(&HeaderMap::new());
+        }
+        assert_eq!(limiter.delay(), Duration::from_millis(750));
+    }
+
+    #[test]
+    fn limiter_honours_retry_after_and_caps_at_max() {
+        let mut limiter = AdaptiveRateLimiter::new(750, 10_000);
+        limiter.on_failure(Some(Duration::from_secs(5)));
+        assert_eq!(limiter.delay(), Duration::from_millis(5_000));
+        limiter.on_failure(Some(Duration::from_secs(3_600)));
+        assert_eq!(limiter.delay(), Duration::from_millis(10_000));
+    }
+
+    #[test]
+    fn limiter_spreads_remaining_quota_until_reset() {
+        let mut limiter = AdaptiveRateLimiter::new(750, 120_000);
+        limiter.on_success(&headers(&[
+            ("x-ratelimit-remaining", "10"),
+            ("x-ratelimit-reset", "30"),
+        ]));
+        assert_eq!(limiter.delay(), Duration::from_millis(3_000));
+
+        // Plenty of quota left: stay at the floor.
+        let mut limiter = AdaptiveRateLimiter::new(750, 120_000);
+        limiter.on_success(&headers(&[
+            ("x-ratelimit-remaining", "1000"),
+            ("x-ratelimit-reset", "60"),
+        ]));
+        assert_eq!(limiter.delay(), Duration::from_millis(750));
+    }
 }
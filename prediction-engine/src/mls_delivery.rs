@@ -0,0 +1,636 @@
+//! MLS delivery-service checks for the backend's message relay.
+//!
+//! Commits are ordered per group by epoch: one is accepted per epoch, at the
+//! group's current one, and numbered from `mls_ds_commit_counters` in the
+//! same transaction. Groups registered with their GroupInfo are tracked as
+//! OpenMLS `PublicGroup`s in `mls_ds_group_state`, and their PublicMessages
+//! are verified before being accepted. Welcomes are stored once per
+//! recipient key package ref.
+
+use crate::api_error::{coded, ErrorCode};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use openmls::messages::group_info::VerifiableGroupInfo;
+use openmls::prelude::*;
+use openmls_memory_storage::MemoryStorage;
+use openmls_rust_crypto::RustCrypto;
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool, Row};
+use tls_codec::Deserialize as _;
+
+pub const MAX_COMMITS: i64 = 500;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrackedGroup {
+    pub group_id: String,
+    pub epoch: i64,
+    /// Whether handshake messages are verified against a tracked PublicGroup.
+    pub verified: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AcceptedMessage {
+    pub group_id: String,
+    /// The epoch the message was sent in.
+    pub epoch: i64,
+    /// "commit" or "proposal".
+    pub content_type: String,
+    /// The group's epoch once the message is applied.
+    pub next_epoch: i64,
+    pub verified: bool,
+    /// The commit's place in its group's backlog; none for proposals.
+    pub sequence: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredCommit {
+    pub sequence: i64,
+    pub epoch: i64,
+    /// Hex-encoded MLS message, as submitted.
+    pub message: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommitBacklog {
+    #[serde(flatten)]
+    pub group: TrackedGroup,
+    /// Sequence number of the group's latest commit, 0 before any.
+    pub last_sequence: i64,
+    pub commits: Vec<StoredCommit>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredWelcome {
+    pub id: i64,
+    pub key_package_ref: String,
+    pub app_group_id: Option<String>,
+    /// Hex-encoded MLS Welcome message, as submitted.
+    pub welcome: String,
+    pub created_at: DateTime<Utc>,
+}
+
+pub async fn ensure_delivery_tables(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS mls_ds_groups (
+            group_id BYTEA PRIMARY KEY,
+            epoch BIGINT NOT NULL,
+            verified BOOLEAN NOT NULL DEFAULT FALSE,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS mls_ds_group_state (
+            group_id BYTEA NOT NULL REFERENCES mls_ds_groups(group_id) ON DELETE CASCADE,
+            key BYTEA NOT NULL,
+            value BYTEA NOT NULL,
+            PRIMARY KEY (group_id, key)
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS mls_ds_commits (
+            group_id BYTEA NOT NULL REFERENCES mls_ds_groups(group_id) ON DELETE CASCADE,
+            epoch BIGINT NOT NULL,
+            message BYTEA NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            PRIMARY KEY (group_id, epoch)
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS mls_ds_commit_counters (
+            group_id BYTEA PRIMARY KEY REFERENCES mls_ds_groups(group_id) ON DELETE CASCADE,
+            last_sequence BIGINT NOT NULL
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("ALTER TABLE mls_ds_commits ADD COLUMN IF NOT EXISTS sequence BIGINT")
+        .execute(pool)
+        .await?;
+    // Commits stored before sequencing are numbered in epoch order
+    sqlx::query(
+        r#"
+        UPDATE mls_ds_commits c
+        SET sequence = numbered.sequence
+        FROM (
+            SELECT group_id, epoch,
+                   ROW_NUMBER() OVER (PARTITION BY group_id ORDER BY epoch) AS sequence
+            FROM mls_ds_commits
+        ) numbered
+        WHERE c.sequence IS NULL
+          AND c.group_id = numbered.group_id
+          AND c.epoch = numbered.epoch
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "INSERT INTO mls_ds_commit_counters (group_id, last_sequence)
+         SELECT group_id, MAX(sequence) FROM mls_ds_commits GROUP BY group_id
+         ON CONFLICT (group_id) DO NOTHING",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("ALTER TABLE mls_ds_commits ALTER COLUMN sequence SET NOT NULL")
+        .execute(pool)
+        .await?;
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_mls_ds_commits_sequence
+         ON mls_ds_commits(group_id, sequence)",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS mls_ds_welcomes (
+            id BIGSERIAL PRIMARY KEY,
+            key_package_ref BYTEA NOT NULL,
+            welcome BYTEA NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("ALTER TABLE mls_ds_welcomes ADD COLUMN IF NOT EXISTS app_group_id TEXT")
+        .execute(pool)
+        .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_mls_ds_welcomes_ref
+         ON mls_ds_welcomes(key_package_ref, created_at)",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Decodes a hex request field.
+pub fn decode_hex(field: &str, value: &str) -> Result<Vec<u8>> {
+    hex::decode(value.trim()).map_err(|_| {
+        coded(
+            ErrorCode::InvalidRequest,
+            format!("{} must be hex-encoded", field),
+        )
+    })
+}
+
+/// The MLS group id and epoch of a TLS-encoded GroupInfo. Its signature
+/// isn't checked, which takes the group's ratchet tree.
+pub fn group_info_header(group_info: &[u8]) -> Result<(Vec<u8>, i64)> {
+    let group_info = VerifiableGroupInfo::tls_deserialize_exact(group_info).map_err(|e| {
+        coded(
+            ErrorCode::InvalidRequest,
+            format!("group_info must be a TLS-encoded GroupInfo: {:?}", e),
+        )
+    })?;
+    Ok((
+        group_info.group_id().as_slice().to_vec(),
+        group_info.epoch().as_u64() as i64,
+    ))
+}
+
+pub(crate) fn epoch_conflict(current: i64, epoch: i64) -> anyhow::Error {
+    coded(
+        ErrorCode::Conflict,
+        format!(
+            "epoch conflict: group is at epoch {}, message is for epoch {}",
+            current, epoch
+        ),
+    )
+}
+
+async fn load_public_group(
+    conn: &mut PgConnection,
+    group_id: &GroupId,
+) -> Result<(MemoryStorage, PublicGroup)> {
+    let rows: Vec<(Vec<u8>, Vec<u8>)> =
+        sqlx::query_as("SELECT key, value FROM mls_ds_group_state WHERE group_id = $1")
+            .bind(group_id.as_slice())
+            .fetch_all(&mut *conn)
+            .await?;
+    let storage = MemoryStorage::default();
+    storage
+        .values
+        .write()
+        .map_err(|_| anyhow!("MLS state lock poisoned"))?
+        .extend(rows);
+    let group = PublicGroup::load(&storage, group_id)
+        .map_err(|e| anyhow!("Error loading tracked MLS group: {:?}", e))?
+        .ok_or_else(|| anyhow!("Tracked MLS group state is missing"))?;
+    Ok((storage, group))
+}
+
+async fn save_public_group(
+    conn: &mut PgConnection,
+    group_id: &GroupId,
+    storage: &MemoryStorage,
+) -> Result<()> {
+    let values: Vec<(Vec<u8>, Vec<u8>)> = storage
+        .values
+        .read()
+        .map_err(|_| anyhow!("MLS state lock poisoned"))?
+        .iter()
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    sqlx::query("DELETE FROM mls_ds_group_state WHERE group_id = $1")
+        .bind(group_id.as_slice())
+        .execute(&mut *conn)
+        .await?;
+    for (key, value) in values {
+        sqlx::query("INSERT INTO mls_ds_group_state (group_id, key, value) VALUES ($1, $2, $3)")
+            .bind(group_id.as_slice())
+            .bind(key)
+            .bind(value)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Starts verifying a group's handshake messages from the epoch of
+/// `group_info` (a TLS-encoded GroupInfo). The ratchet tree comes from
+/// `ratchet_tree`, or from the GroupInfo's extension when not given. A
+/// group the DS already holds at a later epoch can't be rewound.
+pub async fn register_group(
+    pool: &PgPool,
+    group_info: &[u8],
+    ratchet_tree: Option<&[u8]>,
+) -> Result<TrackedGroup> {
+    let group_info = VerifiableGroupInfo::tls_deserialize_exact(group_info).map_err(|e| {
+        coded(
+            ErrorCode::InvalidRequest,
+            format!("group_info must be a TLS-encoded GroupInfo: {:?}", e),
+        )
+    })?;
+    let ratchet_tree = match ratchet_tree {
+        Some(bytes) => RatchetTreeIn::tls_deserialize_exact(bytes).map_err(|e| {
+            coded(
+                ErrorCode::InvalidRequest,
+                format!("ratchet_tree must be a TLS-encoded ratchet tree: {:?}", e),
+            )
+        })?,
+        None => group_info
+            .extensions()
+            .ratchet_tree()
+            .map(|extension| extension.ratchet_tree().clone())
+            .ok_or_else(|| {
+                coded(
+                    ErrorCode::InvalidRequest,
+                    "ratchet_tree must be given when the GroupInfo has none",
+                )
+            })?,
+    };
+    let group_id = group_info.group_id().clone();
+    let storage = MemoryStorage::default();
+    let (public_group, _) = PublicGroup::from_external(
+        &RustCrypto::default(),
+        &storage,
+        ratchet_tree,
+        group_info,
+        ProposalStore::new(),
+    )
+    .map_err(|e| {
+        coded(
+            ErrorCode::InvalidRequest,
+            format!("group_info must verify against its ratchet tree: {:?}", e),
+        )
+    })?;
+    let epoch = public_group.group_context().epoch().as_u64() as i64;
+
+    let mut tx = pool.begin().await?;
+    let current: Option<i64> =
+        sqlx::query_scalar("SELECT epoch FROM mls_ds_groups WHERE group_id = $1 FOR UPDATE")
+            .bind(group_id.as_slice())
+            .fetch_optional(&mut *tx)
+            .await?;
+    if let Some(current) = current.filter(|current| *current > epoch) {
+        return Err(epoch_conflict(current, epoch));
+    }
+    sqlx::query(
+        "INSERT INTO mls_ds_groups (group_id, epoch, verified) VALUES ($1, $2, TRUE)
+         ON CONFLICT (group_id) DO UPDATE
+         SET epoch = EXCLUDED.epoch, verified = TRUE, updated_at = NOW()",
+    )
+    .bind(group_id.as_slice())
+    .bind(epoch)
+    .execute(&mut *tx)
+    .await?;
+    save_public_group(&mut tx, &group_id, &storage).await?;
+    tx.commit().await?;
+
+    Ok(TrackedGroup {
+        group_id: hex::encode(group_id.as_slice()),
+        epoch,
+        verified: true,
+    })
+}
+
+/// Accepts a commit or proposal (a TLS-encoded MLS message) if it is for
+/// its group's current epoch and, on a verified group, verifies. Accepted
+/// commits are sequenced, stored and advance the group's epoch.
+pub async fn submit_message(pool: &PgPool, message: &[u8]) -> Result<AcceptedMessage> {
+    accept_message(pool, None, message).await
+}
+
+/// `submit_message` for a commit the client expects to land in `group_id`.
+pub async fn submit_commit(
+    pool: &PgPool,
+    group_id: &[u8],
+    message: &[u8],
+) -> Result<AcceptedMessage> {
+    accept_message(pool, Some(group_id), message).await
+}
+
+async fn accept_message(
+    pool: &PgPool,
+    expected_group: Option<&[u8]>,
+    message: &[u8],
+) -> Result<AcceptedMessage> {
+    let protocol_message = MlsMessageIn::tls_deserialize_exact(message)
+        .map_err(|e| {
+            coded(
+                ErrorCode::InvalidRequest,
+                format!("message must be a TLS-encoded MLS message: {:?}", e),
+            )
+        })?
+        .try_into_protocol_message()
+        .map_err(|_| {
+            coded(
+                ErrorCode::InvalidRequest,
+                "message must be a PublicMessage or PrivateMessage",
+            )
+        })?;
+    let is_commit = match protocol_message.content_type() {
+        ContentType::Commit => true,
+        ContentType::Proposal if expected_group.is_none() => false,
+        _ if expected_group.is_some() => {
+            return Err(coded(ErrorCode::InvalidRequest, "message must be a commit"))
+        }
+        _ => {
+            return Err(coded(
+                ErrorCode::InvalidRequest,
+                "message must be a commit or proposal",
+            ))
+        }
+    };
+    let group_id = protocol_message.group_id().clone();
+    if let Some(expected) = expected_group.filter(|expected| *expected != group_id.as_slice()) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("message must be for group {}", hex::encode(expected)),
+        ));
+    }
+    let epoch = protocol_message.epoch().as_u64() as i64;
+
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "INSERT INTO mls_ds_groups (group_id, epoch) VALUES ($1, $2)
+         ON CONFLICT (group_id) DO NOTHING",
+    )
+    .bind(group_id.as_slice())
+    .bind(epoch)
+    .execute(&mut *tx)
+    .await?;
+    let group =
+        sqlx::query("SELECT epoch, verified FROM mls_ds_groups WHERE group_id = $1 FOR UPDATE")
+            .bind(group_id.as_slice())
+            .fetch_one(&mut *tx)
+            .await?;
+    let current: i64 = group.get("epoch");
+    let verified: bool = group.get("verified");
+    if epoch != current {
+        return Err(epoch_conflict(current, epoch));
+    }
+
+    let next_epoch = if verified {
+        if matches!(protocol_message, ProtocolMessage::PrivateMessage(_)) {
+            return Err(coded(
+                ErrorCode::InvalidRequest,
+                "message must be a PublicMessage for a verified group",
+            ));
+        }
+        let (storage, mut public_group) = load_public_group(&mut tx, &group_id).await?;
+        let processed = public_group
+            .process_message(&RustCrypto::default(), protocol_message)
+            .map_err(|e| {
+                coded(
+                    ErrorCode::InvalidRequest,
+                    format!("message must verify against the group: {:?}", e),
+                )
+            })?;
+        match processed.into_content() {
+            ProcessedMessageContent::StagedCommitMessage(staged_commit) => public_group
+                .merge_commit(&storage, *staged_commit)
+                .map_err(|e| anyhow!("Error merging commit: {:?}", e))?,
+            ProcessedMessageContent::ProposalMessage(proposal) => public_group
+                .add_proposal(&storage, *proposal)
+                .map_err(|e| anyhow!("Error queueing proposal: {:?}", e))?,
+            _ => {
+                return Err(coded(
+                    ErrorCode::InvalidRequest,
+                    "message must be a commit or proposal",
+                ))
+            }
+        }
+        save_public_group(&mut tx, &group_id, &storage).await?;
+        public_group.group_context().epoch().as_u64() as i64
+    } else if is_commit {
+        current + 1
+    } else {
+        current
+    };
+
+    let mut sequence = None;
+    if is_commit {
+        let next: i64 = sqlx::query_scalar(
+            "INSERT INTO mls_ds_commit_counters (group_id, last_sequence) VALUES ($1, 1)
+             ON CONFLICT (group_id) DO UPDATE
+             SET last_sequence = mls_ds_commit_counters.last_sequence + 1
+             RETURNING last_sequence",
+        )
+        .bind(group_id.as_slice())
+        .fetch_one(&mut *tx)
+        .await?;
+        sqlx::query(
+            "INSERT INTO mls_ds_commits (group_id, epoch, sequence, message)
+             VALUES ($1, $2, $3, $4)",
+        )
+        .bind(group_id.as_slice())
+        .bind(epoch)
+        .bind(next)
+        .bind(message)
+        .execute(&mut *tx)
+        .await?;
+        sequence = Some(next);
+        sqlx::query("UPDATE mls_ds_groups SET epoch = $2, updated_at = NOW() WHERE group_id = $1")
+            .bind(group_id.as_slice())
+            .bind(next_epoch)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+
+    Ok(AcceptedMessage {
+        group_id: hex::encode(group_id.as_slice()),
+        epoch,
+        content_type: if is_commit { "commit" } else { "proposal" }.to_string(),
+        next_epoch,
+        verified,
+        sequence,
+    })
+}
+
+/// The group's commits after sequence number `since`, in order. A client
+/// passes the last sequence number it applied, or 0 for the whole backlog.
+pub async fn get_commits(
+    pool: &PgPool,
+    group_id: &[u8],
+    since: i64,
+    limit: i64,
+) -> Result<CommitBacklog> {
+    if since < 0 {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "since must be non-negative",
+        ));
+    }
+    if !(1..=MAX_COMMITS).contains(&limit) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("limit must be between 1 and {}", MAX_COMMITS),
+        ));
+    }
+    let group = sqlx::query(
+        "SELECT g.epoch, g.verified, COALESCE(c.last_sequence, 0) AS last_sequence
+         FROM mls_ds_groups g
+         LEFT JOIN mls_ds_commit_counters c ON c.group_id = g.group_id
+         WHERE g.group_id = $1",
+    )
+    .bind(group_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| coded(ErrorCode::NotFound, "Group not found"))?;
+    let commits = sqlx::query(
+        "SELECT sequence, epoch, message, created_at FROM mls_ds_commits
+         WHERE group_id = $1 AND sequence > $2
+         ORDER BY sequence
+         LIMIT $3",
+    )
+    .bind(group_id)
+    .bind(since)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(|row| StoredCommit {
+        sequence: row.get("sequence"),
+        epoch: row.get("epoch"),
+        message: hex::encode(row.get::<Vec<u8>, _>("message")),
+        created_at: row.get("created_at"),
+    })
+    .collect();
+    Ok(CommitBacklog {
+        group: TrackedGroup {
+            group_id: hex::encode(group_id),
+            epoch: group.get("epoch"),
+            verified: group.get("verified"),
+        },
+        last_sequence: group.get("last_sequence"),
+        commits,
+    })
+}
+
+/// Stores a Welcome (a TLS-encoded MLS message) for each new member it
+/// carries secrets for, tagged with `app_group_id` when the directory
+/// posts it. Returns their key package refs, hex-encoded.
+pub async fn store_welcome(
+    pool: &PgPool,
+    welcome_message: &[u8],
+    app_group_id: Option<&str>,
+) -> Result<Vec<String>> {
+    let welcome = match MlsMessageIn::tls_deserialize_exact(welcome_message)
+        .map_err(|e| {
+            coded(
+                ErrorCode::InvalidRequest,
+                format!("welcome must be a TLS-encoded MLS message: {:?}", e),
+            )
+        })?
+        .extract()
+    {
+        MlsMessageBodyIn::Welcome(welcome) => welcome,
+        _ => {
+            return Err(coded(
+                ErrorCode::InvalidRequest,
+                "welcome must be an MLS Welcome message",
+            ))
+        }
+    };
+    let recipients: Vec<Vec<u8>> = welcome
+        .secrets()
+        .iter()
+        .map(|secrets| secrets.new_member().as_slice().to_vec())
+        .collect();
+    if recipients.is_empty() {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "welcome must name at least one new member",
+        ));
+    }
+
+    let mut tx = pool.begin().await?;
+    for key_package_ref in &recipients {
+        sqlx::query(
+            "INSERT INTO mls_ds_welcomes (key_package_ref, welcome, app_group_id)
+             VALUES ($1, $2, $3)",
+        )
+        .bind(key_package_ref)
+        .bind(welcome_message)
+        .bind(app_group_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(recipients.iter().map(hex::encode).collect())
+}
+
+/// Welcomes stored for a key package ref, oldest first: those posted for
+/// `app_group_id`, or the untagged ones when it is `None`.
+pub async fn get_welcomes(
+    pool: &PgPool,
+    key_package_ref: &[u8],
+    app_group_id: Option<&str>,
+) -> Result<Vec<StoredWelcome>> {
+    Ok(sqlx::query(
+        "SELECT id, key_package_ref, app_group_id, welcome, created_at FROM mls_ds_welcomes
+         WHERE key_package_ref = $1 AND app_group_id IS NOT DISTINCT FROM $2
+         ORDER BY created_at, id",
+    )
+    .bind(key_package_ref)
+    .bind(app_group_id)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(|row| StoredWelcome {
+        id: row.get("id"),
+        key_package_ref: hex::encode(row.get::<Vec<u8>, _>("key_package_ref")),
+        app_group_id: row.get("app_group_id"),
+        welcome: hex::encode(row.get::<Vec<u8>, _>("welcome")),
+        created_at: row.get("created_at"),
+    })
+    .collect())
+}
@@ -0,0 +1,359 @@
+//! End-to-end test of the browser's MLS client against the delivery service
+//!
+//! Two native builds of `openmls_wasm::MlsClient`, the client the frontend
+//! runs as WASM, talk to the real router the way the frontend does: commits
+//! are sequenced through /mls/groups/:group_id/commits, the GroupInfo and
+//! Welcome go through the group directory, and application messages through
+//! the mailbox. Each step checks that the clients' epochs and the server's
+//! commit sequence agree.
+//!
+//! The key package comes straight from the joiner's client; the key package
+//! directory belongs to the Node backend.
+
+use crate::api_contract_tests::{call, send, test_app, TEST_TOKEN};
+use crate::integration_tests::{cleanup_test_database, create_test_users, setup_test_database};
+use crate::{group_directory, mailbox, mls_delivery};
+use anyhow::{anyhow, Result};
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use openmls_wasm::MlsClient;
+use serde_json::{json, Value};
+
+const APP_GROUP_ID: &str = "e2e-chat";
+
+fn client(name: &str) -> Result<MlsClient> {
+    let mut client = MlsClient::new();
+    client
+        .create_identity(name)
+        .map_err(|_| anyhow!("creating identity {} failed", name))?;
+    Ok(client)
+}
+
+fn field<'a>(body: &'a Value, pointer: &str) -> Result<&'a Value> {
+    body.pointer(pointer)
+        .ok_or_else(|| anyhow!("{} missing from {}", pointer, body))
+}
+
+fn hex_field(body: &Value, pointer: &str) -> Result<Vec<u8>> {
+    let value = field(body, pointer)?
+        .as_str()
+        .ok_or_else(|| anyhow!("{} is not a string in {}", pointer, body))?;
+    Ok(hex::decode(value)?)
+}
+
+/// Submits a commit for sequencing; returns the status and response body.
+async fn submit_commit(
+    app: &Router,
+    group_id: &[u8],
+    commit: &[u8],
+) -> Result<(StatusCode, Value)> {
+    let uri = format!("/mls/groups/{}/commits", hex::encode(group_id));
+    let body = json!({ "message": hex::encode(commit) });
+    call(app, "POST", &uri, Some(body), true).await
+}
+
+/// Commits after `since`, as (sequence, message) pairs.
+async fn commits_since(app: &Router, group_id: &[u8], since: i64) -> Result<Vec<(i64, Vec<u8>)>> {
+    let uri = format!(
+        "/mls/groups/{}/commits?since={}",
+        hex::encode(group_id),
+        since
+    );
+    let (status, body) = call(app, "GET", &uri, None, true).await?;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    field(&body, "/commits")?
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|commit| {
+            Ok((
+                field(commit, "/sequence")?.as_i64().unwrap_or(0),
+                hex_field(commit, "/message")?,
+            ))
+        })
+        .collect()
+}
+
+/// Applies every commit after `since` to `client`'s copy of the group.
+async fn catch_up(
+    app: &Router,
+    client: &mut MlsClient,
+    group_id: &[u8],
+    since: i64,
+) -> Result<i64> {
+    let mut last = since;
+    for (sequence, commit) in commits_since(app, group_id, since).await? {
+        client
+            .process_commit_native(group_id, &commit)
+            .map_err(|e| anyhow!("commit {} failed to process: {}", sequence, e))?;
+        client
+            .merge_staged_commit(group_id)
+            .map_err(|_| anyhow!("commit {} failed to merge", sequence))?;
+        last = sequence;
+    }
+    Ok(last)
+}
+
+async fn publish_group_info(
+    app: &Router,
+    client: &MlsClient,
+    group_id: &[u8],
+    user_id: i32,
+) -> Result<Value> {
+    let group_info = client
+        .export_group_info(group_id, true)
+        .map_err(|_| anyhow!("exporting GroupInfo failed"))?;
+    let uri = format!("/directory/groups/{}", APP_GROUP_ID);
+    let body = json!({ "user_id": user_id, "group_info": hex::encode(group_info) });
+    let (status, body) = call(app, "POST", &uri, Some(body), true).await?;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    Ok(body)
+}
+
+/// A directory GET as `user_id`, presenting `token` when given.
+async fn directory_get(
+    app: &Router,
+    uri: &str,
+    user_id: i32,
+    token: Option<&str>,
+) -> Result<(StatusCode, Value)> {
+    let mut builder = Request::builder()
+        .method("GET")
+        .uri(format!("{}?user_id={}", uri, user_id))
+        .header("x-engine-token", TEST_TOKEN);
+    if let Some(token) = token {
+        builder = builder.header("x-invite-token", token);
+    }
+    send(app, builder, None).await
+}
+
+async fn send_message(
+    app: &Router,
+    sender: &mut MlsClient,
+    group_id: &[u8],
+    sender_id: i32,
+    recipient_id: i32,
+    plaintext: &[u8],
+) -> Result<()> {
+    let message = sender
+        .encrypt_message(group_id, plaintext)
+        .map_err(|_| anyhow!("encrypting failed"))?;
+    let body = json!({
+        "sender_user_id": sender_id,
+        "recipient_user_ids": [recipient_id],
+        "group_id": hex::encode(group_id),
+        "message": hex::encode(message),
+    });
+    let (status, body) = call(app, "POST", "/messages/enqueue", Some(body), true).await?;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    Ok(())
+}
+
+/// Fetches, decrypts and acks the user's pending messages.
+async fn receive_messages(
+    app: &Router,
+    recipient: &mut MlsClient,
+    group_id: &[u8],
+    user_id: i32,
+) -> Result<Vec<Result<Vec<u8>, String>>> {
+    let (status, page) = call(
+        app,
+        "GET",
+        &format!("/users/{}/mailbox", user_id),
+        None,
+        true,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK, "{}", page);
+    let mut received = Vec::new();
+    let mut ids = Vec::new();
+    for message in field(&page, "/messages")?.as_array().into_iter().flatten() {
+        assert_eq!(hex_field(message, "/group_id")?, group_id);
+        received.push(recipient.decrypt_message_native(group_id, &hex_field(message, "/message")?));
+        ids.push(field(message, "/id")?.clone());
+    }
+    if !ids.is_empty() {
+        let uri = format!("/users/{}/mailbox/ack", user_id);
+        let (status, body) =
+            call(app, "POST", &uri, Some(json!({ "message_ids": ids })), true).await?;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+    }
+    Ok(received)
+}
+
+#[tokio::test]
+async fn mls_clients_agree_with_delivery_service_sequencing() -> Result<()> {
+    let test_db = setup_test_database().await?;
+    let pool = test_db.pool.clone();
+    mls_delivery::ensure_delivery_tables(&pool).await?;
+    mailbox::ensure_mailbox_table(&pool).await?;
+    group_directory::ensure_directory_tables(&pool).await?;
+    let users = create_test_users(&pool, 2).await?;
+    let (alice_id, bob_id) = (users[0].id, users[1].id);
+    let app = test_app(pool.clone());
+
+    let mut alice = client("alice")?;
+    let mut bob = client("bob")?;
+    let group_id = alice
+        .create_group(b"e2e-group")
+        .map_err(|_| anyhow!("creating group failed"))?;
+    let listing = publish_group_info(&app, &alice, &group_id, alice_id).await?;
+    assert_eq!(field(&listing, "/group/epoch")?, 0);
+
+    // Alice adds Bob: the commit is sequenced, the Welcome waits in the directory
+    let uri = format!("/directory/groups/{}/invites", APP_GROUP_ID);
+    let body = json!({ "user_id": alice_id, "invitee_user_id": bob_id });
+    let (status, invite) = call(&app, "POST", &uri, Some(body), true).await?;
+    assert_eq!(status, StatusCode::OK, "{}", invite);
+    let token = field(&invite, "/invite/token")?
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    let key_package = bob
+        .get_key_package_bytes()
+        .map_err(|_| anyhow!("no key package"))?;
+    let added = alice
+        .add_member_native(&group_id, &key_package)
+        .map_err(|e| anyhow!(e))?;
+    let (status, body) = submit_commit(&app, &group_id, &added.commit).await?;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(field(&body, "/accepted/sequence")?, 1);
+    assert_eq!(field(&body, "/accepted/next_epoch")?, 1);
+    alice
+        .merge_pending_commit(&group_id)
+        .map_err(|_| anyhow!("merging the add failed"))?;
+    let uri = format!("/directory/groups/{}/welcomes", APP_GROUP_ID);
+    let welcome = added
+        .welcome
+        .ok_or_else(|| anyhow!("add produced no Welcome"))?;
+    let body = json!({ "user_id": alice_id, "welcome": hex::encode(&welcome) });
+    let (status, body) = call(&app, "POST", &uri, Some(body), true).await?;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let key_package_ref = field(&body, "/key_package_refs/0")?
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    publish_group_info(&app, &alice, &group_id, alice_id).await?;
+
+    // Bob needs his invite to see the listing and his Welcome
+    let listing_uri = format!("/directory/groups/{}", APP_GROUP_ID);
+    let welcomes_uri = format!("{}/welcomes/{}", listing_uri, key_package_ref);
+    let (status, _) = directory_get(&app, &welcomes_uri, bob_id, None).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, listing) = directory_get(&app, &listing_uri, bob_id, Some(&token)).await?;
+    assert_eq!(status, StatusCode::OK, "{}", listing);
+    assert_eq!(field(&listing, "/epoch")?, 1);
+    let (status, welcomes) = directory_get(&app, &welcomes_uri, bob_id, None).await?;
+    assert_eq!(status, StatusCode::OK, "{}", welcomes);
+    let joined = bob
+        .process_welcome_native(&hex_field(&welcomes, "/welcomes/0/welcome")?, None)
+        .map_err(|e| anyhow!(e))?;
+    assert_eq!(joined, group_id);
+    assert_eq!(bob.get_group_epoch(&group_id).ok(), Some(1));
+
+    // Messages flow both ways through the mailboxes
+    send_message(&app, &mut alice, &group_id, alice_id, bob_id, b"hi bob").await?;
+    let received = receive_messages(&app, &mut bob, &group_id, bob_id).await?;
+    assert_eq!(received, vec![Ok(b"hi bob".to_vec())]);
+    send_message(&app, &mut bob, &group_id, bob_id, alice_id, b"hi alice").await?;
+    let received = receive_messages(&app, &mut alice, &group_id, alice_id).await?;
+    assert_eq!(received, vec![Ok(b"hi alice".to_vec())]);
+    assert!(receive_messages(&app, &mut bob, &group_id, bob_id)
+        .await?
+        .is_empty());
+
+    // Bob rotates his keys; Alice applies the commit from the backlog
+    let update = bob.self_update_native(&group_id).map_err(|e| anyhow!(e))?;
+    assert!(update.welcome.is_none());
+    let (status, body) = submit_commit(&app, &group_id, &update.commit).await?;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(field(&body, "/accepted/sequence")?, 2);
+    bob.merge_pending_commit(&group_id)
+        .map_err(|_| anyhow!("merging the update failed"))?;
+    let alice_seen = catch_up(&app, &mut alice, &group_id, 1).await?;
+    assert_eq!(alice_seen, 2);
+    assert_eq!(alice.get_group_epoch(&group_id).ok(), Some(2));
+    assert_eq!(
+        alice.get_group_confirmation_tag(&group_id).ok(),
+        bob.get_group_confirmation_tag(&group_id).ok()
+    );
+    send_message(&app, &mut bob, &group_id, bob_id, alice_id, b"rotated").await?;
+    let received = receive_messages(&app, &mut alice, &group_id, alice_id).await?;
+    assert_eq!(received, vec![Ok(b"rotated".to_vec())]);
+
+    // Commits race: Bob's lands first, so Alice's stale one is refused and
+    // she retries once she has caught up
+    let leaf = bob
+        .get_own_leaf_index(&group_id)
+        .map_err(|_| anyhow!("no leaf index"))?;
+    let stale = alice
+        .remove_member_native(&group_id, leaf)
+        .map_err(|e| anyhow!(e))?;
+    let update = bob.self_update_native(&group_id).map_err(|e| anyhow!(e))?;
+    let (status, body) = submit_commit(&app, &group_id, &update.commit).await?;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(field(&body, "/accepted/sequence")?, 3);
+    bob.merge_pending_commit(&group_id)
+        .map_err(|_| anyhow!("merging the update failed"))?;
+    let (status, body) = submit_commit(&app, &group_id, &stale.commit).await?;
+    assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+    alice
+        .clear_pending_commit(&group_id)
+        .map_err(|_| anyhow!("clearing the stale commit failed"))?;
+    let alice_seen = catch_up(&app, &mut alice, &group_id, alice_seen).await?;
+    assert_eq!(alice_seen, 3);
+
+    // Alice removes Bob; he learns it from the backlog and can't read on
+    let removal = alice
+        .remove_member_native(&group_id, leaf)
+        .map_err(|e| anyhow!(e))?;
+    let (status, body) = submit_commit(&app, &group_id, &removal.commit).await?;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(field(&body, "/accepted/sequence")?, 4);
+    assert_eq!(field(&body, "/accepted/next_epoch")?, 4);
+    alice
+        .merge_pending_commit(&group_id)
+        .map_err(|_| anyhow!("merging the removal failed"))?;
+    // Bob's last commit was sequence 3
+    let commits = commits_since(&app, &group_id, 3).await?;
+    assert_eq!(commits.len(), 1);
+    let summary = bob
+        .process_commit_native(&group_id, &commits[0].1)
+        .map_err(|e| anyhow!(e))?;
+    assert!(summary.self_removed);
+    assert_eq!(summary.removes.len(), 1);
+    assert_eq!(summary.removes[0].leaf_index, leaf);
+    bob.merge_staged_commit(&group_id)
+        .map_err(|_| anyhow!("merging the removal failed"))?;
+    send_message(&app, &mut alice, &group_id, alice_id, bob_id, b"bye").await?;
+    let received = receive_messages(&app, &mut bob, &group_id, bob_id).await?;
+    assert_eq!(received.len(), 1);
+    assert!(received[0].is_err());
+
+    // The whole backlog is in order, one epoch per commit
+    let sequences: Vec<i64> = commits_since(&app, &group_id, 0)
+        .await?
+        .iter()
+        .map(|(sequence, _)| *sequence)
+        .collect();
+    assert_eq!(sequences, vec![1, 2, 3, 4]);
+    assert_eq!(alice.get_group_epoch(&group_id).ok(), Some(4));
+
+    // Once the directory's hints drop Bob, he is an outsider there too
+    let group_info = alice
+        .export_group_info(&group_id, true)
+        .map_err(|_| anyhow!("exporting GroupInfo failed"))?;
+    let body = json!({
+        "user_id": alice_id,
+        "group_info": hex::encode(group_info),
+        "member_user_ids": [alice_id],
+    });
+    let (status, body) = call(&app, "POST", &listing_uri, Some(body), true).await?;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(field(&body, "/group/epoch")?, 4);
+    let (status, _) = directory_get(&app, &listing_uri, bob_id, None).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    cleanup_test_database(test_db).await?;
+    Ok(())
+}
@@ -0,0 +1,238 @@
+//! Per-user notification preferences.
+//!
+//! The engine tells users about four things: their markets resolving (or a
+//! resolution being reverted), rank changes from persuasion scoring,
+//! markets they hold closing soon, and their limit orders filling (or
+//! failing). Each goes out on the user's own
+//! WebSocket topic, `user:<id>` (a message with a `"topic"` field; a
+//! connection opened with `/ws?user_id=<id>` gets only its own user's), and
+//! the matching webhook lists who was told in `notify_user_ids`. Users turn
+//! each kind off in `notification_preferences` through
+//! `GET`/`PUT /user/:id/preferences`; users without a row get all four.
+
+use crate::api_error::{coded, ErrorCode};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Notification {
+    Resolution,
+    RankChange,
+    ClosingReminder,
+    OrderFill,
+}
+
+impl Notification {
+    fn column(self) -> &'static str {
+        match self {
+            Notification::Resolution => "resolutions",
+            Notification::RankChange => "rank_changes",
+            Notification::ClosingReminder => "closing_reminders",
+            Notification::OrderFill => "order_fills",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, sqlx::FromRow)]
+pub struct Preferences {
+    pub resolutions: bool,
+    pub rank_changes: bool,
+    pub closing_reminders: bool,
+    pub order_fills: bool,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            resolutions: true,
+            rank_changes: true,
+            closing_reminders: true,
+            order_fills: true,
+        }
+    }
+}
+
+/// A partial update; fields left out keep their current setting.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PreferencesUpdate {
+    pub resolutions: Option<bool>,
+    pub rank_changes: Option<bool>,
+    pub closing_reminders: Option<bool>,
+    pub order_fills: Option<bool>,
+}
+
+pub async fn ensure_preferences_table(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS notification_preferences (
+            user_id INTEGER PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+            resolutions BOOLEAN NOT NULL DEFAULT TRUE,
+            rank_changes BOOLEAN NOT NULL DEFAULT TRUE,
+            closing_reminders BOOLEAN NOT NULL DEFAULT TRUE,
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "ALTER TABLE notification_preferences
+         ADD COLUMN IF NOT EXISTS order_fills BOOLEAN NOT NULL DEFAULT TRUE",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn ensure_user(pool: &PgPool, user_id: i32) -> Result<()> {
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Err(coded(ErrorCode::NotFound, "User not found"));
+    }
+    Ok(())
+}
+
+pub async fn get_preferences(pool: &PgPool, user_id: i32) -> Result<Preferences> {
+    ensure_user(pool, user_id).await?;
+    Ok(sqlx::query_as(
+        "SELECT resolutions, rank_changes, closing_reminders, order_fills
+         FROM notification_preferences WHERE user_id = $1",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?
+    .unwrap_or_default())
+}
+
+pub async fn update_preferences(
+    pool: &PgPool,
+    user_id: i32,
+    update: &PreferencesUpdate,
+) -> Result<Preferences> {
+    ensure_user(pool, user_id).await?;
+    Ok(sqlx::query_as(
+        r#"
+        INSERT INTO notification_preferences
+            (user_id, resolutions, rank_changes, closing_reminders, order_fills)
+        VALUES ($1, COALESCE($2, TRUE), COALESCE($3, TRUE), COALESCE($4, TRUE), COALESCE($5, TRUE))
+        ON CONFLICT (user_id) DO UPDATE
+        SET resolutions = COALESCE($2, notification_preferences.resolutions),
+            rank_changes = COALESCE($3, notification_preferences.rank_changes),
+            closing_reminders = COALESCE($4, notification_preferences.closing_reminders),
+            order_fills = COALESCE($5, notification_preferences.order_fills),
+            updated_at = NOW()
+        RETURNING resolutions, rank_changes, closing_reminders, order_fills
+        "#,
+    )
+    .bind(user_id)
+    .bind(update.resolutions)
+    .bind(update.rank_changes)
+    .bind(update.closing_reminders)
+    .bind(update.order_fills)
+    .fetch_one(pool)
+    .await?)
+}
+
+/// The users among `user_ids` who take `kind`, deduplicated and in order.
+/// Databases without the table have everyone opted in.
+pub async fn recipients(pool: &PgPool, kind: Notification, user_ids: &[i32]) -> Result<Vec<i32>> {
+    if user_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let table_exists: bool =
+        sqlx::query_scalar("SELECT to_regclass('notification_preferences') IS NOT NULL")
+            .fetch_one(pool)
+            .await?;
+    if !table_exists {
+        let mut ids = user_ids.to_vec();
+        ids.sort_unstable();
+        ids.dedup();
+        return Ok(ids);
+    }
+    Ok(sqlx::query_scalar(&format!(
+        "SELECT DISTINCT u.id FROM UNNEST($1::int[]) AS u(id)
+         LEFT JOIN notification_preferences np ON np.user_id = u.id
+         WHERE COALESCE(np.{}, TRUE)
+         ORDER BY u.id",
+        kind.column()
+    ))
+    .bind(user_ids)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Authors of the posts behind persuasion episodes, whose rank they move,
+/// each with their episodes.
+pub async fn episode_authors(pool: &PgPool, episode_ids: &[i32]) -> Result<Vec<(i32, Vec<i32>)>> {
+    if episode_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(sqlx::query_as(
+        "SELECT p.user_id, ARRAY_AGG(pse.id ORDER BY pse.id)
+         FROM post_signal_episodes pse
+         JOIN posts p ON p.id = pse.post_id
+         WHERE pse.id = ANY($1)
+         GROUP BY p.user_id
+         ORDER BY p.user_id",
+    )
+    .bind(episode_ids)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// The topic a user's own notifications are sent on.
+pub fn user_topic(user_id: i32) -> String {
+    format!("user:{}", user_id)
+}
+
+/// The user a topic from `user_topic` belongs to.
+pub fn topic_user_id(topic: &str) -> Option<i32> {
+    topic.strip_prefix("user:")?.parse().ok()
+}
+
+/// Whether a connection narrowed to `topic` should get `msg`: anything
+/// without a topic, and messages on its own.
+pub fn delivers_to(msg: &str, topic: &str) -> bool {
+    if !msg.contains("\"topic\"") {
+        return true;
+    }
+    match serde_json::from_str::<Value>(msg) {
+        Ok(parsed) => match parsed["topic"].as_str() {
+            Some(msg_topic) => msg_topic == topic,
+            None => true,
+        },
+        Err(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn narrowed_connections_only_get_their_own_user_topic() {
+        let topic = user_topic(7);
+        let broadcast = json!({ "type": "market_closed", "data": { "event_id": 1 } });
+        let own = json!({ "type": "marketResolved", "topic": "user:7", "data": {} });
+        let other = json!({ "type": "marketResolved", "topic": "user:70", "data": {} });
+        assert!(delivers_to(&broadcast.to_string(), &topic));
+        assert!(delivers_to(&own.to_string(), &topic));
+        assert!(!delivers_to(&other.to_string(), &topic));
+        // Only a top-level topic narrows delivery
+        let nested = json!({ "type": "x", "data": { "topic": "user:70" } });
+        assert!(delivers_to(&nested.to_string(), &topic));
+    }
+
+    #[test]
+    fn user_topics_name_their_user() {
+        assert_eq!(topic_user_id(&user_topic(7)), Some(7));
+        assert_eq!(topic_user_id("ranks"), None);
+        assert_eq!(topic_user_id("user:x"), None);
+    }
+}
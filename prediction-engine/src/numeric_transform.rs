@@ -4,6 +4,7 @@
 //! Internal coordinate t is in [0,1]; bins are equal-width in t.
 
 use anyhow::{anyhow, Result};
+use serde::Serialize;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct NumericTransform {
@@ -82,6 +83,63 @@ impl NumericTransform {
     }
 }
 
+/// Summary of an implied distribution, in nominal units. Inbound mass is
+/// spread uniformly in t within each bin; quantiles that fall in a tail are
+/// `None`, since a tail has no upper (or lower) edge to interpolate toward.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DistributionSummary {
+    /// Mean of the in-range mass, bins taken at their t midpoints.
+    pub mean: Option<f64>,
+    pub median: Option<f64>,
+    pub p10: Option<f64>,
+    pub p25: Option<f64>,
+    pub p75: Option<f64>,
+    pub p90: Option<f64>,
+    pub below_range: f64,
+    pub above_range: f64,
+}
+
+impl NumericTransform {
+    /// `inbound` holds the bin probabilities in bin order; the tails are the
+    /// open-bound outcomes' probabilities (0 when the bound is closed).
+    pub fn summarize(&self, lower_tail: f64, inbound: &[f64], upper_tail: f64) -> DistributionSummary {
+        let n = inbound.len() as f64;
+        let in_range: f64 = inbound.iter().sum();
+        let mean = (in_range > 0.0).then(|| {
+            inbound
+                .iter()
+                .enumerate()
+                .map(|(i, p)| p * self.to_nominal((i as f64 + 0.5) / n))
+                .sum::<f64>()
+                / in_range
+        });
+        let quantile = |q: f64| -> Option<f64> {
+            let mut cumulative = lower_tail;
+            if q < cumulative {
+                return None;
+            }
+            for (i, p) in inbound.iter().enumerate() {
+                if *p > 0.0 && q <= cumulative + p {
+                    let within = ((q - cumulative) / p).clamp(0.0, 1.0);
+                    return Some(self.to_nominal((i as f64 + within) / n));
+                }
+                cumulative += p;
+            }
+            None
+        };
+        DistributionSummary {
+            mean,
+            median: quantile(0.5),
+            p10: quantile(0.1),
+            p25: quantile(0.25),
+            p75: quantile(0.75),
+            p90: quantile(0.9),
+            below_range: lower_tail,
+            above_range: upper_tail,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BucketKind {
     Inbound,
@@ -310,6 +368,35 @@ mod tests {
         assert_eq!(pick_winning_outcome(&overlapping, 1.0), Some(1));
     }
 
+    #[test]
+    fn summary_interpolates_quantiles_within_bins() {
+        // Four bins of width 1 on 0..4; half the mass in [1,2), half in [2,3).
+        let s = lin_tf().summarize(0.0, &[0.0, 0.5, 0.5, 0.0], 0.0);
+        assert!((s.median.unwrap() - 2.0).abs() < 1e-12);
+        assert!((s.p25.unwrap() - 1.5).abs() < 1e-12);
+        assert!((s.p90.unwrap() - 2.8).abs() < 1e-12);
+        assert!((s.mean.unwrap() - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn summary_quantiles_in_tails_are_none() {
+        let s = lin_tf().summarize(0.2, &[0.15, 0.15, 0.15, 0.15], 0.2);
+        assert_eq!(s.p10, None);
+        assert_eq!(s.p90, None);
+        assert!((s.median.unwrap() - 2.0).abs() < 1e-12);
+        assert_eq!((s.below_range, s.above_range), (0.2, 0.2));
+        // The mean covers only the in-range mass.
+        assert!((s.mean.unwrap() - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn summary_on_log_scale_reports_nominal_units() {
+        // Uniform in t over 1..10000 (log scale): the median is 100.
+        let s = log_tf().summarize(0.0, &[0.25; 4], 0.0);
+        assert!((s.median.unwrap() - 100.0).abs() < 1e-6);
+        assert!((s.p25.unwrap() - 10.0).abs() < 1e-6);
+    }
+
     fn tailed_rows() -> Vec<(i64, BucketKind, Option<f64>, Option<f64>)> {
         vec![
             (1, BucketKind::Inbound, Some(0.0), Some(10.0)),
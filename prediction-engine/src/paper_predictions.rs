@@ -0,0 +1,259 @@
+// Paper predictions: unstaked forecasts on events that have already
+// resolved, scored immediately against the known outcome. New users can
+// practice on historical (e.g. backfilled Metaculus) questions without
+// touching their RP balance, and because every paper prediction is scored
+// next to the market's own final probability we get a known-answer check
+// on the scoring pipeline.
+//
+// v1 scope: binary events only (outcome resolved_yes / resolved_no). One
+// paper prediction per user per event; no edits once scored.
+
+use crate::api_error::{coded, ErrorCode};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::{PgPool, Row};
+
+pub use intellacc_math::scoring::{brier_score, log_score};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PaperPredictionResult {
+    pub id: i64,
+    pub user_id: i32,
+    pub event_id: i32,
+    pub probability: f64,
+    pub outcome: bool,
+    pub brier_score: f64,
+    pub log_score: f64,
+    /// Final market probability of the event and its score, as a baseline.
+    pub market_prob: Option<f64>,
+    pub market_brier_score: Option<f64>,
+    pub created_at: DateTime<Utc>,
+}
+
+async fn ensure_paper_prediction_tables(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS paper_predictions (
+            id BIGSERIAL PRIMARY KEY,
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+            probability DOUBLE PRECISION NOT NULL,
+            outcome BOOLEAN NOT NULL,
+            brier_score DOUBLE PRECISION NOT NULL,
+            log_score DOUBLE PRECISION NOT NULL,
+            market_prob DOUBLE PRECISION,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            UNIQUE (user_id, event_id)
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn submit_paper_prediction(
+    pool: &PgPool,
+    user_id: i32,
+    event_id: i32,
+    probability: f64,
+) -> Result<PaperPredictionResult> {
+    if !probability.is_finite() || !(0.0..=1.0).contains(&probability) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "probability must be between 0 and 1",
+        ));
+    }
+    ensure_paper_prediction_tables(pool).await?;
+
+    let user_exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM users WHERE id = $1)")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+    if !user_exists {
+        return Err(coded(ErrorCode::NotFound, "User not found"));
+    }
+
+    let event = sqlx::query(
+        "SELECT outcome, COALESCE(event_type, 'binary') AS event_type, market_prob FROM events WHERE id = $1",
+    )
+    .bind(event_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| coded(ErrorCode::NotFound, "Event not found"))?;
+
+    let event_type: String = event.get("event_type");
+    if event_type != "binary" {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "Paper predictions only support binary events",
+        ));
+    }
+    let outcome = match event.get::<Option<String>, _>("outcome").as_deref() {
+        Some("resolved_yes") => true,
+        Some("resolved_no") => false,
+        Some(crate::lmsr_api::OUTCOME_NOT_APPLICABLE) => {
+            return Err(coded(
+                ErrorCode::InvalidRequest,
+                "Event resolved N/A and can't be scored",
+            ))
+        }
+        _ => {
+            return Err(coded(
+                ErrorCode::InvalidRequest,
+                "Event is not resolved yet",
+            ))
+        }
+    };
+    let market_prob: Option<f64> = event.get("market_prob");
+
+    let brier = brier_score(probability, outcome);
+    let log = log_score(probability, outcome);
+    let row = sqlx::query(
+        r#"
+        INSERT INTO paper_predictions
+            (user_id, event_id, probability, outcome, brier_score, log_score, market_prob)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (user_id, event_id) DO NOTHING
+        RETURNING id, created_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(event_id)
+    .bind(probability)
+    .bind(outcome)
+    .bind(brier)
+    .bind(log)
+    .bind(market_prob)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| {
+        coded(
+            ErrorCode::InvalidRequest,
+            "Paper prediction already recorded for this event",
+        )
+    })?;
+
+    Ok(PaperPredictionResult {
+        id: row.get("id"),
+        user_id,
+        event_id,
+        probability,
+        outcome,
+        brier_score: brier,
+        log_score: log,
+        market_prob,
+        market_brier_score: market_prob.map(|p| brier_score(p, outcome)),
+        created_at: row.get("created_at"),
+    })
+}
+
+/// Re-scores an event's paper predictions after it (re-)resolves; only a
+/// disputed resolution leaves any to update. An N/A re-resolution (`None`)
+/// leaves nothing to score them against, so they are dropped.
+pub(crate) async fn rescore_event(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    event_id: i32,
+    outcome: Option<bool>,
+) -> Result<()> {
+    let table_exists: bool =
+        sqlx::query_scalar("SELECT to_regclass('paper_predictions') IS NOT NULL")
+            .fetch_one(&mut **tx)
+            .await?;
+    if !table_exists {
+        return Ok(());
+    }
+    let Some(outcome) = outcome else {
+        sqlx::query("DELETE FROM paper_predictions WHERE event_id = $1")
+            .bind(event_id)
+            .execute(&mut **tx)
+            .await?;
+        return Ok(());
+    };
+
+    let rows = sqlx::query(
+        "SELECT id, probability FROM paper_predictions WHERE event_id = $1 AND outcome <> $2",
+    )
+    .bind(event_id)
+    .bind(outcome)
+    .fetch_all(&mut **tx)
+    .await?;
+    for row in rows {
+        let probability: f64 = row.get("probability");
+        sqlx::query(
+            "UPDATE paper_predictions SET outcome = $2, brier_score = $3, log_score = $4 WHERE id = $1",
+        )
+        .bind(row.get::<i64, _>("id"))
+        .bind(outcome)
+        .bind(brier_score(probability, outcome))
+        .bind(log_score(probability, outcome))
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(())
+}
+
+/// A user's paper predictions (newest first) with mean scores against the
+/// market baseline on the same events.
+pub async fn get_user_paper_predictions(pool: &PgPool, user_id: i32, limit: i64) -> Result<Value> {
+    ensure_paper_prediction_tables(pool).await?;
+    let limit = limit.clamp(1, 500);
+    let rows = sqlx::query(
+        r#"
+        SELECT pp.id, pp.event_id, e.title, pp.probability, pp.outcome,
+               pp.brier_score, pp.log_score, pp.market_prob, pp.created_at
+        FROM paper_predictions pp
+        JOIN events e ON e.id = pp.event_id
+        WHERE pp.user_id = $1
+        ORDER BY pp.created_at DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    let summary = sqlx::query(
+        r#"
+        SELECT COUNT(*)::bigint AS count,
+               AVG(brier_score) AS mean_brier,
+               AVG(log_score) AS mean_log,
+               AVG(POWER(market_prob - CASE WHEN outcome THEN 1.0 ELSE 0.0 END, 2))
+                   AS market_mean_brier
+        FROM paper_predictions
+        WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    let predictions: Vec<Value> = rows
+        .into_iter()
+        .map(|row| {
+            json!({
+                "id": row.get::<i64, _>("id"),
+                "event_id": row.get::<i32, _>("event_id"),
+                "title": row.get::<String, _>("title"),
+                "probability": row.get::<f64, _>("probability"),
+                "outcome": row.get::<bool, _>("outcome"),
+                "brier_score": row.get::<f64, _>("brier_score"),
+                "log_score": row.get::<f64, _>("log_score"),
+                "market_prob": row.get::<Option<f64>, _>("market_prob"),
+                "created_at": row.get::<DateTime<Utc>, _>("created_at"),
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "user_id": user_id,
+        "count": summary.get::<i64, _>("count"),
+        "mean_brier_score": summary.get::<Option<f64>, _>("mean_brier"),
+        "mean_log_score": summary.get::<Option<f64>, _>("mean_log"),
+        "market_mean_brier_score": summary.get::<Option<f64>, _>("market_mean_brier"),
+        "predictions": predictions,
+    }))
+}
@@ -0,0 +1,286 @@
+//! Crowd statistics behind the forecast peer bonus.
+//!
+//! A forecast's peer score is its time-weighted log score minus the crowd's
+//! on the same event, so it is positive when the forecaster beat the crowd.
+//! An unweighted crowd can be dragged down by a swarm of throwaway accounts
+//! forecasting badly, which hands everyone else a bonus (the swarm's owner
+//! included). So the crowd only counts forecasters whose account was at
+//! least `MIN_ACCOUNT_AGE_DAYS` old when they first forecast and who have
+//! `MIN_RESOLVED_FORECASTS` resolved forecasts on other events, and weights
+//! each by reputation: RP held plus RP staked, the engine's reputation
+//! measure. Excluded forecasters still get a peer score; they just don't
+//! move the crowd.
+//!
+//! The stats are computed once, when the event resolves, into
+//! `event_score_stats`, and dropped again if a dispute reverts the
+//! resolution.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::{PgPool, Postgres, Row, Transaction};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::forecasts::{score_slices, time_weighted};
+use crate::lmsr_core::from_ledger_units;
+
+pub const MIN_ACCOUNT_AGE_DAYS: i64 = 7;
+pub const MIN_RESOLVED_FORECASTS: i64 = 3;
+
+/// One forecaster's contribution to an event's crowd.
+#[derive(Debug, Clone)]
+pub struct CrowdEntry {
+    pub log_score: f64,
+    pub brier_score: f64,
+    pub weight: f64,
+    pub eligible: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CrowdStats {
+    /// Reputation-weighted mean over eligible forecasters; `None` when no
+    /// eligible forecaster carries any weight.
+    pub crowd_log_score: Option<f64>,
+    pub crowd_brier_score: Option<f64>,
+    pub total_weight: f64,
+    pub eligible_forecasters: i32,
+    pub excluded_forecasters: i32,
+}
+
+/// Old enough at the first forecast, with enough of a resolved record.
+pub fn is_eligible(
+    account_created_at: Option<DateTime<Utc>>,
+    first_forecast_at: DateTime<Utc>,
+    resolved_forecasts: i64,
+) -> bool {
+    account_created_at
+        .is_some_and(|created| first_forecast_at - created >= Duration::days(MIN_ACCOUNT_AGE_DAYS))
+        && resolved_forecasts >= MIN_RESOLVED_FORECASTS
+}
+
+pub fn crowd_stats(entries: &[CrowdEntry]) -> CrowdStats {
+    let counted: Vec<&CrowdEntry> = entries
+        .iter()
+        .filter(|e| e.eligible && e.weight > 0.0 && e.weight.is_finite())
+        .collect();
+    let total_weight: f64 = counted.iter().map(|e| e.weight).sum();
+    let mean = |score: fn(&CrowdEntry) -> f64| {
+        (total_weight > 0.0)
+            .then(|| counted.iter().map(|e| e.weight * score(e)).sum::<f64>() / total_weight)
+    };
+    let eligible = entries.iter().filter(|e| e.eligible).count() as i32;
+    CrowdStats {
+        crowd_log_score: mean(|e| e.log_score),
+        crowd_brier_score: mean(|e| e.brier_score),
+        total_weight,
+        eligible_forecasters: eligible,
+        excluded_forecasters: entries.len() as i32 - eligible,
+    }
+}
+
+pub async fn ensure_stats_table(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS event_score_stats (
+            event_id INTEGER PRIMARY KEY REFERENCES events(id) ON DELETE CASCADE,
+            outcome BOOLEAN NOT NULL,
+            crowd_log_score DOUBLE PRECISION,
+            crowd_brier_score DOUBLE PRECISION,
+            total_weight DOUBLE PRECISION NOT NULL,
+            eligible_forecasters INTEGER NOT NULL,
+            excluded_forecasters INTEGER NOT NULL,
+            computed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Computes and stores the crowd stats of a resolving event's journaled
+/// forecasts (`Some`), or drops them when the resolution is reverted or
+/// N/A (`None`). Databases without the stats table are skipped.
+pub(crate) async fn settle_event(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    outcome: Option<bool>,
+) -> Result<()> {
+    let table_exists: bool =
+        sqlx::query_scalar("SELECT to_regclass('event_score_stats') IS NOT NULL")
+            .fetch_one(&mut **tx)
+            .await?;
+    if !table_exists {
+        return Ok(());
+    }
+    sqlx::query("DELETE FROM event_score_stats WHERE event_id = $1")
+        .bind(event_id)
+        .execute(&mut **tx)
+        .await?;
+    let Some(outcome) = outcome else {
+        return Ok(());
+    };
+
+    let resolved_at: DateTime<Utc> = sqlx::query_scalar(
+        "SELECT COALESCE(resolved_at::timestamptz, NOW()) FROM events WHERE id = $1",
+    )
+    .bind(event_id)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    let mut histories: BTreeMap<i32, Vec<(DateTime<Utc>, f64)>> = BTreeMap::new();
+    for (user_id, at, probability) in sqlx::query_as::<_, (i32, DateTime<Utc>, f64)>(
+        "SELECT user_id, created_at, probability FROM forecast_revisions
+         WHERE event_id = $1
+         ORDER BY user_id, created_at, id",
+    )
+    .bind(event_id)
+    .fetch_all(&mut **tx)
+    .await?
+    {
+        histories
+            .entry(user_id)
+            .or_default()
+            .push((at, probability));
+    }
+
+    let user_ids: Vec<i32> = histories.keys().copied().collect();
+    let forecasters: HashMap<i32, (Option<DateTime<Utc>>, f64, i64)> = sqlx::query(
+        r#"
+        SELECT u.id, u.created_at::timestamptz AS created_at,
+               (COALESCE(u.rp_balance_ledger, 0) + COALESCE(u.rp_staked_ledger, 0))::BIGINT
+                   AS bankroll_ledger,
+               (SELECT COUNT(*) FROM predictions p
+                WHERE p.user_id = u.id AND p.event_id <> $1
+                  AND p.outcome IN ('correct', 'incorrect'))
+                   AS resolved_forecasts
+        FROM users u
+        WHERE u.id = ANY($2)
+        "#,
+    )
+    .bind(event_id)
+    .bind(&user_ids)
+    .fetch_all(&mut **tx)
+    .await?
+    .iter()
+    .map(|row| {
+        (
+            row.get("id"),
+            (
+                row.get("created_at"),
+                from_ledger_units(row.get::<i64, _>("bankroll_ledger") as i128),
+                row.get("resolved_forecasts"),
+            ),
+        )
+    })
+    .collect();
+
+    let entries: Vec<CrowdEntry> = histories
+        .iter()
+        .filter_map(|(user_id, revisions)| {
+            let &(created_at, bankroll, resolved) = forecasters.get(user_id)?;
+            let slices = score_slices(revisions, resolved_at, Some(outcome));
+            Some(CrowdEntry {
+                log_score: time_weighted(&slices, |s| s.log_score)?,
+                brier_score: time_weighted(&slices, |s| s.brier_score)?,
+                weight: bankroll,
+                eligible: is_eligible(created_at, revisions[0].0, resolved),
+            })
+        })
+        .collect();
+    let stats = crowd_stats(&entries);
+
+    sqlx::query(
+        r#"
+        INSERT INTO event_score_stats (
+            event_id, outcome, crowd_log_score, crowd_brier_score, total_weight,
+            eligible_forecasters, excluded_forecasters
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+    )
+    .bind(event_id)
+    .bind(outcome)
+    .bind(stats.crowd_log_score)
+    .bind(stats.crowd_brier_score)
+    .bind(stats.total_weight)
+    .bind(stats.eligible_forecasters)
+    .bind(stats.excluded_forecasters)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// The stored crowd stats of a resolved event, if any were computed.
+pub async fn load_stats(pool: &PgPool, event_id: i32) -> Result<Option<CrowdStats>> {
+    let row = sqlx::query(
+        "SELECT crowd_log_score, crowd_brier_score, total_weight,
+                eligible_forecasters, excluded_forecasters
+         FROM event_score_stats WHERE event_id = $1",
+    )
+    .bind(event_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|row| CrowdStats {
+        crowd_log_score: row.get("crowd_log_score"),
+        crowd_brier_score: row.get("crowd_brier_score"),
+        total_weight: row.get("total_weight"),
+        eligible_forecasters: row.get("eligible_forecasters"),
+        excluded_forecasters: row.get("excluded_forecasters"),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn entry(probability: f64, weight: f64, eligible: bool) -> CrowdEntry {
+        CrowdEntry {
+            log_score: probability.ln(),
+            brier_score: (1.0 - probability).powi(2),
+            weight,
+            eligible,
+        }
+    }
+
+    #[test]
+    fn an_ineligible_swarm_leaves_the_crowd_alone() {
+        let honest = vec![entry(0.8, 1000.0, true), entry(0.6, 3000.0, true)];
+        let expected = (1000.0 * 0.8_f64.ln() + 3000.0 * 0.6_f64.ln()) / 4000.0;
+
+        let mut flooded = honest.clone();
+        flooded.extend((0..200).map(|_| entry(0.01, 1000.0, false)));
+        let stats = crowd_stats(&flooded);
+        assert!((stats.crowd_log_score.unwrap() - expected).abs() < 1e-12);
+        assert_eq!(stats.total_weight, 4000.0);
+        assert_eq!(stats.eligible_forecasters, 2);
+        assert_eq!(stats.excluded_forecasters, 200);
+    }
+
+    #[test]
+    fn low_reputation_accounts_barely_move_the_crowd() {
+        let mut entries = vec![entry(0.8, 10_000.0, true)];
+        entries.extend((0..20).map(|_| entry(0.01, 5.0, true)));
+        let crowd = crowd_stats(&entries).crowd_log_score.unwrap();
+        // An unweighted mean would be below ln(0.02)
+        assert!(crowd > 0.7_f64.ln(), "{}", crowd);
+
+        assert_eq!(crowd_stats(&[entry(0.8, 0.0, true)]).crowd_log_score, None);
+        assert_eq!(crowd_stats(&[]).crowd_log_score, None);
+    }
+
+    #[test]
+    fn eligibility_needs_account_age_and_a_resolved_record() {
+        let first = Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap();
+        let aged = Some(first - Duration::days(MIN_ACCOUNT_AGE_DAYS));
+        assert!(is_eligible(aged, first, MIN_RESOLVED_FORECASTS));
+        assert!(!is_eligible(aged, first, MIN_RESOLVED_FORECASTS - 1));
+        assert!(!is_eligible(
+            Some(first - Duration::hours(1)),
+            first,
+            MIN_RESOLVED_FORECASTS
+        ));
+        assert!(!is_eligible(None, first, MIN_RESOLVED_FORECASTS));
+    }
+}
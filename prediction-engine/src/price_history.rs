@@ -0,0 +1,212 @@
+//! Binary market price history for charts.
+//!
+//! `market_updates` journals buys only, so a chart drawn from it misses
+//! every sell. Each binary trade (buy, sell, limit fill, position close)
+//! now appends its price before and after to `market_price_history` in the
+//! trade's transaction, and on first run the table is backfilled with the
+//! buys already journaled. The table is created at startup, not by the
+//! trades: concurrent `CREATE TABLE IF NOT EXISTS` can fail.
+//!
+//! `GET /events/:id/history?resolution=1h` groups the history into
+//! candles aligned to the resolution: open is the price going into the
+//! bucket's first trade, close the price after its last, and high and low
+//! span every price the market held in between. Buckets without trades are
+//! left out; the price through a gap is the previous bucket's close.
+
+use crate::api_error::{coded, ErrorCode};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool, Row};
+
+pub const DEFAULT_BUCKETS: u32 = 168;
+pub const MAX_BUCKETS: u32 = 1000;
+
+/// Bucket widths the history endpoint accepts.
+pub const RESOLUTIONS: [(&str, i64); 6] = [
+    ("1m", 60),
+    ("5m", 300),
+    ("15m", 900),
+    ("1h", 3600),
+    ("4h", 14_400),
+    ("1d", 86_400),
+];
+
+/// Width in seconds of a resolution like `1h`.
+pub fn parse_resolution(raw: &str) -> Option<i64> {
+    RESOLUTIONS
+        .iter()
+        .find(|(name, _)| *name == raw)
+        .map(|&(_, seconds)| seconds)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PriceBucket {
+    pub start: DateTime<Utc>,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub trades: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PriceHistory {
+    pub event_id: i32,
+    pub resolution: String,
+    /// Start of the oldest bucket in the window
+    pub from: DateTime<Utc>,
+    pub buckets: Vec<PriceBucket>,
+}
+
+/// The table alone, without the backfill.
+pub async fn ensure_price_history_table(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS market_price_history (
+            id BIGSERIAL PRIMARY KEY,
+            event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+            prev_prob DOUBLE PRECISION NOT NULL,
+            prob DOUBLE PRECISION NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_market_price_history_event_created
+         ON market_price_history(event_id, created_at, id)",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// The table, backfilled from the buy journal on first run. Reads the
+/// archive views, so it runs after they exist.
+pub async fn ensure_price_history(pool: &PgPool) -> Result<()> {
+    ensure_price_history_table(pool).await?;
+    sqlx::query(
+        r#"
+        INSERT INTO market_price_history (event_id, prev_prob, prob, created_at)
+        SELECT mu.event_id, mu.prev_prob, mu.new_prob, mu.created_at
+        FROM market_updates_all mu
+        JOIN events e ON e.id = mu.event_id
+        WHERE NOT EXISTS (SELECT 1 FROM market_price_history)
+        ORDER BY mu.created_at, mu.id
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Appends a trade that moved `event_id` from `prev_prob` to `prob`.
+pub(crate) async fn record(
+    conn: &mut PgConnection,
+    event_id: i32,
+    prev_prob: f64,
+    prob: f64,
+) -> Result<()> {
+    sqlx::query("INSERT INTO market_price_history (event_id, prev_prob, prob) VALUES ($1, $2, $3)")
+        .bind(event_id)
+        .bind(prev_prob)
+        .bind(prob)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// Candles of `resolution` over the last `buckets` buckets, oldest first.
+pub async fn get_history(
+    pool: &PgPool,
+    event_id: i32,
+    resolution: &str,
+    buckets: u32,
+) -> Result<PriceHistory> {
+    let width = parse_resolution(resolution).ok_or_else(|| {
+        coded(
+            ErrorCode::InvalidRequest,
+            format!(
+                "resolution must be one of {}",
+                RESOLUTIONS.map(|(name, _)| name).join(", ")
+            ),
+        )
+    })?;
+    if buckets == 0 || buckets > MAX_BUCKETS {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("buckets must be between 1 and {}", MAX_BUCKETS),
+        ));
+    }
+    let event_type: String =
+        sqlx::query_scalar("SELECT COALESCE(event_type, 'binary') FROM events WHERE id = $1")
+            .bind(event_id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| coded(ErrorCode::NotFound, "Event not found"))?;
+    if event_type != "binary" {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!(
+                "Price history covers binary markets only; event {} is {}",
+                event_id, event_type
+            ),
+        ));
+    }
+
+    let now = Utc::now().timestamp();
+    let from = now - now.rem_euclid(width) - (buckets as i64 - 1) * width;
+    let from = DateTime::from_timestamp(from, 0).ok_or_else(|| anyhow!("Window out of range"))?;
+    let rows = sqlx::query(
+        r#"
+        SELECT to_timestamp(floor(extract(epoch FROM created_at)::float8 / $2) * $2) AS start,
+               (array_agg(prev_prob ORDER BY created_at, id))[1] AS open,
+               GREATEST(MAX(prob), MAX(prev_prob)) AS high,
+               LEAST(MIN(prob), MIN(prev_prob)) AS low,
+               (array_agg(prob ORDER BY created_at DESC, id DESC))[1] AS close,
+               COUNT(*) AS trades
+        FROM market_price_history
+        WHERE event_id = $1 AND created_at >= $3
+        GROUP BY 1
+        ORDER BY 1
+        "#,
+    )
+    .bind(event_id)
+    .bind(width as f64)
+    .bind(from)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(PriceHistory {
+        event_id,
+        resolution: resolution.to_string(),
+        from,
+        buckets: rows
+            .iter()
+            .map(|row| PriceBucket {
+                start: row.get("start"),
+                open: row.get("open"),
+                high: row.get("high"),
+                low: row.get("low"),
+                close: row.get("close"),
+                trades: row.get("trades"),
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolutions_parse_to_bucket_widths() {
+        assert_eq!(parse_resolution("1m"), Some(60));
+        assert_eq!(parse_resolution("1h"), Some(3600));
+        assert_eq!(parse_resolution("1d"), Some(86_400));
+        assert_eq!(parse_resolution("2h"), None);
+        assert_eq!(parse_resolution(""), None);
+    }
+}
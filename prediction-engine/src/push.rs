@@ -0,0 +1,389 @@
+// Push notifications for mailbox messages a user hasn't fetched.
+//
+// Users register devices (platform + push token) with the engine. Every
+// PUSH_BATCH_WINDOW_SECS the dispatcher looks for mailbox messages that have
+// waited at least that long without being fetched, which is how it tells an
+// offline recipient from one whose client is polling, and sends each of the
+// recipient's devices one notice covering all of them. A device gets at most
+// one notice per PUSH_MIN_INTERVAL_SECS and PUSH_MAX_PER_HOUR per hour;
+// messages held back by either limit are batched into its next notice.
+//
+// Notices carry routing metadata only (device, platform, token, how many
+// messages are waiting, their group ids and the newest message id), never
+// ciphertext. They are POSTed to PUSH_WEBHOOK_URL, signed like engine
+// webhooks with PUSH_WEBHOOK_SECRET; the receiving push gateway hands them
+// to APNs or FCM by platform. Every notice is logged in push_dispatches.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::json;
+use sqlx::{PgPool, Row};
+use std::env;
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::api_error::{coded, ErrorCode};
+use crate::webhooks;
+
+pub const PLATFORMS: [&str; 3] = ["apns", "fcm", "web"];
+pub const MAX_DEVICES_PER_USER: i64 = 20;
+
+#[derive(Debug, Clone)]
+pub struct PushConfig {
+    pub webhook_url: Option<String>,
+    pub secret: Option<String>,
+    /// Seconds a message waits unfetched before it is pushed; also the
+    /// dispatcher's period.
+    pub batch_window_secs: u64,
+    pub min_interval_secs: u64,
+    pub max_per_hour: i64,
+    pub timeout_secs: u64,
+}
+
+impl PushConfig {
+    pub fn from_env() -> Self {
+        let number = |key: &str, default: u64, max: u64| {
+            env::var(key)
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(default)
+                .clamp(1, max)
+        };
+        let text = |key: &str| {
+            env::var(key)
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        Self {
+            webhook_url: text("PUSH_WEBHOOK_URL"),
+            secret: text("PUSH_WEBHOOK_SECRET"),
+            batch_window_secs: number("PUSH_BATCH_WINDOW_SECS", 10, 3600),
+            min_interval_secs: number("PUSH_MIN_INTERVAL_SECS", 60, 86400),
+            max_per_hour: number("PUSH_MAX_PER_HOUR", 20, 3600) as i64,
+            timeout_secs: number("PUSH_TIMEOUT_SECS", 10, 120),
+        }
+    }
+}
+
+pub fn config() -> &'static PushConfig {
+    static CONFIG: OnceLock<PushConfig> = OnceLock::new();
+    CONFIG.get_or_init(|| {
+        let cfg = PushConfig::from_env();
+        if cfg.webhook_url.is_some() && cfg.secret.is_none() {
+            println!(
+                "⚠️ PUSH_WEBHOOK_URL is set without PUSH_WEBHOOK_SECRET; push notices will be sent unsigned"
+            );
+        }
+        cfg
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PushDevice {
+    pub id: i64,
+    pub user_id: i32,
+    pub platform: String,
+    pub token: String,
+    pub created_at: DateTime<Utc>,
+    pub last_pushed_at: Option<DateTime<Utc>>,
+}
+
+/// What a push gateway is told about a device's waiting messages.
+#[derive(Debug, Clone, Serialize)]
+pub struct PushNotice {
+    pub device_id: i64,
+    pub user_id: i32,
+    pub platform: String,
+    pub token: String,
+    pub pending_messages: i64,
+    /// Hex-encoded MLS group ids of the waiting messages, where known.
+    pub group_ids: Vec<String>,
+    /// The newest message the notice covers.
+    pub through_message_id: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PushDispatch {
+    pub sent: usize,
+    pub failed: usize,
+}
+
+/// Delivers notices to a push gateway.
+pub trait PushSender {
+    fn send(&self, notice: &PushNotice) -> impl Future<Output = Result<()>> + Send;
+}
+
+/// Sends notices to PUSH_WEBHOOK_URL.
+pub struct WebhookSender {
+    client: Client,
+    url: String,
+    secret: Option<String>,
+}
+
+impl WebhookSender {
+    pub fn new(cfg: &PushConfig) -> Result<Option<Self>> {
+        let Some(url) = &cfg.webhook_url else {
+            return Ok(None);
+        };
+        let client = Client::builder()
+            .timeout(Duration::from_secs(cfg.timeout_secs))
+            .user_agent("Intellacc-PredictionEngine/1.0")
+            .build()?;
+        Ok(Some(Self {
+            client,
+            url: url.clone(),
+            secret: cfg.secret.clone(),
+        }))
+    }
+}
+
+impl PushSender for WebhookSender {
+    async fn send(&self, notice: &PushNotice) -> Result<()> {
+        let body = json!({ "type": "mailbox_pending", "data": notice }).to_string();
+        let timestamp = Utc::now().timestamp();
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header("Content-Type", "application/json")
+            .header("X-Intellacc-Event", "mailbox_pending")
+            .header("X-Intellacc-Timestamp", timestamp.to_string())
+            .body(body.clone());
+        if let Some(secret) = &self.secret {
+            request = request.header(
+                "X-Intellacc-Signature",
+                format!("sha256={}", webhooks::sign(secret, timestamp, &body)),
+            );
+        }
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("HTTP {}", response.status()));
+        }
+        Ok(())
+    }
+}
+
+pub async fn ensure_push_tables(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS push_devices (
+            id BIGSERIAL PRIMARY KEY,
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            platform VARCHAR(16) NOT NULL CHECK (platform IN ('apns', 'fcm', 'web')),
+            token TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            last_pushed_at TIMESTAMPTZ,
+            pushed_through BIGINT NOT NULL DEFAULT 0,
+            UNIQUE (platform, token)
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS push_dispatches (
+            id BIGSERIAL PRIMARY KEY,
+            device_id BIGINT NOT NULL REFERENCES push_devices(id) ON DELETE CASCADE,
+            message_count INTEGER NOT NULL,
+            through_message_id BIGINT NOT NULL,
+            status VARCHAR(16) NOT NULL,
+            error TEXT,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_push_dispatches_device
+         ON push_dispatches(device_id, created_at)",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+fn push_device(row: &sqlx::postgres::PgRow) -> PushDevice {
+    PushDevice {
+        id: row.get("id"),
+        user_id: row.get("user_id"),
+        platform: row.get("platform"),
+        token: row.get("token"),
+        created_at: row.get("created_at"),
+        last_pushed_at: row.get("last_pushed_at"),
+    }
+}
+
+/// Registers a device for `user_id`. A token registered before moves to
+/// this user, as happens when someone else signs in on the device.
+pub async fn register_device(
+    pool: &PgPool,
+    user_id: i32,
+    platform: &str,
+    token: &str,
+) -> Result<PushDevice> {
+    if !PLATFORMS.contains(&platform) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("platform must be one of {}", PLATFORMS.join(", ")),
+        ));
+    }
+    let token = token.trim();
+    if token.is_empty() || token.len() > 4096 {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "token must be 1-4096 characters",
+        ));
+    }
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Err(coded(ErrorCode::NotFound, "User not found"));
+    }
+    let devices: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM push_devices
+         WHERE user_id = $1 AND NOT (platform = $2 AND token = $3)",
+    )
+    .bind(user_id)
+    .bind(platform)
+    .bind(token)
+    .fetch_one(pool)
+    .await?;
+    if devices >= MAX_DEVICES_PER_USER {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!(
+                "user must have fewer than {} push devices",
+                MAX_DEVICES_PER_USER
+            ),
+        ));
+    }
+
+    let row = sqlx::query(
+        "INSERT INTO push_devices (user_id, platform, token) VALUES ($1, $2, $3)
+         ON CONFLICT (platform, token) DO UPDATE
+         SET user_id = EXCLUDED.user_id,
+             pushed_through = CASE WHEN push_devices.user_id = EXCLUDED.user_id
+                                   THEN push_devices.pushed_through ELSE 0 END
+         RETURNING *",
+    )
+    .bind(user_id)
+    .bind(platform)
+    .bind(token)
+    .fetch_one(pool)
+    .await?;
+    Ok(push_device(&row))
+}
+
+pub async fn list_devices(pool: &PgPool, user_id: i32) -> Result<Vec<PushDevice>> {
+    Ok(
+        sqlx::query("SELECT * FROM push_devices WHERE user_id = $1 ORDER BY id")
+            .bind(user_id)
+            .fetch_all(pool)
+            .await?
+            .iter()
+            .map(push_device)
+            .collect(),
+    )
+}
+
+/// Returns whether the user had that device.
+pub async fn remove_device(pool: &PgPool, user_id: i32, device_id: i64) -> Result<bool> {
+    Ok(
+        sqlx::query("DELETE FROM push_devices WHERE id = $1 AND user_id = $2")
+            .bind(device_id)
+            .bind(user_id)
+            .execute(pool)
+            .await?
+            .rows_affected()
+            > 0,
+    )
+}
+
+/// Sends one notice to every device with unfetched messages older than the
+/// batch window that isn't rate limited.
+pub async fn dispatch(
+    pool: &PgPool,
+    cfg: &PushConfig,
+    sender: &impl PushSender,
+) -> Result<PushDispatch> {
+    let rows = sqlx::query(
+        r#"
+        SELECT d.id, d.user_id, d.platform, d.token,
+               COUNT(m.id) AS pending_messages,
+               MAX(m.id) AS through_message_id,
+               COALESCE(
+                   ARRAY_AGG(DISTINCT encode(m.group_id, 'hex'))
+                       FILTER (WHERE m.group_id IS NOT NULL),
+                   '{}'
+               ) AS group_ids
+        FROM push_devices d
+        JOIN mls_mailbox_messages m
+          ON m.recipient_user_id = d.user_id
+         AND m.id > d.pushed_through
+         AND m.acked_at IS NULL
+         AND m.delivery_count = 0
+         AND (m.expires_at IS NULL OR m.expires_at > NOW())
+         AND m.enqueued_at <= NOW() - make_interval(secs => $1)
+        WHERE (d.last_pushed_at IS NULL
+               OR d.last_pushed_at <= NOW() - make_interval(secs => $2))
+          AND (SELECT COUNT(*) FROM push_dispatches p
+               WHERE p.device_id = d.id
+                 AND p.status = 'sent'
+                 AND p.created_at > NOW() - INTERVAL '1 hour') < $3
+        GROUP BY d.id
+        ORDER BY d.id
+        "#,
+    )
+    .bind(cfg.batch_window_secs as f64)
+    .bind(cfg.min_interval_secs as f64)
+    .bind(cfg.max_per_hour)
+    .fetch_all(pool)
+    .await?;
+
+    let mut dispatch = PushDispatch::default();
+    for row in &rows {
+        let notice = PushNotice {
+            device_id: row.get("id"),
+            user_id: row.get("user_id"),
+            platform: row.get("platform"),
+            token: row.get("token"),
+            pending_messages: row.get("pending_messages"),
+            group_ids: row.get("group_ids"),
+            through_message_id: row.get("through_message_id"),
+        };
+        let error = sender.send(&notice).await.err().map(|e| e.to_string());
+        sqlx::query(
+            "INSERT INTO push_dispatches
+                 (device_id, message_count, through_message_id, status, error)
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(notice.device_id)
+        .bind(notice.pending_messages as i32)
+        .bind(notice.through_message_id)
+        .bind(if error.is_none() { "sent" } else { "failed" })
+        .bind(&error)
+        .execute(pool)
+        .await?;
+        // A failed notice is retried, with whatever has arrived since, next time
+        if error.is_some() {
+            dispatch.failed += 1;
+            continue;
+        }
+        sqlx::query(
+            "UPDATE push_devices SET last_pushed_at = NOW(), pushed_through = $2 WHERE id = $1",
+        )
+        .bind(notice.device_id)
+        .bind(notice.through_message_id)
+        .execute(pool)
+        .await?;
+        dispatch.sent += 1;
+    }
+    Ok(dispatch)
+}
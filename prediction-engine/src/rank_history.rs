@@ -0,0 +1,244 @@
+//! Global rank transitions.
+//!
+//! Global rank is the dashboard's: reputation (RP held plus RP staked),
+//! then prediction count, then id. `update_global_rankings` re-ranks every
+//! user against the last snapshot in `global_ranks` and records each user
+//! whose rank changed in `rank_transitions`, with the market whose
+//! resolution or sell caused the run. Sells and resolutions only mark the
+//! ranking dirty; a periodic job re-ranks once for however many arrived,
+//! blaming the latest. The first run only takes the snapshot, so history
+//! starts from it rather than with every user "entering" at once.
+//!
+//! Moves into the top 10 or top 100 are milestones: the engine broadcasts
+//! them as `rank_milestone` on the `ranks` topic for the activity feed
+//! (`/ws?topic=ranks` gets only those). `GET /user/:id/rank-history` lists
+//! a user's transitions.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::{FromRow, PgPool};
+use std::sync::atomic::{AtomicI64, Ordering};
+
+use crate::api_error::{coded, ErrorCode};
+use crate::version;
+
+/// The topic rank milestones are broadcast on.
+pub const RANKS_TOPIC: &str = "ranks";
+
+/// Ranks whose entry is a milestone, tightest first.
+pub const MILESTONES: [i64; 2] = [10, 100];
+
+const CLEAN: i64 = -1;
+
+/// The latest market whose sell or resolution moved reputation since the
+/// last re-rank, or `CLEAN`.
+static PENDING_CAUSE: AtomicI64 = AtomicI64::new(CLEAN);
+
+/// Marks the ranking stale after a sell or resolution on `event_id`.
+pub fn mark_dirty(event_id: i32) {
+    PENDING_CAUSE.store(event_id as i64, Ordering::Release);
+}
+
+/// Clears the dirty mark, returning the market to blame if it was set.
+pub fn take_dirty() -> Option<i32> {
+    let cause = PENDING_CAUSE.swap(CLEAN, Ordering::AcqRel);
+    (cause != CLEAN).then_some(cause as i32)
+}
+
+/// Every user's global rank, as `id`, `username`, `reputation_ledger`,
+/// `rank` and `ranked_users`.
+pub const RANKING: &str = r#"
+    SELECT u.id, u.username,
+           COALESCE(u.rp_balance_ledger, 0) + COALESCE(u.rp_staked_ledger, 0)
+               AS reputation_ledger,
+           ROW_NUMBER() OVER (
+               ORDER BY COALESCE(u.rp_balance_ledger, 0)
+                            + COALESCE(u.rp_staked_ledger, 0) DESC,
+                        COALESCE(p.predictions, 0) DESC,
+                        u.id ASC
+           ) AS rank,
+           COUNT(*) OVER () AS ranked_users
+    FROM users u
+    LEFT JOIN (
+        SELECT user_id, COUNT(*) AS predictions FROM predictions GROUP BY user_id
+    ) p ON p.user_id = u.id
+"#;
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct RankTransition {
+    pub id: i64,
+    pub user_id: i32,
+    /// None when the user was not ranked at the last run
+    pub old_rank: Option<i64>,
+    pub new_rank: i64,
+    pub cause_event_id: Option<i32>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl RankTransition {
+    pub fn milestone(&self) -> Option<i64> {
+        milestone(self.old_rank, self.new_rank)
+    }
+}
+
+/// The tightest milestone a move from `old` to `new` enters, if any.
+pub fn milestone(old: Option<i64>, new: i64) -> Option<i64> {
+    MILESTONES
+        .into_iter()
+        .find(|&m| new <= m && old.is_none_or(|old| old > m))
+}
+
+pub async fn ensure_rank_history_tables(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS global_ranks (
+            user_id INTEGER PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+            rank BIGINT NOT NULL,
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS rank_transitions (
+            id BIGSERIAL PRIMARY KEY,
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            old_rank BIGINT,
+            new_rank BIGINT NOT NULL,
+            cause_event_id INTEGER REFERENCES events(id) ON DELETE SET NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_rank_transitions_user
+         ON rank_transitions (user_id, created_at DESC, id DESC)",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Re-ranks every user, records who moved since the last run (blamed on
+/// `cause_event_id`) and returns those transitions. Runs one at a time.
+pub async fn update_global_rankings(
+    pool: &PgPool,
+    cause_event_id: Option<i32>,
+) -> Result<Vec<RankTransition>> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("LOCK TABLE global_ranks IN EXCLUSIVE MODE")
+        .execute(&mut *tx)
+        .await?;
+    let seeded: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM global_ranks)")
+        .fetch_one(&mut *tx)
+        .await?;
+
+    let mut transitions: Vec<RankTransition> = if seeded {
+        sqlx::query_as(&format!(
+            "WITH ranked AS ({})
+             INSERT INTO rank_transitions (user_id, old_rank, new_rank, cause_event_id)
+             SELECT r.id, g.rank, r.rank, $1
+             FROM ranked r
+             LEFT JOIN global_ranks g ON g.user_id = r.id
+             WHERE g.rank IS DISTINCT FROM r.rank
+             ORDER BY r.rank
+             RETURNING id, user_id, old_rank, new_rank, cause_event_id, created_at",
+            RANKING
+        ))
+        .bind(cause_event_id)
+        .fetch_all(&mut *tx)
+        .await?
+    } else {
+        Vec::new()
+    };
+
+    sqlx::query(&format!(
+        "WITH ranked AS ({})
+         INSERT INTO global_ranks (user_id, rank)
+         SELECT id, rank FROM ranked
+         ON CONFLICT (user_id) DO UPDATE
+             SET rank = EXCLUDED.rank, updated_at = NOW()
+             WHERE global_ranks.rank <> EXCLUDED.rank",
+        RANKING
+    ))
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    transitions.sort_by_key(|t| t.new_rank);
+    Ok(transitions)
+}
+
+/// The broadcast announcing a milestone transition.
+pub fn milestone_message(transition: &RankTransition, milestone: i64) -> String {
+    json!({
+        "type": "rank_milestone",
+        "topic": RANKS_TOPIC,
+        "data": {
+            "user_id": transition.user_id,
+            "old_rank": transition.old_rank,
+            "new_rank": transition.new_rank,
+            "milestone": milestone,
+            "cause_event_id": transition.cause_event_id,
+        },
+        "timestamp": transition.created_at,
+        "engine": version::BUILD
+    })
+    .to_string()
+}
+
+/// The user's rank as of the last run and their transitions, newest first.
+pub async fn history(
+    pool: &PgPool,
+    user_id: i32,
+    limit: i64,
+) -> Result<(Option<i64>, Vec<RankTransition>)> {
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Err(coded(ErrorCode::NotFound, "User not found"));
+    }
+    ensure_rank_history_tables(pool).await?;
+    let rank = sqlx::query_scalar("SELECT rank FROM global_ranks WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+    let transitions = sqlx::query_as(
+        "SELECT id, user_id, old_rank, new_rank, cause_event_id, created_at
+         FROM rank_transitions
+         WHERE user_id = $1
+         ORDER BY created_at DESC, id DESC
+         LIMIT $2",
+    )
+    .bind(user_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok((rank, transitions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn milestones_are_entered_not_held() {
+        assert_eq!(milestone(Some(11), 10), Some(10));
+        assert_eq!(milestone(Some(150), 7), Some(10));
+        assert_eq!(milestone(Some(150), 42), Some(100));
+        assert_eq!(milestone(None, 99), Some(100));
+        // Moving within a band, or down out of one, is not a milestone.
+        assert_eq!(milestone(Some(9), 3), None);
+        assert_eq!(milestone(Some(60), 20), None);
+        assert_eq!(milestone(Some(5), 12), None);
+        assert_eq!(milestone(None, 101), None);
+    }
+}
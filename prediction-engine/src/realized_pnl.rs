@@ -0,0 +1,255 @@
+//! Realized profit and loss per user and market.
+//!
+//! A sell realizes its payout minus the cost basis it releases (the
+//! proportional staked ledger it unwinds); a settlement realizes the winning
+//! shares' value minus whatever basis is still staked. `user_shares`
+//! carries the running figure in `realized_pnl_ledger` while a binary
+//! position is open (as `user_outcome_shares` does per outcome of a
+//! categorical market), but settlement and closed-out sides delete those
+//! rows, so the durable per-market total lives in `user_realized_pnl`.
+//! Lifetime realized P&L is the sum over a user's markets.
+//!
+//! Unrealized P&L marks open positions at the current price: each share is
+//! worth its outcome's probability, which is what it pays on average, less
+//! the basis still staked. Selling would pay less, by the price impact.
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use sqlx::{PgPool, Postgres, Row, Transaction};
+
+use crate::api_error::{coded, ErrorCode};
+use crate::lmsr_core::from_ledger_units;
+
+pub async fn ensure_realized_pnl_table(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS user_realized_pnl (
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+            realized_pnl_ledger BIGINT NOT NULL DEFAULT 0,
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            PRIMARY KEY (user_id, event_id)
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Adds `pnl_ledger` to the user's realized P&L on `event_id`.
+pub(crate) async fn record(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: i32,
+    event_id: i32,
+    pnl_ledger: i64,
+) -> Result<()> {
+    record_batch(tx, event_id, &[user_id], &[pnl_ledger]).await
+}
+
+/// Adds `pnl_ledgers[i]` to the realized P&L of `user_ids[i]` on `event_id`.
+pub(crate) async fn record_batch(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    user_ids: &[i32],
+    pnl_ledgers: &[i64],
+) -> Result<()> {
+    if user_ids.is_empty() {
+        return Ok(());
+    }
+    sqlx::query(
+        r#"
+        INSERT INTO user_realized_pnl (user_id, event_id, realized_pnl_ledger)
+        SELECT t.user_id, $1, t.pnl
+        FROM UNNEST($2::integer[], $3::bigint[]) AS t(user_id, pnl)
+        ON CONFLICT (user_id, event_id) DO UPDATE SET
+            realized_pnl_ledger = user_realized_pnl.realized_pnl_ledger
+                + EXCLUDED.realized_pnl_ledger,
+            updated_at = NOW()
+        "#,
+    )
+    .bind(event_id)
+    .bind(user_ids)
+    .bind(pnl_ledgers)
+    .execute(&mut **tx)
+    .await?;
+    sqlx::query(
+        r#"
+        UPDATE user_shares us
+        SET realized_pnl_ledger = COALESCE(us.realized_pnl_ledger, 0) + t.pnl
+        FROM UNNEST($2::integer[], $3::bigint[]) AS t(user_id, pnl)
+        WHERE us.user_id = t.user_id AND us.event_id = $1
+        "#,
+    )
+    .bind(event_id)
+    .bind(user_ids)
+    .bind(pnl_ledgers)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Realized P&L of one user on one market, in ledger units.
+pub async fn market_realized_pnl(pool: &PgPool, user_id: i32, event_id: i32) -> Result<i64> {
+    let pnl: Option<i64> = sqlx::query_scalar(
+        "SELECT realized_pnl_ledger FROM user_realized_pnl WHERE user_id = $1 AND event_id = $2",
+    )
+    .bind(user_id)
+    .bind(event_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(pnl.unwrap_or(0))
+}
+
+/// Reputation totals, open binary positions and per-market realized P&L for
+/// a user. Markets appear while a position is open or once anything was
+/// realized on them; competition markets are left out, since they trade a
+/// competition bankroll rather than the user's RP.
+pub async fn get_portfolio(pool: &PgPool, user_id: i32) -> Result<Value> {
+    let user = sqlx::query("SELECT rp_balance_ledger, rp_staked_ledger FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| coded(ErrorCode::NotFound, "User not found"))?;
+    let balance_ledger: i64 = user.get("rp_balance_ledger");
+    let staked_ledger: i64 = user.get("rp_staked_ledger");
+
+    let rows = sqlx::query(
+        r#"
+        SELECT e.id AS event_id, e.title, e.outcome,
+               COALESCE(us.yes_shares, 0) AS yes_shares,
+               COALESCE(us.no_shares, 0) AS no_shares,
+               COALESCE(us.total_staked_ledger, 0)::BIGINT AS staked_ledger,
+               COALESCE(rp.realized_pnl_ledger, 0)::BIGINT AS realized_pnl_ledger
+        FROM (SELECT * FROM user_shares WHERE user_id = $1) us
+        FULL OUTER JOIN (SELECT * FROM user_realized_pnl WHERE user_id = $1) rp
+            ON rp.event_id = us.event_id
+        JOIN events e ON e.id = COALESCE(us.event_id, rp.event_id)
+        WHERE e.competition_id IS NULL
+        ORDER BY e.id DESC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut lifetime_ledger = 0i64;
+    let markets: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            let realized: i64 = row.get("realized_pnl_ledger");
+            lifetime_ledger += realized;
+            json!({
+                "event_id": row.get::<i32, _>("event_id"),
+                "title": row.get::<String, _>("title"),
+                "outcome": row.get::<Option<String>, _>("outcome"),
+                "yes_shares": row.get::<f64, _>("yes_shares"),
+                "no_shares": row.get::<f64, _>("no_shares"),
+                "staked": from_ledger_units(row.get::<i64, _>("staked_ledger") as i128),
+                "realized_pnl": from_ledger_units(realized as i128),
+                "realized_pnl_ledger": realized,
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "user_id": user_id,
+        "rp_balance": from_ledger_units(balance_ledger as i128),
+        "rp_staked": from_ledger_units(staked_ledger as i128),
+        "total_reputation": from_ledger_units((balance_ledger + staked_ledger) as i128),
+        "lifetime_realized_pnl": from_ledger_units(lifetime_ledger as i128),
+        "lifetime_realized_pnl_ledger": lifetime_ledger,
+        "markets": markets,
+    }))
+}
+
+/// Realized and unrealized P&L for a user, per market and in total. Open
+/// binary, categorical and numeric positions are marked at current prices;
+/// competition markets are left out as in `get_portfolio`.
+pub async fn get_pnl(pool: &PgPool, user_id: i32) -> Result<Value> {
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM users WHERE id = $1)")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Err(coded(ErrorCode::NotFound, "User not found"));
+    }
+
+    // Numeric positions keep their basis in numeric_position_basis, with 0
+    // staked per bin; categorical ones stake per outcome and have no basis row
+    let rows = sqlx::query(
+        r#"
+        WITH open_positions AS (
+            SELECT us.event_id,
+                   COALESCE(us.total_staked_ledger, 0)::BIGINT AS basis_ledger,
+                   COALESCE(us.yes_shares, 0) * COALESCE(e.market_prob, 0.5)
+                       + COALESCE(us.no_shares, 0) * (1 - COALESCE(e.market_prob, 0.5))
+                       AS mark_value
+            FROM user_shares us
+            JOIN events e ON e.id = us.event_id
+            WHERE us.user_id = $1
+            UNION ALL
+            SELECT uos.event_id,
+                   (SUM(uos.staked_ledger) + COALESCE(MAX(npb.basis_ledger), 0))::BIGINT,
+                   SUM(uos.shares * COALESCE(s.prob, 0))
+            FROM user_outcome_shares uos
+            LEFT JOIN event_outcome_states s
+                ON s.event_id = uos.event_id AND s.outcome_id = uos.outcome_id
+            LEFT JOIN numeric_position_basis npb
+                ON npb.user_id = uos.user_id AND npb.event_id = uos.event_id
+            WHERE uos.user_id = $1 AND uos.shares > 0
+            GROUP BY uos.event_id
+        )
+        SELECT e.id AS event_id, e.title, e.outcome,
+               op.basis_ledger, op.mark_value,
+               COALESCE(rp.realized_pnl_ledger, 0)::BIGINT AS realized_pnl_ledger
+        FROM open_positions op
+        FULL OUTER JOIN (SELECT * FROM user_realized_pnl WHERE user_id = $1) rp
+            ON rp.event_id = op.event_id
+        JOIN events e ON e.id = COALESCE(op.event_id, rp.event_id)
+        WHERE e.competition_id IS NULL
+        ORDER BY e.id DESC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut realized_ledger = 0i64;
+    let mut unrealized = 0.0;
+    let markets: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            let realized: i64 = row.get("realized_pnl_ledger");
+            let basis_ledger: Option<i64> = row.get("basis_ledger");
+            let mark_value: Option<f64> = row.get("mark_value");
+            let market_unrealized = match (basis_ledger, mark_value) {
+                (Some(basis), Some(value)) => value - from_ledger_units(basis as i128),
+                _ => 0.0,
+            };
+            realized_ledger += realized;
+            unrealized += market_unrealized;
+            json!({
+                "event_id": row.get::<i32, _>("event_id"),
+                "title": row.get::<String, _>("title"),
+                "outcome": row.get::<Option<String>, _>("outcome"),
+                "open": mark_value.is_some(),
+                "cost_basis": from_ledger_units(basis_ledger.unwrap_or(0) as i128),
+                "mark_value": mark_value.unwrap_or(0.0),
+                "unrealized_pnl": market_unrealized,
+                "realized_pnl": from_ledger_units(realized as i128),
+                "realized_pnl_ledger": realized,
+            })
+        })
+        .collect();
+
+    let realized = from_ledger_units(realized_ledger as i128);
+    Ok(json!({
+        "user_id": user_id,
+        "realized_pnl": realized,
+        "realized_pnl_ledger": realized_ledger,
+        "unrealized_pnl": unrealized,
+        "total_pnl": realized + unrealized,
+        "markets": markets,
+    }))
+}
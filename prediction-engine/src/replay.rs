@@ -0,0 +1,465 @@
+//! Historical market replay
+//!
+//! Rebuilds a binary market's probability path from its `market_updates`
+//! journal, checks each step against what was stored at the time, and can
+//! re-run the same trades under candidate pricing (a different liquidity
+//! `b`, a fee on every stake) so a change to the production math can be
+//! judged on real order flow before it ships.
+//!
+//! Binary sells are not journaled, so a sell shows up as a gap: the next
+//! buy's `prev_prob` no longer matches the replayed price. LMSR prices a
+//! binary trade from `q_yes - q_no` alone, so the replay re-anchors there
+//! without losing accuracy, and every variant is moved to the same price
+//! at each gap to keep the paths comparable.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Row};
+
+use crate::lmsr_core::{from_ledger_units, Market, Side};
+
+/// Probability difference that counts as a mismatch or a gap.
+const PROB_TOLERANCE: f64 = 1e-6;
+/// Relative share difference that counts as a mismatch.
+const SHARES_TOLERANCE: f64 = 1e-6;
+
+fn serialize_side<S: serde::Serializer>(side: &Side, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(side.as_str())
+}
+
+/// One journaled buy.
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalEntry {
+    pub id: i32,
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(serialize_with = "serialize_side")]
+    pub side: Side,
+    pub prev_prob: f64,
+    pub new_prob: f64,
+    pub stake_ledger: i64,
+    pub shares_acquired: f64,
+}
+
+/// Candidate pricing to replay the journal under.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelVariant {
+    pub name: String,
+    /// Replaces the event's liquidity_b when set.
+    pub liquidity_b: Option<f64>,
+    /// Share of each stake kept as a fee; the rest buys shares.
+    pub fee_rate: f64,
+}
+
+impl ModelVariant {
+    /// Parses `name[:b=200][,fee=0.02]`.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (name, params) = spec.split_once(':').unwrap_or((spec, ""));
+        if name.trim().is_empty() {
+            return Err(anyhow!("variant '{}' needs a name", spec));
+        }
+        let mut variant = Self {
+            name: name.trim().to_string(),
+            liquidity_b: None,
+            fee_rate: 0.0,
+        };
+        for param in params.split(',').filter(|p| !p.trim().is_empty()) {
+            let (key, value) = param
+                .split_once('=')
+                .ok_or_else(|| anyhow!("variant parameter '{}' must be key=value", param))?;
+            let value: f64 = value
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("invalid number in variant parameter '{}'", param))?;
+            match key.trim() {
+                "b" => variant.liquidity_b = Some(value),
+                "fee" => variant.fee_rate = value,
+                other => {
+                    return Err(anyhow!(
+                        "unknown variant parameter '{}' (expected b or fee)",
+                        other
+                    ))
+                }
+            }
+        }
+        variant.validate()?;
+        Ok(variant)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if let Some(b) = self.liquidity_b {
+            if !b.is_finite() || b <= 0.0 {
+                return Err(anyhow!("variant '{}': b must be positive", self.name));
+            }
+        }
+        if !(0.0..1.0).contains(&self.fee_rate) {
+            return Err(anyhow!("variant '{}': fee must be in [0, 1)", self.name));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayStep {
+    pub market_update_id: i32,
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(serialize_with = "serialize_side")]
+    pub side: Side,
+    pub stake: f64,
+    pub stored_new_prob: f64,
+    pub replayed_new_prob: f64,
+    pub stored_shares: f64,
+    pub replayed_shares: f64,
+    /// Set when the price had moved since the previous journaled trade.
+    pub gap_from_prob: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VariantOutcome {
+    pub name: String,
+    pub liquidity_b: f64,
+    pub fee_rate: f64,
+    pub final_prob: f64,
+    /// Largest distance from the production path at any step.
+    pub max_prob_divergence: f64,
+    pub path: Vec<f64>,
+    pub fees_collected: f64,
+    /// RP paid into the market maker, net of fees.
+    pub amm_collected: f64,
+    pub yes_shares_issued: f64,
+    pub no_shares_issued: f64,
+    /// Winning shares owed, once the event is resolved.
+    pub payout: Option<f64>,
+    /// Market maker profit (negative: subsidy) once resolved.
+    pub amm_pnl: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayReport {
+    pub event_id: i32,
+    pub liquidity_b: f64,
+    pub outcome: Option<bool>,
+    pub trades: usize,
+    /// Price moves between journaled trades, i.e. unjournaled sells.
+    pub gaps: usize,
+    pub steps: Vec<ReplayStep>,
+    pub stored_final_prob: f64,
+    pub replayed_final_prob: f64,
+    pub mismatches: Vec<String>,
+    pub variants: Vec<VariantOutcome>,
+}
+
+impl ReplayReport {
+    pub fn ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Sets q so the market prices YES at `prob`, keeping q_no at 0.
+fn anchor(market: &mut Market, prob: f64) {
+    let p = prob.clamp(1e-12, 1.0 - 1e-12);
+    market.q_yes = market.b * (p / (1.0 - p)).ln();
+    market.q_no = 0.0;
+}
+
+fn buy(market: &mut Market, side: Side, stake: f64) -> Result<(f64, f64)> {
+    let stake_ledger =
+        crate::lmsr_core::to_ledger_units(stake).map_err(|e| anyhow!("Invalid stake: {}", e))?;
+    let (shares, cost_ledger) = market
+        .apply_trade(side, stake_ledger)
+        .map_err(|e| anyhow!("Replay trade failed: {}", e))?;
+    Ok((shares, from_ledger_units(cost_ledger)))
+}
+
+/// Replays `journal` (oldest first) at `liquidity_b` and under each variant.
+pub fn replay_journal(
+    event_id: i32,
+    journal: &[JournalEntry],
+    liquidity_b: f64,
+    stored_final_prob: f64,
+    outcome: Option<bool>,
+    variants: &[ModelVariant],
+) -> Result<ReplayReport> {
+    let mut market = Market::new(liquidity_b);
+    if let Some(first) = journal.first() {
+        anchor(&mut market, first.prev_prob);
+    }
+    let mut variant_markets: Vec<Market> = variants
+        .iter()
+        .map(|v| {
+            let mut m = Market::new(v.liquidity_b.unwrap_or(liquidity_b));
+            anchor(&mut m, market.prob_yes());
+            m
+        })
+        .collect();
+    let mut outcomes: Vec<VariantOutcome> = variants
+        .iter()
+        .zip(&variant_markets)
+        .map(|(v, m)| VariantOutcome {
+            name: v.name.clone(),
+            liquidity_b: m.b,
+            fee_rate: v.fee_rate,
+            final_prob: m.prob_yes(),
+            max_prob_divergence: 0.0,
+            path: Vec::with_capacity(journal.len()),
+            fees_collected: 0.0,
+            amm_collected: 0.0,
+            yes_shares_issued: 0.0,
+            no_shares_issued: 0.0,
+            payout: None,
+            amm_pnl: None,
+        })
+        .collect();
+
+    let mut steps = Vec::with_capacity(journal.len());
+    let mut mismatches = Vec::new();
+    let mut gaps = 0;
+
+    for entry in journal {
+        let gap_from_prob = if (market.prob_yes() - entry.prev_prob).abs() > PROB_TOLERANCE {
+            gaps += 1;
+            let replayed = market.prob_yes();
+            anchor(&mut market, entry.prev_prob);
+            for m in &mut variant_markets {
+                anchor(m, entry.prev_prob);
+            }
+            Some(replayed)
+        } else {
+            None
+        };
+
+        let stake = from_ledger_units(entry.stake_ledger as i128);
+        let (shares, _) = buy(&mut market, entry.side, stake)?;
+        let replayed_new_prob = market.prob_yes();
+        if (replayed_new_prob - entry.new_prob).abs() > PROB_TOLERANCE {
+            mismatches.push(format!(
+                "market_update {}: stored new_prob {:.8}, replayed {:.8}",
+                entry.id, entry.new_prob, replayed_new_prob
+            ));
+        }
+        if (shares - entry.shares_acquired).abs()
+            > SHARES_TOLERANCE * entry.shares_acquired.abs().max(1.0)
+        {
+            mismatches.push(format!(
+                "market_update {}: stored shares {:.8}, replayed {:.8}",
+                entry.id, entry.shares_acquired, shares
+            ));
+        }
+
+        for ((variant, m), out) in variants.iter().zip(&mut variant_markets).zip(&mut outcomes) {
+            let fee = stake * variant.fee_rate;
+            let (shares, cost) = buy(m, entry.side, stake - fee)?;
+            out.fees_collected += fee;
+            out.amm_collected += cost;
+            match entry.side {
+                Side::Yes => out.yes_shares_issued += shares,
+                Side::No => out.no_shares_issued += shares,
+            }
+            out.path.push(m.prob_yes());
+            out.max_prob_divergence = out
+                .max_prob_divergence
+                .max((m.prob_yes() - replayed_new_prob).abs());
+        }
+
+        steps.push(ReplayStep {
+            market_update_id: entry.id,
+            created_at: entry.created_at,
+            side: entry.side,
+            stake,
+            stored_new_prob: entry.new_prob,
+            replayed_new_prob,
+            stored_shares: entry.shares_acquired,
+            replayed_shares: shares,
+            gap_from_prob,
+        });
+    }
+
+    // A sell after the last journaled buy leaves the stored price elsewhere;
+    // that is a gap, not a mismatch.
+    let replayed_final_prob = market.prob_yes();
+    if (replayed_final_prob - stored_final_prob).abs() > PROB_TOLERANCE {
+        gaps += 1;
+    }
+
+    for (out, m) in outcomes.iter_mut().zip(&variant_markets) {
+        out.final_prob = m.prob_yes();
+        if let Some(outcome) = outcome {
+            let payout = if outcome {
+                out.yes_shares_issued
+            } else {
+                out.no_shares_issued
+            };
+            out.payout = Some(payout);
+            out.amm_pnl = Some(out.amm_collected - payout);
+        }
+    }
+
+    Ok(ReplayReport {
+        event_id,
+        liquidity_b,
+        outcome,
+        trades: journal.len(),
+        gaps,
+        steps,
+        stored_final_prob,
+        replayed_final_prob,
+        mismatches,
+        variants: outcomes,
+    })
+}
+
+/// Loads an event's buy journal, oldest first.
+pub async fn load_journal(pool: &PgPool, event_id: i32) -> Result<Vec<JournalEntry>> {
+    let rows = sqlx::query(
+        "SELECT id, created_at, share_type, prev_prob, new_prob, stake_amount_ledger, shares_acquired
+         FROM market_updates_all
+         WHERE event_id = $1
+         ORDER BY created_at, id",
+    )
+    .bind(event_id)
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            let share_type: String = row.get("share_type");
+            Ok(JournalEntry {
+                id: row.get("id"),
+                created_at: row.get("created_at"),
+                side: Side::from_str(&share_type).map_err(|e| anyhow!(e))?,
+                prev_prob: row.get("prev_prob"),
+                new_prob: row.get("new_prob"),
+                stake_ledger: row.get("stake_amount_ledger"),
+                shares_acquired: row.get("shares_acquired"),
+            })
+        })
+        .collect()
+}
+
+/// Replays a binary event straight from the database.
+pub async fn replay_event(
+    pool: &PgPool,
+    event_id: i32,
+    variants: &[ModelVariant],
+) -> Result<ReplayReport> {
+    let row = sqlx::query(
+        "SELECT liquidity_b, market_prob, outcome, COALESCE(event_type, 'binary') AS event_type
+         FROM events WHERE id = $1",
+    )
+    .bind(event_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| anyhow!("Event {} not found", event_id))?;
+
+    let event_type: String = row.get("event_type");
+    if event_type != "binary" {
+        return Err(anyhow!(
+            "Event {} is a {} market; replay covers binary markets only",
+            event_id,
+            event_type
+        ));
+    }
+    let outcome = match row.get::<Option<String>, _>("outcome").as_deref() {
+        Some("resolved_yes") => Some(true),
+        Some("resolved_no") => Some(false),
+        _ => None,
+    };
+
+    let journal = load_journal(pool, event_id).await?;
+    replay_journal(
+        event_id,
+        &journal,
+        row.get("liquidity_b"),
+        row.get("market_prob"),
+        outcome,
+        variants,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Journals buys the way update_market does, with an optional sell
+    /// (which production does not journal) after `sell_after`.
+    fn simulated_journal(b: f64, sell_after: Option<usize>) -> (Vec<JournalEntry>, f64) {
+        let mut market = Market::new(b);
+        let trades = [
+            (Side::Yes, 40.0),
+            (Side::No, 15.0),
+            (Side::Yes, 25.0),
+            (Side::No, 60.0),
+        ];
+        let mut journal = Vec::new();
+        for (i, (side, stake)) in trades.into_iter().enumerate() {
+            let prev_prob = market.prob_yes();
+            let stake_ledger = crate::lmsr_core::to_ledger_units(stake).unwrap();
+            let (shares, cost_ledger) = market.apply_trade(side, stake_ledger).unwrap();
+            journal.push(JournalEntry {
+                id: i as i32 + 1,
+                created_at: None,
+                side,
+                prev_prob,
+                new_prob: market.prob_yes(),
+                stake_ledger: cost_ledger as i64,
+                shares_acquired: shares,
+            });
+            if sell_after == Some(i) {
+                market.sell_yes(10.0).unwrap();
+            }
+        }
+        (journal, market.prob_yes())
+    }
+
+    #[test]
+    fn replay_matches_production_path_across_unjournaled_sells() {
+        let (journal, final_prob) = simulated_journal(100.0, None);
+        let report = replay_journal(1, &journal, 100.0, final_prob, None, &[]).unwrap();
+        assert!(report.ok(), "{:?}", report.mismatches);
+        assert_eq!(report.gaps, 0);
+
+        let (journal, final_prob) = simulated_journal(100.0, Some(1));
+        let report = replay_journal(1, &journal, 100.0, final_prob, None, &[]).unwrap();
+        assert!(report.ok(), "{:?}", report.mismatches);
+        assert_eq!(report.gaps, 1);
+        assert!(report.steps[2].gap_from_prob.is_some());
+
+        let mut tampered = journal.clone();
+        tampered[3].new_prob += 0.01;
+        let report = replay_journal(1, &tampered, 100.0, final_prob, None, &[]).unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+        assert!(report.mismatches[0].starts_with("market_update 4:"));
+    }
+
+    #[test]
+    fn variants_compare_fee_and_liquidity_changes() {
+        let (journal, final_prob) = simulated_journal(100.0, None);
+        let variants = [
+            ModelVariant::parse("baseline").unwrap(),
+            ModelVariant::parse("fee2pct:fee=0.02").unwrap(),
+            ModelVariant::parse("deep:b=400").unwrap(),
+        ];
+        let report =
+            replay_journal(1, &journal, 100.0, final_prob, Some(false), &variants).unwrap();
+
+        let baseline = &report.variants[0];
+        assert!(baseline.max_prob_divergence < PROB_TOLERANCE);
+        assert_eq!(baseline.fees_collected, 0.0);
+
+        let fee = &report.variants[1];
+        let total_stake: f64 = report.steps.iter().map(|s| s.stake).sum();
+        assert!((fee.fees_collected - total_stake * 0.02).abs() < 1e-9);
+        assert!(fee.no_shares_issued < baseline.no_shares_issued);
+
+        // Deeper liquidity moves the price less for the same order flow
+        let deep = &report.variants[2];
+        assert!((deep.final_prob - 0.5).abs() < (baseline.final_prob - 0.5).abs());
+        assert_eq!(
+            deep.amm_pnl,
+            Some(deep.amm_collected - deep.no_shares_issued)
+        );
+
+        assert!(ModelVariant::parse("bad:fee=1.5").is_err());
+        assert!(ModelVariant::parse("bad:depth=2").is_err());
+    }
+}
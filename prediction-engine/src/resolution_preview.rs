@@ -0,0 +1,226 @@
+//! Two-phase resolution for binary markets.
+//!
+//! Preview runs the real resolution in a transaction that is rolled back,
+//! so the report shows exactly what resolving would pay: each holder's
+//! settlement and the market maker's P&L (stakes released less RP paid
+//! out). The report is kept in `pending_resolutions` until an admin
+//! commits or cancels it. Commit resolves for real in one transaction and
+//! checks the settlements against the report; if trades since the preview
+//! changed them, nothing is applied and the market has to be previewed
+//! again. The one-step resolve endpoint is unchanged.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::api_error::{coded, ErrorCode};
+use crate::lmsr_api::{resolve_event_transaction, ResolutionPayout};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolutionReport {
+    pub event_id: i32,
+    pub outcome: bool,
+    pub actor: Option<String>,
+    pub reason: Option<String>,
+    pub positions: usize,
+    pub total_credited: f64,
+    pub total_stake_released: f64,
+    /// Stakes released less RP credited: what the market maker keeps
+    /// (negative when it pays out more than it took in)
+    pub amm_pnl: f64,
+    pub payouts: Vec<ResolutionPayout>,
+    pub previewed_at: DateTime<Utc>,
+}
+
+impl ResolutionReport {
+    fn new(
+        event_id: i32,
+        outcome: bool,
+        actor: Option<&str>,
+        reason: Option<&str>,
+        mut payouts: Vec<ResolutionPayout>,
+    ) -> Self {
+        payouts.sort_by_key(|p| p.user_id);
+        let total_credited: f64 = payouts.iter().map(|p| p.rp_credited).sum();
+        let total_stake_released: f64 = payouts.iter().map(|p| p.stake_released).sum();
+        Self {
+            event_id,
+            outcome,
+            actor: actor.map(str::to_string),
+            reason: reason.map(str::to_string),
+            positions: payouts.len(),
+            total_credited,
+            total_stake_released,
+            amm_pnl: total_stake_released - total_credited,
+            payouts,
+            previewed_at: Utc::now(),
+        }
+    }
+
+    /// Who gets what, ignoring balances, which other markets move.
+    fn settlements(&self) -> Vec<(i32, f64, f64)> {
+        self.payouts
+            .iter()
+            .map(|p| (p.user_id, p.rp_credited, p.stake_released))
+            .collect()
+    }
+}
+
+pub async fn ensure_pending_table(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS pending_resolutions (
+            event_id INTEGER PRIMARY KEY REFERENCES events(id) ON DELETE CASCADE,
+            outcome BOOLEAN NOT NULL,
+            report JSONB NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Computes what resolving `event_id` as `outcome` would pay and stores
+/// it as the event's pending resolution, replacing any earlier preview.
+pub async fn preview(
+    pool: &PgPool,
+    event_id: i32,
+    outcome: bool,
+    actor: Option<&str>,
+    reason: Option<&str>,
+) -> Result<ResolutionReport> {
+    crate::disputes::ensure_dispute_tables(pool).await?;
+    ensure_pending_table(pool).await?;
+
+    let mut tx = pool.begin().await?;
+    let payouts = resolve_event_transaction(&mut tx, event_id, outcome.into(), actor, reason).await?;
+    tx.rollback().await?;
+
+    let report = ResolutionReport::new(event_id, outcome, actor, reason, payouts);
+    sqlx::query(
+        r#"
+        INSERT INTO pending_resolutions (event_id, outcome, report)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (event_id) DO UPDATE
+        SET outcome = EXCLUDED.outcome, report = EXCLUDED.report, created_at = NOW()
+        "#,
+    )
+    .bind(event_id)
+    .bind(outcome)
+    .bind(sqlx::types::Json(&report))
+    .execute(pool)
+    .await?;
+    Ok(report)
+}
+
+/// The event's pending resolution, if one has been previewed.
+pub async fn get_pending(pool: &PgPool, event_id: i32) -> Result<Option<ResolutionReport>> {
+    ensure_pending_table(pool).await?;
+    let report: Option<sqlx::types::Json<ResolutionReport>> =
+        sqlx::query_scalar("SELECT report FROM pending_resolutions WHERE event_id = $1")
+            .bind(event_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(report.map(|r| r.0))
+}
+
+/// Resolves the event as previewed. Fails, changing nothing, when the
+/// settlements no longer match the preview.
+pub async fn commit(pool: &PgPool, event_id: i32) -> Result<ResolutionReport> {
+    crate::disputes::ensure_dispute_tables(pool).await?;
+    ensure_pending_table(pool).await?;
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE")
+        .execute(&mut *tx)
+        .await?;
+    let previewed: sqlx::types::Json<ResolutionReport> =
+        sqlx::query_scalar("SELECT report FROM pending_resolutions WHERE event_id = $1 FOR UPDATE")
+            .bind(event_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| coded(ErrorCode::NotFound, "No pending resolution"))?;
+    let previewed = previewed.0;
+
+    let payouts = resolve_event_transaction(
+        &mut tx,
+        event_id,
+        previewed.outcome.into(),
+        previewed.actor.as_deref(),
+        previewed.reason.as_deref(),
+    )
+    .await?;
+    let report = ResolutionReport::new(
+        event_id,
+        previewed.outcome,
+        previewed.actor.as_deref(),
+        previewed.reason.as_deref(),
+        payouts,
+    );
+    if report.settlements() != previewed.settlements() {
+        tx.rollback().await?;
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "Positions changed since the preview; the resolution must be previewed again",
+        ));
+    }
+
+    sqlx::query("DELETE FROM pending_resolutions WHERE event_id = $1")
+        .bind(event_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(report)
+}
+
+/// Discards the event's pending resolution. Returns whether there was one.
+pub async fn cancel(pool: &PgPool, event_id: i32) -> Result<bool> {
+    ensure_pending_table(pool).await?;
+    let deleted = sqlx::query("DELETE FROM pending_resolutions WHERE event_id = $1")
+        .bind(event_id)
+        .execute(pool)
+        .await?;
+    Ok(deleted.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payout(user_id: i32, rp_credited: f64, stake_released: f64) -> ResolutionPayout {
+        ResolutionPayout {
+            user_id,
+            shares_redeemed: rp_credited,
+            rp_credited,
+            stake_released,
+            new_balance: 1000.0,
+            score_delta: rp_credited - stake_released,
+        }
+    }
+
+    #[test]
+    fn report_totals_the_market_maker_pnl() {
+        let report = ResolutionReport::new(
+            7,
+            true,
+            None,
+            None,
+            vec![payout(2, 0.0, 30.0), payout(1, 75.0, 40.0)],
+        );
+        assert_eq!(report.positions, 2);
+        assert_eq!(report.total_credited, 75.0);
+        assert_eq!(report.total_stake_released, 70.0);
+        assert_eq!(report.amm_pnl, -5.0);
+        assert_eq!(report.payouts[0].user_id, 1);
+
+        // Balances don't count toward whether a commit matches
+        let mut moved = report.clone();
+        moved.payouts[0].new_balance = 5.0;
+        assert_eq!(moved.settlements(), report.settlements());
+        moved.payouts[1].stake_released = 31.0;
+        assert_ne!(moved.settlements(), report.settlements());
+    }
+}
@@ -15,8 +15,9 @@
 // Numeric markets are Metaculus-only today and Metaculus's resolution field
 // is unreadable at our token's access level (see sync_numeric_resolutions),
 // so this currently finds zero live candidates — the machinery is in place
-// for when/if that changes. Voided/annulled markets are counted but
-// skipped — that needs a refund path.
+// for when/if that changes. Voided/annulled markets (Manifold CANCEL,
+// Metaculus annulled/ambiguous) resolve N/A through lmsr_api::annul_event,
+// which refunds every stake and leaves the event out of scoring.
 
 use anyhow::Result;
 use reqwest::Client;
@@ -25,6 +26,8 @@ use sqlx::{PgPool, Row};
 use std::env;
 use std::time::Duration;
 
+use crate::lmsr_api::Resolution;
+
 const BATCH_LIMIT: i64 = 400;
 const REQUEST_DELAY_MS: u64 = 150;
 
@@ -113,16 +116,39 @@ pub async fn sync_resolutions(pool: &PgPool) -> Result<ResolutionStats> {
         };
 
         match verdict {
-            Ok(Verdict::Resolved(outcome)) => {
-                match crate::lmsr_api::resolve_event(pool, event_id, outcome).await {
-                    Ok(()) => {
+            Ok(Verdict::Resolved(resolution)) => {
+                let settled = match resolution.scored() {
+                    Some(outcome) => crate::lmsr_api::resolve_event(pool, event_id, outcome).await,
+                    None => crate::lmsr_api::annul_event(pool, event_id).await,
+                };
+                match settled {
+                    Ok(payouts) => {
                         stats.resolved += 1;
+                        let holders: Vec<i32> = payouts.iter().map(|p| p.user_id).collect();
+                        let kind = crate::notifications::Notification::Resolution;
+                        let notified = crate::notifications::recipients(pool, kind, &holders)
+                            .await
+                            .unwrap_or_else(|err| {
+                                println!("⚠️ Notification lookup failed: {}", err);
+                                Vec::new()
+                            });
+                        crate::webhooks::emit(
+                            pool,
+                            crate::webhooks::EVENT_RESOLVED,
+                            json!({
+                                "event_id": event_id,
+                                "outcome": resolution.scored(),
+                                "resolution": resolution.as_outcome(),
+                                "source": source,
+                                "notify_user_ids": notified
+                            }),
+                        );
                         println!(
                             "✅ Resolved event {} ({}: {}) -> {}",
                             event_id,
                             source,
                             external_id,
-                            if outcome { "YES" } else { "NO" }
+                            resolution.label()
                         );
                     }
                     Err(err) => {
@@ -157,6 +183,143 @@ pub async fn sync_resolutions(pool: &PgPool) -> Result<ResolutionStats> {
     Ok(stats)
 }
 
+#[derive(Default)]
+pub struct BackfillStats {
+    pub checked: u32,
+    pub backfilled: u32,
+    // Resolved upstream but resolution field came back null/unreadable.
+    pub unavailable: u32,
+    pub unsupported: u32,
+    pub errors: u32,
+}
+
+impl BackfillStats {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "checked": self.checked,
+            "backfilled": self.backfilled,
+            "unavailable": self.unavailable,
+            "unsupported": self.unsupported,
+            "errors": self.errors,
+        })
+    }
+}
+
+// Metaculus resolution backfill. Operator-triggered counterpart to
+// sync_resolutions for the source it skips: imported Metaculus binary
+// questions that already resolved upstream — legacy imports of resolved
+// questions (outcome 'pending') and synced ones past close (outcome NULL).
+// 'pending' events never traded, so they get the outcome written directly;
+// NULL ones go through the normal payout path. With today's token the
+// resolution field is null, so expect mostly `unavailable` — counted rather
+// than guessed so the report stays honest.
+pub async fn backfill_metaculus_resolutions(pool: &PgPool, limit: i64) -> Result<BackfillStats> {
+    let rows = sqlx::query(
+        r#"
+        SELECT e.id, e.outcome,
+               COALESCE(s.external_id, substring(e.details from 'Metaculus ID: ([0-9]+)')) AS external_id
+        FROM events e
+        LEFT JOIN event_external_sources s ON s.event_id = e.id AND s.source = 'metaculus'
+        WHERE (e.outcome = 'pending' OR (e.outcome IS NULL AND e.closing_date <= NOW()))
+          AND COALESCE(e.event_type, 'binary') = 'binary'
+          AND (s.external_id IS NOT NULL OR e.details LIKE '%Metaculus ID: %')
+        ORDER BY e.closing_date ASC
+        LIMIT $1
+        "#,
+    )
+    .bind(limit.clamp(1, BATCH_LIMIT))
+    .fetch_all(pool)
+    .await?;
+
+    let client = Client::builder()
+        .timeout(Duration::from_secs(15))
+        .user_agent("Intellacc-PredictionEngine/1.0")
+        .build()?;
+
+    let mut stats = BackfillStats::default();
+    println!(
+        "📚 Metaculus backfill: checking {} resolved/past-close events",
+        rows.len()
+    );
+
+    for row in rows {
+        let event_id: i32 = row.get("id");
+        let current: Option<String> = row.get("outcome");
+        let Some(external_id) = row.get::<Option<String>, _>("external_id") else {
+            stats.unsupported += 1;
+            continue;
+        };
+        stats.checked += 1;
+
+        match metaculus_resolution(&client, &external_id).await {
+            Ok(Verdict::Resolved(resolution)) => {
+                let settled = if current.as_deref() == Some("pending") {
+                    sqlx::query(
+                        "UPDATE events SET outcome = $1, resolved_at = NOW() WHERE id = $2 AND outcome = 'pending'",
+                    )
+                    .bind(resolution.as_outcome())
+                    .bind(event_id)
+                    .execute(pool)
+                    .await
+                    .map(|_| ())
+                    .map_err(anyhow::Error::from)
+                } else {
+                    match resolution.scored() {
+                        Some(outcome) => crate::lmsr_api::resolve_event(pool, event_id, outcome).await,
+                        None => crate::lmsr_api::annul_event(pool, event_id).await,
+                    }
+                    .map(|_| ())
+                };
+                match settled {
+                    Ok(()) => {
+                        stats.backfilled += 1;
+                        crate::webhooks::emit(
+                            pool,
+                            crate::webhooks::EVENT_RESOLVED,
+                            json!({
+                                "event_id": event_id,
+                                "outcome": resolution.scored(),
+                                "resolution": resolution.as_outcome(),
+                                "source": "metaculus",
+                                "backfill": true
+                            }),
+                        );
+                        println!(
+                            "✅ Backfilled event {} (metaculus: {}) -> {}",
+                            event_id,
+                            external_id,
+                            resolution.label()
+                        );
+                    }
+                    Err(err) => {
+                        stats.errors += 1;
+                        println!("⚠️ Backfill failed for event {}: {}", event_id, err);
+                    }
+                }
+            }
+            // Past close (or flagged resolved at import) but no readable
+            // resolution: see the access-level note in sync_resolutions.
+            Ok(Verdict::StillOpen) => stats.unavailable += 1,
+            Ok(Verdict::Unsupported) => stats.unsupported += 1,
+            Err(err) => {
+                stats.errors += 1;
+                println!(
+                    "⚠️ Backfill lookup failed (metaculus: {}): {}",
+                    external_id, err
+                );
+            }
+        }
+
+        tokio::time::sleep(Duration::from_millis(REQUEST_DELAY_MS)).await;
+    }
+
+    println!(
+        "📚 Metaculus backfill done: {} checked, {} backfilled, {} unavailable, {} unsupported, {} errors",
+        stats.checked, stats.backfilled, stats.unavailable, stats.unsupported, stats.errors
+    );
+    Ok(stats)
+}
+
 // Multiple-choice resolution pass. Same shape as the binary loop above, but
 // the provider verdict carries a winning *label* instead of a bool, which we
 // match against event_outcomes.label (case-insensitive, trimmed) to find the
@@ -231,6 +394,15 @@ async fn sync_mc_resolutions(
                             Ok(()) => {
                                 stats.resolved += 1;
                                 stats.mc_resolved += 1;
+                                crate::webhooks::emit(
+                                    pool,
+                                    crate::webhooks::EVENT_RESOLVED,
+                                    json!({
+                                        "event_id": event_id,
+                                        "outcome_id": outcome_id,
+                                        "source": source
+                                    }),
+                                );
                                 println!(
                                     "✅ Resolved MC event {} ({}: {}) -> outcome {} ({:?})",
                                     event_id, source, external_id, outcome_id, label
@@ -254,6 +426,14 @@ async fn sync_mc_resolutions(
                     }
                 }
             }
+            Ok(McVerdict::Annulled) => {
+                if annul_synced(pool, event_id, &source, &external_id).await {
+                    stats.resolved += 1;
+                    stats.mc_resolved += 1;
+                } else {
+                    stats.errors += 1;
+                }
+            }
             Ok(McVerdict::StillOpen) => stats.still_open += 1,
             Ok(McVerdict::Unsupported) => stats.unsupported += 1,
             Err(err) => {
@@ -366,6 +546,16 @@ async fn sync_numeric_resolutions(
                             Ok(()) => {
                                 stats.resolved += 1;
                                 stats.numeric_resolved += 1;
+                                crate::webhooks::emit(
+                                    pool,
+                                    crate::webhooks::EVENT_RESOLVED,
+                                    json!({
+                                        "event_id": event_id,
+                                        "outcome_id": outcome_id,
+                                        "numerical_outcome": value,
+                                        "source": source
+                                    }),
+                                );
                                 println!(
                                     "✅ Resolved numeric event {} ({}: {}) -> outcome {} (value {})",
                                     event_id, source, external_id, outcome_id, value
@@ -392,6 +582,14 @@ async fn sync_numeric_resolutions(
                     }
                 }
             }
+            Ok(NumericVerdict::Annulled) => {
+                if annul_synced(pool, event_id, &source, &external_id).await {
+                    stats.resolved += 1;
+                    stats.numeric_resolved += 1;
+                } else {
+                    stats.errors += 1;
+                }
+            }
             Ok(NumericVerdict::StillOpen) => stats.still_open += 1,
             Ok(NumericVerdict::Unsupported) => stats.unsupported += 1,
             Err(err) => {
@@ -409,6 +607,33 @@ async fn sync_numeric_resolutions(
     Ok(())
 }
 
+// Resolves a multiple-choice or numeric event N/A on the provider's say-so,
+// refunding its positions. False when settling failed.
+async fn annul_synced(pool: &PgPool, event_id: i32, source: &str, external_id: &str) -> bool {
+    match crate::lmsr_api::annul_event(pool, event_id).await {
+        Ok(_) => {
+            crate::webhooks::emit(
+                pool,
+                crate::webhooks::EVENT_RESOLVED,
+                json!({
+                    "event_id": event_id,
+                    "resolution": crate::lmsr_api::OUTCOME_NOT_APPLICABLE,
+                    "source": source
+                }),
+            );
+            println!(
+                "✅ Resolved event {} ({}: {}) -> N/A",
+                event_id, source, external_id
+            );
+            true
+        }
+        Err(err) => {
+            println!("⚠️ N/A settle failed for event {}: {}", event_id, err);
+            false
+        }
+    }
+}
+
 /// Case-insensitive, whitespace-trimmed match of a provider's winning-option
 /// label against our `event_outcomes` rows. Returns the matching outcome id
 /// only when exactly one row's label matches after normalization; if zero or
@@ -431,10 +656,11 @@ fn match_outcome_label(outcomes: &[(i64, String)], resolution_label: &str) -> Op
 }
 
 enum Verdict {
-    Resolved(bool),
+    // YES/NO, or N/A for cancelled/annulled markets
+    Resolved(Resolution),
     StillOpen,
-    // Resolved on the provider but not expressible as YES/NO (MKT/percent
-    // resolutions, cancelled/annulled markets needing refunds).
+    // Resolved on the provider but not expressible as YES/NO/N/A
+    // (MKT/percent resolutions).
     Unsupported,
 }
 
@@ -446,8 +672,9 @@ async fn manifold_resolution(client: &Client, external_id: &str) -> Result<Verdi
         return Ok(Verdict::StillOpen);
     }
     match body["resolution"].as_str() {
-        Some("YES") => Ok(Verdict::Resolved(true)),
-        Some("NO") => Ok(Verdict::Resolved(false)),
+        Some("YES") => Ok(Verdict::Resolved(Resolution::Yes)),
+        Some("NO") => Ok(Verdict::Resolved(Resolution::No)),
+        Some("CANCEL") => Ok(Verdict::Resolved(Resolution::NotApplicable)),
         _ => Ok(Verdict::Unsupported),
     }
 }
@@ -467,9 +694,9 @@ async fn metaculus_resolution(client: &Client, external_id: &str) -> Result<Verd
 
     let resolution = body["question"]["resolution"].as_str().unwrap_or("");
     match resolution {
-        "yes" => Ok(Verdict::Resolved(true)),
-        "no" => Ok(Verdict::Resolved(false)),
-        "annulled" | "ambiguous" => Ok(Verdict::Unsupported),
+        "yes" => Ok(Verdict::Resolved(Resolution::Yes)),
+        "no" => Ok(Verdict::Resolved(Resolution::No)),
+        "annulled" | "ambiguous" => Ok(Verdict::Resolved(Resolution::NotApplicable)),
         _ => Ok(Verdict::StillOpen),
     }
 }
@@ -494,9 +721,9 @@ async fn polymarket_resolution(client: &Client, external_id: &str) -> Result<Ver
         return Ok(Verdict::Unsupported);
     }
     if parsed[0] > 0.99 && parsed[1] < 0.01 {
-        Ok(Verdict::Resolved(true))
+        Ok(Verdict::Resolved(Resolution::Yes))
     } else if parsed[1] > 0.99 && parsed[0] < 0.01 {
-        Ok(Verdict::Resolved(false))
+        Ok(Verdict::Resolved(Resolution::No))
     } else {
         Ok(Verdict::Unsupported)
     }
@@ -505,10 +732,12 @@ async fn polymarket_resolution(client: &Client, external_id: &str) -> Result<Ver
 enum McVerdict {
     // Winning option's label, verbatim from the provider.
     Resolved(String),
+    // Manifold CANCEL, Metaculus annulled/ambiguous: resolves N/A.
+    Annulled,
     StillOpen,
     // Resolved on the provider but not a single-winner label (Manifold
-    // CANCEL/MKT, Metaculus annulled/ambiguous), or the winning answer
-    // couldn't be matched back to a label at all.
+    // MKT), or the winning answer couldn't be matched back to a label at
+    // all.
     Unsupported,
 }
 
@@ -523,7 +752,8 @@ async fn manifold_mc_resolution(client: &Client, external_id: &str) -> Result<Mc
     // or "CANCEL" (voided) / "MKT" (weighted multi-winner - no single
     // label). Verified live 2026-07-14 against a resolved MC market.
     let resolution = match body["resolution"].as_str() {
-        Some(r) if !r.is_empty() && r != "CANCEL" && r != "MKT" => r,
+        Some("CANCEL") => return Ok(McVerdict::Annulled),
+        Some(r) if !r.is_empty() && r != "MKT" => r,
         _ => return Ok(McVerdict::Unsupported),
     };
 
@@ -561,7 +791,7 @@ async fn metaculus_mc_resolution(client: &Client, external_id: &str) -> Result<M
     // token's access level, for genuinely resolved multiple_choice posts.
     match body["question"]["resolution"].as_str() {
         None => Ok(McVerdict::StillOpen),
-        Some("annulled") | Some("ambiguous") => Ok(McVerdict::Unsupported),
+        Some("annulled") | Some("ambiguous") => Ok(McVerdict::Annulled),
         Some(label) => Ok(McVerdict::Resolved(label.to_string())),
     }
 }
@@ -569,9 +799,11 @@ async fn metaculus_mc_resolution(client: &Client, external_id: &str) -> Result<M
 enum NumericVerdict {
     // Provider's resolved value, verbatim (before bin-mapping).
     Resolved(f64),
+    // Metaculus annulled/ambiguous: resolves N/A.
+    Annulled,
     StillOpen,
-    // Resolved on the provider but not a plain numeric value (Metaculus
-    // annulled/ambiguous, or any other non-numeric resolution string).
+    // Resolved on the provider but not a plain numeric value (any other
+    // non-numeric resolution string).
     Unsupported,
 }
 
@@ -595,7 +827,7 @@ async fn metaculus_numeric_resolution(client: &Client, external_id: &str) -> Res
     // cross-checked one via the detail endpoint).
     match body["question"]["resolution"].as_str() {
         None => Ok(NumericVerdict::StillOpen),
-        Some("annulled") | Some("ambiguous") => Ok(NumericVerdict::Unsupported),
+        Some("annulled") | Some("ambiguous") => Ok(NumericVerdict::Annulled),
         Some(raw) => match raw.parse::<f64>() {
             Ok(value) if value.is_finite() => Ok(NumericVerdict::Resolved(value)),
             _ => Ok(NumericVerdict::Unsupported),
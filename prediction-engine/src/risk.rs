@@ -0,0 +1,294 @@
+//! Risk metrics for the "responsible forecasting" panel.
+//!
+//! Bankroll is RP held plus RP staked. There is no bankroll history, so it
+//! is rebuilt backwards from today's figure by undoing the settlements and
+//! faucet grants since a point in time (see `bankroll_at`). Kelly ratios
+//! compare buys with the Kelly suggestion of their time; drawdown follows
+//! settlement P&L. Competition markets are left out; they trade a
+//! competition bankroll.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use sqlx::{PgPool, Row};
+
+use crate::api_error::{coded, ErrorCode};
+use crate::config::Config;
+use crate::exposure;
+use crate::lmsr_api::kelly_suggestion;
+use crate::lmsr_core::from_ledger_units;
+
+/// A binary buy: the user moved the price from `prev_prob` to `new_prob`.
+#[derive(Debug, Clone)]
+pub struct Buy {
+    pub at: DateTime<Utc>,
+    pub prev_prob: f64,
+    pub new_prob: f64,
+    pub stake: f64,
+}
+
+/// A change to the bankroll total: settlement P&L or a faucet grant.
+#[derive(Debug, Clone)]
+pub struct BankrollChange {
+    pub at: DateTime<Utc>,
+    pub amount: f64,
+    /// Set for settlements, which count toward drawdown.
+    pub event_id: Option<i32>,
+}
+
+/// The bankroll just before `at`, given today's bankroll and every change
+/// in the window. Buys only move RP from held to staked, so they don't
+/// count. Sells realize P&L but aren't journaled with a time, so earlier
+/// bankrolls are off by whatever sells realized since.
+fn bankroll_at(current: f64, changes: &[BankrollChange], at: DateTime<Utc>) -> f64 {
+    current
+        - changes
+            .iter()
+            .filter(|c| c.at >= at)
+            .map(|c| c.amount)
+            .sum::<f64>()
+}
+
+/// Each buy's stake over the Kelly suggestion at its time, taking the price
+/// the user moved the market to as their belief. Buys with no bankroll or
+/// no suggestion to compare against are skipped.
+pub fn kelly_ratios(
+    config: &Config,
+    buys: &[Buy],
+    current_bankroll: f64,
+    changes: &[BankrollChange],
+) -> Vec<f64> {
+    buys.iter()
+        .filter_map(|buy| {
+            let bankroll = bankroll_at(current_bankroll, changes, buy.at);
+            if bankroll <= 0.0 {
+                return None;
+            }
+            let suggested =
+                kelly_suggestion(config, buy.new_prob, buy.prev_prob, bankroll).kelly_suggestion;
+            (suggested > 0.0).then(|| buy.stake / suggested)
+        })
+        .collect()
+}
+
+fn median(values: &[f64]) -> Option<f64> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let mid = sorted.len() / 2;
+    match sorted.len() {
+        0 => None,
+        n if n % 2 == 0 => Some((sorted[mid - 1] + sorted[mid]) / 2.0),
+        _ => Some(sorted[mid]),
+    }
+}
+
+/// Cumulative settlement P&L after each settlement, with the drop from the
+/// running peak. Drawdown percentages are against the bankroll at the peak.
+/// Faucet grants are skipped, so they neither cause nor hide a drawdown.
+pub fn drawdown_series(current_bankroll: f64, changes: &[BankrollChange]) -> Value {
+    let mut cumulative = 0.0;
+    // Cumulative P&L and bankroll at the running peak
+    let mut peak: Option<(f64, f64)> = None;
+    let mut max_drawdown = 0.0f64;
+    let mut max_drawdown_pct = 0.0f64;
+    let mut series = Vec::new();
+    for change in changes {
+        let Some(event_id) = change.event_id else {
+            continue;
+        };
+        let before = bankroll_at(current_bankroll, changes, change.at);
+        let (peak_pnl, _) = *peak.get_or_insert((0.0, before));
+        cumulative += change.amount;
+        let bankroll = before + change.amount;
+        if cumulative >= peak_pnl {
+            peak = Some((cumulative, bankroll));
+        }
+        let (peak_pnl, peak_bankroll) = peak.unwrap_or_default();
+        let drawdown = peak_pnl - cumulative;
+        let drawdown_pct = if peak_bankroll > 0.0 {
+            drawdown / peak_bankroll
+        } else {
+            0.0
+        };
+        max_drawdown = max_drawdown.max(drawdown);
+        max_drawdown_pct = max_drawdown_pct.max(drawdown_pct);
+        series.push(json!({
+            "at": change.at,
+            "event_id": event_id,
+            "pnl": change.amount,
+            "cumulative_pnl": cumulative,
+            "bankroll": bankroll,
+            "drawdown": drawdown,
+            "drawdown_pct": drawdown_pct,
+        }));
+    }
+    json!({
+        "max_drawdown": max_drawdown,
+        "max_drawdown_pct": max_drawdown_pct,
+        "current_drawdown": peak.map_or(0.0, |(p, _)| p - cumulative),
+        "series": series,
+    })
+}
+
+pub async fn get_risk(pool: &PgPool, config: &Config, user_id: i32, days: i64) -> Result<Value> {
+    if !(1..=3650).contains(&days) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "days must be between 1 and 3650",
+        ));
+    }
+    let user = sqlx::query(
+        "SELECT COALESCE(rp_balance_ledger, 0)::BIGINT AS balance_ledger,
+                COALESCE(rp_staked_ledger, 0)::BIGINT AS staked_ledger
+         FROM users WHERE id = $1",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| coded(ErrorCode::NotFound, "User not found"))?;
+    let balance = from_ledger_units(user.get::<i64, _>("balance_ledger") as i128);
+    let staked = from_ledger_units(user.get::<i64, _>("staked_ledger") as i128);
+    let bankroll = balance + staked;
+    let since = Utc::now() - chrono::Duration::days(days);
+
+    crate::disputes::ensure_dispute_tables(pool).await?;
+    crate::faucet::ensure_grants_table(pool).await?;
+    let mut changes: Vec<BankrollChange> = sqlx::query(
+        "SELECT rp.created_at AS at, rp.event_id,
+                rp.payout_ledger - rp.staked_yes_ledger - rp.staked_no_ledger AS amount_ledger
+         FROM resolution_payouts_all rp
+         JOIN events e ON e.id = rp.event_id
+         WHERE rp.user_id = $1 AND rp.reverted_at IS NULL
+           AND e.competition_id IS NULL AND rp.created_at >= $2
+         UNION ALL
+         SELECT g.created_at, NULL, g.amount_ledger
+         FROM rp_faucet_grants g
+         WHERE g.user_id = $1 AND g.created_at >= $2",
+    )
+    .bind(user_id)
+    .bind(since)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(|row| BankrollChange {
+        at: row.get("at"),
+        amount: from_ledger_units(row.get::<i64, _>("amount_ledger") as i128),
+        event_id: row.get("event_id"),
+    })
+    .collect();
+    changes.sort_by_key(|c| c.at);
+
+    let buys: Vec<Buy> = sqlx::query(
+        "SELECT mu.created_at, mu.prev_prob, mu.new_prob, mu.stake_amount
+         FROM market_updates_all mu
+         JOIN events e ON e.id = mu.event_id
+         WHERE mu.user_id = $1 AND e.competition_id IS NULL AND mu.created_at >= $2
+         ORDER BY mu.created_at, mu.id",
+    )
+    .bind(user_id)
+    .bind(since)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(|row| Buy {
+        at: row.get("created_at"),
+        prev_prob: row.get("prev_prob"),
+        new_prob: row.get("new_prob"),
+        stake: row.get("stake_amount"),
+    })
+    .collect();
+
+    let largest = exposure::load_positions(pool, user_id)
+        .await?
+        .into_iter()
+        .map(|p| (exposure::worst_case_loss(&p), p))
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(loss, p)| {
+            json!({
+                "event_id": p.event_id,
+                "title": p.title,
+                "staked": p.staked,
+                "worst_case_loss": loss,
+                "share_of_bankroll": if bankroll > 0.0 { loss / bankroll } else { 0.0 },
+            })
+        });
+
+    let ratios = kelly_ratios(config, &buys, bankroll, &changes);
+    let mean_ratio = (!ratios.is_empty()).then(|| ratios.iter().sum::<f64>() / ratios.len() as f64);
+
+    Ok(json!({
+        "user_id": user_id,
+        "window_days": days,
+        "balance": balance,
+        "staked": staked,
+        "bankroll": bankroll,
+        "fraction_staked": if bankroll > 0.0 { staked / bankroll } else { 0.0 },
+        "largest_exposure": largest,
+        "kelly": {
+            "kelly_fraction": config.market.kelly_fraction,
+            "trades": ratios.len(),
+            "mean_ratio": mean_ratio,
+            "median_ratio": median(&ratios),
+            "over_kelly_trades": ratios.iter().filter(|r| **r > 1.0).count(),
+        },
+        "drawdown": drawdown_series(bankroll, &changes),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 1, hour, 0, 0).unwrap()
+    }
+
+    fn settlement(hour: u32, event_id: i32, amount: f64) -> BankrollChange {
+        BankrollChange {
+            at: at(hour),
+            amount,
+            event_id: Some(event_id),
+        }
+    }
+
+    #[test]
+    fn drawdown_runs_from_the_peak_and_ignores_grants() {
+        let changes = vec![
+            settlement(1, 1, 100.0),
+            settlement(2, 2, -150.0),
+            BankrollChange {
+                at: at(3),
+                amount: 500.0,
+                event_id: None,
+            },
+            settlement(4, 3, 20.0),
+        ];
+        // 1000 before the first settlement: 1000 -> 1100 -> 950 -> 1450 -> 1470
+        let report = drawdown_series(1470.0, &changes);
+        let series = report["series"].as_array().unwrap();
+        assert_eq!(series.len(), 3);
+        assert_eq!(series[0]["bankroll"], 1100.0);
+        assert_eq!(series[1]["drawdown"], 150.0);
+        assert_eq!(report["max_drawdown"], 150.0);
+        assert!((report["max_drawdown_pct"].as_f64().unwrap() - 150.0 / 1100.0).abs() < 1e-12);
+        assert_eq!(report["current_drawdown"], 130.0);
+    }
+
+    #[test]
+    fn kelly_ratios_use_the_bankroll_at_each_trade() {
+        let config = Config::default();
+        let buy = |hour, stake| Buy {
+            at: at(hour),
+            prev_prob: 0.5,
+            new_prob: 0.6,
+            stake,
+        };
+        // Edge 0.2 at quarter Kelly: 5% of the bankroll at the time
+        let changes = vec![settlement(2, 1, 1000.0)];
+        let ratios = kelly_ratios(&config, &[buy(1, 50.0), buy(3, 50.0)], 2000.0, &changes);
+        assert!((ratios[0] - 1.0).abs() < 1e-9, "{:?}", ratios);
+        assert!((ratios[1] - 0.5).abs() < 1e-9, "{:?}", ratios);
+        assert!((median(&ratios).unwrap() - 0.75).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,459 @@
+//! Tamper detection for users' scoring inputs.
+//!
+//! Every user's score rests on what their resolved markets settled: the
+//! predictions marked correct or incorrect, the standing settlement payouts
+//! (stakes cleared, RP credited) and the P&L each resolved market realized.
+//! A SHA-256 checksum over those inputs is sealed in `user_score_checksums`
+//! by the transaction that changes them (a resolution, or a dispute's revert
+//! and re-resolution), so it always matches what the engine itself wrote.
+//! An audit recomputes each sealed checksum from the current rows; anything
+//! that changed them outside the engine, or a pipeline bug that bypassed
+//! the seal, shows up as a mismatch and is reported instead of flowing into
+//! leaderboards.
+//!
+//! The checksum has its own table because `user_reputation` went with the
+//! log-loss scores (reputation is the RP ledger now). Users whose history
+//! predates the table are reported as unsealed until an admin reseals them.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::{PgConnection, PgPool, Row};
+use std::collections::BTreeMap;
+
+use crate::api_error::{coded, ErrorCode};
+use crate::jobs::JobProgress;
+
+/// Users audited or resealed per batch.
+const BATCH: usize = 1000;
+
+/// One user's scoring inputs, each list in id order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScoreInputs {
+    /// (prediction id, event id, outcome) of resolved predictions.
+    pub predictions: Vec<(i32, i32, String)>,
+    /// (event id, outcome, staked yes, staked no, payout) of settlements
+    /// that haven't been reverted, in ledger units.
+    pub settlements: Vec<(i32, String, i64, i64, i64)>,
+    /// (event id, realized P&L) on resolved markets, in ledger units.
+    pub realized: Vec<(i32, i64)>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScoreMismatch {
+    pub user_id: i32,
+    pub sealed_checksum: String,
+    pub current_checksum: String,
+    pub sealed_at: DateTime<Utc>,
+    pub sealed_predictions: i32,
+    pub current_predictions: usize,
+    pub sealed_settlements: i32,
+    pub current_settlements: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScoreAudit {
+    pub users_checked: usize,
+    pub mismatches: Vec<ScoreMismatch>,
+    /// Users with resolved inputs but no sealed checksum.
+    pub unsealed: i64,
+    pub audited_at: DateTime<Utc>,
+}
+
+/// Hex SHA-256 over the inputs, one line per row.
+pub fn checksum(inputs: &ScoreInputs) -> String {
+    let mut hasher = Sha256::new();
+    for (id, event_id, outcome) in &inputs.predictions {
+        hasher.update(format!("prediction:{}:{}:{}\n", id, event_id, outcome));
+    }
+    for (event_id, outcome, staked_yes, staked_no, payout) in &inputs.settlements {
+        hasher.update(format!(
+            "settlement:{}:{}:{}:{}:{}\n",
+            event_id, outcome, staked_yes, staked_no, payout
+        ));
+    }
+    for (event_id, pnl) in &inputs.realized {
+        hasher.update(format!("realized:{}:{}\n", event_id, pnl));
+    }
+    hex::encode(hasher.finalize())
+}
+
+pub async fn ensure_checksums_table(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS user_score_checksums (
+            user_id INTEGER PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+            checksum VARCHAR(64) NOT NULL,
+            resolved_predictions INTEGER NOT NULL,
+            settlements INTEGER NOT NULL,
+            sealed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn table_exists(conn: &mut PgConnection, table: &str) -> Result<bool> {
+    Ok(sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL")
+        .bind(table)
+        .fetch_one(conn)
+        .await?)
+}
+
+/// Where each kind of input is read from; `None` for tables this database
+/// doesn't have.
+struct Sources {
+    predictions: bool,
+    payouts: Option<&'static str>,
+    realized: bool,
+}
+
+impl Sources {
+    async fn find(conn: &mut PgConnection) -> Result<Self> {
+        let payouts = if table_exists(conn, "resolution_payouts_all").await? {
+            Some("resolution_payouts_all")
+        } else if table_exists(conn, "resolution_payouts").await? {
+            Some("resolution_payouts")
+        } else {
+            None
+        };
+        Ok(Self {
+            predictions: table_exists(conn, "predictions").await?,
+            payouts,
+            realized: table_exists(conn, "user_realized_pnl").await?,
+        })
+    }
+
+    /// `SELECT user_id` over every resolved input, for a UNION.
+    fn resolved_users(&self) -> String {
+        let mut selects = Vec::new();
+        if self.predictions {
+            selects.push(
+                "SELECT user_id FROM predictions WHERE outcome IN ('correct', 'incorrect')"
+                    .to_string(),
+            );
+        }
+        if let Some(payouts) = self.payouts {
+            selects.push(format!(
+                "SELECT user_id FROM {} WHERE reverted_at IS NULL",
+                payouts
+            ));
+        }
+        if self.realized {
+            selects.push(
+                "SELECT r.user_id FROM user_realized_pnl r
+                 JOIN events e ON e.id = r.event_id WHERE e.outcome IS NOT NULL"
+                    .to_string(),
+            );
+        }
+        if selects.is_empty() {
+            "SELECT NULL::integer AS user_id WHERE FALSE".to_string()
+        } else {
+            selects.join(" UNION ")
+        }
+    }
+}
+
+/// Scoring inputs of `user_ids`; users without any get empty inputs.
+async fn load_inputs(
+    conn: &mut PgConnection,
+    sources: &Sources,
+    user_ids: &[i32],
+) -> Result<BTreeMap<i32, ScoreInputs>> {
+    let mut inputs: BTreeMap<i32, ScoreInputs> = user_ids
+        .iter()
+        .map(|&user_id| (user_id, ScoreInputs::default()))
+        .collect();
+    if sources.predictions {
+        let rows = sqlx::query(
+            "SELECT user_id, id, event_id, outcome FROM predictions
+             WHERE user_id = ANY($1) AND outcome IN ('correct', 'incorrect')
+             ORDER BY user_id, id",
+        )
+        .bind(user_ids)
+        .fetch_all(&mut *conn)
+        .await?;
+        for row in &rows {
+            if let Some(user) = inputs.get_mut(&row.get("user_id")) {
+                user.predictions
+                    .push((row.get("id"), row.get("event_id"), row.get("outcome")));
+            }
+        }
+    }
+    if let Some(payouts) = sources.payouts {
+        let rows = sqlx::query(&format!(
+            "SELECT user_id, event_id, outcome, staked_yes_ledger, staked_no_ledger, payout_ledger
+             FROM {} WHERE user_id = ANY($1) AND reverted_at IS NULL
+             ORDER BY user_id, id",
+            payouts
+        ))
+        .bind(user_ids)
+        .fetch_all(&mut *conn)
+        .await?;
+        for row in &rows {
+            if let Some(user) = inputs.get_mut(&row.get("user_id")) {
+                user.settlements.push((
+                    row.get("event_id"),
+                    row.get("outcome"),
+                    row.get("staked_yes_ledger"),
+                    row.get("staked_no_ledger"),
+                    row.get("payout_ledger"),
+                ));
+            }
+        }
+    }
+    if sources.realized {
+        let rows = sqlx::query(
+            "SELECT r.user_id, r.event_id, r.realized_pnl_ledger
+             FROM user_realized_pnl r
+             JOIN events e ON e.id = r.event_id
+             WHERE r.user_id = ANY($1) AND e.outcome IS NOT NULL
+             ORDER BY r.user_id, r.event_id",
+        )
+        .bind(user_ids)
+        .fetch_all(&mut *conn)
+        .await?;
+        for row in &rows {
+            if let Some(user) = inputs.get_mut(&row.get("user_id")) {
+                user.realized
+                    .push((row.get("event_id"), row.get("realized_pnl_ledger")));
+            }
+        }
+    }
+    Ok(inputs)
+}
+
+async fn seal(conn: &mut PgConnection, sources: &Sources, user_ids: &[i32]) -> Result<usize> {
+    if user_ids.is_empty() {
+        return Ok(0);
+    }
+    let inputs = load_inputs(conn, sources, user_ids).await?;
+    let ids: Vec<i32> = inputs.keys().copied().collect();
+    let checksums: Vec<String> = inputs.values().map(checksum).collect();
+    let predictions: Vec<i32> = inputs
+        .values()
+        .map(|i| i.predictions.len() as i32)
+        .collect();
+    let settlements: Vec<i32> = inputs
+        .values()
+        .map(|i| i.settlements.len() as i32)
+        .collect();
+    sqlx::query(
+        r#"
+        INSERT INTO user_score_checksums (user_id, checksum, resolved_predictions, settlements)
+        SELECT * FROM UNNEST($1::integer[], $2::text[], $3::integer[], $4::integer[])
+        ON CONFLICT (user_id) DO UPDATE
+        SET checksum = EXCLUDED.checksum,
+            resolved_predictions = EXCLUDED.resolved_predictions,
+            settlements = EXCLUDED.settlements,
+            sealed_at = NOW()
+        "#,
+    )
+    .bind(&ids)
+    .bind(&checksums)
+    .bind(&predictions)
+    .bind(&settlements)
+    .execute(&mut *conn)
+    .await?;
+    Ok(ids.len())
+}
+
+/// Reseals every user with inputs on `event_id`, from inside the
+/// transaction that resolved or reverted it. Databases without the table
+/// skip this.
+pub(crate) async fn seal_event(conn: &mut PgConnection, event_id: i32) -> Result<usize> {
+    if !table_exists(conn, "user_score_checksums").await? {
+        return Ok(0);
+    }
+    let sources = Sources::find(conn).await?;
+    let mut selects = Vec::new();
+    if sources.predictions {
+        selects.push("SELECT user_id FROM predictions WHERE event_id = $1".to_string());
+    }
+    if let Some(payouts) = sources.payouts {
+        selects.push(format!(
+            "SELECT user_id FROM {} WHERE event_id = $1",
+            payouts
+        ));
+    }
+    if sources.realized {
+        selects.push("SELECT user_id FROM user_realized_pnl WHERE event_id = $1".to_string());
+    }
+    if selects.is_empty() {
+        return Ok(0);
+    }
+    let user_ids: Vec<i32> = sqlx::query_scalar(&format!(
+        "SELECT user_id FROM ({}) u WHERE user_id IS NOT NULL ORDER BY user_id",
+        selects.join(" UNION ")
+    ))
+    .bind(event_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    seal(conn, &sources, &user_ids).await
+}
+
+/// Accepts the current inputs as correct: reseals `user_id`, or with none
+/// every user with resolved inputs (the backfill for history that predates
+/// the checksums, and the fix once a reported mismatch is explained).
+#[allow(dead_code)] // only the tests call this, not the server binary
+pub async fn reseal(pool: &PgPool, user_id: Option<i32>) -> Result<usize> {
+    reseal_with_progress(pool, user_id, &JobProgress::untracked("score_reseal")).await
+}
+
+/// `reseal`, reporting the users sealed after each batch.
+pub async fn reseal_with_progress(
+    pool: &PgPool,
+    user_id: Option<i32>,
+    progress: &JobProgress,
+) -> Result<usize> {
+    ensure_checksums_table(pool).await?;
+    let mut conn = pool.acquire().await?;
+    let sources = Sources::find(&mut conn).await?;
+    let user_ids: Vec<i32> = match user_id {
+        Some(user_id) => {
+            let exists: bool =
+                sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
+                    .bind(user_id)
+                    .fetch_one(&mut *conn)
+                    .await?;
+            if !exists {
+                return Err(coded(ErrorCode::NotFound, "User not found"));
+            }
+            vec![user_id]
+        }
+        None => {
+            sqlx::query_scalar(&format!(
+                "SELECT user_id FROM ({}) u WHERE user_id IS NOT NULL ORDER BY user_id",
+                sources.resolved_users()
+            ))
+            .fetch_all(&mut *conn)
+            .await?
+        }
+    };
+    let mut sealed = 0;
+    let mut done = 0;
+    for batch in user_ids.chunks(BATCH) {
+        sealed += seal(&mut conn, &sources, batch).await?;
+        done += batch.len();
+        progress.progress("reseal", done as u64, Some(user_ids.len() as u64));
+    }
+    Ok(sealed)
+}
+
+/// Recomputes every sealed checksum (or only `user_id`'s) and reports the
+/// ones that no longer match, with how many users have nothing sealed.
+pub async fn audit(pool: &PgPool, user_id: Option<i32>) -> Result<ScoreAudit> {
+    audit_with_progress(pool, user_id, &JobProgress::untracked("score_audit")).await
+}
+
+/// `audit`, reporting the users checked after each batch.
+pub async fn audit_with_progress(
+    pool: &PgPool,
+    user_id: Option<i32>,
+    progress: &JobProgress,
+) -> Result<ScoreAudit> {
+    ensure_checksums_table(pool).await?;
+    let mut conn = pool.acquire().await?;
+    let sources = Sources::find(&mut conn).await?;
+    let sealed = sqlx::query(
+        "SELECT user_id, checksum, resolved_predictions, settlements, sealed_at
+         FROM user_score_checksums
+         WHERE $1::integer IS NULL OR user_id = $1
+         ORDER BY user_id",
+    )
+    .bind(user_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut mismatches = Vec::new();
+    let mut checked = 0;
+    for batch in sealed.chunks(BATCH) {
+        let user_ids: Vec<i32> = batch.iter().map(|row| row.get("user_id")).collect();
+        let inputs = load_inputs(&mut conn, &sources, &user_ids).await?;
+        for row in batch {
+            let user_id: i32 = row.get("user_id");
+            let current = &inputs[&user_id];
+            let sealed_checksum: String = row.get("checksum");
+            let current_checksum = checksum(current);
+            if sealed_checksum != current_checksum {
+                mismatches.push(ScoreMismatch {
+                    user_id,
+                    sealed_checksum,
+                    current_checksum,
+                    sealed_at: row.get("sealed_at"),
+                    sealed_predictions: row.get("resolved_predictions"),
+                    current_predictions: current.predictions.len(),
+                    sealed_settlements: row.get("settlements"),
+                    current_settlements: current.settlements.len(),
+                });
+            }
+        }
+        checked += batch.len();
+        progress.progress("audit", checked as u64, Some(sealed.len() as u64));
+    }
+
+    let unsealed: i64 = sqlx::query_scalar(&format!(
+        "SELECT COUNT(*) FROM ({}) u
+         WHERE u.user_id IS NOT NULL AND ($1::integer IS NULL OR u.user_id = $1)
+           AND NOT EXISTS (SELECT 1 FROM user_score_checksums c WHERE c.user_id = u.user_id)",
+        sources.resolved_users()
+    ))
+    .bind(user_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    for mismatch in &mismatches {
+        tracing::error!(
+            user_id = mismatch.user_id,
+            sealed = %mismatch.sealed_checksum,
+            current = %mismatch.current_checksum,
+            "❌ Score checksum mismatch: scoring inputs changed outside the engine"
+        );
+    }
+    Ok(ScoreAudit {
+        users_checked: sealed.len(),
+        mismatches,
+        unsealed,
+        audited_at: Utc::now(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksum_changes_with_any_scoring_input() {
+        let inputs = ScoreInputs {
+            predictions: vec![(11, 3, "correct".to_string())],
+            settlements: vec![(3, "resolved_yes".to_string(), 40_000, 0, 61_000)],
+            realized: vec![(3, 21_000), (5, -10_000)],
+        };
+        let sealed = checksum(&inputs);
+        assert_eq!(sealed.len(), 64);
+        assert_eq!(checksum(&inputs.clone()), sealed);
+
+        let mut payout_edited = inputs.clone();
+        payout_edited.settlements[0].4 += 1;
+        let mut outcome_flipped = inputs.clone();
+        outcome_flipped.predictions[0].2 = "incorrect".to_string();
+        let mut loss_dropped = inputs.clone();
+        loss_dropped.realized.pop();
+        for tampered in [payout_edited, outcome_flipped, loss_dropped] {
+            assert_ne!(checksum(&tampered), sealed);
+        }
+        // Rows of one kind can't pass for another
+        let moved = ScoreInputs {
+            realized: vec![(11, 3)],
+            ..ScoreInputs::default()
+        };
+        let original = ScoreInputs {
+            predictions: vec![(11, 3, String::new())],
+            ..ScoreInputs::default()
+        };
+        assert_ne!(checksum(&moved), checksum(&original));
+    }
+}
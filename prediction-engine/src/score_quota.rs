@@ -0,0 +1,154 @@
+//! Daily limits on how much a user's predictions count toward their scores.
+//!
+//! Forecasting stakes no RP, so a user could make hundreds of predictions a
+//! day on trivial markets to pad their accuracy, mean log score and the
+//! peer bonus that rides on it. Each prediction is numbered within its
+//! user's day by creation time: the first `full_weight_per_day` count
+//! fully, each one after that counts `decay` times the one before, and
+//! those past `daily_cap` don't count at all. The per-user means in the
+//! scoring pipeline (population standing, live score totals, prediction
+//! listings) are weighted that way. Predictions past the cap are still
+//! accepted and listed; they just don't score.
+//!
+//! The policy is `Config::score_quota`. Days are the database's calendar
+//! days, as `predictions.created_at` records them.
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Row};
+
+use crate::user_predictions::SCORING_JOINS;
+
+/// Predictions numbered within their user's day, as `day_nth`.
+const NUMBERED_PREDICTIONS: &str = "SELECT *, ROW_NUMBER() OVER (
+                PARTITION BY user_id, created_at::date ORDER BY created_at, id
+            ) AS day_nth
+            FROM predictions";
+
+/// How much each of a user's predictions of the day counts toward their scores.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct QuotaPolicy {
+    /// Predictions per day that count at all, at least 1 (default: 50)
+    pub daily_cap: i64,
+
+    /// Predictions per day that count fully, 0 to `daily_cap` (default: 10)
+    pub full_weight_per_day: i64,
+
+    /// Each later prediction's weight relative to the one before, 0-1 (default: 0.8)
+    pub decay: f64,
+}
+
+impl Default for QuotaPolicy {
+    fn default() -> Self {
+        Self {
+            daily_cap: 50,
+            full_weight_per_day: 10,
+            decay: 0.8,
+        }
+    }
+}
+
+impl QuotaPolicy {
+    /// Weight of the user's `nth` prediction of the day, counting from 1.
+    pub fn weight(&self, nth: i64) -> f64 {
+        if nth > self.daily_cap {
+            0.0
+        } else if nth <= self.full_weight_per_day {
+            1.0
+        } else {
+            self.decay.powi((nth - self.full_weight_per_day) as i32)
+        }
+    }
+
+    /// `weight` as SQL over the expression `nth`.
+    fn weight_sql(&self, nth: &str) -> String {
+        format!(
+            "CASE WHEN {nth} > {cap} THEN 0::float8
+                  WHEN {nth} <= {full} THEN 1::float8
+                  ELSE POWER({decay:?}::float8, ({nth} - {full})::float8)
+             END",
+            nth = nth,
+            cap = self.daily_cap,
+            full = self.full_weight_per_day,
+            decay = self.decay,
+        )
+    }
+}
+
+/// `SCORING_JOINS` with each prediction's weight under `policy` as
+/// `quota.weight`.
+pub(crate) fn weighted_scoring_joins(policy: &QuotaPolicy) -> String {
+    format!(
+        "{}
+            CROSS JOIN LATERAL (SELECT {} AS weight) quota",
+        SCORING_JOINS.replacen(
+            "FROM predictions p",
+            &format!("FROM ({}) p", NUMBERED_PREDICTIONS),
+            1
+        ),
+        policy.weight_sql("p.day_nth")
+    )
+}
+
+/// Where a user stands against today's quota.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DailyQuota {
+    pub day: NaiveDate,
+    pub predictions_today: i64,
+    /// Predictions left today that count fully.
+    pub full_weight_remaining: i64,
+    /// Predictions left today that count at all.
+    pub scored_remaining: i64,
+    /// What the next prediction today would count for.
+    pub next_weight: f64,
+    pub policy: QuotaPolicy,
+}
+
+impl DailyQuota {
+    pub fn new(day: NaiveDate, predictions_today: i64, policy: QuotaPolicy) -> Self {
+        Self {
+            day,
+            predictions_today,
+            full_weight_remaining: (policy.full_weight_per_day - predictions_today).max(0),
+            scored_remaining: (policy.daily_cap - predictions_today).max(0),
+            next_weight: policy.weight(predictions_today + 1),
+            policy,
+        }
+    }
+}
+
+pub async fn daily_quota(pool: &PgPool, policy: &QuotaPolicy, user_id: i32) -> Result<DailyQuota> {
+    let row = sqlx::query(
+        "SELECT LOCALTIMESTAMP::date AS day,
+                (SELECT COUNT(*) FROM predictions
+                 WHERE user_id = $1 AND created_at::date = LOCALTIMESTAMP::date) AS today",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(DailyQuota::new(row.get("day"), row.get("today"), *policy))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weights_decay_past_the_free_allowance_and_stop_at_the_cap() {
+        let policy = QuotaPolicy {
+            daily_cap: 5,
+            full_weight_per_day: 2,
+            decay: 0.5,
+        };
+        let weights: Vec<f64> = (1..=6).map(|nth| policy.weight(nth)).collect();
+        assert_eq!(weights, vec![1.0, 1.0, 0.5, 0.25, 0.125, 0.0]);
+
+        let day = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let quota = DailyQuota::new(day, 3, policy);
+        assert_eq!(quota.full_weight_remaining, 0);
+        assert_eq!(quota.scored_remaining, 2);
+        assert_eq!(quota.next_weight, 0.25);
+        assert_eq!(DailyQuota::new(day, 9, policy).next_weight, 0.0);
+    }
+}
@@ -0,0 +1,266 @@
+// Source status: how each imported event currently lines up with the
+// provider it was mirrored from. Everything here is read from the
+// event_external_sources mapping the import sync keeps fresh (last_seen_at
+// is bumped and raw_payload rewritten on every sync that sees the market),
+// so no provider calls are made.
+//
+// source_status is the provider's status from the last sync, promoted to
+// "closed" once the provider close time has passed (syncs only fetch open
+// markets, so a closed market simply stops being seen). divergence is our
+// market_prob minus the provider's community forecast / last price, binary
+// events only.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::{PgPool, Row};
+use std::env;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceStatus {
+    pub event_id: i32,
+    pub title: String,
+    pub source: String,
+    pub external_id: String,
+    pub external_url: Option<String>,
+    pub last_synced_at: DateTime<Utc>,
+    /// Not seen by a sync for longer than SOURCE_STALE_HOURS while the
+    /// source still counts as open.
+    pub stale: bool,
+    pub source_status: String,
+    pub local_status: String,
+    pub in_sync: bool,
+    pub market_prob: Option<f64>,
+    pub community_prob: Option<f64>,
+    pub divergence: Option<f64>,
+}
+
+fn stale_after() -> Duration {
+    let hours = env::var("SOURCE_STALE_HOURS")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(48);
+    Duration::hours(hours)
+}
+
+pub(crate) fn derive_source_status(
+    raw_status: Option<&str>,
+    close_time: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> &'static str {
+    match raw_status.map(|s| s.trim().to_lowercase()).as_deref() {
+        Some("resolved") => "resolved",
+        Some("closed") => "closed",
+        _ if close_time.is_some_and(|t| t <= now) => "closed",
+        _ => "open",
+    }
+}
+
+pub(crate) fn derive_local_status(
+    outcome: Option<&str>,
+    closing_date: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> &'static str {
+    match outcome {
+        // Legacy Metaculus imports mark already-resolved questions 'pending'
+        // until the resolution backfill fills in the actual outcome.
+        Some("pending") => "closed",
+        Some(_) => "resolved",
+        None if closing_date.is_some_and(|t| t <= now) => "closed",
+        None => "open",
+    }
+}
+
+pub(crate) fn divergence(market_prob: Option<f64>, community_prob: Option<f64>) -> Option<f64> {
+    match (market_prob, community_prob) {
+        (Some(m), Some(c)) if m.is_finite() && c.is_finite() => Some(m - c),
+        _ => None,
+    }
+}
+
+const STATUS_SELECT: &str = r#"
+    SELECT e.id AS event_id, e.title, e.outcome, e.market_prob,
+           COALESCE(e.event_type, 'binary') AS event_type,
+           e.closing_date::timestamptz AS closing_date,
+           s.source, s.external_id, s.external_url, s.last_seen_at,
+           s.raw_payload->>'status' AS raw_status,
+           (s.raw_payload->>'close_time')::timestamptz AS source_close_time,
+           (s.raw_payload->>'community_prob')::double precision AS community_prob
+    FROM event_external_sources s
+    JOIN events e ON e.id = s.event_id
+"#;
+
+fn row_to_status(
+    row: &sqlx::postgres::PgRow,
+    now: DateTime<Utc>,
+    stale_after: Duration,
+) -> SourceStatus {
+    let event_type: String = row.get("event_type");
+    let market_prob: Option<f64> = if event_type == "binary" {
+        row.get("market_prob")
+    } else {
+        None
+    };
+    let community_prob: Option<f64> = row.get("community_prob");
+    let last_synced_at: DateTime<Utc> = row.get("last_seen_at");
+    let source_status = derive_source_status(
+        row.get::<Option<String>, _>("raw_status").as_deref(),
+        row.get("source_close_time"),
+        now,
+    );
+    let local_status = derive_local_status(
+        row.get::<Option<String>, _>("outcome").as_deref(),
+        row.get("closing_date"),
+        now,
+    );
+
+    SourceStatus {
+        event_id: row.get("event_id"),
+        title: row.get("title"),
+        source: row.get("source"),
+        external_id: row.get("external_id"),
+        external_url: row.get("external_url"),
+        last_synced_at,
+        stale: source_status == "open" && now - last_synced_at > stale_after,
+        source_status: source_status.to_string(),
+        local_status: local_status.to_string(),
+        in_sync: source_status == local_status,
+        market_prob,
+        community_prob,
+        divergence: divergence(market_prob, community_prob),
+    }
+}
+
+/// One entry per provider mapping (merged events can have several).
+pub async fn get_event_source_status(pool: &PgPool, event_id: i32) -> Result<Vec<SourceStatus>> {
+    crate::market_import::ensure_import_tables(pool).await?;
+    let rows = sqlx::query(&format!(
+        "{} WHERE s.event_id = $1 ORDER BY s.source",
+        STATUS_SELECT
+    ))
+    .bind(event_id)
+    .fetch_all(pool)
+    .await?;
+
+    let now = Utc::now();
+    let stale_after = stale_after();
+    Ok(rows
+        .iter()
+        .map(|row| row_to_status(row, now, stale_after))
+        .collect())
+}
+
+/// Bulk report over mapped events that are still open locally, largest
+/// absolute divergence first, plus per-source rollups.
+pub async fn get_source_status_report(
+    pool: &PgPool,
+    provider: Option<&str>,
+    min_divergence: Option<f64>,
+    limit: i64,
+) -> Result<Value> {
+    crate::market_import::ensure_import_tables(pool).await?;
+    let rows = sqlx::query(&format!(
+        "{} WHERE e.outcome IS NULL AND ($1::text IS NULL OR s.source = $1) ORDER BY s.last_seen_at DESC",
+        STATUS_SELECT
+    ))
+    .bind(provider)
+    .fetch_all(pool)
+    .await?;
+
+    let now = Utc::now();
+    let stale_after = stale_after();
+    let mut entries: Vec<SourceStatus> = rows
+        .iter()
+        .map(|row| row_to_status(row, now, stale_after))
+        .collect();
+
+    let mut by_source: std::collections::BTreeMap<String, (u32, u32, u32, f64, u32)> =
+        std::collections::BTreeMap::new();
+    for entry in &entries {
+        let agg = by_source.entry(entry.source.clone()).or_default();
+        agg.0 += 1;
+        if entry.stale {
+            agg.1 += 1;
+        }
+        if !entry.in_sync {
+            agg.2 += 1;
+        }
+        if let Some(d) = entry.divergence {
+            agg.3 += d.abs();
+            agg.4 += 1;
+        }
+    }
+    let summary: Vec<Value> = by_source
+        .into_iter()
+        .map(|(source, (count, stale, out_of_sync, abs_sum, with_div))| {
+            json!({
+                "source": source,
+                "events": count,
+                "stale": stale,
+                "out_of_sync": out_of_sync,
+                "with_community_prob": with_div,
+                "mean_abs_divergence": if with_div > 0 { Some(abs_sum / with_div as f64) } else { None },
+            })
+        })
+        .collect();
+
+    if let Some(threshold) = min_divergence {
+        entries.retain(|e| e.divergence.is_some_and(|d| d.abs() >= threshold));
+    }
+    entries.sort_by(|a, b| {
+        let da = a.divergence.map(f64::abs).unwrap_or(-1.0);
+        let db = b.divergence.map(f64::abs).unwrap_or(-1.0);
+        db.total_cmp(&da)
+    });
+    let total = entries.len();
+    entries.truncate(limit.clamp(1, 1000) as usize);
+
+    Ok(json!({
+        "generated_at": now,
+        "total": total,
+        "summary": summary,
+        "events": entries,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_status_promotes_past_close_to_closed() {
+        let now = Utc::now();
+        let past = Some(now - Duration::hours(1));
+        let future = Some(now + Duration::hours(1));
+        assert_eq!(derive_source_status(Some("open"), future, now), "open");
+        assert_eq!(derive_source_status(Some("open"), past, now), "closed");
+        assert_eq!(derive_source_status(None, None, now), "open");
+        assert_eq!(
+            derive_source_status(Some("Resolved"), future, now),
+            "resolved"
+        );
+    }
+
+    #[test]
+    fn local_status_treats_pending_as_closed() {
+        let now = Utc::now();
+        assert_eq!(derive_local_status(Some("pending"), None, now), "closed");
+        assert_eq!(
+            derive_local_status(Some("resolved_yes"), None, now),
+            "resolved"
+        );
+        assert_eq!(
+            derive_local_status(None, Some(now - Duration::minutes(5)), now),
+            "closed"
+        );
+    }
+
+    #[test]
+    fn divergence_needs_both_sides() {
+        assert!((divergence(Some(0.7), Some(0.55)).unwrap() - 0.15).abs() < 1e-12);
+        assert_eq!(divergence(Some(0.7), None), None);
+        assert_eq!(divergence(None, Some(0.4)), None);
+    }
+}
@@ -0,0 +1,255 @@
+//! Down-sampled probability series for chart sparklines.
+//!
+//! A sparkline is `points` probabilities for a binary market: one at each of
+//! the last `points - 1` interval boundaries, then the live probability. The
+//! boundaries are aligned to the interval, so within an interval every
+//! request asks for the same historical samples; those are cached per event
+//! until the next boundary, and only the live tail (served from the market
+//! state cache) changes between requests.
+//!
+//! The price at a boundary is the `new_prob` of the market's last trade at
+//! or before it. Sells leave no row in `market_updates`, so a sell shows up
+//! at the next boundary after the next buy. Before the first trade the line
+//! sits at that trade's `prev_prob`, the opening price.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use moka::future::Cache;
+use serde_json::{json, Value};
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::api_error::{coded, ErrorCode};
+use crate::market_cache::MarketStateCache;
+
+pub const MAX_EVENTS: usize = 100;
+pub const MAX_POINTS: u32 = 168;
+pub const MAX_INTERVAL_MINUTES: u32 = 1440;
+
+/// Historical samples keyed by (event, points, interval minutes, last
+/// boundary). Keys roll over at each boundary, so the TTL only has to
+/// clear out old ones.
+#[derive(Clone)]
+pub struct SparklineCache {
+    series: Cache<(i32, u32, u32, i64), Vec<f64>>,
+}
+
+impl Default for SparklineCache {
+    fn default() -> Self {
+        Self {
+            series: Cache::builder()
+                .max_capacity(50_000)
+                .time_to_live(Duration::from_secs(MAX_INTERVAL_MINUTES as u64 * 60))
+                .build(),
+        }
+    }
+}
+
+pub async fn ensure_sparkline_index(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_market_updates_event_created
+         ON market_updates(event_id, created_at, id)",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Parses `event_ids=1,2,3`, dropping duplicates but keeping the order.
+pub fn parse_event_ids(raw: &str) -> Result<Vec<i32>> {
+    let mut ids = Vec::new();
+    for part in raw.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let id: i32 = part.parse().map_err(|_| {
+            coded(
+                ErrorCode::InvalidRequest,
+                "event_ids must be comma-separated integers",
+            )
+        })?;
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    if ids.is_empty() || ids.len() > MAX_EVENTS {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("event_ids must name 1-{} events", MAX_EVENTS),
+        ));
+    }
+    Ok(ids)
+}
+
+/// The `points - 1` historical sample times, oldest first, ending at the
+/// last interval boundary at or before `now`.
+pub fn sample_times(now: DateTime<Utc>, points: u32, interval_minutes: u32) -> Vec<DateTime<Utc>> {
+    let interval = interval_minutes as i64 * 60;
+    let last = now.timestamp().div_euclid(interval) * interval;
+    (0..points as i64 - 1)
+        .rev()
+        .filter_map(|k| DateTime::from_timestamp(last - k * interval, 0))
+        .collect()
+}
+
+async fn load_history(
+    pool: &PgPool,
+    event_ids: &[i32],
+    times: &[DateTime<Utc>],
+) -> Result<HashMap<i32, Vec<f64>>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT e.id, s.n,
+            COALESCE(
+                (SELECT mu.new_prob FROM market_updates mu
+                 WHERE mu.event_id = e.id AND mu.created_at <= s.ts
+                 ORDER BY mu.created_at DESC, mu.id DESC LIMIT 1),
+                (SELECT mu.prev_prob FROM market_updates mu
+                 WHERE mu.event_id = e.id
+                 ORDER BY mu.created_at, mu.id LIMIT 1),
+                e.market_prob
+            ) AS prob
+        FROM events e
+        CROSS JOIN unnest($2::timestamptz[]) WITH ORDINALITY AS s(ts, n)
+        WHERE e.id = ANY($1)
+        ORDER BY e.id, s.n
+        "#,
+    )
+    .bind(event_ids)
+    .bind(times)
+    .fetch_all(pool)
+    .await?;
+
+    let mut history: HashMap<i32, Vec<f64>> = HashMap::new();
+    for row in rows {
+        history
+            .entry(row.get("id"))
+            .or_default()
+            .push(row.get("prob"));
+    }
+    Ok(history)
+}
+
+/// Sparklines for `event_ids` in request order. Unknown events are listed
+/// under `missing`, and markets that aren't binary under `unsupported`.
+pub async fn get_sparklines(
+    pool: &PgPool,
+    market_cache: &MarketStateCache,
+    cache: &SparklineCache,
+    event_ids: &[i32],
+    points: u32,
+    interval_minutes: u32,
+) -> Result<Value> {
+    if !(2..=MAX_POINTS).contains(&points) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("points must be between 2 and {}", MAX_POINTS),
+        ));
+    }
+    if !(1..=MAX_INTERVAL_MINUTES).contains(&interval_minutes) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!(
+                "interval_minutes must be between 1 and {}",
+                MAX_INTERVAL_MINUTES
+            ),
+        ));
+    }
+
+    let now = Utc::now();
+    let times = sample_times(now, points, interval_minutes);
+    let last = times.last().map(|t| t.timestamp()).unwrap_or_default();
+
+    let mut live = Vec::new();
+    let mut missing = Vec::new();
+    let mut unsupported = Vec::new();
+    for &event_id in event_ids {
+        match market_cache.get(pool, event_id).await {
+            Ok(state)
+                if state["market_type"]
+                    .as_str()
+                    .is_some_and(|t| t.eq_ignore_ascii_case("binary")) =>
+            {
+                live.push((event_id, state))
+            }
+            Ok(_) => unsupported.push(event_id),
+            Err(e) if e.to_string().contains("not found") => missing.push(event_id),
+            Err(e) => return Err(e),
+        }
+    }
+
+    let key = |event_id| (event_id, points, interval_minutes, last);
+    let mut history = HashMap::new();
+    let mut uncached = Vec::new();
+    for (event_id, _) in &live {
+        match cache.series.get(&key(*event_id)).await {
+            Some(series) => {
+                history.insert(*event_id, series);
+            }
+            None => uncached.push(*event_id),
+        }
+    }
+    if !uncached.is_empty() {
+        for (event_id, series) in load_history(pool, &uncached, &times).await? {
+            cache.series.insert(key(event_id), series.clone()).await;
+            history.insert(event_id, series);
+        }
+    }
+
+    let mut timestamps: Vec<DateTime<Utc>> = times.clone();
+    timestamps.push(now);
+    let sparklines: Vec<Value> = live
+        .into_iter()
+        .map(|(event_id, state)| {
+            let mut probabilities = history.remove(&event_id).unwrap_or_default();
+            probabilities.push(state["market_prob"].as_f64().unwrap_or_default());
+            json!({
+                "event_id": event_id,
+                "title": state["title"],
+                "probabilities": probabilities,
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "points": points,
+        "interval_minutes": interval_minutes,
+        "start": timestamps.first(),
+        "end": now,
+        "timestamps": timestamps,
+        "sparklines": sparklines,
+        "missing": missing,
+        "unsupported": unsupported,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration as ChronoDuration, TimeZone};
+
+    #[test]
+    fn sample_times_align_to_the_interval() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 16, 14, 37, 12).unwrap();
+        let times = sample_times(now, 4, 60);
+        assert_eq!(
+            times,
+            vec![
+                Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2026, 10, 16, 13, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2026, 10, 16, 14, 0, 0).unwrap(),
+            ]
+        );
+        assert_eq!(
+            times,
+            sample_times(now + ChronoDuration::minutes(20), 4, 60)
+        );
+    }
+
+    #[test]
+    fn event_ids_are_deduplicated_and_bounded() {
+        assert_eq!(parse_event_ids("3, 1,3,,2").unwrap(), vec![3, 1, 2]);
+        assert!(parse_event_ids("1,x").is_err());
+        assert!(parse_event_ids("").is_err());
+        let too_many: Vec<String> = (0..=MAX_EVENTS).map(|i| i.to_string()).collect();
+        assert!(parse_event_ids(&too_many.join(",")).is_err());
+    }
+}
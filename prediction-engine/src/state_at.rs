@@ -0,0 +1,238 @@
+//! A binary market's state as of a past moment.
+//!
+//! `GET /events/:id/state-at?ts=` rebuilds `q_yes`, `q_no` and the price at
+//! `ts` from the `market_updates` journal (archived trades included), so a
+//! dispute or a question like "what did the market say when X happened"
+//! doesn't need SQL archaeology. The market opens at q = 0 and each journaled
+//! buy adds the shares it bought.
+//!
+//! Binary sells are not journaled. Where a trade's `prev_prob` shows the price
+//! moved since the trade before it, `q_yes` is re-anchored to that price (LMSR
+//! prices from `q_yes - q_no` alone, so the price is exact even though the
+//! split between the two may not be). If such a move lies between the last
+//! trade before `ts` and the first one after, the sell may have come on either
+//! side of `ts`, and the state is reported as inexact.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+
+use crate::api_error::{coded, ErrorCode};
+use crate::lmsr_core::Market;
+
+/// Probability difference that counts as an unjournaled move.
+const PROB_TOLERANCE: f64 = 1e-6;
+
+/// One journaled buy.
+#[derive(Debug, Clone, Copy)]
+pub struct Trade {
+    pub id: i32,
+    pub created_at: DateTime<Utc>,
+    pub yes: bool,
+    pub prev_prob: f64,
+    pub shares: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketStateAt {
+    pub event_id: i32,
+    pub at: DateTime<Utc>,
+    pub liquidity_b: f64,
+    pub q_yes: f64,
+    pub q_no: f64,
+    pub prob_yes: f64,
+    /// Journaled trades up to `at`.
+    pub trades: usize,
+    pub last_trade_id: Option<i32>,
+    pub last_trade_at: Option<DateTime<Utc>>,
+    /// Unjournaled price moves re-anchored on the way to `at`.
+    pub gaps: usize,
+    /// False when an unjournaled move may have come before or after `at`.
+    pub exact: bool,
+}
+
+/// Sets q_yes so the market prices YES at `prob`, keeping q_no.
+fn anchor(market: &mut Market, prob: f64) {
+    let p = prob.clamp(1e-12, 1.0 - 1e-12);
+    market.q_yes = market.q_no + market.b * (p / (1.0 - p)).ln();
+}
+
+/// Replays `trades` (oldest first, all at or before `at`); `next_prev_prob`
+/// is the price the first trade after `at` saw, if there is one.
+pub fn reconstruct(
+    event_id: i32,
+    liquidity_b: f64,
+    at: DateTime<Utc>,
+    trades: &[Trade],
+    next_prev_prob: Option<f64>,
+) -> MarketStateAt {
+    let mut market = Market::new(liquidity_b);
+    let mut gaps = 0;
+    for trade in trades {
+        if (market.prob_yes() - trade.prev_prob).abs() > PROB_TOLERANCE {
+            anchor(&mut market, trade.prev_prob);
+            gaps += 1;
+        }
+        if trade.yes {
+            market.q_yes += trade.shares;
+        } else {
+            market.q_no += trade.shares;
+        }
+    }
+    let exact = next_prev_prob.is_none_or(|p| (market.prob_yes() - p).abs() <= PROB_TOLERANCE);
+    let last = trades.last();
+    MarketStateAt {
+        event_id,
+        at,
+        liquidity_b,
+        q_yes: market.q_yes,
+        q_no: market.q_no,
+        prob_yes: market.prob_yes(),
+        trades: trades.len(),
+        last_trade_id: last.map(|t| t.id),
+        last_trade_at: last.map(|t| t.created_at),
+        gaps,
+        exact,
+    }
+}
+
+fn trade(row: &sqlx::postgres::PgRow) -> Trade {
+    Trade {
+        id: row.get("id"),
+        created_at: row.get("created_at"),
+        yes: row
+            .get::<String, _>("share_type")
+            .eq_ignore_ascii_case("yes"),
+        prev_prob: row.get("prev_prob"),
+        shares: row.get("shares_acquired"),
+    }
+}
+
+/// The state of binary event `event_id` as of `at`.
+pub async fn state_at(pool: &PgPool, event_id: i32, at: DateTime<Utc>) -> Result<MarketStateAt> {
+    if at > Utc::now() {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "ts must not be in the future",
+        ));
+    }
+    let event = sqlx::query(
+        "SELECT liquidity_b::float8 AS liquidity_b, COALESCE(event_type, 'binary') AS event_type
+         FROM events WHERE id = $1",
+    )
+    .bind(event_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| coded(ErrorCode::NotFound, "Event not found"))?;
+    let event_type: String = event.get("event_type");
+    if event_type != "binary" {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!(
+                "state-at covers binary markets only; event {} is {}",
+                event_id, event_type
+            ),
+        ));
+    }
+
+    let trades: Vec<Trade> = sqlx::query(
+        "SELECT id, created_at, share_type, prev_prob::float8 AS prev_prob,
+                shares_acquired::float8 AS shares_acquired
+         FROM market_updates_all
+         WHERE event_id = $1 AND created_at <= $2
+         ORDER BY created_at, id",
+    )
+    .bind(event_id)
+    .bind(at)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(trade)
+    .collect();
+    let next_prev_prob: Option<f64> = sqlx::query_scalar(
+        "SELECT prev_prob::float8 FROM market_updates_all
+         WHERE event_id = $1 AND created_at > $2
+         ORDER BY created_at, id
+         LIMIT 1",
+    )
+    .bind(event_id)
+    .bind(at)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(reconstruct(
+        event_id,
+        event.get("liquidity_b"),
+        at,
+        &trades,
+        next_prev_prob,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lmsr_core::to_ledger_units;
+    use chrono::Duration;
+
+    #[test]
+    fn reconstructs_journaled_buys_and_flags_unjournaled_sells() {
+        let start = Utc::now() - Duration::hours(3);
+        let mut live = Market::new(100.0);
+        let mut journal = Vec::new();
+        for (i, (yes, stake)) in [(true, 40.0), (false, 15.0), (true, 25.0)]
+            .into_iter()
+            .enumerate()
+        {
+            let prev_prob = live.prob_yes();
+            let stake = to_ledger_units(stake).unwrap();
+            let (shares, _) = if yes {
+                live.buy_yes(stake).unwrap()
+            } else {
+                live.buy_no(stake).unwrap()
+            };
+            journal.push(Trade {
+                id: i as i32 + 1,
+                created_at: start + Duration::hours(i as i64),
+                yes,
+                prev_prob,
+                shares,
+            });
+            if i == 1 {
+                // An unjournaled sell after the second trade
+                live.sell_yes(10.0).unwrap();
+            }
+        }
+
+        // Before any trade: the opening state
+        let opening = reconstruct(1, 100.0, start - Duration::minutes(1), &[], Some(0.5));
+        assert_eq!(
+            (opening.q_yes, opening.q_no, opening.prob_yes),
+            (0.0, 0.0, 0.5)
+        );
+        assert!(opening.exact);
+
+        // After the first trade the state is exact
+        let first = reconstruct(1, 100.0, start, &journal[..1], Some(journal[1].prev_prob));
+        assert_eq!(first.q_yes, journal[0].shares);
+        assert!(first.exact && first.gaps == 0);
+
+        // The sell falls between the second trade and the third: inexact
+        let second = reconstruct(
+            1,
+            100.0,
+            start + Duration::minutes(90),
+            &journal[..2],
+            Some(journal[2].prev_prob),
+        );
+        assert!(!second.exact);
+
+        // Once past it, the re-anchored price matches the live market
+        let now = reconstruct(1, 100.0, Utc::now(), &journal, None);
+        assert!(now.exact);
+        assert_eq!(now.gaps, 1);
+        assert_eq!(now.last_trade_id, Some(3));
+        assert!((now.prob_yes - live.prob_yes()).abs() < 1e-9);
+    }
+}
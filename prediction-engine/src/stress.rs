@@ -6,23 +6,42 @@
 //! 3. **Performance**: Measures transaction throughput under high load
 //! 4. **Concurrency**: Stress-tests the database transaction logic with parallel operations
 //! 5. **Market Accuracy**: Simulates traders with varying skill levels
-
-use anyhow::Result;
+//! 6. **Chaos**: Optionally injects delays, connection kills and
+//!    serialization failures inside trade transactions
+//! 7. **Soak**: Hours at a steady trade rate, watching for slow leaks (see
+//!    [`soak`])
+//! 8. **Resolution storm**: Hundreds of resolutions inside a minute while
+//!    unrelated markets keep trading (see [`storm`])
+
+pub mod soak;
+pub mod storm;
+
+use anyhow::{anyhow, Context, Result};
+use hdrhistogram::Histogram;
 use rand::prelude::*;
+use serde::{Deserialize, Serialize};
 use sqlx::{PgPool, Row};
 use std::env;
-use std::sync::{Arc, OnceLock};
-use std::time::Instant;
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tracing::{error, info};
 
 use crate::config::Config;
-use crate::lmsr_api::{self, MarketUpdate};
+use crate::db_adapter::{self, Dialect};
+use crate::invariants;
+use crate::lmsr_api::{self, ChaosConfig, ChaosInjected, MarketUpdate};
 use crate::lmsr_core::{self, LEDGER_SCALE};
+use intellacc_math::scoring::{brier_score, log_score};
+use soak::SoakConfig;
+use storm::StormConfig;
 
 // --- Test Configuration ---
 const INITIAL_BALANCE_LEDGER: i64 = 1_000 * LEDGER_SCALE as i64; // 1000 RP
 
-// Simulation Parameters (defaults; override via STRESS_* env vars)
+// Simulation Parameters (defaults; override via STRESS_* env vars, a TOML
+// scenario file or stress_test CLI flags)
 const NUM_USERS: usize = 1_000;
 const NUM_EVENTS: usize = 1_000;
 const TRADES_PER_USER: usize = 1_000; // 1M trades total (1k users * 1k trades each)
@@ -31,43 +50,654 @@ const BATCH_SIZE: usize = 100; // Process trades in batches to reduce contention
 const SELL_PROBABILITY: f64 = 0.25;
 const MIN_SELL_SHARES: f64 = 0.0001;
 
-#[derive(Debug, Clone)]
-struct StressConfig {
-    num_users: usize,
-    num_events: usize,
-    trades_per_user: usize,
-    liquidity_b: f64,
-    batch_size: usize,
-    sell_probability: f64,
-    min_sell_shares: f64,
-}
-
-impl StressConfig {
-    fn from_env() -> Self {
-        let num_users = env_usize("STRESS_NUM_USERS", NUM_USERS);
-        let num_events = env_usize("STRESS_NUM_EVENTS", NUM_EVENTS);
-        let trades_per_user = env_usize("STRESS_TRADES_PER_USER", TRADES_PER_USER);
-        let batch_size = env_usize("STRESS_BATCH_SIZE", BATCH_SIZE);
-        let liquidity_b = env_f64("STRESS_LIQUIDITY_B", LIQUIDITY_B);
-        let sell_probability =
-            env_f64_clamped("STRESS_SELL_PROBABILITY", SELL_PROBABILITY, 0.0, 1.0);
-        let min_sell_shares = env_f64_min("STRESS_MIN_SELL_SHARES", MIN_SELL_SHARES, 0.0);
+/// How simulated trader skill (0.0 = pure noise, 1.0 = perfect knowledge)
+/// is drawn per user.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SkillDistribution {
+    Uniform {
+        min: f64,
+        max: f64,
+    },
+    Normal {
+        mean: f64,
+        std_dev: f64,
+    },
+    /// Two populations: `high_fraction` of users at `high`, the rest at `low`.
+    Bimodal {
+        low: f64,
+        high: f64,
+        high_fraction: f64,
+    },
+    Fixed {
+        value: f64,
+    },
+}
+
+impl Default for SkillDistribution {
+    fn default() -> Self {
+        SkillDistribution::Uniform { min: 0.0, max: 1.0 }
+    }
+}
+
+impl SkillDistribution {
+    /// CLI form: `uniform[:min,max]`, `normal:mean,std_dev`,
+    /// `bimodal:low,high,high_fraction` or `fixed:value`.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (kind, args) = spec.split_once(':').unwrap_or((spec, ""));
+        let nums = args
+            .split(',')
+            .filter(|part| !part.trim().is_empty())
+            .map(|part| {
+                part.trim()
+                    .parse::<f64>()
+                    .map_err(|_| anyhow!("invalid number '{}' in skill spec '{}'", part, spec))
+            })
+            .collect::<Result<Vec<f64>>>()?;
+        let dist = match (kind.trim().to_lowercase().as_str(), nums.as_slice()) {
+            ("uniform", []) => SkillDistribution::default(),
+            ("uniform", [min, max]) => SkillDistribution::Uniform {
+                min: *min,
+                max: *max,
+            },
+            ("normal", [mean, std_dev]) => SkillDistribution::Normal {
+                mean: *mean,
+                std_dev: *std_dev,
+            },
+            ("bimodal", [low, high, high_fraction]) => SkillDistribution::Bimodal {
+                low: *low,
+                high: *high,
+                high_fraction: *high_fraction,
+            },
+            ("fixed", [value]) => SkillDistribution::Fixed { value: *value },
+            _ => return Err(anyhow!("unrecognised skill distribution '{}'", spec)),
+        };
+        Ok(dist)
+    }
+
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        let skill = match *self {
+            SkillDistribution::Uniform { min, max } => {
+                if max > min {
+                    rng.gen_range(min..max)
+                } else {
+                    min
+                }
+            }
+            SkillDistribution::Normal { mean, std_dev } => {
+                // Box-Muller; avoids pulling in rand_distr for one draw.
+                let u1: f64 = rng.gen::<f64>().max(f64::MIN_POSITIVE);
+                let u2: f64 = rng.gen();
+                mean + std_dev * (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+            }
+            SkillDistribution::Bimodal {
+                low,
+                high,
+                high_fraction,
+            } => {
+                if rng.gen_bool(high_fraction.clamp(0.0, 1.0)) {
+                    high
+                } else {
+                    low
+                }
+            }
+            SkillDistribution::Fixed { value } => value,
+        };
+        skill.clamp(0.0, 1.0)
+    }
+}
+
+/// One stress run's parameters. Missing TOML keys fall back to the
+/// defaults above, so a scenario file only needs the knobs it changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StressScenario {
+    pub name: String,
+    pub num_users: usize,
+    pub num_events: usize,
+    pub trades_per_user: usize,
+    pub liquidity_b: f64,
+    pub batch_size: usize,
+    /// Trade mix: chance that a trade is a sell of existing shares rather
+    /// than a buy.
+    pub sell_probability: f64,
+    pub min_sell_shares: f64,
+    pub skill: SkillDistribution,
+    /// Wall-clock cap on the trade phase; users stop early once it passes.
+    pub duration_secs: Option<u64>,
+    /// Seed for every random draw (skills, beliefs, stakes, trade mix,
+    /// outcomes). None picks one; the report always carries the seed used,
+    /// so a failing run can be replayed with `--seed`.
+    pub seed: Option<u64>,
+    /// Fault injection inside trade transactions (`[chaos]` table); off by
+    /// default.
+    pub chaos: ChaosConfig,
+    /// Soak mode settings (`[soak]` table); when set, the stress_test binary
+    /// runs a soak instead of the burst simulation.
+    pub soak: Option<SoakConfig>,
+    /// Resolution storm settings (`[storm]` table); when set, the
+    /// stress_test binary runs a storm instead. num_events is then derived
+    /// from the storm's event counts.
+    pub storm: Option<StormConfig>,
+}
+
+impl Default for StressScenario {
+    fn default() -> Self {
+        Self {
+            name: "default".to_string(),
+            num_users: NUM_USERS,
+            num_events: NUM_EVENTS,
+            trades_per_user: TRADES_PER_USER,
+            liquidity_b: LIQUIDITY_B,
+            batch_size: BATCH_SIZE,
+            sell_probability: SELL_PROBABILITY,
+            min_sell_shares: MIN_SELL_SHARES,
+            skill: SkillDistribution::default(),
+            duration_secs: None,
+            seed: None,
+            chaos: ChaosConfig::default(),
+            soak: None,
+            storm: None,
+        }
+    }
+}
+
+impl StressScenario {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            num_users: env_usize("STRESS_NUM_USERS", defaults.num_users),
+            num_events: env_usize("STRESS_NUM_EVENTS", defaults.num_events),
+            trades_per_user: env_usize("STRESS_TRADES_PER_USER", defaults.trades_per_user),
+            batch_size: env_usize("STRESS_BATCH_SIZE", defaults.batch_size),
+            liquidity_b: env_f64("STRESS_LIQUIDITY_B", defaults.liquidity_b),
+            sell_probability: env_f64_clamped(
+                "STRESS_SELL_PROBABILITY",
+                defaults.sell_probability,
+                0.0,
+                1.0,
+            ),
+            min_sell_shares: env_f64_min("STRESS_MIN_SELL_SHARES", defaults.min_sell_shares, 0.0),
+            seed: env::var("STRESS_SEED")
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok()),
+            chaos: ChaosConfig {
+                delay_rate: env_f64_clamped("STRESS_CHAOS_DELAY_RATE", 0.0, 0.0, 1.0),
+                max_delay_ms: env_usize(
+                    "STRESS_CHAOS_MAX_DELAY_MS",
+                    defaults.chaos.max_delay_ms as usize,
+                ) as u64,
+                kill_connection_rate: env_f64_clamped("STRESS_CHAOS_KILL_RATE", 0.0, 0.0, 1.0),
+                serialization_failure_rate: env_f64_clamped(
+                    "STRESS_CHAOS_SERIALIZATION_RATE",
+                    0.0,
+                    0.0,
+                    1.0,
+                ),
+            },
+            soak: env::var("STRESS_SOAK_HOURS")
+                .ok()
+                .and_then(|value| value.parse::<f64>().ok())
+                .filter(|hours| hours.is_finite() && *hours > 0.0)
+                .map(|hours| SoakConfig {
+                    duration_secs: (hours * 3600.0) as u64,
+                    trades_per_sec: env_f64(
+                        "STRESS_SOAK_RATE",
+                        SoakConfig::default().trades_per_sec,
+                    ),
+                    ..SoakConfig::default()
+                }),
+            storm: env::var("STRESS_STORM_EVENTS")
+                .ok()
+                .and_then(|value| value.parse::<usize>().ok())
+                .filter(|events| *events > 0)
+                .map(|events| StormConfig {
+                    events,
+                    window_secs: env_usize(
+                        "STRESS_STORM_WINDOW_SECS",
+                        StormConfig::default().window_secs as usize,
+                    ) as u64,
+                    ..StormConfig::default()
+                }),
+            ..defaults
+        }
+    }
+
+    pub fn from_toml_str(raw: &str) -> Result<Self> {
+        let scenario: Self = toml::from_str(raw)?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    pub fn from_toml_file(path: &Path) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("reading scenario file {}", path.display()))?;
+        Self::from_toml_str(&raw)
+            .with_context(|| format!("parsing scenario file {}", path.display()))
+    }
+
+    /// Applies one `--flag value` CLI override.
+    pub fn apply_flag(&mut self, flag: &str, value: &str) -> Result<()> {
+        fn num<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T> {
+            value
+                .parse::<T>()
+                .map_err(|_| anyhow!("invalid value '{}' for {}", value, flag))
+        }
+        match flag {
+            "--name" => self.name = value.to_string(),
+            "--users" => self.num_users = num(flag, value)?,
+            "--events" => self.num_events = num(flag, value)?,
+            "--trades-per-user" => self.trades_per_user = num(flag, value)?,
+            "--liquidity" => self.liquidity_b = num(flag, value)?,
+            "--batch-size" => self.batch_size = num(flag, value)?,
+            "--sell-ratio" => self.sell_probability = num(flag, value)?,
+            "--min-sell-shares" => self.min_sell_shares = num(flag, value)?,
+            "--skill" => self.skill = SkillDistribution::parse(value)?,
+            "--duration-secs" => self.duration_secs = Some(num(flag, value)?),
+            "--seed" => self.seed = Some(num(flag, value)?),
+            "--chaos-delay-rate" => self.chaos.delay_rate = num(flag, value)?,
+            "--chaos-max-delay-ms" => self.chaos.max_delay_ms = num(flag, value)?,
+            "--chaos-kill-rate" => self.chaos.kill_connection_rate = num(flag, value)?,
+            "--chaos-serialization-rate" => {
+                self.chaos.serialization_failure_rate = num(flag, value)?
+            }
+            "--soak-hours" => {
+                let hours: f64 = num(flag, value)?;
+                self.soak
+                    .get_or_insert_with(SoakConfig::default)
+                    .duration_secs = (hours * 3600.0) as u64;
+            }
+            "--soak-rate" => {
+                self.soak
+                    .get_or_insert_with(SoakConfig::default)
+                    .trades_per_sec = num(flag, value)?
+            }
+            "--soak-sample-secs" => {
+                self.soak
+                    .get_or_insert_with(SoakConfig::default)
+                    .sample_interval_secs = num(flag, value)?
+            }
+            "--storm-events" => {
+                self.storm.get_or_insert_with(StormConfig::default).events = num(flag, value)?
+            }
+            "--storm-window-secs" => {
+                self.storm
+                    .get_or_insert_with(StormConfig::default)
+                    .window_secs = num(flag, value)?
+            }
+            "--storm-predictions" => {
+                self.storm
+                    .get_or_insert_with(StormConfig::default)
+                    .predictions_per_event = num(flag, value)?
+            }
+            other => return Err(anyhow!("unknown flag {}", other)),
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.num_users == 0 || self.num_events == 0 || self.batch_size == 0 {
+            return Err(anyhow!(
+                "num_users, num_events and batch_size must be positive"
+            ));
+        }
+        if !self.liquidity_b.is_finite() || self.liquidity_b <= 0.0 {
+            return Err(anyhow!("liquidity_b must be positive"));
+        }
+        if !(0.0..=1.0).contains(&self.sell_probability) {
+            return Err(anyhow!("sell_probability must be between 0 and 1"));
+        }
+        if !self.min_sell_shares.is_finite() || self.min_sell_shares < 0.0 {
+            return Err(anyhow!("min_sell_shares must be non-negative"));
+        }
+        for (name, rate) in [
+            ("chaos.delay_rate", self.chaos.delay_rate),
+            (
+                "chaos.kill_connection_rate",
+                self.chaos.kill_connection_rate,
+            ),
+            (
+                "chaos.serialization_failure_rate",
+                self.chaos.serialization_failure_rate,
+            ),
+        ] {
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(anyhow!("{} must be between 0 and 1", name));
+            }
+        }
+        if let Some(soak) = &self.soak {
+            soak.validate()?;
+        }
+        if let Some(storm) = &self.storm {
+            if self.soak.is_some() {
+                return Err(anyhow!("soak and storm modes can't be combined"));
+            }
+            storm.validate()?;
+        }
+        Ok(())
+    }
+}
 
+/// Machine-readable result of one stress run.
+#[derive(Debug, Clone, Serialize)]
+pub struct StressReport {
+    pub scenario: StressScenario,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub seed: u64,
+    /// Backend the run's transactions went through
+    pub dialect: Dialect,
+    pub duration_secs: f64,
+    pub trades_attempted: u64,
+    pub trades_executed: u64,
+    pub trades_skipped: u64,
+    pub trades_failed: u64,
+    pub success_rate_pct: f64,
+    pub tps: f64,
+    /// True when duration_secs cut the trade phase short.
+    pub stopped_by_deadline: bool,
+    pub buy_latency: LatencySummary,
+    pub sell_latency: LatencySummary,
+    pub resolution_latency: LatencySummary,
+    pub retries: RetryStats,
+    /// Faults injected by chaos mode; None when it was off.
+    pub chaos: Option<ChaosStats>,
+    pub market_brier_score: f64,
+    pub market_accuracy: MarketAccuracy,
+    pub trader_performance: TraderPerformance,
+    pub initial_total_rp: f64,
+    pub final_total_rp: f64,
+    pub market_maker_subsidy_pct: f64,
+    pub invariant_failures: Vec<String>,
+}
+
+impl StressReport {
+    pub fn invariants_ok(&self) -> bool {
+        self.invariant_failures.is_empty()
+    }
+}
+
+// Latencies are recorded in microseconds; anything past an hour saturates.
+const LATENCY_MAX_MICROS: u64 = 3_600_000_000;
+
+pub(crate) fn latency_histogram() -> Histogram<u64> {
+    Histogram::new_with_bounds(1, LATENCY_MAX_MICROS, 3).expect("valid histogram bounds")
+}
+
+pub(crate) fn record_latency(histogram: &mut Histogram<u64>, latency: Duration) {
+    let micros = (latency.as_micros() as u64).clamp(1, LATENCY_MAX_MICROS);
+    histogram.saturating_record(micros);
+}
+
+/// Percentile summary of one operation's latencies, in milliseconds.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LatencySummary {
+    pub count: u64,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl LatencySummary {
+    pub(crate) fn from_histogram(histogram: &Histogram<u64>) -> Self {
+        if histogram.is_empty() {
+            return Self::default();
+        }
+        let ms = |micros: u64| micros as f64 / 1000.0;
         Self {
-            num_users,
-            num_events,
-            trades_per_user,
-            liquidity_b,
-            batch_size,
-            sell_probability,
-            min_sell_shares,
+            count: histogram.len(),
+            mean_ms: histogram.mean() / 1000.0,
+            p50_ms: ms(histogram.value_at_quantile(0.50)),
+            p95_ms: ms(histogram.value_at_quantile(0.95)),
+            p99_ms: ms(histogram.value_at_quantile(0.99)),
+            max_ms: ms(histogram.max()),
+        }
+    }
+}
+
+/// Transaction retries (serialization failures, deadlocks) per executed
+/// trade — the contention signal to compare between releases.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RetryStats {
+    pub total_retries: u64,
+    pub retries_per_trade: f64,
+    pub trades_with_retries: u64,
+    pub max_retries: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ChaosStats {
+    pub delays: u64,
+    pub connection_kills: u64,
+    pub serialization_failures: u64,
+    /// Trades that failed because their connection was killed (expected).
+    pub trades_failed_by_kill: u64,
+    /// Trades whose retry budget ran out on injected serialization failures.
+    pub trades_exhausted_retries: u64,
+}
+
+impl ChaosStats {
+    fn record(&mut self, injected: &ChaosInjected, failed_by_kill: bool, exhausted: bool) {
+        self.delays += injected.delays as u64;
+        self.connection_kills += injected.connection_kills as u64;
+        self.serialization_failures += injected.serialization_failures as u64;
+        if failed_by_kill {
+            self.trades_failed_by_kill += 1;
+        } else if exhausted {
+            self.trades_exhausted_retries += 1;
         }
     }
+
+    fn merge(&mut self, other: &ChaosStats) {
+        self.delays += other.delays;
+        self.connection_kills += other.connection_kills;
+        self.serialization_failures += other.serialization_failures;
+        self.trades_failed_by_kill += other.trades_failed_by_kill;
+        self.trades_exhausted_retries += other.trades_exhausted_retries;
+    }
+
+    /// Every injected serialization failure must either have been retried or
+    /// be the final error of a trade that used up its attempts.
+    fn unretried_failures(&self, retries: &RetryStats) -> u64 {
+        self.serialization_failures
+            .saturating_sub(retries.total_retries + self.trades_exhausted_retries)
+    }
 }
 
-fn stress_config() -> &'static StressConfig {
-    static CONFIG: OnceLock<StressConfig> = OnceLock::new();
-    CONFIG.get_or_init(StressConfig::from_env)
+const CALIBRATION_BUCKETS: usize = 10;
+
+/// How well final market prices tracked the hidden true probabilities.
+/// Scores against the sampled outcome are noisy by construction, so the
+/// true-prob baseline (the best any market could do on these draws) and
+/// the coin-flip baseline are reported alongside for scale.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MarketAccuracy {
+    pub events: usize,
+    pub brier_vs_outcome: f64,
+    pub log_score_vs_outcome: f64,
+    pub true_prob_brier: f64,
+    pub uninformed_brier: f64,
+    pub mean_abs_error_vs_true_prob: f64,
+    pub rmse_vs_true_prob: f64,
+    pub calibration: Vec<CalibrationBucket>,
+}
+
+/// Events grouped by final market probability decile.
+#[derive(Debug, Clone, Serialize)]
+pub struct CalibrationBucket {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: usize,
+    pub mean_market_prob: f64,
+    pub mean_true_prob: f64,
+    pub observed_yes_rate: f64,
+}
+
+/// Whether skill paid: per-user P&L after resolution against simulated skill.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TraderPerformance {
+    pub users: usize,
+    /// Pearson correlation of skill and P&L; None when either is constant.
+    pub skill_pnl_correlation: Option<f64>,
+    pub top_skill_quintile_mean_pnl: f64,
+    pub bottom_skill_quintile_mean_pnl: f64,
+}
+
+/// One resolved event: (final market prob, hidden true prob, outcome).
+type ResolvedSample = (f64, f64, bool);
+
+fn mean(values: impl Iterator<Item = f64>) -> f64 {
+    let (sum, count) = values.fold((0.0, 0usize), |(s, c), v| (s + v, c + 1));
+    if count == 0 {
+        0.0
+    } else {
+        sum / count as f64
+    }
+}
+
+fn evaluate_market_accuracy(samples: &[ResolvedSample]) -> MarketAccuracy {
+    if samples.is_empty() {
+        return MarketAccuracy::default();
+    }
+    let target = |outcome: bool| if outcome { 1.0 } else { 0.0 };
+    let calibration = (0..CALIBRATION_BUCKETS)
+        .filter_map(|bucket| {
+            let lower = bucket as f64 / CALIBRATION_BUCKETS as f64;
+            let upper = (bucket + 1) as f64 / CALIBRATION_BUCKETS as f64;
+            let members: Vec<&ResolvedSample> = samples
+                .iter()
+                .filter(|(market, _, _)| {
+                    let idx = ((market * CALIBRATION_BUCKETS as f64) as usize)
+                        .min(CALIBRATION_BUCKETS - 1);
+                    idx == bucket
+                })
+                .collect();
+            if members.is_empty() {
+                return None;
+            }
+            Some(CalibrationBucket {
+                lower,
+                upper,
+                count: members.len(),
+                mean_market_prob: mean(members.iter().map(|(m, _, _)| *m)),
+                mean_true_prob: mean(members.iter().map(|(_, t, _)| *t)),
+                observed_yes_rate: mean(members.iter().map(|(_, _, o)| target(*o))),
+            })
+        })
+        .collect();
+
+    MarketAccuracy {
+        events: samples.len(),
+        brier_vs_outcome: mean(samples.iter().map(|(m, _, o)| brier_score(*m, *o))),
+        log_score_vs_outcome: mean(samples.iter().map(|(m, _, o)| log_score(*m, *o))),
+        true_prob_brier: mean(samples.iter().map(|(_, t, o)| brier_score(*t, *o))),
+        uninformed_brier: 0.25,
+        mean_abs_error_vs_true_prob: mean(samples.iter().map(|(m, t, _)| (m - t).abs())),
+        rmse_vs_true_prob: mean(samples.iter().map(|(m, t, _)| (m - t).powi(2))).sqrt(),
+        calibration,
+    }
+}
+
+fn pearson(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    if xs.len() != ys.len() || xs.len() < 2 {
+        return None;
+    }
+    let mx = mean(xs.iter().copied());
+    let my = mean(ys.iter().copied());
+    let (mut cov, mut vx, mut vy) = (0.0, 0.0, 0.0);
+    for (x, y) in xs.iter().zip(ys) {
+        cov += (x - mx) * (y - my);
+        vx += (x - mx).powi(2);
+        vy += (y - my).powi(2);
+    }
+    if vx <= f64::EPSILON || vy <= f64::EPSILON {
+        return None;
+    }
+    Some(cov / (vx.sqrt() * vy.sqrt()))
+}
+
+/// `traders` holds (skill, pnl) per user.
+fn evaluate_trader_performance(traders: &[(f64, f64)]) -> TraderPerformance {
+    if traders.is_empty() {
+        return TraderPerformance::default();
+    }
+    let skills: Vec<f64> = traders.iter().map(|(s, _)| *s).collect();
+    let pnls: Vec<f64> = traders.iter().map(|(_, p)| *p).collect();
+    let mut by_skill = traders.to_vec();
+    by_skill.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let quintile = (by_skill.len() / 5).max(1);
+
+    TraderPerformance {
+        users: traders.len(),
+        skill_pnl_correlation: pearson(&skills, &pnls),
+        top_skill_quintile_mean_pnl: mean(by_skill.iter().rev().take(quintile).map(|(_, p)| *p)),
+        bottom_skill_quintile_mean_pnl: mean(by_skill.iter().take(quintile).map(|(_, p)| *p)),
+    }
+}
+
+/// Per-user-task counters, merged into the run totals after each batch.
+struct TradeTally {
+    executed: u64,
+    failed: u64,
+    skipped: u64,
+    buy_latency: Histogram<u64>,
+    sell_latency: Histogram<u64>,
+    retries: RetryStats,
+    chaos: ChaosStats,
+}
+
+impl TradeTally {
+    fn new() -> Self {
+        Self {
+            executed: 0,
+            failed: 0,
+            skipped: 0,
+            buy_latency: latency_histogram(),
+            sell_latency: latency_histogram(),
+            retries: RetryStats::default(),
+            chaos: ChaosStats::default(),
+        }
+    }
+
+    fn record_executed(&mut self, kind: TradeKind, latency: Duration, retries: u32) {
+        self.executed += 1;
+        match kind {
+            TradeKind::Buy => record_latency(&mut self.buy_latency, latency),
+            TradeKind::Sell => record_latency(&mut self.sell_latency, latency),
+        }
+        self.retries.total_retries += retries as u64;
+        if retries > 0 {
+            self.retries.trades_with_retries += 1;
+        }
+        self.retries.max_retries = self.retries.max_retries.max(retries);
+    }
+
+    fn merge(&mut self, other: &TradeTally) {
+        self.executed += other.executed;
+        self.failed += other.failed;
+        self.skipped += other.skipped;
+        let _ = self.buy_latency.add(&other.buy_latency);
+        let _ = self.sell_latency.add(&other.sell_latency);
+        self.retries.total_retries += other.retries.total_retries;
+        self.retries.trades_with_retries += other.retries.trades_with_retries;
+        self.retries.max_retries = self.retries.max_retries.max(other.retries.max_retries);
+        self.chaos.merge(&other.chaos);
+    }
+}
+
+// RNG streams derived from the run seed. Each user task gets its own stream
+// so draws don't depend on how tasks interleave; DB-level ordering under
+// concurrency still varies, but every random input is replayed exactly.
+const OUTCOME_STREAM: u64 = u64::MAX;
+const SETUP_STREAM: u64 = u64::MAX - 1;
+
+fn stream_rng(seed: u64, stream: u64) -> StdRng {
+    // splitmix64 finaliser, so neighbouring streams aren't correlated.
+    let mut z = seed ^ stream.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    StdRng::seed_from_u64(z ^ (z >> 31))
 }
 
 fn env_usize(name: &str, default: usize) -> usize {
@@ -108,15 +738,28 @@ struct TestEvent {
     true_prob: f64, // The actual, hidden probability of the event
 }
 
+#[derive(Debug, Clone, Copy)]
+enum TradeKind {
+    Buy,
+    Sell,
+}
+
 #[derive(Debug, Clone, Copy)]
 enum TradeOutcome {
-    Executed,
+    Executed {
+        kind: TradeKind,
+        latency: Duration,
+        retries: u32,
+    },
     Skipped,
 }
 
 /// Sets up a clean, isolated database for testing
 pub async fn setup_test_database(pool: &PgPool) -> Result<()> {
     // Drop and recreate tables to ensure clean state
+    sqlx::query("DROP TABLE IF EXISTS user_realized_pnl CASCADE")
+        .execute(pool)
+        .await?;
     sqlx::query("DROP TABLE IF EXISTS market_updates CASCADE")
         .execute(pool)
         .await?;
@@ -158,6 +801,9 @@ pub async fn setup_test_database(pool: &PgPool) -> Result<()> {
             q_yes DOUBLE PRECISION DEFAULT 0.0,
             q_no DOUBLE PRECISION DEFAULT 0.0,
             closing_date TIMESTAMP WITH TIME ZONE,
+            closed_at TIMESTAMP WITH TIME ZONE,
+            competition_id INTEGER,
+            anonymous_trading BOOLEAN NOT NULL DEFAULT FALSE,
             created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
             updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
         )
@@ -177,6 +823,7 @@ pub async fn setup_test_database(pool: &PgPool) -> Result<()> {
             total_staked_ledger BIGINT NOT NULL DEFAULT 0,
             staked_yes_ledger BIGINT NOT NULL DEFAULT 0,
             staked_no_ledger BIGINT NOT NULL DEFAULT 0,
+            realized_pnl_ledger BIGINT NOT NULL DEFAULT 0,
             last_updated TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
             version INTEGER NOT NULL DEFAULT 1,
             UNIQUE(user_id, event_id)
@@ -220,15 +867,20 @@ pub async fn setup_test_database(pool: &PgPool) -> Result<()> {
         .execute(pool)
         .await?;
 
+    // Every trade checks for a forecast-only event
+    crate::market_import::ensure_forecast_only_column(pool).await?;
+
     info!("✅ Test database schema created");
     Ok(())
 }
 
 /// Creates test users with varying skill levels
-async fn create_test_users(pool: &PgPool) -> Result<Vec<TestUser>> {
-    let stress = stress_config();
+async fn create_test_users(
+    pool: &PgPool,
+    stress: &StressScenario,
+    rng: &mut StdRng,
+) -> Result<Vec<TestUser>> {
     let mut users = Vec::new();
-    let mut rng = thread_rng();
 
     for i in 0..stress.num_users {
         let username = format!("testuser_{}", i);
@@ -244,7 +896,7 @@ async fn create_test_users(pool: &PgPool) -> Result<Vec<TestUser>> {
 
         users.push(TestUser {
             id: user_id,
-            skill: rng.gen(), // Random skill between 0.0 and 1.0
+            skill: stress.skill.sample(rng),
         });
     }
 
@@ -256,8 +908,7 @@ async fn create_test_users(pool: &PgPool) -> Result<Vec<TestUser>> {
 }
 
 /// Creates test market events with random "true" outcomes
-async fn create_test_events(pool: &PgPool) -> Result<Vec<TestEvent>> {
-    let stress = stress_config();
+async fn create_test_events(pool: &PgPool, stress: &StressScenario) -> Result<Vec<TestEvent>> {
     let mut events = Vec::new();
 
     info!("Creating {} test events...", stress.num_events);
@@ -311,17 +962,46 @@ fn simulate_belief(skill: f64, true_prob: f64, noise_factor: f64) -> f64 {
     (true_prob + noise).clamp(0.01, 0.99)
 }
 
+/// Runs one engine call under the retry counter and, in chaos mode, the
+/// fault injector, folding whatever was injected into `chaos`.
+async fn instrumented<T>(
+    stress: &StressScenario,
+    chaos_seed: Option<u64>,
+    chaos: &mut ChaosStats,
+    fut: impl Future<Output = Result<T>>,
+) -> (Result<T>, u32) {
+    let Some(seed) = chaos_seed else {
+        return lmsr_api::with_retry_count(fut).await;
+    };
+    let ((result, retries), injected) =
+        lmsr_api::with_chaos(&stress.chaos, seed, lmsr_api::with_retry_count(fut)).await;
+    let failed_by_kill = result.is_err() && injected.connection_kills > 0;
+    let exhausted = result
+        .as_ref()
+        .err()
+        .is_some_and(lmsr_api::is_injected_failure);
+    chaos.record(&injected, failed_by_kill, exhausted);
+    (result, retries)
+}
+
 /// Helper function to execute a single trade with proper error handling
 async fn try_execute_trade(
     pool: &PgPool,
     config: &Config,
-    user_id: i32,
-    event_id: i32,
-    belief: f64,
-    stake_multiplier: f64,
+    stress: &StressScenario,
+    user: &TestUser,
+    event: &TestEvent,
+    rng: &mut StdRng,
+    chaos: &mut ChaosStats,
 ) -> Result<TradeOutcome> {
-    let stress = stress_config();
-    let should_sell = rand::random::<f64>() < stress.sell_probability;
+    let (user_id, event_id) = (user.id, event.id);
+    // Draw every random factor up front, before any await
+    let noise_factor = rng.gen::<f64>();
+    let belief = simulate_belief(user.skill, event.true_prob, noise_factor);
+    let stake_multiplier = 0.5 + rng.gen::<f64>(); // 0.5 to 1.5
+    let should_sell = rng.gen::<f64>() < stress.sell_probability;
+    // Only drawn in chaos mode, so enabling it doesn't shift other draws
+    let chaos_seed = stress.chaos.is_enabled().then(|| rng.gen::<u64>());
 
     if should_sell {
         let shares_row = sqlx::query(
@@ -350,7 +1030,7 @@ async fn try_execute_trade(
         }
 
         let sell_yes = if yes_shares > 0.0 && no_shares > 0.0 {
-            rand::random::<f64>() * total_shares < yes_shares
+            rng.gen::<f64>() * total_shares < yes_shares
         } else {
             yes_shares > 0.0
         };
@@ -365,7 +1045,7 @@ async fn try_execute_trade(
             return Ok(TradeOutcome::Skipped);
         }
 
-        let sell_fraction = 0.1 + rand::random::<f64>() * 0.5; // 10% to 60% of holdings
+        let sell_fraction = 0.1 + rng.gen::<f64>() * 0.5; // 10% to 60% of holdings
         let amount = (available * sell_fraction)
             .max(stress.min_sell_shares)
             .min(available);
@@ -374,8 +1054,22 @@ async fn try_execute_trade(
             return Ok(TradeOutcome::Skipped);
         }
 
-        match lmsr_api::sell_shares(pool, config, user_id, event_id, share_type, amount).await {
-            Ok(_) => return Ok(TradeOutcome::Executed),
+        let started = Instant::now();
+        let (result, retries) = instrumented(
+            stress,
+            chaos_seed,
+            chaos,
+            lmsr_api::sell_shares(pool, config, user_id, event_id, share_type, amount, false),
+        )
+        .await;
+        match result {
+            Ok(_) => {
+                return Ok(TradeOutcome::Executed {
+                    kind: TradeKind::Sell,
+                    latency: started.elapsed(),
+                    retries,
+                })
+            }
             Err(err) => {
                 let message = err.to_string();
                 if message.contains("Hold period not expired")
@@ -418,11 +1112,25 @@ async fn try_execute_trade(
         stake,
         referral_post_id: None,
         referral_click_id: None,
+        max_cost: None,
+        min_shares: None,
     };
 
     // Execute the trade
-    match lmsr_api::update_market(pool, config, user_id, update).await {
-        Ok(_) => Ok(TradeOutcome::Executed),
+    let started = Instant::now();
+    let (result, retries) = instrumented(
+        stress,
+        chaos_seed,
+        chaos,
+        lmsr_api::update_market(pool, config, user_id, update),
+    )
+    .await;
+    match result {
+        Ok(_) => Ok(TradeOutcome::Executed {
+            kind: TradeKind::Buy,
+            latency: started.elapsed(),
+            retries,
+        }),
         Err(err) => {
             let message = err.to_string();
             if message.contains("Insufficient RP balance") {
@@ -434,17 +1142,53 @@ async fn try_execute_trade(
     }
 }
 
-/// Main stress test that simulates a high-load prediction market
+/// Main stress test that simulates a high-load prediction market, using the
+/// STRESS_* env scenario and failing loudly on any broken invariant.
 pub async fn run_stress_test(pool: &PgPool, config: &Config) -> Result<()> {
-    let stress = stress_config();
+    let report = run_scenario(pool, config, &StressScenario::from_env()).await?;
+    assert!(
+        report.market_brier_score < 0.35,
+        "Market should be more accurate than random chance!"
+    );
+    assert!(
+        report.invariants_ok(),
+        "Invariant failures: {:?}",
+        report.invariant_failures
+    );
+    Ok(())
+}
+
+/// Runs one scenario end to end (setup, trade phase, resolution,
+/// verification) and returns its report. Invariant violations are recorded
+/// in the report rather than panicking, so callers decide how to fail.
+pub async fn run_scenario(
+    pool: &PgPool,
+    config: &Config,
+    scenario: &StressScenario,
+) -> Result<StressReport> {
+    scenario.validate()?;
+    let seed = scenario.seed.unwrap_or_else(rand::random);
+    let scenario = StressScenario {
+        seed: Some(seed),
+        ..scenario.clone()
+    };
+    info!("🎲 Stress seed: {} (replay with --seed {})", seed, seed);
+    let stress = Arc::new(scenario.clone());
+    let started_at = chrono::Utc::now();
     // Setup test data
-    let users = create_test_users(pool).await?;
-    let events = create_test_events(pool).await?;
+    let users = create_test_users(pool, &stress, &mut stream_rng(seed, SETUP_STREAM)).await?;
+    let events = Arc::new(create_test_events(pool, &stress).await?);
     let pool = Arc::new(pool.clone());
     let config = Arc::new(config.clone());
     let start_time = Instant::now();
+    let deadline = stress
+        .duration_secs
+        .map(|secs| start_time + Duration::from_secs(secs));
 
-    info!("\n🚀 Starting high-load market simulation...");
+    info!(
+        "\n🚀 Starting high-load market simulation ({})...",
+        stress.name
+    );
     info!(
         "Target: {} trades ({} users × {} trades each)",
         stress.num_users * stress.trades_per_user,
@@ -452,52 +1196,56 @@ pub async fn run_stress_test(pool: &PgPool, config: &Config) -> Result<()> {
         stress.trades_per_user
     );
 
-    let mut successful_trades = 0u64;
-    let mut failed_trades = 0u64;
-    let mut skipped_trades = 0u64;
+    let mut tally = TradeTally::new();
 
     // Process trades in batches by user to reduce contention
     for user_batch_start in (0..stress.num_users).step_by(stress.batch_size) {
+        if deadline.is_some_and(|d| Instant::now() >= d) {
+            break;
+        }
         let user_batch_end = (user_batch_start + stress.batch_size).min(stress.num_users);
         let mut batch_handles = Vec::new();
 
         // Create concurrent tasks for this batch of users
-        for user_idx in user_batch_start..user_batch_end {
+        for (offset, user) in users[user_batch_start..user_batch_end].iter().enumerate() {
             let pool = Arc::clone(&pool);
             let config = Arc::clone(&config);
-            let user = users[user_idx].clone();
-            let events = events.clone();
+            let stress = Arc::clone(&stress);
+            let user = user.clone();
+            let events = Arc::clone(&events);
+
+            let mut rng = stream_rng(seed, (user_batch_start + offset) as u64);
 
             let handle = tokio::spawn(async move {
-                let mut user_successful = 0u64;
-                let mut user_failed = 0u64;
-                let mut user_skipped = 0u64;
+                let mut user_tally = TradeTally::new();
 
                 // Each user makes multiple trades
                 for trade_num in 0..stress.trades_per_user {
+                    if deadline.is_some_and(|d| Instant::now() >= d) {
+                        break;
+                    }
                     // Select a random event (deterministic but spread across events)
                     let event_idx = (user.id as usize + trade_num) % events.len();
                     let event = &events[event_idx];
 
-                    // Generate random factors before async operations
-                    let noise_factor = rand::random::<f64>();
-                    let belief = simulate_belief(user.skill, event.true_prob, noise_factor);
-                    let stake_multiplier = 0.5 + rand::random::<f64>(); // 0.5 to 1.5
-
-                    // Get user balance and current market state
                     match try_execute_trade(
                         &pool,
                         &config,
-                        user.id,
-                        event.id,
-                        belief,
-                        stake_multiplier,
+                        &stress,
+                        &user,
+                        event,
+                        &mut rng,
+                        &mut user_tally.chaos,
                     )
                     .await
                     {
-                        Ok(TradeOutcome::Executed) => user_successful += 1,
-                        Ok(TradeOutcome::Skipped) => user_skipped += 1,
-                        Err(_) => user_failed += 1, // Log details for debugging if needed
+                        Ok(TradeOutcome::Executed {
+                            kind,
+                            latency,
+                            retries,
+                        }) => user_tally.record_executed(kind, latency, retries),
+                        Ok(TradeOutcome::Skipped) => user_tally.skipped += 1,
+                        Err(_) => user_tally.failed += 1, // Log details for debugging if needed
                     }
 
                     // Add small delay every 100 trades to prevent overwhelming the system
@@ -506,7 +1254,7 @@ pub async fn run_stress_test(pool: &PgPool, config: &Config) -> Result<()> {
                     }
                 }
 
-                (user_successful, user_failed, user_skipped)
+                user_tally
             });
 
             batch_handles.push(handle);
@@ -515,39 +1263,38 @@ pub async fn run_stress_test(pool: &PgPool, config: &Config) -> Result<()> {
         // Wait for this batch to complete and collect results
         for handle in batch_handles {
             match handle.await {
-                Ok((s, f, k)) => {
-                    successful_trades += s;
-                    failed_trades += f;
-                    skipped_trades += k;
-                }
+                Ok(user_tally) => tally.merge(&user_tally),
                 Err(e) => {
                     error!("User task failed: {}", e);
-                    failed_trades += stress.trades_per_user as u64;
+                    tally.failed += stress.trades_per_user as u64;
                 }
             }
         }
 
         // Progress reporting
         let completed_users = user_batch_end;
-        let _total_attempted = completed_users * stress.trades_per_user;
         let current_duration = start_time.elapsed();
-        let current_tps = (successful_trades + failed_trades + skipped_trades) as f64
-            / current_duration.as_secs_f64();
+        let current_tps =
+            (tally.executed + tally.failed + tally.skipped) as f64 / current_duration.as_secs_f64();
 
         info!(
             "Progress: {}/{} users ({:.1}%) | {} successful, {} failed, {} skipped | {:.0} TPS",
             completed_users,
             stress.num_users,
             (completed_users as f64 / stress.num_users as f64) * 100.0,
-            successful_trades,
-            failed_trades,
-            skipped_trades,
+            tally.executed,
+            tally.failed,
+            tally.skipped,
             current_tps
         );
     }
 
+    let (successful_trades, failed_trades, skipped_trades) =
+        (tally.executed, tally.failed, tally.skipped);
     let total_trades = successful_trades + failed_trades + skipped_trades;
     let duration = start_time.elapsed();
+    let stopped_by_deadline = deadline.is_some_and(|d| Instant::now() >= d)
+        && total_trades < (stress.num_users * stress.trades_per_user) as u64;
     let tps = total_trades as f64 / duration.as_secs_f64();
     let success_rate = if total_trades == 0 {
         0.0
@@ -563,38 +1310,81 @@ pub async fn run_stress_test(pool: &PgPool, config: &Config) -> Result<()> {
     );
     info!("   Failed {} trades", failed_trades);
     info!("   Performance: {:.2} Transactions/Second", tps);
+    if stopped_by_deadline {
+        info!(
+            "   Trade phase stopped at the {}s duration cap",
+            stress.duration_secs.unwrap_or(0)
+        );
+    }
 
     // --- VERIFICATION & MEASUREMENT ---
     info!("\n🔍 Verifying financial invariants...");
+    let mut invariant_failures = Vec::new();
 
     // 1. Check initial total RP in the system
     let initial_total_rp: i64 = (stress.num_users as i64) * INITIAL_BALANCE_LEDGER;
 
     // 2. Resolve events and measure accuracy
-    let mut brier_scores = vec![];
-    for event in &events {
+    let mut outcome_rng = stream_rng(seed, OUTCOME_STREAM);
+    let mut resolved_samples: Vec<ResolvedSample> = Vec::with_capacity(events.len());
+    let mut resolution_latency = latency_histogram();
+    for event in events.iter() {
         let market_state_json = lmsr_api::get_market_state(&pool, event.id).await?;
         let final_prob = market_state_json["market_prob"].as_f64().unwrap();
 
         // Simulate the actual outcome based on true probability
-        let outcome = thread_rng().gen_bool(event.true_prob);
+        let outcome = outcome_rng.gen_bool(event.true_prob);
 
         // Resolve the event
+        let started = Instant::now();
         lmsr_api::resolve_event(&pool, event.id, outcome).await?;
+        record_latency(&mut resolution_latency, started.elapsed());
 
-        // Calculate Brier score (lower is better)
-        let brier_score = (final_prob - if outcome { 1.0 } else { 0.0 }).powi(2);
-        brier_scores.push(brier_score);
+        resolved_samples.push((final_prob, event.true_prob, outcome));
     }
 
-    let avg_brier_score = brier_scores.iter().sum::<f64>() / brier_scores.len() as f64;
+    let market_accuracy = evaluate_market_accuracy(&resolved_samples);
+    let avg_brier_score = market_accuracy.brier_vs_outcome;
     info!(
-        "   Market Accuracy (Avg Brier Score): {:.4}",
-        avg_brier_score
+        "   Market Accuracy (Avg Brier Score): {:.4} (true-prob baseline {:.4}, coin flip {:.2})",
+        avg_brier_score, market_accuracy.true_prob_brier, market_accuracy.uninformed_brier
     );
-    assert!(
-        avg_brier_score < 0.35,
-        "Market should be more accurate than random chance!"
+    info!(
+        "   Distance from true probabilities: MAE {:.4}, RMSE {:.4}",
+        market_accuracy.mean_abs_error_vs_true_prob, market_accuracy.rmse_vs_true_prob
+    );
+
+    // Trader P&L against skill, now that every position has been paid out.
+    let user_totals: Vec<(i32, i64)> = sqlx::query(
+        "SELECT id, (rp_balance_ledger + rp_staked_ledger)::BIGINT AS total FROM users",
+    )
+    .fetch_all(pool.as_ref())
+    .await?
+    .into_iter()
+    .map(|row| (row.get("id"), row.get("total")))
+    .collect();
+    let skill_by_user: std::collections::HashMap<i32, f64> =
+        users.iter().map(|u| (u.id, u.skill)).collect();
+    let traders: Vec<(f64, f64)> = user_totals
+        .iter()
+        .filter_map(|(id, total)| {
+            skill_by_user.get(id).map(|skill| {
+                (
+                    *skill,
+                    lmsr_core::from_ledger_units((*total - INITIAL_BALANCE_LEDGER) as i128),
+                )
+            })
+        })
+        .collect();
+    let trader_performance = evaluate_trader_performance(&traders);
+    info!(
+        "   Skill vs P&L correlation: {} (top quintile {:.2} RP, bottom quintile {:.2} RP)",
+        trader_performance
+            .skill_pnl_correlation
+            .map(|r| format!("{:.3}", r))
+            .unwrap_or_else(|| "n/a".to_string()),
+        trader_performance.top_skill_quintile_mean_pnl,
+        trader_performance.bottom_skill_quintile_mean_pnl
     );
 
     // 3. Verify final total RP
@@ -623,36 +1413,48 @@ pub async fn run_stress_test(pool: &PgPool, config: &Config) -> Result<()> {
         rp_difference_pct
     );
 
-    // 4. Verify system invariants for a sample of users
-    let sample_users: Vec<i32> =
-        sqlx::query_scalar("SELECT id FROM users ORDER BY RANDOM() LIMIT 10")
-            .fetch_all(pool.as_ref())
-            .await?;
+    // 4. Verify system invariants for a sample of users (all of them under
+    // chaos, where a single torn transaction is what we're hunting for)
+    let sample_size = if stress.chaos.is_enabled() {
+        users.len()
+    } else {
+        10
+    };
+    let sample_users: Vec<i32> = users
+        .choose_multiple(&mut outcome_rng, sample_size)
+        .map(|user| user.id)
+        .collect();
 
     for user_id in sample_users {
-        let balance_result = lmsr_api::verify_balance_invariant(&pool, user_id).await?;
-        assert!(
-            balance_result["valid"].as_bool().unwrap(),
-            "Balance invariant failed for user {}: {}",
-            user_id,
-            balance_result["message"]
-        );
+        for violation in invariants::check_user_ledger(&pool, user_id).await? {
+            invariant_failures.push(violation.to_string());
+        }
+    }
 
-        let staked_result = lmsr_api::verify_staked_invariant(&pool, user_id).await?;
-        assert!(
-            staked_result["valid"].as_bool().unwrap(),
-            "Staked invariant failed for user {}: {}",
-            user_id,
-            staked_result["message"]
+    let chaos = stress.chaos.is_enabled().then(|| tally.chaos.clone());
+    if let Some(chaos) = &chaos {
+        info!(
+            "   Chaos: {} delays, {} connection kills, {} serialization failures injected",
+            chaos.delays, chaos.connection_kills, chaos.serialization_failures
         );
+        let unretried = chaos.unretried_failures(&tally.retries);
+        if unretried > 0 {
+            invariant_failures.push(format!(
+                "{} injected serialization failures were neither retried nor surfaced",
+                unretried
+            ));
+        }
     }
 
-    info!("✅ Financial invariants maintained. System is sound.");
+    if invariant_failures.is_empty() {
+        info!("✅ Financial invariants maintained. System is sound.");
+    } else {
+        for failure in &invariant_failures {
+            error!("❌ {}", failure);
+        }
+    }
     info!("\n📊 Stress Test Summary:");
-    info!(
-        "   - Total trades attempted: {}",
-        stress.num_users * stress.trades_per_user
-    );
+    info!("   - Total trades attempted: {}", total_trades);
     info!("   - Successful trades: {}", successful_trades);
     info!("   - Failed trades: {}", failed_trades);
     info!("   - Skipped trades: {}", skipped_trades);
@@ -661,7 +1463,56 @@ pub async fn run_stress_test(pool: &PgPool, config: &Config) -> Result<()> {
     info!("   - Market accuracy (Brier): {:.4}", avg_brier_score);
     info!("   - Market maker subsidy: {:.2}%", rp_difference_pct);
 
-    Ok(())
+    let buy_latency = LatencySummary::from_histogram(&tally.buy_latency);
+    let sell_latency = LatencySummary::from_histogram(&tally.sell_latency);
+    let resolution_latency = LatencySummary::from_histogram(&resolution_latency);
+    let mut retries = tally.retries.clone();
+    retries.retries_per_trade = if successful_trades == 0 {
+        0.0
+    } else {
+        retries.total_retries as f64 / successful_trades as f64
+    };
+    for (label, summary) in [
+        ("buy", &buy_latency),
+        ("sell", &sell_latency),
+        ("resolve", &resolution_latency),
+    ] {
+        info!(
+            "   - {} latency: p50 {:.2}ms | p95 {:.2}ms | p99 {:.2}ms | max {:.2}ms (n={})",
+            label, summary.p50_ms, summary.p95_ms, summary.p99_ms, summary.max_ms, summary.count
+        );
+    }
+    info!(
+        "   - Retries per trade: {:.3} ({} trades retried, max {})",
+        retries.retries_per_trade, retries.trades_with_retries, retries.max_retries
+    );
+
+    Ok(StressReport {
+        scenario,
+        seed,
+        dialect: db_adapter::tx_backend().dialect(),
+        started_at,
+        duration_secs: duration.as_secs_f64(),
+        trades_attempted: total_trades,
+        trades_executed: successful_trades,
+        trades_skipped: skipped_trades,
+        trades_failed: failed_trades,
+        success_rate_pct: success_rate,
+        tps,
+        stopped_by_deadline,
+        buy_latency,
+        sell_latency,
+        resolution_latency,
+        retries,
+        chaos,
+        market_brier_score: avg_brier_score,
+        market_accuracy,
+        trader_performance,
+        initial_total_rp: lmsr_core::from_ledger_units(initial_total_rp as i128),
+        final_total_rp: lmsr_core::from_ledger_units(final_total_rp as i128),
+        market_maker_subsidy_pct: rp_difference_pct,
+        invariant_failures,
+    })
 }
 
 #[cfg(test)]
@@ -693,16 +1544,227 @@ mod tests {
             .acquire_timeout(Duration::from_secs(acquire_timeout_secs))
             .connect(&database_url)
             .await?;
+        let config = Config::from_env();
+        db_adapter::configure_tx_backend(&pool, &config.database.dialect).await?;
 
         // Setup test database schema
         setup_test_database(&pool).await?;
 
-        // Create test config
-        let config = Config::from_env();
-
         // Run the stress test
         run_stress_test(&pool, &config).await?;
 
         Ok(())
     }
+
+    #[test]
+    fn scenario_toml_overrides_only_given_keys() {
+        let scenario = StressScenario::from_toml_str(
+            r#"
+            name = "contention"
+            num_users = 50
+            sell_probability = 0.5
+            duration_secs = 30
+
+            [skill]
+            kind = "bimodal"
+            low = 0.1
+            high = 0.9
+            high_fraction = 0.2
+            "#,
+        )
+        .unwrap();
+        assert_eq!(scenario.name, "contention");
+        assert_eq!(scenario.num_users, 50);
+        assert_eq!(scenario.num_events, NUM_EVENTS);
+        assert_eq!(scenario.sell_probability, 0.5);
+        assert_eq!(scenario.duration_secs, Some(30));
+        assert_eq!(
+            scenario.skill,
+            SkillDistribution::Bimodal {
+                low: 0.1,
+                high: 0.9,
+                high_fraction: 0.2
+            }
+        );
+        assert!(StressScenario::from_toml_str("sell_probability = 1.5").is_err());
+    }
+
+    #[test]
+    fn cli_flags_and_skill_specs_parse() {
+        let mut scenario = StressScenario::default();
+        scenario.apply_flag("--users", "12").unwrap();
+        scenario.apply_flag("--sell-ratio", "0.1").unwrap();
+        scenario.apply_flag("--skill", "normal:0.6,0.15").unwrap();
+        assert_eq!(scenario.num_users, 12);
+        assert_eq!(scenario.sell_probability, 0.1);
+        assert_eq!(
+            scenario.skill,
+            SkillDistribution::Normal {
+                mean: 0.6,
+                std_dev: 0.15
+            }
+        );
+        assert!(scenario.apply_flag("--users", "many").is_err());
+        assert!(scenario.apply_flag("--bogus", "1").is_err());
+        assert!(SkillDistribution::parse("bimodal:0.1,0.9").is_err());
+    }
+
+    #[test]
+    fn latency_summary_reports_percentiles_in_ms() {
+        let mut histogram = latency_histogram();
+        for ms in 1..=100u64 {
+            record_latency(&mut histogram, Duration::from_millis(ms));
+        }
+        let summary = LatencySummary::from_histogram(&histogram);
+        assert_eq!(summary.count, 100);
+        assert!((summary.p50_ms - 50.0).abs() < 0.1);
+        assert!((summary.p95_ms - 95.0).abs() < 0.1);
+        assert!((summary.p99_ms - 99.0).abs() < 0.1);
+        assert!((summary.max_ms - 100.0).abs() < 0.1);
+
+        let empty = LatencySummary::from_histogram(&latency_histogram());
+        assert_eq!(empty.count, 0);
+        assert_eq!(empty.p99_ms, 0.0);
+    }
+
+    #[test]
+    fn tally_merge_accumulates_retries() {
+        let mut a = TradeTally::new();
+        a.record_executed(TradeKind::Buy, Duration::from_millis(3), 0);
+        let mut b = TradeTally::new();
+        b.record_executed(TradeKind::Sell, Duration::from_millis(8), 2);
+        b.record_executed(TradeKind::Buy, Duration::from_millis(5), 1);
+        a.merge(&b);
+        assert_eq!(a.executed, 3);
+        assert_eq!(a.retries.total_retries, 3);
+        assert_eq!(a.retries.trades_with_retries, 2);
+        assert_eq!(a.retries.max_retries, 2);
+        assert_eq!(a.buy_latency.len(), 2);
+        assert_eq!(a.sell_latency.len(), 1);
+    }
+
+    #[test]
+    fn market_accuracy_scores_against_outcome_and_truth() {
+        let samples = vec![(0.8, 0.7, true), (0.3, 0.2, false), (0.85, 0.9, true)];
+        let accuracy = evaluate_market_accuracy(&samples);
+        assert_eq!(accuracy.events, 3);
+        let expected_brier = (0.04 + 0.09 + 0.0225) / 3.0;
+        assert!((accuracy.brier_vs_outcome - expected_brier).abs() < 1e-12);
+        assert!((accuracy.mean_abs_error_vs_true_prob - (0.1 + 0.1 + 0.05) / 3.0).abs() < 1e-12);
+        // 0.8 and 0.85 share the [0.8, 0.9) decile; 0.3 sits alone.
+        assert_eq!(accuracy.calibration.len(), 2);
+        let high = accuracy.calibration.last().unwrap();
+        assert_eq!(high.count, 2);
+        assert_eq!(high.observed_yes_rate, 1.0);
+        assert!(accuracy.log_score_vs_outcome < 0.0);
+    }
+
+    #[test]
+    fn trader_performance_correlates_skill_with_pnl() {
+        let traders: Vec<(f64, f64)> = (0..10).map(|i| (i as f64 / 10.0, i as f64 * 5.0)).collect();
+        let perf = evaluate_trader_performance(&traders);
+        assert!((perf.skill_pnl_correlation.unwrap() - 1.0).abs() < 1e-12);
+        assert_eq!(perf.top_skill_quintile_mean_pnl, 42.5);
+        assert_eq!(perf.bottom_skill_quintile_mean_pnl, 2.5);
+        // Constant P&L has no defined correlation.
+        assert_eq!(pearson(&[0.1, 0.5, 0.9], &[1.0, 1.0, 1.0]), None);
+    }
+
+    #[test]
+    fn seeded_streams_replay_and_differ() {
+        let draw = |seed, stream| {
+            let mut rng = stream_rng(seed, stream);
+            (0..4).map(|_| rng.gen::<u64>()).collect::<Vec<_>>()
+        };
+        assert_eq!(draw(42, 3), draw(42, 3));
+        assert_ne!(draw(42, 3), draw(42, 4));
+        assert_ne!(draw(42, 3), draw(43, 3));
+
+        let mut scenario = StressScenario::default();
+        scenario.apply_flag("--seed", "1337").unwrap();
+        assert_eq!(scenario.seed, Some(1337));
+    }
+
+    #[test]
+    fn chaos_flags_validate_and_account_for_retries() {
+        let mut scenario = StressScenario::default();
+        assert!(!scenario.chaos.is_enabled());
+        scenario
+            .apply_flag("--chaos-serialization-rate", "0.2")
+            .unwrap();
+        scenario.apply_flag("--chaos-kill-rate", "0.01").unwrap();
+        assert!(scenario.chaos.is_enabled());
+        assert!(scenario.validate().is_ok());
+        scenario.apply_flag("--chaos-delay-rate", "2").unwrap();
+        assert!(scenario.validate().is_err());
+
+        let mut chaos = ChaosStats::default();
+        let injected = ChaosInjected {
+            serialization_failures: 3,
+            ..ChaosInjected::default()
+        };
+        chaos.record(&injected, false, false);
+        chaos.record(&injected, false, true);
+        assert_eq!(chaos.serialization_failures, 6);
+        assert_eq!(chaos.trades_exhausted_retries, 1);
+        let retries = RetryStats {
+            total_retries: 5,
+            ..RetryStats::default()
+        };
+        assert_eq!(chaos.unretried_failures(&retries), 0);
+        let too_few = RetryStats {
+            total_retries: 2,
+            ..RetryStats::default()
+        };
+        assert_eq!(chaos.unretried_failures(&too_few), 3);
+    }
+
+    #[test]
+    fn soak_flags_enable_soak_mode() {
+        let mut scenario = StressScenario::default();
+        assert!(scenario.soak.is_none());
+        scenario.apply_flag("--soak-hours", "2.5").unwrap();
+        scenario.apply_flag("--soak-rate", "40").unwrap();
+        let soak = scenario.soak.as_ref().unwrap();
+        assert_eq!(soak.duration_secs, 9000);
+        assert_eq!(soak.trades_per_sec, 40.0);
+        assert_eq!(soak.sample_interval_secs, 30);
+        scenario.apply_flag("--soak-rate", "0").unwrap();
+        assert!(scenario.validate().is_err());
+
+        let from_toml = StressScenario::from_toml_str("[soak]\nduration_secs = 600").unwrap();
+        assert_eq!(from_toml.soak.unwrap().duration_secs, 600);
+    }
+
+    #[test]
+    fn storm_flags_enable_storm_mode() {
+        let mut scenario = StressScenario::default();
+        scenario.apply_flag("--storm-events", "500").unwrap();
+        scenario.apply_flag("--storm-window-secs", "30").unwrap();
+        let storm = scenario.storm.as_ref().unwrap();
+        assert_eq!((storm.events, storm.window_secs), (500, 30));
+        assert_eq!(storm.predictions_per_event, 10);
+        scenario.validate().unwrap();
+        scenario.apply_flag("--soak-hours", "1").unwrap();
+        assert!(scenario.validate().is_err());
+
+        let from_toml =
+            StressScenario::from_toml_str("[storm]\nevents = 200\nresolve_concurrency = 4")
+                .unwrap();
+        let storm = from_toml.storm.unwrap();
+        assert_eq!((storm.events, storm.resolve_concurrency), (200, 4));
+    }
+
+    #[test]
+    fn sampled_skill_stays_in_unit_interval() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let wide = SkillDistribution::Normal {
+            mean: 0.5,
+            std_dev: 3.0,
+        };
+        for _ in 0..1_000 {
+            let skill = wide.sample(&mut rng);
+            assert!((0.0..=1.0).contains(&skill));
+        }
+    }
 }
@@ -0,0 +1,548 @@
+//! Soak mode: a steady trade rate for hours instead of a burst, sampling
+//! the things that leak slowly (process memory, cache size, DB pool waits,
+//! broadcast backlog) and failing when any of them keeps growing.
+//!
+//! Trades go through the same read-through cache and capacity-100 broadcast
+//! channel setup the server uses, so growth there shows up here first.
+
+use anyhow::{anyhow, Result};
+use moka::future::Cache;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, Semaphore};
+use tracing::{error, info};
+
+use super::{
+    create_test_events, create_test_users, stream_rng, ChaosStats, LatencySummary, RetryStats,
+    StressScenario, TradeOutcome, TradeTally, SETUP_STREAM,
+};
+use crate::config::Config;
+use crate::invariants;
+use crate::lmsr_api;
+
+// Same shape as the server's broadcast channel and response cache.
+pub(super) const BROADCAST_CAPACITY: usize = 100;
+const CACHE_MAX_CAPACITY: u64 = 1000;
+
+// Below these a metric counts as flat no matter its relative growth, so a
+// near-zero baseline (an idle pool, an empty backlog) can't trip the check.
+const RSS_FLOOR_BYTES: f64 = 16.0 * 1024.0 * 1024.0;
+const CACHE_FLOOR_ENTRIES: f64 = 50.0;
+const POOL_WAIT_FLOOR_MS: f64 = 5.0;
+const BACKLOG_FLOOR_MESSAGES: f64 = 10.0;
+const INVARIANT_SAMPLE_USERS: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoakConfig {
+    pub duration_secs: u64,
+    /// Target trades per second; ticks that find `max_in_flight` trades
+    /// still running are dropped and counted rather than queued.
+    pub trades_per_sec: f64,
+    pub max_in_flight: usize,
+    pub sample_interval_secs: u64,
+    /// Samples before this are excluded from trend checks (pool and cache
+    /// fill-up, allocator warm-up).
+    pub warmup_secs: u64,
+    /// A metric fails once its fitted growth exceeds this share of its mean
+    /// per hour.
+    pub max_growth_pct_per_hour: f64,
+}
+
+impl Default for SoakConfig {
+    fn default() -> Self {
+        Self {
+            duration_secs: 4 * 3600,
+            trades_per_sec: 20.0,
+            max_in_flight: 32,
+            sample_interval_secs: 30,
+            warmup_secs: 300,
+            max_growth_pct_per_hour: 10.0,
+        }
+    }
+}
+
+impl SoakConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.duration_secs == 0 || self.sample_interval_secs == 0 || self.max_in_flight == 0 {
+            return Err(anyhow!(
+                "soak duration_secs, sample_interval_secs and max_in_flight must be positive"
+            ));
+        }
+        if !self.trades_per_sec.is_finite() || self.trades_per_sec <= 0.0 {
+            return Err(anyhow!("soak trades_per_sec must be positive"));
+        }
+        if !self.max_growth_pct_per_hour.is_finite() || self.max_growth_pct_per_hour <= 0.0 {
+            return Err(anyhow!("soak max_growth_pct_per_hour must be positive"));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SoakSample {
+    pub elapsed_secs: f64,
+    /// Resident set size; None where /proc isn't available.
+    pub rss_bytes: Option<u64>,
+    pub cache_entries: u64,
+    pub pool_size: u32,
+    pub pool_idle: usize,
+    /// Time to check a connection out of the pool at sample time.
+    pub pool_acquire_ms: f64,
+    /// Messages queued in the broadcast channel for the slowest receiver.
+    pub broadcast_backlog: usize,
+    /// Cumulative messages the subscriber missed by falling behind.
+    pub broadcast_lagged: u64,
+    pub trades_executed: u64,
+    pub in_flight: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricTrend {
+    pub metric: String,
+    pub samples: usize,
+    pub mean: f64,
+    /// Least-squares slope over post-warm-up samples, in metric units/hour.
+    pub slope_per_hour: f64,
+    pub growth_pct_per_hour: f64,
+    pub bounded: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SoakReport {
+    pub scenario: StressScenario,
+    pub seed: u64,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub duration_secs: f64,
+    pub trades_executed: u64,
+    pub trades_skipped: u64,
+    pub trades_failed: u64,
+    /// Ticks dropped because `max_in_flight` trades were still running.
+    pub trades_dropped: u64,
+    pub achieved_tps: f64,
+    pub buy_latency: LatencySummary,
+    pub sell_latency: LatencySummary,
+    pub retries: RetryStats,
+    pub samples: Vec<SoakSample>,
+    pub trends: Vec<MetricTrend>,
+    pub failures: Vec<String>,
+}
+
+impl SoakReport {
+    pub fn ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Resident set size of this process from /proc/self/status (Linux only).
+fn process_rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+/// Fits a line through (elapsed_secs, value) and flags the metric when the
+/// fitted growth per hour exceeds `max_growth_pct_per_hour` of its mean,
+/// with `floor` standing in for the mean when the metric sits near zero.
+fn metric_trend(
+    metric: &str,
+    points: &[(f64, f64)],
+    floor: f64,
+    max_growth_pct_per_hour: f64,
+) -> MetricTrend {
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n.max(1.0);
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n.max(1.0);
+    let (mut cov, mut var_x) = (0.0, 0.0);
+    for (x, y) in points {
+        cov += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+    }
+    let slope_per_hour = if points.len() < 3 || var_x <= 0.0 {
+        0.0
+    } else {
+        cov / var_x * 3600.0
+    };
+    let growth_pct_per_hour = slope_per_hour / mean_y.max(floor) * 100.0;
+    MetricTrend {
+        metric: metric.to_string(),
+        samples: points.len(),
+        mean: mean_y,
+        slope_per_hour,
+        growth_pct_per_hour,
+        bounded: growth_pct_per_hour <= max_growth_pct_per_hour,
+    }
+}
+
+fn evaluate_trends(samples: &[SoakSample], soak: &SoakConfig) -> Vec<MetricTrend> {
+    let steady: Vec<&SoakSample> = samples
+        .iter()
+        .filter(|s| s.elapsed_secs >= soak.warmup_secs as f64)
+        .collect();
+    let series = |value: fn(&SoakSample) -> Option<f64>| -> Vec<(f64, f64)> {
+        steady
+            .iter()
+            .filter_map(|s| value(s).map(|v| (s.elapsed_secs, v)))
+            .collect()
+    };
+    let max = soak.max_growth_pct_per_hour;
+    vec![
+        metric_trend(
+            "rss_bytes",
+            &series(|s| s.rss_bytes.map(|b| b as f64)),
+            RSS_FLOOR_BYTES,
+            max,
+        ),
+        metric_trend(
+            "cache_entries",
+            &series(|s| Some(s.cache_entries as f64)),
+            CACHE_FLOOR_ENTRIES,
+            max,
+        ),
+        metric_trend(
+            "pool_acquire_ms",
+            &series(|s| Some(s.pool_acquire_ms)),
+            POOL_WAIT_FLOOR_MS,
+            max,
+        ),
+        metric_trend(
+            "broadcast_backlog",
+            &series(|s| Some(s.broadcast_backlog as f64)),
+            BACKLOG_FLOOR_MESSAGES,
+            max,
+        ),
+    ]
+}
+
+async fn take_sample(
+    pool: &PgPool,
+    cache: &Cache<String, String>,
+    tx: &broadcast::Sender<String>,
+    lagged: &Arc<std::sync::atomic::AtomicU64>,
+    tally: &Mutex<TradeTally>,
+    in_flight: usize,
+    elapsed: Duration,
+) -> Result<SoakSample> {
+    cache.run_pending_tasks().await;
+    let started = Instant::now();
+    let conn = pool.acquire().await?;
+    let pool_acquire_ms = started.elapsed().as_secs_f64() * 1000.0;
+    drop(conn);
+    Ok(SoakSample {
+        elapsed_secs: elapsed.as_secs_f64(),
+        rss_bytes: process_rss_bytes(),
+        cache_entries: cache.entry_count(),
+        pool_size: pool.size(),
+        pool_idle: pool.num_idle(),
+        pool_acquire_ms,
+        broadcast_backlog: tx.len(),
+        broadcast_lagged: lagged.load(std::sync::atomic::Ordering::Relaxed),
+        trades_executed: tally.lock().map(|t| t.executed).unwrap_or(0),
+        in_flight,
+    })
+}
+
+/// Runs the soak described by `scenario.soak` (defaults if unset) and
+/// returns its report. Unbounded metric growth and broken invariants are
+/// recorded as failures rather than returned as errors.
+pub async fn run_soak(
+    pool: &PgPool,
+    config: &Config,
+    scenario: &StressScenario,
+) -> Result<SoakReport> {
+    scenario.validate()?;
+    let soak = scenario.soak.clone().unwrap_or_default();
+    soak.validate()?;
+    let seed = scenario.seed.unwrap_or_else(rand::random);
+    let scenario = StressScenario {
+        seed: Some(seed),
+        soak: Some(soak.clone()),
+        ..scenario.clone()
+    };
+    info!("🎲 Soak seed: {} (replay with --seed {})", seed, seed);
+    let started_at = chrono::Utc::now();
+
+    let users =
+        Arc::new(create_test_users(pool, &scenario, &mut stream_rng(seed, SETUP_STREAM)).await?);
+    let events = Arc::new(create_test_events(pool, &scenario).await?);
+    let stress = Arc::new(scenario.clone());
+    let pool = Arc::new(pool.clone());
+    let config = Arc::new(config.clone());
+
+    let (tx, mut rx) = broadcast::channel::<String>(BROADCAST_CAPACITY);
+    let lagged = Arc::new(std::sync::atomic::AtomicU64::new(0));
+    let subscriber = {
+        let lagged = Arc::clone(&lagged);
+        tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(_) => {}
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        lagged.fetch_add(missed, std::sync::atomic::Ordering::Relaxed);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    };
+    let cache: Cache<String, String> = Cache::builder()
+        .max_capacity(CACHE_MAX_CAPACITY)
+        .time_to_live(Duration::from_secs(300))
+        .time_to_idle(Duration::from_secs(60))
+        .build();
+
+    let tally = Arc::new(Mutex::new(TradeTally::new()));
+    let permits = Arc::new(Semaphore::new(soak.max_in_flight));
+    let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / soak.trades_per_sec));
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let sample_every = Duration::from_secs(soak.sample_interval_secs);
+
+    info!(
+        "\n🛁 Starting soak ({}): {:.1} trades/s for {}s, sampling every {}s",
+        scenario.name, soak.trades_per_sec, soak.duration_secs, soak.sample_interval_secs
+    );
+
+    let start_time = Instant::now();
+    let deadline = start_time + Duration::from_secs(soak.duration_secs);
+    let mut next_sample = start_time;
+    let mut samples = Vec::new();
+    let mut trades_dropped = 0u64;
+    let mut trade_index = 0u64;
+
+    while Instant::now() < deadline {
+        ticker.tick().await;
+
+        if Instant::now() >= next_sample {
+            let in_flight = soak.max_in_flight - permits.available_permits();
+            let sample = take_sample(
+                &pool,
+                &cache,
+                &tx,
+                &lagged,
+                &tally,
+                in_flight,
+                start_time.elapsed(),
+            )
+            .await?;
+            info!(
+                "   t={:.0}s | rss {} | cache {} | pool acquire {:.2}ms ({}/{} idle) | backlog {} | lagged {} | {} trades",
+                sample.elapsed_secs,
+                sample
+                    .rss_bytes
+                    .map(|b| format!("{:.1}MB", b as f64 / (1024.0 * 1024.0)))
+                    .unwrap_or_else(|| "n/a".to_string()),
+                sample.cache_entries,
+                sample.pool_acquire_ms,
+                sample.pool_idle,
+                sample.pool_size,
+                sample.broadcast_backlog,
+                sample.broadcast_lagged,
+                sample.trades_executed
+            );
+            samples.push(sample);
+            next_sample += sample_every;
+        }
+
+        let Ok(permit) = Arc::clone(&permits).try_acquire_owned() else {
+            trades_dropped += 1;
+            continue;
+        };
+        let mut rng = stream_rng(seed, trade_index);
+        trade_index += 1;
+        let user = users[rng.gen_range(0..users.len())].clone();
+        let event = events[rng.gen_range(0..events.len())].clone();
+        let (pool, config, stress) = (Arc::clone(&pool), Arc::clone(&config), Arc::clone(&stress));
+        let (cache, tx, tally) = (cache.clone(), tx.clone(), Arc::clone(&tally));
+
+        tokio::spawn(async move {
+            // Read-through market state, as a dashboard poll would.
+            let _ = cache
+                .try_get_with(format!("market_state:{}", event.id), async {
+                    lmsr_api::get_market_state(&pool, event.id)
+                        .await
+                        .map(|state| state.to_string())
+                        .map_err(|e| e.to_string())
+                })
+                .await;
+
+            let mut chaos = ChaosStats::default();
+            let outcome = super::try_execute_trade(
+                &pool, &config, &stress, &user, &event, &mut rng, &mut chaos,
+            )
+            .await;
+            if let Ok(TradeOutcome::Executed { .. }) = outcome {
+                cache.invalidate_all();
+                let _ = tx.send(
+                    serde_json::json!({
+                        "type": "market_update",
+                        "data": {"event_id": event.id, "user_id": user.id},
+                        "timestamp": chrono::Utc::now(),
+                    })
+                    .to_string(),
+                );
+            }
+
+            if let Ok(mut tally) = tally.lock() {
+                match outcome {
+                    Ok(TradeOutcome::Executed {
+                        kind,
+                        latency,
+                        retries,
+                    }) => tally.record_executed(kind, latency, retries),
+                    Ok(TradeOutcome::Skipped) => tally.skipped += 1,
+                    Err(_) => tally.failed += 1,
+                }
+                tally.chaos.merge(&chaos);
+            }
+            // Drop the tally handle before the permit, so the drain below
+            // finds it unshared.
+            drop(tally);
+            drop(permit);
+        });
+    }
+
+    // Let in-flight trades finish before the final sample and checks.
+    let _drain = permits
+        .acquire_many(soak.max_in_flight as u32)
+        .await
+        .map_err(|e| anyhow!("soak permits closed: {}", e))?;
+    samples.push(take_sample(&pool, &cache, &tx, &lagged, &tally, 0, start_time.elapsed()).await?);
+    drop(tx);
+    let _ = subscriber.await;
+    let duration = start_time.elapsed();
+
+    let tally = Arc::try_unwrap(tally)
+        .ok()
+        .and_then(|tally| tally.into_inner().ok())
+        .ok_or_else(|| anyhow!("soak trade tally still shared after drain"))?;
+
+    let mut failures = Vec::new();
+    let trends = evaluate_trends(&samples, &soak);
+    for trend in &trends {
+        if !trend.bounded {
+            failures.push(format!(
+                "{} grows {:.1}%/hour (limit {:.1}%), slope {:.3}/hour over {} samples",
+                trend.metric,
+                trend.growth_pct_per_hour,
+                soak.max_growth_pct_per_hour,
+                trend.slope_per_hour,
+                trend.samples
+            ));
+        }
+    }
+    if trends.iter().all(|trend| trend.samples < 3) {
+        failures.push(
+            "not enough post-warm-up samples to judge trends; lengthen duration_secs or shorten sample_interval_secs"
+                .to_string(),
+        );
+    }
+
+    let mut sample_rng = stream_rng(seed, super::OUTCOME_STREAM);
+    for user in users.choose_multiple(&mut sample_rng, INVARIANT_SAMPLE_USERS) {
+        for violation in invariants::check_user_ledger(&pool, user.id).await? {
+            failures.push(violation.to_string());
+        }
+    }
+
+    info!("\n🏁 Soak finished in {:.2?}", duration);
+    for trend in &trends {
+        info!(
+            "   - {}: mean {:.2}, {:+.2}%/hour ({})",
+            trend.metric,
+            trend.mean,
+            trend.growth_pct_per_hour,
+            if trend.bounded { "bounded" } else { "GROWING" }
+        );
+    }
+    if failures.is_empty() {
+        info!("✅ No unbounded growth detected.");
+    } else {
+        for failure in &failures {
+            error!("❌ {}", failure);
+        }
+    }
+
+    let total = tally.executed + tally.skipped + tally.failed;
+    let mut retries = tally.retries.clone();
+    retries.retries_per_trade = if tally.executed == 0 {
+        0.0
+    } else {
+        retries.total_retries as f64 / tally.executed as f64
+    };
+    Ok(SoakReport {
+        scenario,
+        seed,
+        started_at,
+        duration_secs: duration.as_secs_f64(),
+        trades_executed: tally.executed,
+        trades_skipped: tally.skipped,
+        trades_failed: tally.failed,
+        trades_dropped,
+        achieved_tps: total as f64 / duration.as_secs_f64(),
+        buy_latency: LatencySummary::from_histogram(&tally.buy_latency),
+        sell_latency: LatencySummary::from_histogram(&tally.sell_latency),
+        retries,
+        samples,
+        trends,
+        failures,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(elapsed_secs: f64, rss_mb: f64, cache_entries: u64) -> SoakSample {
+        SoakSample {
+            elapsed_secs,
+            rss_bytes: Some((rss_mb * 1024.0 * 1024.0) as u64),
+            cache_entries,
+            pool_size: 10,
+            pool_idle: 8,
+            pool_acquire_ms: 0.4,
+            broadcast_backlog: 0,
+            broadcast_lagged: 0,
+            trades_executed: 0,
+            in_flight: 0,
+        }
+    }
+
+    #[test]
+    fn flat_noisy_metrics_stay_bounded() {
+        let soak = SoakConfig {
+            warmup_secs: 60,
+            ..SoakConfig::default()
+        };
+        // Warm-up growth is ignored; afterwards RSS wobbles around 200MB.
+        let mut samples = vec![sample(0.0, 50.0, 0), sample(30.0, 150.0, 400)];
+        for i in 2..120 {
+            let wobble = if i % 2 == 0 { 2.0 } else { -2.0 };
+            samples.push(sample(i as f64 * 30.0, 200.0 + wobble, 900));
+        }
+        let trends = evaluate_trends(&samples, &soak);
+        assert!(trends.iter().all(|t| t.bounded), "{:?}", trends);
+        assert!(trends.iter().all(|t| t.samples == 118));
+    }
+
+    #[test]
+    fn steady_memory_growth_is_flagged() {
+        let soak = SoakConfig {
+            warmup_secs: 0,
+            ..SoakConfig::default()
+        };
+        // 100MB growing 1MB per minute: 60%/hour.
+        let samples: Vec<SoakSample> = (0..60)
+            .map(|i| sample(i as f64 * 60.0, 100.0 + i as f64, 500))
+            .collect();
+        let trends = evaluate_trends(&samples, &soak);
+        let rss = trends.iter().find(|t| t.metric == "rss_bytes").unwrap();
+        assert!(!rss.bounded);
+        assert!((rss.slope_per_hour - 60.0 * 1024.0 * 1024.0).abs() < 1.0);
+        let cache = trends.iter().find(|t| t.metric == "cache_entries").unwrap();
+        assert!(cache.bounded);
+    }
+}
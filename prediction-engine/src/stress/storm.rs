@@ -0,0 +1,628 @@
+//! Resolution storm: hundreds of events with thousands of predictions all
+//! resolved inside about a minute, the way a Metaculus resolution sync lands.
+//!
+//! Each resolution is announced on a capacity-100 broadcast channel like the
+//! server's, and a ranking task consumes those announcements, scores every
+//! prediction on the event and re-ranks users, the work the backend does per
+//! marketResolved. Meanwhile a background trader keeps trading on markets
+//! the storm doesn't touch. The run fails if the ranking task falls behind
+//! or misses messages, the channel overflows, or background trading slows
+//! past its latency budget.
+
+use anyhow::{anyhow, Result};
+use hdrhistogram::Histogram;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, Semaphore};
+use tracing::{error, info};
+
+use super::soak::BROADCAST_CAPACITY;
+use super::{
+    create_test_events, create_test_users, latency_histogram, record_latency, stream_rng,
+    ChaosStats, LatencySummary, StressScenario, TestEvent, TestUser, TradeOutcome, TradeTally,
+    OUTCOME_STREAM, SETUP_STREAM,
+};
+use crate::config::Config;
+use crate::invariants;
+use crate::lmsr_api;
+
+// Trade RNG streams for the background trader start here, clear of the
+// per-prediction streams used while seeding.
+const BACKGROUND_STREAM_BASE: u64 = 1 << 40;
+const BACKGROUND_MAX_IN_FLIGHT: usize = 32;
+const INVARIANT_SAMPLE_SIZE: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StormConfig {
+    /// Events resolved during the storm.
+    pub events: usize,
+    /// Buys placed on each storm event before it starts.
+    pub predictions_per_event: usize,
+    /// Resolutions are spread evenly over this window.
+    pub window_secs: u64,
+    pub resolve_concurrency: usize,
+    /// Separate markets that keep trading through the storm.
+    pub background_events: usize,
+    pub background_trades_per_sec: f64,
+    /// Background trading before the storm, for a latency baseline.
+    pub baseline_secs: u64,
+    /// Budget for background trade p99 while the storm runs.
+    pub max_background_p99_ms: f64,
+    /// Budget from a resolution's broadcast to its event being scored.
+    pub max_scoring_lag_ms: f64,
+}
+
+impl Default for StormConfig {
+    fn default() -> Self {
+        Self {
+            events: 300,
+            predictions_per_event: 10,
+            window_secs: 60,
+            resolve_concurrency: 16,
+            background_events: 20,
+            background_trades_per_sec: 20.0,
+            baseline_secs: 10,
+            max_background_p99_ms: 2_000.0,
+            max_scoring_lag_ms: 5_000.0,
+        }
+    }
+}
+
+impl StormConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.events == 0
+            || self.predictions_per_event == 0
+            || self.window_secs == 0
+            || self.resolve_concurrency == 0
+            || self.background_events == 0
+        {
+            return Err(anyhow!(
+                "storm events, predictions_per_event, window_secs, resolve_concurrency and background_events must be positive"
+            ));
+        }
+        for (name, value) in [
+            ("background_trades_per_sec", self.background_trades_per_sec),
+            ("max_background_p99_ms", self.max_background_p99_ms),
+            ("max_scoring_lag_ms", self.max_scoring_lag_ms),
+        ] {
+            if !value.is_finite() || value <= 0.0 {
+                return Err(anyhow!("storm {} must be positive", name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Offset from the storm start at which the i-th resolution is due.
+    fn slot(&self, index: usize) -> Duration {
+        Duration::from_secs(self.window_secs).mul_f64(index as f64 / self.events as f64)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StormReport {
+    pub scenario: StressScenario,
+    pub seed: u64,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub predictions_seeded: u64,
+    pub events_resolved: u64,
+    pub resolutions_failed: u64,
+    /// From the first scheduled resolution to the last one committing.
+    pub storm_secs: f64,
+    pub resolution_latency: LatencySummary,
+    pub events_scored: u64,
+    pub predictions_scored: u64,
+    pub users_ranked: usize,
+    pub scoring_lag: LatencySummary,
+    /// Most messages ever queued for the ranking task.
+    pub broadcast_peak_backlog: usize,
+    /// Messages the ranking task lost by falling behind the channel.
+    pub broadcast_lagged: u64,
+    pub baseline_trade_latency: LatencySummary,
+    pub storm_trade_latency: LatencySummary,
+    pub background_trades_executed: u64,
+    pub background_trades_failed: u64,
+    pub failures: Vec<String>,
+}
+
+impl StormReport {
+    pub fn ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Running Brier totals per user, re-ranked after every scored event.
+#[derive(Debug, Default)]
+struct Leaderboard {
+    totals: HashMap<i32, (f64, u64)>,
+    ranking: Vec<(i32, f64)>,
+    predictions_scored: u64,
+}
+
+impl Leaderboard {
+    fn score_event(&mut self, predictions: &[(i32, f64)], outcome: bool) {
+        let target = if outcome { 1.0 } else { 0.0 };
+        for (user_id, prob) in predictions {
+            let entry = self.totals.entry(*user_id).or_default();
+            entry.0 += (prob - target).powi(2);
+            entry.1 += 1;
+        }
+        self.predictions_scored += predictions.len() as u64;
+        self.ranking = self
+            .totals
+            .iter()
+            .map(|(user_id, (sum, count))| (*user_id, sum / *count as f64))
+            .collect();
+        self.ranking
+            .sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Resolved {
+    event_id: i32,
+    outcome: bool,
+    resolved_at: chrono::DateTime<chrono::Utc>,
+}
+
+struct RankingResult {
+    leaderboard: Leaderboard,
+    events_scored: u64,
+    scoring_lag: Histogram<u64>,
+    lagged: u64,
+}
+
+/// Consumes marketResolved broadcasts until the channel closes, scoring
+/// each event's predictions as it arrives.
+async fn run_ranking(
+    pool: Arc<PgPool>,
+    mut rx: broadcast::Receiver<String>,
+) -> Result<RankingResult> {
+    let mut result = RankingResult {
+        leaderboard: Leaderboard::default(),
+        events_scored: 0,
+        scoring_lag: latency_histogram(),
+        lagged: 0,
+    };
+    loop {
+        let message = match rx.recv().await {
+            Ok(message) => message,
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                result.lagged += missed;
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        let envelope: serde_json::Value = serde_json::from_str(&message)?;
+        let resolved: Resolved = serde_json::from_value(envelope["data"].clone())?;
+
+        let predictions: Vec<(i32, f64)> =
+            sqlx::query("SELECT user_id, new_prob FROM market_updates WHERE event_id = $1")
+                .bind(resolved.event_id)
+                .fetch_all(pool.as_ref())
+                .await?
+                .into_iter()
+                .map(|row| (row.get("user_id"), row.get("new_prob")))
+                .collect();
+        result
+            .leaderboard
+            .score_event(&predictions, resolved.outcome);
+        result.events_scored += 1;
+        let lag = (chrono::Utc::now() - resolved.resolved_at)
+            .to_std()
+            .unwrap_or_default();
+        record_latency(&mut result.scoring_lag, lag);
+    }
+    Ok(result)
+}
+
+/// Trades on the background markets, which the storm never resolves.
+#[derive(Clone)]
+struct BackgroundTrader {
+    pool: Arc<PgPool>,
+    config: Arc<Config>,
+    stress: Arc<StressScenario>,
+    users: Arc<Vec<TestUser>>,
+    events: Arc<Vec<TestEvent>>,
+    rate: f64,
+}
+
+impl BackgroundTrader {
+    /// Trades at `rate` until `stop` is set, then waits out in-flight trades.
+    async fn run(self, stream_base: u64, stop: Arc<AtomicBool>) -> Result<TradeTally> {
+        let Self {
+            pool,
+            config,
+            stress,
+            users,
+            events,
+            rate,
+        } = self;
+        let seed = stress.seed.unwrap_or_default();
+        let tally = Arc::new(Mutex::new(TradeTally::new()));
+        let permits = Arc::new(Semaphore::new(BACKGROUND_MAX_IN_FLIGHT));
+        let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / rate));
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut trade_index = 0u64;
+
+        while !stop.load(Ordering::Relaxed) {
+            ticker.tick().await;
+            let Ok(permit) = Arc::clone(&permits).try_acquire_owned() else {
+                continue;
+            };
+            let mut rng = stream_rng(seed, stream_base + trade_index);
+            trade_index += 1;
+            let user = users[rng.gen_range(0..users.len())].clone();
+            let event = events[rng.gen_range(0..events.len())].clone();
+            let (pool, config, stress) =
+                (Arc::clone(&pool), Arc::clone(&config), Arc::clone(&stress));
+            let tally = Arc::clone(&tally);
+
+            tokio::spawn(async move {
+                let mut chaos = ChaosStats::default();
+                let outcome = super::try_execute_trade(
+                    &pool, &config, &stress, &user, &event, &mut rng, &mut chaos,
+                )
+                .await;
+                if let Ok(mut tally) = tally.lock() {
+                    match outcome {
+                        Ok(TradeOutcome::Executed {
+                            kind,
+                            latency,
+                            retries,
+                        }) => tally.record_executed(kind, latency, retries),
+                        Ok(TradeOutcome::Skipped) => tally.skipped += 1,
+                        Err(_) => tally.failed += 1,
+                    }
+                }
+                // Drop the tally handle before the permit, so the drain below
+                // finds it unshared.
+                drop(tally);
+                drop(permit);
+            });
+        }
+
+        let _drain = permits
+            .acquire_many(BACKGROUND_MAX_IN_FLIGHT as u32)
+            .await
+            .map_err(|e| anyhow!("background permits closed: {}", e))?;
+        Arc::try_unwrap(tally)
+            .ok()
+            .and_then(|tally| tally.into_inner().ok())
+            .ok_or_else(|| anyhow!("background trade tally still shared after drain"))
+    }
+}
+
+fn trade_latency(tally: &TradeTally) -> LatencySummary {
+    let mut combined = tally.buy_latency.clone();
+    let _ = combined.add(&tally.sell_latency);
+    LatencySummary::from_histogram(&combined)
+}
+
+/// Seeds the storm events with predictions, measures a background-trading
+/// baseline, then resolves every storm event inside the window while the
+/// ranking task and background trader run. Budget overruns, broadcast
+/// overflow and broken invariants are recorded as failures rather than
+/// returned as errors.
+pub async fn run_storm(
+    pool: &PgPool,
+    config: &Config,
+    scenario: &StressScenario,
+) -> Result<StormReport> {
+    scenario.validate()?;
+    let storm = scenario.storm.clone().unwrap_or_default();
+    storm.validate()?;
+    let seed = scenario.seed.unwrap_or_else(rand::random);
+    let scenario = StressScenario {
+        seed: Some(seed),
+        num_events: storm.events + storm.background_events,
+        storm: Some(storm.clone()),
+        ..scenario.clone()
+    };
+    info!("🎲 Storm seed: {} (replay with --seed {})", seed, seed);
+    let started_at = chrono::Utc::now();
+
+    let users =
+        Arc::new(create_test_users(pool, &scenario, &mut stream_rng(seed, SETUP_STREAM)).await?);
+    let mut all_events = create_test_events(pool, &scenario).await?;
+    let background_events = Arc::new(all_events.split_off(storm.events));
+    let storm_events = all_events;
+    let stress = Arc::new(scenario.clone());
+    let pool = Arc::new(pool.clone());
+    let config = Arc::new(config.clone());
+
+    // 1. Seed predictions: buys only, so every one is still open to score.
+    let seed_stress = Arc::new(StressScenario {
+        sell_probability: 0.0,
+        ..scenario.clone()
+    });
+    let predictions: Vec<(usize, usize)> = (0..storm_events.len())
+        .flat_map(|event_idx| (0..storm.predictions_per_event).map(move |j| (event_idx, j)))
+        .collect();
+    info!(
+        "Seeding {} predictions across {} storm events...",
+        predictions.len(),
+        storm_events.len()
+    );
+    let mut predictions_seeded = 0u64;
+    for (chunk_idx, chunk) in predictions.chunks(scenario.batch_size).enumerate() {
+        let mut handles = Vec::new();
+        for (offset, (event_idx, j)) in chunk.iter().enumerate() {
+            let index = chunk_idx * scenario.batch_size + offset;
+            let user = users[(event_idx * storm.predictions_per_event + j) % users.len()].clone();
+            let event = storm_events[*event_idx].clone();
+            let (pool, config, stress) = (
+                Arc::clone(&pool),
+                Arc::clone(&config),
+                Arc::clone(&seed_stress),
+            );
+            handles.push(tokio::spawn(async move {
+                let mut rng = stream_rng(seed, index as u64);
+                let mut chaos = ChaosStats::default();
+                super::try_execute_trade(
+                    &pool, &config, &stress, &user, &event, &mut rng, &mut chaos,
+                )
+                .await
+            }));
+        }
+        for handle in handles {
+            if let Ok(Ok(TradeOutcome::Executed { .. })) = handle.await {
+                predictions_seeded += 1;
+            }
+        }
+    }
+    info!("✅ Seeded {} predictions", predictions_seeded);
+
+    // 2. Background baseline with nothing else running.
+    let trader = BackgroundTrader {
+        pool: Arc::clone(&pool),
+        config: Arc::clone(&config),
+        stress: Arc::clone(&stress),
+        users: Arc::clone(&users),
+        events: Arc::clone(&background_events),
+        rate: storm.background_trades_per_sec,
+    };
+    let stop = Arc::new(AtomicBool::new(false));
+    let baseline = tokio::spawn(
+        trader
+            .clone()
+            .run(BACKGROUND_STREAM_BASE, Arc::clone(&stop)),
+    );
+    tokio::time::sleep(Duration::from_secs(storm.baseline_secs)).await;
+    stop.store(true, Ordering::Relaxed);
+    let baseline = baseline
+        .await
+        .map_err(|e| anyhow!("baseline trader panicked: {}", e))??;
+
+    // 3. The storm itself.
+    let (tx, rx) = broadcast::channel::<String>(BROADCAST_CAPACITY);
+    let ranking = tokio::spawn(run_ranking(Arc::clone(&pool), rx));
+    let stop = Arc::new(AtomicBool::new(false));
+    let background = tokio::spawn(
+        trader
+            .clone()
+            .run(BACKGROUND_STREAM_BASE * 2, Arc::clone(&stop)),
+    );
+
+    info!(
+        "\n🌩️  Resolving {} events over {}s ({} at a time)...",
+        storm_events.len(),
+        storm.window_secs,
+        storm.resolve_concurrency
+    );
+    let mut outcome_rng = stream_rng(seed, OUTCOME_STREAM);
+    let outcomes: Vec<bool> = storm_events
+        .iter()
+        .map(|event| outcome_rng.gen_bool(event.true_prob))
+        .collect();
+    let permits = Arc::new(Semaphore::new(storm.resolve_concurrency));
+    let peak_backlog = Arc::new(AtomicUsize::new(0));
+    let storm_start = tokio::time::Instant::now();
+    let mut handles = Vec::new();
+    for (index, (event, outcome)) in storm_events.iter().zip(&outcomes).enumerate() {
+        let due = storm_start + storm.slot(index);
+        let (pool, permits, tx) = (Arc::clone(&pool), Arc::clone(&permits), tx.clone());
+        let peak_backlog = Arc::clone(&peak_backlog);
+        let (event_id, outcome) = (event.id, *outcome);
+        handles.push(tokio::spawn(async move {
+            tokio::time::sleep_until(due).await;
+            let _permit = permits
+                .acquire()
+                .await
+                .map_err(|e| anyhow!("resolution permits closed: {}", e))?;
+            let started = Instant::now();
+            lmsr_api::resolve_event(&pool, event_id, outcome).await?;
+            let latency = started.elapsed();
+            let resolved = Resolved {
+                event_id,
+                outcome,
+                resolved_at: chrono::Utc::now(),
+            };
+            let _ = tx.send(
+                serde_json::json!({
+                    "type": "marketResolved",
+                    "data": resolved,
+                    "timestamp": resolved.resolved_at,
+                })
+                .to_string(),
+            );
+            peak_backlog.fetch_max(tx.len(), Ordering::Relaxed);
+            Ok::<Duration, anyhow::Error>(latency)
+        }));
+    }
+
+    let mut resolution_latency = latency_histogram();
+    let (mut events_resolved, mut resolutions_failed) = (0u64, 0u64);
+    for handle in handles {
+        match handle.await {
+            Ok(Ok(latency)) => {
+                record_latency(&mut resolution_latency, latency);
+                events_resolved += 1;
+            }
+            Ok(Err(e)) => {
+                error!("Resolution failed: {}", e);
+                resolutions_failed += 1;
+            }
+            Err(e) => {
+                error!("Resolution task failed: {}", e);
+                resolutions_failed += 1;
+            }
+        }
+    }
+    let storm_duration = storm_start.elapsed();
+    drop(tx);
+    let ranking = ranking
+        .await
+        .map_err(|e| anyhow!("ranking task panicked: {}", e))??;
+    stop.store(true, Ordering::Relaxed);
+    let background = background
+        .await
+        .map_err(|e| anyhow!("background trader panicked: {}", e))??;
+
+    // 4. Verdict.
+    let scoring_lag = LatencySummary::from_histogram(&ranking.scoring_lag);
+    let baseline_trade_latency = trade_latency(&baseline);
+    let storm_trade_latency = trade_latency(&background);
+    let broadcast_peak_backlog = peak_backlog.load(Ordering::Relaxed);
+    let mut failures = Vec::new();
+    if resolutions_failed > 0 {
+        failures.push(format!("{} resolutions failed", resolutions_failed));
+    }
+    if storm_duration.as_secs_f64() > 2.0 * storm.window_secs as f64 {
+        failures.push(format!(
+            "resolutions took {:.1}s, over twice the {}s window",
+            storm_duration.as_secs_f64(),
+            storm.window_secs
+        ));
+    }
+    if ranking.lagged > 0 || broadcast_peak_backlog >= BROADCAST_CAPACITY {
+        failures.push(format!(
+            "broadcast channel overflowed: peak backlog {} of {}, {} messages lost",
+            broadcast_peak_backlog, BROADCAST_CAPACITY, ranking.lagged
+        ));
+    }
+    if ranking.events_scored < events_resolved {
+        failures.push(format!(
+            "ranking scored {} of {} resolved events",
+            ranking.events_scored, events_resolved
+        ));
+    }
+    if scoring_lag.max_ms > storm.max_scoring_lag_ms {
+        failures.push(format!(
+            "scoring lagged {:.0}ms behind a resolution (budget {:.0}ms)",
+            scoring_lag.max_ms, storm.max_scoring_lag_ms
+        ));
+    }
+    if storm_trade_latency.p99_ms > storm.max_background_p99_ms {
+        failures.push(format!(
+            "background trade p99 {:.0}ms during the storm (baseline {:.0}ms, budget {:.0}ms)",
+            storm_trade_latency.p99_ms, baseline_trade_latency.p99_ms, storm.max_background_p99_ms
+        ));
+    }
+    if background.failed > 0 {
+        failures.push(format!(
+            "{} background trades failed during the storm",
+            background.failed
+        ));
+    }
+
+    for event in storm_events.choose_multiple(&mut outcome_rng, INVARIANT_SAMPLE_SIZE) {
+        for violation in invariants::check_event_resolution(&pool, event.id).await? {
+            failures.push(violation.to_string());
+        }
+    }
+    for user in users.choose_multiple(&mut outcome_rng, INVARIANT_SAMPLE_SIZE) {
+        for violation in invariants::check_user_ledger(&pool, user.id).await? {
+            failures.push(violation.to_string());
+        }
+    }
+
+    info!(
+        "\n🏁 Storm finished: {} events resolved in {:.2?}",
+        events_resolved, storm_duration
+    );
+    info!(
+        "   - Scoring: {} events, {} predictions, {} users ranked, lag p99 {:.0}ms max {:.0}ms",
+        ranking.events_scored,
+        ranking.leaderboard.predictions_scored,
+        ranking.leaderboard.ranking.len(),
+        scoring_lag.p99_ms,
+        scoring_lag.max_ms
+    );
+    info!(
+        "   - Broadcast: peak backlog {}/{}, {} lagged",
+        broadcast_peak_backlog, BROADCAST_CAPACITY, ranking.lagged
+    );
+    info!(
+        "   - Background trades: p99 {:.2}ms during storm vs {:.2}ms baseline ({} executed)",
+        storm_trade_latency.p99_ms, baseline_trade_latency.p99_ms, background.executed
+    );
+    if failures.is_empty() {
+        info!("✅ Storm absorbed.");
+    } else {
+        for failure in &failures {
+            error!("❌ {}", failure);
+        }
+    }
+
+    Ok(StormReport {
+        scenario,
+        seed,
+        started_at,
+        predictions_seeded,
+        events_resolved,
+        resolutions_failed,
+        storm_secs: storm_duration.as_secs_f64(),
+        resolution_latency: LatencySummary::from_histogram(&resolution_latency),
+        events_scored: ranking.events_scored,
+        predictions_scored: ranking.leaderboard.predictions_scored,
+        users_ranked: ranking.leaderboard.ranking.len(),
+        scoring_lag,
+        broadcast_peak_backlog,
+        broadcast_lagged: ranking.lagged,
+        baseline_trade_latency,
+        storm_trade_latency,
+        background_trades_executed: background.executed,
+        background_trades_failed: background.failed,
+        failures,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolutions_spread_evenly_over_window() {
+        let storm = StormConfig {
+            events: 4,
+            window_secs: 60,
+            ..StormConfig::default()
+        };
+        let slots: Vec<u64> = (0..4).map(|i| storm.slot(i).as_secs()).collect();
+        assert_eq!(slots, vec![0, 15, 30, 45]);
+        assert!(StormConfig {
+            resolve_concurrency: 0,
+            ..StormConfig::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn leaderboard_ranks_by_mean_brier() {
+        let mut board = Leaderboard::default();
+        board.score_event(&[(1, 0.9), (2, 0.4), (3, 0.1)], true);
+        board.score_event(&[(2, 0.2), (3, 0.5)], false);
+        assert_eq!(board.predictions_scored, 5);
+        let order: Vec<i32> = board.ranking.iter().map(|(user_id, _)| *user_id).collect();
+        // 1: 0.01; 2: (0.36 + 0.04) / 2 = 0.20; 3: (0.81 + 0.25) / 2 = 0.53
+        assert_eq!(order, vec![1, 2, 3]);
+        assert!((board.ranking[1].1 - 0.20).abs() < 1e-12);
+    }
+}
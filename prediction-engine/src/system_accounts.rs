@@ -0,0 +1,247 @@
+//! System accounts: the house, the fee pool and the faucet.
+//!
+//! Every RP movement between a user and the system is booked in
+//! `system_ledger` against the account on the other side: market gains and
+//! losses against the house; grants, rewards, bonds and decay, including the
+//! backend's (`backend/src/services/systemLedgerService.js`), against the
+//! faucet. From the house's opening row on, users' RP plus every account
+//! totals 0, which `audit` checks. Competition wallets stay out of it.
+
+use anyhow::Result;
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool, Row};
+
+use crate::db_adapter::Wallet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SystemAccount {
+    House,
+    FeePool,
+    Faucet,
+}
+
+impl SystemAccount {
+    pub const ALL: [SystemAccount; 3] = [
+        SystemAccount::House,
+        SystemAccount::FeePool,
+        SystemAccount::Faucet,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SystemAccount::House => "house",
+            SystemAccount::FeePool => "fee_pool",
+            SystemAccount::Faucet => "faucet",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountBalance {
+    pub account: SystemAccount,
+    pub balance_ledger: i64,
+    pub entries: i64,
+}
+
+/// Users' RP: balances and stakes, plus what their open limit orders on
+/// main-wallet markets hold in reserve.
+const USERS_LEDGER: &str = "(SELECT COALESCE(SUM(COALESCE(rp_balance_ledger, 0)
+                                                + COALESCE(rp_staked_ledger, 0)), 0)
+                             FROM users)
+                            + (SELECT COALESCE(SUM(o.reserved_ledger), 0)
+                               FROM limit_orders o
+                               JOIN events e ON e.id = o.event_id
+                               WHERE e.competition_id IS NULL)";
+
+/// Users' RP against the system accounts, read in one snapshot.
+#[derive(Debug, Clone, Serialize)]
+pub struct SolvencyAudit {
+    /// Σ rp_balance_ledger + rp_staked_ledger over all users, plus reserves
+    pub users_ledger: i64,
+    pub accounts: Vec<AccountBalance>,
+    /// users_ledger plus every account; 0 when every movement was booked
+    pub total_ledger: i64,
+    pub balanced: bool,
+}
+
+/// The ledger table alone, without the trigger and opening row.
+pub async fn ensure_system_ledger_table(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS system_ledger (
+            id BIGSERIAL PRIMARY KEY,
+            account VARCHAR(16) NOT NULL CHECK (account IN ('house', 'fee_pool', 'faucet')),
+            kind VARCHAR(20) NOT NULL,
+            user_id INTEGER,
+            event_id INTEGER,
+            delta_ledger BIGINT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// The ledger with its append-only trigger, and the house's opening row on
+/// first run. Run once at startup.
+pub async fn ensure_system_accounts(pool: &PgPool) -> Result<()> {
+    ensure_system_ledger_table(pool).await?;
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_system_ledger_opening
+         ON system_ledger (kind) WHERE kind = 'opening'",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        r#"
+        CREATE OR REPLACE FUNCTION system_ledger_append_only() RETURNS trigger AS $$
+        BEGIN
+            RAISE EXCEPTION 'system_ledger is append-only';
+        END;
+        $$ LANGUAGE plpgsql;
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE OR REPLACE TRIGGER system_ledger_append_only
+         BEFORE UPDATE OR DELETE ON system_ledger
+         FOR EACH ROW EXECUTE FUNCTION system_ledger_append_only()",
+    )
+    .execute(pool)
+    .await?;
+    // Anything booked before the opening row is counted in it, so the
+    // total starts at 0 however the first run races with trades
+    sqlx::query(&format!(
+        r#"
+        INSERT INTO system_ledger (account, kind, delta_ledger)
+        SELECT 'house', 'opening',
+               -({}
+                 + (SELECT COALESCE(SUM(delta_ledger), 0) FROM system_ledger))::BIGINT
+        WHERE NOT EXISTS (SELECT 1 FROM system_ledger WHERE kind = 'opening')
+        ON CONFLICT DO NOTHING
+        "#,
+        USERS_LEDGER
+    ))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Books the other side of `user_deltas[i]`, a change to the RP of
+/// `user_ids[i]`, against `account`. Zero deltas book nothing.
+pub(crate) async fn book_batch(
+    conn: &mut PgConnection,
+    account: SystemAccount,
+    kind: &str,
+    event_id: Option<i32>,
+    user_ids: &[i32],
+    user_deltas: &[i64],
+) -> Result<()> {
+    if user_deltas.iter().all(|delta| *delta == 0) {
+        return Ok(());
+    }
+    sqlx::query(
+        r#"
+        INSERT INTO system_ledger (account, kind, user_id, event_id, delta_ledger)
+        SELECT $1, $2, t.user_id, $3, -t.delta
+        FROM UNNEST($4::integer[], $5::bigint[]) AS t(user_id, delta)
+        WHERE t.delta <> 0
+        "#,
+    )
+    .bind(account.as_str())
+    .bind(kind)
+    .bind(event_id)
+    .bind(user_ids)
+    .bind(user_deltas)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Books a user's market gain or loss on `event_id` against the house.
+/// A no-op for competition wallets.
+pub(crate) async fn book_house(
+    conn: &mut PgConnection,
+    wallet: Wallet,
+    kind: &str,
+    event_id: i32,
+    user_id: i32,
+    user_delta: i64,
+) -> Result<()> {
+    book_house_batch(conn, wallet, kind, event_id, &[user_id], &[user_delta]).await
+}
+
+pub(crate) async fn book_house_batch(
+    conn: &mut PgConnection,
+    wallet: Wallet,
+    kind: &str,
+    event_id: i32,
+    user_ids: &[i32],
+    user_deltas: &[i64],
+) -> Result<()> {
+    if wallet != Wallet::Main {
+        return Ok(());
+    }
+    book_batch(
+        conn,
+        SystemAccount::House,
+        kind,
+        Some(event_id),
+        user_ids,
+        user_deltas,
+    )
+    .await
+}
+
+/// Users' RP plus every system account; nonzero means RP moved somewhere
+/// without being booked.
+pub async fn audit(pool: &PgPool) -> Result<SolvencyAudit> {
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT a.account,
+               COALESCE(SUM(l.delta_ledger), 0)::BIGINT AS balance_ledger,
+               COUNT(l.id) AS entries,
+               ({})::BIGINT AS users_ledger
+        FROM UNNEST($1::text[]) AS a(account)
+        LEFT JOIN system_ledger l ON l.account = a.account
+        GROUP BY a.account
+        "#,
+        USERS_LEDGER
+    ))
+    .bind(SystemAccount::ALL.map(SystemAccount::as_str).to_vec())
+    .fetch_all(pool)
+    .await?;
+
+    let users_ledger = rows
+        .first()
+        .map(|row| row.get::<i64, _>("users_ledger"))
+        .unwrap_or(0);
+    let accounts: Vec<AccountBalance> = SystemAccount::ALL
+        .into_iter()
+        .map(|account| {
+            let row = rows
+                .iter()
+                .find(|row| row.get::<String, _>("account") == account.as_str());
+            AccountBalance {
+                account,
+                balance_ledger: row.map_or(0, |row| row.get("balance_ledger")),
+                entries: row.map_or(0, |row| row.get("entries")),
+            }
+        })
+        .collect();
+    let total_ledger = users_ledger
+        + accounts
+            .iter()
+            .map(|account| account.balance_ledger)
+            .sum::<i64>();
+    Ok(SolvencyAudit {
+        users_ledger,
+        accounts,
+        total_ledger,
+        balanced: total_ledger == 0,
+    })
+}
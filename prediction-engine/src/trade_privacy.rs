@@ -0,0 +1,198 @@
+//! Anonymous trading per market.
+//!
+//! Markets flagged with `events.anonymous_trading` (internal company
+//! questions and the like) keep their order flow unattributed: the public
+//! trade tape drops usernames, trade broadcasts drop `user_id`, and the
+//! resolution payout journal drops `user_id`. Admins can still see who
+//! traded through the identified tape, but every such view, like every flip
+//! of the flag, leaves a row in `trade_identity_audit` naming who looked
+//! and why.
+
+use crate::api_error::{coded, ErrorCode};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use sqlx::{PgPool, Row};
+
+pub const ANONYMITY_ENABLED: &str = "anonymity_enabled";
+pub const ANONYMITY_DISABLED: &str = "anonymity_disabled";
+pub const IDENTITIES_VIEWED: &str = "identities_viewed";
+
+pub async fn ensure_privacy_schema(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        "ALTER TABLE events ADD COLUMN IF NOT EXISTS anonymous_trading BOOLEAN NOT NULL DEFAULT FALSE",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS trade_identity_audit (
+            id BIGSERIAL PRIMARY KEY,
+            event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+            action VARCHAR(32) NOT NULL,
+            actor VARCHAR(255) NOT NULL,
+            reason TEXT,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_trade_identity_audit_event
+         ON trade_identity_audit(event_id, created_at)",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Whether `event_id` trades anonymously. Unknown events are not.
+pub async fn is_anonymous(pool: &PgPool, event_id: i32) -> Result<bool> {
+    let anonymous: Option<bool> =
+        sqlx::query_scalar("SELECT anonymous_trading FROM events WHERE id = $1")
+            .bind(event_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(anonymous.unwrap_or(false))
+}
+
+fn require_actor(actor: &str) -> Result<&str> {
+    let actor = actor.trim();
+    if actor.is_empty() || actor.chars().count() > 255 {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "actor must be 1-255 characters",
+        ));
+    }
+    Ok(actor)
+}
+
+async fn record_access(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    event_id: i32,
+    action: &str,
+    actor: &str,
+    reason: Option<&str>,
+) -> Result<i64> {
+    let id = sqlx::query_scalar(
+        "INSERT INTO trade_identity_audit (event_id, action, actor, reason)
+         VALUES ($1, $2, $3, $4)
+         RETURNING id",
+    )
+    .bind(event_id)
+    .bind(action)
+    .bind(actor)
+    .bind(reason)
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(id)
+}
+
+/// Turns anonymous trading on or off. Trades already on the tape follow the
+/// current flag, so enabling it also hides earlier traders.
+pub async fn set_anonymous(
+    pool: &PgPool,
+    event_id: i32,
+    anonymous: bool,
+    actor: &str,
+    reason: Option<&str>,
+) -> Result<Value> {
+    let actor = require_actor(actor)?;
+    ensure_privacy_schema(pool).await?;
+    let mut tx = pool.begin().await?;
+
+    let previous: bool =
+        sqlx::query_scalar("SELECT anonymous_trading FROM events WHERE id = $1 FOR UPDATE")
+            .bind(event_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| coded(ErrorCode::NotFound, "Event not found"))?;
+    if previous != anonymous {
+        sqlx::query("UPDATE events SET anonymous_trading = $1 WHERE id = $2")
+            .bind(anonymous)
+            .bind(event_id)
+            .execute(&mut *tx)
+            .await?;
+        let action = if anonymous {
+            ANONYMITY_ENABLED
+        } else {
+            ANONYMITY_DISABLED
+        };
+        record_access(&mut tx, event_id, action, actor, reason).await?;
+    }
+    tx.commit().await?;
+
+    Ok(json!({
+        "event_id": event_id,
+        "anonymous": anonymous,
+        "changed": previous != anonymous,
+    }))
+}
+
+/// The trade tape with usernames, for admins. Requires a reason, and the
+/// access is logged before anything is returned.
+pub async fn get_identified_trades(
+    pool: &PgPool,
+    event_id: i32,
+    limit: i32,
+    actor: &str,
+    reason: &str,
+) -> Result<Value> {
+    let actor = require_actor(actor)?;
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err(coded(ErrorCode::InvalidRequest, "reason must not be empty"));
+    }
+    ensure_privacy_schema(pool).await?;
+
+    let mut tx = pool.begin().await?;
+    let anonymous: bool = sqlx::query_scalar("SELECT anonymous_trading FROM events WHERE id = $1")
+        .bind(event_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| coded(ErrorCode::NotFound, "Event not found"))?;
+    let audit_id = record_access(&mut tx, event_id, IDENTITIES_VIEWED, actor, Some(reason)).await?;
+    tx.commit().await?;
+
+    let mut tape = crate::lmsr_api::load_event_trades(pool, event_id, limit, true).await?;
+    tape["anonymous"] = json!(anonymous);
+    tape["audit_id"] = json!(audit_id);
+    Ok(tape)
+}
+
+/// Flag changes and identified-tape views for one event, newest first.
+pub async fn get_identity_audit(pool: &PgPool, event_id: i32) -> Result<Value> {
+    ensure_privacy_schema(pool).await?;
+    let anonymous: bool = sqlx::query_scalar("SELECT anonymous_trading FROM events WHERE id = $1")
+        .bind(event_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| coded(ErrorCode::NotFound, "Event not found"))?;
+
+    let entries: Vec<Value> = sqlx::query(
+        "SELECT id, action, actor, reason, created_at
+         FROM trade_identity_audit WHERE event_id = $1
+         ORDER BY created_at DESC, id DESC",
+    )
+    .bind(event_id)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(|row| {
+        json!({
+            "id": row.get::<i64, _>("id"),
+            "action": row.get::<String, _>("action"),
+            "actor": row.get::<String, _>("actor"),
+            "reason": row.get::<Option<String>, _>("reason"),
+            "created_at": row.get::<DateTime<Utc>, _>("created_at"),
+        })
+    })
+    .collect();
+
+    Ok(json!({
+        "event_id": event_id,
+        "anonymous": anonymous,
+        "entries": entries,
+    }))
+}
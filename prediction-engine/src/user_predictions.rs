@@ -0,0 +1,441 @@
+//! A user's predictions, filtered, sorted and paged, with their scores.
+//!
+//! Covers every row in `predictions` joined to its event. Resolved binary
+//! predictions get the same Brier and log scores as paper predictions;
+//! other types carry their stored `numerical_score`, and nothing resolved
+//! N/A is scored. Means weight each prediction by its `score_weight` (see
+//! `score_quota`). Each listing also places the user among the population
+//! by accuracy and mean log score, as percentiles and z-scores.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use intellacc_math::scoring::LOG_SCORE_FLOOR;
+use serde_json::{json, Value};
+use sqlx::{PgPool, Row};
+
+use crate::api_error::{coded, ErrorCode};
+use crate::score_quota::{weighted_scoring_joins, QuotaPolicy};
+
+pub const MAX_LIMIT: i64 = 100;
+
+const PREDICTION_TYPES: [&str; 5] = ["binary", "numeric", "discrete", "multiple_choice", "date"];
+
+/// Resolved predictions a user needs before they are ranked against
+/// everyone else's accuracy.
+pub const MIN_RANKED_PREDICTIONS: i64 = 5;
+
+/// Joins a `predictions p` row to its event and derives `prob.probability`
+/// (binary only: `prob_vector[0]`, else the confidence put on the side in
+/// `prediction_value`) and `won.yes` (NULL until the event resolves YES or
+/// NO).
+pub(crate) const SCORING_JOINS: &str = r#"FROM predictions p
+            JOIN events e ON e.id = p.event_id
+            CROSS JOIN LATERAL (
+                SELECT CASE WHEN COALESCE(p.prediction_type, 'binary') = 'binary' THEN
+                    LEAST(GREATEST(COALESCE(
+                        (p.prob_vector->>0)::float8,
+                        CASE LOWER(p.prediction_value)
+                            WHEN 'yes' THEN p.confidence / 100.0
+                            WHEN 'no' THEN 1 - p.confidence / 100.0
+                        END
+                    ), 0), 1)
+                END AS probability
+            ) prob
+            CROSS JOIN LATERAL (
+                SELECT CASE e.outcome
+                    WHEN 'resolved_yes' THEN TRUE
+                    WHEN 'resolved_no' THEN FALSE
+                END AS yes
+            ) won"#;
+
+/// Log score over `SCORING_JOINS`, floored at the bind parameter `floor`.
+pub(crate) fn log_score_sql(floor: &str) -> String {
+    format!(
+        "CASE WHEN won.yes IS NOT NULL AND prob.probability IS NOT NULL THEN
+                       LN(GREATEST(
+                           CASE WHEN won.yes THEN prob.probability ELSE 1 - prob.probability END,
+                           {}
+                       ))
+                   END",
+        floor
+    )
+}
+
+/// Which predictions to list, by outcome.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// Not yet marked correct or incorrect
+    Pending,
+    Resolved,
+    Correct,
+    Incorrect,
+    /// On events resolved N/A; never scored
+    NotApplicable,
+    All,
+}
+
+impl Status {
+    pub fn parse(raw: Option<&str>) -> Result<Self> {
+        match raw.map(|s| s.trim().to_ascii_lowercase()).as_deref() {
+            None | Some("") | Some("all") => Ok(Status::All),
+            Some("pending") => Ok(Status::Pending),
+            Some("resolved") => Ok(Status::Resolved),
+            Some("correct") => Ok(Status::Correct),
+            Some("incorrect") => Ok(Status::Incorrect),
+            Some("not_applicable") => Ok(Status::NotApplicable),
+            Some(other) => Err(coded(ErrorCode::InvalidRequest, format!(
+                "status must be one of pending, resolved, correct, incorrect, not_applicable, all (got {})",
+                other
+            ))),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Status::Pending => "pending",
+            Status::Resolved => "resolved",
+            Status::Correct => "correct",
+            Status::Incorrect => "incorrect",
+            Status::NotApplicable => "not_applicable",
+            Status::All => "all",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sort {
+    /// Newest first
+    Recent,
+    /// Best log score first; unscored predictions last
+    Score,
+}
+
+impl Sort {
+    pub fn parse(raw: Option<&str>) -> Result<Self> {
+        match raw.map(|s| s.trim().to_ascii_lowercase()).as_deref() {
+            None | Some("") | Some("recent") => Ok(Sort::Recent),
+            Some("score") => Ok(Sort::Score),
+            Some(other) => Err(coded(
+                ErrorCode::InvalidRequest,
+                format!("sort must be one of recent, score (got {})", other),
+            )),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Sort::Recent => "recent",
+            Sort::Score => "score",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PredictionFilters {
+    pub status: Status,
+    pub category: Option<String>,
+    pub prediction_type: Option<String>,
+    /// Resolved at or after this moment.
+    pub resolved_from: Option<DateTime<Utc>>,
+    /// Resolved at or before this moment.
+    pub resolved_to: Option<DateTime<Utc>>,
+    pub sort: Sort,
+}
+
+/// One page of `user_id`'s predictions matching `filters`, with the total
+/// count and mean scores over every match and the user's population
+/// standing. A page past the last match
+/// reports a total of 0.
+pub async fn get_user_predictions(
+    pool: &PgPool,
+    policy: &QuotaPolicy,
+    user_id: i32,
+    filters: &PredictionFilters,
+    limit: i64,
+    offset: i64,
+) -> Result<Value> {
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            format!("limit must be between 1 and {}", MAX_LIMIT),
+        ));
+    }
+    if offset < 0 {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "offset must be non-negative",
+        ));
+    }
+    let category = filters
+        .category
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty());
+    let prediction_type = filters
+        .prediction_type
+        .as_deref()
+        .map(|t| t.trim().to_ascii_lowercase())
+        .filter(|t| !t.is_empty());
+    if let Some(prediction_type) = &prediction_type {
+        if !PREDICTION_TYPES.contains(&prediction_type.as_str()) {
+            return Err(coded(
+                ErrorCode::InvalidRequest,
+                format!(
+                    "type must be one of {} (got {})",
+                    PREDICTION_TYPES.join(", "),
+                    prediction_type
+                ),
+            ));
+        }
+    }
+    if let (Some(from), Some(to)) = (filters.resolved_from, filters.resolved_to) {
+        if from > to {
+            return Err(coded(
+                ErrorCode::InvalidRequest,
+                "resolved_from must not be after resolved_to",
+            ));
+        }
+    }
+
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Err(coded(ErrorCode::NotFound, "User not found"));
+    }
+
+    let rows = sqlx::query(&format!(
+        r#"
+        WITH matches AS (
+            SELECT p.id, p.event_id, e.title, e.category,
+                   COALESCE(p.prediction_type, 'binary') AS prediction_type,
+                   p.prediction_value, p.confidence,
+                   COALESCE(p.outcome, 'pending') AS outcome, e.outcome AS event_outcome,
+                   p.numerical_value::float8 AS numerical_value,
+                   p.lower_bound::float8 AS lower_bound,
+                   p.upper_bound::float8 AS upper_bound,
+                   p.actual_value::float8 AS actual_value,
+                   CASE WHEN e.outcome IS DISTINCT FROM 'resolved_na' THEN
+                       p.numerical_score::float8
+                   END AS numerical_score,
+                   p.created_at::timestamptz AS created_at,
+                   p.resolved_at::timestamptz AS resolved_at,
+                   prob.probability,
+                   POWER(prob.probability - won.yes::int, 2) AS brier_score,
+                   {} AS log_score,
+                   quota.weight AS score_weight
+            {}
+            WHERE p.user_id = $1
+              AND CASE $2::text
+                    WHEN 'pending' THEN COALESCE(p.outcome, 'pending') = 'pending'
+                    WHEN 'resolved' THEN p.outcome IN ('correct', 'incorrect')
+                    WHEN 'correct' THEN p.outcome = 'correct'
+                    WHEN 'incorrect' THEN p.outcome = 'incorrect'
+                    WHEN 'not_applicable' THEN p.outcome = 'not_applicable'
+                    ELSE true
+                  END
+              AND ($3::text IS NULL OR LOWER(e.category) = LOWER($3))
+              AND ($4::text IS NULL OR COALESCE(p.prediction_type, 'binary') = $4)
+              AND ($5::timestamptz IS NULL OR p.resolved_at::timestamptz >= $5)
+              AND ($6::timestamptz IS NULL OR p.resolved_at::timestamptz <= $6)
+        )
+        SELECT *,
+               COUNT(*) OVER () AS total,
+               SUM(score_weight * brier_score) OVER ()
+                   / NULLIF(SUM(score_weight) FILTER (WHERE brier_score IS NOT NULL) OVER (), 0)
+                   AS mean_brier,
+               SUM(score_weight * log_score) OVER ()
+                   / NULLIF(SUM(score_weight) FILTER (WHERE log_score IS NOT NULL) OVER (), 0)
+                   AS mean_log
+        FROM matches
+        ORDER BY {}
+        LIMIT $7 OFFSET $9
+        "#,
+        log_score_sql("$8"),
+        weighted_scoring_joins(policy),
+        match filters.sort {
+            Sort::Recent => "created_at DESC NULLS LAST, id DESC",
+            Sort::Score => "log_score DESC NULLS LAST, resolved_at DESC NULLS LAST, id DESC",
+        }
+    ))
+    .bind(user_id)
+    .bind(filters.status.as_str())
+    .bind(category)
+    .bind(prediction_type.as_deref())
+    .bind(filters.resolved_from)
+    .bind(filters.resolved_to)
+    .bind(limit)
+    .bind(LOG_SCORE_FLOOR)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    let total = rows.first().map_or(0, |row| row.get::<i64, _>("total"));
+    let predictions: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            json!({
+                "id": row.get::<i32, _>("id"),
+                "event_id": row.get::<i32, _>("event_id"),
+                "title": row.get::<String, _>("title"),
+                "category": row.get::<Option<String>, _>("category"),
+                "prediction_type": row.get::<String, _>("prediction_type"),
+                "prediction_value": row.get::<String, _>("prediction_value"),
+                "confidence": row.get::<Option<i32>, _>("confidence"),
+                "probability": row.get::<Option<f64>, _>("probability"),
+                "outcome": row.get::<String, _>("outcome"),
+                "event_outcome": row.get::<Option<String>, _>("event_outcome"),
+                "numerical_value": row.get::<Option<f64>, _>("numerical_value"),
+                "lower_bound": row.get::<Option<f64>, _>("lower_bound"),
+                "upper_bound": row.get::<Option<f64>, _>("upper_bound"),
+                "actual_value": row.get::<Option<f64>, _>("actual_value"),
+                "numerical_score": row.get::<Option<f64>, _>("numerical_score"),
+                "brier_score": row.get::<Option<f64>, _>("brier_score"),
+                "log_score": row.get::<Option<f64>, _>("log_score"),
+                "score_weight": row.get::<f64, _>("score_weight"),
+                "created_at": row.get::<Option<DateTime<Utc>>, _>("created_at"),
+                "resolved_at": row.get::<Option<DateTime<Utc>>, _>("resolved_at"),
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "user_id": user_id,
+        "status": filters.status.as_str(),
+        "category": category,
+        "type": prediction_type,
+        "resolved_from": filters.resolved_from,
+        "resolved_to": filters.resolved_to,
+        "sort": filters.sort.as_str(),
+        "total": total,
+        "limit": limit,
+        "offset": offset,
+        "has_more": offset + (predictions.len() as i64) < total,
+        "mean_brier_score": rows.first().and_then(|row| row.get::<Option<f64>, _>("mean_brier")),
+        "mean_log_score": rows.first().and_then(|row| row.get::<Option<f64>, _>("mean_log")),
+        "population": population_context(pool, policy, user_id).await?,
+        "predictions": predictions,
+    }))
+}
+
+/// Where `user_id` stands among ranked forecasters: everyone with at least
+/// `MIN_RANKED_PREDICTIONS` predictions marked correct or incorrect. Covers
+/// all of the user's predictions regardless of the listing's filters.
+/// Percentiles are the share of the other ranked forecasters the user
+/// strictly beats, so 84 reads as "better than 84% of forecasters"; the
+/// log-score ranking only counts forecasters with a scored binary
+/// prediction. Everything comparative is null until the user is ranked
+/// and has someone to be compared with. Computed from `predictions` on
+/// every read; there are no per-user summary tables to keep in step.
+pub async fn population_context(
+    pool: &PgPool,
+    policy: &QuotaPolicy,
+    user_id: i32,
+) -> Result<Value> {
+    let row = sqlx::query(&format!(
+        r#"
+        WITH scored AS (
+            SELECT p.user_id, (p.outcome = 'correct')::int::float8 AS correct,
+                   {} AS log_score, quota.weight
+            {}
+            WHERE p.outcome IN ('correct', 'incorrect')
+        ),
+        per_user AS (
+            SELECT user_id,
+                   COUNT(*) AS resolved,
+                   SUM(weight * correct) / NULLIF(SUM(weight), 0) AS accuracy,
+                   SUM(weight * log_score)
+                       / NULLIF(SUM(weight) FILTER (WHERE log_score IS NOT NULL), 0) AS mean_log
+            FROM scored
+            GROUP BY user_id
+        ),
+        ranked AS (
+            SELECT user_id,
+                   COUNT(*) OVER () AS forecasters,
+                   RANK() OVER (ORDER BY accuracy) - 1 AS beaten_on_accuracy,
+                   AVG(accuracy) OVER () AS population_accuracy,
+                   STDDEV_POP(accuracy) OVER () AS accuracy_sd,
+                   COUNT(mean_log) OVER () AS log_forecasters,
+                   RANK() OVER (ORDER BY mean_log NULLS LAST) - 1 AS beaten_on_log,
+                   AVG(mean_log) OVER () AS population_log,
+                   STDDEV_POP(mean_log) OVER () AS log_sd
+            FROM per_user
+            WHERE resolved >= $2 AND accuracy IS NOT NULL
+        )
+        SELECT COALESCE(me.resolved, 0) AS resolved, me.accuracy, me.mean_log,
+               (SELECT COUNT(*) FROM ranked) AS ranked_forecasters,
+               r.forecasters, r.beaten_on_accuracy, r.population_accuracy, r.accuracy_sd,
+               r.log_forecasters, r.beaten_on_log, r.population_log, r.log_sd
+        FROM (SELECT $1::int AS user_id) target
+        LEFT JOIN per_user me ON me.user_id = target.user_id
+        LEFT JOIN ranked r ON r.user_id = target.user_id
+        "#,
+        log_score_sql("$3"),
+        weighted_scoring_joins(policy)
+    ))
+    .bind(user_id)
+    .bind(MIN_RANKED_PREDICTIONS)
+    .bind(LOG_SCORE_FLOOR)
+    .fetch_one(pool)
+    .await?;
+
+    let accuracy: Option<f64> = row.get("accuracy");
+    let mean_log: Option<f64> = row.get("mean_log");
+    let ranked = row.get::<Option<i64>, _>("forecasters").is_some();
+    let standing = |value: Option<f64>, count: &str, beaten: &str, mean: &str, sd: &str| {
+        let value = value.filter(|_| ranked)?;
+        let others = row.get::<Option<i64>, _>(count)? - 1;
+        if others < 1 {
+            return None;
+        }
+        let percentile = 100.0 * row.get::<Option<i64>, _>(beaten)? as f64 / others as f64;
+        let z_score = row
+            .get::<Option<f64>, _>(sd)
+            .filter(|sd| *sd > 0.0)
+            .and_then(|sd| Some((value - row.get::<Option<f64>, _>(mean)?) / sd));
+        Some((percentile, z_score))
+    };
+    let accuracy_standing = standing(
+        accuracy,
+        "forecasters",
+        "beaten_on_accuracy",
+        "population_accuracy",
+        "accuracy_sd",
+    );
+    let log_standing = standing(
+        mean_log,
+        "log_forecasters",
+        "beaten_on_log",
+        "population_log",
+        "log_sd",
+    );
+
+    Ok(json!({
+        "min_resolved": MIN_RANKED_PREDICTIONS,
+        "ranked_forecasters": row.get::<i64, _>("ranked_forecasters"),
+        "resolved": row.get::<i64, _>("resolved"),
+        "ranked": ranked,
+        "accuracy": accuracy,
+        "accuracy_percentile": accuracy_standing.map(|(percentile, _)| percentile),
+        "accuracy_z_score": accuracy_standing.and_then(|(_, z)| z),
+        "mean_log_score": mean_log,
+        "log_score_percentile": log_standing.map(|(percentile, _)| percentile),
+        "log_score_z_score": log_standing.and_then(|(_, z)| z),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_parse_case_insensitively_and_reject_unknowns() {
+        assert_eq!(Status::parse(None).unwrap(), Status::All);
+        assert_eq!(Status::parse(Some(" Correct ")).unwrap(), Status::Correct);
+        assert!(Status::parse(Some("won")).is_err());
+        assert_eq!(Sort::parse(Some("")).unwrap(), Sort::Recent);
+        assert_eq!(Sort::parse(Some("SCORE")).unwrap(), Sort::Score);
+        assert!(Sort::parse(Some("brier")).is_err());
+    }
+}
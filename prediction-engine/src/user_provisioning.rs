@@ -0,0 +1,111 @@
+//! Provisioning users from the backend's signup flow.
+//!
+//! Trades assume the user row and its RP already exist, but the backend
+//! creates accounts on its own schedule and the faucet sweep only credits
+//! the onboarding grant on its next pass, so a new user's first trade
+//! could land before either. The backend instead calls provisioning on
+//! signup: one transaction creates the `users` row and credits the
+//! onboarding grant, which is the user's whole reputation record now that
+//! reputation is the RP ledger (`user_reputation` went with log-loss
+//! scoring). Calls are idempotent, so a retried signup or a user the
+//! backend inserted itself just gets whatever is still missing.
+
+use anyhow::Result;
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+
+use crate::api_error::{coded, ErrorCode};
+use crate::config::FaucetConfig;
+use crate::faucet::{self, FaucetGrant};
+use crate::lmsr_core::from_ledger_units;
+
+pub const ERR_IDENTITY_TAKEN: &str = "username or email already belongs to another user";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProvisionedUser {
+    pub user_id: i32,
+    pub username: String,
+    pub email: String,
+    /// False when the row already existed.
+    pub created: bool,
+    /// The onboarding grant this call credited, if it was still owed.
+    pub onboarding_grant: Option<FaucetGrant>,
+    pub balance_rp: f64,
+}
+
+/// Creates `username`/`email` if it doesn't exist and credits its
+/// onboarding grant if it hasn't had one. An existing row must match on
+/// both username and email; its password hash is left alone.
+pub async fn provision_user(
+    pool: &PgPool,
+    config: &FaucetConfig,
+    username: &str,
+    email: &str,
+    password_hash: &str,
+) -> Result<ProvisionedUser> {
+    let username = username.trim();
+    let email = email.trim();
+    if username.is_empty() || username.chars().count() > 50 {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "username must be 1-50 characters",
+        ));
+    }
+    if !email.contains('@') || email.chars().count() > 100 {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "email must be a valid address",
+        ));
+    }
+    if password_hash.is_empty() || password_hash.chars().count() > 255 {
+        return Err(coded(
+            ErrorCode::InvalidRequest,
+            "password_hash must be 1-255 characters",
+        ));
+    }
+    let grant = faucet::ledger(config.onboarding_grant_rp)?;
+
+    faucet::ensure_grants_table(pool).await?;
+    let mut tx = pool.begin().await?;
+    // Also serializes concurrent provisioning of the same signup
+    faucet::lock_grants(&mut tx).await?;
+
+    let inserted: Option<i32> = sqlx::query_scalar(
+        "INSERT INTO users (username, email, password_hash, rp_balance_ledger, rp_staked_ledger)
+         VALUES ($1, $2, $3, 0, 0)
+         ON CONFLICT DO NOTHING
+         RETURNING id",
+    )
+    .bind(username)
+    .bind(email)
+    .bind(password_hash)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let user_id = match inserted {
+        Some(id) => id,
+        None => sqlx::query_scalar("SELECT id FROM users WHERE username = $1 AND email = $2")
+            .bind(username)
+            .bind(email)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| coded(ErrorCode::Conflict, ERR_IDENTITY_TAKEN))?,
+    };
+
+    let credited = faucet::credit_onboarding(&mut tx, grant, Some(user_id)).await?;
+    let balance: i64 =
+        sqlx::query("SELECT COALESCE(rp_balance_ledger, 0) AS balance FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&mut *tx)
+            .await?
+            .get("balance");
+    tx.commit().await?;
+
+    Ok(ProvisionedUser {
+        user_id,
+        username: username.to_string(),
+        email: email.to_string(),
+        created: inserted.is_some(),
+        onboarding_grant: faucet::grants(&credited, faucet::ONBOARDING).pop(),
+        balance_rp: from_ledger_units(balance as i128),
+    })
+}
@@ -0,0 +1,80 @@
+//! Build and schema identity of this engine.
+//!
+//! The commit and build time are stamped in by `build.rs`. `BUILD` goes out
+//! with every broadcast and webhook, so a message can be traced back to the
+//! engine build that sent it. `REQUIRED_MIGRATION` is the newest backend
+//! migration whose schema the engine relies on; the backend compares it
+//! with its `schema_migrations` to decide whether the two are compatible.
+
+use anyhow::Result;
+use chrono::DateTime;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const GIT_SHA: &str = env!("ENGINE_GIT_SHA");
+/// Crate version and short commit, e.g. `0.1.0+1a2b3c4`.
+pub const BUILD: &str = concat!(env!("CARGO_PKG_VERSION"), "+", env!("ENGINE_GIT_SHORT_SHA"));
+pub const REQUIRED_MIGRATION: &str = "20261016_add_weighted_consensus_comment_buzz.sql";
+
+/// Cargo features compiled into this build.
+pub fn features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "invariant-checks") {
+        features.push("invariant-checks");
+    }
+    features
+}
+
+/// Build info plus what the database reports about applied migrations.
+/// Databases migrated without the backend's runner have no
+/// `schema_migrations`; their migration fields are null.
+pub async fn get_version(pool: &PgPool) -> Result<Value> {
+    let built_at = env!("ENGINE_BUILT_AT")
+        .parse::<i64>()
+        .ok()
+        .and_then(|secs| DateTime::from_timestamp(secs, 0));
+
+    let tracked: bool = sqlx::query_scalar("SELECT to_regclass('schema_migrations') IS NOT NULL")
+        .fetch_one(pool)
+        .await?;
+    let (latest_applied, required_applied): (Option<String>, Option<bool>) = if tracked {
+        sqlx::query_as(
+            "SELECT (SELECT filename FROM schema_migrations
+                     ORDER BY applied_at DESC, filename DESC LIMIT 1),
+                    EXISTS (SELECT 1 FROM schema_migrations WHERE filename = $1)",
+        )
+        .bind(REQUIRED_MIGRATION)
+        .fetch_one(pool)
+        .await?
+    } else {
+        (None, None)
+    };
+
+    Ok(json!({
+        "service": "prediction-engine",
+        "version": VERSION,
+        "build": BUILD,
+        "git_sha": GIT_SHA,
+        "built_at": built_at,
+        "features": features(),
+        "schema": {
+            "required_migration": REQUIRED_MIGRATION,
+            "required_migration_applied": required_applied,
+            "latest_applied_migration": latest_applied,
+        },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_id_is_the_version_and_short_commit() {
+        let (version, sha) = BUILD.split_once('+').unwrap();
+        assert_eq!(version, VERSION);
+        assert!(GIT_SHA.starts_with(sha), "{} vs {}", BUILD, GIT_SHA);
+        assert!(env!("ENGINE_BUILT_AT").parse::<i64>().is_ok());
+    }
+}
@@ -0,0 +1,430 @@
+// Outbound webhooks: signed JSON notifications for engine events, so the
+// Node backend can react to resolutions (and disputed reversals), new
+// imported markets, markets closing, finished syncs, ranking changes and
+// failed score audits without polling or reading our tables.
+//
+// Endpoints, the event filter and retry settings are `Config::webhooks`
+// (WEBHOOK_URLS, WEBHOOK_EVENTS, ...), installed by `configure` at startup.
+// Each body is signed with HMAC-SHA256 over "{timestamp}.{body}" using
+// WEBHOOK_SECRET and sent as `X-Intellacc-Signature: sha256=<hex>` alongside
+// `X-Intellacc-Timestamp`, so receivers can reject stale replays. Failed
+// deliveries are retried with exponential backoff; every delivery and its
+// final state is recorded in webhook_deliveries. A delivery whose sender
+// died mid-retry stays `pending`; `sweep_stale_deliveries` claims those and
+// sends them again under their original id.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use sqlx::{PgPool, Row};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+pub use crate::config::WebhookConfig;
+
+pub const EVENT_RESOLVED: &str = "event_resolved";
+pub const RESOLUTION_REVERTED: &str = "resolution_reverted";
+pub const MARKET_CREATED: &str = "market_created";
+pub const MARKET_CLOSED: &str = "market_closed";
+pub const SYNC_COMPLETED: &str = "sync_completed";
+pub const RANKING_UPDATED: &str = "ranking_updated";
+pub const SCORE_INTEGRITY_FAILED: &str = "score_integrity_failed";
+
+const MAX_BACKOFF_MS: u64 = 60_000;
+
+/// Stale deliveries claimed per sweep.
+const SWEEP_BATCH: i64 = 100;
+
+static CONFIG: OnceLock<WebhookConfig> = OnceLock::new();
+
+/// Installs the settings `emit` delivers with; called once at startup.
+/// Until then nothing is sent.
+pub fn configure(cfg: WebhookConfig) {
+    if !cfg.urls.is_empty() && cfg.secret.is_none() {
+        println!("⚠️ WEBHOOK_URLS is set without WEBHOOK_SECRET; webhooks will be sent unsigned");
+    }
+    let _ = CONFIG.set(cfg);
+}
+
+fn config() -> &'static WebhookConfig {
+    CONFIG.get_or_init(WebhookConfig::default)
+}
+
+fn build_client(cfg: &WebhookConfig) -> Result<Client> {
+    Ok(Client::builder()
+        .timeout(Duration::from_secs(cfg.timeout_secs))
+        .user_agent("Intellacc-PredictionEngine/1.0")
+        .build()?)
+}
+
+/// Fire-and-forget: queue `event_type` for every configured endpoint. Never
+/// blocks the caller and is a no-op when no endpoints are configured.
+pub fn emit(pool: &PgPool, event_type: &str, data: Value) {
+    let cfg = config();
+    if !cfg.wants(event_type) {
+        return;
+    }
+    let pool = pool.clone();
+    let event_type = event_type.to_string();
+    tokio::spawn(async move {
+        if let Err(err) = ensure_webhook_tables(&pool).await {
+            tracing::warn!(error = %err, "webhook delivery log unavailable");
+        }
+        let client = match build_client(cfg) {
+            Ok(client) => client,
+            Err(err) => {
+                tracing::warn!(error = %err, "failed to build webhook client");
+                return;
+            }
+        };
+        for url in &cfg.urls {
+            deliver(&pool, &client, cfg, url, &event_type, &data).await;
+        }
+    });
+}
+
+/// Hex HMAC-SHA256 of "{timestamp}.{body}".
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Delay before retry number `attempt` (1-based): base * 2^(attempt-1), capped.
+fn backoff_delay(base_ms: u64, attempt: u32) -> Duration {
+    let factor = 1u64 << attempt.saturating_sub(1).min(16);
+    Duration::from_millis(base_ms.saturating_mul(factor).min(MAX_BACKOFF_MS))
+}
+
+async fn deliver(
+    pool: &PgPool,
+    client: &Client,
+    cfg: &WebhookConfig,
+    url: &str,
+    event_type: &str,
+    data: &Value,
+) {
+    let created_at = Utc::now();
+    let delivery_id = match insert_delivery(pool, url, event_type, data).await {
+        Ok(id) => Some(id),
+        Err(err) => {
+            tracing::warn!(error = %err, url, event_type, "failed to log webhook delivery");
+            None
+        }
+    };
+    let delivery = Delivery {
+        id: delivery_id,
+        url,
+        event_type,
+        data,
+        created_at,
+    };
+    send(pool, client, cfg, &delivery).await;
+}
+
+/// One event bound for one endpoint; `id` is its webhook_deliveries row.
+struct Delivery<'a> {
+    id: Option<i64>,
+    url: &'a str,
+    event_type: &'a str,
+    data: &'a Value,
+    created_at: DateTime<Utc>,
+}
+
+/// Posts one delivery with retries and records how it ended. The body keeps
+/// the delivery's id and original timestamp, so a redelivery after a crash
+/// looks the same to the receiver as the first attempt would have.
+async fn send(pool: &PgPool, client: &Client, cfg: &WebhookConfig, delivery: &Delivery<'_>) {
+    let Delivery {
+        id: delivery_id,
+        url,
+        event_type,
+        data,
+        created_at,
+    } = *delivery;
+    let body = json!({
+        "id": delivery_id,
+        "type": event_type,
+        "data": data,
+        "timestamp": created_at,
+        "engine": crate::version::BUILD,
+    })
+    .to_string();
+
+    let mut last_status: Option<i32> = None;
+    let mut last_error: Option<String> = None;
+    for attempt in 1..=cfg.max_attempts {
+        let timestamp = Utc::now().timestamp();
+        let mut request = client
+            .post(url)
+            .header("Content-Type", "application/json")
+            .header("X-Intellacc-Event", event_type)
+            .header("X-Intellacc-Timestamp", timestamp.to_string())
+            .body(body.clone());
+        if let Some(id) = delivery_id {
+            request = request.header("X-Intellacc-Delivery", id.to_string());
+        }
+        if let Some(secret) = &cfg.secret {
+            request = request.header(
+                "X-Intellacc-Signature",
+                format!("sha256={}", sign(secret, timestamp, &body)),
+            );
+        }
+
+        match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                if let Some(id) = delivery_id {
+                    let _ = finish_delivery(
+                        pool,
+                        id,
+                        "delivered",
+                        attempt,
+                        Some(response.status().as_u16() as i32),
+                        None,
+                    )
+                    .await;
+                }
+                return;
+            }
+            Ok(response) => {
+                last_status = Some(response.status().as_u16() as i32);
+                last_error = Some(format!("HTTP {}", response.status()));
+            }
+            Err(err) => {
+                last_status = None;
+                last_error = Some(err.to_string());
+            }
+        }
+
+        if attempt < cfg.max_attempts {
+            tokio::time::sleep(backoff_delay(cfg.base_backoff_ms, attempt)).await;
+        }
+    }
+
+    println!(
+        "⚠️ Webhook {} to {} failed after {} attempts: {}",
+        event_type,
+        url,
+        cfg.max_attempts,
+        last_error.as_deref().unwrap_or("unknown error")
+    );
+    if let Some(id) = delivery_id {
+        let _ = finish_delivery(
+            pool,
+            id,
+            "failed",
+            cfg.max_attempts,
+            last_status,
+            last_error.as_deref(),
+        )
+        .await;
+    }
+}
+
+async fn ensure_webhook_tables(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS webhook_deliveries (
+            id BIGSERIAL PRIMARY KEY,
+            endpoint TEXT NOT NULL,
+            event_type VARCHAR(64) NOT NULL,
+            payload JSONB NOT NULL,
+            status VARCHAR(16) NOT NULL DEFAULT 'pending',
+            attempts INTEGER NOT NULL DEFAULT 0,
+            last_status_code INTEGER,
+            last_error TEXT,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            finished_at TIMESTAMPTZ
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    // Set when the stale sweep claims a delivery, so the next sweep waits a
+    // full period before presuming the redelivery abandoned too
+    sqlx::query("ALTER TABLE webhook_deliveries ADD COLUMN IF NOT EXISTS claimed_at TIMESTAMPTZ")
+        .execute(pool)
+        .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_pending
+         ON webhook_deliveries(created_at) WHERE status = 'pending'",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Sends again every delivery left `pending` for longer than
+/// `stale_pending_secs`, typically by a process that died mid-retry. Rows are
+/// claimed with SKIP LOCKED and stamped, so concurrent sweeps never send one
+/// twice. A delivery to an endpoint no longer configured is marked failed.
+/// Returns how many deliveries were claimed.
+pub async fn sweep_stale_deliveries(pool: &PgPool, cfg: &WebhookConfig) -> Result<usize> {
+    ensure_webhook_tables(pool).await?;
+    let rows = sqlx::query(
+        r#"
+        UPDATE webhook_deliveries
+        SET claimed_at = NOW()
+        WHERE id IN (
+            SELECT id FROM webhook_deliveries
+            WHERE status = 'pending'
+              AND COALESCE(claimed_at, created_at) < NOW() - make_interval(secs => $1)
+            ORDER BY id
+            LIMIT $2
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING id, endpoint, event_type, payload, created_at
+        "#,
+    )
+    .bind(cfg.stale_pending_secs as f64)
+    .bind(SWEEP_BATCH)
+    .fetch_all(pool)
+    .await?;
+    if rows.is_empty() {
+        return Ok(0);
+    }
+
+    let client = build_client(cfg)?;
+    for row in &rows {
+        let id: i64 = row.get("id");
+        let endpoint: String = row.get("endpoint");
+        if !cfg.urls.contains(&endpoint) {
+            finish_delivery(pool, id, "failed", 0, None, Some("endpoint no longer configured"))
+                .await?;
+            continue;
+        }
+        let event_type: String = row.get("event_type");
+        let data: Value = row.get("payload");
+        let delivery = Delivery {
+            id: Some(id),
+            url: &endpoint,
+            event_type: &event_type,
+            data: &data,
+            created_at: row.get("created_at"),
+        };
+        send(pool, &client, cfg, &delivery).await;
+    }
+    Ok(rows.len())
+}
+
+async fn insert_delivery(pool: &PgPool, url: &str, event_type: &str, data: &Value) -> Result<i64> {
+    let id: i64 = sqlx::query_scalar(
+        r#"
+        INSERT INTO webhook_deliveries (endpoint, event_type, payload)
+        VALUES ($1, $2, $3::jsonb)
+        RETURNING id
+        "#,
+    )
+    .bind(url)
+    .bind(event_type)
+    .bind(data.to_string())
+    .fetch_one(pool)
+    .await?;
+    Ok(id)
+}
+
+async fn finish_delivery(
+    pool: &PgPool,
+    id: i64,
+    status: &str,
+    attempts: u32,
+    status_code: Option<i32>,
+    error: Option<&str>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE webhook_deliveries
+        SET status = $2, attempts = $3, last_status_code = $4, last_error = $5, finished_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(status)
+    .bind(attempts as i32)
+    .bind(status_code)
+    .bind(error)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn get_recent_deliveries(pool: &PgPool, limit: i64) -> Result<Vec<Value>> {
+    ensure_webhook_tables(pool).await?;
+    let limit = limit.clamp(1, 200);
+    let rows = sqlx::query(
+        r#"
+        SELECT id, endpoint, event_type, status, attempts, last_status_code, last_error,
+               created_at, finished_at
+        FROM webhook_deliveries
+        ORDER BY created_at DESC
+        LIMIT $1
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            json!({
+                "id": row.get::<i64, _>("id"),
+                "endpoint": row.get::<String, _>("endpoint"),
+                "event_type": row.get::<String, _>("event_type"),
+                "status": row.get::<String, _>("status"),
+                "attempts": row.get::<i32, _>("attempts"),
+                "last_status_code": row.get::<Option<i32>, _>("last_status_code"),
+                "last_error": row.get::<Option<String>, _>("last_error"),
+                "created_at": row.get::<DateTime<Utc>, _>("created_at"),
+                "finished_at": row.get::<Option<DateTime<Utc>>, _>("finished_at"),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_is_hmac_sha256_over_timestamp_and_body() {
+        // Reference value: printf '1700000000.{"a":1}' | openssl dgst -sha256 -hmac secret
+        assert_eq!(
+            sign("secret", 1_700_000_000, r#"{"a":1}"#),
+            "49f24e537407743fa4a0242bb63b94b9a47ee99cbbe071ccd8a22550ae411686"
+        );
+        assert_ne!(
+            sign("secret", 1_700_000_001, r#"{"a":1}"#),
+            sign("secret", 1_700_000_000, r#"{"a":1}"#)
+        );
+    }
+
+    #[test]
+    fn backoff_doubles_and_caps() {
+        assert_eq!(backoff_delay(1_000, 1), Duration::from_millis(1_000));
+        assert_eq!(backoff_delay(1_000, 2), Duration::from_millis(2_000));
+        assert_eq!(backoff_delay(1_000, 4), Duration::from_millis(8_000));
+        assert_eq!(
+            backoff_delay(1_000, 30),
+            Duration::from_millis(MAX_BACKOFF_MS)
+        );
+    }
+
+    #[test]
+    fn event_filter_defaults_to_all_when_endpoints_exist() {
+        let mut cfg = WebhookConfig::default();
+        assert!(!cfg.wants(EVENT_RESOLVED));
+        cfg.urls
+            .push("http://backend:3000/hooks/engine".to_string());
+        assert!(cfg.wants(EVENT_RESOLVED));
+        cfg.events = vec![MARKET_CREATED.to_string()];
+        assert!(!cfg.wants(EVENT_RESOLVED));
+        assert!(cfg.wants(MARKET_CREATED));
+    }
+}
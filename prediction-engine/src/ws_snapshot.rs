@@ -0,0 +1,225 @@
+//! Snapshots a WebSocket client gets when it connects: open markets'
+//! probabilities, the top of the leaderboard with its version, and on a
+//! user's topic their reputation and rank. Broadcasts then carry the deltas.
+//! Snapshots are read on the analytics pool through the response cache,
+//! keyed by a generation each broadcast bumps before clearing it.
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use moka::future::Cache;
+use serde_json::{json, Value};
+use sqlx::{PgPool, Row};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::api_error::{coded, ErrorCode};
+use crate::lmsr_core::from_ledger_units;
+use crate::notifications;
+use crate::rank_history::{RANKING, RANKS_TOPIC};
+use crate::version;
+
+/// Users listed in the leaderboard snapshot.
+pub const TOP_USERS: i64 = 10;
+
+const MARKETS_KEY: &str = "markets";
+const LEADERBOARD_KEY: &str = "leaderboard";
+
+/// Bumped before each cache invalidation, so a snapshot loaded across one
+/// is cached under a key no later connection reads.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Retires every cached snapshot; call before clearing the response cache.
+pub fn invalidate() {
+    GENERATION.fetch_add(1, Ordering::AcqRel);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Snapshot {
+    Markets,
+    Leaderboard,
+    User(i32),
+}
+
+/// The snapshots a connection narrowed to `topic` (every topic if None)
+/// starts with, in the order they are sent.
+pub fn for_topic(topic: Option<&str>) -> Vec<Snapshot> {
+    match topic {
+        None => vec![Snapshot::Markets, Snapshot::Leaderboard],
+        Some(RANKS_TOPIC) => vec![Snapshot::Leaderboard],
+        Some(topic) => match notifications::topic_user_id(topic) {
+            Some(user_id) => vec![
+                Snapshot::Markets,
+                Snapshot::Leaderboard,
+                Snapshot::User(user_id),
+            ],
+            None => Vec::new(),
+        },
+    }
+}
+
+/// The snapshot messages for a connection narrowed to `topic`. One that
+/// fails to load is left out; the stream still updates it.
+pub async fn messages(
+    pool: &PgPool,
+    cache: &Cache<String, String>,
+    topic: Option<&str>,
+) -> Vec<String> {
+    let mut messages = Vec::new();
+    for snapshot in for_topic(topic) {
+        let (event_type, loaded) = match snapshot {
+            Snapshot::Markets => (
+                "marketsSnapshot",
+                cached(cache, MARKETS_KEY.to_string(), markets(pool)).await,
+            ),
+            Snapshot::Leaderboard => (
+                "leaderboardSnapshot",
+                cached(cache, LEADERBOARD_KEY.to_string(), leaderboard(pool)).await,
+            ),
+            Snapshot::User(user_id) => (
+                "userSnapshot",
+                cached(cache, user_key(user_id), user_stats(pool, user_id)).await,
+            ),
+        };
+        let data = match loaded {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("❌ WebSocket {} failed: {}", event_type, e);
+                continue;
+            }
+        };
+        let mut msg = json!({
+            "type": event_type,
+            "data": data,
+            "timestamp": Utc::now(),
+            "engine": version::BUILD
+        });
+        if let Snapshot::User(user_id) = snapshot {
+            msg["topic"] = json!(notifications::user_topic(user_id));
+        }
+        messages.push(msg.to_string());
+    }
+    messages
+}
+
+fn user_key(user_id: i32) -> String {
+    format!("user:{}", user_id)
+}
+
+/// The snapshot under `key`, loaded once per generation however many
+/// connections ask for it at the same time.
+async fn cached(
+    cache: &Cache<String, String>,
+    key: String,
+    load: impl Future<Output = Result<Value>>,
+) -> Result<Value> {
+    let key = format!("ws_snapshot:{}:{}", GENERATION.load(Ordering::Acquire), key);
+    let json = cache
+        .try_get_with(key, async { load.await.map(|value| value.to_string()) })
+        .await
+        .map_err(|e| anyhow!("{}", e))?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+/// Open markets and their probabilities.
+async fn markets(pool: &PgPool) -> Result<Value> {
+    let rows = sqlx::query(
+        "SELECT id, market_prob::float8 AS market_prob
+         FROM events
+         WHERE outcome IS NULL
+           AND closed_at IS NULL
+           AND hidden_at IS NULL
+           AND closing_date::timestamptz > NOW()
+         ORDER BY id",
+    )
+    .fetch_all(pool)
+    .await?;
+    let markets: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            json!({
+                "event_id": row.get::<i32, _>("id"),
+                "market_prob": row.get::<Option<f64>, _>("market_prob"),
+            })
+        })
+        .collect();
+    Ok(json!({ "markets": markets }))
+}
+
+async fn leaderboard(pool: &PgPool) -> Result<Value> {
+    let version: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(id), 0) FROM rank_transitions")
+        .fetch_one(pool)
+        .await?;
+    let rows = sqlx::query(&format!(
+        "SELECT id, username, reputation_ledger, rank, ranked_users
+         FROM ({}) ranked
+         ORDER BY rank
+         LIMIT $1",
+        RANKING
+    ))
+    .bind(TOP_USERS)
+    .fetch_all(pool)
+    .await?;
+    let ranked_users = rows
+        .first()
+        .map(|row| row.get::<i64, _>("ranked_users"))
+        .unwrap_or(0);
+    let top: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            json!({
+                "rank": row.get::<i64, _>("rank"),
+                "user_id": row.get::<i32, _>("id"),
+                "username": row.get::<String, _>("username"),
+                "reputation": from_ledger_units(row.get::<i64, _>("reputation_ledger") as i128),
+            })
+        })
+        .collect();
+    Ok(json!({
+        "version": version,
+        "ranked_users": ranked_users,
+        "top": top,
+    }))
+}
+
+async fn user_stats(pool: &PgPool, user_id: i32) -> Result<Value> {
+    let row = sqlx::query(&format!(
+        "SELECT u.rp_balance_ledger, u.rp_staked_ledger, ranked.rank, ranked.ranked_users
+         FROM ({}) ranked
+         JOIN users u ON u.id = ranked.id
+         WHERE ranked.id = $1",
+        RANKING
+    ))
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| coded(ErrorCode::NotFound, "User not found"))?;
+    let balance_ledger: i64 = row.get::<Option<i64>, _>("rp_balance_ledger").unwrap_or(0);
+    let staked_ledger: i64 = row.get::<Option<i64>, _>("rp_staked_ledger").unwrap_or(0);
+    Ok(json!({
+        "user_id": user_id,
+        "rp_balance": from_ledger_units(balance_ledger as i128),
+        "rp_staked": from_ledger_units(staked_ledger as i128),
+        "total_reputation": from_ledger_units((balance_ledger + staked_ledger) as i128),
+        "rank": row.get::<i64, _>("rank"),
+        "ranked_users": row.get::<i64, _>("ranked_users"),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connections_get_the_snapshots_of_the_topics_they_receive() {
+        assert_eq!(
+            for_topic(None),
+            vec![Snapshot::Markets, Snapshot::Leaderboard]
+        );
+        assert_eq!(
+            for_topic(Some(&notifications::user_topic(7))),
+            vec![Snapshot::Markets, Snapshot::Leaderboard, Snapshot::User(7)]
+        );
+        assert_eq!(for_topic(Some(RANKS_TOPIC)), vec![Snapshot::Leaderboard]);
+        assert!(for_topic(Some(crate::jobs::JOBS_TOPIC)).is_empty());
+    }
+}
@@ -0,0 +1,14 @@
+#!/usr/bin/env bash
+# Builds the browser trade-preview math (prediction-engine/intellacc-math-wasm)
+# into shared/intellacc-math-pkg, which the frontend imports as @intellacc-math.
+# Needs wasm-pack and the wasm32-unknown-unknown target.
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")/.." && pwd)"
+
+wasm-pack build "$ROOT_DIR/prediction-engine/intellacc-math-wasm" \
+  --release \
+  --target web \
+  --out-dir "$ROOT_DIR/shared/intellacc-math-pkg" \
+  --out-name intellacc_math
+rm -f "$ROOT_DIR/shared/intellacc-math-pkg/.gitignore"
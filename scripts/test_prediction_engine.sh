@@ -5,9 +5,9 @@ ROOT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")/.." && pwd)"
 COMPOSE_FILE="$ROOT_DIR/prediction-engine/docker-compose.test.yml"
 
 if [[ "${1:-}" == "--full" ]]; then
-  export CARGO_TEST_ARGS="-- --nocapture"
+  export CARGO_TEST_ARGS="--workspace -- --nocapture"
 else
-  export CARGO_TEST_ARGS="--lib -- --nocapture --skip stress::tests::test_comprehensive_market_simulation"
+  export CARGO_TEST_ARGS="--workspace --lib -- --nocapture --skip stress::tests::test_comprehensive_market_simulation"
 fi
 
 cleanup() {
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ErrorCode = "INVALID_REQUEST" | "UNAUTHORIZED" | "FORBIDDEN" | "NOT_FOUND" | "CONFLICT" | "RATE_LIMITED" | "INSUFFICIENT_BALANCE" | "INSUFFICIENT_SHARES" | "MARKET_CLOSED" | "MARKET_RESOLVED" | "MARKET_EMBARGOED" | "MARKET_FORECAST_ONLY" | "HOLD_ACTIVE" | "SLIPPAGE_EXCEEDED" | "STALE_VERSION" | "SERIALIZATION_RETRIES_EXHAUSTED" | "INTERNAL";
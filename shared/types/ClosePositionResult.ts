@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A binary position closed out: both sides sold in one transaction.
+ */
+export type ClosePositionResult = { yes_shares_sold: number, no_shares_sold: number, 
+/**
+ * RP paid out for both sides together
+ */
+payout: number, 
+/**
+ * payout less the stake the position had open
+ */
+realized_pnl: number, new_prob: number, current_cost_c: number, };
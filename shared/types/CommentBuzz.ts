@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type CommentBuzz = { comments_24h: bigint, comments_prev_24h: bigint, 
+/**
+ * Comments per hour over the last 24 hours.
+ */
+velocity_per_hour: number, 
+/**
+ * Relative change of the last 24 hours over the 24 before them;
+ * `None` when the earlier day had no comments.
+ */
+velocity_change: number | null, 
+/**
+ * Comment-weighted mean sentiment over the last 24 hours.
+ */
+sentiment_24h: number | null, last_comment_hour: string | null, };
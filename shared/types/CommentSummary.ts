@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * One event's comments in one hour, as pushed by the backend.
+ */
+export type CommentSummary = { event_id: number, 
+/**
+ * Any time within the hour; stored truncated to the hour.
+ */
+hour: string, comment_count: number, 
+/**
+ * Mean sentiment of the hour's comments, -1 (negative) to 1 (positive).
+ */
+sentiment: number | null, };
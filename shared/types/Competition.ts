@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type Competition = { id: number, name: string, starting_bankroll: number, starting_bankroll_ledger: bigint, ends_at: string, created_at: string, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type CreateCompetition = { name: string, 
+/**
+ * RP each entrant starts with.
+ */
+starting_bankroll: number, ends_at: string, };
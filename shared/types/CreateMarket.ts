@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * New binary market. Liquidity comes from exactly one of `liquidity_b`
+ * or `max_subsidy` (the most RP the market maker may lose), or else from
+ * the category's liquidity recommendation if it is auto-applied.
+ * `use_recommended_liquidity` overrides that: true takes the
+ * recommendation even if it isn't auto-applied, false never takes it.
+ */
+export type CreateMarket = { title: string, details: string | null, closing_date: string, category: string | null, liquidity_b: number | null, max_subsidy: number | null, use_recommended_liquidity: boolean | null, };
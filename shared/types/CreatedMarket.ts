@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type CreatedMarket = { event_id: number, title: string, closing_date: string, category: string | null, liquidity_b: number, max_subsidy: number, market_prob: number, q_yes: number, q_no: number, };
@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What moving a binary market to one probability costs.
+ */
+export type DepthLevel = { target_prob: number, 
+/**
+ * RP a single buy reaching the target would debit
+ */
+cost: number, 
+/**
+ * Shares that buy gets
+ */
+shares: number, 
+/**
+ * Cost per share
+ */
+avg_price: number, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type EmbargoWindow = { id: bigint, event_id: number, starts_at: string, ends_at: string, reason: string | null, created_by: string, created_at: string, };
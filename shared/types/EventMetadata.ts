@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type EventMetadata = { resolution_criteria: string | null, fine_print: string | null, 
+/**
+ * Unit of the quantity a numeric question resolves to
+ */
+value_unit: string | null, value_lower_bound: number | null, value_upper_bound: number | null, source_url: string | null, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type LiquidityMigration = { id: bigint, event_id: number, old_b: number, new_b: number, old_q_yes: number, old_q_no: number, new_q_yes: number, new_q_no: number, old_cost: number, new_cost: number, market_prob: number, actor: string, reason: string | null, created_at: string, };
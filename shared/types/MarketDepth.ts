@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DepthLevel } from "./DepthLevel";
+
+/**
+ * The cost curve of a binary market: what it takes to move the price to
+ * each probability on a grid, buying YES above the current price and NO
+ * below it, nearest target first.
+ */
+export type MarketDepth = { prob: number, liquidity_b: number, step: number, yes: Array<DepthLevel>, no: Array<DepthLevel>, };
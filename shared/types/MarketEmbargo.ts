@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EmbargoWindow } from "./EmbargoWindow";
+
+/**
+ * What market state shows: whether trading is paused now, until when, and
+ * the windows that have not ended yet.
+ */
+export type MarketEmbargo = { active: boolean, until: string | null, windows: Array<EmbargoWindow>, };
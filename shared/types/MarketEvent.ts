@@ -1,3 +1,4 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EventMetadata } from "./EventMetadata";
 
-export type MarketEvent = { id: number, topic_id: number | null, title: string, details: string | null, closing_date: string | null, outcome: string | null, event_type: string | null, market_prob: number, liquidity_b: number, cumulative_stake: number, };
+export type MarketEvent = { id: number, topic_id: number | null, title: string, details: string | null, closing_date: string | null, outcome: string | null, event_type: string | null, market_prob: number, liquidity_b: number, cumulative_stake: number, metadata: EventMetadata, };
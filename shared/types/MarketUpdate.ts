@@ -1,3 +1,11 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type MarketUpdate = { event_id: number, target_prob: number, stake: number, referral_post_id: number | null, referral_click_id: number | null, };
+export type MarketUpdate = { event_id: number, target_prob: number, stake: number, referral_post_id: number | null, referral_click_id: number | null, 
+/**
+ * Most RP the trade may cost; it aborts rather than fill above this
+ */
+max_cost: number | null, 
+/**
+ * Fewest shares the trade may buy; it aborts rather than fill below this
+ */
+min_shares: number | null, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type NumericBucketTradeResult = { event_id: number, trade_id: bigint, outcome_id: bigint, shares: number, cost_ledger: bigint, market_version: bigint, post_distribution: Array<number>, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type NumericSellResult = { event_id: number, trade_id: bigint, payout_ledger: bigint, market_version: bigint, };
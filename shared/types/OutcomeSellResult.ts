@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { MarketOutcomeView } from "./MarketOutcomeView";
+
+export type OutcomeSellResult = { event_id: number, outcome_id: bigint, payout: number, new_prob: number, current_cost_c: number, market_prob: number, outcomes: Array<MarketOutcomeView>, };
@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * One user's settlement when a binary market resolves.
+ */
+export type ResolutionPayout = { user_id: number, 
+/**
+ * Winning shares, redeemed at 1 RP each
+ */
+shares_redeemed: number, rp_credited: number, stake_released: number, 
+/**
+ * Balance of the wallet the market settles into (the competition
+ * wallet for competition markets), after the payout
+ */
+new_balance: number, 
+/**
+ * Change in reputation (RP held plus staked): credited less released
+ */
+score_delta: number, };
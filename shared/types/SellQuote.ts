@@ -0,0 +1,42 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A binary sell previewed without executing it.
+ */
+export type SellQuote = { share_type: string, 
+/**
+ * Shares the sell would sell
+ */
+shares: number, 
+/**
+ * Shares of this side held now
+ */
+shares_held: number, 
+/**
+ * The sell closes the side, releasing all of its stake
+ */
+closes_side: boolean, payout: number, 
+/**
+ * Payout per share
+ */
+avg_price: number, prob_before: number, prob_after: number, 
+/**
+ * prob_after - prob_before
+ */
+price_impact: number, 
+/**
+ * Staked RP the sell would release
+ */
+stake_unwound: number, 
+/**
+ * payout - stake_unwound
+ */
+realized_pnl: number, 
+/**
+ * False while a recent purchase is still in its hold period
+ */
+can_sell: boolean, 
+/**
+ * When the last running hold expires
+ */
+hold_until: string | null, };
@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type SellResult = { payout: number, new_prob: number, current_cost_c: number, };
+export type SellResult = { payout: number, shares_sold: number, new_prob: number, current_cost_c: number, };
@@ -0,0 +1,31 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A binary trade previewed without executing it.
+ */
+export type TradeQuote = { share_type: string, target_prob: number, stake: number, 
+/**
+ * Shares the stake would buy
+ */
+shares: number, 
+/**
+ * RP the trade would debit: the stake, to ledger precision
+ */
+cost: number, 
+/**
+ * Cost per share
+ */
+avg_price: number, prob_before: number, prob_after: number, 
+/**
+ * Whether the stake moves the price as far as the target
+ */
+reaches_target: boolean, 
+/**
+ * How much more than the pre-trade price of the side each share costs,
+ * as a fraction of that price
+ */
+slippage: number, 
+/**
+ * What the shares pay if their side wins
+ */
+payout_if_correct: number, };